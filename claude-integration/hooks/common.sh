@@ -2,11 +2,36 @@
 # Engram Claude Code Integration - Common Utilities
 # Source this file in hooks for shared functionality
 
-ENGRAM_SOCKET="${ENGRAM_SOCKET:-/tmp/engram.sock}"
+# The daemon's socket lives in the per-user runtime directory
+ENGRAM_RUNTIME_DIR="${XDG_RUNTIME_DIR:+$XDG_RUNTIME_DIR/engram}"
+ENGRAM_RUNTIME_DIR="${ENGRAM_RUNTIME_DIR:-/tmp/engram-$(id -u)}"
+ENGRAM_SOCKET="${ENGRAM_SOCKET:-$ENGRAM_RUNTIME_DIR/engram.sock}"
 ENGRAM_CACHE_DIR="${ENGRAM_CACHE_DIR:-/tmp/engram_cache}"
 
+# Windows has no Unix sockets; the daemon listens on a named pipe there and
+# requests go through the CLI instead of nc
+engram_use_cli() {
+    case "$(uname -s)" in
+        MINGW*|MSYS*|CYGWIN*) return 0 ;;
+        *) return 1 ;;
+    esac
+}
+
+# Frame a request for nc, preceded by the token when the daemon requires one
+# (auth.token in its config)
+engram_frame() {
+    if [[ -n "$ENGRAM_TOKEN" ]]; then
+        printf '{"action":"authenticate","token":%s}\n' "$(json_escape "$ENGRAM_TOKEN")"
+    fi
+    echo "$1"
+}
+
 # Check if daemon is running
 engram_is_running() {
+    if engram_use_cli; then
+        engram send '{"action":"ping"}' >/dev/null 2>&1
+        return
+    fi
     [[ -S "$ENGRAM_SOCKET" ]] && nc -z -U "$ENGRAM_SOCKET" 2>/dev/null
 }
 
@@ -19,7 +44,11 @@ engram_send() {
         return 1
     fi
     
-    echo "$request" | nc -U -w"$timeout" "$ENGRAM_SOCKET" 2>/dev/null
+    if engram_use_cli; then
+        engram send "$request" 2>/dev/null
+        return
+    fi
+    engram_frame "$request" | nc -U -w"$timeout" "$ENGRAM_SOCKET" 2>/dev/null
 }
 
 # Send request to daemon (fire-and-forget, non-blocking)
@@ -27,7 +56,11 @@ engram_send_async() {
     local request="$1"
     
     if engram_is_running; then
-        (echo "$request" | nc -U -w0 "$ENGRAM_SOCKET" 2>/dev/null) &
+        if engram_use_cli; then
+            (engram send "$request" >/dev/null 2>&1) &
+            return
+        fi
+        (engram_frame "$request" | nc -U -w0 "$ENGRAM_SOCKET" 2>/dev/null) &
     fi
 }
 
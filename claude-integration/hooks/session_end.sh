@@ -63,6 +63,11 @@ fi
 
 ENTRY='{"id":'"$ESCAPED_ENTRY_ID"',"kind":"session_summary","content":'"$ESCAPED_CONTENT"',"tags":["hook","session_end"],"created_at":'"$TIMESTAMP"',"updated_at":'"$TIMESTAMP"',"session_id":'"$SESSION_ID_VALUE"',"subagent_id":null,"deleted":false}'
 
+# Fire-and-forget: end the session; the daemon summarizes it for the next one
+if [[ "$SESSION_ID" != "unknown" ]]; then
+    engram_send_async '{"action":"session_end","cwd":"'"$PWD"'","session_id":'"$SESSION_ID_VALUE"'}'
+fi
+
 # Fire-and-forget: persist end-of-session summary as memory entry
 engram_send_async '{"action":"memory_put","cwd":"'"$PWD"'","entry":'"$ENTRY"'}'
 
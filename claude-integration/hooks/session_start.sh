@@ -6,6 +6,31 @@ set -euo pipefail
 SCRIPT_DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")" && pwd)"
 source "$SCRIPT_DIR/common.sh"
 
+# Read event payload from stdin (best-effort; tolerate malformed input)
+INPUT=$(cat 2>/dev/null || true)
+
+SESSION_ID=$(
+    printf '%s' "$INPUT" | python3 -c 'import json,sys
+try:
+    data = json.load(sys.stdin)
+except Exception:
+    data = {}
+session_id = data.get("session_id", "")
+print(session_id if isinstance(session_id, str) else "")
+' 2>/dev/null || echo ""
+)
+
+SOURCE=$(
+    printf '%s' "$INPUT" | python3 -c 'import json,sys
+try:
+    data = json.load(sys.stdin)
+except Exception:
+    data = {}
+source = data.get("source") or "startup"
+print(source if isinstance(source, str) else "startup")
+' 2>/dev/null || echo "startup"
+)
+
 # Check if daemon is running
 if ! engram_is_running; then
     echo "⚠️ Engram daemon not running. Start with: engram start"
@@ -21,6 +46,14 @@ if [[ "$RESULT" == *'"initialized":false'* ]]; then
     exit 0
 fi
 
+# Fire-and-forget: open the session so its scopes, decisions and memory are
+# grouped under it
+if [[ -n "$SESSION_ID" ]]; then
+    ESCAPED_SESSION_ID=$(json_escape "$SESSION_ID")
+    ESCAPED_SOURCE=$(json_escape "$SOURCE")
+    engram_send_async '{"action":"session_start","cwd":"'"$PWD"'","session_id":'"$ESCAPED_SESSION_ID"',"agent":"claude","metadata":{"source":'"$ESCAPED_SOURCE"'}}'
+fi
+
 # Fire-and-forget: prepare session context
 engram_send_async '{"action":"prepare_context","cwd":"'"$PWD"'","prompt":null}'
 
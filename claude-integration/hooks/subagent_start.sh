@@ -15,8 +15,15 @@ if ! engram_is_running; then
     exit 0
 fi
 
+# With ENGRAM_SUBAGENT_READ_ONLY_MEMORY=1, memory writes from this subagent
+# are staged until approved (`engram pending approve`)
+READ_ONLY_SUBAGENT=null
+if [[ "${ENGRAM_SUBAGENT_READ_ONLY_MEMORY:-0}" == "1" && -n "$AGENT_ID" ]]; then
+    READ_ONLY_SUBAGENT=$(echo -n "$AGENT_ID" | python3 -c 'import json,sys; print(json.dumps(sys.stdin.read()))')
+fi
+
 # Request context for subagent (blocking - we need the response)
-RESULT=$(engram_send '{"action":"get_context","cwd":"'"$PWD"'","prompt":null}' 2)
+RESULT=$(engram_send '{"action":"get_context","cwd":"'"$PWD"'","prompt":null,"cold_start":true,"read_only_subagent":'"$READ_ONLY_SUBAGENT"'}' 2)
 
 if [[ -n "$RESULT" ]]; then
     CONTEXT=$(echo "$RESULT" | python3 -c 'import json,sys; d=json.load(sys.stdin); print(d.get("data",{}).get("context",""))' 2>/dev/null || echo "")
@@ -54,6 +54,7 @@ rm -f "$CLAUDE_DIR/commands/refresh-context.md"
 echo "🧹 Cleaning up cache..."
 rm -rf /tmp/engram_cache
 rm -f /tmp/engram.sock
+rm -rf "${XDG_RUNTIME_DIR:+$XDG_RUNTIME_DIR/engram}" "/tmp/engram-$(id -u)"
 
 echo ""
 echo "═══════════════════════════════════════════"
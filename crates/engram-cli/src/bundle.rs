@@ -0,0 +1,87 @@
+//! Debug bundle archive for bug reports.
+
+use anyhow::{Context, Result};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Log files written by the launchd service definition
+pub const DAEMON_LOG_FILES: &[&str] = &["/tmp/engram.out.log", "/tmp/engram.err.log"];
+
+/// Only the tail of each log is included
+pub const MAX_LOG_BYTES: u64 = 1024 * 1024;
+
+/// A file to place in the bundle, with a one-line disclosure.
+pub struct BundleEntry {
+    pub name: String,
+    pub description: &'static str,
+    pub contents: Vec<u8>,
+}
+
+/// Read at most the last `max_bytes` of a file.
+pub fn read_tail(path: &Path, max_bytes: u64) -> std::io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    file.seek(SeekFrom::Start(len.saturating_sub(max_bytes)))?;
+    let mut contents = Vec::new();
+    file.read_to_end(&mut contents)?;
+    Ok(contents)
+}
+
+/// Describe what the bundle contains (written as README.txt).
+pub fn disclosure(entries: &[BundleEntry]) -> String {
+    let mut readme = String::from(
+        "Engram debug bundle\n\
+         ===================\n\n\
+         This archive was created by `engram debug-bundle` for attaching to an issue.\n\
+         It never includes file contents, summaries or memories. File and directory\n\
+         names in structure.json are replaced by salted hashes (extensions are kept).\n\n\
+         Included files:\n",
+    );
+    for entry in entries {
+        readme.push_str(&format!("  {:<24} {}\n", entry.name, entry.description));
+    }
+    readme
+}
+
+/// Write the entries plus a README into a gzipped tarball.
+pub fn write_bundle(output: &Path, entries: &[BundleEntry]) -> Result<()> {
+    let file =
+        File::create(output).with_context(|| format!("Failed to create {}", output.display()))?;
+    let mut archive = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+
+    let readme = disclosure(entries);
+    append(&mut archive, "README.txt", readme.as_bytes())?;
+    for entry in entries {
+        append(&mut archive, &entry.name, &entry.contents)?;
+    }
+
+    archive
+        .into_inner()
+        .context("Failed to finish archive")?
+        .finish()
+        .context("Failed to finish compression")?;
+    Ok(())
+}
+
+fn append<W: std::io::Write>(
+    archive: &mut tar::Builder<W>,
+    name: &str,
+    contents: &[u8],
+) -> Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(contents.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+    );
+    header.set_cksum();
+    archive
+        .append_data(&mut header, format!("engram-debug/{}", name), contents)
+        .with_context(|| format!("Failed to add {} to archive", name))
+}
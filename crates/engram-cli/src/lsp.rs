@@ -0,0 +1,684 @@
+//! Language server over stdio for editor integration.
+//!
+//! `engram lsp` answers a small subset of LSP from the daemon:
+//! `workspace/symbol` from search, `textDocument/references` from impact
+//! analysis (declarations, importing files and mentions), and the custom
+//! `engram/context` request, which renders context for a prompt focused on
+//! the current document. Documents are synced in full so references see
+//! unsaved edits.
+
+use anyhow::{bail, Context, Result};
+use engram_ipc::{IpcClient, Request, Response, ResponseData};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Maximum symbols returned for `workspace/symbol`
+const WORKSPACE_SYMBOL_LIMIT: usize = 100;
+
+/// Largest message body read; a bigger `Content-Length` ends the session
+/// rather than allocating whatever the header claims
+const MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+/// JSON-RPC and LSP error codes
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+const SERVER_NOT_INITIALIZED: i64 = -32002;
+
+/// LSP `TextDocumentSyncKind.Full`
+const SYNC_FULL: u8 = 1;
+
+/// Error answered to a request
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// Serve LSP on stdin/stdout until the client sends `exit`.
+pub async fn run() -> Result<()> {
+    let mut reader = tokio::io::BufReader::new(tokio::io::stdin());
+    let mut writer = tokio::io::stdout();
+    Server::new().serve(&mut reader, &mut writer).await
+}
+
+struct Server {
+    client: IpcClient,
+    /// Project root, from `initialize`
+    root: Option<PathBuf>,
+    /// Text of open documents by URI
+    documents: HashMap<String, String>,
+}
+
+impl Server {
+    fn new() -> Self {
+        Self {
+            client: IpcClient::new(),
+            root: None,
+            documents: HashMap::new(),
+        }
+    }
+
+    async fn serve<R, W>(&mut self, reader: &mut R, writer: &mut W) -> Result<()>
+    where
+        R: AsyncBufRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        while let Some(body) = read_message(reader).await? {
+            let message: Value = match serde_json::from_slice(&body) {
+                Ok(message) => message,
+                Err(e) => {
+                    let error = RpcError::new(PARSE_ERROR, e.to_string());
+                    write_message(writer, &error_response(Value::Null, error)).await?;
+                    continue;
+                }
+            };
+            let Some(method) = message.get("method").and_then(Value::as_str) else {
+                // Responses to requests we never send
+                continue;
+            };
+            let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+            match message.get("id").cloned() {
+                Some(id) => {
+                    let response = match self.request(method, params).await {
+                        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                        Err(error) => error_response(id, error),
+                    };
+                    write_message(writer, &response).await?;
+                }
+                None if method == "exit" => break,
+                None => self.notification(method, params),
+            }
+        }
+        Ok(())
+    }
+
+    async fn request(&mut self, method: &str, params: Value) -> Result<Value, RpcError> {
+        if method == "initialize" {
+            return Ok(self.initialize(&params));
+        }
+        let Some(root) = self.root.clone() else {
+            return Err(RpcError::new(
+                SERVER_NOT_INITIALIZED,
+                "Server not initialized",
+            ));
+        };
+        match method {
+            "shutdown" => Ok(Value::Null),
+            "workspace/symbol" => self.workspace_symbols(&root, &params).await,
+            "textDocument/references" => self.references(&root, &params).await,
+            "engram/context" => self.context(&root, &params).await,
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("Unsupported method: {}", method),
+            )),
+        }
+    }
+
+    fn notification(&mut self, method: &str, params: Value) {
+        let uri = params["textDocument"]["uri"].as_str().map(str::to_string);
+        match (method, uri) {
+            ("textDocument/didOpen", Some(uri)) => {
+                if let Some(text) = params["textDocument"]["text"].as_str() {
+                    self.documents.insert(uri, text.to_string());
+                }
+            }
+            ("textDocument/didChange", Some(uri)) => {
+                // Full sync: the last change holds the whole text
+                let text = params["contentChanges"]
+                    .as_array()
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str());
+                if let Some(text) = text {
+                    self.documents.insert(uri, text.to_string());
+                }
+            }
+            ("textDocument/didClose", Some(uri)) => {
+                self.documents.remove(&uri);
+            }
+            _ => {}
+        }
+    }
+
+    fn initialize(&mut self, params: &Value) -> Value {
+        let root_uri = params["rootUri"]
+            .as_str()
+            .or_else(|| params["workspaceFolders"][0]["uri"].as_str());
+        self.root = root_uri
+            .and_then(uri_to_path)
+            .or_else(|| params["rootPath"].as_str().map(PathBuf::from))
+            .or_else(|| std::env::current_dir().ok());
+
+        json!({
+            "capabilities": {
+                "textDocumentSync": SYNC_FULL,
+                "workspaceSymbolProvider": true,
+                "referencesProvider": true,
+                "experimental": { "engramContext": true },
+            },
+            "serverInfo": {
+                "name": "engram",
+                "version": env!("CARGO_PKG_VERSION"),
+            },
+        })
+    }
+
+    async fn workspace_symbols(&self, root: &Path, params: &Value) -> Result<Value, RpcError> {
+        let query = params["query"].as_str().unwrap_or_default();
+        if query.trim().is_empty() {
+            return Ok(json!([]));
+        }
+        let data = self
+            .daemon(Request::Search {
+                cwd: root.to_path_buf(),
+                query: query.to_string(),
+                limit: WORKSPACE_SYMBOL_LIMIT,
+                explain: false,
+            })
+            .await?;
+        let ResponseData::SearchResults { hits } = data else {
+            return Err(RpcError::new(INTERNAL_ERROR, "Unexpected response"));
+        };
+
+        let symbols: Vec<Value> = hits
+            .into_iter()
+            .filter_map(|hit| {
+                let line = hit.line?.saturating_sub(1);
+                Some(json!({
+                    "name": hit.name,
+                    "kind": symbol_kind(&hit.kind),
+                    "location": {
+                        "uri": path_to_uri(&root.join(&hit.path)),
+                        "range": range(line, 0, 0),
+                    },
+                }))
+            })
+            .collect();
+        Ok(Value::Array(symbols))
+    }
+
+    async fn references(&self, root: &Path, params: &Value) -> Result<Value, RpcError> {
+        let uri = params["textDocument"]["uri"]
+            .as_str()
+            .ok_or_else(|| RpcError::new(INVALID_PARAMS, "Missing textDocument.uri"))?;
+        let (line, character) = position(&params["position"])?;
+        let Some(text) = self.document_text(uri) else {
+            return Ok(json!([]));
+        };
+        let Some(symbol) = text
+            .lines()
+            .nth(line)
+            .and_then(|line| word_at(line, character))
+        else {
+            return Ok(json!([]));
+        };
+        let include_declaration = params["context"]["includeDeclaration"]
+            .as_bool()
+            .unwrap_or(true);
+
+        let data = self
+            .daemon(Request::ImpactAnalysis {
+                cwd: root.to_path_buf(),
+                symbol: symbol.clone(),
+            })
+            .await?;
+        let ResponseData::Impact { definitions, files } = data else {
+            return Err(RpcError::new(INTERNAL_ERROR, "Unexpected response"));
+        };
+
+        let mut locations = Vec::new();
+        for file in files {
+            let file_uri = path_to_uri(&root.join(&file.path));
+            let Some(text) = self.document_text(&file_uri) else {
+                continue;
+            };
+            let lines: Vec<&str> = text.lines().collect();
+            for number in file.lines {
+                let declaration = definitions
+                    .iter()
+                    .any(|d| d.path == file.path && d.line == number);
+                if declaration && !include_declaration {
+                    continue;
+                }
+                let Some(line) = lines.get(number.saturating_sub(1)) else {
+                    continue;
+                };
+                for start in word_matches(line, &symbol) {
+                    let start_col = utf16_len(&line[..start]);
+                    let end_col = start_col + utf16_len(&symbol);
+                    locations.push(json!({
+                        "uri": file_uri,
+                        "range": range(number - 1, start_col, end_col),
+                    }));
+                }
+            }
+        }
+        Ok(Value::Array(locations))
+    }
+
+    async fn context(&self, root: &Path, params: &Value) -> Result<Value, RpcError> {
+        let prompt = params["prompt"].as_str().map(str::to_string);
+        let focus = params["textDocument"]["uri"]
+            .as_str()
+            .and_then(uri_to_path)
+            .and_then(|path| Some(path.strip_prefix(root).ok()?.display().to_string()))
+            .into_iter()
+            .collect();
+
+        let data = self
+            .daemon(Request::GetContext {
+                cwd: root.to_path_buf(),
+                prompt,
+                no_cache: false,
+                cold_start: true,
+                read_only_subagent: None,
+                focus,
+                changed_since: None,
+                structured: false,
+                profile: None,
+                session_id: None,
+            })
+            .await?;
+        let ResponseData::Context {
+            context,
+            nodes,
+            scope_id,
+        } = data
+        else {
+            return Err(RpcError::new(INTERNAL_ERROR, "Unexpected response"));
+        };
+        Ok(json!({ "context": context, "nodes": nodes, "scopeId": scope_id }))
+    }
+
+    /// Send a request to the daemon, mapping failures to RPC errors.
+    async fn daemon(&self, request: Request) -> Result<ResponseData, RpcError> {
+        match self.client.request(request).await {
+            Ok(Response::Ok { data: Some(data) }) => Ok(data),
+            Ok(Response::Error { message, .. }) => Err(RpcError::new(INTERNAL_ERROR, message)),
+            Ok(_) => Err(RpcError::new(INTERNAL_ERROR, "Unexpected response")),
+            Err(e) => Err(RpcError::new(INTERNAL_ERROR, e.to_string())),
+        }
+    }
+
+    /// Text of an open document, else of the file on disk.
+    fn document_text(&self, uri: &str) -> Option<String> {
+        match self.documents.get(uri) {
+            Some(text) => Some(text.clone()),
+            None => std::fs::read_to_string(uri_to_path(uri)?).ok(),
+        }
+    }
+}
+
+/// Read one `Content-Length` framed message; `None` at end of input.
+async fn read_message<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<Option<Vec<u8>>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).await? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = Some(
+                    value
+                        .trim()
+                        .parse::<usize>()
+                        .context("Invalid Content-Length")?,
+                );
+            }
+        }
+    }
+    let length = length.context("Missing Content-Length header")?;
+    if length > MAX_MESSAGE_SIZE {
+        bail!(
+            "Message of {} bytes exceeds the {} byte limit",
+            length,
+            MAX_MESSAGE_SIZE
+        );
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await?;
+    Ok(Some(body))
+}
+
+async fn write_message<W: AsyncWrite + Unpin>(writer: &mut W, message: &Value) -> Result<()> {
+    let body = serde_json::to_vec(message)?;
+    writer
+        .write_all(format!("Content-Length: {}\r\n\r\n", body.len()).as_bytes())
+        .await?;
+    writer.write_all(&body).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": error.code, "message": error.message },
+    })
+}
+
+/// Zero-based line and UTF-16 column of an LSP position.
+fn position(value: &Value) -> Result<(usize, usize), RpcError> {
+    let field = |name| {
+        value[name]
+            .as_u64()
+            .map(|n| n as usize)
+            .ok_or_else(|| RpcError::new(INVALID_PARAMS, format!("Missing position.{}", name)))
+    };
+    Ok((field("line")?, field("character")?))
+}
+
+fn range(line: usize, start: usize, end: usize) -> Value {
+    json!({
+        "start": { "line": line, "character": start },
+        "end": { "line": line, "character": end },
+    })
+}
+
+fn is_ident(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// The identifier at a UTF-16 column of a line.
+fn word_at(line: &str, column: usize) -> Option<String> {
+    // Byte offset of the column, clamped to the end of the line
+    let mut units = 0;
+    let offset = line
+        .char_indices()
+        .find(|(_, c)| {
+            units += c.len_utf16();
+            units > column
+        })
+        .map_or(line.len(), |(i, _)| i);
+
+    let start = line[..offset]
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| is_ident(*c))
+        .last()
+        .map_or(offset, |(i, _)| i);
+    let end = line[offset..]
+        .char_indices()
+        .find(|(_, c)| !is_ident(*c))
+        .map_or(line.len(), |(i, _)| offset + i);
+    (start < end).then(|| line[start..end].to_string())
+}
+
+/// Byte offsets of whole-identifier occurrences of `name` in a line.
+fn word_matches<'a>(line: &'a str, name: &'a str) -> impl Iterator<Item = usize> + 'a {
+    line.match_indices(name)
+        .map(|(start, _)| start)
+        .filter(move |&start| {
+            let before = line[..start].chars().next_back();
+            let after = line[start + name.len()..].chars().next();
+            !before.is_some_and(is_ident) && !after.is_some_and(is_ident)
+        })
+}
+
+fn utf16_len(text: &str) -> usize {
+    text.encode_utf16().count()
+}
+
+/// LSP `SymbolKind` for an Engram symbol kind.
+fn symbol_kind(kind: &str) -> u8 {
+    match kind {
+        "module" => 2,
+        "class" => 5,
+        "method" => 6,
+        "enum" => 10,
+        "interface" | "trait" => 11,
+        "function" | "target" => 12,
+        "variable" => 13,
+        "constant" => 14,
+        "struct" => 23,
+        "heading" => 15,
+        "key" => 20,
+        _ => 13,
+    }
+}
+
+/// Path of a `file://` URI, percent-decoded.
+fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let encoded = uri.strip_prefix("file://")?;
+    let bytes = encoded.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| encoded.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    let path = String::from_utf8(decoded).ok()?;
+    // Windows URIs carry the drive after the authority: file:///C:/src
+    let path = match path.strip_prefix('/') {
+        Some(rest) if rest.get(1..2) == Some(":") => rest.to_string(),
+        _ => path,
+    };
+    Some(PathBuf::from(path))
+}
+
+/// `file://` URI of a path, percent-encoding reserved characters.
+fn path_to_uri(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut uri = String::from("file://");
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' | b':' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(message: &Value) -> Vec<u8> {
+        let body = serde_json::to_vec(message).unwrap();
+        let mut framed = format!("Content-Length: {}\r\n\r\n", body.len()).into_bytes();
+        framed.extend(body);
+        framed
+    }
+
+    /// Run a session over `input` and return the messages written back.
+    async fn session(input: &[u8]) -> Result<Vec<Value>> {
+        let mut reader = input;
+        let mut output = Vec::new();
+        Server::new().serve(&mut reader, &mut output).await?;
+
+        let mut reader = output.as_slice();
+        let mut messages = Vec::new();
+        while let Some(body) = read_message(&mut reader).await? {
+            messages.push(serde_json::from_slice(&body)?);
+        }
+        Ok(messages)
+    }
+
+    #[tokio::test]
+    async fn test_initialize_and_shutdown() {
+        let mut input = frame(&json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": { "rootUri": "file:///work/my%20project" },
+        }));
+        input.extend(frame(&json!({ "jsonrpc": "2.0", "method": "initialized" })));
+        input.extend(frame(
+            &json!({ "jsonrpc": "2.0", "id": 2, "method": "shutdown" }),
+        ));
+        input.extend(frame(&json!({ "jsonrpc": "2.0", "method": "exit" })));
+        // Nothing after `exit` is read
+        input.extend(frame(
+            &json!({ "jsonrpc": "2.0", "id": 3, "method": "shutdown" }),
+        ));
+
+        let messages = session(&input).await.unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0]["id"], 1);
+        let capabilities = &messages[0]["result"]["capabilities"];
+        assert_eq!(capabilities["textDocumentSync"], SYNC_FULL);
+        assert_eq!(capabilities["referencesProvider"], true);
+        assert_eq!(messages[0]["result"]["serverInfo"]["name"], "engram");
+        assert_eq!(
+            messages[1],
+            json!({ "jsonrpc": "2.0", "id": 2, "result": null })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_requests_need_initialize() {
+        let input = frame(&json!({ "jsonrpc": "2.0", "id": 1, "method": "shutdown" }));
+        let messages = session(&input).await.unwrap();
+        assert_eq!(messages[0]["error"]["code"], SERVER_NOT_INITIALIZED);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_method() {
+        let mut input = frame(&json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": { "rootPath": "/work" },
+        }));
+        input.extend(frame(&json!({
+            "jsonrpc": "2.0",
+            "id": "a",
+            "method": "textDocument/hover",
+        })));
+        // Unknown notifications are ignored
+        input.extend(frame(&json!({ "jsonrpc": "2.0", "method": "$/setTrace" })));
+
+        let messages = session(&input).await.unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1]["id"], "a");
+        assert_eq!(messages[1]["error"]["code"], METHOD_NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_framing() {
+        // Header names are case-insensitive and other headers are skipped
+        let body = r#"{"jsonrpc":"2.0","id":7,"method":"shutdown"}"#;
+        let input = format!(
+            "content-length: {}\r\nContent-Type: application/vscode-jsonrpc; charset=utf-8\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        let messages = session(input.as_bytes()).await.unwrap();
+        assert_eq!(messages[0]["id"], 7);
+
+        // A body that is not JSON gets a parse error; the session goes on
+        let mut input = b"Content-Length: 3\r\n\r\n{{{".to_vec();
+        input.extend(frame(
+            &json!({ "jsonrpc": "2.0", "id": 1, "method": "shutdown" }),
+        ));
+        let messages = session(&input).await.unwrap();
+        assert_eq!(messages[0]["error"]["code"], PARSE_ERROR);
+        assert_eq!(messages[0]["id"], Value::Null);
+        assert_eq!(messages[1]["id"], 1);
+
+        // End of input ends the session
+        assert!(session(b"").await.unwrap().is_empty());
+
+        assert!(session(b"Content-Type: text/plain\r\n\r\n{}")
+            .await
+            .is_err());
+        assert!(session(b"Content-Length: ten\r\n\r\n").await.is_err());
+        assert!(session(b"Content-Length: 10\r\n\r\n{}").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rejects_oversized_message() {
+        let input = format!("Content-Length: {}\r\n\r\n", MAX_MESSAGE_SIZE + 1);
+        let error = session(input.as_bytes()).await.unwrap_err();
+        assert!(error.to_string().contains("exceeds"));
+
+        let input = format!("Content-Length: {}\r\n\r\n", usize::MAX);
+        assert!(read_message(&mut input.as_bytes()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_document_sync() {
+        let mut server = Server::new();
+        let uri = "file:///work/src/lib.rs";
+        server.notification(
+            "textDocument/didOpen",
+            json!({ "textDocument": { "uri": uri, "text": "fn a() {}" } }),
+        );
+        assert_eq!(server.document_text(uri).unwrap(), "fn a() {}");
+
+        server.notification(
+            "textDocument/didChange",
+            json!({
+                "textDocument": { "uri": uri },
+                "contentChanges": [{ "text": "fn b() {}" }, { "text": "fn c() {}" }],
+            }),
+        );
+        assert_eq!(server.document_text(uri).unwrap(), "fn c() {}");
+
+        server.notification(
+            "textDocument/didClose",
+            json!({ "textDocument": { "uri": uri } }),
+        );
+        assert!(server.documents.is_empty());
+    }
+
+    #[test]
+    fn test_word_at() {
+        assert_eq!(word_at("let foo_bar = 1;", 6).as_deref(), Some("foo_bar"));
+        assert_eq!(word_at("let foo_bar = 1;", 12), None);
+        // Columns count UTF-16 units
+        assert_eq!(word_at("\"é\" + name", 7).as_deref(), Some("name"));
+        assert_eq!(
+            word_matches("a ab a_ a", "a").collect::<Vec<_>>(),
+            vec![0, 8]
+        );
+    }
+
+    #[test]
+    fn test_uri_round_trip() {
+        let path = Path::new("/work/my project/a#b.rs");
+        let uri = path_to_uri(path);
+        assert_eq!(uri, "file:///work/my%20project/a%23b.rs");
+        assert_eq!(uri_to_path(&uri).unwrap(), path);
+        assert_eq!(
+            uri_to_path("file:///C:/src/main.rs").unwrap(),
+            PathBuf::from("C:/src/main.rs")
+        );
+        assert!(uri_to_path("https://example.com").is_none());
+    }
+}
@@ -2,10 +2,24 @@
 //!
 //! Command-line interface for managing the Engram daemon and projects.
 
+mod bundle;
+mod lsp;
+mod output;
+mod service;
+
 use anyhow::{Context, Result};
+use bundle::BundleEntry;
 use clap::{Parser, Subcommand};
-use engram_ipc::{IpcClient, Request, Response, ResponseData};
-use std::path::PathBuf;
+use engram_ipc::{
+    BenchReport, CheckStatus, DaemonInfo, DoctorCheck, EventInfo, FileCount, GarbageProject,
+    IpcClient, MatchReason, MemoryBundle, MemoryEntry, MemoryPatch, MemoryQuery, MemoryVisibility,
+    NamedCount, Request, Response, ResponseData, PROTOCOL_VERSION,
+};
+use output::{Format, Output};
+use serde_json::json;
+use service::ServiceManager;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
 #[derive(Parser)]
 #[command(name = "engram")]
@@ -14,6 +28,10 @@ use std::path::PathBuf;
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = Format::Text, global = true)]
+    format: Format,
 }
 
 #[derive(Subcommand)]
@@ -28,8 +46,20 @@ enum Commands {
     /// Stop the Engram daemon
     Stop,
 
+    /// Re-read the daemon config and apply the settings that can change
+    /// without a restart (also done on SIGHUP)
+    Reload,
+
     /// Show daemon status
-    Status,
+    Status {
+        /// Include per-request resource usage
+        #[arg(short, long)]
+        verbose: bool,
+
+        /// List loaded projects instead of daemon totals
+        #[arg(long)]
+        projects: bool,
+    },
 
     /// Initialize a project for Engram
     Init {
@@ -40,42 +70,684 @@ enum Commands {
         /// Skip AI enrichment (fast mode)
         #[arg(long)]
         quick: bool,
+
+        /// Keep a directory that looks like build output (repeatable)
+        #[arg(long = "keep", value_name = "DIR")]
+        keep: Vec<PathBuf>,
     },
 
     /// Show project information
     Project {
+        #[command(subcommand)]
+        action: Option<ProjectCommand>,
+
         /// Project path (default: current directory)
         #[arg(default_value = ".")]
         path: String,
     },
 
+    /// Inspect and curate the memory entries agents stored
+    Memory {
+        #[command(subcommand)]
+        action: MemoryCommand,
+
+        /// Project path (default: current directory)
+        #[arg(short, long, default_value = ".", global = true)]
+        path: String,
+    },
+
+    /// Review memory entries staged by read-only sub-agents
+    Pending {
+        #[command(subcommand)]
+        action: PendingCommand,
+
+        /// Project path (default: current directory)
+        #[arg(short, long, default_value = ".", global = true)]
+        path: String,
+    },
+
+    /// Inspect agent sessions and what was recorded in them
+    Sessions {
+        #[command(subcommand)]
+        action: SessionsCommand,
+
+        /// Project path (default: current directory)
+        #[arg(short, long, default_value = ".", global = true)]
+        path: String,
+    },
+
+    /// Export, import or reset a project's retrieval scoring calibration
+    Calibration {
+        #[command(subcommand)]
+        action: CalibrationCommand,
+
+        /// Project path (default: current directory)
+        #[arg(short, long, default_value = ".", global = true)]
+        path: String,
+    },
+
+    /// Remove paths from the index without re-scanning
+    Prune {
+        /// Globs relative to the project root (e.g. `vendor/sdk`, `**/*.pb.go`)
+        #[arg(required = true)]
+        globs: Vec<String>,
+
+        /// Project path (default: current directory)
+        #[arg(short, long, default_value = ".")]
+        path: String,
+    },
+
+    /// Free a project's memory in the daemon; it reloads on next use
+    Unload {
+        /// Project path (default: current directory)
+        #[arg(short, long, default_value = ".")]
+        path: String,
+    },
+
+    /// Encrypt the experience log lines written before a key was configured
+    EncryptLogs,
+
+    /// Search the project index
+    Search {
+        /// Query, e.g. "how are sessions validated"
+        query: String,
+
+        /// Project path (default: current directory)
+        #[arg(short, long, default_value = ".")]
+        path: String,
+
+        /// Maximum number of results
+        #[arg(short = 'n', long, default_value_t = 10)]
+        limit: usize,
+
+        /// Show why each result matched
+        #[arg(long)]
+        explain: bool,
+
+        /// Same as `--format json`
+        #[arg(long, hide = true)]
+        json: bool,
+    },
+
+    /// Find files by fuzzy path match
+    Find {
+        /// Pattern, e.g. "auth middleware"
+        pattern: String,
+
+        /// Project path (default: current directory)
+        #[arg(short, long, default_value = ".")]
+        path: String,
+
+        /// Maximum number of results
+        #[arg(short = 'n', long, default_value_t = 10)]
+        limit: usize,
+    },
+
+    /// Print the context an agent would receive for a prompt
+    Context {
+        /// Prompt to build the context for
+        prompt: Option<String>,
+
+        /// Context profile from .engram/config.toml (e.g. review)
+        #[arg(long)]
+        profile: Option<String>,
+
+        /// Files or symbols to focus on (e.g. src/auth.rs::authenticate)
+        #[arg(long)]
+        focus: Vec<String>,
+
+        /// Also focus on files changed since the merge-base with BRANCH
+        #[arg(long, value_name = "BRANCH")]
+        changed_since: Option<String>,
+
+        /// Project path (default: current directory)
+        #[arg(short, long, default_value = ".")]
+        path: String,
+    },
+
+    /// Analyze the import graph (default: list import cycles)
+    Deps {
+        /// List groups of files importing each other
+        #[arg(long)]
+        cycles: bool,
+
+        /// List files with dependencies before their importers
+        #[arg(long)]
+        order: bool,
+
+        /// List files affected by changing FILE
+        #[arg(long, value_name = "FILE")]
+        dependents: Option<String>,
+
+        /// Import hops to follow with --dependents (default: unlimited)
+        #[arg(long)]
+        depth: Option<usize>,
+
+        /// Project path (default: current directory)
+        #[arg(short, long, default_value = ".")]
+        path: String,
+    },
+
+    /// Show index statistics: languages, symbol kinds, largest and most
+    /// connected files, and index size on disk
+    Stats {
+        /// Entries per ranked list
+        #[arg(short = 'n', long, default_value_t = 10)]
+        top: usize,
+
+        /// Project path (default: current directory)
+        #[arg(short, long, default_value = ".")]
+        path: String,
+    },
+
+    /// Show files and symbols likely affected by renaming or changing a
+    /// symbol
+    Impact {
+        /// Symbol name, optionally qualified (e.g. Session::validate)
+        symbol: String,
+
+        /// Project path (default: current directory)
+        #[arg(short, long, default_value = ".")]
+        path: String,
+    },
+
+    /// Show a symbol's signature, docs, callers, callees and related
+    /// experiences
+    Symbol {
+        /// Declaring file
+        file: String,
+
+        /// Symbol name, optionally qualified (e.g. Session::validate)
+        symbol: String,
+
+        /// Project path (default: current directory)
+        #[arg(short, long, default_value = ".")]
+        path: String,
+    },
+
+    /// List the tests of a file, or the files a test file covers
+    Tests {
+        /// File to look up
+        file: String,
+
+        /// Project path (default: current directory)
+        #[arg(short, long, default_value = ".")]
+        path: String,
+    },
+
+    /// Show files and symbols that changed between two tree snapshots
+    Diff {
+        /// Snapshot to compare against (e.g. 20240101_120000)
+        from: String,
+
+        /// Snapshot to compare (default: the current tree)
+        to: Option<String>,
+
+        /// Project path (default: current directory)
+        #[arg(short, long, default_value = ".")]
+        path: String,
+    },
+
+    /// Export the index in other formats
+    Export {
+        #[command(subcommand)]
+        what: ExportCommand,
+    },
+
+    /// Restore exported project data
+    Import {
+        #[command(subcommand)]
+        what: ImportCommand,
+    },
+
+    /// Serve the Language Server Protocol on stdio for editors
+    Lsp,
+
     /// Check if daemon is running
     Ping,
+
+    /// Probe the daemon's storage, file watching, enrichment backend and
+    /// memory pressure
+    Health,
+
+    /// Send a raw JSON request and print the JSON response (used by hooks)
+    Send {
+        /// Request JSON, e.g. '{"action":"ping"}' (default: read from stdin)
+        request: Option<String>,
+    },
+
+    /// List or cancel running indexing and enrichment jobs
+    Jobs {
+        #[command(subcommand)]
+        action: JobsCommand,
+    },
+
+    /// Show recent daemon events: project loads, index runs, errors and
+    /// memory compactions
+    Logs {
+        /// Keep printing new events as they happen
+        #[arg(short, long)]
+        follow: bool,
+
+        /// Number of recent events to show
+        #[arg(short = 'n', long, default_value = "20")]
+        limit: usize,
+    },
+
+    /// Check socket, PID file, storage and grammars, and suggest fixes
+    Doctor,
+
+    /// Find and delete stored data of deleted, moved or abandoned projects
+    Gc {
+        /// Days without use after which a project is garbage (default: the
+        /// daemon's gc.max_idle_days; 0 collects only projects whose root
+        /// is gone, after gc.missing_grace_days)
+        #[arg(long)]
+        max_idle_days: Option<u64>,
+
+        /// Only report what would be deleted
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Delete without asking
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Time indexing, incremental updates and context rendering on a
+    /// synthetic repository, against the stored baseline
+    Bench {
+        /// Files in the synthetic repository
+        #[arg(long, default_value_t = 2000)]
+        files: usize,
+
+        /// Timed runs per measurement; medians are reported
+        #[arg(long, default_value_t = 5)]
+        runs: usize,
+
+        /// Store the results as the baseline for this repository size
+        #[arg(long)]
+        save_baseline: bool,
+    },
+
+    /// Run the daemon at login as a systemd user unit (Linux) or launchd
+    /// agent (macOS)
+    Service {
+        #[command(subcommand)]
+        action: ServiceCommand,
+    },
+
+    /// Package logs, status and an anonymized project structure for bug reports
+    DebugBundle {
+        /// Project path (default: current directory)
+        #[arg(default_value = ".")]
+        path: String,
+
+        /// Output archive (default: engram-debug-<timestamp>.tar.gz)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Leave out daemon logs
+        #[arg(long)]
+        no_logs: bool,
+
+        /// Leave out the daemon status snapshot
+        #[arg(long)]
+        no_status: bool,
+
+        /// Leave out project stats and the anonymized structure
+        #[arg(long)]
+        no_tree: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum MemoryCommand {
+    /// Store a new entry
+    Add {
+        /// Entry text (default: read from stdin)
+        content: Option<String>,
+
+        /// Entry kind, e.g. `decision` or `note`
+        #[arg(short, long, default_value = "note")]
+        kind: String,
+
+        /// Tag the entry (repeatable)
+        #[arg(short, long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+    },
+
+    /// Print an entry
+    Show {
+        /// Entry id (from `engram memory list`)
+        id: String,
+    },
+
+    /// List entries, newest first
+    List {
+        /// Only entries of this kind
+        #[arg(short, long)]
+        kind: Option<String>,
+
+        /// Only entries carrying this tag (repeatable, all must match)
+        #[arg(short, long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+
+        /// Only entries recorded in this session
+        #[arg(long)]
+        session: Option<String>,
+
+        /// Only entries containing this text (case-insensitive)
+        #[arg(long)]
+        text: Option<String>,
+
+        /// Maximum number of entries
+        #[arg(short = 'n', long, default_value_t = 50)]
+        limit: usize,
+    },
+
+    /// Find entries similar in meaning to a query, best first
+    Search {
+        /// Query, e.g. "how are tokens rotated"
+        query: String,
+
+        /// Maximum number of entries
+        #[arg(short = 'n', long, default_value_t = 10)]
+        limit: usize,
+    },
+
+    /// Change an entry's content, kind or tags
+    Edit {
+        /// Entry id (from `engram memory list`)
+        id: String,
+
+        /// New entry text
+        #[arg(long)]
+        content: Option<String>,
+
+        /// New entry kind
+        #[arg(short, long)]
+        kind: Option<String>,
+
+        /// Replace the entry's tags (repeatable)
+        #[arg(short, long = "tag", value_name = "TAG", conflicts_with = "clear_tags")]
+        tags: Vec<String>,
+
+        /// Remove all tags
+        #[arg(long)]
+        clear_tags: bool,
+    },
+
+    /// Delete entries
+    Rm {
+        /// Entry ids (from `engram memory list`)
+        #[arg(required = true)]
+        ids: Vec<String>,
+    },
+
+    /// Reload the memory log from disk, e.g. after editing it by hand
+    Sync,
+
+    /// Write every entry and experience to a portable JSON bundle
+    Export {
+        /// Bundle file to write
+        file: PathBuf,
+    },
+
+    /// Add the entries and experiences of a bundle from `engram memory
+    /// export`, keeping their ids and timestamps. Entries replace local
+    /// ones only if newer.
+    Import {
+        /// Bundle file to read
+        file: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum PendingCommand {
+    /// List staged entries
+    List,
+
+    /// Store staged entries in long-term memory (all if no ids given)
+    Approve { ids: Vec<String> },
+
+    /// Discard staged entries (all if no ids given)
+    Reject { ids: Vec<String> },
+}
+
+#[derive(Subcommand)]
+enum SessionsCommand {
+    /// List sessions, most recently started first
+    List {
+        /// Maximum number of sessions
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+    },
+
+    /// Print a session with its decisions, memory entries and summary
+    Show {
+        /// Session id (from `engram sessions list`)
+        id: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum CalibrationCommand {
+    /// Print the calibration as JSON, or write it to a file
+    Export {
+        /// Output file (default: stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Replace the calibration with an exported one
+    Import {
+        /// Exported calibration file
+        file: PathBuf,
+    },
+
+    /// Return to default weights
+    Reset,
+}
+
+#[derive(Subcommand)]
+enum ProjectCommand {
+    /// Reattach a moved checkout to its index and memory
+    Relink {
+        /// Project path (default: current directory)
+        #[arg(default_value = ".")]
+        path: String,
+
+        /// Path the project was initialized at, if it had no project id
+        #[arg(long)]
+        from: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ExportCommand {
+    /// Write Markdown docs of directories, file summaries and symbols
+    Docs {
+        /// Directory to write the pages to
+        output: PathBuf,
+
+        /// Lay the pages out as an mdBook source tree
+        #[arg(long)]
+        mdbook: bool,
+
+        /// Project path (default: current directory)
+        #[arg(short, long, default_value = ".")]
+        path: String,
+    },
+
+    /// Archive the index, dependencies, memory and snapshots to one file
+    Bundle {
+        /// Archive to write (.tar.gz); must not exist
+        output: PathBuf,
+
+        /// Project path (default: current directory)
+        #[arg(short, long, default_value = ".")]
+        path: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ImportCommand {
+    /// Replace the project's index and memory with an exported bundle
+    Bundle {
+        /// Archive written by `engram export bundle`
+        archive: PathBuf,
+
+        /// Project path (default: current directory)
+        #[arg(short, long, default_value = ".")]
+        path: String,
+
+        /// Replace an initialized project's data without asking
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum JobsCommand {
+    /// List running jobs with their progress
+    List,
+
+    /// Cancel a running job
+    Cancel {
+        /// Job id (from `engram jobs list`)
+        id: u64,
+    },
+}
+
+#[derive(Subcommand)]
+enum ServiceCommand {
+    /// Write the service definition and start the daemon, now and at login
+    Install,
+
+    /// Stop the service and remove its definition
+    Uninstall,
+
+    /// Show whether the service is installed and running
+    Status,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Simple logging for CLI
+    // Simple logging for CLI, on stderr to keep stdout for output (and LSP)
     if std::env::var("RUST_LOG").is_ok() {
-        tracing_subscriber::fmt().with_target(false).init();
+        tracing_subscriber::fmt()
+            .with_target(false)
+            .with_writer(std::io::stderr)
+            .init();
     }
 
     let cli = Cli::parse();
+    let out = Output::new(cli.format);
 
     match cli.command {
-        Commands::Start { foreground } => cmd_start(foreground).await,
-        Commands::Stop => cmd_stop().await,
-        Commands::Status => cmd_status().await,
-        Commands::Init { path, quick } => cmd_init(&path, quick).await,
-        Commands::Project { path } => cmd_project(&path).await,
-        Commands::Ping => cmd_ping().await,
+        Commands::Start { foreground } => cmd_start(out, foreground).await,
+        Commands::Stop => cmd_stop(out).await,
+        Commands::Status { projects: true, .. } => cmd_status_projects(out).await,
+        Commands::Status { verbose, .. } => cmd_status(out, verbose).await,
+        Commands::Init { path, quick, keep } => cmd_init(out, &path, quick, keep).await,
+        Commands::Project {
+            action: Some(ProjectCommand::Relink { path, from }),
+            ..
+        } => cmd_relink(out, &path, from).await,
+        Commands::Project { action: None, path } => cmd_project(out, &path).await,
+        Commands::Memory { action, path } => cmd_memory(out, &path, action).await,
+        Commands::Pending { action, path } => cmd_pending(out, &path, action).await,
+        Commands::Sessions { action, path } => cmd_sessions(out, &path, action).await,
+        Commands::Calibration { action, path } => cmd_calibration(out, &path, action).await,
+        Commands::Prune { globs, path } => cmd_prune(out, &path, globs).await,
+        Commands::Unload { path } => cmd_unload(out, &path).await,
+        Commands::EncryptLogs => cmd_encrypt_logs(out).await,
+        Commands::Search {
+            query,
+            path,
+            limit,
+            explain,
+            json,
+        } => {
+            let out = if json { Output::new(Format::Json) } else { out };
+            cmd_search(out, &path, query, (limit, explain)).await
+        }
+        Commands::Find {
+            pattern,
+            path,
+            limit,
+        } => cmd_find(out, &path, pattern, limit).await,
+        Commands::Context {
+            prompt,
+            profile,
+            focus,
+            changed_since,
+            path,
+        } => cmd_context(out, &path, prompt, profile, focus, changed_since).await,
+        Commands::Deps {
+            cycles,
+            order,
+            dependents,
+            depth,
+            path,
+        } => {
+            // Without a query, report cycles
+            let cycles = cycles || (!order && dependents.is_none());
+            cmd_deps(out, &path, (cycles, order), dependents, depth).await
+        }
+        Commands::Stats { top, path } => cmd_stats(out, &path, top).await,
+        Commands::Impact { symbol, path } => cmd_impact(out, &path, symbol).await,
+        Commands::Symbol { file, symbol, path } => cmd_symbol(out, &path, file, symbol).await,
+        Commands::Tests { file, path } => cmd_tests(out, &path, file).await,
+        Commands::Diff { from, to, path } => cmd_diff(out, &path, from, to).await,
+        Commands::Export {
+            what:
+                ExportCommand::Docs {
+                    output,
+                    mdbook,
+                    path,
+                },
+        } => cmd_export_docs(out, &path, output, mdbook).await,
+        Commands::Export {
+            what: ExportCommand::Bundle { output, path },
+        } => cmd_export_bundle(out, &path, output).await,
+        Commands::Import {
+            what: ImportCommand::Bundle { archive, path, yes },
+        } => cmd_import_bundle(out, &path, archive, yes).await,
+        Commands::Lsp => lsp::run().await,
+        Commands::Ping => cmd_ping(out).await,
+        Commands::Health => cmd_health(out).await,
+        Commands::Send { request } => cmd_send(request).await,
+        Commands::Jobs { action } => cmd_jobs(out, action).await,
+        Commands::Logs { follow, limit } => cmd_logs(out, follow, limit).await,
+        Commands::Doctor => cmd_doctor(out).await,
+        Commands::Reload => cmd_reload(out).await,
+        Commands::Gc {
+            max_idle_days,
+            dry_run,
+            yes,
+        } => cmd_gc(out, max_idle_days, dry_run, yes).await,
+        Commands::Bench {
+            files,
+            runs,
+            save_baseline,
+        } => cmd_bench(out, files, runs, save_baseline),
+        Commands::Service { action } => cmd_service(out, action),
+        Commands::DebugBundle {
+            path,
+            output,
+            no_logs,
+            no_status,
+            no_tree,
+        } => cmd_debug_bundle(out, &path, output, no_logs, no_status, no_tree).await,
     }
 }
 
-async fn cmd_start(foreground: bool) -> Result<()> {
+async fn cmd_start(out: Output, foreground: bool) -> Result<()> {
     if foreground {
-        println!("Starting Engram daemon in foreground...");
-        println!("Press Ctrl+C to stop.");
+        out.info("Starting Engram daemon in foreground...");
+        out.info("Press Ctrl+C to stop.");
 
         // Execute daemon directly
         let status = std::process::Command::new("engram-daemon")
@@ -88,27 +760,22 @@ async fn cmd_start(foreground: bool) -> Result<()> {
     } else {
         // Check if already running
         if IpcClient::new().is_daemon_running() {
-            println!("Engram daemon is already running.");
+            out.message("Engram daemon is already running.");
             return Ok(());
         }
 
-        // Try launchctl on macOS
-        #[cfg(target_os = "macos")]
-        {
-            let plist_path = dirs::home_dir()
-                .unwrap()
-                .join("Library/LaunchAgents/com.engram.daemon.plist");
-
-            if plist_path.exists() {
-                let status = std::process::Command::new("launchctl")
-                    .args(["load", "-w"])
-                    .arg(&plist_path)
-                    .status()?;
-
-                if status.success() {
-                    println!("✓ Engram daemon started via launchctl");
+        // Prefer the login service if one is installed
+        if let Some(manager) = ServiceManager::current().filter(|m| m.is_installed()) {
+            match manager.start() {
+                Ok(()) => {
+                    out.success(format!("Engram daemon started via {}", manager.name()));
                     return Ok(());
                 }
+                Err(e) => out.info(format!(
+                    "Could not start the {} service: {:#}",
+                    manager.name(),
+                    e
+                )),
             }
         }
 
@@ -119,53 +786,197 @@ async fn cmd_start(foreground: bool) -> Result<()> {
             .spawn()
             .context("Failed to start daemon")?;
 
-        println!("✓ Engram daemon started (PID: {})", child.id());
+        out.success(format!("Engram daemon started (PID: {})", child.id()));
     }
 
     Ok(())
 }
 
-async fn cmd_stop() -> Result<()> {
+/// How long `engram stop` waits for the daemon to drain and exit
+const STOP_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Interval between checks whether a stopping daemon has exited
+const STOP_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
+async fn cmd_stop(out: Output) -> Result<()> {
     let client = IpcClient::new();
 
     if !client.is_daemon_running() {
-        println!("Engram daemon is not running.");
+        out.message("Engram daemon is not running.");
         return Ok(());
     }
 
     match client.request(Request::Shutdown).await {
         Ok(Response::Ack) => {
-            println!("✓ Engram daemon stopping...");
+            out.info("✓ Engram daemon stopping...");
 
-            // Wait a moment for cleanup
-            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
-
-            if !client.is_daemon_running() {
-                println!("✓ Daemon stopped.");
+            // The daemon drains in-flight requests before removing its socket
+            let deadline = std::time::Instant::now() + STOP_TIMEOUT;
+            while client.is_daemon_running() && std::time::Instant::now() < deadline {
+                tokio::time::sleep(STOP_POLL_INTERVAL).await;
             }
+
+            let stopped = !client.is_daemon_running();
+            out.emit(&json!({ "ok": true, "stopped": stopped }), || {
+                if stopped {
+                    println!("✓ Daemon stopped.");
+                } else {
+                    println!("⚠ Daemon has not exited yet; it may still be draining requests.");
+                }
+            })?;
         }
-        Ok(resp) => {
-            println!("Unexpected response: {:?}", resp);
-        }
-        Err(e) => {
-            println!("Failed to stop daemon: {}", e);
-        }
+        Ok(resp) => out.failure(format!("Unexpected response: {:?}", resp)),
+        Err(e) => out.failure(format!("Failed to stop daemon: {}", e)),
     }
 
     Ok(())
 }
 
-async fn cmd_status() -> Result<()> {
+async fn cmd_reload(out: Output) -> Result<()> {
     let client = IpcClient::new();
 
     if !client.is_daemon_running() {
-        println!("Engram daemon is not running.");
-        println!("\nStart with: engram start");
+        out.failure("Daemon not running");
         return Ok(());
     }
 
-    match client.get_status().await {
-        Ok(ResponseData::Status {
+    match client.request(Request::ReloadConfig).await {
+        Ok(Response::Ok {
+            data:
+                Some(ResponseData::ConfigReloaded {
+                    path,
+                    applied,
+                    restart_required,
+                }),
+        }) => {
+            let value = json!({
+                "path": path,
+                "applied": applied,
+                "restart_required": restart_required,
+            });
+            out.emit(&value, || {
+                println!("✓ Reloaded {}", path.display());
+                if applied.is_empty() && restart_required.is_empty() {
+                    println!("  No settings changed.");
+                }
+                for field in &applied {
+                    println!("  applied           {}", field);
+                }
+                for field in &restart_required {
+                    println!("  needs a restart   {}", field);
+                }
+            })?;
+        }
+        Ok(Response::Error { message, .. }) => {
+            out.failure(format!("Reload failed: {}", message));
+        }
+        Ok(resp) => out.failure(format!("Unexpected response: {:?}", resp)),
+        Err(e) => out.failure(format!("Error: {}", e)),
+    }
+
+    Ok(())
+}
+
+async fn cmd_status_projects(out: Output) -> Result<()> {
+    let client = IpcClient::new();
+
+    if !client.is_daemon_running() {
+        return out.emit(&json!({ "running": false, "projects": [] }), || {
+            println!("Engram daemon is not running.");
+            println!("\nStart with: engram start");
+        });
+    }
+
+    match client.request(Request::ListProjects).await {
+        Ok(Response::Ok {
+            data: Some(ResponseData::Projects { projects }),
+        }) => {
+            let value = json!({ "running": true, "projects": projects });
+            out.emit(&value, || {
+                if projects.is_empty() {
+                    println!("No projects loaded.");
+                    return;
+                }
+                println!(
+                    "{:<24} {:>8}  {:<19} {:>6} {:>6} {:>9}  PATH",
+                    "PROJECT", "FILES", "LAST INDEX", "SCOPES", "MEMORY", "CACHE HIT"
+                );
+                for project in &projects {
+                    let mut name = project.name.clone();
+                    if project.pinned {
+                        name.push_str(" (pinned)");
+                    }
+                    if project.read_only {
+                        name.push_str(" (ro)");
+                    }
+                    println!(
+                        "{:<24} {:>8}  {:<19} {:>6} {:>6} {:>8.1}%  {}",
+                        name,
+                        project.file_count,
+                        project
+                            .last_index
+                            .map(local_time)
+                            .unwrap_or_else(|| "never".to_string()),
+                        project.scopes_active,
+                        project.memory_entries,
+                        project.cache_hit_rate * 100.0,
+                        project.path.display()
+                    );
+                }
+            })?
+        }
+        Ok(Response::Error { message, .. }) => {
+            out.failure(format!("Failed to list projects: {}", message))
+        }
+        Ok(_) => out.failure("Unexpected response"),
+        Err(e) => out.failure(format!("Error: {}", e)),
+    }
+
+    Ok(())
+}
+
+async fn cmd_encrypt_logs(out: Output) -> Result<()> {
+    let client = IpcClient::new();
+
+    if !client.is_daemon_running() {
+        out.failure("Daemon not running");
+        return Ok(());
+    }
+
+    match client.request(Request::EncryptLogs).await {
+        Ok(Response::Ok {
+            data: Some(ResponseData::LogsEncrypted { projects, lines }),
+        }) => {
+            let value = json!({ "projects": projects, "lines": lines });
+            out.emit(&value, || {
+                println!(
+                    "✓ Encrypted {} experience log line(s) in {} project(s)",
+                    lines, projects
+                );
+            })?;
+        }
+        Ok(Response::Error { message, .. }) => {
+            out.failure(format!("Encryption failed: {}", message))
+        }
+        Ok(_) => out.failure("Unexpected response"),
+        Err(e) => out.failure(format!("Error: {}", e)),
+    }
+
+    Ok(())
+}
+
+async fn cmd_status(out: Output, verbose: bool) -> Result<()> {
+    let client = IpcClient::new();
+
+    if !client.is_daemon_running() {
+        return out.emit(&json!({ "running": false }), || {
+            println!("Engram daemon is not running.");
+            println!("\nStart with: engram start");
+        });
+    }
+
+    match client.get_status().await {
+        Ok(ResponseData::Status {
             version,
             uptime_secs,
             projects_loaded,
@@ -173,53 +984,210 @@ async fn cmd_status() -> Result<()> {
             requests_total,
             cache_hit_rate,
             avg_latency_ms,
+            request_usage,
+            render_cache,
+            experiments,
+            slos,
+            enrichment,
+            summarizer,
+            latency,
+            scopes_active,
+            prefetches,
+            prefetch_hits,
+            recovery,
         }) => {
-            println!("Engram Daemon v{}", version);
-            println!();
-            println!("  Status:     Running");
-            println!("  Uptime:     {}", format_duration(uptime_secs));
-            println!("  Projects:   {} loaded", projects_loaded);
-            println!(
-                "  Memory:     {:.1} MB",
-                memory_usage_bytes as f64 / 1024.0 / 1024.0
-            );
-            println!();
-            println!("  Requests:   {}", requests_total);
-            println!("  Cache Hit:  {:.1}%", cache_hit_rate * 100.0);
-            println!("  Avg Latency: {}ms", avg_latency_ms);
-        }
-        Ok(_) => {
-            println!("Unexpected status response");
-        }
-        Err(e) => {
-            println!("Failed to get status: {}", e);
+            let value = json!({
+                "running": true,
+                "version": version,
+                "uptime_secs": uptime_secs,
+                "projects_loaded": projects_loaded,
+                "memory_usage_bytes": memory_usage_bytes,
+                "requests_total": requests_total,
+                "cache_hit_rate": cache_hit_rate,
+                "avg_latency_ms": avg_latency_ms,
+                "experiments": experiments,
+                "slos": slos,
+                "enrichment": enrichment,
+                "summarizer": summarizer,
+                "latency": latency,
+                "scopes_active": scopes_active,
+                "prefetches": prefetches,
+                "prefetch_hits": prefetch_hits,
+                "request_usage": request_usage,
+                "render_cache": render_cache,
+                "recovery": recovery,
+            });
+            out.emit(&value, || {
+                println!("Engram Daemon v{}", version);
+                println!();
+                println!("  Status:     Running");
+                if let Some(recovery) = &recovery {
+                    println!(
+                        "  ⚠ Recovered from unclean shutdown of PID {} (last heartbeat {})",
+                        recovery.previous_pid,
+                        local_time(recovery.last_heartbeat)
+                    );
+                    for action in &recovery.actions {
+                        println!("    ✓ {}", action);
+                    }
+                    for problem in &recovery.problems {
+                        println!("    ✗ {}", problem);
+                    }
+                }
+                println!("  Uptime:     {}", format_duration(uptime_secs));
+                println!("  Projects:   {} loaded", projects_loaded);
+                println!("  Scopes:     {} active", scopes_active);
+                println!(
+                    "  Memory:     {:.1} MB",
+                    memory_usage_bytes as f64 / 1024.0 / 1024.0
+                );
+                println!();
+                println!("  Requests:   {}", requests_total);
+                println!("  Cache Hit:  {:.1}%", cache_hit_rate * 100.0);
+                if prefetches > 0 {
+                    println!("  Prefetch:   {} of {} used", prefetch_hits, prefetches);
+                }
+                println!("  Avg Latency: {}ms", avg_latency_ms);
+
+                let enabled: Vec<&str> = experiments
+                    .iter()
+                    .filter(|e| e.enabled)
+                    .map(|e| e.name.as_str())
+                    .collect();
+                if !enabled.is_empty() {
+                    println!("  Experiments: {}", enabled.join(", "));
+                }
+
+                if !slos.is_empty() {
+                    println!();
+                    println!("  SLOs:");
+                    for slo in &slos {
+                        println!(
+                            "    {} {:<32} {:>6.2}% ok  burn {:.2}x  ({} reqs / {})",
+                            if slo.at_risk { "⚠" } else { "✓" },
+                            slo.name,
+                            slo.compliance * 100.0,
+                            slo.burn_rate,
+                            slo.total,
+                            format_duration(slo.window_secs)
+                        );
+                    }
+                }
+
+                if !latency.is_empty() {
+                    println!();
+                    println!("  Latency by request:");
+                    println!(
+                        "    {:<22} {:>8} {:>10} {:>10} {:>10}",
+                        "action", "count", "p50", "p95", "p99"
+                    );
+                    for endpoint in &latency {
+                        println!(
+                            "    {:<22} {:>8} {:>8.1}ms {:>8.1}ms {:>8.1}ms",
+                            endpoint.action,
+                            endpoint.count,
+                            endpoint.p50_ms,
+                            endpoint.p95_ms,
+                            endpoint.p99_ms
+                        );
+                    }
+                }
+
+                if !enrichment.is_empty() {
+                    println!();
+                    println!("  Enrichment:");
+                    for job in &enrichment {
+                        println!(
+                            "    {}  {}/{} files",
+                            job.project.display(),
+                            job.processed,
+                            job.total
+                        );
+                    }
+                }
+
+                if let Some(summarizer) = &summarizer {
+                    println!();
+                    println!(
+                        "  Summarizer: {} ({}), {} requests, {} failed",
+                        summarizer.backend,
+                        summarizer.model,
+                        summarizer.requests,
+                        summarizer.failed
+                    );
+                    println!(
+                        "    {} input / {} output tokens, est. cost {:.4}",
+                        summarizer.input_tokens, summarizer.output_tokens, summarizer.cost
+                    );
+                }
+
+                if verbose && !request_usage.is_empty() {
+                    println!();
+                    println!("  Resource usage by request:");
+                    println!(
+                        "    {:<22} {:>8} {:>12} {:>10} {:>12}",
+                        "action", "count", "disk read", "nodes", "alloc"
+                    );
+                    for usage in &request_usage {
+                        println!(
+                            "    {:<22} {:>8} {:>12} {:>10} {:>12}",
+                            usage.action,
+                            usage.count,
+                            format_bytes(usage.bytes_read),
+                            usage.nodes_touched,
+                            format_bytes(usage.alloc_bytes)
+                        );
+                    }
+                }
+
+                if verbose && !render_cache.is_empty() {
+                    println!();
+                    println!("  Render cache by project:");
+                    println!(
+                        "    {:>8} {:>8} {:>10} {:>8}  project",
+                        "hits", "misses", "evictions", "entries"
+                    );
+                    for cache in &render_cache {
+                        println!(
+                            "    {:>8} {:>8} {:>10} {:>8}  {}",
+                            cache.hits,
+                            cache.misses,
+                            cache.evictions,
+                            cache.entries,
+                            cache.project.display()
+                        );
+                    }
+                }
+            })?;
         }
+        Ok(_) => out.failure("Unexpected status response"),
+        Err(e) => out.failure(format!("Failed to get status: {}", e)),
     }
 
     Ok(())
 }
 
-async fn cmd_init(path: &str, quick: bool) -> Result<()> {
+async fn cmd_init(out: Output, path: &str, quick: bool, keep: Vec<PathBuf>) -> Result<()> {
     let cwd = PathBuf::from(path).canonicalize().context("Invalid path")?;
 
-    println!("Initializing Engram for: {}", cwd.display());
+    out.info(format!("Initializing Engram for: {}", cwd.display()));
 
     let client = IpcClient::new();
 
     if !client.is_daemon_running() {
-        println!("✗ Daemon not running. Start with: engram start");
+        out.failure("Daemon not running. Start with: engram start");
         return Ok(());
     }
 
     // Check if already initialized
     match client.is_project_initialized(&cwd).await {
         Ok(true) => {
-            println!("✓ Project is already initialized.");
+            out.success("Project is already initialized.");
             return Ok(());
         }
         Ok(false) => {}
         Err(e) => {
-            println!("✗ Failed to check project: {}", e);
+            out.failure(format!("Failed to check project: {}", e));
             return Ok(());
         }
     }
@@ -228,89 +1196,2461 @@ async fn cmd_init(path: &str, quick: bool) -> Result<()> {
     let request = Request::InitProject {
         cwd: cwd.clone(),
         async_mode: !quick,
+        artifact_allowlist: keep,
     };
 
     match client.request(request).await {
-        Ok(Response::Ok { .. }) => {
-            println!("✓ Project initialized successfully!");
+        Ok(Response::Ok { data }) => {
+            let (file_count, auto_excluded) = match data {
+                Some(ResponseData::ScanReport {
+                    file_count,
+                    auto_excluded,
+                }) => (Some(file_count), auto_excluded),
+                _ if !quick && out.is_interactive() => {
+                    (show_init_progress(&client, out, &cwd).await, vec![])
+                }
+                _ => (None, vec![]),
+            };
+            let value = json!({
+                "ok": true,
+                "path": cwd,
+                "file_count": file_count,
+                "auto_excluded": auto_excluded,
+                "enriching": !quick,
+            });
+            out.emit(&value, || {
+                println!("✓ Project initialized successfully!");
 
-            if !quick {
-                println!();
-                println!("AI enrichment is running in the background.");
-                println!("Check status with: engram project");
-            }
+                if let Some(file_count) = file_count {
+                    println!("  Indexed {} files", file_count);
+                    if !auto_excluded.is_empty() {
+                        println!();
+                        println!("Auto-excluded build artifact directories:");
+                        for dir in &auto_excluded {
+                            println!("  {}/  ({})", dir.path.display(), dir.reason);
+                        }
+                        println!("Keep one with: engram init --keep <dir>");
+                    }
+                }
+
+                if !quick {
+                    println!();
+                    println!("AI enrichment is running in the background.");
+                    println!("Check status with: engram project");
+                }
+            })?;
         }
         Ok(Response::Error { message, .. }) => {
-            println!("✗ Initialization failed: {}", message);
-        }
-        Ok(_) => {
-            println!("✗ Unexpected response");
-        }
-        Err(e) => {
-            println!("✗ Error: {}", e);
+            out.failure(format!("Initialization failed: {}", message))
         }
+        Ok(_) => out.failure("Unexpected response"),
+        Err(e) => out.failure(format!("Error: {}", e)),
     }
 
     Ok(())
 }
 
-async fn cmd_project(path: &str) -> Result<()> {
+/// Polls before giving up on a background index that has not started
+const INIT_START_POLLS: u32 = 25;
+
+/// Interval between progress polls
+const PROGRESS_INTERVAL: std::time::Duration = std::time::Duration::from_millis(200);
+
+/// Width of the progress bar in characters
+const PROGRESS_BAR_WIDTH: usize = 30;
+
+/// Draw a progress bar until background indexing hands over to enrichment
+/// or finishes. Returns the number of files parsed, if seen.
+async fn show_init_progress(client: &IpcClient, out: Output, cwd: &Path) -> Option<usize> {
+    let mut started = false;
+    let mut file_count = None;
+    for polls in 0.. {
+        let request = Request::GetInitProgress {
+            cwd: cwd.to_path_buf(),
+        };
+        let Ok(Response::Ok {
+            data:
+                Some(ResponseData::InitProgress {
+                    phase,
+                    processed,
+                    total,
+                    eta_secs,
+                    ..
+                }),
+        }) = client.request(request).await
+        else {
+            break;
+        };
+        match phase.as_deref() {
+            None if started || polls >= INIT_START_POLLS => break,
+            None => {}
+            Some("enriching") => break,
+            Some(phase) => {
+                started = true;
+                if phase == "parsing" {
+                    file_count = Some(total);
+                }
+                out.progress(progress_line(phase, processed, total, eta_secs));
+            }
+        }
+        tokio::time::sleep(PROGRESS_INTERVAL).await;
+    }
+    out.clear_progress();
+    file_count
+}
+
+/// `Parsing   [=========>          ]  120/400  ETA 12s`
+fn progress_line(phase: &str, processed: usize, total: usize, eta_secs: Option<u64>) -> String {
+    let mut name = phase.to_string();
+    if let Some(first) = name.get_mut(..1) {
+        first.make_ascii_uppercase();
+    }
+    if total == 0 {
+        return format!("{:<9} ...", name);
+    }
+
+    let filled = (processed.min(total) * PROGRESS_BAR_WIDTH) / total;
+    let bar = if filled < PROGRESS_BAR_WIDTH {
+        format!(
+            "{}>{}",
+            "=".repeat(filled),
+            " ".repeat(PROGRESS_BAR_WIDTH - filled - 1)
+        )
+    } else {
+        "=".repeat(PROGRESS_BAR_WIDTH)
+    };
+    let eta = eta_secs
+        .map(|secs| format!("  ETA {}s", secs))
+        .unwrap_or_default();
+    format!("{:<9} [{}] {:>6}/{}{}", name, bar, processed, total, eta)
+}
+
+async fn cmd_project(out: Output, path: &str) -> Result<()> {
     let cwd = PathBuf::from(path).canonicalize().context("Invalid path")?;
 
     let client = IpcClient::new();
 
     if !client.is_daemon_running() {
-        println!("Engram daemon is not running.");
+        out.failure("Engram daemon is not running.");
         return Ok(());
     }
 
     match client.is_project_initialized(&cwd).await {
-        Ok(true) => {
-            println!("Project: {}", cwd.display());
-            println!("  Status: Initialized");
-            // TODO: Load more project info (file count, languages, etc.)
+        Ok(initialized) => {
+            let value = json!({ "path": cwd, "initialized": initialized });
+            out.emit(&value, || {
+                println!("Project: {}", cwd.display());
+                if initialized {
+                    println!("  Status: Initialized");
+                    // TODO: Load more project info (file count, languages, etc.)
+                } else {
+                    println!("  Status: Not initialized");
+                    println!();
+                    println!("Initialize with: engram init");
+                }
+            })?;
         }
-        Ok(false) => {
-            println!("Project: {}", cwd.display());
-            println!("  Status: Not initialized");
-            println!();
-            println!("Initialize with: engram init");
+        Err(e) => out.failure(format!("Failed to check project: {}", e)),
+    }
+
+    Ok(())
+}
+
+async fn cmd_memory(out: Output, path: &str, action: MemoryCommand) -> Result<()> {
+    let cwd = PathBuf::from(path).canonicalize().context("Invalid path")?;
+    let client = IpcClient::new();
+
+    if !client.is_daemon_running() {
+        out.failure("Daemon not running");
+        return Ok(());
+    }
+
+    let requests = match action {
+        MemoryCommand::Add {
+            content,
+            kind,
+            tags,
+        } => {
+            let content = match content {
+                Some(content) => content,
+                None => {
+                    std::io::read_to_string(std::io::stdin()).context("Failed to read stdin")?
+                }
+            };
+            vec![Request::MemoryPut {
+                cwd,
+                entry: MemoryEntry {
+                    id: String::new(),
+                    kind,
+                    content: content.trim().to_string(),
+                    tags,
+                    created_at: 0,
+                    updated_at: 0,
+                    session_id: None,
+                    subagent_id: None,
+                    deleted: false,
+                    experience_id: None,
+                    visibility: MemoryVisibility::Project,
+                },
+            }]
         }
-        Err(e) => {
-            println!("Failed to check project: {}", e);
+        MemoryCommand::Show { id } => vec![Request::MemoryGet { cwd, id }],
+        MemoryCommand::List {
+            kind,
+            tags,
+            session,
+            text,
+            limit,
+        } => vec![Request::MemoryList {
+            cwd,
+            limit,
+            query: MemoryQuery {
+                kind,
+                tags,
+                session_id: session,
+                text,
+                ..Default::default()
+            },
+        }],
+        MemoryCommand::Search { query, limit } => {
+            vec![Request::MemorySearch {
+                cwd,
+                query,
+                limit,
+                viewer: None,
+            }]
+        }
+        MemoryCommand::Edit {
+            id,
+            content,
+            kind,
+            tags,
+            clear_tags,
+        } => vec![Request::MemoryPatch {
+            cwd,
+            id,
+            patch: MemoryPatch {
+                kind,
+                content,
+                tags: (clear_tags || !tags.is_empty()).then_some(tags),
+                ..Default::default()
+            },
+        }],
+        MemoryCommand::Rm { ids } => ids
+            .into_iter()
+            .map(|id| Request::MemoryDelete {
+                cwd: cwd.clone(),
+                id,
+            })
+            .collect(),
+        MemoryCommand::Sync => vec![Request::MemorySync { cwd }],
+        MemoryCommand::Export { file } => return cmd_memory_export(out, &client, cwd, &file).await,
+        MemoryCommand::Import { file } => return cmd_memory_import(out, &client, cwd, &file).await,
+    };
+
+    for request in requests {
+        let verb = match &request {
+            Request::MemoryPut { .. } => "Stored",
+            Request::MemoryPatch { .. } => "Updated",
+            _ => "Deleted",
+        };
+        match client.request(request).await {
+            Ok(Response::Ok {
+                data: Some(ResponseData::MemoryEntry { entry }),
+            }) => out.emit(&entry, || {
+                println!("{}  [{}]", entry.id, entry.kind);
+                if !entry.tags.is_empty() {
+                    println!("  Tags:     {}", entry.tags.join(", "));
+                }
+                if let Some(session) = &entry.session_id {
+                    println!("  Session:  {}", session);
+                }
+                if let Some(agent) = &entry.subagent_id {
+                    println!("  Agent:    {}", agent);
+                }
+                println!();
+                println!("{}", entry.content);
+            })?,
+            Ok(Response::Ok {
+                data: Some(ResponseData::MemoryEntries { entries }),
+            }) => out.emit(&entries, || {
+                if entries.is_empty() {
+                    println!("No memory entries.");
+                }
+                for entry in &entries {
+                    let tags = if entry.tags.is_empty() {
+                        String::new()
+                    } else {
+                        format!("  #{}", entry.tags.join(" #"))
+                    };
+                    println!("{}  [{}]{}", entry.id, entry.kind, tags);
+                    println!("    {}", entry.content.lines().next().unwrap_or_default());
+                }
+            })?,
+            Ok(Response::Ok {
+                data: Some(ResponseData::MemoryMatches { matches }),
+            }) => out.emit(&matches, || {
+                if matches.is_empty() {
+                    println!("No matching memory entries.");
+                }
+                for found in &matches {
+                    let entry = &found.entry;
+                    println!("{:.3}  {}  [{}]", found.score, entry.id, entry.kind);
+                    println!("    {}", entry.content.lines().next().unwrap_or_default());
+                }
+            })?,
+            Ok(Response::Ok {
+                data: Some(ResponseData::MemoryAck { id }),
+            }) => {
+                let value = json!({ "ok": true, "action": verb.to_lowercase(), "id": id });
+                out.emit(&value, || println!("✓ {} {}", verb, id))?
+            }
+            Ok(Response::Ok { data: None }) => out.success("Memory reloaded from disk"),
+            Ok(Response::Error { message, .. }) => out.failure(message),
+            Ok(_) => out.failure("Unexpected response"),
+            Err(e) => out.failure(format!("Error: {}", e)),
+        }
+    }
+
+    Ok(())
+}
+
+async fn cmd_memory_export(
+    out: Output,
+    client: &IpcClient,
+    cwd: PathBuf,
+    file: &Path,
+) -> Result<()> {
+    match client.request(Request::ExportMemory { cwd }).await {
+        Ok(Response::Ok {
+            data: Some(ResponseData::MemoryBundle { bundle }),
+        }) => {
+            std::fs::write(file, serde_json::to_string_pretty(&bundle)?)
+                .with_context(|| format!("Failed to write {}", file.display()))?;
+            let value = json!({
+                "ok": true,
+                "file": file,
+                "entries": bundle.entries.len(),
+                "experiences": bundle.experiences.len(),
+            });
+            out.emit(&value, || {
+                println!(
+                    "✓ Exported {} memory entries and {} experiences to {}",
+                    bundle.entries.len(),
+                    bundle.experiences.len(),
+                    file.display()
+                )
+            })?;
+        }
+        Ok(Response::Error { message, .. }) => out.failure(message),
+        Ok(_) => out.failure("Unexpected response"),
+        Err(e) => out.failure(format!("Error: {}", e)),
+    }
+    Ok(())
+}
+
+/// Serialized size of the records sent in one import request, well under
+/// the daemon's request size limit
+const IMPORT_CHUNK_BYTES: usize = 512 * 1024;
+
+async fn cmd_memory_import(
+    out: Output,
+    client: &IpcClient,
+    cwd: PathBuf,
+    file: &Path,
+) -> Result<()> {
+    let content = std::fs::read_to_string(file)
+        .with_context(|| format!("Failed to read {}", file.display()))?;
+    let bundle: MemoryBundle = serde_json::from_str(&content)
+        .with_context(|| format!("{} is not a memory bundle", file.display()))?;
+
+    let mut totals = [0usize; 4];
+    for chunk in chunk_bundle(bundle, IMPORT_CHUNK_BYTES) {
+        let request = Request::ImportMemory {
+            cwd: cwd.clone(),
+            bundle: chunk,
+        };
+        match client.request(request).await {
+            Ok(Response::Ok {
+                data:
+                    Some(ResponseData::MemoryImported {
+                        entries_imported,
+                        entries_skipped,
+                        experiences_imported,
+                        experiences_skipped,
+                    }),
+            }) => {
+                let counts = [
+                    entries_imported,
+                    entries_skipped,
+                    experiences_imported,
+                    experiences_skipped,
+                ];
+                for (total, count) in totals.iter_mut().zip(counts) {
+                    *total += count;
+                }
+            }
+            Ok(Response::Error { message, .. }) => {
+                out.failure(message);
+                return Ok(());
+            }
+            Ok(_) => {
+                out.failure("Unexpected response");
+                return Ok(());
+            }
+            Err(e) => {
+                out.failure(format!("Error: {}", e));
+                return Ok(());
+            }
         }
     }
 
+    let [entries_imported, entries_skipped, experiences_imported, experiences_skipped] = totals;
+    let value = json!({
+        "ok": true,
+        "entries_imported": entries_imported,
+        "entries_skipped": entries_skipped,
+        "experiences_imported": experiences_imported,
+        "experiences_skipped": experiences_skipped,
+    });
+    out.emit(&value, || {
+        println!(
+            "✓ Imported {} memory entries and {} experiences from {}",
+            entries_imported,
+            experiences_imported,
+            file.display()
+        );
+        if entries_skipped + experiences_skipped > 0 {
+            println!(
+                "  Skipped {} entries with a newer local version and {} known experiences",
+                entries_skipped, experiences_skipped
+            );
+        }
+    })?;
     Ok(())
 }
 
-async fn cmd_ping() -> Result<()> {
+/// Split a bundle into bundles whose records serialize to at most about
+/// `max_bytes` each, so each fits in one request.
+fn chunk_bundle(bundle: MemoryBundle, max_bytes: usize) -> Vec<MemoryBundle> {
+    let empty = || MemoryBundle {
+        format: bundle.format,
+        exported_at: bundle.exported_at,
+        entries: vec![],
+        experiences: vec![],
+    };
+    let mut chunks = vec![empty()];
+    let mut size = 0;
+    // Start a new chunk if a record of `bytes` would overflow a filled one
+    let mut reserve = |chunks: &mut Vec<MemoryBundle>, bytes: usize| {
+        if size > 0 && size + bytes > max_bytes {
+            chunks.push(empty());
+            size = 0;
+        }
+        size += bytes.max(1);
+    };
+    for entry in bundle.entries {
+        reserve(
+            &mut chunks,
+            serde_json::to_vec(&entry).map_or(0, |json| json.len()),
+        );
+        chunks.last_mut().unwrap().entries.push(entry);
+    }
+    for experience in bundle.experiences {
+        reserve(
+            &mut chunks,
+            serde_json::to_vec(&experience).map_or(0, |json| json.len()),
+        );
+        chunks.last_mut().unwrap().experiences.push(experience);
+    }
+    chunks
+}
+
+async fn cmd_pending(out: Output, path: &str, action: PendingCommand) -> Result<()> {
+    let cwd = PathBuf::from(path).canonicalize().context("Invalid path")?;
     let client = IpcClient::new();
 
     if !client.is_daemon_running() {
-        println!("✗ Daemon not running");
+        out.failure("Daemon not running");
         return Ok(());
     }
 
-    let start = std::time::Instant::now();
-    match client.request(Request::Ping).await {
+    let (request, verb) = match action {
+        PendingCommand::List => (Request::ListPending { cwd }, "Listed"),
+        PendingCommand::Approve { ids } => (
+            Request::ApprovePending {
+                cwd,
+                ids,
+                reject: false,
+            },
+            "Approved",
+        ),
+        PendingCommand::Reject { ids } => (
+            Request::ApprovePending {
+                cwd,
+                ids,
+                reject: true,
+            },
+            "Rejected",
+        ),
+    };
+
+    match client.request(request).await {
         Ok(Response::Ok {
-            data: Some(ResponseData::Pong { .. }),
+            data: Some(ResponseData::MemoryEntries { entries }),
+        }) => out.emit(&entries, || {
+            if entries.is_empty() {
+                println!("No staged memory entries.");
+            }
+            for entry in &entries {
+                println!(
+                    "{}  [{}] from {}",
+                    entry.id,
+                    entry.kind,
+                    entry.subagent_id.as_deref().unwrap_or("unknown")
+                );
+                println!("    {}", entry.content.lines().next().unwrap_or_default());
+            }
+        })?,
+        Ok(Response::Ok {
+            data: Some(ResponseData::PendingResolved { ids }),
         }) => {
-            let elapsed = start.elapsed();
-            println!("✓ Pong! ({:.2}ms)", elapsed.as_secs_f64() * 1000.0);
+            let value = json!({ "ok": true, "action": verb.to_lowercase(), "ids": ids });
+            out.emit(&value, || {
+                println!("✓ {} {} staged entries", verb, ids.len())
+            })?
         }
-        Ok(_) => {
-            println!("✗ Unexpected response");
+        Ok(Response::Error { message, .. }) => out.failure(message),
+        Ok(_) => out.failure("Unexpected response"),
+        Err(e) => out.failure(format!("Error: {}", e)),
+    }
+
+    Ok(())
+}
+
+async fn cmd_calibration(out: Output, path: &str, action: CalibrationCommand) -> Result<()> {
+    let cwd = PathBuf::from(path).canonicalize().context("Invalid path")?;
+    let client = IpcClient::new();
+
+    if !client.is_daemon_running() {
+        out.failure("Daemon not running");
+        return Ok(());
+    }
+
+    let request = match &action {
+        CalibrationCommand::Export { .. } => Request::CalibrationExport { cwd },
+        CalibrationCommand::Import { file } => Request::CalibrationImport {
+            cwd,
+            calibration: std::fs::read_to_string(file)
+                .with_context(|| format!("Failed to read {}", file.display()))?,
+        },
+        CalibrationCommand::Reset => Request::CalibrationReset { cwd },
+    };
+
+    match (action, client.request(request).await) {
+        (
+            CalibrationCommand::Export { output },
+            Ok(Response::Ok {
+                data: Some(ResponseData::Calibration { calibration }),
+            }),
+        ) => match output {
+            Some(output) => {
+                std::fs::write(&output, calibration)
+                    .with_context(|| format!("Failed to write {}", output.display()))?;
+                out.success(format!("Calibration exported to {}", output.display()));
+            }
+            // Already JSON in either format
+            None => println!("{}", calibration),
+        },
+        (
+            CalibrationCommand::Import { file },
+            Ok(Response::Ok {
+                data: Some(ResponseData::Calibration { .. }),
+            }),
+        ) => out.success(format!("Calibration imported from {}", file.display())),
+        (CalibrationCommand::Reset, Ok(Response::Ok { .. })) => {
+            out.success("Calibration reset to defaults")
         }
-        Err(e) => {
-            println!("✗ Error: {}", e);
+        (_, Ok(Response::Error { message, .. })) => out.failure(message),
+        (_, Ok(_)) => out.failure("Unexpected response"),
+        (_, Err(e)) => out.failure(format!("Error: {}", e)),
+    }
+
+    Ok(())
+}
+
+async fn cmd_prune(out: Output, path: &str, globs: Vec<String>) -> Result<()> {
+    let path = PathBuf::from(path).canonicalize().context("Invalid path")?;
+    let client = IpcClient::new();
+
+    if !client.is_daemon_running() {
+        out.failure("Daemon not running");
+        return Ok(());
+    }
+
+    match client
+        .request(Request::PruneTree { cwd: path, globs })
+        .await
+    {
+        Ok(Response::Ok {
+            data:
+                Some(ResponseData::PruneReport {
+                    removed_dirs,
+                    removed_files,
+                    removed_symbols,
+                    removed_edges,
+                }),
+        }) => {
+            let value = json!({
+                "ok": true,
+                "removed_dirs": removed_dirs,
+                "removed_files": removed_files,
+                "removed_symbols": removed_symbols,
+                "removed_edges": removed_edges,
+            });
+            out.emit(&value, || {
+                if removed_dirs + removed_files == 0 {
+                    println!("Nothing matched; the index is unchanged.");
+                } else {
+                    println!(
+                        "✓ Pruned {} files, {} directories, {} symbols, {} dependency edges",
+                        removed_files, removed_dirs, removed_symbols, removed_edges
+                    );
+                    println!("  Add the paths to .gitignore to keep them out of future scans.");
+                }
+            })?;
+        }
+        Ok(Response::Error { message, .. }) => out.failure(format!("Prune failed: {}", message)),
+        Ok(_) => out.failure("Unexpected response"),
+        Err(e) => out.failure(format!("Error: {}", e)),
+    }
+
+    Ok(())
+}
+
+async fn cmd_unload(out: Output, path: &str) -> Result<()> {
+    let path = PathBuf::from(path).canonicalize().context("Invalid path")?;
+    let client = IpcClient::new();
+
+    if !client.is_daemon_running() {
+        out.failure("Daemon not running");
+        return Ok(());
+    }
+
+    match client
+        .request(Request::UnloadProject { cwd: path.clone() })
+        .await
+    {
+        Ok(Response::Ok {
+            data: Some(ResponseData::Unloaded { was_loaded }),
+        }) => {
+            let value = json!({ "ok": true, "was_loaded": was_loaded });
+            out.emit(&value, || {
+                if was_loaded {
+                    println!("✓ Unloaded {}", path.display());
+                } else {
+                    println!("{} was not loaded", path.display());
+                }
+            })?;
+        }
+        Ok(Response::Error { message, .. }) => out.failure(format!("Unload failed: {}", message)),
+        Ok(_) => out.failure("Unexpected response"),
+        Err(e) => out.failure(format!("Error: {}", e)),
+    }
+
+    Ok(())
+}
+
+async fn cmd_relink(out: Output, path: &str, from: Option<PathBuf>) -> Result<()> {
+    let path = PathBuf::from(path).canonicalize().context("Invalid path")?;
+    let client = IpcClient::new();
+
+    if !client.is_daemon_running() {
+        out.failure("Daemon not running");
+        return Ok(());
+    }
+
+    // The old location usually no longer exists, so it is not canonicalized
+    let from = match from {
+        Some(from) if from.is_relative() => Some(std::env::current_dir()?.join(from)),
+        from => from,
+    };
+    match client
+        .request(Request::RelinkProject {
+            cwd: path.clone(),
+            from,
+        })
+        .await
+    {
+        Ok(Response::Ok {
+            data:
+                Some(ResponseData::ProjectRelinked {
+                    id,
+                    previous_path,
+                    adopted,
+                }),
+        }) => {
+            let value = json!({
+                "ok": true,
+                "id": id,
+                "previous_path": previous_path,
+                "adopted": adopted,
+            });
+            out.emit(&value, || {
+                if previous_path == path {
+                    println!("✓ {} is linked (id {})", path.display(), id);
+                } else {
+                    println!(
+                        "✓ Relinked {} from {} (id {})",
+                        path.display(),
+                        previous_path.display(),
+                        id
+                    );
+                }
+                if adopted {
+                    println!("  Moved index and memory to the project id");
+                }
+            })?;
         }
+        Ok(Response::Error { message, .. }) => out.failure(format!("Relink failed: {}", message)),
+        Ok(_) => out.failure("Unexpected response"),
+        Err(e) => out.failure(format!("Error: {}", e)),
+    }
+
+    Ok(())
+}
+
+async fn cmd_search(
+    out: Output,
+    path: &str,
+    query: String,
+    (limit, explain): (usize, bool),
+) -> Result<()> {
+    let path = PathBuf::from(path).canonicalize().context("Invalid path")?;
+    let client = IpcClient::new();
+
+    if !client.is_daemon_running() {
+        out.failure("Daemon not running");
+        return Ok(());
+    }
+
+    match client
+        .request(Request::Search {
+            cwd: path,
+            query,
+            limit,
+            explain,
+        })
+        .await
+    {
+        Ok(Response::Ok {
+            data: Some(ResponseData::SearchResults { hits }),
+        }) => out.emit(&hits, || {
+            if hits.is_empty() {
+                println!("No results.");
+                return;
+            }
+            println!("{:>6}  {:<10} {:<40} Snippet", "Score", "Kind", "Location");
+            for hit in &hits {
+                let location = match hit.line {
+                    Some(line) => format!("{}:{} {}", hit.path.display(), line, hit.name),
+                    None => hit.path.display().to_string(),
+                };
+                let snippet = hit
+                    .snippet
+                    .as_deref()
+                    .and_then(|s| s.lines().next())
+                    .unwrap_or_default();
+                println!(
+                    "{:>6.3}  {:<10} {:<40} {}",
+                    hit.score, hit.kind, location, snippet
+                );
+                for reason in hit.explanation.iter().flatten() {
+                    let reason = match reason {
+                        MatchReason::NameMatch { name } => format!("name matches \"{}\"", name),
+                        MatchReason::DependencyEdge { imports } => {
+                            format!("imports {}", imports.display())
+                        }
+                        MatchReason::VectorSimilarity { similarity } => {
+                            format!("vector similarity {:.3}", similarity)
+                        }
+                        MatchReason::Calibration { weight } => {
+                            format!("calibration weight {:.2}", weight)
+                        }
+                        MatchReason::Relevance { weight } => {
+                            format!("outcome relevance {:.2}", weight)
+                        }
+                        MatchReason::HotNode { boost } => {
+                            format!("hot file boost {:.2}", boost)
+                        }
+                    };
+                    println!("{:>6}  ↳ {}", "", reason);
+                }
+            }
+        })?,
+        Ok(Response::Error { message, .. }) => out.failure(format!("Search failed: {}", message)),
+        Ok(_) => out.failure("Unexpected response"),
+        Err(e) => out.failure(format!("Error: {}", e)),
     }
 
     Ok(())
 }
 
+async fn cmd_find(out: Output, path: &str, pattern: String, limit: usize) -> Result<()> {
+    let path = PathBuf::from(path).canonicalize().context("Invalid path")?;
+    let client = IpcClient::new();
+
+    if !client.is_daemon_running() {
+        out.failure("Daemon not running");
+        return Ok(());
+    }
+
+    match client
+        .request(Request::FindFiles {
+            cwd: path,
+            pattern,
+            limit,
+        })
+        .await
+    {
+        Ok(Response::Ok {
+            data: Some(ResponseData::FileMatches { matches }),
+        }) => out.emit(&matches, || {
+            if matches.is_empty() {
+                println!("No matching files.");
+                return;
+            }
+            for m in &matches {
+                println!("{:>6}  {}", m.score, m.path.display());
+            }
+        })?,
+        Ok(Response::Error { message, .. }) => out.failure(format!("Find failed: {}", message)),
+        Ok(_) => out.failure("Unexpected response"),
+        Err(e) => out.failure(format!("Error: {}", e)),
+    }
+
+    Ok(())
+}
+
+async fn cmd_deps(
+    out: Output,
+    path: &str,
+    (cycles, order): (bool, bool),
+    dependents_of: Option<String>,
+    depth: Option<usize>,
+) -> Result<()> {
+    let path = PathBuf::from(path).canonicalize().context("Invalid path")?;
+    // Existing files resolve against the current directory, others are
+    // taken relative to the project
+    let dependents_of = dependents_of.map(|file| {
+        let file = PathBuf::from(file);
+        file.canonicalize().unwrap_or(file)
+    });
+    let show_dependents = dependents_of.is_some();
+    let client = IpcClient::new();
+
+    if !client.is_daemon_running() {
+        out.failure("Daemon not running");
+        return Ok(());
+    }
+
+    match client
+        .request(Request::AnalyzeDependencies {
+            cwd: path,
+            cycles,
+            order,
+            dependents_of,
+            depth,
+        })
+        .await
+    {
+        Ok(Response::Ok {
+            data:
+                Some(ResponseData::Dependencies {
+                    cycles: found,
+                    order: sorted,
+                    dependents,
+                }),
+        }) => {
+            let value = json!({
+                "cycles": found,
+                "order": sorted,
+                "dependents": dependents,
+            });
+            out.emit(&value, || {
+                if cycles {
+                    if found.is_empty() {
+                        println!("No import cycles.");
+                    } else {
+                        println!("Import cycles ({}):", found.len());
+                        for cycle in &found {
+                            let paths: Vec<_> =
+                                cycle.iter().map(|p| p.display().to_string()).collect();
+                            println!("  {}", paths.join(" <-> "));
+                        }
+                    }
+                }
+                if order {
+                    match &sorted {
+                        Some(sorted) => {
+                            println!("Dependency order ({} files):", sorted.len());
+                            for file in sorted {
+                                println!("  {}", file.display());
+                            }
+                        }
+                        None => println!("No dependency order: the imports have cycles."),
+                    }
+                }
+                if show_dependents {
+                    if dependents.is_empty() {
+                        println!("No dependents.");
+                    } else {
+                        println!("{:>5}  Dependent", "Depth");
+                        for dependent in &dependents {
+                            println!("{:>5}  {}", dependent.depth, dependent.path.display());
+                        }
+                    }
+                }
+            })?
+        }
+        Ok(Response::Error { message, .. }) => {
+            out.failure(format!("Dependency analysis failed: {}", message))
+        }
+        Ok(_) => out.failure("Unexpected response"),
+        Err(e) => out.failure(format!("Error: {}", e)),
+    }
+
+    Ok(())
+}
+
+async fn cmd_context(
+    out: Output,
+    path: &str,
+    prompt: Option<String>,
+    profile: Option<String>,
+    focus: Vec<String>,
+    changed_since: Option<String>,
+) -> Result<()> {
+    let path = PathBuf::from(path).canonicalize().context("Invalid path")?;
+    let client = IpcClient::new();
+
+    if !client.is_daemon_running() {
+        out.failure("Daemon not running");
+        return Ok(());
+    }
+
+    match client
+        .request(Request::GetContext {
+            cwd: path,
+            prompt,
+            no_cache: false,
+            cold_start: false,
+            read_only_subagent: None,
+            focus,
+            changed_since,
+            structured: false,
+            profile,
+            session_id: None,
+        })
+        .await
+    {
+        Ok(Response::Ok {
+            data:
+                Some(ResponseData::Context {
+                    context,
+                    nodes,
+                    scope_id,
+                }),
+        }) => {
+            let value = json!({ "context": context, "nodes": nodes, "scope_id": scope_id });
+            out.emit(&value, || print!("{}", context))?
+        }
+        Ok(Response::Error { message, .. }) => out.failure(format!("Context failed: {}", message)),
+        Ok(_) => out.failure("Unexpected response"),
+        Err(e) => out.failure(format!("Error: {}", e)),
+    }
+
+    Ok(())
+}
+
+async fn cmd_export_docs(out: Output, path: &str, output: PathBuf, mdbook: bool) -> Result<()> {
+    let path = PathBuf::from(path).canonicalize().context("Invalid path")?;
+    let client = IpcClient::new();
+
+    if !client.is_daemon_running() {
+        out.failure("Daemon not running");
+        return Ok(());
+    }
+
+    match client
+        .request(Request::ExportDocs { cwd: path, mdbook })
+        .await
+    {
+        Ok(Response::Ok {
+            data: Some(ResponseData::Docs { files, enriched }),
+        }) => {
+            for file in &files {
+                let target = output.join(&file.path);
+                if let Some(parent) = target.parent() {
+                    std::fs::create_dir_all(parent)
+                        .with_context(|| format!("Failed to create {}", parent.display()))?;
+                }
+                std::fs::write(&target, &file.content)
+                    .with_context(|| format!("Failed to write {}", target.display()))?;
+            }
+            let value = json!({ "output": output, "files": files.len(), "enriched": enriched });
+            out.emit(&value, || {
+                println!("Wrote {} files to {}", files.len(), output.display());
+                if !enriched {
+                    println!("  The project is not enriched yet: files have no summaries.");
+                }
+                if mdbook {
+                    println!("  Build with: mdbook build {}", output.display());
+                }
+            })?
+        }
+        Ok(Response::Error { message, .. }) => out.failure(format!("Export failed: {}", message)),
+        Ok(_) => out.failure("Unexpected response"),
+        Err(e) => out.failure(format!("Error: {}", e)),
+    }
+
+    Ok(())
+}
+
+async fn cmd_export_bundle(out: Output, path: &str, output: PathBuf) -> Result<()> {
+    let path = PathBuf::from(path).canonicalize().context("Invalid path")?;
+    let client = IpcClient::new();
+
+    if !client.is_daemon_running() {
+        out.failure("Daemon not running");
+        return Ok(());
+    }
+
+    // The daemon writes the archive, so it gets an absolute path
+    let output = std::env::current_dir()?.join(output);
+    match client
+        .request(Request::ExportProject { cwd: path, output })
+        .await
+    {
+        Ok(Response::Ok {
+            data:
+                Some(ResponseData::ProjectArchive {
+                    path, files, bytes, ..
+                }),
+        }) => {
+            let value = json!({ "ok": true, "path": path, "files": files, "bytes": bytes });
+            out.emit(&value, || {
+                println!(
+                    "✓ Wrote {} files ({}) to {}",
+                    files,
+                    format_bytes(bytes),
+                    path.display()
+                )
+            })?
+        }
+        Ok(Response::Error { message, .. }) => out.failure(format!("Export failed: {}", message)),
+        Ok(_) => out.failure("Unexpected response"),
+        Err(e) => out.failure(format!("Error: {}", e)),
+    }
+
+    Ok(())
+}
+
+async fn cmd_import_bundle(out: Output, path: &str, archive: PathBuf, yes: bool) -> Result<()> {
+    let path = PathBuf::from(path).canonicalize().context("Invalid path")?;
+    let archive = archive
+        .canonicalize()
+        .with_context(|| format!("Failed to read {}", archive.display()))?;
+    let client = IpcClient::new();
+
+    if !client.is_daemon_running() {
+        out.failure("Daemon not running");
+        return Ok(());
+    }
+
+    let initialized = matches!(
+        client
+            .request(Request::CheckInit { cwd: path.clone() })
+            .await,
+        Ok(Response::Ok {
+            data: Some(ResponseData::InitStatus { initialized: true }),
+        })
+    );
+    if initialized && !yes {
+        eprint!("Replace the index and memory of {}? [y/N] ", path.display());
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        if !matches!(answer.trim(), "y" | "Y" | "yes") {
+            out.message("Nothing imported");
+            return Ok(());
+        }
+    }
+
+    match client
+        .request(Request::ImportProject {
+            cwd: path.clone(),
+            archive,
+        })
+        .await
+    {
+        Ok(Response::Ok {
+            data:
+                Some(ResponseData::ProjectArchive {
+                    files, source_path, ..
+                }),
+        }) => {
+            let value = json!({ "ok": true, "files": files, "source_path": source_path });
+            out.emit(&value, || {
+                println!(
+                    "✓ Imported {} files into {} (exported from {})",
+                    files,
+                    path.display(),
+                    source_path.display()
+                )
+            })?
+        }
+        Ok(Response::Error { message, .. }) => out.failure(format!("Import failed: {}", message)),
+        Ok(_) => out.failure("Unexpected response"),
+        Err(e) => out.failure(format!("Error: {}", e)),
+    }
+
+    Ok(())
+}
+
+async fn cmd_stats(out: Output, path: &str, top: usize) -> Result<()> {
+    let path = PathBuf::from(path).canonicalize().context("Invalid path")?;
+    let client = IpcClient::new();
+
+    if !client.is_daemon_running() {
+        out.failure("Daemon not running");
+        return Ok(());
+    }
+
+    match client
+        .request(Request::ProjectStats {
+            cwd: path.clone(),
+            top: Some(top),
+        })
+        .await
+    {
+        Ok(Response::Ok {
+            data:
+                Some(ResponseData::ProjectStats {
+                    files,
+                    symbols,
+                    lines,
+                    bytes,
+                    dependency_edges,
+                    languages,
+                    assets,
+                    symbol_kinds,
+                    largest_files,
+                    most_imported,
+                    most_importing,
+                    index_files,
+                    index_bytes,
+                    created_at,
+                    last_scan,
+                    enriched,
+                }),
+        }) => {
+            let value = json!({
+                "files": files,
+                "symbols": symbols,
+                "lines": lines,
+                "bytes": bytes,
+                "dependency_edges": dependency_edges,
+                "languages": languages,
+                "assets": assets,
+                "symbol_kinds": symbol_kinds,
+                "largest_files": largest_files,
+                "most_imported": most_imported,
+                "most_importing": most_importing,
+                "index_files": index_files,
+                "index_bytes": index_bytes,
+                "created_at": created_at,
+                "last_scan": last_scan,
+                "enriched": enriched,
+            });
+            out.emit(&value, || {
+                println!("Project: {}", path.display());
+                println!(
+                    "  {} files, {} lines, {}",
+                    files,
+                    lines,
+                    format_bytes(bytes)
+                );
+                println!("  {} symbols, {} import edges", symbols, dependency_edges);
+                println!(
+                    "  Index: {} on disk{}",
+                    format_bytes(index_bytes),
+                    if enriched { ", enriched" } else { "" }
+                );
+                println!("  Initialized: {}", local_time(created_at));
+                if let Some(last_scan) = last_scan {
+                    println!("  Last scan:   {}", local_time(last_scan));
+                }
+
+                let section = |title: &str, rows: Vec<(String, String)>| {
+                    if rows.is_empty() {
+                        return;
+                    }
+                    println!();
+                    println!("{}:", title);
+                    let width = rows.iter().map(|(_, value)| value.len()).max().unwrap_or(0);
+                    for (label, value) in rows {
+                        println!("  {:>width$}  {}", value, label, width = width);
+                    }
+                };
+                let counts = |entries: &[NamedCount]| {
+                    entries
+                        .iter()
+                        .map(|e| (e.name.clone(), e.count.to_string()))
+                        .collect()
+                };
+                let file_counts = |entries: &[FileCount]| {
+                    entries
+                        .iter()
+                        .map(|e| (e.path.display().to_string(), e.count.to_string()))
+                        .collect()
+                };
+                section("Languages", counts(&languages));
+                section(
+                    "Assets",
+                    assets
+                        .iter()
+                        .map(|a| {
+                            (
+                                format!("{} ({} files)", a.kind, a.count),
+                                format_bytes(a.bytes),
+                            )
+                        })
+                        .collect(),
+                );
+                section("Symbols", counts(&symbol_kinds));
+                section(
+                    "Largest files",
+                    largest_files
+                        .iter()
+                        .map(|f| {
+                            (
+                                format!("{} ({} lines)", f.path.display(), f.line_count),
+                                format_bytes(f.size),
+                            )
+                        })
+                        .collect(),
+                );
+                section("Most imported", file_counts(&most_imported));
+                section("Most imports", file_counts(&most_importing));
+                section(
+                    "Index files",
+                    index_files
+                        .iter()
+                        .map(|f| (f.name.clone(), format_bytes(f.size)))
+                        .collect(),
+                );
+            })?
+        }
+        Ok(Response::Error { message, .. }) => out.failure(format!("Stats failed: {}", message)),
+        Ok(_) => out.failure("Unexpected response"),
+        Err(e) => out.failure(format!("Error: {}", e)),
+    }
+
+    Ok(())
+}
+
+async fn cmd_sessions(out: Output, path: &str, action: SessionsCommand) -> Result<()> {
+    let cwd = PathBuf::from(path).canonicalize().context("Invalid path")?;
+    let client = IpcClient::new();
+
+    if !client.is_daemon_running() {
+        out.failure("Daemon not running");
+        return Ok(());
+    }
+
+    let request = match action {
+        SessionsCommand::List { limit } => Request::ListSessions { cwd, limit },
+        SessionsCommand::Show { id } => Request::GetSession {
+            cwd,
+            session_id: id,
+        },
+    };
+
+    match client.request(request).await {
+        Ok(Response::Ok {
+            data: Some(ResponseData::Sessions { sessions }),
+        }) => out.emit(&sessions, || {
+            if sessions.is_empty() {
+                println!("No sessions.");
+            }
+            for session in &sessions {
+                let state = match session.ended_at {
+                    Some(ended_at) => format!("ended {}", local_time(ended_at)),
+                    None => "active".to_string(),
+                };
+                println!(
+                    "{}  {}  {}",
+                    session.id,
+                    local_time(session.started_at),
+                    session.agent.as_deref().unwrap_or("unknown agent")
+                );
+                println!(
+                    "    {}, {} scopes, {} decisions, {} memory entries",
+                    state,
+                    session.scope_ids.len(),
+                    session.experiences,
+                    session.memory_entries
+                );
+            }
+        })?,
+        Ok(Response::Ok {
+            data:
+                Some(ResponseData::SessionDetail {
+                    session,
+                    experiences,
+                    memory,
+                }),
+        }) => {
+            let value = json!({
+                "session": session,
+                "experiences": experiences,
+                "memory": memory,
+            });
+            out.emit(&value, || {
+                println!("Session {}", session.id);
+                if let Some(agent) = &session.agent {
+                    println!("  Agent:    {}", agent);
+                }
+                if let Some(model) = &session.model {
+                    println!("  Model:    {}", model);
+                }
+                for (key, value) in &session.metadata {
+                    println!("  {}: {}", key, value);
+                }
+                println!("  Started:  {}", local_time(session.started_at));
+                match session.ended_at {
+                    Some(ended_at) => println!("  Ended:    {}", local_time(ended_at)),
+                    None => println!("  Ended:    (active)"),
+                }
+                println!("  Scopes:   {}", session.scope_ids.len());
+
+                if !experiences.is_empty() {
+                    println!();
+                    println!("Decisions:");
+                    for experience in &experiences {
+                        println!("  - {}", experience.decision);
+                    }
+                }
+                if !memory.is_empty() {
+                    println!();
+                    println!("Memory:");
+                    for entry in &memory {
+                        println!("  {}  [{}]", entry.id, entry.kind);
+                        println!("      {}", entry.content.lines().next().unwrap_or_default());
+                    }
+                }
+                if let Some(summary) = &session.summary {
+                    println!();
+                    println!("Summary:");
+                    for line in summary.lines() {
+                        println!("  {}", line);
+                    }
+                }
+            })?
+        }
+        Ok(Response::Error { message, .. }) => out.failure(message),
+        Ok(_) => out.failure("Unexpected response"),
+        Err(e) => out.failure(format!("Error: {}", e)),
+    }
+
+    Ok(())
+}
+
+/// Unix timestamp in local time, e.g. `2024-01-15 10:30:00`
+fn local_time(timestamp: i64) -> String {
+    chrono::DateTime::from_timestamp(timestamp, 0)
+        .map(|time| {
+            time.with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+        })
+        .unwrap_or_default()
+}
+
+async fn cmd_tests(out: Output, path: &str, file: String) -> Result<()> {
+    let path = PathBuf::from(path).canonicalize().context("Invalid path")?;
+    // Existing files resolve against the current directory, others are
+    // taken relative to the project
+    let file = PathBuf::from(file);
+    let file = file.canonicalize().unwrap_or(file);
+    let client = IpcClient::new();
+
+    if !client.is_daemon_running() {
+        out.failure("Daemon not running");
+        return Ok(());
+    }
+
+    match client
+        .request(Request::ListTests {
+            cwd: path.clone(),
+            path: file.clone(),
+        })
+        .await
+    {
+        Ok(Response::Ok {
+            data:
+                Some(ResponseData::Tests {
+                    is_test,
+                    inline,
+                    files,
+                }),
+        }) => {
+            let value = json!({ "is_test": is_test, "inline": inline, "files": files });
+            let name = file
+                .strip_prefix(&path)
+                .unwrap_or(&file)
+                .display()
+                .to_string();
+            out.emit(&value, || {
+                if is_test {
+                    if files.is_empty() {
+                        println!("{} is a test, covering no known files.", name);
+                    } else {
+                        println!("{} tests:", name);
+                    }
+                } else if files.is_empty() && !inline {
+                    println!("No tests found for {}.", name);
+                } else {
+                    println!("Tests of {}:", name);
+                    if inline {
+                        println!("  {} (inline)", name);
+                    }
+                }
+                for file in &files {
+                    println!("  {}", file.display());
+                }
+            })?
+        }
+        Ok(Response::Error { message, .. }) => {
+            out.failure(format!("Listing tests failed: {}", message))
+        }
+        Ok(_) => out.failure("Unexpected response"),
+        Err(e) => out.failure(format!("Error: {}", e)),
+    }
+
+    Ok(())
+}
+
+async fn cmd_impact(out: Output, path: &str, symbol: String) -> Result<()> {
+    let path = PathBuf::from(path).canonicalize().context("Invalid path")?;
+    let client = IpcClient::new();
+
+    if !client.is_daemon_running() {
+        out.failure("Daemon not running");
+        return Ok(());
+    }
+
+    match client
+        .request(Request::ImpactAnalysis {
+            cwd: path,
+            symbol: symbol.clone(),
+        })
+        .await
+    {
+        Ok(Response::Ok {
+            data: Some(ResponseData::Impact { definitions, files }),
+        }) => {
+            let value = json!({ "definitions": definitions, "files": files });
+            out.emit(&value, || {
+                if files.is_empty() {
+                    println!("No references to {}.", symbol);
+                    return;
+                }
+                println!(
+                    "{} ({} definitions, {} files)",
+                    symbol,
+                    definitions.len(),
+                    files.len()
+                );
+                for (i, file) in files.iter().enumerate() {
+                    let last = i + 1 == files.len();
+                    let reason = match file.depth {
+                        Some(0) => "declares".to_string(),
+                        Some(1) => "imports".to_string(),
+                        Some(depth) => format!("{} import hops", depth),
+                        None => "mentions".to_string(),
+                    };
+                    println!(
+                        "{} {}  [{}{}]",
+                        if last { "└──" } else { "├──" },
+                        file.path.display(),
+                        reason,
+                        line_list(&file.lines)
+                    );
+                    for (j, symbol) in file.symbols.iter().enumerate() {
+                        println!(
+                            "{}   {} {} ({}, line {})",
+                            if last { " " } else { "│" },
+                            if j + 1 == file.symbols.len() {
+                                "└──"
+                            } else {
+                                "├──"
+                            },
+                            symbol.name,
+                            symbol.kind,
+                            symbol.line
+                        );
+                    }
+                }
+            })?
+        }
+        Ok(Response::Error { message, .. }) => {
+            out.failure(format!("Impact analysis failed: {}", message))
+        }
+        Ok(_) => out.failure("Unexpected response"),
+        Err(e) => out.failure(format!("Error: {}", e)),
+    }
+
+    Ok(())
+}
+
+async fn cmd_symbol(out: Output, path: &str, file: String, symbol: String) -> Result<()> {
+    let path = PathBuf::from(path).canonicalize().context("Invalid path")?;
+    // Existing files resolve against the current directory, others are
+    // taken relative to the project
+    let file = PathBuf::from(file);
+    let file = file.canonicalize().unwrap_or(file);
+    let client = IpcClient::new();
+
+    if !client.is_daemon_running() {
+        out.failure("Daemon not running");
+        return Ok(());
+    }
+
+    match client
+        .request(Request::GetSymbol {
+            cwd: path,
+            path: file,
+            symbol,
+        })
+        .await
+    {
+        Ok(Response::Ok {
+            data:
+                Some(ResponseData::Symbol {
+                    symbol,
+                    signature,
+                    doc,
+                    file_summary,
+                    callers,
+                    callees,
+                    experiences,
+                }),
+        }) => {
+            let value = json!({
+                "symbol": symbol,
+                "signature": signature,
+                "doc": doc,
+                "file_summary": file_summary,
+                "callers": callers,
+                "callees": callees,
+                "experiences": experiences,
+            });
+            out.emit(&value, || {
+                println!(
+                    "{} ({}, {}:{})",
+                    symbol.name,
+                    symbol.kind,
+                    symbol.path.display(),
+                    symbol.line
+                );
+                if !signature.is_empty() {
+                    println!();
+                    for line in signature.lines() {
+                        println!("    {}", line);
+                    }
+                }
+                if let Some(doc) = &doc {
+                    println!();
+                    println!("{}", doc);
+                }
+                if let Some(summary) = &file_summary {
+                    println!();
+                    println!("File: {}", summary);
+                }
+                for (title, symbols) in [("Callers", &callers), ("Callees", &callees)] {
+                    println!();
+                    println!("{} ({})", title, symbols.len());
+                    for symbol in symbols {
+                        println!(
+                            "  {} ({}, {}:{})",
+                            symbol.name,
+                            symbol.kind,
+                            symbol.path.display(),
+                            symbol.line
+                        );
+                    }
+                }
+                if !experiences.is_empty() {
+                    println!();
+                    println!("Experiences");
+                    for experience in &experiences {
+                        println!(
+                            "  {}  {}",
+                            local_time(experience.timestamp),
+                            experience.decision
+                        );
+                    }
+                }
+            })?
+        }
+        Ok(Response::Error { message, .. }) => {
+            out.failure(format!("Symbol lookup failed: {}", message))
+        }
+        Ok(_) => out.failure("Unexpected response"),
+        Err(e) => out.failure(format!("Error: {}", e)),
+    }
+
+    Ok(())
+}
+
+async fn cmd_diff(out: Output, path: &str, from: String, to: Option<String>) -> Result<()> {
+    let path = PathBuf::from(path).canonicalize().context("Invalid path")?;
+    let client = IpcClient::new();
+
+    if !client.is_daemon_running() {
+        out.failure("Daemon not running");
+        return Ok(());
+    }
+
+    match client
+        .request(Request::DiffSnapshots {
+            cwd: path,
+            from,
+            to,
+        })
+        .await
+    {
+        Ok(Response::Ok {
+            data:
+                Some(ResponseData::TreeDiff {
+                    added_files,
+                    removed_files,
+                    modified_files,
+                    added_symbols,
+                    removed_symbols,
+                    modified_symbols,
+                }),
+        }) => {
+            let value = json!({
+                "added_files": added_files,
+                "removed_files": removed_files,
+                "modified_files": modified_files,
+                "added_symbols": added_symbols,
+                "removed_symbols": removed_symbols,
+                "modified_symbols": modified_symbols,
+            });
+            out.emit(&value, || {
+                if added_files.is_empty() && removed_files.is_empty() && modified_files.is_empty() {
+                    println!("No changes.");
+                    return;
+                }
+                for (sign, files) in [
+                    ('+', &added_files),
+                    ('~', &modified_files),
+                    ('-', &removed_files),
+                ] {
+                    for file in files {
+                        println!("{} {}", sign, file.display());
+                        for (sign, symbols) in [
+                            ('+', &added_symbols),
+                            ('~', &modified_symbols),
+                            ('-', &removed_symbols),
+                        ] {
+                            for symbol in symbols.iter().filter(|s| &s.path == file) {
+                                println!(
+                                    "    {} {} ({}, line {})",
+                                    sign, symbol.name, symbol.kind, symbol.line
+                                );
+                            }
+                        }
+                    }
+                }
+                println!(
+                    "\n{} added, {} modified, {} removed",
+                    added_files.len(),
+                    modified_files.len(),
+                    removed_files.len()
+                );
+            })?
+        }
+        Ok(Response::Error { message, .. }) => out.failure(format!("Diff failed: {}", message)),
+        Ok(_) => out.failure("Unexpected response"),
+        Err(e) => out.failure(format!("Error: {}", e)),
+    }
+
+    Ok(())
+}
+
+/// Describe referencing lines, e.g. `, lines 4, 12`.
+fn line_list(lines: &[usize]) -> String {
+    /// Lines listed before eliding the rest
+    const MAX_LINES: usize = 5;
+
+    let mut listed: Vec<String> = lines.iter().take(MAX_LINES).map(usize::to_string).collect();
+    if lines.len() > MAX_LINES {
+        listed.push("…".to_string());
+    }
+    match lines.len() {
+        0 => String::new(),
+        1 => format!(", line {}", listed[0]),
+        _ => format!(", lines {}", listed.join(", ")),
+    }
+}
+
+async fn cmd_ping(out: Output) -> Result<()> {
+    let client = IpcClient::new();
+
+    if !client.is_daemon_running() {
+        out.failure("Daemon not running");
+        return Ok(());
+    }
+
+    let start = std::time::Instant::now();
+    match client.request(Request::Ping).await {
+        Ok(Response::Ok {
+            data: Some(ResponseData::Pong { .. }),
+        }) => {
+            let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+            let info = client
+                .hello()
+                .await
+                .unwrap_or_else(|_| DaemonInfo::legacy());
+            out.emit(
+                &json!({
+                    "ok": true,
+                    "latency_ms": latency_ms,
+                    "daemon_version": info.version,
+                    "protocol_version": info.protocol_version,
+                    "features": info.features,
+                }),
+                || {
+                    println!("✓ Pong! ({:.2}ms)", latency_ms);
+                    match &info.version {
+                        Some(version) => println!(
+                            "  Daemon {} (protocol {})",
+                            version, info.protocol_version
+                        ),
+                        None => println!("  Daemon predates protocol versioning"),
+                    }
+                    if info.protocol_version < PROTOCOL_VERSION {
+                        println!(
+                            "  ⚠ Daemon speaks an older protocol than this CLI ({}); restart it to upgrade",
+                            PROTOCOL_VERSION
+                        );
+                    }
+                },
+            )?;
+        }
+        Ok(_) => out.failure("Unexpected response"),
+        Err(e) => out.failure(format!("Error: {}", e)),
+    }
+
+    Ok(())
+}
+
+async fn cmd_send(request: Option<String>) -> Result<()> {
+    let raw = match request {
+        Some(raw) => raw,
+        None => std::io::read_to_string(std::io::stdin()).context("Failed to read stdin")?,
+    };
+    let request: Request = serde_json::from_str(raw.trim()).context("Invalid request JSON")?;
+
+    let client = IpcClient::new();
+    if !client.is_daemon_running() {
+        anyhow::bail!("Daemon not running");
+    }
+
+    let response = client.request(request).await?;
+    println!("{}", serde_json::to_string(&response)?);
+
+    Ok(())
+}
+
+/// Interval between polls for new events with `engram logs --follow`
+const FOLLOW_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Most events fetched per poll with `engram logs --follow`
+const FOLLOW_LIMIT: usize = 1000;
+
+async fn cmd_logs(out: Output, follow: bool, limit: usize) -> Result<()> {
+    let client = IpcClient::new();
+
+    if !client.is_daemon_running() {
+        out.failure("Daemon not running");
+        return Ok(());
+    }
+
+    let mut since = 0;
+    let mut limit = limit;
+    loop {
+        let events = match client.request(Request::GetEvents { since, limit }).await {
+            Ok(Response::Ok {
+                data: Some(ResponseData::Events { events }),
+            }) => events,
+            Ok(Response::Error { message, .. }) => {
+                out.failure(message);
+                return Ok(());
+            }
+            Ok(_) => {
+                out.failure("Unexpected response");
+                return Ok(());
+            }
+            Err(e) => {
+                out.failure(format!("Error: {}", e));
+                return Ok(());
+            }
+        };
+
+        if !follow {
+            return out.emit(&events, || {
+                if events.is_empty() {
+                    println!("No events recorded.");
+                }
+                for event in &events {
+                    println!("{}", event_line(event));
+                }
+            });
+        }
+
+        // One JSON document per event, as they arrive
+        for event in &events {
+            out.emit(event, || println!("{}", event_line(event)))?;
+        }
+        since = events.last().map_or(since, |event| event.seq);
+        limit = FOLLOW_LIMIT;
+        tokio::time::sleep(FOLLOW_INTERVAL).await;
+    }
+}
+
+/// `2024-01-15 10:30:00  index_run         /path/to/project  Indexed 120 files`
+fn event_line(event: &EventInfo) -> String {
+    let time = local_time(event.time);
+    match &event.project {
+        Some(project) => format!(
+            "{}  {:<16}  {}  {}",
+            time,
+            event.kind,
+            project.display(),
+            event.message
+        ),
+        None => format!("{}  {:<16}  {}", time, event.kind, event.message),
+    }
+}
+
+async fn cmd_jobs(out: Output, action: JobsCommand) -> Result<()> {
+    let client = IpcClient::new();
+
+    if !client.is_daemon_running() {
+        out.failure("Daemon not running");
+        return Ok(());
+    }
+
+    match action {
+        JobsCommand::List => match client.request(Request::ListJobs).await {
+            Ok(Response::Ok {
+                data: Some(ResponseData::Jobs { jobs }),
+            }) => out.emit(&jobs, || {
+                if jobs.is_empty() {
+                    println!("No running jobs.");
+                    return;
+                }
+                println!(
+                    "{:<6} {:<12} {:>16} {:>8}  Project",
+                    "ID", "Kind", "Progress", "Elapsed"
+                );
+                for job in &jobs {
+                    let progress = if job.queued {
+                        "queued".to_string()
+                    } else if job.total > 0 {
+                        format!("{}/{}", job.processed, job.total)
+                    } else {
+                        "discovering".to_string()
+                    };
+                    let kind = if job.cancelling {
+                        format!("{}*", job.kind)
+                    } else {
+                        job.kind.clone()
+                    };
+                    println!(
+                        "{:<6} {:<12} {:>16} {:>7}s  {}",
+                        job.id,
+                        kind,
+                        progress,
+                        job.elapsed_secs,
+                        job.project.display()
+                    );
+                }
+                println!();
+                println!("* cancellation requested");
+            })?,
+            Ok(_) => out.failure("Unexpected response"),
+            Err(e) => out.failure(format!("Error: {}", e)),
+        },
+        JobsCommand::Cancel { id } => match client.request(Request::CancelJob { id }).await {
+            Ok(Response::Ok { .. }) => {
+                out.success(format!("Cancellation requested for job {}", id));
+                out.info("  The job stops at its next checkpoint.");
+            }
+            Ok(Response::Error { message, .. }) => out.failure(message),
+            Ok(_) => out.failure("Unexpected response"),
+            Err(e) => out.failure(format!("Error: {}", e)),
+        },
+    }
+
+    Ok(())
+}
+
+fn cmd_service(out: Output, action: ServiceCommand) -> Result<()> {
+    let Some(manager) = ServiceManager::current() else {
+        anyhow::bail!("Services are supported on Linux (systemd) and macOS (launchd)");
+    };
+
+    match action {
+        ServiceCommand::Install => {
+            if !manager.is_installed() && IpcClient::new().is_daemon_running() {
+                anyhow::bail!(
+                    "The daemon is already running outside the service; stop it first with `engram stop`"
+                );
+            }
+            let daemon = service::daemon_binary().with_context(|| {
+                format!(
+                    "{} not found next to engram or in PATH",
+                    service::DAEMON_BINARY
+                )
+            })?;
+            let path = manager.install(&daemon)?;
+            out.success(format!(
+                "Installed {} service at {}",
+                manager.name(),
+                path.display()
+            ));
+        }
+        ServiceCommand::Uninstall => match manager.uninstall()? {
+            Some(path) => out.success(format!(
+                "Removed {} service at {}",
+                manager.name(),
+                path.display()
+            )),
+            None => out.message("No service installed."),
+        },
+        ServiceCommand::Status => {
+            let path = manager.definition_path()?;
+            let installed = path.exists();
+            let active = installed && manager.is_active();
+            out.emit(
+                &json!({
+                    "manager": manager.name(),
+                    "path": path,
+                    "installed": installed,
+                    "active": active,
+                }),
+                || {
+                    if !installed {
+                        println!("No {} service installed.", manager.name());
+                        println!("\nInstall with: engram service install");
+                        return;
+                    }
+                    println!("Service: {} ({})", manager.name(), path.display());
+                    println!("  Active: {}", if active { "yes" } else { "no" });
+                },
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn cmd_gc(out: Output, max_idle_days: Option<u64>, dry_run: bool, yes: bool) -> Result<()> {
+    let client = IpcClient::new();
+
+    if !client.is_daemon_running() {
+        out.failure("Daemon not running");
+        return Ok(());
+    }
+
+    let collect = |dry_run: bool, only: Vec<PathBuf>| {
+        client.request(Request::CollectGarbage {
+            max_idle_days,
+            dry_run,
+            only,
+        })
+    };
+    let (projects, reclaimable_bytes) = match collect(true, vec![]).await {
+        Ok(Response::Ok {
+            data:
+                Some(ResponseData::Garbage {
+                    projects,
+                    reclaimable_bytes,
+                    ..
+                }),
+        }) => (projects, reclaimable_bytes),
+        Ok(Response::Error { message, .. }) => {
+            out.failure(format!("Garbage collection failed: {}", message));
+            return Ok(());
+        }
+        Ok(_) => {
+            out.failure("Unexpected response");
+            return Ok(());
+        }
+        Err(e) => {
+            out.failure(format!("Error: {}", e));
+            return Ok(());
+        }
+    };
+
+    if projects.is_empty() {
+        out.success("No garbage found");
+        return Ok(());
+    }
+    if dry_run {
+        let value = json!({ "projects": projects, "reclaimable_bytes": reclaimable_bytes });
+        return out.emit(&value, || {
+            print_garbage(&projects);
+            println!();
+            println!(
+                "{} project(s), {} reclaimable. Run without --dry-run to delete.",
+                projects.len(),
+                format_bytes(reclaimable_bytes)
+            );
+        });
+    }
+
+    if !yes {
+        print_garbage(&projects);
+        eprint!(
+            "Delete {} project(s), freeing {}? [y/N] ",
+            projects.len(),
+            format_bytes(reclaimable_bytes)
+        );
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        if !matches!(answer.trim(), "y" | "Y" | "yes") {
+            out.message("Nothing deleted");
+            return Ok(());
+        }
+    }
+
+    // Delete only what was listed, even if more became garbage meanwhile
+    let only = projects.iter().flat_map(|p| p.dirs.clone()).collect();
+    match collect(false, only).await {
+        Ok(Response::Ok {
+            data:
+                Some(ResponseData::Garbage {
+                    deleted,
+                    reclaimable_bytes,
+                    ..
+                }),
+        }) => out.success(format!(
+            "Deleted {} project(s), freed {}",
+            deleted,
+            format_bytes(reclaimable_bytes)
+        )),
+        Ok(Response::Error { message, .. }) => {
+            out.failure(format!("Garbage collection failed: {}", message))
+        }
+        Ok(_) => out.failure("Unexpected response"),
+        Err(e) => out.failure(format!("Error: {}", e)),
+    }
+
+    Ok(())
+}
+
+/// Print garbage found by `engram gc` as a table.
+fn print_garbage(projects: &[GarbageProject]) {
+    println!("{:<16} {:>6} {:>10}  PROJECT", "REASON", "IDLE", "SIZE");
+    for project in projects {
+        let root = match &project.root {
+            Some(root) => root.display().to_string(),
+            None => format!("(unknown) {}", project.dirs[0].display()),
+        };
+        println!(
+            "{:<16} {:>5}d {:>10}  {}",
+            project.reason,
+            project.idle_days,
+            format_bytes(project.bytes),
+            root
+        );
+    }
+}
+
+async fn cmd_health(out: Output) -> Result<()> {
+    let client = IpcClient::new();
+
+    if !client.is_daemon_running() {
+        out.failure("Daemon not running");
+        return Ok(());
+    }
+
+    match client.request(Request::HealthCheck).await {
+        Ok(Response::Ok {
+            data:
+                Some(ResponseData::Health {
+                    status,
+                    uptime_secs,
+                    components,
+                    ..
+                }),
+        }) => out.emit(
+            &json!({ "status": status, "uptime_secs": uptime_secs, "components": components }),
+            || {
+                for check in &components {
+                    let mark = match check.status {
+                        CheckStatus::Ok => "✓",
+                        CheckStatus::Warning => "!",
+                        CheckStatus::Failed => "✗",
+                    };
+                    println!("{} {}: {}", mark, check.name, check.message);
+                    if let Some(fix) = &check.fix {
+                        println!("    fix: {}", fix);
+                    }
+                }
+                println!();
+                let status = match status {
+                    CheckStatus::Ok => "healthy",
+                    CheckStatus::Warning => "degraded",
+                    CheckStatus::Failed => "unhealthy",
+                };
+                println!("Daemon is {} (up {}s).", status, uptime_secs);
+            },
+        )?,
+        Ok(Response::Error { message, .. }) => {
+            out.failure(format!("Health check failed: {}", message))
+        }
+        Ok(_) => out.failure("Unexpected response"),
+        Err(e) => out.failure(format!("Error: {}", e)),
+    }
+
+    Ok(())
+}
+
+async fn cmd_doctor(out: Output) -> Result<()> {
+    let client = IpcClient::new();
+    let mut checks = Vec::new();
+
+    if client.is_daemon_running() {
+        match client.request(Request::Doctor).await {
+            Ok(Response::Ok {
+                data:
+                    Some(ResponseData::Doctor {
+                        checks: daemon_checks,
+                    }),
+            }) => {
+                checks.push(DoctorCheck::new("daemon", CheckStatus::Ok, "running"));
+                checks.extend(daemon_checks);
+            }
+            Ok(resp) => checks.push(
+                DoctorCheck::new(
+                    "daemon",
+                    CheckStatus::Warning,
+                    format!("running, but cannot diagnose itself: {:?}", resp),
+                )
+                .with_fix("Restart it after upgrading: engram stop && engram start"),
+            ),
+            Err(e) => {
+                checks.push(
+                    DoctorCheck::new(
+                        "daemon",
+                        CheckStatus::Failed,
+                        format!("socket exists but the daemon does not respond: {}", e),
+                    )
+                    .with_fix("See the socket and pid_file checks below"),
+                );
+                checks.extend(standalone_checks());
+            }
+        }
+    } else {
+        checks.push(
+            DoctorCheck::new("daemon", CheckStatus::Warning, "not running")
+                .with_fix("engram start; if it exits, engram start --foreground shows the error"),
+        );
+        checks.extend(standalone_checks());
+    }
+
+    let count = |status| checks.iter().filter(|c| c.status == status).count();
+    let (failed, warnings) = (count(CheckStatus::Failed), count(CheckStatus::Warning));
+    out.emit(&json!({ "ok": failed == 0, "checks": checks }), || {
+        for check in &checks {
+            let mark = match check.status {
+                CheckStatus::Ok => "✓",
+                CheckStatus::Warning => "!",
+                CheckStatus::Failed => "✗",
+            };
+            println!("{} {}: {}", mark, check.name, check.message);
+            if let Some(fix) = &check.fix {
+                println!("    fix: {}", fix);
+            }
+        }
+        println!();
+        if failed + warnings == 0 {
+            println!("No problems found.");
+        } else {
+            println!("{} failed, {} warning(s).", failed, warnings);
+        }
+    })
+}
+
+/// Baselines `engram bench` compares against, by repository size
+const BENCH_BASELINES_FILE: &str = "bench-baselines.json";
+
+/// Run the benchmark in a separate daemon process (`engram-daemon --bench`)
+/// and compare it against the baseline for the same repository size.
+fn cmd_bench(out: Output, files: usize, runs: usize, save_baseline: bool) -> Result<()> {
+    if files == 0 || runs == 0 {
+        out.failure("--files and --runs must be positive");
+        return Ok(());
+    }
+    out.info(format!(
+        "Benchmarking a synthetic repository of {} files...",
+        files
+    ));
+    let output = std::process::Command::new("engram-daemon")
+        .args(["--bench", "--files", &files.to_string()])
+        .args(["--runs", &runs.to_string()])
+        .output()
+        .context("Cannot run engram-daemon; make sure it is in PATH")?;
+    if !output.status.success() {
+        out.failure(format!(
+            "Benchmark failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+        return Ok(());
+    }
+    let report: BenchReport =
+        serde_json::from_slice(&output.stdout).context("Unreadable benchmark report")?;
+
+    let path = engram_ipc::paths::data_dir().join(BENCH_BASELINES_FILE);
+    let mut baselines: BTreeMap<String, BenchReport> = match std::fs::read(&path) {
+        Ok(data) => serde_json::from_slice(&data)
+            .with_context(|| format!("Failed to parse {}", path.display()))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    let key = report.files.to_string();
+    let baseline = baselines.get(&key).cloned();
+
+    let value = json!({ "ok": true, "report": report, "baseline": baseline });
+    out.emit(&value, || {
+        println!(
+            "{} files, median of {} run{}",
+            report.files,
+            report.runs,
+            if report.runs == 1 { "" } else { "s" }
+        );
+        let baseline = baseline.as_ref();
+        let rows = [
+            (
+                "Full index",
+                report.full_index_ms,
+                baseline.map(|b| b.full_index_ms),
+            ),
+            (
+                "Incremental update",
+                report.incremental_update_ms,
+                baseline.map(|b| b.incremental_update_ms),
+            ),
+            (
+                "Context render",
+                report.context_render_ms,
+                baseline.map(|b| b.context_render_ms),
+            ),
+        ];
+        for (name, ms, base) in rows {
+            println!("  {:<20} {:>10.1} ms{}", name, ms, bench_delta(ms, base));
+        }
+        println!(
+            "  {:<20} {:>10}{}",
+            "Peak memory",
+            format_bytes(report.peak_memory_bytes),
+            bench_delta(
+                report.peak_memory_bytes as f64,
+                baseline.map(|b| b.peak_memory_bytes as f64)
+            )
+        );
+        if baseline.is_none() {
+            println!();
+            println!(
+                "No baseline for {} files; store one with --save-baseline",
+                report.files
+            );
+        }
+    })?;
+
+    if save_baseline {
+        baselines.insert(key, report);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&path, serde_json::to_string_pretty(&baselines)?)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        out.success(format!("Baseline saved to {}", path.display()));
+    }
+    Ok(())
+}
+
+/// Change against a baseline value, e.g. ` (+12.5% vs baseline)`.
+fn bench_delta(value: f64, baseline: Option<f64>) -> String {
+    match baseline {
+        Some(baseline) if baseline > 0.0 => {
+            format!(
+                "  ({:+.1}% vs baseline)",
+                (value - baseline) / baseline * 100.0
+            )
+        }
+        _ => String::new(),
+    }
+}
+
+/// Run the daemon's checks without starting it (`engram-daemon --doctor`).
+fn standalone_checks() -> Vec<DoctorCheck> {
+    let failed = |message: String| DoctorCheck::new("daemon_binary", CheckStatus::Failed, message);
+    match std::process::Command::new("engram-daemon")
+        .arg("--doctor")
+        .output()
+    {
+        Ok(output) if output.status.success() => serde_json::from_slice(&output.stdout)
+            .unwrap_or_else(|e| vec![failed(format!("unreadable diagnostics: {}", e))]),
+        Ok(output) => vec![failed(format!(
+            "engram-daemon --doctor failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))],
+        Err(e) => vec![failed(format!("cannot run engram-daemon: {}", e))
+            .with_fix("Install engram-daemon and make sure it is in PATH")],
+    }
+}
+
+async fn cmd_debug_bundle(
+    out: Output,
+    path: &str,
+    output: Option<PathBuf>,
+    no_logs: bool,
+    no_status: bool,
+    no_tree: bool,
+) -> Result<()> {
+    let cwd = PathBuf::from(path).canonicalize().context("Invalid path")?;
+    let client = IpcClient::new();
+    let daemon_running = client.is_daemon_running();
+    let mut entries = Vec::new();
+    let mut warnings = Vec::new();
+    let mut warn = |message: String| {
+        out.info(format!("! {}", message));
+        warnings.push(message);
+    };
+
+    if !daemon_running && (!no_status || !no_tree) {
+        warn("Daemon not running; status and project structure are skipped.".to_string());
+    }
+
+    if !no_status && daemon_running {
+        match client.request(Request::Status).await {
+            Ok(response) => {
+                let mut status = serde_json::to_value(&response)?;
+                // Project paths are identifying; keep only their position.
+                if let Some(caches) = status
+                    .pointer_mut("/data/render_cache")
+                    .and_then(|v| v.as_array_mut())
+                {
+                    for (idx, cache) in caches.iter_mut().enumerate() {
+                        cache["project"] = format!("project-{}", idx + 1).into();
+                    }
+                }
+                entries.push(BundleEntry {
+                    name: "status.json".to_string(),
+                    description: "daemon version, uptime, request and cache counters",
+                    contents: serde_json::to_vec_pretty(&status)?,
+                });
+            }
+            Err(e) => warn(format!("Status unavailable: {}", e)),
+        }
+    }
+
+    if !no_tree && daemon_running {
+        match client
+            .request(Request::DebugSnapshot { cwd: cwd.clone() })
+            .await
+        {
+            Ok(Response::Ok {
+                data: Some(ResponseData::DebugSnapshot { project, structure }),
+            }) => {
+                entries.push(BundleEntry {
+                    name: "project.json".to_string(),
+                    description: "file count, languages, frameworks, last scan time",
+                    contents: serde_json::to_vec_pretty(&project)?,
+                });
+                entries.push(BundleEntry {
+                    name: "structure.json".to_string(),
+                    description: "directory/file shape with hashed names, sizes, line counts",
+                    contents: serde_json::to_vec_pretty(&structure)?,
+                });
+            }
+            Ok(resp) => warn(format!("Unexpected snapshot response: {:?}", resp)),
+            Err(e) => warn(format!("Project snapshot unavailable: {}", e)),
+        }
+    }
+
+    if !no_logs {
+        for log in bundle::DAEMON_LOG_FILES {
+            let log_path = Path::new(log);
+            if let Ok(contents) = bundle::read_tail(log_path, bundle::MAX_LOG_BYTES) {
+                let name = log_path.file_name().unwrap_or_default().to_string_lossy();
+                entries.push(BundleEntry {
+                    name: format!("logs/{}", name),
+                    description: "daemon log tail (may mention file paths)",
+                    contents,
+                });
+            }
+        }
+    }
+
+    let output = output.unwrap_or_else(|| {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        PathBuf::from(format!("engram-debug-{}.tar.gz", timestamp))
+    });
+
+    bundle::write_bundle(&output, &entries)?;
+
+    let value = json!({
+        "ok": true,
+        "path": output,
+        "entries": entries
+            .iter()
+            .map(|entry| json!({ "name": entry.name, "description": entry.description }))
+            .collect::<Vec<_>>(),
+        "warnings": warnings,
+    });
+    out.emit(&value, || {
+        println!("✓ Debug bundle written to {}", output.display());
+        println!();
+        print!("{}", bundle::disclosure(&entries));
+        println!();
+        println!("Review the archive before attaching it. Opt out of parts with");
+        println!("--no-logs, --no-status or --no-tree.");
+    })
+}
+
+fn format_bytes(bytes: u64) -> String {
+    if bytes < 1024 {
+        format!("{}B", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1}KB", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.1}MB", bytes as f64 / 1024.0 / 1024.0)
+    }
+}
+
 fn format_duration(secs: u64) -> String {
     if secs < 60 {
         format!("{}s", secs)
@@ -0,0 +1,207 @@
+//! Text or JSON output for commands.
+//!
+//! Commands describe each result once: a serializable value for
+//! `--format json` and a closure printing it for humans. Status lines
+//! (`✓ ...` / `✗ ...`) become `{"ok": ..., "message"|"error": ...}` objects,
+//! so scripts get one JSON document per result on stdout.
+
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::json;
+use std::fmt::Display;
+use std::io::{IsTerminal, Write};
+
+/// Output format, selected with `--format`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Human-readable text
+    #[default]
+    Text,
+    /// JSON documents for scripting
+    Json,
+}
+
+/// Prints command results in the selected format.
+#[derive(Debug, Clone, Copy)]
+pub struct Output {
+    format: Format,
+}
+
+impl Output {
+    pub fn new(format: Format) -> Self {
+        Self { format }
+    }
+
+    /// Print `value` as JSON, or run `text` to print it for humans.
+    pub fn emit<T: Serialize + ?Sized>(&self, value: &T, text: impl FnOnce()) -> Result<()> {
+        match self.format {
+            Format::Json => println!("{}", serde_json::to_string_pretty(value)?),
+            Format::Text => text(),
+        }
+        Ok(())
+    }
+
+    /// Report a completed action (`✓ message`).
+    pub fn success(&self, message: impl Display) {
+        self.print(Status::Success, message);
+    }
+
+    /// Report a failed action (`✗ message`).
+    pub fn failure(&self, message: impl Display) {
+        self.print(Status::Failure, message);
+    }
+
+    /// Report an outcome that is neither success nor failure.
+    pub fn message(&self, message: impl Display) {
+        self.print(Status::Message, message);
+    }
+
+    /// Print hints and progress for humans; nothing in JSON mode.
+    pub fn info(&self, message: impl Display) {
+        self.print(Status::Info, message);
+    }
+
+    /// Whether transient progress can be drawn: text mode with stderr on
+    /// a terminal.
+    pub fn is_interactive(&self) -> bool {
+        self.format == Format::Text && std::io::stderr().is_terminal()
+    }
+
+    /// Redraw the progress line on stderr; nothing unless interactive.
+    pub fn progress(&self, line: impl Display) {
+        if self.is_interactive() {
+            let mut stderr = std::io::stderr();
+            let _ = write!(stderr, "\r\x1b[2K{}", line);
+            let _ = stderr.flush();
+        }
+    }
+
+    /// Erase the progress line.
+    pub fn clear_progress(&self) {
+        self.progress("");
+    }
+
+    fn print(&self, status: Status, message: impl Display) {
+        if let Some(line) = self.status_line(status, message) {
+            println!("{}", line);
+        }
+    }
+
+    /// The line printed for a status message, if the format keeps it.
+    fn status_line(&self, status: Status, message: impl Display) -> Option<String> {
+        let (ok, key, prefix) = match status {
+            Status::Success => (true, "message", "✓ "),
+            Status::Failure => (false, "error", "✗ "),
+            Status::Message => (true, "message", ""),
+            Status::Info if self.format == Format::Json => return None,
+            Status::Info => (true, "message", ""),
+        };
+        Some(match self.format {
+            Format::Json => {
+                let mut value = json!({ "ok": ok });
+                value[key] = message.to_string().into();
+                value.to_string()
+            }
+            Format::Text => format!("{}{}", prefix, message),
+        })
+    }
+}
+
+/// Kinds of status line
+#[derive(Debug, Clone, Copy)]
+enum Status {
+    Success,
+    Failure,
+    Message,
+    Info,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    const TEXT: Output = Output {
+        format: Format::Text,
+    };
+    const JSON: Output = Output {
+        format: Format::Json,
+    };
+
+    fn parse(line: Option<String>) -> Value {
+        serde_json::from_str(&line.unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_text_status_lines() {
+        assert_eq!(
+            TEXT.status_line(Status::Success, "Saved").unwrap(),
+            "✓ Saved"
+        );
+        assert_eq!(TEXT.status_line(Status::Failure, "Lost").unwrap(), "✗ Lost");
+        assert_eq!(
+            TEXT.status_line(Status::Message, "3 items").unwrap(),
+            "3 items"
+        );
+        assert_eq!(TEXT.status_line(Status::Info, "Hint").unwrap(), "Hint");
+    }
+
+    #[test]
+    fn test_json_status_lines() {
+        assert_eq!(
+            parse(JSON.status_line(Status::Success, "Saved")),
+            json!({ "ok": true, "message": "Saved" })
+        );
+        assert_eq!(
+            parse(JSON.status_line(Status::Failure, "Lost")),
+            json!({ "ok": false, "error": "Lost" })
+        );
+        assert_eq!(
+            parse(JSON.status_line(Status::Message, "3 items")),
+            json!({ "ok": true, "message": "3 items" })
+        );
+        // Hints are for humans only
+        assert!(JSON.status_line(Status::Info, "Hint").is_none());
+
+        // One document per line, so scripts can read them line by line
+        assert!(!JSON
+            .status_line(Status::Success, "Saved")
+            .unwrap()
+            .contains('\n'));
+    }
+
+    #[test]
+    fn test_json_escaping() {
+        let message = "Path \"C:\\src\"\n\tnot found: ✗ café";
+        let line = JSON.status_line(Status::Failure, message).unwrap();
+        assert!(!line.contains('\n'));
+        assert_eq!(parse(Some(line))["error"], message);
+
+        // Text passes messages through unchanged
+        assert_eq!(TEXT.status_line(Status::Message, message).unwrap(), message);
+    }
+
+    #[test]
+    fn test_empty_results() {
+        let mut printed = false;
+        JSON.emit(&Vec::<String>::new(), || printed = true).unwrap();
+        assert!(!printed);
+        TEXT.emit(&Vec::<String>::new(), || printed = true).unwrap();
+        assert!(printed);
+
+        assert_eq!(TEXT.status_line(Status::Message, "").unwrap(), "");
+        assert_eq!(
+            parse(JSON.status_line(Status::Message, "")),
+            json!({ "ok": true, "message": "" })
+        );
+    }
+
+    #[test]
+    fn test_format_flag() {
+        assert_eq!(Format::default(), Format::Text);
+        assert_eq!(Format::from_str("json", true).unwrap(), Format::Json);
+        assert_eq!(Format::from_str("text", true).unwrap(), Format::Text);
+        assert!(Format::from_str("yaml", true).is_err());
+    }
+}
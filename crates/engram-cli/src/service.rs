@@ -0,0 +1,237 @@
+//! Login service definitions for the daemon.
+//!
+//! Linux gets a systemd user unit, macOS a launchd agent. The systemd unit
+//! is `Type=notify`: the daemon signals readiness once its socket is bound,
+//! so `systemctl --user start` returns when the daemon accepts requests.
+
+use crate::bundle::DAEMON_LOG_FILES;
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Name of the daemon binary
+pub const DAEMON_BINARY: &str = "engram-daemon";
+
+/// systemd user unit name
+pub const SYSTEMD_UNIT: &str = "engram.service";
+
+/// launchd agent label
+pub const LAUNCHD_LABEL: &str = "com.engram.daemon";
+
+/// Service manager running the daemon at login
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceManager {
+    Systemd,
+    Launchd,
+}
+
+impl ServiceManager {
+    /// The service manager of this platform, if supported
+    pub fn current() -> Option<Self> {
+        if cfg!(target_os = "macos") {
+            Some(Self::Launchd)
+        } else if cfg!(target_os = "linux") {
+            Some(Self::Systemd)
+        } else {
+            None
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Systemd => "systemd",
+            Self::Launchd => "launchd",
+        }
+    }
+
+    /// Where the service definition is installed
+    pub fn definition_path(self) -> Result<PathBuf> {
+        Ok(match self {
+            Self::Systemd => dirs::config_dir()
+                .context("No config directory")?
+                .join("systemd/user")
+                .join(SYSTEMD_UNIT),
+            Self::Launchd => dirs::home_dir()
+                .context("No home directory")?
+                .join("Library/LaunchAgents")
+                .join(format!("{}.plist", LAUNCHD_LABEL)),
+        })
+    }
+
+    /// Service definition running the daemon at `daemon`
+    pub fn definition(self, daemon: &Path) -> String {
+        match self {
+            Self::Systemd => format!(
+                "[Unit]\n\
+                 Description=Engram context daemon\n\
+                 \n\
+                 [Service]\n\
+                 Type=notify\n\
+                 ExecStart=\"{}\"\n\
+                 Restart=on-failure\n\
+                 Environment=RUST_LOG=info\n\
+                 MemoryHigh=80M\n\
+                 MemoryMax=100M\n\
+                 \n\
+                 [Install]\n\
+                 WantedBy=default.target\n",
+                daemon.display()
+            ),
+            Self::Launchd => format!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{label}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{daemon}</string>
+    </array>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <dict>
+        <key>SuccessfulExit</key>
+        <false/>
+    </dict>
+    <key>StandardOutPath</key>
+    <string>{stdout}</string>
+    <key>StandardErrorPath</key>
+    <string>{stderr}</string>
+    <key>EnvironmentVariables</key>
+    <dict>
+        <key>RUST_LOG</key>
+        <string>info</string>
+    </dict>
+    <key>ProcessType</key>
+    <string>Background</string>
+    <key>LowPriorityIO</key>
+    <true/>
+</dict>
+</plist>
+"#,
+                label = LAUNCHD_LABEL,
+                daemon = xml_escape(&daemon.display().to_string()),
+                stdout = DAEMON_LOG_FILES[0],
+                stderr = DAEMON_LOG_FILES[1],
+            ),
+        }
+    }
+
+    /// Whether a service definition is installed
+    pub fn is_installed(self) -> bool {
+        self.definition_path().is_ok_and(|path| path.exists())
+    }
+
+    /// Write the service definition and start the service, now and at login.
+    pub fn install(self, daemon: &Path) -> Result<PathBuf> {
+        let path = self.definition_path()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        std::fs::write(&path, self.definition(daemon))
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+
+        match self {
+            Self::Systemd => {
+                run(Command::new("systemctl").args(["--user", "daemon-reload"]))?;
+                run(Command::new("systemctl").args(["--user", "enable", "--now", SYSTEMD_UNIT]))?;
+            }
+            Self::Launchd => run(Command::new("launchctl").args(["load", "-w"]).arg(&path))?,
+        }
+        Ok(path)
+    }
+
+    /// Stop the service and remove its definition; `None` if none was
+    /// installed.
+    pub fn uninstall(self) -> Result<Option<PathBuf>> {
+        let path = self.definition_path()?;
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        // Stopping fails if the service is not running; remove it anyway
+        match self {
+            Self::Systemd => {
+                let _ = run(Command::new("systemctl").args([
+                    "--user",
+                    "disable",
+                    "--now",
+                    SYSTEMD_UNIT,
+                ]));
+            }
+            Self::Launchd => {
+                let _ = run(Command::new("launchctl").args(["unload", "-w"]).arg(&path));
+            }
+        }
+        std::fs::remove_file(&path)
+            .with_context(|| format!("Failed to remove {}", path.display()))?;
+        if self == Self::Systemd {
+            run(Command::new("systemctl").args(["--user", "daemon-reload"]))?;
+        }
+        Ok(Some(path))
+    }
+
+    /// Start the installed service
+    pub fn start(self) -> Result<()> {
+        match self {
+            Self::Systemd => run(Command::new("systemctl").args(["--user", "start", SYSTEMD_UNIT])),
+            Self::Launchd => run(Command::new("launchctl")
+                .args(["load", "-w"])
+                .arg(self.definition_path()?)),
+        }
+    }
+
+    /// Whether the service manager reports the service running
+    pub fn is_active(self) -> bool {
+        let mut command = match self {
+            Self::Systemd => {
+                let mut command = Command::new("systemctl");
+                command.args(["--user", "is-active", "--quiet", SYSTEMD_UNIT]);
+                command
+            }
+            Self::Launchd => {
+                let mut command = Command::new("launchctl");
+                command.args(["list", LAUNCHD_LABEL]);
+                command
+            }
+        };
+        command
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+    }
+}
+
+/// Locate the daemon: next to this executable, else on `PATH`.
+pub fn daemon_binary() -> Option<PathBuf> {
+    let sibling = std::env::current_exe()
+        .ok()
+        .and_then(|exe| Some(exe.parent()?.join(DAEMON_BINARY)));
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    sibling
+        .into_iter()
+        .chain(std::env::split_paths(&path).map(|dir| dir.join(DAEMON_BINARY)))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Run a service manager command, failing on a non-zero exit.
+fn run(command: &mut Command) -> Result<()> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let status = command
+        .status()
+        .with_context(|| format!("Failed to run {}", program))?;
+    if !status.success() {
+        bail!("{} exited with {}", program, status);
+    }
+    Ok(())
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
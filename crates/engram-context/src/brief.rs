@@ -0,0 +1,251 @@
+//! Project brief: a standing overview rendered at the top of every context.
+//!
+//! The brief is a memory entry of kind [`PROJECT_BRIEF_KIND`], kept under
+//! one fixed id per project so each `MemoryPut` of that kind replaces it.
+//! Agents curate it with architecture notes, conventions and a glossary.
+//! Until they do, enrichment fills in a generated brief from the tree's
+//! languages and the summaries of its top-level files and directories; a
+//! curated brief is never overwritten by a generated one.
+
+use engram_indexer::tree::{Node, NodeId, NodeKind, Tree};
+use engram_ipc::{MemoryEntry, MemoryVisibility};
+use std::collections::VecDeque;
+
+/// Memory kind of project brief entries.
+pub const PROJECT_BRIEF_KIND: &str = "project_brief";
+
+/// Memory id the brief of every project is stored under.
+pub const PROJECT_BRIEF_ID: &str = "project-brief";
+
+/// Tag marking a brief generated from the tree rather than curated
+pub const GENERATED_BRIEF_TAG: &str = "generated";
+
+/// File summaries quoted per top-level directory
+const DIR_SUMMARIES: usize = 2;
+
+/// Characters kept of a quoted summary
+const MAX_SUMMARY_CHARS: usize = 160;
+
+/// The brief of a project.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectBrief {
+    /// Markdown content
+    pub content: String,
+    /// Whether the brief was generated from the tree
+    pub generated: bool,
+    /// Last update (unix seconds)
+    pub updated_at: i64,
+}
+
+impl ProjectBrief {
+    /// Read a brief from a live memory entry of the brief kind.
+    pub fn from_entry(entry: &MemoryEntry) -> Option<Self> {
+        if entry.kind != PROJECT_BRIEF_KIND || entry.deleted {
+            return None;
+        }
+        Some(Self {
+            content: entry.content.clone(),
+            generated: entry.tags.iter().any(|tag| tag == GENERATED_BRIEF_TAG),
+            updated_at: entry.updated_at,
+        })
+    }
+
+    /// The memory entry storing this brief.
+    pub fn to_entry(&self) -> MemoryEntry {
+        MemoryEntry {
+            id: PROJECT_BRIEF_ID.to_string(),
+            kind: PROJECT_BRIEF_KIND.to_string(),
+            content: self.content.clone(),
+            tags: if self.generated {
+                vec![GENERATED_BRIEF_TAG.to_string()]
+            } else {
+                vec![]
+            },
+            created_at: 0,
+            updated_at: self.updated_at,
+            session_id: None,
+            subagent_id: None,
+            deleted: false,
+            experience_id: None,
+            visibility: MemoryVisibility::Project,
+        }
+    }
+}
+
+/// Generate a brief from an enriched tree; `None` before any file has a
+/// summary.
+pub fn summarize_brief(tree: &Tree) -> Option<String> {
+    let mut entries = Vec::new();
+    let mut top_level = tree.children(tree.root_id);
+    top_level.sort_by(|a, b| a.name.cmp(&b.name));
+    for node in top_level {
+        match node.kind {
+            NodeKind::Directory => {
+                let (files, summaries) = directory_summaries(tree, node.id);
+                if files == 0 {
+                    continue;
+                }
+                let mut line = format!(
+                    "- `{}/` ({} file{})",
+                    node.name,
+                    files,
+                    if files == 1 { "" } else { "s" }
+                );
+                if !summaries.is_empty() {
+                    line.push_str(": ");
+                    line.push_str(&summaries.join(" "));
+                }
+                entries.push((line, !summaries.is_empty()));
+            }
+            NodeKind::File { .. } => {
+                if let Some(summary) = file_summary(tree, node) {
+                    entries.push((format!("- `{}`: {}", node.name, summary), true));
+                }
+            }
+            NodeKind::Symbol { .. } => {}
+        }
+    }
+    if !entries.iter().any(|(_, summarized)| *summarized) {
+        return None;
+    }
+
+    let mut out = String::new();
+    if !tree.languages.is_empty() {
+        let languages: Vec<_> = tree.languages.iter().map(|l| l.name()).collect();
+        out.push_str(&format!("Languages: {}\n", languages.join(", ")));
+    }
+    if !tree.frameworks.is_empty() {
+        let frameworks: Vec<_> = tree.frameworks.iter().map(|f| f.name.as_str()).collect();
+        out.push_str(&format!("Frameworks: {}\n", frameworks.join(", ")));
+    }
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    for (line, _) in entries {
+        out.push_str(&line);
+        out.push('\n');
+    }
+    Some(out)
+}
+
+/// File count of a directory and the summaries of its shallowest
+/// summarized files.
+fn directory_summaries(tree: &Tree, dir: NodeId) -> (usize, Vec<String>) {
+    let mut files = 0;
+    let mut summaries = Vec::new();
+    let mut queue = VecDeque::from([dir]);
+    while let Some(id) = queue.pop_front() {
+        let mut children = tree.children(id);
+        children.sort_by(|a, b| a.name.cmp(&b.name));
+        for child in children {
+            match child.kind {
+                NodeKind::Directory => queue.push_back(child.id),
+                NodeKind::File { .. } => {
+                    files += 1;
+                    if summaries.len() < DIR_SUMMARIES {
+                        summaries.extend(file_summary(tree, child));
+                    }
+                }
+                NodeKind::Symbol { .. } => {}
+            }
+        }
+    }
+    (files, summaries)
+}
+
+/// First sentence of a file's summary.
+fn file_summary(tree: &Tree, file: &Node) -> Option<String> {
+    let summary = tree.content(file.id)?.summary.clone()?;
+    let line = summary.lines().next()?.trim();
+    let sentence = match line.find(". ") {
+        Some(end) => &line[..=end],
+        None => line,
+    };
+    if sentence.is_empty() {
+        return None;
+    }
+    Some(match sentence.char_indices().nth(MAX_SUMMARY_CHARS) {
+        Some((end, _)) => format!("{}…", &sentence[..end]),
+        None => sentence.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engram_indexer::scanner::{Language, ScanResult, ScannedFile};
+    use engram_indexer::tree::TreeBuilder;
+    use std::path::PathBuf;
+
+    fn tree(summaries: &[(&str, &str)]) -> Tree {
+        let file = |path: &str| ScannedFile {
+            path: PathBuf::from(path),
+            language: Some(Language::Rust),
+            size: 0,
+            hash: String::new(),
+            line_count: 10,
+            symbols: vec![],
+            imports: vec![],
+            truncated: false,
+            asset: None,
+        };
+        let scan = ScanResult {
+            root: PathBuf::from("/project"),
+            files: vec![
+                file("build.rs"),
+                file("src/lib.rs"),
+                file("src/net/http.rs"),
+                file("src/net/tcp.rs"),
+                file("benches/load.rs"),
+            ],
+            languages: vec![Language::Rust],
+            frameworks: vec![],
+            duration_ms: 0,
+            skipped_count: 0,
+            excluded_dirs: vec![],
+        };
+        let mut tree = TreeBuilder::new().build(&scan);
+        for (path, summary) in summaries {
+            let id = tree.find_node_by_path(&PathBuf::from(path)).unwrap();
+            tree.content_mut(id).unwrap().summary = Some(summary.to_string());
+        }
+        tree
+    }
+
+    #[test]
+    fn test_summarize_brief() {
+        assert_eq!(summarize_brief(&tree(&[])), None);
+
+        let brief = summarize_brief(&tree(&[
+            ("src/lib.rs", "Crate root. Re-exports the client."),
+            ("src/net/tcp.rs", "TCP transport."),
+            ("build.rs", "Generates bindings."),
+        ]))
+        .unwrap();
+        assert_eq!(
+            brief,
+            "Languages: Rust\n\n\
+             - `benches/` (1 file)\n\
+             - `build.rs`: Generates bindings.\n\
+             - `src/` (3 files): Crate root. TCP transport.\n"
+        );
+    }
+
+    #[test]
+    fn test_brief_entry_roundtrip() {
+        let brief = ProjectBrief {
+            content: "Hexagonal architecture.".to_string(),
+            generated: true,
+            updated_at: 42,
+        };
+        let entry = brief.to_entry();
+        assert_eq!(entry.id, PROJECT_BRIEF_ID);
+        assert_eq!(ProjectBrief::from_entry(&entry), Some(brief));
+
+        let deleted = MemoryEntry {
+            deleted: true,
+            ..entry
+        };
+        assert_eq!(ProjectBrief::from_entry(&deleted), None);
+    }
+}
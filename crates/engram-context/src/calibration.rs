@@ -0,0 +1,204 @@
+//! Per-project scoring calibration for hybrid retrieval.
+//!
+//! Calibration is data, not code: it is stored next to the project's tree
+//! in the data directory, carries a format version, and can be exported,
+//! imported (e.g. to share tuning across a team) or reset to defaults.
+
+use crate::error::{ContextError, Result};
+use crate::router::ResultSource;
+use chrono::Utc;
+use engram_indexer::storage::Storage;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Current calibration format version.
+pub const CALIBRATION_VERSION: u32 = 1;
+
+/// File name of the calibration in the project data directory.
+const CALIBRATION_FILE: &str = "calibration.json";
+
+/// Score multipliers per retrieval source.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SourceWeights {
+    pub tree: f32,
+    pub vector: f32,
+    pub merged: f32,
+}
+
+impl Default for SourceWeights {
+    fn default() -> Self {
+        Self {
+            tree: 1.0,
+            vector: 1.0,
+            merged: 1.0,
+        }
+    }
+}
+
+/// Learned re-ranking calibration for one project.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Calibration {
+    /// Format version (see [`CALIBRATION_VERSION`])
+    pub version: u32,
+    /// Score multipliers per result source
+    #[serde(default)]
+    pub weights: SourceWeights,
+    /// Number of feedback samples the weights were learned from
+    #[serde(default)]
+    pub samples: u64,
+    /// Last update (Unix timestamp)
+    #[serde(default)]
+    pub updated_at: i64,
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Self {
+            version: CALIBRATION_VERSION,
+            weights: SourceWeights::default(),
+            samples: 0,
+            updated_at: 0,
+        }
+    }
+}
+
+impl Calibration {
+    /// Multiplier for results from `source`.
+    pub fn weight(&self, source: &ResultSource) -> f32 {
+        match source {
+            ResultSource::Tree => self.weights.tree,
+            ResultSource::Vector => self.weights.vector,
+            ResultSource::Merged => self.weights.merged,
+        }
+    }
+
+    /// Parse an exported calibration, upgrading older versions.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let mut calibration: Calibration = serde_json::from_str(json)?;
+        if calibration.version > CALIBRATION_VERSION {
+            return Err(ContextError::Calibration(format!(
+                "unsupported calibration version {} (this daemon supports up to {})",
+                calibration.version, CALIBRATION_VERSION
+            )));
+        }
+        let weights = calibration.weights;
+        if [weights.tree, weights.vector, weights.merged]
+            .iter()
+            .any(|w| !w.is_finite() || *w < 0.0)
+        {
+            return Err(ContextError::Calibration(
+                "weights must be finite and non-negative".to_string(),
+            ));
+        }
+        calibration.version = CALIBRATION_VERSION;
+        Ok(calibration)
+    }
+}
+
+/// Loads and stores calibration per project.
+pub struct CalibrationStore {
+    storage: Arc<Storage>,
+}
+
+impl CalibrationStore {
+    /// Create a calibration store.
+    pub fn new(storage: Arc<Storage>) -> Self {
+        Self { storage }
+    }
+
+    fn path(&self, project_path: &Path) -> PathBuf {
+        let hash = self.storage.project_hash(project_path);
+        self.storage.project_dir(&hash).join(CALIBRATION_FILE)
+    }
+
+    /// Load a project's calibration, or defaults if none is stored.
+    pub async fn load(&self, project_path: &Path) -> Result<Calibration> {
+        let path = self.path(project_path);
+        match tokio::fs::read_to_string(&path).await {
+            Ok(json) => Calibration::from_json(&json),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Calibration::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Store a project's calibration.
+    pub async fn save(&self, project_path: &Path, calibration: &Calibration) -> Result<()> {
+        let path = self.path(project_path);
+        if let Some(dir) = path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        let mut calibration = calibration.clone();
+        calibration.updated_at = Utc::now().timestamp();
+
+        let temp_path = path.with_extension("json.tmp");
+        tokio::fs::write(&temp_path, serde_json::to_vec_pretty(&calibration)?).await?;
+        tokio::fs::rename(&temp_path, &path).await?;
+        Ok(())
+    }
+
+    /// Replace a project's calibration with an exported one.
+    pub async fn import(&self, project_path: &Path, json: &str) -> Result<Calibration> {
+        let calibration = Calibration::from_json(json)?;
+        self.save(project_path, &calibration).await?;
+        Ok(calibration)
+    }
+
+    /// Drop a project's calibration, returning to defaults.
+    pub async fn reset(&self, project_path: &Path) -> Result<()> {
+        match tokio::fs::remove_file(self.path(project_path)).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engram_indexer::storage::StorageOptions;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_save_import_reset() {
+        let temp_dir = tempdir().unwrap();
+        let storage = Arc::new(Storage::with_options(StorageOptions {
+            base_dir: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        }));
+        let store = CalibrationStore::new(storage);
+        let project = Path::new("/project");
+
+        assert_eq!(store.load(project).await.unwrap(), Calibration::default());
+
+        let imported = store
+            .import(
+                project,
+                r#"{"version":1,"weights":{"tree":0.5,"vector":2.0,"merged":1.0}}"#,
+            )
+            .await
+            .unwrap();
+        assert_eq!(imported.weight(&ResultSource::Vector), 2.0);
+
+        let loaded = store.load(project).await.unwrap();
+        assert_eq!(loaded.weights, imported.weights);
+        assert!(loaded.updated_at > 0);
+
+        store.reset(project).await.unwrap();
+        assert_eq!(store.load(project).await.unwrap(), Calibration::default());
+    }
+
+    #[test]
+    fn test_rejects_newer_version_and_bad_weights() {
+        assert!(Calibration::from_json(r#"{"version":99}"#).is_err());
+        assert!(Calibration::from_json(
+            r#"{"version":1,"weights":{"tree":-1.0,"vector":1.0,"merged":1.0}}"#
+        )
+        .is_err());
+        assert_eq!(
+            Calibration::from_json(r#"{"version":1}"#).unwrap().weights,
+            SourceWeights::default()
+        );
+    }
+}
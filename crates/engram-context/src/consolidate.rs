@@ -0,0 +1,296 @@
+//! Experience deduplication and summarization.
+//!
+//! Long sessions graft many near-identical decisions. Consolidation merges
+//! each into its latest near-duplicate and folds all but the most recent
+//! experiences into one summary entry per day, keeping the anchor's recent
+//! decisions short and distinct.
+
+use crate::scope::Experience;
+use crate::vector::{dot, Embedder, HashingEmbedder};
+use chrono::{DateTime, NaiveDate};
+use std::collections::BTreeMap;
+
+/// Agent id of summary entries.
+pub const SUMMARY_AGENT: &str = "engram";
+
+/// Decisions quoted in a summary; the rest are only counted
+const SUMMARY_DECISIONS: usize = 5;
+
+/// Files listed on a summary
+const SUMMARY_FILES: usize = 20;
+
+/// When experiences are consolidated and what is merged.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConsolidationPolicy {
+    /// Consolidate a project automatically after this many grafts (0 disables)
+    pub every: usize,
+    /// Decisions at least this similar (cosine, 0 to 1) are duplicates
+    pub similarity: f32,
+    /// Most recent experiences kept as they are; older ones are summarized
+    pub keep_recent: usize,
+}
+
+impl Default for ConsolidationPolicy {
+    fn default() -> Self {
+        Self {
+            every: 50,
+            similarity: 0.9,
+            keep_recent: 20,
+        }
+    }
+}
+
+/// Outcome of an experience consolidation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ConsolidationStats {
+    /// Experiences before consolidation
+    pub experiences_before: usize,
+    /// Experiences after consolidation, summaries included
+    pub experiences_after: usize,
+    /// Duplicates merged into a later experience
+    pub merged: usize,
+    /// Experiences folded into summaries
+    pub summarized: usize,
+    /// Summary entries created
+    pub summaries: usize,
+}
+
+/// Deduplicate and summarize experiences; returns them oldest first.
+pub fn consolidate(
+    mut experiences: Vec<Experience>,
+    policy: &ConsolidationPolicy,
+) -> (Vec<Experience>, ConsolidationStats) {
+    let mut stats = ConsolidationStats {
+        experiences_before: experiences.len(),
+        ..Default::default()
+    };
+    experiences.sort_by_key(|experience| experience.timestamp);
+    let experiences = dedup(experiences, policy.similarity, &mut stats);
+    let experiences = summarize(experiences, policy.keep_recent, &mut stats);
+    stats.experiences_after = experiences.len();
+    (experiences, stats)
+}
+
+/// Merge each experience into a later near-duplicate; summaries are kept.
+fn dedup(
+    experiences: Vec<Experience>,
+    similarity: f32,
+    stats: &mut ConsolidationStats,
+) -> Vec<Experience> {
+    let mut kept: Vec<(Experience, Option<Vec<f32>>)> = Vec::new();
+    for experience in experiences {
+        if experience.summarizes > 0 {
+            kept.push((experience, None));
+            continue;
+        }
+        let embedding = HashingEmbedder.embed(&experience.decision);
+        let duplicate = kept.iter().position(|(other, other_embedding)| {
+            other.summarizes == 0
+                && (normalize(&other.decision) == normalize(&experience.decision)
+                    || matches!(
+                        (&embedding, other_embedding),
+                        (Some(a), Some(b)) if dot(a, b) >= similarity
+                    ))
+        });
+        match duplicate {
+            Some(index) => {
+                let (earlier, _) = kept.remove(index);
+                stats.merged += 1;
+                kept.push((merge(earlier, experience), embedding));
+            }
+            None => kept.push((experience, embedding)),
+        }
+    }
+    kept.into_iter().map(|(experience, _)| experience).collect()
+}
+
+/// The later experience, keeping what only the earlier one recorded.
+fn merge(earlier: Experience, mut later: Experience) -> Experience {
+    for file in earlier.files_touched {
+        if !later.files_touched.contains(&file) {
+            later.files_touched.push(file);
+        }
+    }
+    for id in earlier.memory_ids {
+        if !later.memory_ids.contains(&id) {
+            later.memory_ids.push(id);
+        }
+    }
+    later.rationale = later.rationale.or(earlier.rationale);
+    later.outcome = later.outcome.or(earlier.outcome);
+    later
+}
+
+/// Fold all but the `keep_recent` latest decisions into daily summaries.
+///
+/// A day with a single folded decision keeps it as it is.
+fn summarize(
+    experiences: Vec<Experience>,
+    keep_recent: usize,
+    stats: &mut ConsolidationStats,
+) -> Vec<Experience> {
+    let decisions = experiences.iter().filter(|e| e.summarizes == 0).count();
+    let mut fold = decisions.saturating_sub(keep_recent);
+    if fold == 0 {
+        return experiences;
+    }
+
+    let mut days: BTreeMap<NaiveDate, Vec<Experience>> = BTreeMap::new();
+    let mut result = Vec::new();
+    for experience in experiences {
+        if experience.summarizes == 0 && fold > 0 {
+            fold -= 1;
+            let day = DateTime::from_timestamp(experience.timestamp, 0)
+                .map(|time| time.date_naive())
+                .unwrap_or_default();
+            days.entry(day).or_default().push(experience);
+        } else {
+            result.push(experience);
+        }
+    }
+
+    for (day, group) in days {
+        if group.len() == 1 {
+            result.extend(group);
+            continue;
+        }
+        stats.summarized += group.len();
+        stats.summaries += 1;
+        result.push(summary(day, group));
+    }
+    result.sort_by_key(|experience| experience.timestamp);
+    result
+}
+
+/// One entry standing for a day's decisions.
+fn summary(day: NaiveDate, group: Vec<Experience>) -> Experience {
+    let mut decisions: Vec<&str> = Vec::new();
+    for experience in &group {
+        if !decisions.contains(&experience.decision.as_str()) {
+            decisions.push(&experience.decision);
+        }
+    }
+    let mut decision = format!(
+        "Summary of {} decisions on {}: {}",
+        group.len(),
+        day,
+        decisions
+            .iter()
+            .take(SUMMARY_DECISIONS)
+            .copied()
+            .collect::<Vec<_>>()
+            .join("; ")
+    );
+    if decisions.len() > SUMMARY_DECISIONS {
+        decision.push_str(&format!(" (+{} more)", decisions.len() - SUMMARY_DECISIONS));
+    }
+
+    let mut files = Vec::new();
+    let mut memory_ids = Vec::new();
+    for experience in &group {
+        for file in &experience.files_touched {
+            if files.len() < SUMMARY_FILES && !files.contains(file) {
+                files.push(file.clone());
+            }
+        }
+        for id in &experience.memory_ids {
+            if !memory_ids.contains(id) {
+                memory_ids.push(id.clone());
+            }
+        }
+    }
+
+    let mut summary = Experience::new(SUMMARY_AGENT, decision)
+        .with_files(files)
+        .with_memory_ids(memory_ids);
+    summary.timestamp = group.last().map_or(summary.timestamp, |e| e.timestamp);
+    summary.summarizes = group.len();
+    summary
+}
+
+/// Decision text compared for exact duplicates.
+fn normalize(decision: &str) -> String {
+    decision
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    const DAY: i64 = 24 * 60 * 60;
+
+    fn experience(decision: &str, timestamp: i64) -> Experience {
+        let mut experience = Experience::new("agent", decision);
+        experience.timestamp = timestamp;
+        experience
+    }
+
+    #[test]
+    fn test_duplicates_merge_into_latest() {
+        let first = experience("Added caching to the parser", 100)
+            .with_files(vec![PathBuf::from("src/parser.rs")])
+            .with_rationale("parsing is slow");
+        let second = experience("Refactored config loading", 200);
+        let third = experience("added  caching to the Parser", 300)
+            .with_files(vec![PathBuf::from("src/cache.rs")]);
+
+        let (experiences, stats) = consolidate(
+            vec![first, second, third.clone()],
+            &ConsolidationPolicy::default(),
+        );
+        assert_eq!(stats.merged, 1);
+        assert_eq!(experiences.len(), 2);
+        let merged = &experiences[1];
+        assert_eq!(merged.id, third.id);
+        assert_eq!(
+            merged.files_touched,
+            vec![
+                PathBuf::from("src/cache.rs"),
+                PathBuf::from("src/parser.rs")
+            ]
+        );
+        assert_eq!(merged.rationale.as_deref(), Some("parsing is slow"));
+    }
+
+    #[test]
+    fn test_old_experiences_summarized_per_day() {
+        let policy = ConsolidationPolicy {
+            keep_recent: 2,
+            ..Default::default()
+        };
+        let experiences = vec![
+            experience("Split the scanner module", 0),
+            experience("Renamed tree nodes", 10),
+            experience("Dropped the legacy socket", 20),
+            experience("Fixed the watcher debounce", DAY),
+            experience("Tuned rerank weights", 2 * DAY),
+            experience("Documented the diff command", 2 * DAY + 10),
+        ];
+
+        let (experiences, stats) = consolidate(experiences, &policy);
+        assert_eq!(stats.summaries, 1);
+        assert_eq!(stats.summarized, 3);
+        assert_eq!(stats.experiences_after, 4);
+
+        let summary = &experiences[0];
+        assert_eq!(summary.summarizes, 3);
+        assert_eq!(summary.agent_id, SUMMARY_AGENT);
+        assert_eq!(summary.timestamp, 20);
+        assert!(summary
+            .decision
+            .starts_with("Summary of 3 decisions on 1970-01-01:"));
+        assert!(summary.decision.contains("Renamed tree nodes"));
+        // A day's lone decision is kept as it is
+        assert_eq!(experiences[1].decision, "Fixed the watcher debounce");
+
+        // Summaries are neither merged nor summarized again
+        let (again, stats) = consolidate(experiences, &policy);
+        assert_eq!(again.len(), 4);
+        assert_eq!(stats.merged + stats.summarized, 0);
+    }
+}
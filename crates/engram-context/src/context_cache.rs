@@ -0,0 +1,406 @@
+//! Cache of complete context responses.
+//!
+//! Repeated prompts skip scope building, tree loading and rendering. Unlike
+//! the render cache, entries cannot be checked against the tree, so they
+//! are dropped whenever a project's files, experiences or memory change.
+//! Each invalidation bumps the project's generation, and inserts computed
+//! against an older generation are discarded.
+//!
+//! Contexts can also be prefetched before anyone asks for them. A
+//! prefetched entry is stored without a prompt and serves any prompt with
+//! the same focus, since the prompt does not change what is rendered.
+
+use parking_lot::RwLock;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Default number of contexts kept per project.
+const DEFAULT_ENTRIES_PER_PROJECT: usize = 32;
+
+/// Cache key for one context request.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ContextKey {
+    /// Hash of the prompt
+    pub prompt_hash: u64,
+    /// Hash of the focus references, independent of their order
+    pub focus_hash: u64,
+    /// Sub-agent with read-only memory the context was built for
+    pub agent_id: Option<String>,
+    /// Project override of the renderer's size limit
+    pub max_content_size: Option<usize>,
+    /// Context profile the context was built with
+    pub profile: Option<String>,
+    /// Agent session whose scope the context was built in
+    pub session_id: Option<String>,
+}
+
+impl ContextKey {
+    /// Build the key for a prompt and focus set.
+    pub fn new(prompt: Option<&str>, focus: &[String]) -> Self {
+        let mut focus: Vec<&String> = focus.iter().collect();
+        focus.sort();
+        focus.dedup();
+        Self {
+            prompt_hash: hash(&prompt),
+            focus_hash: hash(&focus),
+            agent_id: None,
+            max_content_size: None,
+            profile: None,
+            session_id: None,
+        }
+    }
+
+    /// Key a context built for a read-only sub-agent.
+    pub fn with_agent(mut self, agent_id: Option<String>) -> Self {
+        self.agent_id = agent_id;
+        self
+    }
+
+    /// Key a context rendered with a project size limit.
+    pub fn with_max_content_size(mut self, size: Option<usize>) -> Self {
+        self.max_content_size = size;
+        self
+    }
+
+    /// Key a context built with a named profile.
+    pub fn with_profile(mut self, profile: Option<String>) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    /// Key a context built for an agent session.
+    pub fn with_session(mut self, session_id: Option<String>) -> Self {
+        self.session_id = session_id;
+        self
+    }
+
+    /// The same key for a request without a prompt.
+    pub fn without_prompt(&self) -> Self {
+        Self {
+            prompt_hash: hash(&None::<&str>),
+            ..self.clone()
+        }
+    }
+}
+
+fn hash(value: &impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// A cached context response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedContext {
+    /// Rendered context
+    pub context: String,
+    /// Primary focus node ids
+    pub nodes: Vec<String>,
+    /// Scope the context was built from
+    pub scope_id: String,
+}
+
+/// Prefetch counters, across projects.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PrefetchStats {
+    /// Contexts cached ahead of a request
+    pub prefetched: u64,
+    /// Prefetched contexts that served a request
+    pub hits: u64,
+}
+
+#[derive(Default)]
+struct ProjectCache {
+    entries: HashMap<ContextKey, CachedContext>,
+    /// Insertion order, oldest first
+    order: VecDeque<ContextKey>,
+    generation: u64,
+    /// Prefetched entries not served yet, with the scope each was built from
+    prefetched: HashMap<ContextKey, String>,
+}
+
+/// Per-project cache of context responses.
+pub struct ContextCache {
+    projects: RwLock<HashMap<PathBuf, ProjectCache>>,
+    max_entries: usize,
+    prefetches: AtomicU64,
+    prefetch_hits: AtomicU64,
+}
+
+impl ContextCache {
+    /// Create a cache with the default per-project capacity.
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_ENTRIES_PER_PROJECT)
+    }
+
+    /// Create a cache holding at most `max_entries` contexts per project.
+    pub fn with_capacity(max_entries: usize) -> Self {
+        Self {
+            projects: RwLock::new(HashMap::new()),
+            max_entries: max_entries.max(1),
+            prefetches: AtomicU64::new(0),
+            prefetch_hits: AtomicU64::new(0),
+        }
+    }
+
+    /// Look up a cached context, falling back to a prefetched context with
+    /// the same focus.
+    pub fn get(&self, project: &Path, key: &ContextKey) -> Option<CachedContext> {
+        let (key, context, prefetched) = {
+            let projects = self.projects.read();
+            let cache = projects.get(project)?;
+            match cache.entries.get(key) {
+                Some(context) => (
+                    key.clone(),
+                    context.clone(),
+                    cache.prefetched.contains_key(key),
+                ),
+                None => {
+                    let key = key.without_prompt();
+                    if !cache.prefetched.contains_key(&key) {
+                        return None;
+                    }
+                    let context = cache.entries.get(&key)?.clone();
+                    (key, context, true)
+                }
+            }
+        };
+
+        // Count each prefetched entry once, then treat it like any other
+        if prefetched {
+            let served = self
+                .projects
+                .write()
+                .get_mut(project)
+                .and_then(|cache| cache.prefetched.remove(&key));
+            if served.is_some() {
+                self.prefetch_hits.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        Some(context)
+    }
+
+    /// Current generation of a project; pass it to [`ContextCache::insert`].
+    pub fn generation(&self, project: &Path) -> u64 {
+        self.projects
+            .read()
+            .get(project)
+            .map_or(0, |cache| cache.generation)
+    }
+
+    /// Store a context built while the project was at `generation`.
+    ///
+    /// Returns `false` if the project was invalidated in the meantime, in
+    /// which case nothing is stored. The oldest entries are evicted once the
+    /// project is over capacity.
+    pub fn insert(
+        &self,
+        project: &Path,
+        key: ContextKey,
+        context: CachedContext,
+        generation: u64,
+    ) -> bool {
+        let mut projects = self.projects.write();
+        let cache = projects.entry(project.to_path_buf()).or_default();
+        if cache.generation != generation {
+            return false;
+        }
+
+        if cache.entries.insert(key.clone(), context).is_none() {
+            cache.order.push_back(key);
+        }
+        while cache.order.len() > self.max_entries {
+            if let Some(oldest) = cache.order.pop_front() {
+                cache.entries.remove(&oldest);
+            }
+        }
+        true
+    }
+
+    /// Store a context built ahead of a request; `key` should have no
+    /// prompt. Returns `false` like [`ContextCache::insert`].
+    pub fn insert_prefetched(
+        &self,
+        project: &Path,
+        key: ContextKey,
+        context: CachedContext,
+        generation: u64,
+    ) -> bool {
+        let scope_id = context.scope_id.clone();
+        if !self.insert(project, key.clone(), context, generation) {
+            return false;
+        }
+        if let Some(cache) = self.projects.write().get_mut(project) {
+            cache.prefetched.insert(key, scope_id);
+        }
+        self.prefetches.fetch_add(1, Ordering::Relaxed);
+        true
+    }
+
+    /// Drop a project's prefetched contexts that never served a request,
+    /// returning the scopes they were built from.
+    pub fn take_unserved(&self, project: &Path) -> Vec<String> {
+        let mut projects = self.projects.write();
+        let Some(cache) = projects.get_mut(project) else {
+            return Vec::new();
+        };
+        let unserved: Vec<(ContextKey, String)> = cache.prefetched.drain().collect();
+        for (key, _) in &unserved {
+            cache.entries.remove(key);
+            cache.order.retain(|k| k != key);
+        }
+        unserved.into_iter().map(|(_, scope_id)| scope_id).collect()
+    }
+
+    /// Prefetch counters since the cache was created.
+    pub fn prefetch_stats(&self) -> PrefetchStats {
+        PrefetchStats {
+            prefetched: self.prefetches.load(Ordering::Relaxed),
+            hits: self.prefetch_hits.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Drop one entry, e.g. when its scope is gone.
+    pub fn remove(&self, project: &Path, key: &ContextKey) {
+        if let Some(cache) = self.projects.write().get_mut(project) {
+            cache.entries.remove(key);
+            cache.order.retain(|k| k != key);
+        }
+    }
+
+    /// Drop all cached contexts for a project.
+    pub fn invalidate(&self, project: &Path) {
+        let mut projects = self.projects.write();
+        let cache = projects.entry(project.to_path_buf()).or_default();
+        cache.entries.clear();
+        cache.order.clear();
+        cache.generation += 1;
+    }
+
+    /// Number of contexts cached for a project.
+    pub fn len(&self, project: &Path) -> usize {
+        self.projects
+            .read()
+            .get(project)
+            .map_or(0, |cache| cache.entries.len())
+    }
+}
+
+impl Default for ContextCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cached(context: &str) -> CachedContext {
+        CachedContext {
+            context: context.to_string(),
+            nodes: vec![],
+            scope_id: "scope".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_key_ignores_focus_order() {
+        let focus = ["src/a.rs".to_string(), "src/b.rs".to_string()];
+        let reversed = [focus[1].clone(), focus[0].clone()];
+        assert_eq!(
+            ContextKey::new(Some("fix it"), &focus),
+            ContextKey::new(Some("fix it"), &reversed)
+        );
+        assert_ne!(
+            ContextKey::new(Some("fix it"), &focus),
+            ContextKey::new(Some("fix that"), &focus)
+        );
+        assert_ne!(
+            ContextKey::new(None, &focus),
+            ContextKey::new(None, &focus).with_agent(Some("sub".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_invalidate_rejects_stale_inserts() {
+        let cache = ContextCache::new();
+        let project = Path::new("/project");
+        let key = ContextKey::new(Some("prompt"), &[]);
+
+        let generation = cache.generation(project);
+        assert!(cache.insert(project, key.clone(), cached("ctx"), generation));
+        assert_eq!(cache.get(project, &key), Some(cached("ctx")));
+
+        // Built before the invalidation finished
+        let stale = cache.generation(project);
+        cache.invalidate(project);
+        assert!(cache.get(project, &key).is_none());
+        assert!(!cache.insert(project, key.clone(), cached("old"), stale));
+        assert_eq!(cache.len(project), 0);
+    }
+
+    #[test]
+    fn test_capacity_evicts_oldest() {
+        let cache = ContextCache::with_capacity(2);
+        let project = Path::new("/project");
+        let key = |prompt: &str| ContextKey::new(Some(prompt), &[]);
+
+        for prompt in ["a", "b", "c"] {
+            cache.insert(project, key(prompt), cached(prompt), 0);
+        }
+        assert!(cache.get(project, &key("a")).is_none());
+        assert!(cache.get(project, &key("c")).is_some());
+
+        cache.remove(project, &key("c"));
+        assert_eq!(cache.len(project), 1);
+    }
+
+    #[test]
+    fn test_prefetched_entries_serve_any_prompt_once() {
+        let cache = ContextCache::new();
+        let project = Path::new("/project");
+        let focus = ["src/a.rs".to_string()];
+
+        let generation = cache.generation(project);
+        assert!(cache.insert_prefetched(
+            project,
+            ContextKey::new(None, &focus),
+            cached("ctx"),
+            generation
+        ));
+        assert!(cache
+            .get(
+                project,
+                &ContextKey::new(Some("fix it"), &["src/b.rs".to_string()])
+            )
+            .is_none());
+        assert_eq!(
+            cache.get(project, &ContextKey::new(Some("fix it"), &focus)),
+            Some(cached("ctx"))
+        );
+        assert_eq!(
+            cache.prefetch_stats(),
+            PrefetchStats {
+                prefetched: 1,
+                hits: 1
+            }
+        );
+
+        // Served entries are no longer prefetched
+        assert!(cache
+            .get(project, &ContextKey::new(Some("other"), &focus))
+            .is_none());
+        assert!(cache.get(project, &ContextKey::new(None, &focus)).is_some());
+        assert_eq!(cache.prefetch_stats().hits, 1);
+        assert!(cache.take_unserved(project).is_empty());
+
+        let key = ContextKey::new(None, &["src/b.rs".to_string()]);
+        cache.insert_prefetched(project, key.clone(), cached("b"), generation);
+        assert_eq!(cache.take_unserved(project), vec!["scope".to_string()]);
+        assert!(cache.get(project, &key).is_none());
+    }
+}
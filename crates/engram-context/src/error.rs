@@ -1,6 +1,9 @@
 //! Error types for context management.
 
+use engram_indexer::IndexerError;
+use engram_ipc::{io_error_details, DescribeError, ErrorDetails, ErrorKind};
 use std::path::PathBuf;
+use std::time::Duration;
 use thiserror::Error;
 
 /// Errors that can occur during context operations.
@@ -24,12 +27,24 @@ pub enum ContextError {
 
     /// Indexer error
     #[error("Indexer error: {0}")]
-    Indexer(#[from] engram_indexer::IndexerError),
+    Indexer(#[from] IndexerError),
+
+    /// A forked scope's focus overlaps one of its siblings
+    #[error("Focus overlaps sibling scope {scope_id}: {path}")]
+    FocusOverlap { scope_id: String, path: PathBuf },
 
     /// Render error
     #[error("Render error: {0}")]
     Render(String),
 
+    /// Cancelled by the caller or past its deadline
+    #[error("Cancelled")]
+    Cancelled,
+
+    /// Invalid or unsupported calibration
+    #[error("Calibration error: {0}")]
+    Calibration(String),
+
     /// IO error
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
@@ -40,3 +55,71 @@ pub enum ContextError {
 }
 
 pub type Result<T> = std::result::Result<T, ContextError>;
+
+impl DescribeError for ContextError {
+    fn details(&self) -> ErrorDetails {
+        match self {
+            ContextError::ScopeNotFound(_) => {
+                ErrorDetails::new(ErrorKind::NotFound).with_field("scope_id")
+            }
+            ContextError::ProjectNotFound(_) => {
+                ErrorDetails::new(ErrorKind::NotFound).with_field("cwd")
+            }
+            ContextError::NodeNotFound(_) => {
+                ErrorDetails::new(ErrorKind::NotFound).with_field("node")
+            }
+            ContextError::Storage(_) | ContextError::Json(_) => {
+                ErrorDetails::new(ErrorKind::Storage)
+            }
+            ContextError::Indexer(e) => indexer_error_details(e),
+            ContextError::FocusOverlap { .. } => {
+                ErrorDetails::new(ErrorKind::Conflict).with_field("focus")
+            }
+            ContextError::Render(_) => ErrorDetails::new(ErrorKind::Internal),
+            ContextError::Cancelled => ErrorDetails::new(ErrorKind::Cancelled),
+            ContextError::Calibration(_) => {
+                ErrorDetails::new(ErrorKind::InvalidInput).with_field("calibration")
+            }
+            ContextError::Io(e) => io_error_details(e),
+        }
+    }
+}
+
+/// Wire details for an indexer error.
+///
+/// Git failures are retryable, since they are usually another process
+/// holding the repository lock.
+pub fn indexer_error_details(error: &IndexerError) -> ErrorDetails {
+    match error {
+        IndexerError::Io(e) => io_error_details(e),
+        IndexerError::Parse { .. } | IndexerError::Grammar { .. } => {
+            ErrorDetails::new(ErrorKind::Parse)
+        }
+        IndexerError::Serialization(_) | IndexerError::Storage(_) => {
+            ErrorDetails::new(ErrorKind::Storage)
+        }
+        IndexerError::Watcher(_) => ErrorDetails::new(ErrorKind::Internal),
+        IndexerError::NotFound(_) => ErrorDetails::new(ErrorKind::NotFound),
+        IndexerError::UnsupportedLanguage(_) => ErrorDetails::new(ErrorKind::Unsupported),
+        IndexerError::InvalidGlob(_) => ErrorDetails::new(ErrorKind::InvalidInput),
+        IndexerError::Git(_) => {
+            ErrorDetails::new(ErrorKind::Internal).with_retry_after(Duration::from_millis(500))
+        }
+        IndexerError::Cancelled => ErrorDetails::new(ErrorKind::Cancelled),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_details() {
+        let details = ContextError::ScopeNotFound("scope-1".to_string()).details();
+        assert_eq!(details.kind, ErrorKind::NotFound);
+        assert_eq!(details.field.as_deref(), Some("scope_id"));
+
+        let details = ContextError::Indexer(IndexerError::Git("index.lock".to_string())).details();
+        assert_eq!(details.retry_after(), Some(Duration::from_millis(500)));
+    }
+}
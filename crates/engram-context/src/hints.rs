@@ -0,0 +1,274 @@
+//! Per-line hints for editor gutter annotations.
+//!
+//! Combines symbol boundaries, memories that reference the file, and the
+//! latest experience touching the file into a compact, line-sorted list.
+
+use crate::scope::Experience;
+use engram_indexer::scanner::Symbol;
+use engram_ipc::{LineHint, LineHintKind, MemoryEntry};
+use std::path::{Path, PathBuf};
+
+/// Maximum label length in characters.
+const MAX_LABEL_CHARS: usize = 80;
+
+/// Build line hints for one file.
+///
+/// `rel_path` is the file path relative to the project root. Memories are
+/// attached when their content or tags mention the file: explicit
+/// `path:LINE` / `path:START-END` references map to those lines, mentions of
+/// a symbol name map to that symbol's range, and anything else lands on
+/// line 1 as a file-level hint.
+pub fn build_line_hints(
+    rel_path: &Path,
+    symbols: &[Symbol],
+    memories: &[MemoryEntry],
+) -> Vec<LineHint> {
+    let mut hints: Vec<LineHint> = symbols
+        .iter()
+        .map(|symbol| LineHint {
+            line: symbol.start_line,
+            end_line: symbol.end_line,
+            kind: LineHintKind::Symbol,
+            label: truncate_label(&format!("{} {}", symbol_kind_label(symbol), symbol.name)),
+            ref_id: None,
+        })
+        .collect();
+
+    let path_str = rel_path.to_string_lossy();
+    for memory in memories.iter().filter(|m| !m.deleted) {
+        let texts: Vec<&str> = std::iter::once(memory.content.as_str())
+            .chain(memory.tags.iter().map(String::as_str))
+            .collect();
+
+        if !texts.iter().any(|text| text.contains(path_str.as_ref())) {
+            continue;
+        }
+
+        let mut ranges: Vec<(usize, usize)> = texts
+            .iter()
+            .flat_map(|text| line_refs(text, &path_str))
+            .collect();
+
+        if ranges.is_empty() {
+            ranges = symbols
+                .iter()
+                .filter(|symbol| texts.iter().any(|text| mentions_word(text, &symbol.name)))
+                .map(|symbol| (symbol.start_line, symbol.end_line))
+                .collect();
+        }
+
+        if ranges.is_empty() {
+            ranges.push((1, 1));
+        }
+
+        ranges.sort_unstable();
+        ranges.dedup();
+
+        let label = truncate_label(&format!(
+            "{}: {}",
+            memory.kind,
+            memory.content.lines().next().unwrap_or_default()
+        ));
+        for (line, end_line) in ranges {
+            hints.push(LineHint {
+                line,
+                end_line,
+                kind: LineHintKind::Memory,
+                label: label.clone(),
+                ref_id: Some(memory.id.clone()),
+            });
+        }
+    }
+
+    hints.sort_by(|a, b| {
+        a.line
+            .cmp(&b.line)
+            .then(a.end_line.cmp(&b.end_line))
+            .then(kind_rank(a.kind).cmp(&kind_rank(b.kind)))
+    });
+    hints
+}
+
+/// Pick the most recent experience whose touched files include `rel_path`.
+///
+/// Touched files may be recorded relative to the project root or absolute.
+pub fn last_experience_for<'a>(
+    project_root: &Path,
+    rel_path: &Path,
+    experiences: &'a [Experience],
+) -> Option<&'a Experience> {
+    let absolute: PathBuf = project_root.join(rel_path);
+    experiences
+        .iter()
+        .filter(|exp| {
+            exp.files_touched
+                .iter()
+                .any(|f| f == rel_path || *f == absolute)
+        })
+        .max_by_key(|exp| exp.timestamp)
+}
+
+/// Parse `path:LINE` and `path:START-END` references to `path` in `text`.
+fn line_refs(text: &str, path: &str) -> Vec<(usize, usize)> {
+    let mut refs = Vec::new();
+    for (idx, _) in text.match_indices(path) {
+        let rest = &text[idx + path.len()..];
+        let Some(rest) = rest.strip_prefix(':') else {
+            continue;
+        };
+        let (start, rest) = leading_number(rest);
+        let Some(start) = start.filter(|n| *n > 0) else {
+            continue;
+        };
+        let end = rest
+            .strip_prefix('-')
+            .and_then(|r| leading_number(r).0)
+            .filter(|end| *end >= start)
+            .unwrap_or(start);
+        refs.push((start, end));
+    }
+    refs
+}
+
+fn leading_number(text: &str) -> (Option<usize>, &str) {
+    let digits = text.bytes().take_while(u8::is_ascii_digit).count();
+    (text[..digits].parse().ok(), &text[digits..])
+}
+
+/// Check whether `word` appears in `text` as a whole identifier.
+fn mentions_word(text: &str, word: &str) -> bool {
+    if word.is_empty() {
+        return false;
+    }
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    text.match_indices(word).any(|(idx, _)| {
+        let before = text[..idx].chars().next_back();
+        let after = text[idx + word.len()..].chars().next();
+        !before.is_some_and(is_ident) && !after.is_some_and(is_ident)
+    })
+}
+
+fn symbol_kind_label(symbol: &Symbol) -> String {
+    serde_json::to_value(symbol.kind)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+fn kind_rank(kind: LineHintKind) -> u8 {
+    match kind {
+        LineHintKind::Symbol => 0,
+        LineHintKind::Memory => 1,
+    }
+}
+
+fn truncate_label(label: &str) -> String {
+    if label.chars().count() <= MAX_LABEL_CHARS {
+        return label.to_string();
+    }
+    let mut truncated: String = label.chars().take(MAX_LABEL_CHARS - 1).collect();
+    truncated.push('…');
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engram_indexer::scanner::SymbolKind;
+    use engram_ipc::MemoryVisibility;
+
+    fn symbol(name: &str, start_line: usize, end_line: usize) -> Symbol {
+        Symbol {
+            name: name.to_string(),
+            kind: SymbolKind::Function,
+            start_line,
+            end_line,
+            parent: None,
+            doc: None,
+        }
+    }
+
+    fn memory(id: &str, content: &str) -> MemoryEntry {
+        MemoryEntry {
+            id: id.to_string(),
+            kind: "decision".to_string(),
+            content: content.to_string(),
+            tags: vec![],
+            created_at: 1,
+            updated_at: 1,
+            session_id: None,
+            subagent_id: None,
+            deleted: false,
+            experience_id: None,
+            visibility: MemoryVisibility::Project,
+        }
+    }
+
+    #[test]
+    fn test_symbol_hints_sorted() {
+        let symbols = vec![symbol("b", 10, 12), symbol("a", 1, 5)];
+        let hints = build_line_hints(Path::new("src/lib.rs"), &symbols, &[]);
+
+        assert_eq!(hints.len(), 2);
+        assert_eq!(hints[0].line, 1);
+        assert_eq!(hints[0].label, "function a");
+        assert_eq!(hints[1].end_line, 12);
+    }
+
+    #[test]
+    fn test_memory_line_references() {
+        let memories = vec![
+            memory("m1", "Retry loop in src/lib.rs:20-25 must stay bounded"),
+            memory("m2", "Check src/lib.rs:7 before release"),
+            memory("m3", "Unrelated src/other.rs:3"),
+        ];
+        let hints = build_line_hints(Path::new("src/lib.rs"), &[], &memories);
+
+        assert_eq!(hints.len(), 2);
+        assert_eq!((hints[0].line, hints[0].end_line), (7, 7));
+        assert_eq!(hints[0].ref_id.as_deref(), Some("m2"));
+        assert_eq!((hints[1].line, hints[1].end_line), (20, 25));
+    }
+
+    #[test]
+    fn test_memory_symbol_and_file_level() {
+        let symbols = vec![symbol("parse_config", 4, 9)];
+        let memories = vec![
+            memory("m1", "src/lib.rs: parse_config ignores env overrides"),
+            memory("m2", "src/lib.rs is generated, do not edit"),
+        ];
+        let hints = build_line_hints(Path::new("src/lib.rs"), &symbols, &memories);
+
+        let memory_hints: Vec<_> = hints
+            .iter()
+            .filter(|h| h.kind == LineHintKind::Memory)
+            .collect();
+        assert_eq!(memory_hints.len(), 2);
+        assert_eq!(memory_hints[0].line, 1);
+        assert_eq!(memory_hints[0].ref_id.as_deref(), Some("m2"));
+        assert_eq!((memory_hints[1].line, memory_hints[1].end_line), (4, 9));
+    }
+
+    #[test]
+    fn test_last_experience_for_file() {
+        let root = Path::new("/project");
+        let mut old = Experience::new("agent", "old").with_files(vec!["src/lib.rs".into()]);
+        old.timestamp = 10;
+        let mut new =
+            Experience::new("agent", "new").with_files(vec![PathBuf::from("/project/src/lib.rs")]);
+        new.timestamp = 20;
+        let mut other = Experience::new("agent", "other").with_files(vec!["src/main.rs".into()]);
+        other.timestamp = 30;
+
+        let experiences = vec![old, new, other];
+        let last = last_experience_for(root, Path::new("src/lib.rs"), &experiences).unwrap();
+        assert_eq!(last.decision, "new");
+    }
+
+    #[test]
+    fn test_truncate_label() {
+        let label = truncate_label(&"x".repeat(200));
+        assert_eq!(label.chars().count(), MAX_LABEL_CHARS);
+        assert!(label.ends_with('…'));
+    }
+}
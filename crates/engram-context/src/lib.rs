@@ -3,16 +3,47 @@
 //! Provides intelligent context management for AI agents using
 //! hybrid retrieval with tree-based and semantic search.
 
+mod brief;
+mod calibration;
+mod consolidate;
+mod context_cache;
 mod error;
+mod hints;
 mod manager;
 mod memory;
+mod pending;
+mod relevance;
 mod render;
+mod render_cache;
 mod router;
+mod rules;
 mod scope;
+mod session;
+mod vector;
 
-pub use error::ContextError;
-pub use manager::{ContextManager, ScopeRequest};
-pub use memory::{MemoryStore, MemoryStoreError, MemorySyncStats};
-pub use render::ContextRenderer;
-pub use router::{HybridRouter, QueryIntent, RetrievalResult};
+pub use brief::{
+    summarize_brief, ProjectBrief, GENERATED_BRIEF_TAG, PROJECT_BRIEF_ID, PROJECT_BRIEF_KIND,
+};
+pub use calibration::{Calibration, CalibrationStore, SourceWeights, CALIBRATION_VERSION};
+pub use consolidate::{consolidate, ConsolidationPolicy, ConsolidationStats, SUMMARY_AGENT};
+pub use context_cache::{CachedContext, ContextCache, ContextKey, PrefetchStats};
+pub use error::{indexer_error_details, ContextError};
+pub use hints::{build_line_hints, last_experience_for};
+pub use manager::{
+    ContextManager, DependencyExpansion, ScopeRequest, SymbolRef, DEFAULT_SCOPE_TTL,
+};
+pub use memory::{
+    CompactionPolicy, MemoryCompactStats, MemoryImportStats, MemoryStore, MemoryStoreError,
+    MemorySyncStats,
+};
+pub use pending::PendingMemory;
+pub use relevance::{FileRelevance, Relevance, RelevanceStore};
+pub use render::{ContextRenderer, RenderedContext, Section};
+pub use render_cache::{RenderCache, RenderCacheStats, RenderKey};
+pub use router::{HybridRouter, MatchReason, QueryIntent, ResultSource, RetrievalResult};
+pub use rules::{Rule, RuleSet, DEFAULT_RULE_CATEGORY, RULES_DIR};
 pub use scope::{AnchorContext, ContextScope, Experience, FocusContext, HorizonContext, Outcome};
+pub use session::{summarize_session, Session, SessionStore};
+pub use vector::{
+    embed, Embedder, HashingEmbedder, VectorIndex, VectorStore, EMBEDDING_DIM, VECTOR_INDEX_VERSION,
+};
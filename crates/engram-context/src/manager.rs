@@ -1,17 +1,53 @@
 //! Context manager for AI agent sessions.
 //!
 //! Manages context scopes, including creation, expansion, and experience grafting.
+//! Grafted experiences are consolidated every so many grafts, merging
+//! near-duplicates and summarizing old ones.
+//!
+//! Scopes expire once unused for a TTL or when released, and can be
+//! persisted to the project data directory to survive a daemon restart.
+//! Rendered contexts are cached per prompt for as long as their scope lives
+//! and the project does not change. After a file changes, contexts focused
+//! on it and on its importers can be prefetched into that cache.
+//!
+//! With a memory store attached, the project brief heads every anchor.
+//! With a session store attached, scopes are recorded in the session they
+//! were created for and the summary of the last ended session joins the
+//! anchor.
 
+use crate::brief::{summarize_brief, ProjectBrief, PROJECT_BRIEF_ID};
+use crate::consolidate::{consolidate, ConsolidationPolicy, ConsolidationStats};
+use crate::context_cache::{CachedContext, ContextCache, ContextKey, PrefetchStats};
 use crate::error::{ContextError, Result};
+use crate::memory::MemoryStore;
+use crate::relevance::RelevanceStore;
+use crate::render::ContextRenderer;
+use crate::rules::{Rule, RuleSet};
 use crate::scope::{AnchorContext, ContextScope, Experience, FocusContext, HorizonContext};
+use crate::session::SessionStore;
+use engram_indexer::git;
 use engram_indexer::storage::Storage;
-use engram_indexer::tree::{NodeId, Tree};
+use engram_indexer::tree::{diff_trees, NodeId, NodeKind, SkeletonOptions, Tree, TreeDiff};
+use engram_indexer::CancelToken;
 use parking_lot::RwLock;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 use tracing::{debug, info, warn};
 
+/// File name of persisted scopes in the project data directory.
+const SCOPES_FILE: &str = "scopes.json";
+
+/// Most importers of a changed file whose contexts are prefetched.
+const MAX_PREFETCH_IMPORTERS: usize = 3;
+
+/// Most changed files listed in the anchor.
+const MAX_CHANGED_FILES: usize = 20;
+
+/// Default time an unused scope is kept.
+pub const DEFAULT_SCOPE_TTL: Duration = Duration::from_secs(30 * 60);
+
 /// Request to create a new context scope.
 #[derive(Debug, Clone)]
 pub struct ScopeRequest {
@@ -19,10 +55,26 @@ pub struct ScopeRequest {
     pub project_path: PathBuf,
     /// Initial focus paths (relative to project root)
     pub focus_paths: Vec<PathBuf>,
+    /// Initial focus symbols
+    pub focus_symbols: Vec<SymbolRef>,
     /// Constraints from parent agent
     pub constraints: Vec<String>,
     /// Whether to auto-load dependencies
     pub auto_load_deps: bool,
+    /// Whether to also auto-load the files importing the focus
+    pub load_importers: bool,
+    /// Whether the anchor lists recent experiences
+    pub experiences: bool,
+    /// Sub-agent with read-only memory, if any
+    pub read_only_agent: Option<String>,
+    /// Also focus on files changed since the merge-base with this branch
+    pub changed_since: Option<String>,
+    /// Agent session the scope is created for
+    pub session_id: Option<String>,
+    /// Whether the anchor summarizes the last ended session
+    pub previous_session: bool,
+    /// Checked between layers; scope creation stops once it is cancelled
+    pub cancel: CancelToken,
 }
 
 impl ScopeRequest {
@@ -31,22 +83,131 @@ impl ScopeRequest {
         Self {
             project_path: project_path.into(),
             focus_paths: vec![],
+            focus_symbols: vec![],
             constraints: vec![],
             auto_load_deps: true,
+            load_importers: false,
+            experiences: true,
+            read_only_agent: None,
+            changed_since: None,
+            session_id: None,
+            previous_session: true,
+            cancel: CancelToken::new(),
         }
     }
 
+    /// Record the scope in an agent session.
+    pub fn with_session(mut self, session_id: impl Into<String>) -> Self {
+        self.session_id = Some(session_id.into());
+        self
+    }
+
+    /// Leave the summary of the last session out of the anchor.
+    pub fn without_previous_session(mut self) -> Self {
+        self.previous_session = false;
+        self
+    }
+
+    /// Stop building the scope once `cancel` is cancelled.
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Make memory read-only for a sub-agent; its writes get staged.
+    pub fn with_read_only_memory(mut self, agent_id: impl Into<String>) -> Self {
+        self.read_only_agent = Some(agent_id.into());
+        self
+    }
+
     /// Add focus paths.
     pub fn with_focus(mut self, paths: Vec<PathBuf>) -> Self {
         self.focus_paths = paths;
         self
     }
 
+    /// Add focus references: file paths or symbols (`src/auth.rs::authenticate`).
+    pub fn with_focus_refs(mut self, refs: &[String]) -> Self {
+        for reference in refs {
+            match SymbolRef::parse(reference) {
+                Some(symbol) => self.focus_symbols.push(symbol),
+                None => self.focus_paths.push(PathBuf::from(reference)),
+            }
+        }
+        self
+    }
+
+    /// Focus on the files changed since the merge-base with `base`, for
+    /// review-style prompts.
+    pub fn with_changed_since(mut self, base: impl Into<String>) -> Self {
+        self.changed_since = Some(base.into());
+        self
+    }
+
     /// Add constraints.
     pub fn with_constraints(mut self, constraints: Vec<String>) -> Self {
         self.constraints = constraints;
         self
     }
+
+    /// Choose which neighbours of the focus are auto-loaded: its imports,
+    /// and with `importers` also the files importing it.
+    pub fn with_dependencies(mut self, imports: bool, importers: bool) -> Self {
+        self.auto_load_deps = imports;
+        self.load_importers = importers;
+        self
+    }
+
+    /// Leave recent experiences out of the anchor.
+    pub fn without_experiences(mut self) -> Self {
+        self.experiences = false;
+        self
+    }
+}
+
+/// Reference to a symbol in a file, written `path::name`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolRef {
+    /// File path relative to the project root
+    pub path: PathBuf,
+    /// Symbol name, optionally qualified (`Session::validate`)
+    pub name: String,
+}
+
+impl SymbolRef {
+    /// Parse `src/auth.rs::authenticate`; `None` for plain paths.
+    pub fn parse(reference: &str) -> Option<Self> {
+        let (path, name) = reference.split_once("::")?;
+        if path.is_empty() || name.is_empty() {
+            return None;
+        }
+        Some(Self {
+            path: PathBuf::from(path),
+            name: name.to_string(),
+        })
+    }
+}
+
+/// Direct dependency neighbours of a file, pulled into a scope's focus.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DependencyExpansion {
+    /// File whose neighbours were expanded
+    pub node: NodeId,
+    /// Files importing it, by path
+    pub importers: Vec<NodeId>,
+    /// Files it imports, by path
+    pub imports: Vec<NodeId>,
+}
+
+impl DependencyExpansion {
+    /// All expanded nodes, importers first.
+    pub fn nodes(&self) -> Vec<NodeId> {
+        self.importers
+            .iter()
+            .chain(&self.imports)
+            .copied()
+            .collect()
+    }
 }
 
 /// Central context manager for AI agents.
@@ -57,6 +218,26 @@ pub struct ContextManager {
     scopes: RwLock<HashMap<String, ContextScope>>,
     /// Cached trees (project_hash -> tree)
     trees: RwLock<HashMap<String, Arc<Tree>>>,
+    /// Horizon skeleton rendering options
+    skeleton_options: SkeletonOptions,
+    /// How long an unused scope is kept (zero keeps scopes until released)
+    scope_ttl: Duration,
+    /// Whether scopes are persisted to the project data directory
+    persist_scopes: bool,
+    /// Projects whose persisted scopes were loaded (project_hash)
+    restored: RwLock<HashSet<String>>,
+    /// Contexts served for repeated prompts
+    context_cache: ContextCache,
+    /// When grafted experiences are consolidated
+    consolidation: ConsolidationPolicy,
+    /// Grafts since the last consolidation (project_hash -> count)
+    grafts: parking_lot::Mutex<HashMap<String, usize>>,
+    /// Memory holding project briefs
+    memory: Option<Arc<MemoryStore>>,
+    /// Agent sessions the scopes belong to
+    sessions: Option<Arc<SessionStore>>,
+    /// Per-file relevance learned from experience outcomes
+    relevance: Option<Arc<RelevanceStore>>,
 }
 
 impl ContextManager {
@@ -66,43 +247,168 @@ impl ContextManager {
             storage,
             scopes: RwLock::new(HashMap::new()),
             trees: RwLock::new(HashMap::new()),
+            skeleton_options: SkeletonOptions::default(),
+            scope_ttl: DEFAULT_SCOPE_TTL,
+            persist_scopes: false,
+            restored: RwLock::new(HashSet::new()),
+            context_cache: ContextCache::new(),
+            consolidation: ConsolidationPolicy::default(),
+            grafts: parking_lot::Mutex::new(HashMap::new()),
+            memory: None,
+            sessions: None,
+            relevance: None,
         }
     }
 
+    /// Use the given options when rendering horizon skeletons.
+    pub fn with_skeleton_options(mut self, options: SkeletonOptions) -> Self {
+        self.skeleton_options = options;
+        self
+    }
+
+    /// Expire scopes unused for `ttl`; zero keeps them until released.
+    pub fn with_scope_ttl(mut self, ttl: Duration) -> Self {
+        self.scope_ttl = ttl;
+        self
+    }
+
+    /// Persist active scopes per project so they survive a restart.
+    pub fn with_scope_persistence(mut self, enabled: bool) -> Self {
+        self.persist_scopes = enabled;
+        self
+    }
+
+    /// Consolidate experiences according to `policy`.
+    pub fn with_consolidation(mut self, policy: ConsolidationPolicy) -> Self {
+        self.consolidation = policy;
+        self
+    }
+
+    /// Read and maintain project briefs in `memory`.
+    pub fn with_memory_store(mut self, memory: Arc<MemoryStore>) -> Self {
+        self.memory = Some(memory);
+        self
+    }
+
+    /// Record scopes in and summarize past sessions from `sessions`.
+    pub fn with_session_store(mut self, sessions: Arc<SessionStore>) -> Self {
+        self.sessions = Some(sessions);
+        self
+    }
+
+    /// Learn file relevance from experience outcomes in `relevance`, and
+    /// load the most relevant dependencies first.
+    pub fn with_relevance_store(mut self, relevance: Arc<RelevanceStore>) -> Self {
+        self.relevance = Some(relevance);
+        self
+    }
+
     /// Create a new context scope for an agent session.
+    ///
+    /// Returns [`ContextError::Cancelled`] if the request's token is
+    /// cancelled before the scope is stored.
     pub async fn create_scope(&self, req: ScopeRequest) -> Result<ContextScope> {
         info!(project = ?req.project_path, "Creating context scope");
+        let cancel = req.cancel.clone();
+        let check = || {
+            if cancel.is_cancelled() {
+                Err(ContextError::Cancelled)
+            } else {
+                Ok(())
+            }
+        };
 
         // Verify project exists
         if !req.project_path.exists() {
             return Err(ContextError::ProjectNotFound(req.project_path));
         }
 
+        self.restore_scopes(&req.project_path).await;
+        self.expire_scopes().await;
+
         // Load or get tree
+        check()?;
         let tree = self.get_tree(&req.project_path).await?;
 
         // Build scope layers
         let mut scope = ContextScope::new(req.project_path.clone());
+        if let Some(agent_id) = req.read_only_agent {
+            scope.agent_id = Some(agent_id);
+            scope.read_only_memory = true;
+        }
 
         // Layer 1: Anchor
         scope.anchor = self
             .build_anchor(&req.project_path, &req.constraints)
             .await?;
+        if !req.experiences {
+            scope.anchor.experiences.clear();
+        }
+        if req.previous_session {
+            scope.anchor.previous_session = self.previous_session(&req.project_path).await;
+        }
+        scope.anchor.changes = self.session_changes(&req.project_path, &tree).await;
+        scope.session_id = req.session_id;
 
         // Layer 2: Focus
-        scope.focus = self.build_focus(&tree, &req.focus_paths, req.auto_load_deps)?;
+        check()?;
+        let mut focus_paths = req.focus_paths;
+        if let Some(base) = &req.changed_since {
+            for path in changed_paths(&tree, &req.project_path, base) {
+                if !focus_paths.contains(&path) {
+                    focus_paths.push(path);
+                }
+            }
+        }
+        scope.focus = self.build_focus(
+            &tree,
+            &focus_paths,
+            &req.focus_symbols,
+            req.auto_load_deps,
+            req.load_importers,
+        )?;
+        self.rank_auto_loaded(&req.project_path, &tree, &mut scope.focus)
+            .await;
+
+        // Rules are scoped by path, so they are picked once the focus is known
+        let mut rule_paths = focus_paths;
+        rule_paths.extend(req.focus_symbols.iter().map(|symbol| symbol.path.clone()));
+        scope.anchor.rules = RuleSet::load(&req.project_path)
+            .await
+            .relevant(&rule_paths)
+            .into_iter()
+            .map(Rule::render)
+            .collect();
 
         // Layer 3: Horizon
+        check()?;
         scope.horizon = self.build_horizon(&tree, &scope.focus)?;
+        check()?;
 
         // Store scope
         let scope_id = scope.id.clone();
         self.scopes.write().insert(scope_id.clone(), scope.clone());
+        self.persist(&req.project_path).await;
+        self.record_in_session(&scope).await;
 
         debug!(scope_id = %scope_id, "Scope created");
         Ok(scope)
     }
 
+    /// Check whether an agent holds a read-only memory scope for a project.
+    ///
+    /// Only loaded scopes count; call [`Self::restore_scopes`] first after a
+    /// restart.
+    pub fn is_memory_read_only(&self, project_path: &Path, agent_id: &str) -> bool {
+        let now = chrono::Utc::now().timestamp();
+        self.scopes.read().values().any(|scope| {
+            scope.read_only_memory
+                && scope.project_path == project_path
+                && scope.agent_id.as_deref() == Some(agent_id)
+                && !self.is_expired(scope, now)
+        })
+    }
+
     /// Expand focus to include additional nodes.
     pub fn expand_focus(&self, scope_id: &str, node_ids: Vec<NodeId>) -> Result<()> {
         let mut scopes = self.scopes.write();
@@ -115,11 +421,116 @@ impl ContextManager {
                 scope.focus.expanded.push(node_id);
             }
         }
+        scope.last_used = chrono::Utc::now().timestamp();
 
         debug!(scope_id = %scope_id, expanded = scope.focus.expanded.len(), "Focus expanded");
         Ok(())
     }
 
+    /// Pull the direct importers and imports of a file into a scope's focus.
+    ///
+    /// `reference` is a node id from a rendered context, a file path, or a
+    /// symbol (`src/auth.rs::authenticate`), which expands its file.
+    /// Neighbours already in focus are left out.
+    pub async fn expand_dependents(
+        &self,
+        project_path: &Path,
+        scope_id: &str,
+        reference: &str,
+    ) -> Result<DependencyExpansion> {
+        self.restore_scopes(project_path).await;
+        let focus = match self.get_scope(scope_id) {
+            Some(scope) if scope.project_path == project_path => scope.focus.all_nodes(),
+            _ => return Err(ContextError::ScopeNotFound(scope_id.to_string())),
+        };
+
+        let tree = self.get_tree(project_path).await?;
+        let node = resolve_node(&tree, reference)
+            .ok_or_else(|| ContextError::NodeNotFound(reference.to_string()))?;
+
+        let mut seen: HashSet<NodeId> = focus.into_iter().collect();
+        seen.insert(node);
+        let mut neighbours = |ids: Vec<NodeId>| {
+            let mut ids: Vec<NodeId> = ids.into_iter().filter(|id| seen.insert(*id)).collect();
+            ids.sort_by(|a, b| {
+                let path = |id| tree.get(id).map(|node| &node.path);
+                path(*a).cmp(&path(*b))
+            });
+            ids
+        };
+        let importers = neighbours(tree.dependencies.imported_by(node).collect());
+        let imports = neighbours(tree.dependencies.imports(node).collect());
+
+        let expansion = DependencyExpansion {
+            node,
+            importers,
+            imports,
+        };
+        self.expand_focus(scope_id, expansion.nodes())?;
+        self.persist(project_path).await;
+        Ok(expansion)
+    }
+
+    /// Fork a child scope for a parallel sub-agent.
+    ///
+    /// The child shares the parent's anchor layer and gets its own focus
+    /// built from `focus_delta` (with their imports auto-loaded) and a
+    /// horizon around it. Focus must be disjoint between the children of a
+    /// parent: a file already in a live sibling's primary focus fails with
+    /// [`ContextError::FocusOverlap`]. Releasing the child grafts its
+    /// experience back into the parent.
+    pub async fn fork_scope(
+        &self,
+        project_path: &Path,
+        parent_id: &str,
+        focus_delta: &[PathBuf],
+    ) -> Result<ContextScope> {
+        self.restore_scopes(project_path).await;
+        let parent = match self.get_scope(parent_id) {
+            Some(scope) if scope.project_path == project_path => scope,
+            _ => return Err(ContextError::ScopeNotFound(parent_id.to_string())),
+        };
+
+        let tree = self.get_tree(project_path).await?;
+        let focus = self.build_focus(&tree, focus_delta, &[], true, false)?;
+        if focus.primary_nodes.is_empty() {
+            let path = focus_delta.first().cloned().unwrap_or_default();
+            return Err(ContextError::NodeNotFound(path.display().to_string()));
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        for sibling in self.scopes.read().values().filter(|scope| {
+            scope.parent_id.as_deref() == Some(parent_id) && !self.is_expired(scope, now)
+        }) {
+            if let Some(node) = focus
+                .primary_nodes
+                .iter()
+                .find(|id| sibling.focus.primary_nodes.contains(id))
+            {
+                return Err(ContextError::FocusOverlap {
+                    scope_id: sibling.id.clone(),
+                    path: tree.get(*node).map(|n| n.path.clone()).unwrap_or_default(),
+                });
+            }
+        }
+
+        let mut child = ContextScope::new(project_path.to_path_buf());
+        child.anchor = parent.anchor.clone();
+        child.agent_id = parent.agent_id.clone();
+        child.read_only_memory = parent.read_only_memory;
+        child.parent_id = Some(parent_id.to_string());
+        child.session_id = parent.session_id.clone();
+        child.horizon = self.build_horizon(&tree, &focus)?;
+        child.focus = focus;
+
+        self.scopes.write().insert(child.id.clone(), child.clone());
+        self.persist(project_path).await;
+        self.record_in_session(&child).await;
+
+        debug!(scope_id = %child.id, parent = %parent_id, "Scope forked");
+        Ok(child)
+    }
+
     /// Graft experience from a completed agent.
     pub async fn graft_experience(
         &self,
@@ -137,14 +548,49 @@ impl ContextManager {
             .append_experience(project_path, &experience)
             .await?;
 
+        if let (Some(relevance), Some(outcome)) = (&self.relevance, &experience.outcome) {
+            if let Err(e) = relevance
+                .record(project_path, &experience.files_touched, outcome)
+                .await
+            {
+                warn!(path = ?project_path, error = %e, "Failed to record outcome");
+            }
+        }
+
         // Update any active scopes for this project
-        let mut scopes = self.scopes.write();
-        for scope in scopes.values_mut() {
-            if scope.project_path == project_path {
-                scope.anchor.experiences.push(experience.clone());
-                // Keep only recent experiences
-                if scope.anchor.experiences.len() > 10 {
-                    scope.anchor.experiences.remove(0);
+        {
+            let mut scopes = self.scopes.write();
+            for scope in scopes.values_mut() {
+                if scope.project_path == project_path {
+                    scope.anchor.experiences.push(experience.clone());
+                    // Keep only recent experiences
+                    if scope.anchor.experiences.len() > 10 {
+                        scope.anchor.experiences.remove(0);
+                    }
+                }
+            }
+        }
+        self.context_cache.invalidate(project_path);
+
+        let every = self.consolidation.every;
+        let due = every > 0 && {
+            let mut grafts = self.grafts.lock();
+            let count = grafts
+                .entry(self.storage.project_hash(project_path))
+                .or_default();
+            *count += 1;
+            *count >= every
+        };
+        if due {
+            match self.consolidate_experiences(project_path).await {
+                Ok(stats) => debug!(
+                    path = ?project_path,
+                    merged = stats.merged,
+                    summarized = stats.summarized,
+                    "Consolidated experiences"
+                ),
+                Err(e) => {
+                    warn!(path = ?project_path, error = %e, "Experience consolidation failed")
                 }
             }
         }
@@ -152,14 +598,499 @@ impl ContextManager {
         Ok(())
     }
 
-    /// Get a scope by ID.
+    /// Merge near-duplicate experiences and summarize old ones.
+    ///
+    /// The experience log is rewritten in place; other entries in it are
+    /// kept. Active scopes of the project pick up the consolidated
+    /// experiences.
+    pub async fn consolidate_experiences(&self, project_path: &Path) -> Result<ConsolidationStats> {
+        let policy = self.consolidation;
+        let mut stats = ConsolidationStats::default();
+        let mut serialize_error = None;
+        self.storage
+            .rewrite_experiences(project_path, |lines| {
+                let mut kept = Vec::new();
+                let mut experiences = Vec::new();
+                for line in lines {
+                    match serde_json::from_str::<Experience>(line) {
+                        Ok(experience) => experiences.push(experience),
+                        Err(_) => kept.push(line.to_string()),
+                    }
+                }
+                let (experiences, consolidated) = consolidate(experiences, &policy);
+                for experience in &experiences {
+                    match serde_json::to_string(experience) {
+                        Ok(line) => kept.push(line),
+                        Err(e) => serialize_error = Some(e),
+                    }
+                }
+                stats = consolidated;
+                kept
+            })
+            .await?;
+        if let Some(e) = serialize_error {
+            return Err(ContextError::Storage(e.to_string()));
+        }
+        self.grafts
+            .lock()
+            .remove(&self.storage.project_hash(project_path));
+
+        let recent: Vec<Experience> = self
+            .storage
+            .load_experiences(project_path, 10)
+            .await
+            .unwrap_or_default();
+        {
+            let mut scopes = self.scopes.write();
+            for scope in scopes.values_mut() {
+                if scope.project_path == project_path {
+                    scope.anchor.experiences = recent.clone();
+                }
+            }
+        }
+        self.context_cache.invalidate(project_path);
+
+        info!(
+            path = ?project_path,
+            before = stats.experiences_before,
+            after = stats.experiences_after,
+            "Consolidated experiences"
+        );
+        Ok(stats)
+    }
+
+    /// Add experiences exported from another project log, keeping their
+    /// ids and timestamps.
+    ///
+    /// Experiences already in the log (by id, or by time, agent and
+    /// decision for records without one) are skipped. The log is rewritten
+    /// with experiences in time order, so imported ones sit among the local
+    /// ones rather than after them. Returns the number imported.
+    pub async fn import_experiences(
+        &self,
+        project_path: &Path,
+        imported: Vec<Experience>,
+    ) -> Result<usize> {
+        fn key(experience: &Experience) -> (String, i64, String, String) {
+            match experience.id.is_empty() {
+                false => (experience.id.clone(), 0, String::new(), String::new()),
+                true => (
+                    String::new(),
+                    experience.timestamp,
+                    experience.agent_id.clone(),
+                    experience.decision.clone(),
+                ),
+            }
+        }
+
+        let mut added = 0;
+        let mut serialize_error = None;
+        if self.storage.experience_size(project_path).await == 0 {
+            // Nothing to merge with, and the log may not exist yet
+            let mut seen = HashSet::new();
+            let mut experiences: Vec<_> = imported
+                .into_iter()
+                .filter(|experience| seen.insert(key(experience)))
+                .collect();
+            experiences.sort_by_key(|experience| experience.timestamp);
+            for experience in &experiences {
+                self.storage
+                    .append_experience(project_path, experience)
+                    .await?;
+            }
+            added = experiences.len();
+        } else {
+            self.storage
+                .rewrite_experiences(project_path, |lines| {
+                    let mut kept = Vec::new();
+                    let mut experiences = Vec::new();
+                    for line in lines {
+                        match serde_json::from_str::<Experience>(line) {
+                            Ok(experience) => experiences.push(experience),
+                            Err(_) => kept.push(line.to_string()),
+                        }
+                    }
+                    let mut seen: HashSet<_> = experiences.iter().map(key).collect();
+                    for experience in imported {
+                        if seen.insert(key(&experience)) {
+                            experiences.push(experience);
+                            added += 1;
+                        }
+                    }
+                    experiences.sort_by_key(|experience| experience.timestamp);
+                    for experience in &experiences {
+                        match serde_json::to_string(experience) {
+                            Ok(line) => kept.push(line),
+                            Err(e) => serialize_error = Some(e),
+                        }
+                    }
+                    kept
+                })
+                .await?;
+        }
+        if let Some(e) = serialize_error {
+            return Err(ContextError::Storage(e.to_string()));
+        }
+
+        let recent: Vec<Experience> = self
+            .storage
+            .load_experiences(project_path, 10)
+            .await
+            .unwrap_or_default();
+        {
+            let mut scopes = self.scopes.write();
+            for scope in scopes.values_mut() {
+                if scope.project_path == project_path {
+                    scope.anchor.experiences = recent.clone();
+                }
+            }
+        }
+        self.context_cache.invalidate(project_path);
+
+        info!(path = ?project_path, imported = added, "Imported experiences");
+        Ok(added)
+    }
+
+    /// Get a scope by ID, keeping it alive for another TTL.
     pub fn get_scope(&self, scope_id: &str) -> Option<ContextScope> {
-        self.scopes.read().get(scope_id).cloned()
+        let now = chrono::Utc::now().timestamp();
+        let mut scopes = self.scopes.write();
+        let scope = scopes
+            .get_mut(scope_id)
+            .filter(|scope| !self.is_expired(scope, now))?;
+        scope.last_used = now;
+        Some(scope.clone())
+    }
+
+    /// Release a scope of a project once its agent is done with it.
+    ///
+    /// A forked scope grafts back into its parent: experiences the parent
+    /// lacks join its anchor, and the child's focus joins its expanded
+    /// nodes. Returns the released scope, or `None` if it did not exist or
+    /// had already expired.
+    pub async fn release_scope(&self, project_path: &Path, scope_id: &str) -> Option<ContextScope> {
+        self.restore_scopes(project_path).await;
+        let scope = {
+            let mut scopes = self.scopes.write();
+            let scope = scopes
+                .remove(scope_id)
+                .filter(|scope| scope.project_path == project_path)?;
+            if let Some(parent) = scope.parent_id.as_ref().and_then(|id| scopes.get_mut(id)) {
+                graft_back(parent, &scope);
+            }
+            scope
+        };
+        self.persist(project_path).await;
+        self.end_session(project_path).await;
+        debug!(scope_id = %scope_id, "Scope released");
+        Some(scope)
+    }
+
+    /// Drop scopes unused for longer than the TTL; returns how many expired.
+    pub async fn expire_scopes(&self) -> usize {
+        let now = chrono::Utc::now().timestamp();
+        let expired: Vec<ContextScope> = {
+            let mut scopes = self.scopes.write();
+            let ids: Vec<String> = scopes
+                .values()
+                .filter(|scope| self.is_expired(scope, now))
+                .map(|scope| scope.id.clone())
+                .collect();
+            ids.iter().filter_map(|id| scopes.remove(id)).collect()
+        };
+
+        let projects: HashSet<&Path> = expired
+            .iter()
+            .map(|scope| scope.project_path.as_path())
+            .collect();
+        for project_path in projects {
+            self.persist(project_path).await;
+            self.end_session(project_path).await;
+        }
+        if !expired.is_empty() {
+            debug!(count = expired.len(), "Expired scopes");
+        }
+        expired.len()
+    }
+
+    /// Number of scopes that have not expired.
+    pub fn active_scope_count(&self) -> usize {
+        let now = chrono::Utc::now().timestamp();
+        self.scopes
+            .read()
+            .values()
+            .filter(|scope| !self.is_expired(scope, now))
+            .count()
+    }
+
+    /// Number of scopes that have not expired, by project root.
+    pub fn active_scopes_by_project(&self) -> HashMap<PathBuf, usize> {
+        let now = chrono::Utc::now().timestamp();
+        let mut counts = HashMap::new();
+        for scope in self
+            .scopes
+            .read()
+            .values()
+            .filter(|scope| !self.is_expired(scope, now))
+        {
+            *counts.entry(scope.project_path.clone()).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Load a project's persisted scopes, once per project.
+    ///
+    /// Does nothing unless persistence is enabled.
+    pub async fn restore_scopes(&self, project_path: &Path) {
+        if !self.persist_scopes {
+            return;
+        }
+        let hash = self.storage.project_hash(project_path);
+        if !self.restored.write().insert(hash.clone()) {
+            return;
+        }
+
+        let path = self.storage.project_dir(&hash).join(SCOPES_FILE);
+        let restored: Vec<ContextScope> = match tokio::fs::read(&path).await {
+            Ok(data) => match serde_json::from_slice(&data) {
+                Ok(scopes) => scopes,
+                Err(e) => {
+                    warn!(path = ?path, error = %e, "Ignoring unreadable persisted scopes");
+                    return;
+                }
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return,
+            Err(e) => {
+                warn!(path = ?path, error = %e, "Failed to read persisted scopes");
+                return;
+            }
+        };
+
+        let now = chrono::Utc::now().timestamp();
+        let mut scopes = self.scopes.write();
+        let mut count = 0;
+        for scope in restored {
+            if !self.is_expired(&scope, now) {
+                scopes.entry(scope.id.clone()).or_insert(scope);
+                count += 1;
+            }
+        }
+        debug!(project = ?project_path, count, "Restored scopes");
+    }
+
+    /// Whether a scope has gone unused for longer than the TTL.
+    fn is_expired(&self, scope: &ContextScope, now: i64) -> bool {
+        !self.scope_ttl.is_zero()
+            && now.saturating_sub(scope.last_used) >= self.scope_ttl.as_secs() as i64
+    }
+
+    /// Write a project's scopes to its data directory, if persistence is on.
+    async fn persist(&self, project_path: &Path) {
+        if !self.persist_scopes {
+            return;
+        }
+        let scopes: Vec<ContextScope> = self
+            .scopes
+            .read()
+            .values()
+            .filter(|scope| scope.project_path == project_path)
+            .cloned()
+            .collect();
+
+        let dir = self
+            .storage
+            .project_dir(&self.storage.project_hash(project_path));
+        let result = async {
+            tokio::fs::create_dir_all(&dir).await?;
+            let temp_path = dir.join(format!(".{}.tmp", SCOPES_FILE));
+            tokio::fs::write(&temp_path, serde_json::to_vec(&scopes)?).await?;
+            tokio::fs::rename(&temp_path, dir.join(SCOPES_FILE)).await
+        }
+        .await;
+        if let Err(e) = result {
+            warn!(project = ?project_path, error = %e, "Failed to persist scopes");
+        }
+    }
+
+    /// Drop the cached tree and contexts of a project so the next scope
+    /// reloads it.
+    pub fn invalidate_tree(&self, project_path: &Path) {
+        let project_hash = self.storage.project_hash(project_path);
+        self.trees.write().remove(&project_hash);
+        self.context_cache.invalidate(project_path);
+    }
+
+    /// Drop the cached contexts of a project, e.g. after a memory write.
+    pub fn invalidate_context(&self, project_path: &Path) {
+        self.context_cache.invalidate(project_path);
+    }
+
+    /// The brief of a project, if one was curated or generated.
+    pub async fn project_brief(&self, project_path: &Path) -> Option<ProjectBrief> {
+        let memory = self.memory.as_ref()?;
+        match memory.get(project_path, PROJECT_BRIEF_ID).await {
+            Ok(entry) => entry.as_ref().and_then(ProjectBrief::from_entry),
+            Err(e) => {
+                warn!(error = %e, "Failed to load project brief");
+                None
+            }
+        }
+    }
+
+    /// Update time for a new version of a project's brief: now, or just
+    /// after the current version so that the new one wins even within the
+    /// same second.
+    pub async fn next_brief_update(&self, project_path: &Path) -> i64 {
+        let now = chrono::Utc::now().timestamp();
+        let Some(memory) = &self.memory else {
+            return now;
+        };
+        match memory.get_latest(project_path, PROJECT_BRIEF_ID).await {
+            Ok(Some(current)) => now.max(current.updated_at + 1),
+            _ => now,
+        }
+    }
+
+    /// Regenerate a project's brief from its tree, unless a curated brief
+    /// exists. Returns whether the brief changed.
+    pub async fn refresh_brief(&self, project_path: &Path) -> Result<bool> {
+        let Some(memory) = &self.memory else {
+            return Ok(false);
+        };
+        let current = self.project_brief(project_path).await;
+        if current.as_ref().is_some_and(|brief| !brief.generated) {
+            return Ok(false);
+        }
+
+        let tree = self.get_tree(project_path).await?;
+        let Some(content) = summarize_brief(&tree) else {
+            return Ok(false);
+        };
+        if current.is_some_and(|brief| brief.content == content) {
+            return Ok(false);
+        }
+
+        let brief = ProjectBrief {
+            content,
+            generated: true,
+            updated_at: self.next_brief_update(project_path).await,
+        };
+        memory
+            .put(project_path, brief.to_entry())
+            .await
+            .map_err(|e| ContextError::Storage(e.to_string()))?;
+        self.invalidate_context(project_path);
+        Ok(true)
+    }
+
+    /// Look up a cached context whose scope is still alive.
+    ///
+    /// A hit keeps the scope alive like any other use; entries whose scope
+    /// expired or was released are dropped.
+    pub fn cached_context(&self, project_path: &Path, key: &ContextKey) -> Option<CachedContext> {
+        let cached = self.context_cache.get(project_path, key)?;
+        if self.get_scope(&cached.scope_id).is_none() {
+            self.context_cache.remove(project_path, key);
+            return None;
+        }
+        Some(cached)
     }
 
-    /// Remove a scope.
-    pub fn remove_scope(&self, scope_id: &str) -> Option<ContextScope> {
-        self.scopes.write().remove(scope_id)
+    /// Generation to pass to [`ContextManager::cache_context`], taken before
+    /// the context is built.
+    pub fn context_generation(&self, project_path: &Path) -> u64 {
+        self.context_cache.generation(project_path)
+    }
+
+    /// Cache a context built at `generation`; dropped if the project changed
+    /// since.
+    pub fn cache_context(
+        &self,
+        project_path: &Path,
+        key: ContextKey,
+        context: CachedContext,
+        generation: u64,
+    ) {
+        self.context_cache
+            .insert(project_path, key, context, generation);
+    }
+
+    /// Build and cache contexts focused on a changed file and on the files
+    /// importing it, so the next request focused on one of them is served
+    /// from the cache whatever its prompt. Returns how many were cached.
+    ///
+    /// Prefetched scopes that never served a request are dropped first.
+    /// Call this after the tree was updated for the change.
+    pub async fn prefetch(
+        &self,
+        project_path: &Path,
+        changed: &Path,
+        max_content_size: Option<usize>,
+    ) -> Result<usize> {
+        let unserved = self.context_cache.take_unserved(project_path);
+        if !unserved.is_empty() {
+            {
+                let mut scopes = self.scopes.write();
+                for scope_id in &unserved {
+                    scopes.remove(scope_id);
+                }
+            }
+            self.persist(project_path).await;
+        }
+
+        let generation = self.context_generation(project_path);
+        let tree = self.get_tree(project_path).await?;
+        let path = changed.strip_prefix(project_path).unwrap_or(changed);
+        let Some(node_id) = tree.find_node_by_path(&path.to_path_buf()) else {
+            // Deleted, or not indexed
+            return Ok(0);
+        };
+        let mut importers: Vec<PathBuf> = tree
+            .dependencies
+            .imported_by(node_id)
+            .filter_map(|id| tree.get(id))
+            .map(|node| node.path.clone())
+            .collect();
+        importers.sort();
+        importers.truncate(MAX_PREFETCH_IMPORTERS);
+
+        let renderer = match max_content_size {
+            Some(size) => ContextRenderer::with_max_size(size),
+            None => ContextRenderer::new(),
+        };
+        let mut cached = 0;
+        for path in std::iter::once(path.to_path_buf()).chain(importers) {
+            let focus = vec![path.display().to_string()];
+            let req = ScopeRequest::new(project_path).with_focus_refs(&focus);
+            let scope = self.create_scope(req).await?;
+            let context = CachedContext {
+                context: renderer.render(&scope, &tree),
+                nodes: scope
+                    .focus
+                    .primary_nodes
+                    .iter()
+                    .map(|id| id.to_string())
+                    .collect(),
+                scope_id: scope.id.clone(),
+            };
+            let key = ContextKey::new(None, &focus).with_max_content_size(max_content_size);
+            if !self
+                .context_cache
+                .insert_prefetched(project_path, key, context, generation)
+            {
+                // The project changed again; a later prefetch takes over
+                self.scopes.write().remove(&scope.id);
+                break;
+            }
+            cached += 1;
+        }
+        debug!(project = ?project_path, changed = ?changed, cached, "Prefetched contexts");
+        Ok(cached)
+    }
+
+    /// Prefetch counters, across projects.
+    pub fn prefetch_stats(&self) -> PrefetchStats {
+        self.context_cache.prefetch_stats()
     }
 
     /// Get or load tree for a project.
@@ -171,10 +1102,10 @@ impl ContextManager {
             return Ok(tree.clone());
         }
 
-        // Load from storage
+        // Load from storage; node content is read on demand
         let tree = self
             .storage
-            .load_tree(project_path, false)
+            .load_tree_lazy(&project_hash)
             .await
             .map_err(|e| ContextError::Storage(e.to_string()))?;
 
@@ -190,9 +1121,6 @@ impl ContextManager {
         project_path: &Path,
         constraints: &[String],
     ) -> Result<AnchorContext> {
-        // Load project rules (e.g., from .engram/rules.md or similar)
-        let rules = self.load_project_rules(project_path).await;
-
         // Load recent experiences
         let experiences = self
             .storage
@@ -200,38 +1128,148 @@ impl ContextManager {
             .await
             .unwrap_or_default();
 
+        let brief = self
+            .project_brief(project_path)
+            .await
+            .map(|brief| brief.content);
+
         Ok(AnchorContext {
-            rules,
+            brief,
+            rules: Vec::new(),
             experiences,
             constraints: constraints.to_vec(),
+            changes: Vec::new(),
+            previous_session: None,
         })
     }
 
-    /// Build focus context layer.
-    fn build_focus(
-        &self,
-        tree: &Tree,
-        focus_paths: &[PathBuf],
-        auto_load: bool,
-    ) -> Result<FocusContext> {
-        let mut primary_nodes = Vec::new();
-        let mut auto_loaded = Vec::new();
-
+    /// Add a scope to the session it was created for, if any.
+    async fn record_in_session(&self, scope: &ContextScope) {
+        let (Some(sessions), Some(session_id)) = (&self.sessions, &scope.session_id) else {
+            return;
+        };
+        if let Err(e) = sessions
+            .add_scope(&scope.project_path, session_id, &scope.id)
+            .await
+        {
+            warn!(error = %e, "Failed to record scope in session");
+        }
+    }
+
+    /// Summary of the last ended session, if a session store is attached.
+    async fn previous_session(&self, project_path: &Path) -> Option<String> {
+        let sessions = self.sessions.as_ref()?;
+        match sessions.previous(project_path).await {
+            Ok(session) => session.and_then(|session| session.summary),
+            Err(e) => {
+                warn!(error = %e, "Failed to load previous session");
+                None
+            }
+        }
+    }
+
+    /// Describe what changed since the last session ended.
+    ///
+    /// The first session of a project records its tree as the baseline and
+    /// reports no changes.
+    async fn session_changes(&self, project_path: &Path, tree: &Tree) -> Vec<String> {
+        let hash = self.storage.project_hash(project_path);
+        match self.storage.load_baseline(&hash).await {
+            Ok(Some(baseline)) => describe_changes(&diff_trees(&baseline, tree)),
+            Ok(None) => {
+                if let Err(e) = self.storage.save_baseline(tree, &hash).await {
+                    warn!(error = %e, "Failed to save session baseline");
+                }
+                Vec::new()
+            }
+            Err(e) => {
+                warn!(error = %e, "Failed to load session baseline");
+                Vec::new()
+            }
+        }
+    }
+
+    /// Record the current tree as the baseline of the next session, once
+    /// the last scope of a project is gone.
+    async fn end_session(&self, project_path: &Path) {
+        let now = chrono::Utc::now().timestamp();
+        let active = self
+            .scopes
+            .read()
+            .values()
+            .any(|scope| scope.project_path == project_path && !self.is_expired(scope, now));
+        if active {
+            return;
+        }
+        let Ok(tree) = self.get_tree(project_path).await else {
+            return;
+        };
+        let hash = self.storage.project_hash(project_path);
+        if let Err(e) = self.storage.save_baseline(&tree, &hash).await {
+            warn!(error = %e, "Failed to save session baseline");
+        }
+    }
+
+    /// Build focus context layer.
+    fn build_focus(
+        &self,
+        tree: &Tree,
+        focus_paths: &[PathBuf],
+        focus_symbols: &[SymbolRef],
+        auto_load: bool,
+        load_importers: bool,
+    ) -> Result<FocusContext> {
+        let mut primary_nodes = Vec::new();
+        // Files whose imports are auto-loaded
+        let mut dependents = Vec::new();
+
         // Find primary nodes from paths
         for path in focus_paths {
             if let Some(node_id) = tree.find_node_by_path(path) {
                 primary_nodes.push(node_id);
+                dependents.push(node_id);
+            } else {
+                warn!(path = ?path, "Focus path not found in tree");
+            }
+        }
 
-                // Auto-load dependencies if enabled
-                if auto_load {
-                    for dep_id in tree.dependencies.imports(node_id) {
-                        if !primary_nodes.contains(&dep_id) && !auto_loaded.contains(&dep_id) {
-                            auto_loaded.push(dep_id);
-                        }
-                    }
+        // Symbols bring in the imports of the file declaring them
+        for symbol in focus_symbols {
+            if let Some(node_id) = tree.find_symbol(&symbol.path, &symbol.name) {
+                if !primary_nodes.contains(&node_id) {
+                    primary_nodes.push(node_id);
                 }
+                dependents.extend(tree.get(node_id).and_then(|node| node.parent));
             } else {
-                warn!(path = ?path, "Focus path not found in tree");
+                warn!(path = ?symbol.path, symbol = %symbol.name, "Focus symbol not found in tree");
+            }
+        }
+
+        let mut auto_loaded = Vec::new();
+        let mut tests = Vec::new();
+        for &node_id in &dependents {
+            for test_id in tree.tests.tests_of(node_id) {
+                if test_id != node_id
+                    && !primary_nodes.contains(&test_id)
+                    && !tests.contains(&test_id)
+                {
+                    tests.push(test_id);
+                }
+            }
+        }
+        for node_id in dependents {
+            let imports = tree.dependencies.imports(node_id).filter(|_| auto_load);
+            let importers = tree
+                .dependencies
+                .imported_by(node_id)
+                .filter(|_| load_importers);
+            for dep_id in imports.chain(importers) {
+                if !primary_nodes.contains(&dep_id)
+                    && !auto_loaded.contains(&dep_id)
+                    && !tests.contains(&dep_id)
+                {
+                    auto_loaded.push(dep_id);
+                }
             }
         }
 
@@ -239,46 +1277,147 @@ impl ContextManager {
             primary_nodes,
             auto_loaded,
             expanded: vec![],
+            tests,
         })
     }
 
+    /// Order auto-loaded dependencies by learned relevance, most relevant
+    /// first, so they are the last to go when the budget runs out.
+    async fn rank_auto_loaded(&self, project_path: &Path, tree: &Tree, focus: &mut FocusContext) {
+        let Some(store) = &self.relevance else {
+            return;
+        };
+        let relevance = match store.load(project_path).await {
+            Ok(relevance) if !relevance.is_empty() => relevance,
+            Ok(_) => return,
+            Err(e) => {
+                warn!(path = ?project_path, error = %e, "Failed to load relevance");
+                return;
+            }
+        };
+        let weight = |node_id: &NodeId| {
+            tree.get(*node_id)
+                .map_or(1.0, |node| relevance.weight(&node.path))
+        };
+        focus
+            .auto_loaded
+            .sort_by(|a, b| weight(b).total_cmp(&weight(a)));
+    }
+
     /// Build horizon context layer.
     fn build_horizon(&self, tree: &Tree, focus: &FocusContext) -> Result<HorizonContext> {
         // Generate skeleton tree (directories + file names)
         let focus_nodes = focus.all_nodes();
-        let skeleton = tree.to_skeleton_string(&focus_nodes);
+        let skeleton = tree.to_skeleton_string_with(&focus_nodes, &self.skeleton_options);
 
         Ok(HorizonContext {
             skeleton,
             hot_nodes: vec![],
         })
     }
+}
 
-    /// Load project rules from configuration files.
-    async fn load_project_rules(&self, project_path: &Path) -> Vec<String> {
-        let rules_paths = [
-            project_path.join(".engram/rules.md"),
-            project_path.join(".engram/guidelines.md"),
-            project_path.join("CONTRIBUTING.md"),
-        ];
+/// Resolve a node id, file path or symbol reference to the file node it
+/// belongs to.
+fn resolve_node(tree: &Tree, reference: &str) -> Option<NodeId> {
+    let node = match reference.parse::<NodeId>() {
+        Ok(id) if tree.get(id).is_some() => id,
+        _ => match SymbolRef::parse(reference) {
+            Some(symbol) => tree.find_symbol(&symbol.path, &symbol.name)?,
+            None => tree.find_node_by_path(&PathBuf::from(reference))?,
+        },
+    };
+    match tree.get(node)?.kind {
+        NodeKind::Symbol { .. } => tree.get(node)?.parent,
+        _ => Some(node),
+    }
+}
 
-        let mut rules = Vec::new();
+/// Indexed files changed since the merge-base of `HEAD` and `base`.
+fn changed_paths(tree: &Tree, project_path: &Path, base: &str) -> Vec<PathBuf> {
+    match git::changed_since_merge_base(project_path, base) {
+        Ok(paths) => paths
+            .into_iter()
+            .filter(|path| tree.find_node_by_path(path).is_some())
+            .collect(),
+        Err(e) => {
+            warn!(base = %base, error = %e, "Could not list changed files");
+            vec![]
+        }
+    }
+}
 
-        for path in &rules_paths {
-            if path.exists() {
-                if let Ok(content) = tokio::fs::read_to_string(path).await {
-                    // Extract key rules (first few lines or bullet points)
-                    for line in content.lines().take(20) {
-                        let line = line.trim();
-                        if line.starts_with('-') || line.starts_with('*') {
-                            rules.push(line.to_string());
-                        }
-                    }
-                }
-            }
+/// One line per changed file, naming its added (`+`), removed (`-`) and
+/// modified (`~`) symbols.
+fn describe_changes(diff: &TreeDiff) -> Vec<String> {
+    let symbols = |path: &PathBuf| {
+        let mut names = Vec::new();
+        for (sign, changes) in [
+            ('+', &diff.added_symbols),
+            ('-', &diff.removed_symbols),
+            ('~', &diff.modified_symbols),
+        ] {
+            names.extend(
+                changes
+                    .iter()
+                    .filter(|change| &change.file == path)
+                    .map(|change| format!("{}{}", sign, change.name)),
+            );
+        }
+        if names.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", names.join(", "))
+        }
+    };
+
+    let mut lines: Vec<String> = diff
+        .added_files
+        .iter()
+        .map(|path| format!("Added {}", path.display()))
+        .chain(
+            diff.modified_files
+                .iter()
+                .map(|path| format!("Modified {}{}", path.display(), symbols(path))),
+        )
+        .chain(
+            diff.removed_files
+                .iter()
+                .map(|path| format!("Removed {}", path.display())),
+        )
+        .collect();
+    if lines.len() > MAX_CHANGED_FILES {
+        let more = lines.len() - MAX_CHANGED_FILES;
+        lines.truncate(MAX_CHANGED_FILES);
+        lines.push(format!("... and {} more files", more));
+    }
+    lines
+}
+
+/// Merge a finished child scope into its parent.
+fn graft_back(parent: &mut ContextScope, child: &ContextScope) {
+    let same = |a: &Experience, b: &Experience| {
+        (a.timestamp, &a.agent_id, &a.session_id, &a.decision)
+            == (b.timestamp, &b.agent_id, &b.session_id, &b.decision)
+    };
+    for experience in &child.anchor.experiences {
+        if !parent
+            .anchor
+            .experiences
+            .iter()
+            .any(|e| same(e, experience))
+        {
+            parent.anchor.experiences.push(experience.clone());
         }
+    }
+    let excess = parent.anchor.experiences.len().saturating_sub(10);
+    parent.anchor.experiences.drain(..excess);
 
-        rules
+    let focus = parent.focus.all_nodes();
+    for node in child.focus.all_nodes() {
+        if !focus.contains(&node) && !parent.focus.expanded.contains(&node) {
+            parent.focus.expanded.push(node);
+        }
     }
 }
 
@@ -300,6 +1439,22 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_create_scope_cancelled() {
+        let temp_dir = tempdir().unwrap();
+        let storage = Arc::new(Storage::new(temp_dir.path().join("data")));
+        let manager = ContextManager::new(storage);
+
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        let req = ScopeRequest::new(temp_dir.path()).with_cancel(cancel);
+        assert!(matches!(
+            manager.create_scope(req).await,
+            Err(ContextError::Cancelled)
+        ));
+        assert_eq!(manager.active_scope_count(), 0);
+    }
+
     #[test]
     fn test_expand_focus_not_found() {
         let temp_dir = tempdir().unwrap();
@@ -310,6 +1465,116 @@ mod tests {
         assert!(result.is_err());
     }
 
+    /// Add a scope last used `age_secs` ago, bypassing tree loading.
+    fn insert_scope(manager: &ContextManager, project_path: &Path, age_secs: i64) -> String {
+        let mut scope = ContextScope::new(project_path.to_path_buf());
+        scope.last_used -= age_secs;
+        let id = scope.id.clone();
+        manager.scopes.write().insert(id.clone(), scope);
+        id
+    }
+
+    #[tokio::test]
+    async fn test_scopes_expire_after_ttl() {
+        let temp_dir = tempdir().unwrap();
+        let storage = Arc::new(Storage::new(temp_dir.path().to_path_buf()));
+        let manager = ContextManager::new(storage).with_scope_ttl(Duration::from_secs(60));
+        let project = Path::new("/test/project");
+
+        let fresh = insert_scope(&manager, project, 10);
+        let stale = insert_scope(&manager, project, 120);
+        insert_scope(&manager, Path::new("/test/other"), 10);
+        assert_eq!(manager.active_scope_count(), 2);
+        assert_eq!(
+            manager.active_scopes_by_project(),
+            HashMap::from([
+                (project.to_path_buf(), 1),
+                (PathBuf::from("/test/other"), 1)
+            ])
+        );
+        assert!(manager.get_scope(&stale).is_none());
+
+        assert_eq!(manager.expire_scopes().await, 1);
+        assert!(manager.get_scope(&fresh).is_some());
+        assert_eq!(manager.active_scope_count(), 2);
+
+        // A zero TTL keeps scopes until released
+        let manager = ContextManager::new(Arc::new(Storage::new(temp_dir.path().into())))
+            .with_scope_ttl(Duration::ZERO);
+        let old = insert_scope(&manager, project, 365 * 24 * 3600);
+        assert_eq!(manager.expire_scopes().await, 0);
+        assert!(manager.release_scope(project, &old).await.is_some());
+        assert!(manager.release_scope(project, &old).await.is_none());
+        assert_eq!(manager.active_scope_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_release_scope_checks_project() {
+        let temp_dir = tempdir().unwrap();
+        let storage = Arc::new(Storage::new(temp_dir.path().to_path_buf()));
+        let manager = ContextManager::new(storage);
+
+        let id = insert_scope(&manager, Path::new("/test/a"), 0);
+        assert!(manager
+            .release_scope(Path::new("/test/b"), &id)
+            .await
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cached_context_follows_scope_and_project() {
+        let temp_dir = tempdir().unwrap();
+        let storage = Arc::new(Storage::new(temp_dir.path().to_path_buf()));
+        let manager = ContextManager::new(storage);
+        let project = Path::new("/test/project");
+        let key = ContextKey::new(Some("prompt"), &[]);
+        let cache = |scope_id: &str| {
+            let generation = manager.context_generation(project);
+            manager.cache_context(
+                project,
+                key.clone(),
+                CachedContext {
+                    context: "ctx".to_string(),
+                    nodes: vec![],
+                    scope_id: scope_id.to_string(),
+                },
+                generation,
+            );
+        };
+
+        let scope = insert_scope(&manager, project, 0);
+        cache(&scope);
+        assert!(manager.cached_context(project, &key).is_some());
+
+        // Changes to the project drop cached contexts
+        manager.invalidate_tree(project);
+        assert!(manager.cached_context(project, &key).is_none());
+
+        // So does releasing the scope they were built from
+        cache(&scope);
+        manager.release_scope(project, &scope).await.unwrap();
+        assert!(manager.cached_context(project, &key).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_persisted_scopes_survive_restart() {
+        let temp_dir = tempdir().unwrap();
+        let storage = Arc::new(Storage::new(temp_dir.path().to_path_buf()));
+        let project = Path::new("/test/project");
+        let manager = ContextManager::new(storage.clone()).with_scope_persistence(true);
+
+        let kept = insert_scope(&manager, project, 0);
+        let released = insert_scope(&manager, project, 0);
+        manager.release_scope(project, &released).await.unwrap();
+
+        let restarted = ContextManager::new(storage).with_scope_persistence(true);
+        assert_eq!(restarted.active_scope_count(), 0);
+        restarted.restore_scopes(project).await;
+        assert_eq!(restarted.active_scope_count(), 1);
+        assert!(restarted.get_scope(&kept).is_some());
+        assert!(restarted.get_scope(&released).is_none());
+    }
+
     #[test]
     fn test_scope_request_builder() {
         let req = ScopeRequest::new("/test/project")
@@ -321,6 +1586,215 @@ mod tests {
         assert_eq!(req.constraints.len(), 1);
     }
 
+    #[test]
+    fn test_scope_request_focus_refs() {
+        let req = ScopeRequest::new("/test/project").with_focus_refs(&[
+            "src/main.rs".to_string(),
+            "src/auth.rs::Session::validate".to_string(),
+            "::broken".to_string(),
+        ]);
+
+        assert_eq!(
+            req.focus_paths,
+            vec![PathBuf::from("src/main.rs"), PathBuf::from("::broken")]
+        );
+        assert_eq!(
+            req.focus_symbols,
+            vec![SymbolRef {
+                path: PathBuf::from("src/auth.rs"),
+                name: "Session::validate".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_build_focus_resolves_symbols() {
+        use engram_indexer::scanner::{Language, ScanResult, ScannedFile, Symbol, SymbolKind};
+        use engram_indexer::tree::{NodeKind, TreeBuilder};
+
+        let file = |path: &str, symbols: Vec<Symbol>, imports: Vec<&str>| ScannedFile {
+            path: PathBuf::from(path),
+            language: Some(Language::JavaScript),
+            size: 0,
+            hash: String::new(),
+            line_count: 20,
+            symbols,
+            imports: imports.into_iter().map(String::from).collect(),
+            truncated: false,
+            asset: None,
+        };
+        let scan = ScanResult {
+            root: PathBuf::from("/project"),
+            files: vec![
+                file(
+                    "src/auth.js",
+                    vec![Symbol {
+                        name: "authenticate".to_string(),
+                        kind: SymbolKind::Function,
+                        start_line: 3,
+                        end_line: 9,
+                        parent: None,
+                        doc: None,
+                    }],
+                    vec!["./db"],
+                ),
+                file("src/db.js", vec![], vec![]),
+            ],
+            languages: vec![],
+            frameworks: vec![],
+            duration_ms: 0,
+            skipped_count: 0,
+            excluded_dirs: vec![],
+        };
+        let tree = TreeBuilder::new().build(&scan);
+
+        let temp_dir = tempdir().unwrap();
+        let manager = ContextManager::new(Arc::new(Storage::new(temp_dir.path().to_path_buf())));
+        let symbols = [
+            SymbolRef::parse("src/auth.js::authenticate").unwrap(),
+            SymbolRef::parse("src/auth.js::missing").unwrap(),
+        ];
+        let focus = manager
+            .build_focus(&tree, &[], &symbols, true, false)
+            .unwrap();
+
+        assert_eq!(focus.primary_nodes.len(), 1);
+        let node = tree.get(focus.primary_nodes[0]).unwrap();
+        assert!(matches!(
+            node.kind,
+            NodeKind::Symbol {
+                start_line: 3,
+                end_line: 9,
+                ..
+            }
+        ));
+        let deps: Vec<_> = focus
+            .auto_loaded
+            .iter()
+            .map(|id| tree.get(*id).unwrap().path.clone())
+            .collect();
+        assert_eq!(deps, vec![PathBuf::from("src/db.js")]);
+    }
+
+    #[test]
+    fn test_build_focus_includes_tests() {
+        use engram_indexer::scanner::{Language, ScanResult, ScannedFile};
+        use engram_indexer::tree::TreeBuilder;
+
+        let file = |path: &str, imports: Vec<&str>| ScannedFile {
+            path: PathBuf::from(path),
+            language: Some(Language::JavaScript),
+            size: 0,
+            hash: String::new(),
+            line_count: 20,
+            symbols: vec![],
+            imports: imports.into_iter().map(String::from).collect(),
+            truncated: false,
+            asset: None,
+        };
+        let scan = ScanResult {
+            root: PathBuf::from("/project"),
+            files: vec![
+                file("src/auth.js", vec!["./db"]),
+                file("src/db.js", vec![]),
+                file("src/auth.test.js", vec!["./auth"]),
+            ],
+            languages: vec![],
+            frameworks: vec![],
+            duration_ms: 0,
+            skipped_count: 0,
+            excluded_dirs: vec![],
+        };
+        let tree = TreeBuilder::new().build(&scan);
+
+        let temp_dir = tempdir().unwrap();
+        let manager = ContextManager::new(Arc::new(Storage::new(temp_dir.path().to_path_buf())));
+        let focus = manager
+            .build_focus(&tree, &[PathBuf::from("src/auth.js")], &[], true, true)
+            .unwrap();
+
+        let paths = |ids: &[NodeId]| -> Vec<PathBuf> {
+            ids.iter()
+                .map(|id| tree.get(*id).unwrap().path.clone())
+                .collect()
+        };
+        assert_eq!(paths(&focus.auto_loaded), vec![PathBuf::from("src/db.js")]);
+        assert_eq!(paths(&focus.tests), vec![PathBuf::from("src/auth.test.js")]);
+    }
+
+    #[tokio::test]
+    async fn test_outcomes_rank_auto_loaded_dependencies() {
+        use crate::scope::Outcome;
+        use engram_indexer::scanner::{Language, ScanResult, ScannedFile};
+        use engram_indexer::tree::TreeBuilder;
+
+        let file = |path: &str, imports: Vec<&str>| ScannedFile {
+            path: PathBuf::from(path),
+            language: Some(Language::JavaScript),
+            size: 0,
+            hash: String::new(),
+            line_count: 20,
+            symbols: vec![],
+            imports: imports.into_iter().map(String::from).collect(),
+            truncated: false,
+            asset: None,
+        };
+        let tree = TreeBuilder::new().build(&ScanResult {
+            root: PathBuf::from("/project"),
+            files: vec![
+                file("src/auth.js", vec!["./db", "./cache"]),
+                file("src/db.js", vec![]),
+                file("src/cache.js", vec![]),
+            ],
+            languages: vec![],
+            frameworks: vec![],
+            duration_ms: 0,
+            skipped_count: 0,
+            excluded_dirs: vec![],
+        });
+
+        let temp_dir = tempdir().unwrap();
+        let storage = Arc::new(Storage::new(temp_dir.path().to_path_buf()));
+        let relevance = Arc::new(RelevanceStore::new(storage.clone()));
+        let manager = ContextManager::new(storage).with_relevance_store(relevance.clone());
+        let project_path = Path::new("/project");
+        let paths = |ids: &[NodeId]| -> Vec<PathBuf> {
+            ids.iter()
+                .map(|id| tree.get(*id).unwrap().path.clone())
+                .collect()
+        };
+
+        let mut focus = manager
+            .build_focus(&tree, &[PathBuf::from("src/auth.js")], &[], true, false)
+            .unwrap();
+        let unranked = paths(&focus.auto_loaded);
+        assert_eq!(unranked.len(), 2);
+
+        // Changes to the first dependency were reverted, to the second
+        // they worked
+        let outcomes = [
+            (&unranked[0], Outcome::Reverted),
+            (&unranked[1], Outcome::Success),
+        ];
+        for (path, outcome) in outcomes {
+            let experience = Experience::new("agent", "change")
+                .with_files(vec![project_path.join(path)])
+                .with_outcome(outcome);
+            manager
+                .graft_experience(project_path, experience)
+                .await
+                .unwrap();
+        }
+
+        manager
+            .rank_auto_loaded(project_path, &tree, &mut focus)
+            .await;
+        assert_eq!(
+            paths(&focus.auto_loaded),
+            vec![unranked[1].clone(), unranked[0].clone()]
+        );
+    }
+
     #[tokio::test]
     async fn test_create_scope_with_mixed_experience_log_formats() {
         use serde::Serialize;
@@ -371,4 +1845,485 @@ mod tests {
         assert_eq!(scope.anchor.experiences.len(), 1);
         assert_eq!(scope.anchor.experiences[0].agent_id, "legacy-agent");
     }
+
+    #[tokio::test]
+    async fn test_grafts_trigger_consolidation() {
+        let temp_dir = tempdir().unwrap();
+        let project_path = temp_dir.path().join("project");
+        std::fs::create_dir_all(&project_path).unwrap();
+
+        let storage = Arc::new(Storage::new(temp_dir.path().to_path_buf()));
+        storage
+            .append_experience(
+                &project_path,
+                &serde_json::json!({"id": "mem-1", "kind": "note"}),
+            )
+            .await
+            .unwrap();
+        let manager =
+            ContextManager::new(storage.clone()).with_consolidation(ConsolidationPolicy {
+                every: 3,
+                ..Default::default()
+            });
+
+        manager
+            .graft_experience(
+                &project_path,
+                Experience::new("agent", "Cached parsed trees"),
+            )
+            .await
+            .unwrap();
+        manager
+            .graft_experience(
+                &project_path,
+                Experience::new("agent", "Renamed the router"),
+            )
+            .await
+            .unwrap();
+        let experiences: Vec<Experience> =
+            storage.load_all_experiences(&project_path).await.unwrap();
+        assert_eq!(experiences.len(), 2);
+
+        // The third graft duplicates the first and consolidates the log
+        manager
+            .graft_experience(
+                &project_path,
+                Experience::new("agent", "cached parsed trees"),
+            )
+            .await
+            .unwrap();
+        let experiences: Vec<Experience> =
+            storage.load_all_experiences(&project_path).await.unwrap();
+        let decisions: Vec<_> = experiences.iter().map(|e| e.decision.as_str()).collect();
+        assert_eq!(decisions, vec!["Renamed the router", "cached parsed trees"]);
+
+        // Other entries in the log survive
+        let entries: Vec<serde_json::Value> =
+            storage.load_all_experiences(&project_path).await.unwrap();
+        assert!(entries.iter().any(|entry| entry["id"] == "mem-1"));
+    }
+
+    #[tokio::test]
+    async fn test_import_experiences() {
+        let temp_dir = tempdir().unwrap();
+        let project_path = temp_dir.path().join("project");
+        std::fs::create_dir_all(&project_path).unwrap();
+        let storage = Arc::new(Storage::new(temp_dir.path().to_path_buf()));
+        let manager = ContextManager::new(storage.clone());
+
+        let experience = |id: &str, decision: &str, timestamp| {
+            let mut experience = Experience::new("agent", decision).with_id(id);
+            experience.timestamp = timestamp;
+            experience
+        };
+
+        // Into a project without a log
+        let imported = manager
+            .import_experiences(
+                &project_path,
+                vec![
+                    experience("exp-2", "Split the parser", 200),
+                    experience("exp-1", "Cached parsed trees", 100),
+                ],
+            )
+            .await
+            .unwrap();
+        assert_eq!(imported, 2);
+
+        storage
+            .append_experience(
+                &project_path,
+                &serde_json::json!({"id": "mem-1", "kind": "note"}),
+            )
+            .await
+            .unwrap();
+        let imported = manager
+            .import_experiences(
+                &project_path,
+                vec![
+                    experience("exp-1", "Cached parsed trees", 100),
+                    experience("exp-3", "Renamed the router", 150),
+                ],
+            )
+            .await
+            .unwrap();
+        assert_eq!(imported, 1);
+
+        let experiences: Vec<Experience> =
+            storage.load_all_experiences(&project_path).await.unwrap();
+        let ids: Vec<_> = experiences.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, vec!["exp-1", "exp-3", "exp-2"]);
+        let entries: Vec<serde_json::Value> =
+            storage.load_all_experiences(&project_path).await.unwrap();
+        assert!(entries.iter().any(|entry| entry["id"] == "mem-1"));
+    }
+
+    #[tokio::test]
+    async fn test_create_scope_changed_since() {
+        use engram_indexer::scanner::{ScanResult, ScannedFile};
+        use engram_indexer::tree::TreeBuilder;
+        use std::process::Command;
+
+        let temp_dir = tempdir().unwrap();
+        let project_path = temp_dir.path().join("project");
+        std::fs::create_dir_all(project_path.join("src")).unwrap();
+        let git = |args: &[&str]| {
+            let output = Command::new("git")
+                .arg("-C")
+                .arg(&project_path)
+                .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+                .args(args)
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "git {:?} failed", args);
+        };
+        std::fs::write(project_path.join("src/a.rs"), "fn a() {}").unwrap();
+        std::fs::write(project_path.join("src/b.rs"), "fn b() {}").unwrap();
+        git(&["init", "--quiet", "--initial-branch=main"]);
+        git(&["add", "."]);
+        git(&["commit", "--quiet", "-m", "initial"]);
+        git(&["checkout", "--quiet", "-b", "feature"]);
+        std::fs::write(project_path.join("src/b.rs"), "fn b() { changed() }").unwrap();
+
+        let file = |path: &str| ScannedFile {
+            path: PathBuf::from(path),
+            language: None,
+            size: 0,
+            hash: String::new(),
+            line_count: 1,
+            symbols: vec![],
+            imports: vec![],
+            truncated: false,
+            asset: None,
+        };
+        let tree = TreeBuilder::new().build(&ScanResult {
+            root: project_path.clone(),
+            files: vec![file("src/a.rs"), file("src/b.rs")],
+            languages: vec![],
+            frameworks: vec![],
+            duration_ms: 0,
+            skipped_count: 0,
+            excluded_dirs: vec![],
+        });
+        assert_eq!(
+            tree.git.as_ref().and_then(|git| git.branch.as_deref()),
+            Some("feature")
+        );
+
+        let storage = Arc::new(Storage::new(temp_dir.path().join("data")));
+        let hash = storage.project_hash(&project_path);
+        storage.save_skeleton(&tree, &hash).await.unwrap();
+
+        let manager = ContextManager::new(storage);
+        let scope = manager
+            .create_scope(ScopeRequest::new(&project_path).with_changed_since("main"))
+            .await
+            .unwrap();
+        assert_eq!(
+            scope.focus.primary_nodes,
+            vec![tree.find_node_by_path(&PathBuf::from("src/b.rs")).unwrap()]
+        );
+
+        // An unknown base leaves the focus empty rather than failing
+        let scope = manager
+            .create_scope(ScopeRequest::new(&project_path).with_changed_since("nope"))
+            .await
+            .unwrap();
+        assert!(scope.focus.primary_nodes.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_fork_scope() {
+        use engram_indexer::scanner::{ScanResult, ScannedFile};
+        use engram_indexer::tree::TreeBuilder;
+
+        let temp_dir = tempdir().unwrap();
+        let project_path = temp_dir.path().join("project");
+        std::fs::create_dir_all(&project_path).unwrap();
+        let file = |path: &str| ScannedFile {
+            path: PathBuf::from(path),
+            language: None,
+            size: 0,
+            hash: String::new(),
+            line_count: 1,
+            symbols: vec![],
+            imports: vec![],
+            truncated: false,
+            asset: None,
+        };
+        let tree = TreeBuilder::new().build(&ScanResult {
+            root: project_path.clone(),
+            files: vec![file("src/a.rs"), file("src/b.rs"), file("src/c.rs")],
+            languages: vec![],
+            frameworks: vec![],
+            duration_ms: 0,
+            skipped_count: 0,
+            excluded_dirs: vec![],
+        });
+        let id = |path: &str| tree.find_node_by_path(&PathBuf::from(path)).unwrap();
+
+        let storage = Arc::new(Storage::new(temp_dir.path().join("data")));
+        let hash = storage.project_hash(&project_path);
+        storage.save_skeleton(&tree, &hash).await.unwrap();
+
+        let manager = ContextManager::new(storage);
+        let parent = manager
+            .create_scope(
+                ScopeRequest::new(&project_path)
+                    .with_focus(vec![PathBuf::from("src/a.rs")])
+                    .with_constraints(vec!["no new deps".to_string()]),
+            )
+            .await
+            .unwrap();
+
+        let child = manager
+            .fork_scope(&project_path, &parent.id, &[PathBuf::from("src/b.rs")])
+            .await
+            .unwrap();
+        assert_eq!(child.parent_id.as_deref(), Some(parent.id.as_str()));
+        assert_eq!(child.anchor.constraints, vec!["no new deps"]);
+        assert_eq!(child.focus.primary_nodes, vec![id("src/b.rs")]);
+
+        // Siblings must not share focus
+        assert!(matches!(
+            manager
+                .fork_scope(
+                    &project_path,
+                    &parent.id,
+                    &[PathBuf::from("src/b.rs"), PathBuf::from("src/c.rs")]
+                )
+                .await,
+            Err(ContextError::FocusOverlap { scope_id, .. }) if scope_id == child.id
+        ));
+        let sibling = manager
+            .fork_scope(&project_path, &parent.id, &[PathBuf::from("src/c.rs")])
+            .await
+            .unwrap();
+        assert!(matches!(
+            manager
+                .fork_scope(&project_path, &parent.id, &[PathBuf::from("missing.rs")])
+                .await,
+            Err(ContextError::NodeNotFound(_))
+        ));
+        assert!(matches!(
+            manager
+                .fork_scope(&project_path, "nope", &[PathBuf::from("src/c.rs")])
+                .await,
+            Err(ContextError::ScopeNotFound(_))
+        ));
+
+        // Completing a child grafts its experience and focus into the parent
+        {
+            let mut scopes = manager.scopes.write();
+            let child = scopes.get_mut(&child.id).unwrap();
+            child
+                .anchor
+                .experiences
+                .push(Experience::new("sub-agent", "split module b"));
+        }
+        manager
+            .release_scope(&project_path, &child.id)
+            .await
+            .unwrap();
+        let parent = manager.get_scope(&parent.id).unwrap();
+        assert_eq!(parent.anchor.experiences.len(), 1);
+        assert_eq!(parent.focus.expanded, vec![id("src/b.rs")]);
+
+        // The released child's focus is free again
+        manager
+            .fork_scope(&project_path, &parent.id, &[PathBuf::from("src/b.rs")])
+            .await
+            .unwrap();
+        assert!(manager.get_scope(&sibling.id).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_session_changes_in_anchor() {
+        use engram_indexer::scanner::{ScanResult, ScannedFile};
+        use engram_indexer::tree::TreeBuilder;
+
+        let temp_dir = tempdir().unwrap();
+        let project_path = temp_dir.path().join("project");
+        std::fs::create_dir_all(&project_path).unwrap();
+        let build = |files: &[(&str, &str)]| {
+            TreeBuilder::new().build(&ScanResult {
+                root: project_path.clone(),
+                files: files
+                    .iter()
+                    .map(|(path, hash)| ScannedFile {
+                        path: PathBuf::from(path),
+                        language: None,
+                        size: 0,
+                        hash: hash.to_string(),
+                        line_count: 1,
+                        symbols: vec![],
+                        imports: vec![],
+                        truncated: false,
+                        asset: None,
+                    })
+                    .collect(),
+                languages: vec![],
+                frameworks: vec![],
+                duration_ms: 0,
+                skipped_count: 0,
+                excluded_dirs: vec![],
+            })
+        };
+
+        let storage = Arc::new(Storage::new(temp_dir.path().join("data")));
+        let hash = storage.project_hash(&project_path);
+        storage
+            .save_skeleton(&build(&[("src/a.rs", "a1"), ("src/b.rs", "b1")]), &hash)
+            .await
+            .unwrap();
+
+        // The first session records the baseline
+        let manager = ContextManager::new(storage.clone());
+        let scope = manager
+            .create_scope(ScopeRequest::new(&project_path))
+            .await
+            .unwrap();
+        assert!(scope.anchor.changes.is_empty());
+        manager
+            .release_scope(&project_path, &scope.id)
+            .await
+            .unwrap();
+
+        storage
+            .save_skeleton(&build(&[("src/a.rs", "a2"), ("src/c.rs", "c1")]), &hash)
+            .await
+            .unwrap();
+        manager.invalidate_tree(&project_path);
+
+        let scope = manager
+            .create_scope(ScopeRequest::new(&project_path))
+            .await
+            .unwrap();
+        assert_eq!(
+            scope.anchor.changes,
+            vec!["Added src/c.rs", "Modified src/a.rs", "Removed src/b.rs"]
+        );
+        let tree = manager.get_tree(&project_path).await.unwrap();
+        assert!(crate::render::ContextRenderer::new()
+            .render(&scope, &tree)
+            .contains("## Changes Since Last Session\n- Added src/c.rs"));
+    }
+
+    #[tokio::test]
+    async fn test_sessions_record_scopes_and_summarize_the_previous_one() {
+        use crate::session::Session;
+
+        let temp_dir = tempdir().unwrap();
+        let project_path = temp_dir.path().join("project");
+        std::fs::create_dir_all(project_path.join("src")).unwrap();
+        std::fs::write(project_path.join("src/lib.rs"), "pub fn lib() {}\n").unwrap();
+        let storage = Arc::new(Storage::new(temp_dir.path().join("data")));
+        let hash = storage.project_hash(&project_path);
+        let tree = engram_indexer::tree::TreeBuilder::new().build(
+            &engram_indexer::scanner::Scanner::new()
+                .scan(&project_path)
+                .await
+                .unwrap(),
+        );
+        storage.save_skeleton(&tree, &hash).await.unwrap();
+
+        let sessions = Arc::new(SessionStore::new(storage.clone()));
+        let manager = ContextManager::new(storage).with_session_store(sessions.clone());
+        sessions
+            .start(&project_path, Session::new("s1"))
+            .await
+            .unwrap();
+        let scope = manager
+            .create_scope(ScopeRequest::new(&project_path).with_session("s1"))
+            .await
+            .unwrap();
+        assert_eq!(scope.session_id.as_deref(), Some("s1"));
+        assert!(scope.anchor.previous_session.is_none());
+        let child = manager
+            .fork_scope(&project_path, &scope.id, &[PathBuf::from("src/lib.rs")])
+            .await
+            .unwrap();
+        assert_eq!(child.session_id.as_deref(), Some("s1"));
+        let session = sessions.get(&project_path, "s1").await.unwrap().unwrap();
+        assert_eq!(session.scope_ids, vec![scope.id.clone(), child.id.clone()]);
+
+        sessions
+            .end(&project_path, "s1", Some("Refactored lib".to_string()))
+            .await
+            .unwrap();
+        let scope = manager
+            .create_scope(ScopeRequest::new(&project_path).with_session("s2"))
+            .await
+            .unwrap();
+        assert_eq!(
+            scope.anchor.previous_session.as_deref(),
+            Some("Refactored lib")
+        );
+        let tree = manager.get_tree(&project_path).await.unwrap();
+        assert!(crate::render::ContextRenderer::new()
+            .render(&scope, &tree)
+            .contains("## Previous Session\nRefactored lib\n"));
+        // The scope started session s2
+        assert!(sessions.active(&project_path).await.unwrap().is_some());
+
+        let scope = manager
+            .create_scope(ScopeRequest::new(&project_path).without_previous_session())
+            .await
+            .unwrap();
+        assert!(scope.anchor.previous_session.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_anchor_rules_follow_focus() {
+        use engram_indexer::scanner::{ScanResult, ScannedFile};
+        use engram_indexer::tree::TreeBuilder;
+
+        let temp_dir = tempdir().unwrap();
+        let project_path = temp_dir.path().join("project");
+        std::fs::create_dir_all(project_path.join(".engram/rules")).unwrap();
+        std::fs::write(
+            project_path.join(".engram/rules/api.md"),
+            "---\npaths: [\"src/api/**\"]\ncategory: security\n---\n- Check authorization first\n",
+        )
+        .unwrap();
+        std::fs::write(project_path.join(".engram/rules.md"), "- Write tests\n").unwrap();
+
+        let tree = TreeBuilder::new().build(&ScanResult {
+            root: project_path.clone(),
+            files: ["src/api/users.rs", "src/db.rs"]
+                .iter()
+                .map(|path| ScannedFile {
+                    path: PathBuf::from(path),
+                    language: None,
+                    size: 0,
+                    hash: path.to_string(),
+                    line_count: 1,
+                    symbols: vec![],
+                    imports: vec![],
+                    truncated: false,
+                    asset: None,
+                })
+                .collect(),
+            languages: vec![],
+            frameworks: vec![],
+            duration_ms: 0,
+            skipped_count: 0,
+            excluded_dirs: vec![],
+        });
+        let storage = Arc::new(Storage::new(temp_dir.path().join("data")));
+        let hash = storage.project_hash(&project_path);
+        storage.save_skeleton(&tree, &hash).await.unwrap();
+        let manager = ContextManager::new(storage);
+
+        let rules = |focus: &str| {
+            let request = ScopeRequest::new(&project_path).with_focus(vec![PathBuf::from(focus)]);
+            let manager = &manager;
+            async move { manager.create_scope(request).await.unwrap().anchor.rules }
+        };
+        assert_eq!(
+            rules("src/api/users.rs").await,
+            vec!["- [security] Check authorization first", "- Write tests"]
+        );
+        assert_eq!(rules("src/db.rs").await, vec!["- Write tests"]);
+    }
 }
@@ -1,11 +1,17 @@
 //! Memory store with per-project in-memory indexing and durable replay.
 
+use crate::vector::{dot, Embedder, HashingEmbedder};
 use chrono::Utc;
 use engram_indexer::storage::Storage;
-use engram_ipc::{MemoryEntry, MemoryPatch};
+use engram_ipc::{
+    DescribeError, ErrorDetails, ErrorKind, MemoryEntry, MemoryPatch, MemoryQuery, MemoryViewer,
+    MemoryVisibility,
+};
 use parking_lot::RwLock;
 use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
 use std::path::Path;
 use std::sync::Arc;
 use tokio::sync::Mutex;
@@ -30,6 +36,22 @@ pub enum MemoryStoreError {
 
 pub type Result<T> = std::result::Result<T, MemoryStoreError>;
 
+impl DescribeError for MemoryStoreError {
+    fn details(&self) -> ErrorDetails {
+        match self {
+            MemoryStoreError::Storage(_) | MemoryStoreError::Serialization(_) => {
+                ErrorDetails::new(ErrorKind::Storage)
+            }
+            MemoryStoreError::InvalidEntry(_) => {
+                ErrorDetails::new(ErrorKind::InvalidInput).with_field("entry")
+            }
+            MemoryStoreError::InvalidPatch(_) => {
+                ErrorDetails::new(ErrorKind::InvalidInput).with_field("patch")
+            }
+        }
+    }
+}
+
 /// Sync summary for one project index.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub struct MemorySyncStats {
@@ -39,6 +61,48 @@ pub struct MemorySyncStats {
     pub live_entries: usize,
     /// Deleted entries retained as tombstones.
     pub tombstones: usize,
+    /// Corrupt log lines moved to the `.corrupt` file during replay.
+    pub quarantined: usize,
+}
+
+/// When the durable log is compacted and what compaction drops.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompactionPolicy {
+    /// Compact automatically once the log reaches this many bytes (0 disables)
+    pub size_threshold: u64,
+    /// Tombstones older than this many seconds are dropped entirely
+    pub tombstone_retention_secs: i64,
+}
+
+impl Default for CompactionPolicy {
+    fn default() -> Self {
+        Self {
+            size_threshold: 4 * 1024 * 1024,
+            tombstone_retention_secs: 30 * 24 * 60 * 60,
+        }
+    }
+}
+
+/// Outcome of a log compaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MemoryCompactStats {
+    /// Log lines before compaction.
+    pub lines_before: usize,
+    /// Log lines after compaction.
+    pub lines_after: usize,
+    /// Superseded entry versions removed.
+    pub dropped_versions: usize,
+    /// Expired tombstones removed.
+    pub dropped_tombstones: usize,
+}
+
+/// Outcome of a memory import.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MemoryImportStats {
+    /// Entries written, new or newer than the local version.
+    pub imported: usize,
+    /// Entries whose local version is as new or newer.
+    pub skipped: usize,
 }
 
 /// In-memory + durable memory storage service.
@@ -47,21 +111,30 @@ pub struct MemorySyncStats {
 /// - per-project index keyed by `MemoryEntry.id`,
 /// - latest state chosen deterministically,
 /// - tombstones retained in index,
-/// - writes append durably before mutating memory.
+/// - writes append durably before mutating memory,
+/// - the log is compacted to latest versions once it grows too large.
 pub struct MemoryStore {
     storage: Arc<Storage>,
     projects: RwLock<HashMap<String, Arc<ProjectMemory>>>,
+    policy: CompactionPolicy,
+    embedder: Arc<dyn Embedder>,
 }
 
 struct ProjectMemory {
     gate: Mutex<()>,
     index: RwLock<ProjectIndex>,
+    /// Overrides the store-wide policy for this project
+    policy: RwLock<Option<CompactionPolicy>>,
 }
 
 #[derive(Default)]
 struct ProjectIndex {
     synced: bool,
     entries: HashMap<String, MemoryEntry>,
+    /// Log size right after the last compaction
+    compacted_size: u64,
+    /// Embedding per entry id, with the hash of the text it was computed from
+    embeddings: HashMap<String, (u64, Option<Vec<f32>>)>,
 }
 
 struct MemoryPatchData {
@@ -72,6 +145,7 @@ struct MemoryPatchData {
     subagent_id: NullableStringPatch,
     deleted: Option<bool>,
     updated_at: Option<i64>,
+    visibility: Option<MemoryVisibility>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -91,6 +165,7 @@ impl Default for MemoryPatchData {
             subagent_id: NullableStringPatch::Missing,
             deleted: None,
             updated_at: None,
+            visibility: None,
         }
     }
 }
@@ -104,6 +179,7 @@ impl MemoryPatchData {
             && self.subagent_id == NullableStringPatch::Missing
             && self.deleted.is_none()
             && self.updated_at.is_none()
+            && self.visibility.is_none()
     }
 }
 
@@ -112,6 +188,7 @@ impl Default for ProjectMemory {
         Self {
             gate: Mutex::new(()),
             index: RwLock::new(ProjectIndex::default()),
+            policy: RwLock::new(None),
         }
     }
 }
@@ -122,7 +199,54 @@ impl MemoryStore {
         Self {
             storage,
             projects: RwLock::new(HashMap::new()),
+            policy: CompactionPolicy::default(),
+            embedder: Arc::new(HashingEmbedder),
+        }
+    }
+
+    /// Embed entries for [`MemoryStore::search`] with `embedder` instead of
+    /// feature hashing.
+    pub fn with_embedder(mut self, embedder: Arc<dyn Embedder>) -> Self {
+        self.embedder = embedder;
+        self
+    }
+
+    /// Use the given compaction policy instead of the default.
+    pub fn with_compaction(mut self, policy: CompactionPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Use `policy` for one project instead of the store-wide policy.
+    pub fn set_project_policy(&self, project_path: &Path, policy: CompactionPolicy) {
+        *self.project_memory(project_path).policy.write() = Some(policy);
+    }
+
+    /// Drop a project's in-memory index once in-flight writes have finished.
+    ///
+    /// Returns whether the project had been loaded. Entries are durable, so
+    /// the next access simply replays the log again.
+    pub async fn unload(&self, project_path: &Path) -> bool {
+        let hash = self.storage.project_hash(project_path);
+        let Some(project) = self.projects.read().get(&hash).cloned() else {
+            return false;
+        };
+
+        let _guard = project.gate.lock().await;
+        self.projects.write().remove(&hash).is_some()
+    }
+
+    /// Wait for in-flight writes of every loaded project to finish, before
+    /// shutdown.
+    ///
+    /// Writes are durable once they return, so afterwards the logs hold
+    /// every acknowledged entry. Returns the number of projects flushed.
+    pub async fn flush(&self) -> usize {
+        let projects: Vec<_> = self.projects.read().values().cloned().collect();
+        for project in &projects {
+            let _guard = project.gate.lock().await;
         }
+        projects.len()
     }
 
     /// Replay durable storage and rebuild one project's in-memory index.
@@ -130,8 +254,11 @@ impl MemoryStore {
         let project = self.project_memory(project_path);
         let _guard = project.gate.lock().await;
 
-        let entries = self.rebuild_from_storage(project_path).await?;
-        let stats = stats_for_entries(&entries);
+        let (entries, quarantined) = self.rebuild_from_storage(project_path).await?;
+        let stats = MemorySyncStats {
+            quarantined,
+            ..stats_for_entries(&entries)
+        };
 
         let mut index = project.index.write();
         index.entries = entries;
@@ -159,19 +286,12 @@ impl MemoryStore {
         let _guard = project.gate.lock().await;
         self.ensure_synced_locked(project_path, &project).await?;
 
-        self.storage
-            .append_experience_durable(project_path, &entry)
-            .await
-            .map_err(|e| MemoryStoreError::Storage(e.to_string()))?;
-
-        let mut index = project.index.write();
-        apply_latest(&mut index.entries, entry.clone());
+        self.append_locked(project_path, &project, entry.clone())
+            .await?;
 
-        Ok(index
-            .entries
-            .get(&entry.id)
-            .cloned()
-            .expect("entry must exist after apply"))
+        // An already expired tombstone may be compacted away right away
+        let index = project.index.read();
+        Ok(index.entries.get(&entry.id).cloned().unwrap_or(entry))
     }
 
     /// Get latest entry by ID including tombstones.
@@ -193,8 +313,17 @@ impl MemoryStore {
             .filter(|entry| !entry.deleted))
     }
 
-    /// List latest non-deleted entries ordered by recency, oldest to newest.
-    pub async fn list(&self, project_path: &Path, limit: usize) -> Result<Vec<MemoryEntry>> {
+    /// List the latest non-deleted entries matching `query`, ordered by
+    /// recency, oldest to newest.
+    ///
+    /// With a viewer in the query, entries outside its namespace are left
+    /// out.
+    pub async fn list(
+        &self,
+        project_path: &Path,
+        query: &MemoryQuery,
+        limit: usize,
+    ) -> Result<Vec<MemoryEntry>> {
         if limit == 0 {
             return Ok(Vec::new());
         }
@@ -202,11 +331,12 @@ impl MemoryStore {
         let project = self.project_memory(project_path);
         self.ensure_synced(project_path, &project).await?;
 
+        let text = query.text.as_deref().map(str::to_lowercase);
         let index = project.index.read();
         let mut entries: Vec<MemoryEntry> = index
             .entries
             .values()
-            .filter(|entry| !entry.deleted)
+            .filter(|entry| !entry.deleted && matches_query(entry, text.as_deref(), query))
             .cloned()
             .collect();
         entries.sort_by(compare_entries);
@@ -218,6 +348,70 @@ impl MemoryStore {
         Ok(entries)
     }
 
+    /// Find the live entries most similar to `query`, best first.
+    ///
+    /// Scores are cosine similarities; entries sharing nothing with the
+    /// query, or hidden from `viewer`, are left out. Embeddings are cached
+    /// until an entry changes.
+    pub async fn search(
+        &self,
+        project_path: &Path,
+        query: &str,
+        limit: usize,
+        viewer: Option<&MemoryViewer>,
+    ) -> Result<Vec<(MemoryEntry, f32)>> {
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+        let Some(query) = self.embedder.embed(query) else {
+            return Ok(Vec::new());
+        };
+
+        let project = self.project_memory(project_path);
+        self.ensure_synced(project_path, &project).await?;
+
+        // Embed outside the lock; model-backed embedders may be slow
+        let stale: Vec<(String, u64, String)> = {
+            let index = project.index.read();
+            index
+                .entries
+                .values()
+                .filter(|entry| !entry.deleted)
+                .filter_map(|entry| {
+                    let text = embedding_text(entry);
+                    let hash = text_hash(&text);
+                    let cached = index.embeddings.get(&entry.id);
+                    (cached.map(|(h, _)| *h) != Some(hash)).then(|| (entry.id.clone(), hash, text))
+                })
+                .collect()
+        };
+        let embedded: Vec<_> = stale
+            .into_iter()
+            .map(|(id, hash, text)| (id, (hash, self.embedder.embed(&text))))
+            .collect();
+
+        let mut index = project.index.write();
+        let ProjectIndex {
+            entries,
+            embeddings,
+            ..
+        } = &mut *index;
+        embeddings.extend(embedded);
+        embeddings.retain(|id, _| entries.get(id).is_some_and(|entry| !entry.deleted));
+
+        let mut scored: Vec<(MemoryEntry, f32)> = embeddings
+            .iter()
+            .filter(|(id, _)| viewer.is_none_or(|viewer| is_visible(&entries[*id], viewer)))
+            .filter_map(|(id, (_, vector))| {
+                let score = dot(&query, vector.as_deref()?);
+                (score > 0.0).then(|| (entries[id].clone(), score))
+            })
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.id.cmp(&b.0.id)));
+        scored.truncate(limit);
+        Ok(scored)
+    }
+
     /// Patch an existing entry version using an IPC-compatible payload.
     ///
     /// The payload is normalized via serde, so any IPC patch struct that
@@ -278,6 +472,9 @@ impl MemoryStore {
         if let Some(deleted) = patch.deleted {
             updated.deleted = deleted;
         }
+        if let Some(visibility) = patch.visibility {
+            updated.visibility = visibility;
+        }
 
         let now = current_timestamp();
         let patched_updated_at = patch.updated_at.unwrap_or(now);
@@ -286,13 +483,9 @@ impl MemoryStore {
         updated.id = id.to_string();
         validate_entry(&updated)?;
 
-        self.storage
-            .append_experience_durable(project_path, &updated)
-            .await
-            .map_err(|e| MemoryStoreError::Storage(e.to_string()))?;
+        self.append_locked(project_path, &project, updated).await?;
 
-        let mut index = project.index.write();
-        apply_latest(&mut index.entries, updated.clone());
+        let index = project.index.read();
         Ok(index.entries.get(id).cloned())
     }
 
@@ -328,14 +521,181 @@ impl MemoryStore {
         tombstone.updated_at =
             std::cmp::max(candidate_updated_at, current.updated_at.saturating_add(1));
 
+        self.append_locked(project_path, &project, tombstone)
+            .await?;
+
+        let index = project.index.read();
+        Ok(index.entries.get(id).cloned())
+    }
+
+    /// Latest version of every entry, tombstones included, oldest first.
+    pub async fn export(&self, project_path: &Path) -> Result<Vec<MemoryEntry>> {
+        let project = self.project_memory(project_path);
+        self.ensure_synced(project_path, &project).await?;
+
+        let mut entries: Vec<MemoryEntry> =
+            project.index.read().entries.values().cloned().collect();
+        entries.sort_by(compare_entries);
+        Ok(entries)
+    }
+
+    /// Append entries exported from another store, keeping their ids and
+    /// timestamps.
+    ///
+    /// Conflicts resolve the way replay does: an entry is written only if
+    /// it is newer than the local version with the same id. Nothing is
+    /// written unless every entry is valid.
+    pub async fn import(
+        &self,
+        project_path: &Path,
+        entries: Vec<MemoryEntry>,
+    ) -> Result<MemoryImportStats> {
+        for entry in &entries {
+            validate_entry(entry)?;
+        }
+
+        let project = self.project_memory(project_path);
+        let _guard = project.gate.lock().await;
+        self.ensure_synced_locked(project_path, &project).await?;
+
+        let mut stats = MemoryImportStats::default();
+        for entry in entries {
+            let newer = project
+                .index
+                .read()
+                .entries
+                .get(&entry.id)
+                .is_none_or(|current| compare_entries(current, &entry).is_lt());
+            if newer {
+                self.append_locked(project_path, &project, entry).await?;
+                stats.imported += 1;
+            } else {
+                stats.skipped += 1;
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Rewrite the log keeping only the latest version of each entry.
+    ///
+    /// Tombstones older than the policy's retention window are dropped
+    /// entirely. Lines that are not memory entries are kept as-is.
+    pub async fn compact(&self, project_path: &Path) -> Result<MemoryCompactStats> {
+        let project = self.project_memory(project_path);
+        let _guard = project.gate.lock().await;
+        self.ensure_synced_locked(project_path, &project).await?;
+        self.compact_locked(project_path, &project).await
+    }
+
+    async fn compact_locked(
+        &self,
+        project_path: &Path,
+        project: &ProjectMemory,
+    ) -> Result<MemoryCompactStats> {
+        let policy = self.policy_for(project);
+        let cutoff = current_timestamp().saturating_sub(policy.tombstone_retention_secs);
+        let mut stats = MemoryCompactStats::default();
+
+        let (lines_before, lines_after) =
+            self.storage
+                .rewrite_experiences(project_path, |lines| {
+                    let parsed: Vec<Option<MemoryEntry>> = lines
+                        .iter()
+                        .map(|line| serde_json::from_str(line).ok())
+                        .collect();
+
+                    // Line of the winning version per id
+                    let mut latest: HashMap<&str, usize> = HashMap::new();
+                    for (line, entry) in parsed.iter().enumerate() {
+                        let Some(entry) = entry else { continue };
+                        match latest.get(entry.id.as_str()) {
+                            Some(&current)
+                                if parsed[current].as_ref().is_some_and(|current| {
+                                    compare_entries(current, entry).is_ge()
+                                }) => {}
+                            _ => {
+                                latest.insert(&entry.id, line);
+                            }
+                        }
+                    }
+
+                    lines
+                        .iter()
+                        .zip(&parsed)
+                        .enumerate()
+                        .filter_map(|(line, (raw, entry))| match entry {
+                            None => Some(raw.to_string()),
+                            Some(entry) if latest[entry.id.as_str()] != line => {
+                                stats.dropped_versions += 1;
+                                None
+                            }
+                            Some(entry) if entry.deleted && entry.updated_at < cutoff => {
+                                stats.dropped_tombstones += 1;
+                                None
+                            }
+                            Some(_) => Some(raw.to_string()),
+                        })
+                        .collect()
+                })
+                .await
+                .map_err(|e| MemoryStoreError::Storage(e.to_string()))?;
+        stats.lines_before = lines_before;
+        stats.lines_after = lines_after;
+
+        let compacted_size = self.storage.experience_size(project_path).await;
+        let mut index = project.index.write();
+        index
+            .entries
+            .retain(|_, entry| !(entry.deleted && entry.updated_at < cutoff));
+        index.compacted_size = compacted_size;
+
+        Ok(stats)
+    }
+
+    /// Durably append an entry version, apply it to the index and compact
+    /// the log if it outgrew the policy threshold.
+    async fn append_locked(
+        &self,
+        project_path: &Path,
+        project: &ProjectMemory,
+        entry: MemoryEntry,
+    ) -> Result<()> {
         self.storage
-            .append_experience_durable(project_path, &tombstone)
+            .append_experience_durable(project_path, &entry)
             .await
             .map_err(|e| MemoryStoreError::Storage(e.to_string()))?;
 
-        let mut index = project.index.write();
-        apply_latest(&mut index.entries, tombstone.clone());
-        Ok(index.entries.get(id).cloned())
+        let compacted_size = {
+            let mut index = project.index.write();
+            apply_latest(&mut index.entries, entry);
+            index.compacted_size
+        };
+
+        // Logs dominated by live entries do not shrink much; wait for them
+        // to double before compacting again.
+        let threshold = self.policy_for(project).size_threshold;
+        if threshold > 0 {
+            let size = self.storage.experience_size(project_path).await;
+            if size >= threshold.max(compacted_size.saturating_mul(2)) {
+                match self.compact_locked(project_path, project).await {
+                    Ok(stats) => tracing::debug!(
+                        path = ?project_path,
+                        before = stats.lines_before,
+                        after = stats.lines_after,
+                        "Compacted memory log"
+                    ),
+                    Err(e) => {
+                        tracing::warn!(path = ?project_path, error = %e, "Memory log compaction failed")
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn policy_for(&self, project: &ProjectMemory) -> CompactionPolicy {
+        project.policy.read().unwrap_or(self.policy)
     }
 
     fn project_memory(&self, project_path: &Path) -> Arc<ProjectMemory> {
@@ -370,7 +730,7 @@ impl MemoryStore {
             return Ok(());
         }
 
-        let entries = self.rebuild_from_storage(project_path).await?;
+        let (entries, _) = self.rebuild_from_storage(project_path).await?;
         let mut index = project.index.write();
         index.entries = entries;
         index.synced = true;
@@ -378,25 +738,43 @@ impl MemoryStore {
         Ok(())
     }
 
+    /// Latest entry per id from the log, and the number of corrupt lines
+    /// moved aside while replaying it.
     async fn rebuild_from_storage(
         &self,
         project_path: &Path,
-    ) -> Result<HashMap<String, MemoryEntry>> {
-        let all_entries: Vec<MemoryEntry> =
-            self.storage
-                .load_all_experiences(project_path)
-                .await
-                .map_err(|e| MemoryStoreError::Storage(e.to_string()))?;
+    ) -> Result<(HashMap<String, MemoryEntry>, usize)> {
+        let replay = self
+            .storage
+            .replay_experiences::<MemoryEntry>(project_path)
+            .await
+            .map_err(|e| MemoryStoreError::Storage(e.to_string()))?;
 
         let mut latest_by_id = HashMap::new();
-        for entry in all_entries {
+        for entry in replay.entries {
             apply_latest(&mut latest_by_id, entry);
         }
 
-        Ok(latest_by_id)
+        Ok((latest_by_id, replay.quarantined))
     }
 }
 
+/// Text an entry is embedded from.
+fn embedding_text(entry: &MemoryEntry) -> String {
+    let mut text = format!("{} {}", entry.kind, entry.content);
+    for tag in &entry.tags {
+        text.push(' ');
+        text.push_str(tag);
+    }
+    text
+}
+
+fn text_hash(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
+
 fn current_timestamp() -> i64 {
     Utc::now().timestamp()
 }
@@ -427,6 +805,19 @@ fn validate_entry(entry: &MemoryEntry) -> Result<()> {
             "memory updated_at must be positive".to_string(),
         ));
     }
+    match entry.visibility {
+        MemoryVisibility::Session if entry.session_id.is_none() => {
+            return Err(MemoryStoreError::InvalidEntry(
+                "session visibility requires a session_id".to_string(),
+            ));
+        }
+        MemoryVisibility::Private if entry.session_id.is_none() && entry.subagent_id.is_none() => {
+            return Err(MemoryStoreError::InvalidEntry(
+                "private visibility requires a session_id or subagent_id".to_string(),
+            ));
+        }
+        _ => {}
+    }
     Ok(())
 }
 
@@ -468,6 +859,9 @@ fn normalize_patch(patch: MemoryPatch) -> Result<MemoryPatchData> {
     if let Some(raw) = object.get("updated_at") {
         patch.updated_at = Some(serde_json::from_value(raw.clone())?);
     }
+    if let Some(raw) = object.get("visibility") {
+        patch.visibility = Some(serde_json::from_value(raw.clone())?);
+    }
 
     if patch.is_empty() {
         return Err(MemoryStoreError::InvalidPatch(
@@ -496,6 +890,7 @@ fn stats_for_entries(entries: &HashMap<String, MemoryEntry>) -> MemorySyncStats
         total_entries,
         live_entries: total_entries - tombstones,
         tombstones,
+        quarantined: 0,
     }
 }
 
@@ -521,6 +916,42 @@ fn compare_entries(left: &MemoryEntry, right: &MemoryEntry) -> Ordering {
         .then_with(|| left.subagent_id.cmp(&right.subagent_id))
 }
 
+/// Whether an entry passes a list query; `text` is the query text, lowercased.
+fn matches_query(entry: &MemoryEntry, text: Option<&str>, query: &MemoryQuery) -> bool {
+    query.kind.as_ref().is_none_or(|kind| &entry.kind == kind)
+        && query.tags.iter().all(|tag| entry.tags.contains(tag))
+        && query
+            .session_id
+            .as_ref()
+            .is_none_or(|session| entry.session_id.as_ref() == Some(session))
+        && query
+            .created_after
+            .is_none_or(|after| entry.created_at >= after)
+        && query
+            .created_before
+            .is_none_or(|before| entry.created_at < before)
+        && text.is_none_or(|text| entry.content.to_lowercase().contains(text))
+        && query
+            .viewer
+            .as_ref()
+            .is_none_or(|viewer| is_visible(entry, viewer))
+}
+
+/// Whether `viewer` may read an entry.
+///
+/// Private entries belong to the sub-agent (or parent session) that wrote
+/// them, session entries to everyone in the session, so sub-agents inherit
+/// what their parent shares with the session.
+fn is_visible(entry: &MemoryEntry, viewer: &MemoryViewer) -> bool {
+    match entry.visibility {
+        MemoryVisibility::Project => true,
+        MemoryVisibility::Session => entry.session_id == viewer.session_id,
+        MemoryVisibility::Private => {
+            entry.session_id == viewer.session_id && entry.subagent_id == viewer.subagent_id
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -539,6 +970,8 @@ mod tests {
             session_id: Some("session-1".to_string()),
             subagent_id: None,
             deleted: false,
+            experience_id: None,
+            visibility: MemoryVisibility::Project,
         }
     }
 
@@ -588,6 +1021,7 @@ mod tests {
                 total_entries: 2,
                 live_entries: 1,
                 tombstones: 1,
+                quarantined: 0,
             }
         );
 
@@ -596,7 +1030,10 @@ mod tests {
         assert_eq!(mem1.updated_at, 20);
         assert!(store.get(&project, "mem-2").await.unwrap().is_none());
 
-        let listed = store.list(&project, 10).await.unwrap();
+        let listed = store
+            .list(&project, &MemoryQuery::default(), 10)
+            .await
+            .unwrap();
         assert_eq!(listed.len(), 1);
         assert_eq!(listed[0].id, "mem-1");
 
@@ -607,6 +1044,487 @@ mod tests {
         assert!(restarted.get(&project, "mem-2").await.unwrap().is_none());
     }
 
+    #[tokio::test]
+    async fn test_replay_quarantines_corrupt_suffix() {
+        let temp_dir = tempdir().unwrap();
+        let project = temp_dir.path().join("project");
+        std::fs::create_dir_all(&project).unwrap();
+
+        let storage = Arc::new(Storage::new(temp_dir.path().join("storage")));
+        storage
+            .append_experience_durable(&project, &test_entry("mem-1", "kept", 10))
+            .await
+            .unwrap();
+        storage
+            .append_experience_durable(&project, &test_entry("mem-2", "flipped", 11))
+            .await
+            .unwrap();
+        let log = storage.experience_log(&storage.project_hash(&project));
+        let content = std::fs::read_to_string(log.path()).unwrap();
+        std::fs::write(log.path(), content.replace("flipped", "flopped")).unwrap();
+
+        let store = MemoryStore::new(storage.clone());
+        let stats = store.sync(&project).await.unwrap();
+        assert_eq!(stats.live_entries, 1);
+        assert_eq!(stats.quarantined, 1);
+        assert!(store.get(&project, "mem-2").await.unwrap().is_none());
+        assert!(std::fs::read_to_string(log.quarantine_path())
+            .unwrap()
+            .contains("flopped"));
+
+        assert_eq!(store.sync(&project).await.unwrap().quarantined, 0);
+    }
+
+    #[tokio::test]
+    async fn test_unload_drops_index() {
+        let temp_dir = tempdir().unwrap();
+        let project = temp_dir.path().join("project");
+        std::fs::create_dir_all(&project).unwrap();
+
+        let storage = Arc::new(Storage::new(temp_dir.path().join("storage")));
+        let store = MemoryStore::new(storage);
+        assert!(!store.unload(&project).await);
+
+        store
+            .put(&project, test_entry("mem-1", "kept", 10))
+            .await
+            .unwrap();
+        assert!(store.unload(&project).await);
+        assert!(store.projects.read().is_empty());
+
+        // Replayed from the log on next access
+        let entry = store.get(&project, "mem-1").await.unwrap().unwrap();
+        assert_eq!(entry.content, "kept");
+    }
+
+    #[tokio::test]
+    async fn test_flush_loaded_projects() {
+        let temp_dir = tempdir().unwrap();
+        let project = temp_dir.path().join("project");
+        std::fs::create_dir_all(&project).unwrap();
+
+        let storage = Arc::new(Storage::new(temp_dir.path().join("storage")));
+        let store = Arc::new(MemoryStore::new(storage.clone()));
+        assert_eq!(store.flush().await, 0);
+
+        store
+            .put(&project, test_entry("mem-1", "kept", 10))
+            .await
+            .unwrap();
+        let write = tokio::spawn({
+            let store = store.clone();
+            let project = project.clone();
+            async move { store.put(&project, test_entry("mem-2", "kept", 10)).await }
+        });
+        assert_eq!(store.flush().await, 1);
+        write.await.unwrap().unwrap();
+
+        // Everything written is on disk for the next daemon
+        let restarted = MemoryStore::new(storage);
+        assert_eq!(restarted.sync(&project).await.unwrap().total_entries, 2);
+    }
+
+    #[tokio::test]
+    async fn test_export_import() {
+        let temp_dir = tempdir().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+        std::fs::create_dir_all(&source).unwrap();
+        std::fs::create_dir_all(&target).unwrap();
+        let store = MemoryStore::new(Arc::new(Storage::new(temp_dir.path().join("storage"))));
+
+        store
+            .put(&source, test_entry("mem-1", "exported", 10))
+            .await
+            .unwrap();
+        store
+            .put(&source, test_entry("mem-2", "older", 10))
+            .await
+            .unwrap();
+        store
+            .put(&source, test_entry("mem-3", "deleted", 10))
+            .await
+            .unwrap();
+        store.delete(&source, "mem-3", None).await.unwrap();
+        store
+            .put(&target, test_entry("mem-2", "kept locally", 20))
+            .await
+            .unwrap();
+
+        let exported = store.export(&source).await.unwrap();
+        assert_eq!(exported.len(), 3);
+        let stats = store.import(&target, exported).await.unwrap();
+        assert_eq!(
+            stats,
+            MemoryImportStats {
+                imported: 2,
+                skipped: 1
+            }
+        );
+
+        let entry = store.get(&target, "mem-1").await.unwrap().unwrap();
+        assert_eq!((entry.content.as_str(), entry.updated_at), ("exported", 10));
+        let entry = store.get(&target, "mem-2").await.unwrap().unwrap();
+        assert_eq!(entry.content, "kept locally");
+        assert!(store.get(&target, "mem-3").await.unwrap().is_none());
+
+        // Importing again changes nothing
+        let exported = store.export(&source).await.unwrap();
+        let stats = store.import(&target, exported).await.unwrap();
+        assert_eq!(stats.imported, 0);
+    }
+
+    #[tokio::test]
+    async fn test_list_query_filters() {
+        let temp_dir = tempdir().unwrap();
+        let project = temp_dir.path().join("project");
+        std::fs::create_dir_all(&project).unwrap();
+        let store = MemoryStore::new(Arc::new(Storage::new(temp_dir.path().join("storage"))));
+
+        store
+            .put(&project, test_entry("mem-1", "Auth uses JWT", 10))
+            .await
+            .unwrap();
+        store
+            .put(
+                &project,
+                MemoryEntry {
+                    kind: "decision".to_string(),
+                    tags: vec!["phase2".to_string(), "auth".to_string()],
+                    created_at: 1_700_000_100,
+                    session_id: Some("session-2".to_string()),
+                    ..test_entry("mem-2", "Rotate auth keys daily", 11)
+                },
+            )
+            .await
+            .unwrap();
+
+        let ids = |entries: Vec<MemoryEntry>| -> Vec<String> {
+            entries.into_iter().map(|entry| entry.id).collect()
+        };
+        let list = |query: MemoryQuery| {
+            let store = &store;
+            let project = &project;
+            async move { ids(store.list(project, &query, 10).await.unwrap()) }
+        };
+
+        assert_eq!(list(MemoryQuery::default()).await, ["mem-1", "mem-2"]);
+        let by_kind = MemoryQuery {
+            kind: Some("decision".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(list(by_kind).await, ["mem-2"]);
+        let by_tags = MemoryQuery {
+            tags: vec!["auth".to_string(), "phase2".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(list(by_tags).await, ["mem-2"]);
+        let by_session = MemoryQuery {
+            session_id: Some("session-1".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(list(by_session).await, ["mem-1"]);
+        let by_time = MemoryQuery {
+            created_after: Some(1_700_000_000),
+            created_before: Some(1_700_000_100),
+            ..Default::default()
+        };
+        assert_eq!(list(by_time).await, ["mem-1"]);
+        let by_text = MemoryQuery {
+            text: Some("AUTH".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(list(by_text).await, ["mem-1", "mem-2"]);
+        let no_match = MemoryQuery {
+            text: Some("jwt".to_string()),
+            kind: Some("decision".to_string()),
+            ..Default::default()
+        };
+        assert!(list(no_match).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_viewer_namespaces() {
+        let temp_dir = tempdir().unwrap();
+        let project = temp_dir.path().join("project");
+        std::fs::create_dir_all(&project).unwrap();
+        let store = MemoryStore::new(Arc::new(Storage::new(temp_dir.path().join("storage"))));
+
+        let entry = |id: &str, session: &str, agent: Option<&str>, visibility| MemoryEntry {
+            session_id: Some(session.to_string()),
+            subagent_id: agent.map(str::to_string),
+            visibility,
+            ..test_entry(id, "token rotation notes", 10)
+        };
+        for entry in [
+            entry("project", "s1", None, MemoryVisibility::Project),
+            entry("shared", "s1", None, MemoryVisibility::Session),
+            entry("parent", "s1", None, MemoryVisibility::Private),
+            entry("agent-a", "s1", Some("a"), MemoryVisibility::Private),
+            entry("agent-b", "s1", Some("b"), MemoryVisibility::Private),
+            entry("other", "s2", None, MemoryVisibility::Session),
+        ] {
+            store.put(&project, entry).await.unwrap();
+        }
+
+        let visible = |session: &str, agent: Option<&str>| {
+            let viewer = MemoryViewer {
+                session_id: Some(session.to_string()),
+                subagent_id: agent.map(str::to_string),
+            };
+            let store = &store;
+            let project = &project;
+            async move {
+                let query = MemoryQuery {
+                    viewer: Some(viewer.clone()),
+                    ..Default::default()
+                };
+                let mut listed: Vec<String> = store
+                    .list(project, &query, 10)
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|entry| entry.id)
+                    .collect();
+                listed.sort();
+                let mut found: Vec<String> = store
+                    .search(project, "token rotation", 10, Some(&viewer))
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|(entry, _)| entry.id)
+                    .collect();
+                found.sort();
+                assert_eq!(listed, found);
+                listed
+            }
+        };
+
+        // A sub-agent inherits what its parent shares with the session
+        assert_eq!(
+            visible("s1", Some("a")).await,
+            ["agent-a", "project", "shared"]
+        );
+        assert_eq!(visible("s1", None).await, ["parent", "project", "shared"]);
+        assert_eq!(visible("s2", None).await, ["other", "project"]);
+        let all = store
+            .list(&project, &MemoryQuery::default(), 10)
+            .await
+            .unwrap();
+        assert_eq!(all.len(), 6);
+
+        let unscoped = MemoryEntry {
+            session_id: None,
+            visibility: MemoryVisibility::Session,
+            ..test_entry("unscoped", "no session", 10)
+        };
+        assert!(matches!(
+            store.put(&project, unscoped).await,
+            Err(MemoryStoreError::InvalidEntry(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_search_ranks_by_similarity() {
+        let temp_dir = tempdir().unwrap();
+        let project = temp_dir.path().join("project");
+        std::fs::create_dir_all(&project).unwrap();
+        let store = MemoryStore::new(Arc::new(Storage::new(temp_dir.path().join("storage"))));
+
+        for (id, content) in [
+            (
+                "mem-1",
+                "Session tokens are validated in the auth middleware",
+            ),
+            ("mem-2", "Invoice totals include tax per line item"),
+            ("mem-3", "Cache eviction drops the oldest entry"),
+        ] {
+            store
+                .put(&project, test_entry(id, content, 10))
+                .await
+                .unwrap();
+        }
+
+        let ids = |matches: Vec<(MemoryEntry, f32)>| -> Vec<String> {
+            matches.into_iter().map(|(entry, _)| entry.id).collect()
+        };
+        let results = store
+            .search(&project, "where is the session token checked", 2, None)
+            .await
+            .unwrap();
+        assert_eq!(results[0].0.id, "mem-1");
+        assert!(results.len() <= 2);
+        assert!(results.windows(2).all(|w| w[0].1 >= w[1].1));
+
+        // Edits re-embed the entry, deletions drop it
+        store
+            .patch(
+                &project,
+                "mem-3",
+                MemoryPatch {
+                    content: Some("Invoice tax is rounded per line".to_string()),
+                    updated_at: Some(20),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        store.delete(&project, "mem-2", None).await.unwrap();
+        let results = store
+            .search(&project, "invoice tax", 3, None)
+            .await
+            .unwrap();
+        assert_eq!(ids(results), ["mem-3"]);
+
+        assert!(store
+            .search(&project, "how does it work", 3, None)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_compact_keeps_latest_versions() {
+        let temp_dir = tempdir().unwrap();
+        let project = temp_dir.path().join("project");
+        std::fs::create_dir_all(&project).unwrap();
+        let storage = Arc::new(Storage::new(temp_dir.path().join("storage")));
+        let store = MemoryStore::new(storage.clone()).with_compaction(CompactionPolicy {
+            size_threshold: 0,
+            tombstone_retention_secs: 3600,
+        });
+
+        let now = current_timestamp();
+        store
+            .put(&project, test_entry("mem-1", "v1", now - 10))
+            .await
+            .unwrap();
+        store
+            .patch(
+                &project,
+                "mem-1",
+                MemoryPatch {
+                    content: Some("v2".to_string()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        store
+            .put(&project, test_entry("mem-old", "gone", now - 7200))
+            .await
+            .unwrap();
+        store
+            .delete(&project, "mem-old", Some(now - 7200))
+            .await
+            .unwrap();
+        store
+            .put(&project, test_entry("mem-recent", "gone soon", now - 10))
+            .await
+            .unwrap();
+        store.delete(&project, "mem-recent", None).await.unwrap();
+        storage
+            .append_experience(&project, &serde_json::json!({"task": "graft"}))
+            .await
+            .unwrap();
+
+        let stats = store.compact(&project).await.unwrap();
+        assert_eq!(
+            stats,
+            MemoryCompactStats {
+                lines_before: 7,
+                lines_after: 3,
+                dropped_versions: 3,
+                dropped_tombstones: 1,
+            }
+        );
+
+        // Compacted log replays to the same state
+        let restarted = MemoryStore::new(storage);
+        let mem1 = restarted.get(&project, "mem-1").await.unwrap().unwrap();
+        assert_eq!(mem1.content, "v2");
+        assert!(
+            restarted
+                .get_latest(&project, "mem-recent")
+                .await
+                .unwrap()
+                .unwrap()
+                .deleted
+        );
+        assert!(restarted
+            .get_latest(&project, "mem-old")
+            .await
+            .unwrap()
+            .is_none());
+        assert!(store
+            .get_latest(&project, "mem-old")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_compact_on_size_threshold() {
+        let temp_dir = tempdir().unwrap();
+        let project = temp_dir.path().join("project");
+        std::fs::create_dir_all(&project).unwrap();
+        let storage = Arc::new(Storage::new(temp_dir.path().join("storage")));
+        let store = MemoryStore::new(storage.clone()).with_compaction(CompactionPolicy {
+            size_threshold: 2048,
+            ..Default::default()
+        });
+
+        store
+            .put(&project, test_entry("mem-1", "v0", 1))
+            .await
+            .unwrap();
+        for version in 1..100 {
+            store
+                .patch(
+                    &project,
+                    "mem-1",
+                    MemoryPatch {
+                        content: Some(format!("v{}", version)),
+                        ..Default::default()
+                    },
+                )
+                .await
+                .unwrap();
+        }
+
+        assert!(storage.experience_size(&project).await < 2048);
+        let mem1 = store.get(&project, "mem-1").await.unwrap().unwrap();
+        assert_eq!(mem1.content, "v99");
+    }
+
+    #[tokio::test]
+    async fn test_project_policy_overrides_store_policy() {
+        let temp_dir = tempdir().unwrap();
+        let project = temp_dir.path().join("project");
+        std::fs::create_dir_all(&project).unwrap();
+        let storage = Arc::new(Storage::new(temp_dir.path().join("storage")));
+        let store = MemoryStore::new(storage.clone()).with_compaction(CompactionPolicy {
+            size_threshold: 2048,
+            ..Default::default()
+        });
+        store.set_project_policy(
+            &project,
+            CompactionPolicy {
+                size_threshold: 0,
+                ..Default::default()
+            },
+        );
+
+        for version in 0..100 {
+            store
+                .put(&project, test_entry("mem-1", &format!("v{}", version), 1))
+                .await
+                .unwrap();
+        }
+
+        assert!(storage.experience_size(&project).await >= 2048);
+    }
+
     #[tokio::test]
     async fn test_patch_delete_tombstone_behavior() {
         let temp_dir = tempdir().unwrap();
@@ -646,7 +1564,14 @@ mod tests {
             .unwrap();
         assert!(tombstone.deleted);
         assert!(store.get(&project, "mem-1").await.unwrap().is_none());
-        assert_eq!(store.list(&project, 10).await.unwrap().len(), 0);
+        assert_eq!(
+            store
+                .list(&project, &MemoryQuery::default(), 10)
+                .await
+                .unwrap()
+                .len(),
+            0
+        );
 
         // Patching a tombstoned entry keeps it tombstoned unless explicitly revived.
         let still_deleted = store
@@ -742,7 +1667,10 @@ mod tests {
         let tie_latest = store.get(&project, "tie-break").await.unwrap().unwrap();
         assert_eq!(tie_latest.content, "omega");
 
-        let listed = store.list(&project, 1000).await.unwrap();
+        let listed = store
+            .list(&project, &MemoryQuery::default(), 1000)
+            .await
+            .unwrap();
         assert_eq!(listed.len(), UNIQUE_WRITES + 2);
 
         let persisted: Vec<MemoryEntry> = storage.load_all_experiences(&project).await.unwrap();
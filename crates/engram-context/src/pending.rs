@@ -0,0 +1,164 @@
+//! Staging queue for memory writes from read-only scopes.
+//!
+//! Sub-agents whose scope is read-only cannot write long-term memory
+//! directly; their entries are staged here until the parent agent approves
+//! or rejects them. The queue is persisted per project so staged entries
+//! survive a daemon restart.
+
+use crate::memory::{MemoryStoreError, Result};
+use engram_indexer::storage::Storage;
+use engram_ipc::MemoryEntry;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// File name of the queue in the project data directory.
+const PENDING_FILE: &str = "pending_memory.json";
+
+/// Per-project queue of memory entries awaiting approval.
+pub struct PendingMemory {
+    storage: Arc<Storage>,
+    /// Loaded queues keyed by project hash
+    projects: Mutex<HashMap<String, Vec<MemoryEntry>>>,
+}
+
+impl PendingMemory {
+    /// Create an empty pending queue.
+    pub fn new(storage: Arc<Storage>) -> Self {
+        Self {
+            storage,
+            projects: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn path(&self, hash: &str) -> PathBuf {
+        self.storage.project_dir(hash).join(PENDING_FILE)
+    }
+
+    /// Stage an entry, replacing any staged entry with the same id.
+    pub async fn stage(&self, project_path: &Path, entry: MemoryEntry) -> Result<()> {
+        self.update(project_path, |queue| {
+            queue.retain(|staged| staged.id != entry.id);
+            queue.push(entry);
+        })
+        .await
+        .map(|_| ())
+    }
+
+    /// Staged entries, oldest first.
+    pub async fn list(&self, project_path: &Path) -> Result<Vec<MemoryEntry>> {
+        self.update(project_path, |queue| queue.clone()).await
+    }
+
+    /// Remove and return staged entries by id (all entries if `ids` is empty).
+    pub async fn take(&self, project_path: &Path, ids: &[String]) -> Result<Vec<MemoryEntry>> {
+        self.update(project_path, |queue| {
+            let (taken, kept) = queue
+                .drain(..)
+                .partition(|entry| ids.is_empty() || ids.contains(&entry.id));
+            *queue = kept;
+            taken
+        })
+        .await
+    }
+
+    /// Drop a project's cached queue; it is persisted on every change.
+    pub async fn unload(&self, project_path: &Path) {
+        let hash = self.storage.project_hash(project_path);
+        self.projects.lock().await.remove(&hash);
+    }
+
+    /// Apply `f` to a project's queue, loading it first and persisting it
+    /// afterwards.
+    async fn update<T>(
+        &self,
+        project_path: &Path,
+        f: impl FnOnce(&mut Vec<MemoryEntry>) -> T,
+    ) -> Result<T> {
+        let hash = self.storage.project_hash(project_path);
+        let path = self.path(&hash);
+        let mut projects = self.projects.lock().await;
+
+        if !projects.contains_key(&hash) {
+            let queue = match tokio::fs::read(&path).await {
+                Ok(data) => serde_json::from_slice(&data)?,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+                Err(e) => return Err(MemoryStoreError::Storage(e.to_string())),
+            };
+            projects.insert(hash.clone(), queue);
+        }
+
+        let queue = projects.get_mut(&hash).expect("queue loaded above");
+        let before = queue.clone();
+        let result = f(queue);
+        if *queue != before {
+            persist(&path, queue).await?;
+        }
+        Ok(result)
+    }
+}
+
+async fn persist(path: &Path, queue: &[MemoryEntry]) -> Result<()> {
+    let storage_err = |e: std::io::Error| MemoryStoreError::Storage(e.to_string());
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await.map_err(storage_err)?;
+    }
+    let temp_path = path.with_extension("json.tmp");
+    tokio::fs::write(&temp_path, serde_json::to_vec(queue)?)
+        .await
+        .map_err(storage_err)?;
+    tokio::fs::rename(&temp_path, path)
+        .await
+        .map_err(storage_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engram_indexer::storage::StorageOptions;
+    use engram_ipc::MemoryVisibility;
+    use tempfile::tempdir;
+
+    fn entry(id: &str) -> MemoryEntry {
+        MemoryEntry {
+            id: id.to_string(),
+            kind: "note".to_string(),
+            content: "content".to_string(),
+            tags: vec![],
+            created_at: 1,
+            updated_at: 1,
+            session_id: None,
+            subagent_id: Some("sub".to_string()),
+            deleted: false,
+            experience_id: None,
+            visibility: MemoryVisibility::Project,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stage_take_and_reload() {
+        let temp_dir = tempdir().unwrap();
+        let storage = Arc::new(Storage::with_options(StorageOptions {
+            base_dir: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        }));
+        let project = Path::new("/project");
+
+        let pending = PendingMemory::new(storage.clone());
+        pending.stage(project, entry("a")).await.unwrap();
+        pending.stage(project, entry("b")).await.unwrap();
+
+        // A fresh queue reloads staged entries from disk
+        let reloaded = PendingMemory::new(storage);
+        assert_eq!(reloaded.list(project).await.unwrap().len(), 2);
+
+        let taken = reloaded.take(project, &["a".to_string()]).await.unwrap();
+        assert_eq!(taken.len(), 1);
+        assert_eq!(taken[0].id, "a");
+
+        let rest = reloaded.take(project, &[]).await.unwrap();
+        assert_eq!(rest.len(), 1);
+        assert!(reloaded.list(project).await.unwrap().is_empty());
+    }
+}
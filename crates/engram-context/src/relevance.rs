@@ -0,0 +1,281 @@
+//! Per-file relevance learned from the outcomes of agent decisions.
+//!
+//! Every experience grafted with an [`Outcome`] credits the files it
+//! touched: a success counts towards them, a failure or revert against
+//! them. The counts decay with a half-life of two weeks, so the weights
+//! follow how the project is worked on now. Retrieval and focus
+//! auto-loading multiply by the resulting weight, preferring files whose
+//! past changes held up.
+
+use crate::error::Result;
+use crate::scope::Outcome;
+use chrono::Utc;
+use engram_indexer::storage::Storage;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// File name of the learned relevance in the project data directory.
+const RELEVANCE_FILE: &str = "relevance.json";
+
+/// Time for outcome counts to lose half their weight (seconds).
+const HALF_LIFE_SECS: f64 = 14.0 * 24.0 * 60.0 * 60.0;
+
+/// Most files tracked per project; the least recently credited are dropped.
+const MAX_FILES: usize = 5000;
+
+/// Decayed outcome counts of one file.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FileRelevance {
+    /// Graded successes, as of `updated_at`
+    pub successes: f32,
+    /// Graded failures, as of `updated_at`
+    pub failures: f32,
+    /// Last outcome recorded (Unix timestamp)
+    pub updated_at: i64,
+}
+
+impl FileRelevance {
+    /// Counts decayed to `now`.
+    fn decayed(&self, now: i64) -> (f32, f32) {
+        let age = now.saturating_sub(self.updated_at).max(0) as f64;
+        let factor = 0.5f64.powf(age / HALF_LIFE_SECS) as f32;
+        (self.successes * factor, self.failures * factor)
+    }
+}
+
+/// Learned relevance of one project's files.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Relevance {
+    /// Outcome counts by project-relative path
+    #[serde(default)]
+    pub files: BTreeMap<PathBuf, FileRelevance>,
+}
+
+impl Relevance {
+    /// Credit `files` with an outcome graded `grade` (0 = failure,
+    /// 1 = success) at `now`.
+    pub fn record(&mut self, files: &[PathBuf], grade: f32, now: i64) {
+        let grade = grade.clamp(0.0, 1.0);
+        for path in files {
+            let entry = self.files.entry(path.clone()).or_insert(FileRelevance {
+                successes: 0.0,
+                failures: 0.0,
+                updated_at: now,
+            });
+            let (successes, failures) = entry.decayed(now);
+            *entry = FileRelevance {
+                successes: successes + grade,
+                failures: failures + (1.0 - grade),
+                updated_at: now,
+            };
+        }
+
+        if self.files.len() > MAX_FILES {
+            let mut by_age: Vec<(i64, PathBuf)> = self
+                .files
+                .iter()
+                .map(|(path, file)| (file.updated_at, path.clone()))
+                .collect();
+            by_age.sort();
+            for (_, path) in by_age.into_iter().take(self.files.len() - MAX_FILES) {
+                self.files.remove(&path);
+            }
+        }
+    }
+
+    /// Score multiplier for `path` at `now`, between 0.5 and 1.5.
+    ///
+    /// Files without outcomes get 1.0; each success pulls the weight up
+    /// and each failure down, with diminishing effect.
+    pub fn weight_at(&self, path: &Path, now: i64) -> f32 {
+        let Some(file) = self.files.get(path) else {
+            return 1.0;
+        };
+        let (successes, failures) = file.decayed(now);
+        0.5 + (successes + 1.0) / (successes + failures + 2.0)
+    }
+
+    /// Score multiplier for `path` now.
+    pub fn weight(&self, path: &Path) -> f32 {
+        self.weight_at(path, Utc::now().timestamp())
+    }
+
+    /// Up to `limit` files most worked on lately, by decayed outcome count.
+    pub fn hot_files_at(&self, limit: usize, now: i64) -> Vec<&PathBuf> {
+        let mut files: Vec<(f32, &PathBuf)> = self
+            .files
+            .iter()
+            .map(|(path, file)| {
+                let (successes, failures) = file.decayed(now);
+                (successes + failures, path)
+            })
+            .collect();
+        files.sort_by(|a, b| b.0.total_cmp(&a.0));
+        files
+            .into_iter()
+            .take(limit)
+            .map(|(_, path)| path)
+            .collect()
+    }
+
+    /// Files most worked on lately.
+    pub fn hot_files(&self, limit: usize) -> Vec<&PathBuf> {
+        self.hot_files_at(limit, Utc::now().timestamp())
+    }
+
+    /// Whether no outcomes were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+}
+
+/// Loads, updates and stores relevance per project.
+pub struct RelevanceStore {
+    storage: Arc<Storage>,
+    projects: Mutex<HashMap<String, Relevance>>,
+}
+
+impl RelevanceStore {
+    /// Create a relevance store.
+    pub fn new(storage: Arc<Storage>) -> Self {
+        Self {
+            storage,
+            projects: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// A project's learned relevance, empty if nothing was recorded.
+    pub async fn load(&self, project_path: &Path) -> Result<Relevance> {
+        self.update(project_path, |relevance| relevance.clone())
+            .await
+    }
+
+    /// Credit the files an experience touched with its outcome.
+    ///
+    /// Absolute paths inside the project are made relative to it.
+    pub async fn record(
+        &self,
+        project_path: &Path,
+        files: &[PathBuf],
+        outcome: &Outcome,
+    ) -> Result<()> {
+        if files.is_empty() {
+            return Ok(());
+        }
+        let files: Vec<PathBuf> = files
+            .iter()
+            .map(|path| {
+                path.strip_prefix(project_path)
+                    .map(Path::to_path_buf)
+                    .unwrap_or_else(|_| path.clone())
+            })
+            .collect();
+        let now = Utc::now().timestamp();
+        self.update(project_path, |relevance| {
+            relevance.record(&files, outcome.grade(), now)
+        })
+        .await
+    }
+
+    /// Drop a project's cached relevance; it is persisted on every change.
+    pub async fn unload(&self, project_path: &Path) {
+        let hash = self.storage.project_hash(project_path);
+        self.projects.lock().await.remove(&hash);
+    }
+
+    /// Apply `f` to a project's relevance, loading it first and persisting
+    /// it afterwards if it changed.
+    async fn update<T>(
+        &self,
+        project_path: &Path,
+        f: impl FnOnce(&mut Relevance) -> T,
+    ) -> Result<T> {
+        let hash = self.storage.project_hash(project_path);
+        let path = self.storage.project_dir(&hash).join(RELEVANCE_FILE);
+        let mut projects = self.projects.lock().await;
+
+        if !projects.contains_key(&hash) {
+            let relevance = match tokio::fs::read(&path).await {
+                Ok(data) => serde_json::from_slice(&data)?,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Relevance::default(),
+                Err(e) => return Err(e.into()),
+            };
+            projects.insert(hash.clone(), relevance);
+        }
+
+        let relevance = projects.get_mut(&hash).expect("relevance loaded above");
+        let before = relevance.clone();
+        let result = f(relevance);
+        if *relevance != before {
+            if let Some(dir) = path.parent() {
+                tokio::fs::create_dir_all(dir).await?;
+            }
+            let temp_path = path.with_extension("json.tmp");
+            tokio::fs::write(&temp_path, serde_json::to_vec(&*relevance)?).await?;
+            tokio::fs::rename(&temp_path, &path).await?;
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_weights_follow_outcomes() {
+        let mut relevance = Relevance::default();
+        let good = PathBuf::from("src/good.rs");
+        let bad = PathBuf::from("src/bad.rs");
+        for _ in 0..3 {
+            relevance.record(std::slice::from_ref(&good), 1.0, 1000);
+            relevance.record(std::slice::from_ref(&bad), 0.0, 1000);
+        }
+
+        assert_eq!(relevance.weight_at(Path::new("src/other.rs"), 1000), 1.0);
+        let good_weight = relevance.weight_at(&good, 1000);
+        let bad_weight = relevance.weight_at(&bad, 1000);
+        assert!((good_weight - 1.3).abs() < 1e-4, "{}", good_weight);
+        assert!((bad_weight - 0.7).abs() < 1e-4, "{}", bad_weight);
+
+        // Old outcomes fade back towards neutral
+        let later = 1000 + (10.0 * HALF_LIFE_SECS) as i64;
+        assert!((relevance.weight_at(&good, later) - 1.0).abs() < 0.01);
+
+        // Files worked on most are hot, whatever the outcome
+        relevance.record(std::slice::from_ref(&bad), 0.0, 1000);
+        assert_eq!(relevance.hot_files_at(1, 1000), vec![&bad]);
+        assert_eq!(relevance.hot_files_at(5, 1000).len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_store_records_relative_paths() {
+        let temp_dir = tempdir().unwrap();
+        let storage = Arc::new(Storage::new(temp_dir.path().to_path_buf()));
+        let project = temp_dir.path().join("project");
+
+        let store = RelevanceStore::new(storage.clone());
+        store
+            .record(&project, &[project.join("src/lib.rs")], &Outcome::Success)
+            .await
+            .unwrap();
+        store
+            .record(
+                &project,
+                &[PathBuf::from("src/main.rs")],
+                &Outcome::Reverted,
+            )
+            .await
+            .unwrap();
+
+        // Reloaded from disk
+        let store = RelevanceStore::new(storage);
+        let relevance = store.load(&project).await.unwrap();
+        assert!(relevance.weight(Path::new("src/lib.rs")) > 1.0);
+        assert!(relevance.weight(Path::new("src/main.rs")) < 1.0);
+    }
+}
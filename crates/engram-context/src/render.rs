@@ -3,8 +3,67 @@
 //! Converts context scopes into markdown strings suitable for
 //! injection into AI agent prompts.
 
+use crate::manager::DependencyExpansion;
 use crate::scope::ContextScope;
-use engram_indexer::tree::Tree;
+use engram_indexer::tree::{NodeId, NodeKind, Tree};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+
+/// Header every rendered context starts with
+const CONTEXT_HEADER: &str = "# PROJECT CONTEXT\n\n";
+
+/// Rough size of a token, for budgeting sections
+const BYTES_PER_TOKEN: usize = 4;
+
+/// One titled part of a rendered context, with where it came from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Section {
+    /// Section title, e.g. `Focus Area`
+    pub title: String,
+    /// Tree nodes rendered into the section
+    pub source_nodes: Vec<NodeId>,
+    /// Estimated token count of `text`
+    pub tokens: usize,
+    /// Rendered markdown
+    pub text: String,
+}
+
+impl Section {
+    fn new(title: &str, source_nodes: Vec<NodeId>, text: String) -> Self {
+        Self {
+            title: title.to_string(),
+            source_nodes,
+            tokens: text.len().div_ceil(BYTES_PER_TOKEN),
+            text,
+        }
+    }
+}
+
+/// A context rendered as sections, so clients can show provenance or drop
+/// sections to fit their own budgets.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RenderedContext {
+    /// Sections in display order
+    pub sections: Vec<Section>,
+}
+
+impl RenderedContext {
+    /// Join the sections under the context header.
+    pub fn to_text(&self) -> String {
+        let mut output = String::from(CONTEXT_HEADER);
+        for section in &self.sections {
+            output.push_str(&section.text);
+        }
+        output
+    }
+
+    /// Estimated token count of all sections.
+    pub fn tokens(&self) -> usize {
+        self.sections.iter().map(|section| section.tokens).sum()
+    }
+}
 
 /// Renderer for context scopes.
 pub struct ContextRenderer {
@@ -27,94 +86,163 @@ impl ContextRenderer {
         }
     }
 
+    /// Hash of the options that affect rendered output.
+    pub fn options_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.max_content_size.hash(&mut hasher);
+        hasher.finish()
+    }
+
     /// Render a context scope to a string.
     pub fn render(&self, scope: &ContextScope, tree: &Tree) -> String {
-        let mut output = String::new();
+        self.render_sections(scope, tree).to_text()
+    }
+
+    /// Render a context scope as separate sections, in display order.
+    ///
+    /// Joined under the context header, the sections are exactly what
+    /// [`ContextRenderer::render`] returns.
+    pub fn render_sections(&self, scope: &ContextScope, tree: &Tree) -> RenderedContext {
+        let mut sections = Vec::new();
         let mut current_size = 0;
 
-        // Header
-        output.push_str("# PROJECT CONTEXT\n\n");
+        // Anchor: Project brief
+        if let Some(brief) = &scope.anchor.brief {
+            let output = format!("## Project Brief\n{}\n\n", brief.trim_end());
+            sections.push(Section::new("Project Brief", vec![], output));
+        }
 
         // Anchor: Rules
         if !scope.anchor.rules.is_empty() {
-            output.push_str("## Rules\n");
+            let mut output = String::from("## Rules\n");
             for rule in &scope.anchor.rules {
                 output.push_str(rule);
                 output.push('\n');
             }
             output.push('\n');
+            sections.push(Section::new("Rules", vec![], output));
         }
 
         // Anchor: Constraints
         if !scope.anchor.constraints.is_empty() {
-            output.push_str("## Constraints\n");
+            let mut output = String::from("## Constraints\n");
             for constraint in &scope.anchor.constraints {
                 output.push_str(&format!("- {}\n", constraint));
             }
             output.push('\n');
+            sections.push(Section::new("Constraints", vec![], output));
+        }
+
+        // Anchor: Summary of the last session
+        if let Some(summary) = &scope.anchor.previous_session {
+            let output = format!("## Previous Session\n{}\n\n", summary.trim_end());
+            sections.push(Section::new("Previous Session", vec![], output));
+        }
+
+        // Anchor: Changes since the last session
+        if !scope.anchor.changes.is_empty() {
+            let mut output = String::from("## Changes Since Last Session\n");
+            for change in &scope.anchor.changes {
+                output.push_str(&format!("- {}\n", change));
+            }
+            output.push('\n');
+            sections.push(Section::new("Changes Since Last Session", vec![], output));
         }
 
         // Anchor: Recent Experiences
         if !scope.anchor.experiences.is_empty() {
-            output.push_str("## Recent Decisions\n");
+            let mut output = String::from("## Recent Decisions\n");
             for exp in scope.anchor.experiences.iter().rev().take(5) {
                 output.push_str(&format!("- **{}**: {}\n", exp.agent_id, exp.decision));
                 if let Some(rationale) = &exp.rationale {
                     output.push_str(&format!("  - Rationale: {}\n", rationale));
                 }
+                if !exp.memory_ids.is_empty() {
+                    output.push_str(&format!("  - Memories: {}\n", exp.memory_ids.join(", ")));
+                }
             }
             output.push('\n');
+            sections.push(Section::new("Recent Decisions", vec![], output));
+        }
+
+        if scope.read_only_memory {
+            sections.push(Section::new(
+                "Memory",
+                vec![],
+                "_(Memory is read-only for this agent: new memory entries are staged for approval by the parent agent.)_\n\n".to_string(),
+            ));
         }
 
         // Focus: Primary files with content
         if !scope.focus.primary_nodes.is_empty() {
-            output.push_str("## Focus Area\n\n");
+            let mut output = String::from("## Focus Area\n\n");
+            let mut rendered = Vec::new();
 
             for node_id in &scope.focus.primary_nodes {
-                if let Some(node) = tree.get_node(*node_id) {
-                    let path = node.path.display();
-                    output.push_str(&format!("### {} (primary)\n", path));
-
-                    if let Some(content) = &node.content {
-                        let content_str = self.render_node_content(content, &mut current_size);
-                        output.push_str("```\n");
-                        output.push_str(&content_str);
-                        output.push_str("\n```\n\n");
+                let Some(node) = tree.get_node(*node_id) else {
+                    continue;
+                };
+                rendered.push(*node_id);
+
+                // Symbols render only their own lines
+                if let NodeKind::Symbol {
+                    start_line,
+                    end_line,
+                    ..
+                } = node.kind
+                {
+                    if let Some(file) = node.parent.and_then(|id| tree.get_node(id)) {
+                        output.push_str(&self.render_symbol(
+                            &scope.project_path,
+                            &file.path,
+                            &node.name,
+                            (start_line, end_line),
+                            &mut current_size,
+                        ));
+                        continue;
                     }
                 }
+
+                output.push_str(&format!("### {} (primary)\n", node.path.display()));
+
+                if let Some(content) = tree.content(node.id) {
+                    let content_str = self.render_node_content(&content, &mut current_size);
+                    output.push_str("```\n");
+                    output.push_str(&content_str);
+                    output.push_str("\n```\n\n");
+                }
             }
+            sections.push(Section::new("Focus Area", rendered, output));
         }
 
         // Focus: Auto-loaded dependencies
         if !scope.focus.auto_loaded.is_empty() {
-            output.push_str("### Dependencies\n\n");
-
-            for node_id in &scope.focus.auto_loaded {
-                if current_size >= self.max_content_size {
-                    output.push_str("_(content truncated due to size limit)_\n");
-                    break;
-                }
-
-                if let Some(node) = tree.get_node(*node_id) {
-                    output.push_str(&format!("#### {}\n", node.path.display()));
+            let mut output = String::from("### Dependencies\n\n");
+            let rendered = self.render_files(
+                &scope.focus.auto_loaded,
+                tree,
+                &mut output,
+                &mut current_size,
+            );
+            sections.push(Section::new("Dependencies", rendered, output));
+        }
 
-                    if let Some(content) = &node.content {
-                        let content_str = self.render_node_content(content, &mut current_size);
-                        output.push_str("```\n");
-                        output.push_str(&content_str);
-                        output.push_str("\n```\n\n");
-                    }
-                }
-            }
+        // Focus: Tests of the primary files
+        if !scope.focus.tests.is_empty() {
+            let mut output = String::from("### Tests\n\n");
+            let rendered =
+                self.render_files(&scope.focus.tests, tree, &mut output, &mut current_size);
+            sections.push(Section::new("Tests", rendered, output));
         }
 
         // Horizon: Project structure
-        output.push_str("## Project Structure (overview)\n\n");
+        let mut output = String::from("## Project Structure (overview)\n\n");
         output.push_str("```\n");
         output.push_str(&scope.horizon.skeleton);
         output.push_str("\n```\n");
+        sections.push(Section::new("Project Structure", vec![], output));
 
-        output
+        RenderedContext { sections }
     }
 
     /// Render a compact version of the context.
@@ -139,6 +267,108 @@ impl ContextRenderer {
         output
     }
 
+    /// Render the importers and imports pulled in by a focus expansion.
+    pub fn render_expansion(&self, expansion: &DependencyExpansion, tree: &Tree) -> String {
+        let mut output = String::new();
+        let mut current_size = 0;
+
+        let path = tree
+            .get_node(expansion.node)
+            .map(|node| node.path.display().to_string())
+            .unwrap_or_default();
+        output.push_str(&format!("## Dependencies of {}\n\n", path));
+
+        if expansion.importers.is_empty() && expansion.imports.is_empty() {
+            output.push_str("_(no importers or imports outside the current focus)_\n");
+            return output;
+        }
+
+        for (title, nodes) in [
+            ("Imported by", &expansion.importers),
+            ("Imports", &expansion.imports),
+        ] {
+            if nodes.is_empty() {
+                continue;
+            }
+            output.push_str(&format!("### {}\n\n", title));
+            self.render_files(nodes, tree, &mut output, &mut current_size);
+        }
+
+        output
+    }
+
+    /// Render file headers with content summaries until the size limit,
+    /// returning the nodes rendered.
+    fn render_files(
+        &self,
+        nodes: &[NodeId],
+        tree: &Tree,
+        output: &mut String,
+        current_size: &mut usize,
+    ) -> Vec<NodeId> {
+        let mut rendered = Vec::new();
+        for node_id in nodes {
+            if *current_size >= self.max_content_size {
+                output.push_str("_(content truncated due to size limit)_\n");
+                break;
+            }
+
+            if let Some(node) = tree.get_node(*node_id) {
+                rendered.push(*node_id);
+                output.push_str(&format!("#### {}\n", node.path.display()));
+
+                if let Some(content) = tree.content(node.id) {
+                    let content_str = self.render_node_content(&content, current_size);
+                    output.push_str("```\n");
+                    output.push_str(&content_str);
+                    output.push_str("\n```\n\n");
+                }
+            }
+        }
+        rendered
+    }
+
+    /// Render only the lines of a focused symbol, read from its file.
+    fn render_symbol(
+        &self,
+        project_path: &Path,
+        file: &Path,
+        name: &str,
+        (start_line, end_line): (usize, usize),
+        current_size: &mut usize,
+    ) -> String {
+        let mut output = format!(
+            "### {}::{} (primary, lines {}-{})\n",
+            file.display(),
+            name,
+            start_line,
+            end_line
+        );
+
+        let source = std::fs::read_to_string(project_path.join(file)).ok();
+        let Some(source) = source else {
+            output.push_str("_(source unavailable)_\n\n");
+            return output;
+        };
+
+        output.push_str("```\n");
+        for line in source
+            .lines()
+            .skip(start_line.saturating_sub(1))
+            .take(end_line.saturating_sub(start_line) + 1)
+        {
+            if *current_size + line.len() > self.max_content_size {
+                output.push_str("```\n_(content truncated due to size limit)_\n\n");
+                return output;
+            }
+            *current_size += line.len() + 1;
+            output.push_str(line);
+            output.push('\n');
+        }
+        output.push_str("```\n\n");
+        output
+    }
+
     /// Render node content with size tracking.
     fn render_node_content(
         &self,
@@ -167,7 +397,7 @@ impl Default for ContextRenderer {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::scope::{AnchorContext, Experience, FocusContext, HorizonContext};
+    use crate::scope::Experience;
     use std::path::PathBuf;
 
     fn create_test_scope() -> ContextScope {
@@ -205,6 +435,72 @@ mod tests {
         assert!(output.contains("Added caching"));
     }
 
+    #[test]
+    fn test_render_symbol_line_range() {
+        use engram_indexer::scanner::{ScanResult, ScannedFile, Symbol, SymbolKind};
+        use engram_indexer::tree::TreeBuilder;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("src")).unwrap();
+        std::fs::write(
+            temp_dir.path().join("src/auth.rs"),
+            "use db;\n\nfn authenticate() {\n    check();\n}\n\nfn unrelated() {}\n",
+        )
+        .unwrap();
+
+        let scan = ScanResult {
+            root: temp_dir.path().to_path_buf(),
+            files: vec![ScannedFile {
+                path: PathBuf::from("src/auth.rs"),
+                language: None,
+                size: 0,
+                hash: String::new(),
+                line_count: 7,
+                symbols: vec![Symbol {
+                    name: "authenticate".to_string(),
+                    kind: SymbolKind::Function,
+                    start_line: 3,
+                    end_line: 5,
+                    parent: None,
+                    doc: None,
+                }],
+                imports: vec![],
+                truncated: false,
+                asset: None,
+            }],
+            languages: vec![],
+            frameworks: vec![],
+            duration_ms: 0,
+            skipped_count: 0,
+            excluded_dirs: vec![],
+        };
+        let tree = TreeBuilder::new().build(&scan);
+
+        let mut scope = ContextScope::new(temp_dir.path().to_path_buf());
+        scope.focus.primary_nodes = vec![tree
+            .find_symbol(&PathBuf::from("src/auth.rs"), "authenticate")
+            .unwrap()];
+
+        let output = ContextRenderer::new().render(&scope, &tree);
+        assert!(output.contains("### src/auth.rs::authenticate (primary, lines 3-5)\n"));
+        assert!(output.contains("```\nfn authenticate() {\n    check();\n}\n```"));
+        assert!(!output.contains("unrelated"));
+        assert!(!output.contains("use db;"));
+    }
+
+    #[test]
+    fn test_render_links_experience_memories() {
+        let renderer = ContextRenderer::new();
+        let mut scope = create_test_scope();
+        scope.anchor.experiences = vec![Experience::new("agent-1", "Split parser")
+            .with_memory_ids(vec!["mem-1".to_string(), "mem-2".to_string()])];
+        let tree = Tree::new(PathBuf::from("/test/project"));
+
+        let output = renderer.render(&scope, &tree);
+
+        assert!(output.contains("  - Memories: mem-1, mem-2"));
+    }
+
     #[test]
     fn test_render_includes_skeleton() {
         let renderer = ContextRenderer::new();
@@ -217,6 +513,36 @@ mod tests {
         assert!(output.contains("src/"));
     }
 
+    #[test]
+    fn test_render_sections() {
+        let renderer = ContextRenderer::new();
+        let scope = create_test_scope();
+        let tree = Tree::new(PathBuf::from("/test/project"));
+
+        let rendered = renderer.render_sections(&scope, &tree);
+        let titles: Vec<&str> = rendered.sections.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(
+            titles,
+            vec![
+                "Rules",
+                "Recent Decisions",
+                "Focus Area",
+                "Project Structure"
+            ]
+        );
+        // Focus nodes missing from the tree are not claimed as sources
+        assert!(rendered.sections[2].source_nodes.is_empty());
+        assert_eq!(
+            rendered.tokens(),
+            rendered
+                .sections
+                .iter()
+                .map(|s| s.text.len().div_ceil(4))
+                .sum::<usize>()
+        );
+        assert_eq!(rendered.to_text(), renderer.render(&scope, &tree));
+    }
+
     #[test]
     fn test_render_compact() {
         let renderer = ContextRenderer::new();
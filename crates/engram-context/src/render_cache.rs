@@ -0,0 +1,223 @@
+//! Cache of rendered context strings.
+//!
+//! Entries are keyed by the tree generation, a hash of the scope content and
+//! a hash of the render options, so a rebuilt tree or a changed scope never
+//! serves stale output. Hit/miss/eviction counters are tracked per project.
+
+use crate::render::ContextRenderer;
+use crate::scope::ContextScope;
+use engram_indexer::tree::Tree;
+use parking_lot::RwLock;
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+
+/// Default number of rendered contexts kept per project.
+const DEFAULT_ENTRIES_PER_PROJECT: usize = 16;
+
+/// Cache key for one rendered context.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RenderKey {
+    /// Generation of the tree the context was rendered from
+    pub tree_generation: u64,
+    /// Hash of the scope content (excluding its random id)
+    pub scope_hash: u64,
+    /// Hash of the renderer options
+    pub options_hash: u64,
+}
+
+impl RenderKey {
+    /// Build the key for rendering `scope` against `tree` with `renderer`.
+    pub fn new(tree: &Tree, scope: &ContextScope, renderer: &ContextRenderer) -> Self {
+        Self {
+            tree_generation: tree.generation(),
+            scope_hash: scope.content_hash(),
+            options_hash: renderer.options_hash(),
+        }
+    }
+}
+
+/// Cache counters for one project.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RenderCacheStats {
+    pub project: PathBuf,
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    /// Entries currently cached
+    pub entries: usize,
+}
+
+#[derive(Default)]
+struct ProjectCache {
+    entries: HashMap<RenderKey, String>,
+    /// Insertion order, oldest first
+    order: VecDeque<RenderKey>,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+}
+
+/// Per-project cache of rendered context.
+pub struct RenderCache {
+    projects: RwLock<HashMap<PathBuf, ProjectCache>>,
+    max_entries: usize,
+}
+
+impl RenderCache {
+    /// Create a cache with the default per-project capacity.
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_ENTRIES_PER_PROJECT)
+    }
+
+    /// Create a cache holding at most `max_entries` renders per project.
+    pub fn with_capacity(max_entries: usize) -> Self {
+        Self {
+            projects: RwLock::new(HashMap::new()),
+            max_entries: max_entries.max(1),
+        }
+    }
+
+    /// Look up a rendered context, counting a hit or miss.
+    pub fn get(&self, project: &Path, key: &RenderKey) -> Option<String> {
+        let mut projects = self.projects.write();
+        let cache = projects.entry(project.to_path_buf()).or_default();
+        match cache.entries.get(key) {
+            Some(context) => {
+                cache.hits += 1;
+                Some(context.clone())
+            }
+            None => {
+                cache.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Store a rendered context.
+    ///
+    /// Entries rendered from an older tree generation are dropped, and the
+    /// oldest entries are evicted once the project is over capacity.
+    pub fn insert(&self, project: &Path, key: RenderKey, context: String) {
+        let mut projects = self.projects.write();
+        let cache = projects.entry(project.to_path_buf()).or_default();
+
+        let stale: Vec<RenderKey> = cache
+            .order
+            .iter()
+            .filter(|k| k.tree_generation != key.tree_generation)
+            .copied()
+            .collect();
+        for stale_key in stale {
+            cache.remove(&stale_key);
+        }
+
+        if cache.entries.insert(key, context).is_none() {
+            cache.order.push_back(key);
+        }
+
+        while cache.order.len() > self.max_entries {
+            if let Some(oldest) = cache.order.front().copied() {
+                cache.remove(&oldest);
+            }
+        }
+    }
+
+    /// Drop all cached renders for a project (counters are kept).
+    pub fn invalidate(&self, project: &Path) {
+        if let Some(cache) = self.projects.write().get_mut(project) {
+            cache.evictions += cache.entries.len() as u64;
+            cache.entries.clear();
+            cache.order.clear();
+        }
+    }
+
+    /// Counters for every project, sorted by path.
+    pub fn stats(&self) -> Vec<RenderCacheStats> {
+        let mut stats: Vec<RenderCacheStats> = self
+            .projects
+            .read()
+            .iter()
+            .map(|(project, cache)| RenderCacheStats {
+                project: project.clone(),
+                hits: cache.hits,
+                misses: cache.misses,
+                evictions: cache.evictions,
+                entries: cache.entries.len(),
+            })
+            .collect();
+        stats.sort_by(|a, b| a.project.cmp(&b.project));
+        stats
+    }
+}
+
+impl ProjectCache {
+    fn remove(&mut self, key: &RenderKey) {
+        if self.entries.remove(key).is_some() {
+            self.evictions += 1;
+        }
+        self.order.retain(|k| k != key);
+    }
+}
+
+impl Default for RenderCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(tree_generation: u64, scope_hash: u64) -> RenderKey {
+        RenderKey {
+            tree_generation,
+            scope_hash,
+            options_hash: 0,
+        }
+    }
+
+    #[test]
+    fn test_hit_and_miss_counters() {
+        let cache = RenderCache::new();
+        let project = Path::new("/project");
+
+        assert!(cache.get(project, &key(1, 1)).is_none());
+        cache.insert(project, key(1, 1), "ctx".to_string());
+        assert_eq!(cache.get(project, &key(1, 1)).as_deref(), Some("ctx"));
+
+        let stats = cache.stats();
+        assert_eq!(stats.len(), 1);
+        assert_eq!((stats[0].hits, stats[0].misses), (1, 1));
+        assert_eq!(stats[0].entries, 1);
+    }
+
+    #[test]
+    fn test_new_generation_evicts_stale_entries() {
+        let cache = RenderCache::new();
+        let project = Path::new("/project");
+
+        cache.insert(project, key(1, 1), "old".to_string());
+        cache.insert(project, key(1, 2), "old".to_string());
+        cache.insert(project, key(2, 1), "new".to_string());
+
+        assert!(cache.get(project, &key(1, 1)).is_none());
+        let stats = &cache.stats()[0];
+        assert_eq!(stats.evictions, 2);
+        assert_eq!(stats.entries, 1);
+    }
+
+    #[test]
+    fn test_capacity_evicts_oldest() {
+        let cache = RenderCache::with_capacity(2);
+        let project = Path::new("/project");
+
+        cache.insert(project, key(1, 1), "a".to_string());
+        cache.insert(project, key(1, 2), "b".to_string());
+        cache.insert(project, key(1, 3), "c".to_string());
+
+        assert!(cache.get(project, &key(1, 1)).is_none());
+        assert!(cache.get(project, &key(1, 3)).is_some());
+        assert_eq!(cache.stats()[0].evictions, 1);
+    }
+}
@@ -1,14 +1,25 @@
 //! Hybrid retrieval router for context queries.
 //!
 //! Routes queries to appropriate indexes (tree-based or semantic)
-//! based on query intent classification.
+//! based on query intent classification. Each result records why it
+//! matched, so poor retrieval can be traced to the signal behind it.
 
+use crate::calibration::Calibration;
+use crate::relevance::Relevance;
 use crate::scope::ContextScope;
+use crate::vector::VectorIndex;
 use engram_indexer::tree::{NodeId, Tree};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tracing::debug;
 
+/// Number of nearest neighbors taken from the vector index.
+const VECTOR_RESULTS: usize = 10;
+
+/// Score multiplier for results in the scope's hot nodes.
+const HOT_NODE_BOOST: f32 = 1.25;
+
 /// Query intent classification.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum QueryIntent {
@@ -31,6 +42,26 @@ pub struct RetrievalResult {
     pub source: ResultSource,
     /// Snippet of matching content
     pub snippet: Option<String>,
+    /// Why the result matched, in the order the signals were applied
+    #[serde(default)]
+    pub reasons: Vec<MatchReason>,
+}
+
+/// A signal contributing to a retrieval result.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MatchReason {
+    /// The node's name matched a name in the query
+    NameMatch { name: String },
+    /// The node imports the node named in the query
+    DependencyEdge { target: NodeId },
+    /// Cosine similarity (-1 to 1) of the node's text to the query
+    VectorSimilarity { similarity: f32 },
+    /// Calibration weight applied to results from the node's source
+    Calibration { weight: f32 },
+    /// Weight learned from past outcomes of work on the node's file
+    Relevance { weight: f32 },
+    /// Boost for a node in, or in a file among, the scope's hot nodes
+    HotNode { boost: f32 },
 }
 
 /// Source of a retrieval result.
@@ -50,7 +81,12 @@ pub struct HybridRouter {
     tree: Arc<Tree>,
     /// Query classifier
     classifier: QueryClassifier,
-    // Future: vector_index: Option<VectorIndex>,
+    /// Per-project re-ranking calibration
+    calibration: Calibration,
+    /// Per-file weights learned from agent outcomes
+    relevance: Relevance,
+    /// Semantic index (semantic queries fall back to the tree without one)
+    vector_index: Option<Arc<VectorIndex>>,
 }
 
 impl HybridRouter {
@@ -59,29 +95,110 @@ impl HybridRouter {
         Self {
             tree,
             classifier: QueryClassifier::new(),
+            calibration: Calibration::default(),
+            relevance: Relevance::default(),
+            vector_index: None,
         }
     }
 
+    /// Re-rank results with a project's calibration.
+    pub fn with_calibration(mut self, calibration: Calibration) -> Self {
+        self.calibration = calibration;
+        self
+    }
+
+    /// Prefer nodes in files whose past changes worked out.
+    pub fn with_relevance(mut self, relevance: Relevance) -> Self {
+        self.relevance = relevance;
+        self
+    }
+
+    /// Answer semantic queries from a vector index.
+    pub fn with_vector_index(mut self, index: Arc<VectorIndex>) -> Self {
+        self.vector_index = Some(index);
+        self
+    }
+
     /// Query the indexes based on intent classification.
+    ///
+    /// Results among `scope.horizon.hot_nodes`, or in files among them, are
+    /// boosted.
     pub fn query(&self, q: &str, scope: &ContextScope) -> Vec<RetrievalResult> {
+        let mut results = self.query_unranked(q, scope);
+        let hot: HashSet<NodeId> = scope.horizon.hot_nodes.iter().copied().collect();
+        for result in &mut results {
+            let weight = self.calibration.weight(&result.source);
+            result.score = (result.score * weight).clamp(0.0, 1.0);
+            if (weight - 1.0).abs() > f32::EPSILON {
+                result.reasons.push(MatchReason::Calibration { weight });
+            }
+            if !self.relevance.is_empty() {
+                let weight = self
+                    .file_path(result.node_id)
+                    .map_or(1.0, |path| self.relevance.weight(path));
+                if (weight - 1.0).abs() > f32::EPSILON {
+                    result.score = (result.score * weight).clamp(0.0, 1.0);
+                    result.reasons.push(MatchReason::Relevance { weight });
+                }
+            }
+            let is_hot = hot.contains(&result.node_id)
+                || self
+                    .file_node(result.node_id)
+                    .is_some_and(|id| hot.contains(&id));
+            if is_hot {
+                result.score = (result.score * HOT_NODE_BOOST).clamp(0.0, 1.0);
+                result.reasons.push(MatchReason::HotNode {
+                    boost: HOT_NODE_BOOST,
+                });
+            }
+        }
+        results.sort_by(|a, b| b.score.total_cmp(&a.score));
+        results
+    }
+
+    fn query_unranked(&self, q: &str, scope: &ContextScope) -> Vec<RetrievalResult> {
         let intent = self.classifier.classify(q);
         debug!(query = %q, intent = ?intent, "Query classified");
 
         match intent {
-            QueryIntent::Structural => self.query_tree(q, scope),
-            QueryIntent::Semantic => {
-                // Future: self.query_vector(q, scope)
-                // For now, fall back to tree search
-                self.query_tree(q, scope)
+            QueryIntent::Structural => {
+                // Plain keyword queries classify as structural; without a
+                // structural match they are still worth a semantic lookup
+                let results = self.query_tree(q, scope);
+                if results.is_empty() {
+                    self.query_vector(q, scope)
+                } else {
+                    results
+                }
             }
+            QueryIntent::Semantic if self.vector_index.is_some() => self.query_vector(q, scope),
+            QueryIntent::Semantic => self.query_tree(q, scope),
             QueryIntent::Hybrid => {
-                let tree_results = self.query_tree(q, scope);
-                // Future: merge with vector results
-                tree_results
+                merge_results(self.query_tree(q, scope), self.query_vector(q, scope))
             }
         }
     }
 
+    /// Query the vector index for semantically similar nodes.
+    fn query_vector(&self, q: &str, _scope: &ContextScope) -> Vec<RetrievalResult> {
+        let Some(index) = &self.vector_index else {
+            return Vec::new();
+        };
+
+        index
+            .search(q, VECTOR_RESULTS)
+            .into_iter()
+            .map(|(node_id, similarity)| RetrievalResult {
+                node_id,
+                // Cosine similarity mapped from [-1, 1] to [0, 1]
+                score: (similarity + 1.0) / 2.0,
+                source: ResultSource::Vector,
+                snippet: self.tree.content(node_id).and_then(|c| c.summary.clone()),
+                reasons: vec![MatchReason::VectorSimilarity { similarity }],
+            })
+            .collect()
+    }
+
     /// Query the tree index for structural information.
     fn query_tree(&self, q: &str, _scope: &ContextScope) -> Vec<RetrievalResult> {
         let q_lower = q.to_lowercase();
@@ -100,6 +217,7 @@ impl HybridRouter {
                             score: 1.0 - (i as f32 * 0.1).min(0.9),
                             source: ResultSource::Tree,
                             snippet: None,
+                            reasons: vec![MatchReason::DependencyEdge { target: node_id }],
                         });
                     }
                 }
@@ -115,6 +233,7 @@ impl HybridRouter {
                         score: 1.0,
                         source: ResultSource::Tree,
                         snippet: None,
+                        reasons: vec![MatchReason::NameMatch { name: target }],
                     });
                 }
             }
@@ -123,6 +242,22 @@ impl HybridRouter {
         results
     }
 
+    /// Path of the file a node is in (the node's own path for files).
+    fn file_path(&self, node_id: NodeId) -> Option<&std::path::Path> {
+        Some(&self.tree.get(self.file_node(node_id)?)?.path)
+    }
+
+    /// The file node a node is in (the node itself for files).
+    fn file_node(&self, mut node_id: NodeId) -> Option<NodeId> {
+        loop {
+            let node = self.tree.get(node_id)?;
+            if !node.is_symbol() {
+                return Some(node_id);
+            }
+            node_id = node.parent?;
+        }
+    }
+
     /// Find nodes that import a given node.
     pub fn find_importers(&self, node_id: NodeId) -> Vec<NodeId> {
         self.tree.dependencies.imported_by(node_id).collect()
@@ -172,6 +307,65 @@ impl HybridRouter {
     }
 }
 
+/// Min-max normalize scores to [0, 1], keeping the best score per node.
+fn normalize(results: Vec<RetrievalResult>) -> HashMap<NodeId, RetrievalResult> {
+    let (min, max) = results.iter().fold((f32::MAX, f32::MIN), |(lo, hi), r| {
+        (lo.min(r.score), hi.max(r.score))
+    });
+    let range = max - min;
+
+    let mut by_node: HashMap<NodeId, RetrievalResult> = HashMap::new();
+    for mut result in results {
+        result.score = if range > f32::EPSILON {
+            (result.score - min) / range
+        } else {
+            1.0
+        };
+        match by_node.get(&result.node_id) {
+            Some(existing) if existing.score >= result.score => {}
+            _ => {
+                by_node.insert(result.node_id, result);
+            }
+        }
+    }
+    by_node
+}
+
+/// Merge tree and vector results after normalizing each list.
+///
+/// Each source contributes half of the final score, so nodes found by both
+/// outrank nodes found by only one.
+fn merge_results(tree: Vec<RetrievalResult>, vector: Vec<RetrievalResult>) -> Vec<RetrievalResult> {
+    let mut merged = normalize(tree);
+    for result in merged.values_mut() {
+        result.score /= 2.0;
+    }
+
+    for (node_id, result) in normalize(vector) {
+        match merged.get_mut(&node_id) {
+            Some(existing) => {
+                existing.score += result.score / 2.0;
+                existing.source = ResultSource::Merged;
+                if existing.snippet.is_none() {
+                    existing.snippet = result.snippet;
+                }
+                existing.reasons.extend(result.reasons);
+            }
+            None => {
+                merged.insert(
+                    node_id,
+                    RetrievalResult {
+                        score: result.score / 2.0,
+                        ..result
+                    },
+                );
+            }
+        }
+    }
+
+    merged.into_values().collect()
+}
+
 /// Query intent classifier.
 pub struct QueryClassifier {
     structural_patterns: Vec<&'static str>,
@@ -310,6 +504,158 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_calibration_scales_scores() {
+        let tree = Arc::new(Tree::new(std::path::PathBuf::from("/test")));
+        let scope = ContextScope::new(std::path::PathBuf::from("/test"));
+        let mut calibration = Calibration::default();
+        calibration.weights.tree = 0.5;
+
+        let router = HybridRouter::new(tree).with_calibration(calibration);
+        let results = router.query("Find `test`", &scope);
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].score, 0.5);
+        assert_eq!(
+            results[0].reasons,
+            vec![
+                MatchReason::NameMatch {
+                    name: "test".to_string()
+                },
+                MatchReason::Calibration { weight: 0.5 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_relevance_scales_scores() {
+        let tree = Arc::new(Tree::new(std::path::PathBuf::from("/test")));
+        let scope = ContextScope::new(std::path::PathBuf::from("/test"));
+        let node_id = HybridRouter::new(tree.clone()).query("Find `test`", &scope)[0].node_id;
+
+        // Past changes to the file were reverted
+        let mut relevance = Relevance::default();
+        let path = tree.get(node_id).unwrap().path.clone();
+        relevance.record(&[path], 0.0, chrono::Utc::now().timestamp());
+
+        let router = HybridRouter::new(tree).with_relevance(relevance);
+        let results = router.query("Find `test`", &scope);
+        assert!((results[0].score - 5.0 / 6.0).abs() < 1e-3);
+        assert!(matches!(
+            results[0].reasons.last(),
+            Some(MatchReason::Relevance { weight }) if *weight < 1.0
+        ));
+    }
+
+    #[test]
+    fn test_hot_nodes_boost_scores() {
+        let tree = Arc::new(Tree::new(std::path::PathBuf::from("/test")));
+        let mut scope = ContextScope::new(std::path::PathBuf::from("/test"));
+        let mut calibration = Calibration::default();
+        calibration.weights.tree = 0.5;
+        let router = HybridRouter::new(tree).with_calibration(calibration);
+        let node_id = router.query("Find `test`", &scope)[0].node_id;
+
+        scope.horizon.hot_nodes = vec![node_id];
+        let results = router.query("Find `test`", &scope);
+        assert_eq!(results[0].score, 0.5 * HOT_NODE_BOOST);
+        assert_eq!(
+            results[0].reasons.last(),
+            Some(&MatchReason::HotNode {
+                boost: HOT_NODE_BOOST
+            })
+        );
+    }
+
+    fn result(node_id: NodeId, score: f32, source: ResultSource) -> RetrievalResult {
+        RetrievalResult {
+            node_id,
+            score,
+            source,
+            snippet: None,
+            reasons: vec![],
+        }
+    }
+
+    #[test]
+    fn test_merge_normalizes_and_boosts_overlap() {
+        let tree = vec![
+            result(1, 1.0, ResultSource::Tree),
+            result(2, 0.9, ResultSource::Tree),
+        ];
+        let vector = vec![
+            result(2, 0.8, ResultSource::Vector),
+            result(3, 0.6, ResultSource::Vector),
+        ];
+
+        let mut merged = merge_results(tree, vector);
+        merged.sort_by_key(|r| r.node_id);
+
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged[0].score, 0.5);
+        assert_eq!(merged[1].source, ResultSource::Merged);
+        assert_eq!(merged[1].score, 0.5);
+        assert_eq!(merged[2].score, 0.0);
+    }
+
+    #[test]
+    fn test_queries_use_vector_index() {
+        use engram_indexer::scanner::{Language, ScanResult, ScannedFile, Symbol, SymbolKind};
+        use engram_indexer::tree::TreeBuilder;
+
+        let tree = TreeBuilder::new().build(&ScanResult {
+            root: std::path::PathBuf::from("/test"),
+            files: vec![ScannedFile {
+                path: std::path::PathBuf::from("src/cache.rs"),
+                language: Some(Language::Rust),
+                size: 10,
+                hash: "h".to_string(),
+                line_count: 10,
+                symbols: vec![Symbol {
+                    name: "evict_oldest".to_string(),
+                    kind: SymbolKind::Function,
+                    start_line: 1,
+                    end_line: 5,
+                    parent: None,
+                    doc: Some("Drops least recently used cache entry".to_string()),
+                }],
+                imports: vec![],
+                truncated: false,
+                asset: None,
+            }],
+            languages: vec![Language::Rust],
+            frameworks: vec![],
+            duration_ms: 0,
+            skipped_count: 0,
+            excluded_dirs: vec![],
+        });
+        let index = Arc::new(VectorIndex::build(&tree));
+        let tree = Arc::new(tree);
+        let scope = ContextScope::new(std::path::PathBuf::from("/test"));
+        let router = HybridRouter::new(tree.clone()).with_vector_index(index);
+
+        let results = router.query("How does least recently used eviction work?", &scope);
+        assert_eq!(results[0].source, ResultSource::Vector);
+        assert_eq!(tree.get(results[0].node_id).unwrap().name, "evict_oldest");
+        assert!(results[0].snippet.is_some());
+
+        let results = router.query("Where is `evict_oldest` and how does it work?", &scope);
+        assert_eq!(results[0].source, ResultSource::Merged);
+        assert_eq!(tree.get(results[0].node_id).unwrap().name, "evict_oldest");
+        assert!(matches!(
+            results[0].reasons.as_slice(),
+            [
+                MatchReason::NameMatch { .. },
+                MatchReason::VectorSimilarity { similarity }
+            ] if *similarity > 0.0
+        ));
+
+        // Keyword queries classify as structural but fall back to vectors
+        let results = router.query("least recently used entry", &scope);
+        assert_eq!(results[0].source, ResultSource::Vector);
+        assert_eq!(tree.get(results[0].node_id).unwrap().name, "evict_oldest");
+    }
+
     #[test]
     fn test_result_source() {
         let result = RetrievalResult {
@@ -317,6 +663,7 @@ mod tests {
             score: 0.95,
             source: ResultSource::Tree,
             snippet: Some("test".to_string()),
+            reasons: vec![],
         };
 
         assert_eq!(result.source, ResultSource::Tree);
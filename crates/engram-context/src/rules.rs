@@ -0,0 +1,314 @@
+//! Project rules injected into the anchor layer.
+//!
+//! Rules are read from the Markdown and YAML files in `.engram/rules/`, and
+//! from `.engram/rules.md`, `.engram/guidelines.md` and `CONTRIBUTING.md`.
+//! A Markdown file may open with a YAML frontmatter scoping each of its
+//! bullets:
+//!
+//! ```markdown
+//! ---
+//! paths: ["src/api/**"]
+//! priority: 10
+//! category: security
+//! ---
+//! - Validate every request body before touching storage
+//! ```
+//!
+//! A YAML file lists rules under `rules:`, each with its own `text`,
+//! `paths`, `priority` and `category`. Rules without `paths` apply to the
+//! whole project; scoped rules are only injected when a focus path matches
+//! one of their globs. Markdown files without a frontmatter contribute the
+//! bullets of their first lines as project-wide rules.
+
+use engram_indexer::tree::compile_globs;
+use globset::GlobSet;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Directory of structured rules files, relative to the project root
+pub const RULES_DIR: &str = ".engram/rules";
+
+/// Category of rules that do not name one
+pub const DEFAULT_RULE_CATEGORY: &str = "general";
+
+/// Single rules files, relative to the project root
+const RULE_FILES: &[&str] = &[
+    ".engram/rules.md",
+    ".engram/guidelines.md",
+    "CONTRIBUTING.md",
+];
+
+/// Lines of a Markdown file without frontmatter searched for bullets
+const UNSCOPED_RULE_LINES: usize = 20;
+
+/// One project rule.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rule {
+    pub text: String,
+    /// Globs of the files the rule applies to, relative to the project
+    /// root; empty for every file
+    #[serde(default)]
+    pub paths: Vec<String>,
+    /// Higher priorities are injected first
+    #[serde(default)]
+    pub priority: i32,
+    #[serde(default = "default_category")]
+    pub category: String,
+}
+
+impl Rule {
+    /// Line of the anchor's rules section.
+    pub fn render(&self) -> String {
+        if self.category == DEFAULT_RULE_CATEGORY {
+            format!("- {}", self.text)
+        } else {
+            format!("- [{}] {}", self.category, self.text)
+        }
+    }
+}
+
+/// Scope shared by the rules of a Markdown file
+#[derive(Debug, Deserialize)]
+struct Frontmatter {
+    #[serde(default)]
+    paths: Vec<String>,
+    #[serde(default)]
+    priority: i32,
+    #[serde(default = "default_category")]
+    category: String,
+}
+
+impl Default for Frontmatter {
+    fn default() -> Self {
+        Self {
+            paths: Vec::new(),
+            priority: 0,
+            category: default_category(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RulesFile {
+    #[serde(default)]
+    rules: Vec<Rule>,
+}
+
+fn default_category() -> String {
+    DEFAULT_RULE_CATEGORY.to_string()
+}
+
+/// The rules of a project, with their compiled path globs.
+#[derive(Debug, Default)]
+pub struct RuleSet {
+    rules: Vec<(Rule, Option<GlobSet>)>,
+}
+
+impl RuleSet {
+    /// Create an empty rule set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the rules of a project. Unreadable files and rules with
+    /// invalid globs are skipped with a warning.
+    pub async fn load(project_path: &Path) -> Self {
+        let mut files: Vec<PathBuf> = Vec::new();
+        if let Ok(mut entries) = tokio::fs::read_dir(project_path.join(RULES_DIR)).await {
+            while let Ok(Some(entry)) = entries.next_entry().await {
+                files.push(entry.path());
+            }
+        }
+        files.sort();
+        files.extend(RULE_FILES.iter().map(|file| project_path.join(file)));
+
+        let mut set = Self::new();
+        for path in files {
+            let extension = path.extension().and_then(|ext| ext.to_str());
+            if !matches!(extension, Some("md" | "yaml" | "yml")) {
+                continue;
+            }
+            let Ok(content) = tokio::fs::read_to_string(&path).await else {
+                continue;
+            };
+            let rules = if extension == Some("md") {
+                parse_markdown(&content)
+            } else {
+                parse_yaml(&content)
+            };
+            match rules {
+                Ok(rules) => rules.into_iter().for_each(|rule| set.add(rule)),
+                Err(e) => warn!(path = ?path, error = %e, "Skipping unreadable rules file"),
+            }
+        }
+        set
+    }
+
+    /// Add a rule. A rule with an invalid glob is skipped with a warning.
+    pub fn add(&mut self, rule: Rule) {
+        let globs = if rule.paths.is_empty() {
+            None
+        } else {
+            match compile_globs(&rule.paths) {
+                Ok(globs) => Some(globs),
+                Err(e) => {
+                    warn!(rule = %rule.text, error = %e, "Skipping rule with invalid paths");
+                    return;
+                }
+            }
+        };
+        self.rules.push((rule, globs));
+    }
+
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Rules applying to any of `focus` (paths relative to the project
+    /// root), highest priority first; ties keep their load order.
+    pub fn relevant(&self, focus: &[PathBuf]) -> Vec<&Rule> {
+        let mut rules: Vec<&Rule> = self
+            .rules
+            .iter()
+            .filter(|(_, globs)| match globs {
+                None => true,
+                Some(globs) => focus.iter().any(|path| globs.is_match(path)),
+            })
+            .map(|(rule, _)| rule)
+            .collect();
+        rules.sort_by_key(|rule| std::cmp::Reverse(rule.priority));
+        rules
+    }
+}
+
+/// Rules of a Markdown file: each bullet, scoped by the frontmatter.
+fn parse_markdown(content: &str) -> Result<Vec<Rule>, serde_yaml::Error> {
+    let (scope, body, lines) = match split_frontmatter(content) {
+        Some((yaml, body)) => (
+            serde_yaml::from_str::<Option<Frontmatter>>(yaml)?.unwrap_or_default(),
+            body,
+            usize::MAX,
+        ),
+        None => (Frontmatter::default(), content, UNSCOPED_RULE_LINES),
+    };
+
+    Ok(body
+        .lines()
+        .take(lines)
+        .filter_map(|line| line.trim().strip_prefix(['-', '*']))
+        .map(str::trim)
+        .filter(|text| !text.is_empty() && !text.starts_with(['-', '*']))
+        .map(|text| Rule {
+            text: text.to_string(),
+            paths: scope.paths.clone(),
+            priority: scope.priority,
+            category: scope.category.clone(),
+        })
+        .collect())
+}
+
+fn parse_yaml(content: &str) -> Result<Vec<Rule>, serde_yaml::Error> {
+    Ok(serde_yaml::from_str::<Option<RulesFile>>(content)?
+        .map(|file| file.rules)
+        .unwrap_or_default())
+}
+
+/// Split a leading `---` delimited frontmatter from the body.
+fn split_frontmatter(content: &str) -> Option<(&str, &str)> {
+    let rest = content
+        .strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))?;
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            return Some((&rest[..offset], &rest[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_markdown() {
+        let rules = parse_markdown(
+            "---\npaths: [\"src/api/**\"]\npriority: 5\ncategory: security\n---\n\
+             # API\n- Validate request bodies\n* Never log tokens\n",
+        )
+        .unwrap();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].text, "Validate request bodies");
+        assert_eq!(rules[1].paths, vec!["src/api/**"]);
+        assert_eq!(rules[1].priority, 5);
+        assert_eq!(rules[1].render(), "- [security] Never log tokens");
+
+        // Without frontmatter, the first lines' bullets apply everywhere
+        let mut content = String::from("# Contributing\n---\n- Run the tests\n");
+        content.push_str(&"text\n".repeat(UNSCOPED_RULE_LINES));
+        content.push_str("- Too far down\n");
+        let rules = parse_markdown(&content).unwrap();
+        assert_eq!(rules.len(), 1);
+        assert!(rules[0].paths.is_empty());
+        assert_eq!(rules[0].render(), "- Run the tests");
+    }
+
+    #[tokio::test]
+    async fn test_relevant_rules() {
+        let dir = tempdir().unwrap();
+        let rules_dir = dir.path().join(RULES_DIR);
+        std::fs::create_dir_all(&rules_dir).unwrap();
+        std::fs::write(
+            rules_dir.join("api.yaml"),
+            "rules:\n\
+             - text: Return typed errors\n  paths: [\"src/api/**\"]\n\
+             - text: Check authorization first\n  paths: [\"src/api/**\"]\n  priority: 10\n  category: security\n\
+             - text: Keep migrations reversible\n  paths: [\"migrations/*.sql\"]\n",
+        )
+        .unwrap();
+        std::fs::write(rules_dir.join("broken.yaml"), "rules: [").unwrap();
+        std::fs::write(dir.path().join("CONTRIBUTING.md"), "- Write tests\n").unwrap();
+
+        let set = RuleSet::load(dir.path()).await;
+        assert_eq!(set.len(), 4);
+
+        let texts = |focus: &[&str]| -> Vec<String> {
+            let focus: Vec<PathBuf> = focus.iter().map(PathBuf::from).collect();
+            set.relevant(&focus)
+                .into_iter()
+                .map(|rule| rule.text.clone())
+                .collect()
+        };
+        assert_eq!(texts(&[]), vec!["Write tests"]);
+        assert_eq!(
+            texts(&["src/api/users.rs"]),
+            vec![
+                "Check authorization first",
+                "Return typed errors",
+                "Write tests"
+            ]
+        );
+        assert_eq!(
+            texts(&["migrations/001.sql", "README.md"]),
+            vec!["Keep migrations reversible", "Write tests"]
+        );
+
+        // Invalid globs drop the rule
+        let mut set = RuleSet::new();
+        set.add(Rule {
+            text: "broken".to_string(),
+            paths: vec!["src/[".to_string()],
+            priority: 0,
+            category: default_category(),
+        });
+        assert!(set.is_empty());
+    }
+}
@@ -5,6 +5,8 @@
 
 use engram_indexer::tree::NodeId;
 use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::path::PathBuf;
 
 /// A complete context scope for an agent session.
@@ -22,18 +24,41 @@ pub struct ContextScope {
     pub horizon: HorizonContext,
     /// Creation timestamp
     pub created_at: i64,
+    /// Agent the scope was created for (sub-agents only)
+    #[serde(default)]
+    pub agent_id: Option<String>,
+    /// Memory is read-only for this agent; its writes are staged for
+    /// approval by the parent agent
+    #[serde(default)]
+    pub read_only_memory: bool,
+    /// When the scope was last created, read or expanded; scopes expire a
+    /// TTL after this
+    #[serde(default)]
+    pub last_used: i64,
+    /// Scope this one was forked from, for parallel sub-agents
+    #[serde(default)]
+    pub parent_id: Option<String>,
+    /// Agent session the scope was created in
+    #[serde(default)]
+    pub session_id: Option<String>,
 }
 
 impl ContextScope {
     /// Create a new context scope.
     pub fn new(project_path: PathBuf) -> Self {
+        let now = chrono::Utc::now().timestamp();
         Self {
             id: uuid::Uuid::new_v4().to_string(),
             project_path,
             anchor: AnchorContext::default(),
             focus: FocusContext::default(),
             horizon: HorizonContext::default(),
-            created_at: chrono::Utc::now().timestamp(),
+            created_at: now,
+            agent_id: None,
+            read_only_memory: false,
+            last_used: now,
+            parent_id: None,
+            session_id: None,
         }
     }
 
@@ -42,19 +67,46 @@ impl ContextScope {
         let mut nodes = self.focus.primary_nodes.clone();
         nodes.extend(self.focus.auto_loaded.iter().cloned());
         nodes.extend(self.focus.expanded.iter().cloned());
+        nodes.extend(self.focus.tests.iter().cloned());
         nodes
     }
+
+    /// Hash of the scope content, ignoring its id and creation time.
+    ///
+    /// Two scopes with equal hashes render identically against the same tree.
+    pub fn content_hash(&self) -> u64 {
+        let content = serde_json::to_vec(&(
+            &self.project_path,
+            &self.anchor,
+            &self.focus,
+            &self.horizon,
+            self.read_only_memory,
+        ))
+        .unwrap_or_default();
+        let mut hasher = DefaultHasher::new();
+        content.hash(&mut hasher);
+        hasher.finish()
+    }
 }
 
 /// Layer 1: Anchor context - immutable project-level information.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AnchorContext {
+    /// Project brief: architecture, conventions, glossary
+    #[serde(default)]
+    pub brief: Option<String>,
     /// Project rules and guidelines
     pub rules: Vec<String>,
     /// Recent agent experiences/decisions
     pub experiences: Vec<Experience>,
     /// Constraints from parent agent
     pub constraints: Vec<String>,
+    /// Files and symbols changed since the last session, one line each
+    #[serde(default)]
+    pub changes: Vec<String>,
+    /// Summary of the last session that ended
+    #[serde(default)]
+    pub previous_session: Option<String>,
 }
 
 /// Layer 2: Focus context - mutable working area.
@@ -66,6 +118,9 @@ pub struct FocusContext {
     pub auto_loaded: Vec<NodeId>,
     /// User-expanded nodes
     pub expanded: Vec<NodeId>,
+    /// Test files of the primary files
+    #[serde(default)]
+    pub tests: Vec<NodeId>,
 }
 
 impl FocusContext {
@@ -74,6 +129,7 @@ impl FocusContext {
         let mut nodes = self.primary_nodes.clone();
         nodes.extend(self.auto_loaded.iter().cloned());
         nodes.extend(self.expanded.iter().cloned());
+        nodes.extend(self.tests.iter().cloned());
         nodes
     }
 }
@@ -104,6 +160,15 @@ pub struct Experience {
     pub files_touched: Vec<PathBuf>,
     /// Outcome of the decision
     pub outcome: Option<Outcome>,
+    /// Stable identifier (empty for records written before ids existed)
+    #[serde(default)]
+    pub id: String,
+    /// Memory entries recorded about the same work
+    #[serde(default)]
+    pub memory_ids: Vec<String>,
+    /// Number of experiences this entry summarizes (0 for a single decision)
+    #[serde(default)]
+    pub summarizes: usize,
 }
 
 impl Experience {
@@ -117,9 +182,24 @@ impl Experience {
             rationale: None,
             files_touched: vec![],
             outcome: None,
+            id: uuid::Uuid::new_v4().to_string(),
+            memory_ids: vec![],
+            summarizes: 0,
         }
     }
 
+    /// Use a caller-provided id.
+    pub fn with_id(mut self, id: impl Into<String>) -> Self {
+        self.id = id.into();
+        self
+    }
+
+    /// Link memory entries to the experience.
+    pub fn with_memory_ids(mut self, memory_ids: Vec<String>) -> Self {
+        self.memory_ids = memory_ids;
+        self
+    }
+
     /// Add rationale to the experience.
     pub fn with_rationale(mut self, rationale: impl Into<String>) -> Self {
         self.rationale = Some(rationale.into());
@@ -150,6 +230,20 @@ pub enum Outcome {
     Reverted,
 }
 
+impl Outcome {
+    /// How well the decision turned out, from 0 (undone) to 1 (worked).
+    ///
+    /// A failure scores a little above a revert: the change stayed, but
+    /// needed follow-up work.
+    pub fn grade(&self) -> f32 {
+        match self {
+            Outcome::Success => 1.0,
+            Outcome::Failure { .. } => 0.25,
+            Outcome::Reverted => 0.0,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -162,15 +256,29 @@ mod tests {
         assert!(scope.anchor.rules.is_empty());
     }
 
+    #[test]
+    fn test_content_hash_ignores_id() {
+        let a = ContextScope::new(PathBuf::from("/test/project"));
+        let mut b = ContextScope::new(PathBuf::from("/test/project"));
+        b.created_at = a.created_at + 10;
+        assert_ne!(a.id, b.id);
+        assert_eq!(a.content_hash(), b.content_hash());
+
+        b.anchor.rules.push("- rule".to_string());
+        assert_ne!(a.content_hash(), b.content_hash());
+    }
+
     #[test]
     fn test_focus_all_nodes() {
-        let mut focus = FocusContext::default();
-        focus.primary_nodes = vec![1, 2];
-        focus.auto_loaded = vec![3, 4];
-        focus.expanded = vec![5];
+        let focus = FocusContext {
+            primary_nodes: vec![1, 2],
+            auto_loaded: vec![3, 4],
+            expanded: vec![5],
+            tests: vec![6],
+        };
 
         let all = focus.all_nodes();
-        assert_eq!(all.len(), 5);
+        assert_eq!(all.len(), 6);
         assert!(all.contains(&1));
         assert!(all.contains(&5));
     }
@@ -0,0 +1,449 @@
+//! Agent session tracking.
+//!
+//! A session spans an agent's work on a project, from `SessionStart` to
+//! `SessionEnd`. Scopes, experiences and memory entries carry the id of the
+//! session they were created in; the session record adds the agent's
+//! metadata and, once the session ended, a summary that heads the anchor of
+//! the next session. Sessions are persisted per project so they survive a
+//! daemon restart.
+
+use crate::memory::{MemoryStoreError, Result};
+use crate::scope::Experience;
+use engram_indexer::storage::Storage;
+use engram_ipc::MemoryEntry;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// File name of the sessions in the project data directory.
+const SESSIONS_FILE: &str = "sessions.json";
+
+/// Most sessions kept per project; the oldest ended ones are dropped first.
+const MAX_SESSIONS: usize = 100;
+
+/// Decisions listed in a generated summary.
+const SUMMARY_DECISIONS: usize = 5;
+
+/// Files listed in a generated summary.
+const SUMMARY_FILES: usize = 8;
+
+/// An agent session on a project.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    /// Session identifier, usually chosen by the agent
+    pub id: String,
+    /// Agent running the session, e.g. `claude`
+    #[serde(default)]
+    pub agent: Option<String>,
+    /// Model the agent runs on
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Other agent metadata
+    #[serde(default)]
+    pub metadata: BTreeMap<String, String>,
+    /// When the session started (unix seconds)
+    pub started_at: i64,
+    /// When the session ended; `None` while it is active
+    #[serde(default)]
+    pub ended_at: Option<i64>,
+    /// Scopes created in the session, oldest first
+    #[serde(default)]
+    pub scope_ids: Vec<String>,
+    /// What the session did, set when it ends
+    #[serde(default)]
+    pub summary: Option<String>,
+}
+
+impl Session {
+    /// Create an active session starting now.
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            agent: None,
+            model: None,
+            metadata: BTreeMap::new(),
+            started_at: chrono::Utc::now().timestamp(),
+            ended_at: None,
+            scope_ids: Vec::new(),
+            summary: None,
+        }
+    }
+
+    /// Set the agent and the model it runs on.
+    pub fn with_agent(mut self, agent: Option<String>, model: Option<String>) -> Self {
+        self.agent = agent;
+        self.model = model;
+        self
+    }
+
+    /// Set other agent metadata.
+    pub fn with_metadata(mut self, metadata: BTreeMap<String, String>) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Whether the session has not ended.
+    pub fn is_active(&self) -> bool {
+        self.ended_at.is_none()
+    }
+}
+
+/// Per-project record of agent sessions.
+pub struct SessionStore {
+    storage: Arc<Storage>,
+    /// Loaded sessions keyed by project hash, oldest first
+    projects: Mutex<HashMap<String, Vec<Session>>>,
+}
+
+impl SessionStore {
+    /// Create an empty session store.
+    pub fn new(storage: Arc<Storage>) -> Self {
+        Self {
+            storage,
+            projects: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn path(&self, hash: &str) -> PathBuf {
+        self.storage.project_dir(hash).join(SESSIONS_FILE)
+    }
+
+    /// Start a session.
+    ///
+    /// Starting a known session reopens it, keeping its scopes and summary
+    /// and updating the agent metadata that was given.
+    pub async fn start(&self, project_path: &Path, session: Session) -> Result<Session> {
+        self.update(project_path, |sessions| {
+            match sessions.iter_mut().find(|known| known.id == session.id) {
+                Some(known) => {
+                    known.ended_at = None;
+                    if session.agent.is_some() {
+                        known.agent = session.agent;
+                    }
+                    if session.model.is_some() {
+                        known.model = session.model;
+                    }
+                    known.metadata.extend(session.metadata);
+                    known.clone()
+                }
+                None => {
+                    sessions.push(session.clone());
+                    session
+                }
+            }
+        })
+        .await
+    }
+
+    /// End a session, replacing its summary if one is given. Returns the
+    /// ended session, or `None` if it is unknown.
+    pub async fn end(
+        &self,
+        project_path: &Path,
+        id: &str,
+        summary: Option<String>,
+    ) -> Result<Option<Session>> {
+        let now = chrono::Utc::now().timestamp();
+        self.update(project_path, |sessions| {
+            let session = sessions.iter_mut().find(|session| session.id == id)?;
+            session.ended_at = Some(now);
+            if summary.is_some() {
+                session.summary = summary;
+            }
+            Some(session.clone())
+        })
+        .await
+    }
+
+    /// Record a scope created in a session, starting the session if it is
+    /// unknown.
+    pub async fn add_scope(&self, project_path: &Path, id: &str, scope_id: &str) -> Result<()> {
+        self.update(project_path, |sessions| {
+            let index = match sessions.iter().position(|session| session.id == id) {
+                Some(index) => index,
+                None => {
+                    sessions.push(Session::new(id));
+                    sessions.len() - 1
+                }
+            };
+            let session = &mut sessions[index];
+            if !session.scope_ids.iter().any(|known| known == scope_id) {
+                session.scope_ids.push(scope_id.to_string());
+            }
+        })
+        .await
+    }
+
+    /// A session by id.
+    pub async fn get(&self, project_path: &Path, id: &str) -> Result<Option<Session>> {
+        self.update(project_path, |sessions| {
+            sessions.iter().find(|session| session.id == id).cloned()
+        })
+        .await
+    }
+
+    /// All sessions, most recently started first.
+    pub async fn list(&self, project_path: &Path) -> Result<Vec<Session>> {
+        let mut sessions = self
+            .update(project_path, |sessions| sessions.clone())
+            .await?;
+        sessions.sort_by_key(|session| std::cmp::Reverse(session.started_at));
+        Ok(sessions)
+    }
+
+    /// The most recently started session that has not ended.
+    pub async fn active(&self, project_path: &Path) -> Result<Option<Session>> {
+        self.update(project_path, |sessions| {
+            sessions
+                .iter()
+                .filter(|session| session.is_active())
+                .max_by_key(|session| session.started_at)
+                .cloned()
+        })
+        .await
+    }
+
+    /// The most recently ended session with a summary.
+    pub async fn previous(&self, project_path: &Path) -> Result<Option<Session>> {
+        self.update(project_path, |sessions| {
+            sessions
+                .iter()
+                .filter(|session| session.summary.is_some())
+                .filter_map(|session| Some((session.ended_at?, session)))
+                .max_by_key(|(ended_at, _)| *ended_at)
+                .map(|(_, session)| session.clone())
+        })
+        .await
+    }
+
+    /// Drop a project's cached sessions; they are persisted on every change.
+    pub async fn unload(&self, project_path: &Path) {
+        let hash = self.storage.project_hash(project_path);
+        self.projects.lock().await.remove(&hash);
+    }
+
+    /// Apply `f` to a project's sessions, loading them first and persisting
+    /// them afterwards.
+    async fn update<T>(
+        &self,
+        project_path: &Path,
+        f: impl FnOnce(&mut Vec<Session>) -> T,
+    ) -> Result<T> {
+        let hash = self.storage.project_hash(project_path);
+        let path = self.path(&hash);
+        let mut projects = self.projects.lock().await;
+
+        if !projects.contains_key(&hash) {
+            let sessions = match tokio::fs::read(&path).await {
+                Ok(data) => serde_json::from_slice(&data)?,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+                Err(e) => return Err(MemoryStoreError::Storage(e.to_string())),
+            };
+            projects.insert(hash.clone(), sessions);
+        }
+
+        let sessions = projects.get_mut(&hash).expect("sessions loaded above");
+        let before = sessions.clone();
+        let result = f(sessions);
+        if *sessions != before {
+            trim(sessions);
+            persist(&path, sessions).await?;
+        }
+        Ok(result)
+    }
+}
+
+/// Drop the oldest ended sessions beyond [`MAX_SESSIONS`].
+fn trim(sessions: &mut Vec<Session>) {
+    let mut excess = sessions.len().saturating_sub(MAX_SESSIONS);
+    if excess == 0 {
+        return;
+    }
+    let mut ended: Vec<(i64, String)> = sessions
+        .iter()
+        .filter_map(|session| Some((session.ended_at?, session.id.clone())))
+        .collect();
+    ended.sort();
+    excess = excess.min(ended.len());
+    let dropped: BTreeSet<String> = ended.into_iter().take(excess).map(|(_, id)| id).collect();
+    sessions.retain(|session| !dropped.contains(&session.id));
+}
+
+async fn persist(path: &Path, sessions: &[Session]) -> Result<()> {
+    let storage_err = |e: std::io::Error| MemoryStoreError::Storage(e.to_string());
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await.map_err(storage_err)?;
+    }
+    let temp_path = path.with_extension("json.tmp");
+    tokio::fs::write(&temp_path, serde_json::to_vec(sessions)?)
+        .await
+        .map_err(storage_err)?;
+    tokio::fs::rename(&temp_path, path)
+        .await
+        .map_err(storage_err)
+}
+
+/// Summarize a session from the experiences and memory entries recorded in
+/// it: its latest decisions, the files they touched and the memory kinds
+/// written. `None` if the session recorded nothing.
+pub fn summarize_session(experiences: &[Experience], memory: &[MemoryEntry]) -> Option<String> {
+    let memory: Vec<&MemoryEntry> = memory.iter().filter(|entry| !entry.deleted).collect();
+    if experiences.is_empty() && memory.is_empty() {
+        return None;
+    }
+
+    let mut lines = Vec::new();
+    let mut experiences: Vec<&Experience> = experiences.iter().collect();
+    experiences.sort_by_key(|experience| experience.timestamp);
+    let skipped = experiences.len().saturating_sub(SUMMARY_DECISIONS);
+    for experience in &experiences[skipped..] {
+        lines.push(format!("- {}", experience.decision));
+    }
+    if skipped > 0 {
+        lines.push(format!("- ...and {} earlier decisions", skipped));
+    }
+
+    let files: BTreeSet<&PathBuf> = experiences
+        .iter()
+        .flat_map(|experience| &experience.files_touched)
+        .collect();
+    if !files.is_empty() {
+        let mut listed: Vec<String> = files
+            .iter()
+            .take(SUMMARY_FILES)
+            .map(|path| path.display().to_string())
+            .collect();
+        if files.len() > SUMMARY_FILES {
+            listed.push(format!("+{} more", files.len() - SUMMARY_FILES));
+        }
+        lines.push(format!("Files touched: {}", listed.join(", ")));
+    }
+
+    if !memory.is_empty() {
+        let mut kinds: BTreeMap<&str, usize> = BTreeMap::new();
+        for entry in &memory {
+            *kinds.entry(entry.kind.as_str()).or_insert(0) += 1;
+        }
+        let kinds: Vec<String> = kinds
+            .into_iter()
+            .map(|(kind, count)| format!("{} {}", count, kind))
+            .collect();
+        lines.push(format!("Memory written: {}", kinds.join(", ")));
+    }
+
+    Some(lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engram_indexer::storage::StorageOptions;
+    use engram_ipc::MemoryVisibility;
+    use tempfile::tempdir;
+
+    fn storage(dir: &Path) -> Arc<Storage> {
+        Arc::new(Storage::with_options(StorageOptions {
+            base_dir: dir.to_path_buf(),
+            ..Default::default()
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_session_lifecycle_and_reload() {
+        let temp_dir = tempdir().unwrap();
+        let project = Path::new("/project");
+
+        let sessions = SessionStore::new(storage(temp_dir.path()));
+        let started = sessions
+            .start(
+                project,
+                Session::new("s1").with_agent(Some("claude".to_string()), None),
+            )
+            .await
+            .unwrap();
+        assert!(started.is_active());
+        sessions.add_scope(project, "s1", "scope-a").await.unwrap();
+        sessions.add_scope(project, "s1", "scope-a").await.unwrap();
+        // Scopes of unknown sessions start them
+        sessions.add_scope(project, "s2", "scope-b").await.unwrap();
+        assert!(sessions.previous(project).await.unwrap().is_none());
+
+        let ended = sessions
+            .end(project, "s1", Some("Fixed login".to_string()))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(ended.scope_ids, vec!["scope-a".to_string()]);
+        assert!(sessions
+            .end(project, "missing", None)
+            .await
+            .unwrap()
+            .is_none());
+
+        // A fresh store reloads sessions from disk
+        let reloaded = SessionStore::new(storage(temp_dir.path()));
+        assert_eq!(reloaded.list(project).await.unwrap().len(), 2);
+        assert_eq!(reloaded.active(project).await.unwrap().unwrap().id, "s2");
+        let previous = reloaded.previous(project).await.unwrap().unwrap();
+        assert_eq!(previous.id, "s1");
+        assert_eq!(previous.agent.as_deref(), Some("claude"));
+        assert_eq!(previous.summary.as_deref(), Some("Fixed login"));
+
+        // Restarting keeps the agent unless a new one is given
+        let reopened = reloaded.start(project, Session::new("s1")).await.unwrap();
+        assert!(reopened.is_active());
+        assert_eq!(reopened.agent.as_deref(), Some("claude"));
+    }
+
+    #[test]
+    fn test_trim_drops_oldest_ended_sessions() {
+        let mut sessions: Vec<Session> = (0..MAX_SESSIONS + 2)
+            .map(|i| {
+                let mut session = Session::new(format!("s{}", i));
+                session.ended_at = (i != 0).then_some(i as i64);
+                session
+            })
+            .collect();
+        trim(&mut sessions);
+        assert_eq!(sessions.len(), MAX_SESSIONS);
+        // The active session is kept even though it is the oldest
+        assert_eq!(sessions[0].id, "s0");
+        assert_eq!(sessions[1].id, "s3");
+    }
+
+    #[test]
+    fn test_summarize_session() {
+        assert!(summarize_session(&[], &[]).is_none());
+
+        let experiences: Vec<Experience> = (0..7)
+            .map(|i| {
+                let mut experience = Experience::new("agent", format!("step {}", i))
+                    .with_files(vec![PathBuf::from("src/auth.rs")]);
+                experience.timestamp = i;
+                experience
+            })
+            .collect();
+        let note = MemoryEntry {
+            id: "m1".to_string(),
+            kind: "note".to_string(),
+            content: "tokens rotate hourly".to_string(),
+            tags: vec![],
+            created_at: 1,
+            updated_at: 1,
+            session_id: Some("s1".to_string()),
+            subagent_id: None,
+            deleted: false,
+            experience_id: None,
+            visibility: MemoryVisibility::Project,
+        };
+
+        let summary = summarize_session(&experiences, &[note]).unwrap();
+        assert_eq!(
+            summary,
+            "- step 2\n- step 3\n- step 4\n- step 5\n- step 6\n- ...and 2 earlier decisions\n\
+             Files touched: src/auth.rs\nMemory written: 1 note"
+        );
+    }
+}
@@ -0,0 +1,388 @@
+//! Embedded vector index for semantic retrieval.
+//!
+//! Nodes are embedded with signed feature hashing over identifier-aware
+//! tokens (camelCase and snake_case are split) plus character trigrams, so
+//! "how is the invoice total computed" finds `compute_invoice_total` without
+//! a model download. Search is an exact cosine scan: at 256 dimensions a
+//! 50k-node project is ~13M multiply-adds per query, well under the latency
+//! budget, and unlike graph indexes it never misses the true best match on
+//! sparse hashed vectors. The index is persisted next to the project's tree,
+//! keyed by the tree generation it was built from.
+
+use crate::error::{ContextError, Result};
+use engram_indexer::storage::Storage;
+use engram_indexer::tree::{Node, NodeId, NodeKind, Tree};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::debug;
+
+/// Current on-disk format version.
+pub const VECTOR_INDEX_VERSION: u32 = 1;
+
+/// Embedding dimensionality.
+pub const EMBEDDING_DIM: usize = 256;
+
+/// File name of the index in the project data directory.
+const VECTOR_INDEX_FILE: &str = "vectors.bin";
+
+/// Weight of a character trigram relative to a whole token.
+const TRIGRAM_WEIGHT: f32 = 0.3;
+
+/// Words that carry no meaning for retrieval.
+const STOPWORDS: &[&str] = &[
+    "a", "an", "and", "are", "be", "by", "do", "does", "explain", "for", "from", "how", "in", "is",
+    "it", "of", "on", "or", "the", "this", "that", "to", "what", "when", "where", "which", "why",
+    "with", "work", "works",
+];
+
+/// Nearest-neighbor index over tree node embeddings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VectorIndex {
+    /// Format version
+    version: u32,
+    /// Generation of the tree this index was built from
+    generation: u64,
+    /// Node ID per indexed point
+    ids: Vec<NodeId>,
+    /// Normalized embeddings, `EMBEDDING_DIM` floats per point
+    vectors: Vec<f32>,
+}
+
+impl VectorIndex {
+    /// Embed and index every file and symbol node in a tree.
+    pub fn build(tree: &Tree) -> Self {
+        let mut index = Self {
+            version: VECTOR_INDEX_VERSION,
+            generation: tree.generation(),
+            ids: Vec::new(),
+            vectors: Vec::new(),
+        };
+
+        // Insert in ID order so the index is reproducible.
+        let mut nodes: Vec<&Node> = tree.nodes.values().filter(|n| !n.is_directory()).collect();
+        nodes.sort_by_key(|n| n.id);
+
+        for node in nodes {
+            if let Some(vector) = embed(&node_text(tree, node)) {
+                index.ids.push(node.id);
+                index.vectors.extend_from_slice(&vector);
+            }
+        }
+
+        debug!(points = index.len(), "Vector index built");
+        index
+    }
+
+    /// Number of indexed nodes.
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Whether nothing is indexed.
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Whether this index was built from the given tree state.
+    pub fn is_current(&self, tree: &Tree) -> bool {
+        self.version == VECTOR_INDEX_VERSION && self.generation == tree.generation()
+    }
+
+    /// Find the `k` nodes most similar to a query, best first.
+    ///
+    /// Scores are cosine similarities in `[-1, 1]`.
+    pub fn search(&self, query: &str, k: usize) -> Vec<(NodeId, f32)> {
+        let Some(query) = embed(query) else {
+            return Vec::new();
+        };
+
+        let mut scored: Vec<(NodeId, f32)> = self
+            .vectors
+            .chunks_exact(EMBEDDING_DIM)
+            .zip(&self.ids)
+            .map(|(vector, id)| (*id, dot(&query, vector)))
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        scored.truncate(k);
+        scored
+    }
+}
+
+/// Text describing a node for embedding.
+///
+/// Symbols use their own name and doc rather than the full path, and files
+/// leave out their symbols (indexed as separate nodes), so a symbol isn't
+/// outranked by the file that contains it.
+fn node_text(tree: &Tree, node: &Node) -> String {
+    let mut text = match &node.kind {
+        NodeKind::Symbol { symbol_kind, .. } => format!("{} {:?}", node.name, symbol_kind),
+        _ => node.path.to_string_lossy().into_owned(),
+    };
+    if let Some(content) = tree.content(node.id) {
+        for part in content.summary.iter().chain(&content.tags) {
+            text.push(' ');
+            text.push_str(part);
+        }
+    }
+    text
+}
+
+/// Split text into lowercase tokens, breaking up identifiers.
+fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    for word in text.split(|c: char| !c.is_alphanumeric()) {
+        let mut current = String::new();
+        let mut prev_lower = false;
+        for c in word.chars() {
+            if c.is_uppercase() && prev_lower {
+                tokens.push(std::mem::take(&mut current).to_lowercase());
+            }
+            prev_lower = c.is_lowercase() || c.is_ascii_digit();
+            current.push(c);
+        }
+        if !current.is_empty() {
+            tokens.push(current.to_lowercase());
+        }
+    }
+    tokens.retain(|t| t.chars().count() > 1 && !STOPWORDS.contains(&t.as_str()));
+    tokens
+}
+
+/// Turns text into normalized vectors for similarity search.
+///
+/// Vectors from different embedders are not comparable, so callers that
+/// cache them must not mix embedders.
+pub trait Embedder: Send + Sync {
+    /// Embed text into a normalized vector, or `None` if it has nothing to
+    /// match on.
+    fn embed(&self, text: &str) -> Option<Vec<f32>>;
+}
+
+/// Feature-hashing embedder; needs no model and is used by default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HashingEmbedder;
+
+impl Embedder for HashingEmbedder {
+    fn embed(&self, text: &str) -> Option<Vec<f32>> {
+        embed(text)
+    }
+}
+
+/// Embed text into a normalized vector, or `None` if it has no tokens.
+pub fn embed(text: &str) -> Option<Vec<f32>> {
+    let mut vector = vec![0.0f32; EMBEDDING_DIM];
+    for token in tokenize(text) {
+        add_feature(&mut vector, token.as_bytes(), 1.0);
+
+        let padded: Vec<char> = format!("^{}$", token).chars().collect();
+        for trigram in padded.windows(3) {
+            let trigram: String = trigram.iter().collect();
+            add_feature(&mut vector, trigram.as_bytes(), TRIGRAM_WEIGHT);
+        }
+    }
+
+    let norm = dot(&vector, &vector).sqrt();
+    if norm == 0.0 {
+        return None;
+    }
+    vector.iter_mut().for_each(|v| *v /= norm);
+    Some(vector)
+}
+
+/// Signed feature hashing: the top bit picks the sign to cancel collisions.
+fn add_feature(vector: &mut [f32], feature: &[u8], weight: f32) {
+    let hash = fnv1a(feature);
+    let slot = (hash % EMBEDDING_DIM as u64) as usize;
+    if hash >> 63 == 0 {
+        vector[slot] += weight;
+    } else {
+        vector[slot] -= weight;
+    }
+}
+
+/// FNV-1a, stable across builds so persisted vectors stay valid.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ *b as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+pub(crate) fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// Loads and stores vector indexes per project.
+pub struct VectorStore {
+    storage: Arc<Storage>,
+}
+
+impl VectorStore {
+    /// Create a vector store.
+    pub fn new(storage: Arc<Storage>) -> Self {
+        Self { storage }
+    }
+
+    fn path(&self, project_path: &Path) -> PathBuf {
+        let hash = self.storage.project_hash(project_path);
+        self.storage.project_dir(&hash).join(VECTOR_INDEX_FILE)
+    }
+
+    /// Load a project's stored index, if any.
+    pub async fn load(&self, project_path: &Path) -> Result<Option<VectorIndex>> {
+        match tokio::fs::read(self.path(project_path)).await {
+            Ok(bytes) => rmp_serde::from_slice(&bytes)
+                .map(Some)
+                .map_err(|e| ContextError::Storage(e.to_string())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Store a project's index.
+    pub async fn save(&self, project_path: &Path, index: &VectorIndex) -> Result<()> {
+        let path = self.path(project_path);
+        if let Some(dir) = path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        let bytes = rmp_serde::to_vec(index).map_err(|e| ContextError::Storage(e.to_string()))?;
+
+        let temp_path = path.with_extension("bin.tmp");
+        tokio::fs::write(&temp_path, bytes).await?;
+        tokio::fs::rename(&temp_path, &path).await?;
+        Ok(())
+    }
+
+    /// Load the stored index, rebuilding it if missing or built from an
+    /// older tree.
+    pub async fn load_or_build(&self, project_path: &Path, tree: &Tree) -> Result<VectorIndex> {
+        match self.load(project_path).await {
+            Ok(Some(index)) if index.is_current(tree) => return Ok(index),
+            Ok(_) => {}
+            Err(e) => debug!(error = %e, "Discarding unreadable vector index"),
+        }
+
+        let index = VectorIndex::build(tree);
+        self.save(project_path, &index).await?;
+        Ok(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engram_indexer::scanner::{Language, ScanResult, ScannedFile, Symbol, SymbolKind};
+    use engram_indexer::storage::StorageOptions;
+    use engram_indexer::tree::TreeBuilder;
+    use tempfile::tempdir;
+
+    fn file(path: &str, symbols: &[(&str, &str)]) -> ScannedFile {
+        ScannedFile {
+            path: PathBuf::from(path),
+            language: Some(Language::Rust),
+            size: 100,
+            hash: path.to_string(),
+            line_count: 10,
+            symbols: symbols
+                .iter()
+                .map(|(name, doc)| Symbol {
+                    name: name.to_string(),
+                    kind: SymbolKind::Function,
+                    start_line: 1,
+                    end_line: 5,
+                    parent: None,
+                    doc: Some(doc.to_string()),
+                })
+                .collect(),
+            imports: vec![],
+            truncated: false,
+            asset: None,
+        }
+    }
+
+    fn test_tree() -> Tree {
+        let scan = ScanResult {
+            root: PathBuf::from("/project"),
+            files: vec![
+                file(
+                    "src/billing/invoice.rs",
+                    &[("compute_invoice_total", "Sums line items and tax")],
+                ),
+                file(
+                    "src/auth/session.rs",
+                    &[("validateSessionToken", "Checks the login token")],
+                ),
+                file(
+                    "src/cache/lru.rs",
+                    &[("evict_oldest", "Drops least recently used entry")],
+                ),
+            ],
+            languages: vec![Language::Rust],
+            frameworks: vec![],
+            duration_ms: 0,
+            skipped_count: 0,
+            excluded_dirs: vec![],
+        };
+        TreeBuilder::new().build(&scan)
+    }
+
+    #[test]
+    fn test_tokenize_splits_identifiers() {
+        assert_eq!(
+            tokenize("validateSessionToken compute_invoice_total"),
+            vec!["validate", "session", "token", "compute", "invoice", "total"]
+        );
+        assert_eq!(tokenize("How does the cache work?"), vec!["cache"]);
+        assert!(embed("how does it work").is_none());
+    }
+
+    #[test]
+    fn test_search_finds_semantic_match() {
+        let tree = test_tree();
+        let index = VectorIndex::build(&tree);
+        assert_eq!(index.len(), 6); // 3 files + 3 symbols
+
+        let results = index.search("how is the invoice total computed", 2);
+        let top = tree.get(results[0].0).unwrap();
+        assert_eq!(top.name, "compute_invoice_total");
+        assert!(results[0].1 > results[1].1);
+
+        let results = index.search("session token validation", 1);
+        assert_eq!(tree.get(results[0].0).unwrap().name, "validateSessionToken");
+    }
+
+    #[test]
+    fn test_search_limits_and_orders_results() {
+        let tree = test_tree();
+        let index = VectorIndex::build(&tree);
+
+        let results = index.search("cache entry eviction", 3);
+        assert_eq!(results.len(), 3);
+        assert!(results.windows(2).all(|w| w[0].1 >= w[1].1));
+        assert_eq!(tree.get(results[0].0).unwrap().name, "evict_oldest");
+        assert!(index.search("how does it work", 3).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_store_rebuilds_stale_index() {
+        let temp_dir = tempdir().unwrap();
+        let storage = Arc::new(Storage::with_options(StorageOptions {
+            base_dir: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        }));
+        let store = VectorStore::new(storage);
+        let project = Path::new("/project");
+        let mut tree = test_tree();
+
+        assert!(store.load(project).await.unwrap().is_none());
+        let built = store.load_or_build(project, &tree).await.unwrap();
+        let loaded = store.load(project).await.unwrap().unwrap();
+        assert_eq!(loaded.len(), built.len());
+        assert!(loaded.is_current(&tree));
+
+        tree.updated_at += chrono::Duration::seconds(1);
+        assert!(!loaded.is_current(&tree));
+        let rebuilt = store.load_or_build(project, &tree).await.unwrap();
+        assert!(rebuilt.is_current(&tree));
+    }
+}
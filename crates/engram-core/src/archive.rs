@@ -0,0 +1,286 @@
+//! Portable archives of a project's stored data.
+//!
+//! An archive is a gzipped tar holding `archive.json`, which records the
+//! root and time it was exported at, the project's manifest directory under
+//! `project/`, and its stored trees, dependency graph, memory and snapshots
+//! under `data/`. Importing one replaces whatever the target project had
+//! stored and points the manifest and trees at the target's root, so an
+//! index built on one machine, or in CI, is reused at another path.
+
+use crate::CoreError;
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Archive format written by this version; newer formats are refused
+pub const ARCHIVE_FORMAT: u32 = 1;
+
+/// Archive entry describing the archive
+const INFO_FILE: &str = "archive.json";
+
+/// Prefix of the manifest directory's files in an archive
+const PROJECT_PREFIX: &str = "project/";
+
+/// Prefix of the stored data's files in an archive
+const DATA_PREFIX: &str = "data/";
+
+/// Most bytes read from one archive entry
+const MAX_ENTRY_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Most bytes read from all entries of an archive
+const MAX_TOTAL_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// Where and when an archive was exported
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveInfo {
+    pub format: u32,
+    pub exported_at: DateTime<Utc>,
+    /// Root of the project the archive was exported from
+    pub project_path: PathBuf,
+}
+
+/// Outcome of exporting or importing an archive
+#[derive(Debug, Clone)]
+pub struct ArchiveReport {
+    /// Files in the archive
+    pub files: usize,
+    /// Uncompressed size of the files
+    pub bytes: u64,
+    /// Root of the project the archive was exported from
+    pub source_path: PathBuf,
+}
+
+/// Files of an archive, by path relative to the directory they belong in
+#[derive(Debug, Default)]
+pub(crate) struct ArchiveContents {
+    pub project: Vec<(String, Vec<u8>)>,
+    pub data: Vec<(String, Vec<u8>)>,
+}
+
+impl ArchiveContents {
+    pub fn report(&self, info: &ArchiveInfo) -> ArchiveReport {
+        let files = self.project.iter().chain(&self.data);
+        ArchiveReport {
+            files: self.project.len() + self.data.len(),
+            bytes: files.map(|(_, data)| data.len() as u64).sum(),
+            source_path: info.project_path.clone(),
+        }
+    }
+}
+
+/// Write `contents` to a new archive at `output`, which must not exist.
+///
+/// The archive is written to a temp file next to `output` and renamed into
+/// place, so a failed export leaves no partial archive behind.
+pub(crate) fn write_archive(
+    output: &Path,
+    info: &ArchiveInfo,
+    contents: &ArchiveContents,
+) -> Result<ArchiveReport, CoreError> {
+    if output.symlink_metadata().is_ok() {
+        return Err(CoreError::Storage(format!(
+            "{} already exists",
+            output.display()
+        )));
+    }
+    let file_name = output
+        .file_name()
+        .ok_or_else(|| CoreError::InvalidPath(output.display().to_string()))?;
+    let temp_path = output.with_file_name(format!(".{}.tmp", file_name.to_string_lossy()));
+    let result = write_archive_file(&temp_path, info, contents)
+        .and_then(|()| std::fs::rename(&temp_path, output).map_err(CoreError::from));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result.map(|()| contents.report(info))
+}
+
+fn write_archive_file(
+    path: &Path,
+    info: &ArchiveInfo,
+    contents: &ArchiveContents,
+) -> Result<(), CoreError> {
+    let info_json =
+        serde_json::to_vec_pretty(info).map_err(|e| CoreError::Serialization(e.to_string()))?;
+    // A stale temp file is replaced; `create_new` never follows a symlink
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+        _ => {}
+    }
+    let file = File::options().write(true).create_new(true).open(path)?;
+    let mut archive = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    let mtime = info.exported_at.timestamp().max(0) as u64;
+    append(&mut archive, INFO_FILE, &info_json, mtime)?;
+    for (path, data) in &contents.project {
+        append(
+            &mut archive,
+            &format!("{}{}", PROJECT_PREFIX, path),
+            data,
+            mtime,
+        )?;
+    }
+    for (path, data) in &contents.data {
+        append(
+            &mut archive,
+            &format!("{}{}", DATA_PREFIX, path),
+            data,
+            mtime,
+        )?;
+    }
+    archive.into_inner()?.finish()?.sync_all()?;
+    Ok(())
+}
+
+fn append<W: std::io::Write>(
+    archive: &mut tar::Builder<W>,
+    path: &str,
+    data: &[u8],
+    mtime: u64,
+) -> Result<(), CoreError> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(mtime);
+    header.set_cksum();
+    archive.append_data(&mut header, path, data)?;
+    Ok(())
+}
+
+/// Read the archive at `path`. Nothing is unpacked; stored data paths are
+/// checked when restored, manifest directory files must be plain names.
+pub(crate) fn read_archive(path: &Path) -> Result<(ArchiveInfo, ArchiveContents), CoreError> {
+    read_archive_with_limits(path, MAX_ENTRY_BYTES, MAX_TOTAL_BYTES)
+}
+
+/// Read an archive, refusing entries larger than `max_entry` bytes or more
+/// than `max_total` bytes in all, whatever their headers claim.
+fn read_archive_with_limits(
+    path: &Path,
+    max_entry: u64,
+    max_total: u64,
+) -> Result<(ArchiveInfo, ArchiveContents), CoreError> {
+    let invalid = |reason: String| {
+        CoreError::Storage(format!(
+            "{} is not a project archive: {}",
+            path.display(),
+            reason
+        ))
+    };
+
+    let mut archive = tar::Archive::new(GzDecoder::new(File::open(path)?));
+    let mut info = None;
+    let mut contents = ArchiveContents::default();
+    let mut total = 0u64;
+    for entry in archive.entries().map_err(|e| invalid(e.to_string()))? {
+        let mut entry = entry.map_err(|e| invalid(e.to_string()))?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let name = entry
+            .path()
+            .map_err(|e| invalid(e.to_string()))?
+            .to_string_lossy()
+            .replace('\\', "/");
+        let limit = max_entry.min(max_total - total);
+        let mut data = Vec::new();
+        (&mut entry)
+            .take(limit + 1)
+            .read_to_end(&mut data)
+            .map_err(|e| invalid(e.to_string()))?;
+        if data.len() as u64 > limit {
+            return Err(invalid(format!("{} is too large", name)));
+        }
+        total += data.len() as u64;
+
+        if name == INFO_FILE {
+            info = Some(
+                serde_json::from_slice::<ArchiveInfo>(&data)
+                    .map_err(|e| invalid(format!("{}: {}", INFO_FILE, e)))?,
+            );
+        } else if let Some(file) = name.strip_prefix(PROJECT_PREFIX) {
+            if file.is_empty() || file.contains('/') || file.starts_with('.') {
+                return Err(invalid(format!("unexpected entry {}", name)));
+            }
+            contents.project.push((file.to_string(), data));
+        } else if let Some(file) = name.strip_prefix(DATA_PREFIX) {
+            contents.data.push((file.to_string(), data));
+        } else {
+            return Err(invalid(format!("unexpected entry {}", name)));
+        }
+    }
+
+    let info = info.ok_or_else(|| invalid(format!("no {}", INFO_FILE)))?;
+    if info.format > ARCHIVE_FORMAT {
+        return Err(invalid(format!(
+            "format {} is newer than this version reads ({})",
+            info.format, ARCHIVE_FORMAT
+        )));
+    }
+    Ok((info, contents))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_archive_round_trip() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("project.tar.gz");
+        let info = ArchiveInfo {
+            format: ARCHIVE_FORMAT,
+            exported_at: Utc::now(),
+            project_path: PathBuf::from("/work/app"),
+        };
+        let contents = ArchiveContents {
+            project: vec![("manifest.json".to_string(), b"{}".to_vec())],
+            data: vec![
+                ("skeleton.json".to_string(), b"{\"nodes\":[]}".to_vec()),
+                (
+                    "snapshots/20260101_000000/skeleton.json".to_string(),
+                    vec![1, 2],
+                ),
+            ],
+        };
+
+        let report = write_archive(&path, &info, &contents).unwrap();
+        assert_eq!(report.files, 3);
+        assert_eq!(report.bytes, 16);
+
+        let (read_info, read) = read_archive(&path).unwrap();
+        assert_eq!(read_info.project_path, info.project_path);
+        assert_eq!(read.project, contents.project);
+        assert_eq!(read.data, contents.data);
+
+        // Entries are read up to a per-entry and a total size
+        let all = serde_json::to_vec_pretty(&info).unwrap().len() as u64 + report.bytes;
+        assert!(read_archive_with_limits(&path, 1024, all).is_ok());
+        assert!(read_archive_with_limits(&path, 8, u64::MAX).is_err());
+        assert!(read_archive_with_limits(&path, 1024, all - 1).is_err());
+
+        // Archives from a newer version are refused
+        let newer = ArchiveInfo {
+            format: ARCHIVE_FORMAT + 1,
+            ..info
+        };
+        // An existing file is not overwritten
+        assert!(write_archive(&path, &newer, &contents).is_err());
+        std::fs::remove_file(&path).unwrap();
+        write_archive(&path, &newer, &contents).unwrap();
+        assert!(read_archive(&path).is_err());
+        let names: Vec<_> = std::fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(names, vec!["project.tar.gz"]);
+
+        std::fs::write(&path, b"not an archive").unwrap();
+        assert!(read_archive(&path).is_err());
+    }
+}
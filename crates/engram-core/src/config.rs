@@ -1,7 +1,21 @@
 //! Configuration for the Engram daemon.
 
+use crate::events::EventsConfig;
+use crate::flags::ExperimentFlags;
+use crate::gc::GcConfig;
+use crate::slo::SloConfig;
+use crate::CoreError;
+use engram_indexer::storage::{FilesystemBackend, LogCipher, StorageBackend};
+use engram_indexer::tree::SkeletonOptions;
+use engram_ipc::paths;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Settings a running daemon applies when its config is reloaded; changes
+/// to any other setting take effect after a restart.
+pub const RELOADABLE_SETTINGS: &[&str] = &["max_projects", "experiments", "enrichment"];
 
 /// Daemon configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,6 +36,15 @@ pub struct DaemonConfig {
     #[serde(default = "default_max_projects")]
     pub max_projects: usize,
 
+    /// Projects never evicted from the cache, however many are loaded
+    #[serde(default)]
+    pub pinned_projects: Vec<PathBuf>,
+
+    /// Projects opened read-only: once indexed, their stored tree and
+    /// experience log are never written
+    #[serde(default)]
+    pub read_only_projects: Vec<PathBuf>,
+
     /// Log level (trace, debug, info, warn, error)
     #[serde(default = "default_log_level")]
     pub log_level: String,
@@ -33,6 +56,74 @@ pub struct DaemonConfig {
     /// Auto-initialize new projects on detection
     #[serde(default)]
     pub auto_init: AutoInitConfig,
+
+    /// Experiment flags (can be overridden per project)
+    #[serde(default)]
+    pub experiments: ExperimentFlags,
+
+    /// Project structure rendering
+    #[serde(default)]
+    pub skeleton: SkeletonOptions,
+
+    /// Latency SLOs and alerting
+    #[serde(default)]
+    pub slo: SloConfig,
+
+    /// Loopback address for the HTTP/JSON bridge (disabled when unset)
+    #[serde(default)]
+    pub http_addr: Option<SocketAddr>,
+
+    /// Loopback address serving Prometheus metrics (disabled when unset)
+    #[serde(default)]
+    pub metrics_addr: Option<SocketAddr>,
+
+    /// File watching for loaded projects
+    #[serde(default)]
+    pub watch: WatchConfig,
+
+    /// Memory log retention
+    #[serde(default)]
+    pub memory: MemoryConfig,
+
+    /// Who may issue requests over the socket
+    #[serde(default)]
+    pub auth: AuthConfig,
+
+    /// Background summary generation
+    #[serde(default)]
+    pub enrichment: EnrichmentConfig,
+
+    /// Automatic tree snapshots
+    #[serde(default)]
+    pub snapshots: SnapshotConfig,
+
+    /// Context scope lifecycle
+    #[serde(default)]
+    pub scopes: ScopeConfig,
+
+    /// Event journal shown by `engram logs`
+    #[serde(default)]
+    pub events: EventsConfig,
+
+    /// Heartbeat and crash recovery
+    #[serde(default)]
+    pub supervisor: SupervisorConfig,
+
+    /// Draining of in-flight requests on shutdown
+    #[serde(default)]
+    pub shutdown: ShutdownConfig,
+
+    /// Deletion of data left by moved, deleted or abandoned projects
+    #[serde(default)]
+    pub gc: GcConfig,
+
+    /// Encryption of experience logs at rest
+    #[serde(default)]
+    pub encryption: EncryptionConfig,
+
+    /// Where stored trees are kept
+    #[serde(default)]
+    pub storage: StorageConfig,
 }
 
 /// Auto-initialization configuration
@@ -51,14 +142,412 @@ pub struct AutoInitConfig {
     pub exclude_patterns: Vec<String>,
 }
 
+/// File watcher configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchConfig {
+    /// Keep loaded projects up to date as files change
+    #[serde(default = "default_watch_enabled")]
+    pub enabled: bool,
+
+    /// Quiet period before a file system event is reported
+    #[serde(default = "default_debounce_ms")]
+    pub debounce_ms: u64,
+
+    /// Minimum interval between incremental tree updates of a project
+    #[serde(default = "default_batch_ms")]
+    pub batch_ms: u64,
+}
+
+impl Default for WatchConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_watch_enabled(),
+            debounce_ms: default_debounce_ms(),
+            batch_ms: default_batch_ms(),
+        }
+    }
+}
+
+/// Memory log retention configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryConfig {
+    /// Compact a project's memory log once it reaches this many bytes (0 disables)
+    #[serde(default = "default_compact_threshold")]
+    pub compact_threshold: u64,
+
+    /// Days a deleted entry's tombstone survives compaction
+    #[serde(default = "default_tombstone_retention_days")]
+    pub tombstone_retention_days: u64,
+
+    /// Consolidate a project's experiences after this many grafts (0 disables)
+    #[serde(default = "default_consolidate_every")]
+    pub consolidate_every: usize,
+
+    /// Latest experiences kept as they are; older ones are summarized
+    #[serde(default = "default_keep_recent_experiences")]
+    pub keep_recent_experiences: usize,
+}
+
+impl Default for MemoryConfig {
+    fn default() -> Self {
+        Self {
+            compact_threshold: default_compact_threshold(),
+            tombstone_retention_days: default_tombstone_retention_days(),
+            consolidate_every: default_consolidate_every(),
+            keep_recent_experiences: default_keep_recent_experiences(),
+        }
+    }
+}
+
+/// IPC access control
+///
+/// The daemon's own user is always allowed; connections from other users are
+/// rejected based on peer credentials unless listed here.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuthConfig {
+    /// Additional UIDs allowed to connect
+    #[serde(default)]
+    pub allowed_uids: Vec<u32>,
+
+    /// Shared token clients must present (via `ENGRAM_TOKEN`)
+    #[serde(default)]
+    pub token: Option<String>,
+}
+
+/// Encryption of experience logs at rest
+///
+/// With a key configured, lines appended to experience logs are encrypted
+/// with AES-256-GCM; `engram encrypt-logs` encrypts the lines written before.
+/// Logs encrypted with a key cannot be read without it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EncryptionConfig {
+    /// Absolute path of a file holding the key as 64 hex digits, created
+    /// with a random key, readable by its owner only, if it does not exist
+    #[serde(default)]
+    pub key_file: Option<PathBuf>,
+
+    /// Keep the key in the macOS or Windows keychain instead (needs the
+    /// `keychain` feature)
+    #[serde(default)]
+    pub keychain: bool,
+}
+
+impl EncryptionConfig {
+    /// Cipher for the configured key, or `None` if no key is configured.
+    pub fn cipher(&self) -> Result<Option<LogCipher>, CoreError> {
+        if let Some(path) = &self.key_file {
+            if !path.is_absolute() {
+                return Err(CoreError::InvalidPath(format!(
+                    "encryption.key_file must be absolute: {}",
+                    path.display()
+                )));
+            }
+            return LogCipher::load_or_create(path)
+                .map(Some)
+                .map_err(|e| CoreError::Storage(e.to_string()));
+        }
+        if self.keychain {
+            return keychain_cipher().map(Some);
+        }
+        Ok(None)
+    }
+}
+
+/// Cipher for the key in the OS keychain, storing a random key there first
+/// if it has none.
+#[cfg(feature = "keychain")]
+fn keychain_cipher() -> Result<LogCipher, CoreError> {
+    // Linux keyutils keys do not survive a reboot, which would lose the logs
+    if !cfg!(any(target_os = "macos", target_os = "windows")) {
+        return Err(CoreError::Storage(
+            "encryption.keychain is only supported on macOS and Windows; use key_file".to_string(),
+        ));
+    }
+    let keychain_error = |e: keyring::Error| CoreError::Storage(format!("Keychain: {}", e));
+    let entry = keyring::Entry::new("engram", "experience-log-key").map_err(keychain_error)?;
+    let key = match entry.get_password() {
+        Ok(key) => key,
+        Err(keyring::Error::NoEntry) => {
+            let key = LogCipher::generate_key();
+            entry.set_password(&key).map_err(keychain_error)?;
+            tracing::warn!("Generated an experience log key in the keychain");
+            key
+        }
+        Err(e) => return Err(keychain_error(e)),
+    };
+    LogCipher::from_hex(&key).map_err(|e| CoreError::Storage(e.to_string()))
+}
+
+#[cfg(not(feature = "keychain"))]
+fn keychain_cipher() -> Result<LogCipher, CoreError> {
+    Err(CoreError::Storage(
+        "encryption.keychain needs a build with the keychain feature".to_string(),
+    ))
+}
+
+/// Storage backend configuration
+///
+/// Trees and dependency graphs are files in each project's storage directory
+/// by default; `sqlite` keeps those of every project in `storage.db` in the
+/// data dir instead. Experience logs stay files either way. Trees stored
+/// with the other backend are not moved, so projects are indexed again.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StorageConfig {
+    #[serde(default)]
+    pub backend: StorageBackendKind,
+}
+
+/// Where stored trees are kept
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackendKind {
+    /// Files in each project's storage directory
+    #[default]
+    Filesystem,
+    /// One SQLite database (needs the `sqlite` feature)
+    Sqlite,
+}
+
+/// SQLite database of the `sqlite` backend, in the data dir
+const SQLITE_FILE: &str = "storage.db";
+
+impl StorageConfig {
+    /// Open the configured backend for the data dir `data_dir`.
+    pub fn open(&self, data_dir: &Path) -> Result<Arc<dyn StorageBackend>, CoreError> {
+        match self.backend {
+            StorageBackendKind::Filesystem => {
+                Ok(Arc::new(FilesystemBackend::new(data_dir.to_path_buf())))
+            }
+            StorageBackendKind::Sqlite => sqlite_backend(&data_dir.join(SQLITE_FILE)),
+        }
+    }
+}
+
+#[cfg(feature = "sqlite")]
+fn sqlite_backend(path: &Path) -> Result<Arc<dyn StorageBackend>, CoreError> {
+    engram_indexer::storage::SqliteBackend::open(path)
+        .map(|backend| Arc::new(backend) as Arc<dyn StorageBackend>)
+        .map_err(|e| CoreError::Storage(e.to_string()))
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn sqlite_backend(_path: &Path) -> Result<Arc<dyn StorageBackend>, CoreError> {
+    Err(CoreError::Storage(
+        "storage.backend: sqlite needs a build with the sqlite feature".to_string(),
+    ))
+}
+
+/// Background enrichment configuration
+///
+/// Without a command or API backend, summaries are derived from doc
+/// comments and the symbols a file declares.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnrichmentConfig {
+    /// Files summarized concurrently
+    #[serde(default = "default_enrichment_workers")]
+    pub workers: usize,
+
+    /// Summarizer command (program and arguments, e.g. an LLM CLI); it gets
+    /// a prompt on stdin and prints the summary
+    #[serde(default)]
+    pub command: Vec<String>,
+
+    /// Longest file excerpt passed to the summarizer, in bytes
+    #[serde(default = "default_enrichment_max_input")]
+    pub max_input_bytes: usize,
+
+    /// Summarizer to use; `command` when a command is set, `heuristic`
+    /// otherwise
+    #[serde(default)]
+    pub backend: Option<SummarizerBackend>,
+
+    /// Settings of the `openai` and `anthropic` backends
+    #[serde(default)]
+    pub api: SummarizerApiConfig,
+}
+
+/// Where file summaries come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SummarizerBackend {
+    /// Doc comments and declared symbols
+    Heuristic,
+    /// The configured command
+    Command,
+    /// An OpenAI-compatible chat completions API
+    OpenAi,
+    /// The Anthropic messages API
+    Anthropic,
+}
+
+/// Settings of the HTTP summarizer backends
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SummarizerApiConfig {
+    /// API base URL, e.g. `http://localhost:11434/v1` for a local
+    /// OpenAI-compatible server; defaults to the provider's public API
+    #[serde(default)]
+    pub base_url: Option<String>,
+
+    /// Model name; defaults to a small model of the provider
+    #[serde(default)]
+    pub model: Option<String>,
+
+    /// Environment variable holding the API key; defaults to
+    /// `OPENAI_API_KEY` or `ANTHROPIC_API_KEY`
+    #[serde(default)]
+    pub api_key_env: Option<String>,
+
+    /// Longest summary requested, in tokens
+    #[serde(default = "default_api_max_tokens")]
+    pub max_tokens: u32,
+
+    /// Requests sent per minute across all workers (0 for no limit)
+    #[serde(default = "default_api_requests_per_minute")]
+    pub requests_per_minute: u32,
+
+    /// Price of a million input tokens, for cost tracking
+    #[serde(default)]
+    pub input_cost_per_mtok: f64,
+
+    /// Price of a million output tokens, for cost tracking
+    #[serde(default)]
+    pub output_cost_per_mtok: f64,
+}
+
+impl Default for SummarizerApiConfig {
+    fn default() -> Self {
+        Self {
+            base_url: None,
+            model: None,
+            api_key_env: None,
+            max_tokens: default_api_max_tokens(),
+            requests_per_minute: default_api_requests_per_minute(),
+            input_cost_per_mtok: 0.0,
+            output_cost_per_mtok: 0.0,
+        }
+    }
+}
+
+impl Default for EnrichmentConfig {
+    fn default() -> Self {
+        Self {
+            workers: default_enrichment_workers(),
+            command: Vec::new(),
+            max_input_bytes: default_enrichment_max_input(),
+            backend: None,
+            api: SummarizerApiConfig::default(),
+        }
+    }
+}
+
+/// Tree snapshot configuration
+///
+/// Snapshots are taken after full indexes and restored when a stored tree
+/// can no longer be read.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotConfig {
+    /// Take snapshots after full indexes
+    #[serde(default = "default_snapshots_enabled")]
+    pub enabled: bool,
+
+    /// Minimum time between snapshots of a project, in seconds
+    #[serde(default = "default_snapshot_interval_secs")]
+    pub interval_secs: u64,
+
+    /// Snapshots kept per project
+    #[serde(default = "default_snapshot_keep")]
+    pub keep: usize,
+}
+
+impl Default for SnapshotConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_snapshots_enabled(),
+            interval_secs: default_snapshot_interval_secs(),
+            keep: default_snapshot_keep(),
+        }
+    }
+}
+
+/// Heartbeat and crash recovery.
+///
+/// The daemon keeps a state file with its PID and a heartbeat in the data
+/// dir. Finding it unclosed on start means the last daemon did not shut
+/// down cleanly, and storage is checked and repaired before serving.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SupervisorConfig {
+    /// Write the state file and recover after unclean shutdowns
+    #[serde(default = "default_supervisor_enabled")]
+    pub enabled: bool,
+
+    /// Seconds between heartbeats
+    #[serde(default = "default_heartbeat_secs")]
+    pub heartbeat_secs: u64,
+}
+
+impl Default for SupervisorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_supervisor_enabled(),
+            heartbeat_secs: default_heartbeat_secs(),
+        }
+    }
+}
+
+/// Shutdown configuration
+///
+/// On shutdown the daemon stops accepting connections and waits for the
+/// requests already being handled before flushing its caches and exiting.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShutdownConfig {
+    /// Seconds in-flight requests are given to finish; requests still
+    /// running afterwards are aborted
+    #[serde(default = "default_drain_secs")]
+    pub drain_secs: u64,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self {
+            drain_secs: default_drain_secs(),
+        }
+    }
+}
+
+/// Context scope lifecycle configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScopeConfig {
+    /// Seconds an unused scope is kept (0 keeps scopes until released)
+    #[serde(default = "default_scope_ttl_secs")]
+    pub ttl_secs: u64,
+
+    /// Persist active scopes in the project data directory so they survive
+    /// a daemon restart
+    #[serde(default)]
+    pub persist: bool,
+
+    /// After a file change, cache contexts focused on the file and its
+    /// importers before they are requested
+    #[serde(default = "default_prefetch")]
+    pub prefetch: bool,
+}
+
+impl Default for ScopeConfig {
+    fn default() -> Self {
+        Self {
+            ttl_secs: default_scope_ttl_secs(),
+            persist: false,
+            prefetch: default_prefetch(),
+        }
+    }
+}
+
 fn default_socket_path() -> PathBuf {
-    PathBuf::from("/tmp/engram.sock")
+    paths::default_socket_path()
 }
 
 fn default_data_dir() -> PathBuf {
-    dirs::home_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join(".engram")
+    paths::data_dir()
 }
 
 fn default_max_memory() -> usize {
@@ -74,13 +563,89 @@ fn default_log_level() -> String {
 }
 
 fn default_pid_file() -> PathBuf {
-    PathBuf::from("/tmp/engram.pid")
+    paths::default_pid_file()
 }
 
 fn default_min_files() -> usize {
     10
 }
 
+fn default_compact_threshold() -> u64 {
+    4 * 1024 * 1024 // 4MB, below the 10MB log rotation size
+}
+
+fn default_tombstone_retention_days() -> u64 {
+    30
+}
+
+fn default_consolidate_every() -> usize {
+    50
+}
+
+fn default_keep_recent_experiences() -> usize {
+    20
+}
+
+fn default_enrichment_workers() -> usize {
+    4
+}
+
+fn default_enrichment_max_input() -> usize {
+    16 * 1024
+}
+
+fn default_api_max_tokens() -> u32 {
+    200
+}
+
+fn default_api_requests_per_minute() -> u32 {
+    60
+}
+
+fn default_snapshots_enabled() -> bool {
+    true
+}
+
+fn default_snapshot_interval_secs() -> u64 {
+    60 * 60 // 1 hour
+}
+
+fn default_snapshot_keep() -> usize {
+    3
+}
+
+fn default_supervisor_enabled() -> bool {
+    true
+}
+
+fn default_heartbeat_secs() -> u64 {
+    15
+}
+
+fn default_drain_secs() -> u64 {
+    10
+}
+
+fn default_scope_ttl_secs() -> u64 {
+    30 * 60 // 30 minutes
+}
+
+fn default_prefetch() -> bool {
+    true
+}
+
+fn default_watch_enabled() -> bool {
+    true
+}
+
+fn default_debounce_ms() -> u64 {
+    500
+}
+
+fn default_batch_ms() -> u64 {
+    1000
+}
+
 impl Default for DaemonConfig {
     fn default() -> Self {
         Self {
@@ -88,17 +653,49 @@ impl Default for DaemonConfig {
             data_dir: default_data_dir(),
             max_memory: default_max_memory(),
             max_projects: default_max_projects(),
+            pinned_projects: Vec::new(),
+            read_only_projects: Vec::new(),
             log_level: default_log_level(),
             pid_file: default_pid_file(),
             auto_init: AutoInitConfig::default(),
+            experiments: ExperimentFlags::default(),
+            skeleton: SkeletonOptions::default(),
+            slo: SloConfig::default(),
+            http_addr: None,
+            metrics_addr: None,
+            watch: WatchConfig::default(),
+            memory: MemoryConfig::default(),
+            auth: AuthConfig::default(),
+            enrichment: EnrichmentConfig::default(),
+            snapshots: SnapshotConfig::default(),
+            scopes: ScopeConfig::default(),
+            events: EventsConfig::default(),
+            supervisor: SupervisorConfig::default(),
+            shutdown: ShutdownConfig::default(),
+            gc: GcConfig::default(),
+            encryption: EncryptionConfig::default(),
+            storage: StorageConfig::default(),
         }
     }
 }
 
 impl DaemonConfig {
+    /// Default config file location
+    pub fn config_path() -> PathBuf {
+        paths::config_file()
+    }
+
     /// Load configuration from file, falling back to defaults
+    ///
+    /// `ENGRAM_SOCKET` overrides the socket path, as it does for clients.
     pub fn load() -> Self {
-        let config_path = default_data_dir().join("config.yaml");
+        let mut config = Self::load_file();
+        config.socket_path = paths::socket_path(Some(&config.socket_path));
+        config
+    }
+
+    fn load_file() -> Self {
+        let config_path = Self::config_path();
 
         if config_path.exists() {
             match std::fs::read_to_string(&config_path) {
@@ -117,6 +714,42 @@ impl DaemonConfig {
         Self::default()
     }
 
+    /// Re-read the config file at `path` as [`load`](Self::load) does,
+    /// failing rather than falling back to defaults when it is invalid.
+    ///
+    /// A missing file means the defaults.
+    pub fn reload_from(path: &PathBuf) -> Result<Self, std::io::Error> {
+        let mut config = if path.exists() {
+            Self::load_from(path)?
+        } else {
+            Self::default()
+        };
+        config.socket_path = paths::socket_path(Some(&config.socket_path));
+        Ok(config)
+    }
+
+    /// Dotted names of the settings that differ from `other`, e.g.
+    /// `enrichment.api.model`.
+    pub fn changed_fields(&self, other: &DaemonConfig) -> Vec<String> {
+        let (Ok(old), Ok(new)) = (serde_json::to_value(self), serde_json::to_value(other)) else {
+            return Vec::new();
+        };
+        let mut changed = Vec::new();
+        diff_values("", &old, &new, &mut changed);
+        changed
+    }
+
+    /// Whether a field named by [`changed_fields`](Self::changed_fields) is
+    /// applied on reload.
+    pub fn is_reloadable(field: &str) -> bool {
+        RELOADABLE_SETTINGS.iter().any(|setting| {
+            field == *setting
+                || field
+                    .strip_prefix(setting)
+                    .is_some_and(|rest| rest.starts_with('.'))
+        })
+    }
+
     /// Load configuration from a specific path
     pub fn load_from(path: &PathBuf) -> Result<Self, std::io::Error> {
         let content = std::fs::read_to_string(path)?;
@@ -124,6 +757,18 @@ impl DaemonConfig {
             .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
     }
 
+    /// Check settings that only make sense together.
+    pub fn validate(&self) -> Result<(), CoreError> {
+        // Peer credentials cannot be checked over TCP
+        if self.http_addr.is_some() && self.auth.token.is_none() {
+            return Err(CoreError::InvalidConfig(
+                "http_addr requires auth.token: HTTP bridge clients cannot be identified otherwise"
+                    .to_string(),
+            ));
+        }
+        Ok(())
+    }
+
     /// Get the projects directory
     pub fn projects_dir(&self) -> PathBuf {
         self.data_dir.join("projects")
@@ -133,10 +778,53 @@ impl DaemonConfig {
     pub fn ensure_dirs(&self) -> std::io::Result<()> {
         std::fs::create_dir_all(&self.data_dir)?;
         std::fs::create_dir_all(self.projects_dir())?;
+        // The runtime dir holds the socket, so only the user may enter it. A
+        // directory configured elsewhere (e.g. /tmp) is left as it is; the
+        // socket itself is closed to other users.
+        let runtime_dir = paths::runtime_dir();
+        for path in [&self.socket_path, &self.pid_file] {
+            match path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+                Some(dir) if dir == runtime_dir => paths::create_private_dir(dir)?,
+                Some(dir) => std::fs::create_dir_all(dir)?,
+                None => {}
+            }
+        }
         Ok(())
     }
 }
 
+/// Collect the dotted paths at which `old` and `new` differ; objects are
+/// compared key by key, anything else as a whole.
+fn diff_values(
+    prefix: &str,
+    old: &serde_json::Value,
+    new: &serde_json::Value,
+    changed: &mut Vec<String>,
+) {
+    use serde_json::Value;
+
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                match (old.get(key), new.get(key)) {
+                    (Some(old), Some(new)) => diff_values(&path, old, new, changed),
+                    _ => changed.push(path),
+                }
+            }
+        }
+        _ if old != new => changed.push(prefix.to_string()),
+        _ => {}
+    }
+}
+
 // Need serde_yaml for config loading
 #[cfg(test)]
 mod tests {
@@ -145,11 +833,126 @@ mod tests {
     #[test]
     fn test_default_config() {
         let config = DaemonConfig::default();
-        assert_eq!(config.socket_path, PathBuf::from("/tmp/engram.sock"));
+        assert_eq!(config.socket_path, paths::default_socket_path());
+        assert_eq!(config.pid_file.parent(), config.socket_path.parent());
         assert_eq!(config.max_memory, 100 * 1024 * 1024);
         assert_eq!(config.max_projects, 3);
     }
 
+    #[test]
+    fn test_validate_http_requires_token() {
+        let mut config = DaemonConfig::default();
+        assert!(config.validate().is_ok());
+        config.http_addr = Some("127.0.0.1:7878".parse().unwrap());
+        assert!(matches!(
+            config.validate(),
+            Err(CoreError::InvalidConfig(_))
+        ));
+        config.auth.token = Some("secret".to_string());
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_storage_config() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = DaemonConfig::default();
+        assert_eq!(config.storage.backend, StorageBackendKind::Filesystem);
+        let backend = config.storage.open(temp_dir.path()).unwrap();
+        assert_eq!(
+            backend.location("abc", "skeleton.json"),
+            temp_dir.path().join("abc/skeleton.json")
+        );
+
+        let config: DaemonConfig = serde_yaml::from_str("storage:\n  backend: sqlite\n").unwrap();
+        assert_eq!(config.storage.backend, StorageBackendKind::Sqlite);
+        let backend = config.storage.open(temp_dir.path());
+        #[cfg(feature = "sqlite")]
+        {
+            backend.unwrap();
+            assert!(temp_dir.path().join(SQLITE_FILE).exists());
+        }
+        #[cfg(not(feature = "sqlite"))]
+        assert!(backend.is_err());
+    }
+
+    #[test]
+    fn test_encryption_config() {
+        assert!(DaemonConfig::default()
+            .encryption
+            .cipher()
+            .unwrap()
+            .is_none());
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let key_file = temp_dir.path().join("log.key");
+        let config: DaemonConfig = serde_yaml::from_str(&format!(
+            "encryption:\n  key_file: {}\n",
+            key_file.display()
+        ))
+        .unwrap();
+        let cipher = config.encryption.cipher().unwrap().unwrap();
+        assert!(key_file.exists());
+        let again = config.encryption.cipher().unwrap().unwrap();
+        assert_eq!(cipher.key_id(), again.key_id());
+
+        let relative = EncryptionConfig {
+            key_file: Some(PathBuf::from("log.key")),
+            keychain: false,
+        };
+        assert!(relative.cipher().is_err());
+    }
+
+    #[test]
+    fn test_config_experiments_from_yaml() {
+        let config: DaemonConfig =
+            serde_yaml::from_str("max_projects: 5\nexperiments:\n  group_commit_writes: true\n")
+                .unwrap();
+        assert!(config
+            .experiments
+            .is_enabled(crate::Experiment::GroupCommitWrites));
+        assert!(!config
+            .experiments
+            .is_enabled(crate::Experiment::SemanticRetrieval));
+    }
+
+    #[test]
+    fn test_config_summarizer_from_yaml() {
+        let config: DaemonConfig = serde_yaml::from_str(
+            "enrichment:\n  backend: openai\n  api:\n    base_url: http://localhost:11434/v1\n    requests_per_minute: 0\n",
+        )
+        .unwrap();
+        assert_eq!(config.enrichment.backend, Some(SummarizerBackend::OpenAi));
+        assert_eq!(config.enrichment.api.requests_per_minute, 0);
+        assert_eq!(config.enrichment.api.max_tokens, 200);
+        assert_eq!(DaemonConfig::default().enrichment.backend, None);
+    }
+
+    #[test]
+    fn test_changed_fields() {
+        let old = DaemonConfig::default();
+        let new: DaemonConfig = serde_yaml::from_str(
+            "max_projects: 5\nlog_level: debug\nenrichment:\n  api:\n    requests_per_minute: 10\n",
+        )
+        .unwrap();
+
+        let changed = old.changed_fields(&new);
+        assert_eq!(
+            changed,
+            vec![
+                "enrichment.api.requests_per_minute",
+                "log_level",
+                "max_projects"
+            ]
+        );
+        let reloadable: Vec<bool> = changed
+            .iter()
+            .map(|field| DaemonConfig::is_reloadable(field))
+            .collect();
+        assert_eq!(reloadable, vec![true, false, true]);
+        assert!(!DaemonConfig::is_reloadable("max_projects_extra"));
+        assert!(old.changed_fields(&old.clone()).is_empty());
+    }
+
     #[test]
     fn test_config_serialization() {
         let config = DaemonConfig::default();
@@ -1,5 +1,6 @@
 //! Core error types for Engram.
 
+use engram_ipc::{io_error_details, DescribeError, ErrorDetails, ErrorKind};
 use thiserror::Error;
 
 /// Errors that can occur in core operations
@@ -28,4 +29,74 @@ pub enum CoreError {
     /// Storage error
     #[error("Storage error: {0}")]
     Storage(String),
+
+    /// Invalid glob pattern
+    #[error("Invalid glob: {0}")]
+    InvalidGlob(String),
+
+    /// Invalid `.engram/workspace.toml`
+    #[error("Invalid workspace: {0}")]
+    InvalidWorkspace(String),
+
+    /// Invalid daemon configuration
+    #[error("Invalid config: {0}")]
+    InvalidConfig(String),
+
+    /// Project is read-only and the operation would write to it
+    #[error("Project is read-only: {0}")]
+    ReadOnly(String),
+
+    /// Operation was cancelled
+    #[error("Cancelled")]
+    Cancelled,
+}
+
+impl DescribeError for CoreError {
+    fn details(&self) -> ErrorDetails {
+        match self {
+            CoreError::NotInitialized(_) => {
+                ErrorDetails::new(ErrorKind::NotInitialized).with_field("cwd")
+            }
+            CoreError::AlreadyInitialized(_) => {
+                ErrorDetails::new(ErrorKind::Conflict).with_field("cwd")
+            }
+            CoreError::Io(e) => io_error_details(e),
+            CoreError::Serialization(_) | CoreError::Storage(_) => {
+                ErrorDetails::new(ErrorKind::Storage)
+            }
+            CoreError::InvalidPath(_) => {
+                ErrorDetails::new(ErrorKind::InvalidInput).with_field("cwd")
+            }
+            CoreError::InvalidGlob(_) => {
+                ErrorDetails::new(ErrorKind::InvalidInput).with_field("globs")
+            }
+            CoreError::InvalidWorkspace(_) | CoreError::InvalidConfig(_) => {
+                ErrorDetails::new(ErrorKind::InvalidInput)
+            }
+            CoreError::ReadOnly(_) => ErrorDetails::new(ErrorKind::ReadOnly),
+            CoreError::Cancelled => ErrorDetails::new(ErrorKind::Cancelled),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engram_ipc::{ErrorCode, Response};
+
+    #[test]
+    fn test_error_details() {
+        let response = Response::failure(&CoreError::InvalidGlob("[".to_string()));
+        let Response::Error { code, details, .. } = response else {
+            panic!("Expected error response");
+        };
+        assert_eq!(code, ErrorCode::InvalidRequest);
+        let details = details.unwrap();
+        assert_eq!(details.kind, ErrorKind::InvalidInput);
+        assert_eq!(details.field.as_deref(), Some("globs"));
+        assert!(!details.retryable);
+
+        let interrupted = std::io::Error::new(std::io::ErrorKind::Interrupted, "interrupted");
+        assert!(CoreError::Io(interrupted).details().retryable);
+    }
 }
@@ -0,0 +1,277 @@
+//! Journal of significant daemon events.
+//!
+//! Project loads, index runs, failed requests and memory compactions are
+//! kept in a bounded in-memory ring buffer, so `engram logs` can show what
+//! the daemon did without digging through its log file. With the journal
+//! enabled, events are also appended to a JSON-lines file and the most
+//! recent ones are reloaded on startup.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Journal file name in the data directory.
+pub const EVENTS_FILE: &str = "events.jsonl";
+
+/// Event journal configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventsConfig {
+    /// Events kept in memory
+    #[serde(default = "default_capacity")]
+    pub capacity: usize,
+
+    /// Also append events to `events.jsonl` in the data directory
+    #[serde(default)]
+    pub journal: bool,
+}
+
+impl Default for EventsConfig {
+    fn default() -> Self {
+        Self {
+            capacity: default_capacity(),
+            journal: false,
+        }
+    }
+}
+
+fn default_capacity() -> usize {
+    1000
+}
+
+/// Kind of daemon event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    /// A project was loaded from disk
+    ProjectLoaded,
+    /// A project was indexed
+    IndexRun,
+    /// A request or background task failed
+    Error,
+    /// A project's memory log was compacted
+    MemoryCompacted,
+    /// A project's experiences were deduplicated and summarized
+    ExperiencesConsolidated,
+    /// Memory from another machine was imported into a project
+    MemoryImported,
+    /// A project was found at a new path and its storage relinked
+    ProjectRelinked,
+    /// A project's stored data was replaced from an archive
+    ProjectImported,
+    /// The daemon recovered from an unclean shutdown
+    Recovered,
+    /// Stored data of a deleted, moved or abandoned project was removed
+    GarbageCollected,
+    /// The daemon config was re-read and changes applied
+    ConfigReloaded,
+}
+
+impl EventKind {
+    /// Display name of the event kind.
+    pub fn name(&self) -> &'static str {
+        match self {
+            EventKind::ProjectLoaded => "project_loaded",
+            EventKind::IndexRun => "index_run",
+            EventKind::Error => "error",
+            EventKind::MemoryCompacted => "memory_compacted",
+            EventKind::ExperiencesConsolidated => "experiences_consolidated",
+            EventKind::MemoryImported => "memory_imported",
+            EventKind::ProjectRelinked => "project_relinked",
+            EventKind::ProjectImported => "project_imported",
+            EventKind::Recovered => "recovered",
+            EventKind::GarbageCollected => "garbage_collected",
+            EventKind::ConfigReloaded => "config_reloaded",
+        }
+    }
+}
+
+/// A recorded event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Event {
+    /// Sequence number, increasing across restarts when journaled
+    pub seq: u64,
+    pub time: DateTime<Utc>,
+    pub kind: EventKind,
+    /// Project the event concerns, if any
+    pub project: Option<PathBuf>,
+    pub message: String,
+}
+
+/// Ring buffer of recent events, optionally backed by a journal file.
+pub struct EventLog {
+    capacity: usize,
+    journal: Option<PathBuf>,
+    state: Mutex<EventState>,
+}
+
+#[derive(Default)]
+struct EventState {
+    events: VecDeque<Event>,
+    next_seq: u64,
+}
+
+impl EventLog {
+    /// Create an in-memory log keeping the last `capacity` events.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            journal: None,
+            state: Mutex::new(EventState {
+                events: VecDeque::new(),
+                next_seq: 1,
+            }),
+        }
+    }
+
+    /// Create a log from configuration, journaling to `data_dir` if enabled.
+    pub fn from_config(config: &EventsConfig, data_dir: &Path) -> Self {
+        let log = Self::new(config.capacity);
+        if config.journal {
+            log.with_journal(data_dir.join(EVENTS_FILE))
+        } else {
+            log
+        }
+    }
+
+    /// Append events to `path`, first reloading its most recent events.
+    ///
+    /// The file is rewritten to hold only the reloaded events, which keeps
+    /// it bounded across restarts. Unreadable lines are skipped.
+    pub fn with_journal(mut self, path: PathBuf) -> Self {
+        {
+            let state = self.state.get_mut().unwrap();
+            if let Ok(content) = std::fs::read_to_string(&path) {
+                for event in content
+                    .lines()
+                    .filter_map(|line| serde_json::from_str::<Event>(line).ok())
+                {
+                    state.next_seq = state.next_seq.max(event.seq + 1);
+                    state.events.push_back(event);
+                    if state.events.len() > self.capacity {
+                        state.events.pop_front();
+                    }
+                }
+            }
+            if let Err(e) = rewrite_journal(&path, &state.events) {
+                tracing::warn!(path = ?path, error = %e, "Failed to rewrite event journal");
+            }
+        }
+        self.journal = Some(path);
+        self
+    }
+
+    /// Record an event.
+    pub fn record(&self, kind: EventKind, project: Option<&Path>, message: impl Into<String>) {
+        let mut state = self.state.lock().unwrap();
+        let event = Event {
+            seq: state.next_seq,
+            time: Utc::now(),
+            kind,
+            project: project.map(Path::to_path_buf),
+            message: message.into(),
+        };
+        state.next_seq += 1;
+
+        if let Some(path) = &self.journal {
+            if let Err(e) = append_journal(path, &event) {
+                tracing::warn!(path = ?path, error = %e, "Failed to append to event journal");
+            }
+        }
+        state.events.push_back(event);
+        if state.events.len() > self.capacity {
+            state.events.pop_front();
+        }
+    }
+
+    /// The latest `limit` events after sequence number `since`, oldest
+    /// first.
+    pub fn since(&self, since: u64, limit: usize) -> Vec<Event> {
+        let state = self.state.lock().unwrap();
+        let newer: Vec<&Event> = state
+            .events
+            .iter()
+            .filter(|event| event.seq > since)
+            .collect();
+        newer[newer.len().saturating_sub(limit)..]
+            .iter()
+            .map(|event| (*event).clone())
+            .collect()
+    }
+}
+
+fn append_journal(path: &Path, event: &Event) -> std::io::Result<()> {
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{}", serde_json::to_string(event)?)
+}
+
+fn rewrite_journal(path: &Path, events: &VecDeque<Event>) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut content = String::new();
+    for event in events {
+        content.push_str(&serde_json::to_string(event)?);
+        content.push('\n');
+    }
+    let temp_path = path.with_extension("jsonl.tmp");
+    std::fs::write(&temp_path, content)?;
+    std::fs::rename(&temp_path, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_ring_buffer() {
+        let log = EventLog::new(3);
+        for i in 0..5 {
+            log.record(EventKind::IndexRun, None, format!("run {}", i));
+        }
+
+        let events = log.since(0, 10);
+        assert_eq!(
+            events.iter().map(|e| e.seq).collect::<Vec<_>>(),
+            vec![3, 4, 5]
+        );
+        assert_eq!(events[0].message, "run 2");
+        assert_eq!(log.since(4, 10).len(), 1);
+        assert_eq!(log.since(0, 2)[0].seq, 4);
+        assert!(log.since(5, 10).is_empty());
+    }
+
+    #[test]
+    fn test_journal_survives_restart() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join(EVENTS_FILE);
+
+        let log = EventLog::new(2).with_journal(path.clone());
+        log.record(EventKind::ProjectLoaded, Some(Path::new("/a")), "loaded");
+        log.record(EventKind::Error, None, "failed");
+        log.record(
+            EventKind::MemoryCompacted,
+            Some(Path::new("/a")),
+            "compacted",
+        );
+        drop(log);
+
+        let log = EventLog::new(2).with_journal(path.clone());
+        let events = log.since(0, 10);
+        assert_eq!(
+            events.iter().map(|e| e.kind).collect::<Vec<_>>(),
+            vec![EventKind::Error, EventKind::MemoryCompacted]
+        );
+        assert_eq!(events[1].project, Some(PathBuf::from("/a")));
+
+        log.record(EventKind::IndexRun, None, "indexed");
+        assert_eq!(log.since(0, 10).last().unwrap().seq, 4);
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 3);
+    }
+}
@@ -0,0 +1,209 @@
+//! Experiment flags for gradual rollout of risky subsystems.
+//!
+//! Flags are configured under `experiments:` in the daemon config and can be
+//! overridden per project in the project manifest. The daemon re-reads the
+//! config file when it changes, so flags can be toggled without a restart.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::SystemTime;
+
+/// An experimental subsystem that can be toggled at runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Experiment {
+    /// Embedding-based retrieval alongside tree navigation
+    SemanticRetrieval,
+    /// Re-index projects on file change notifications
+    IncrementalIndexing,
+    /// Batch fsyncs of durable log writes
+    GroupCommitWrites,
+}
+
+impl Experiment {
+    /// All known experiments.
+    pub const ALL: [Experiment; 3] = [
+        Experiment::SemanticRetrieval,
+        Experiment::IncrementalIndexing,
+        Experiment::GroupCommitWrites,
+    ];
+
+    /// Config key of the experiment.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Experiment::SemanticRetrieval => "semantic_retrieval",
+            Experiment::IncrementalIndexing => "incremental_indexing",
+            Experiment::GroupCommitWrites => "group_commit_writes",
+        }
+    }
+}
+
+/// Explicit flag values; experiments not listed are disabled.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ExperimentFlags(BTreeMap<Experiment, bool>);
+
+impl ExperimentFlags {
+    /// Check whether an experiment is enabled.
+    pub fn is_enabled(&self, experiment: Experiment) -> bool {
+        self.0.get(&experiment).copied().unwrap_or(false)
+    }
+
+    /// Set an experiment explicitly.
+    pub fn set(&mut self, experiment: Experiment, enabled: bool) {
+        self.0.insert(experiment, enabled);
+    }
+
+    /// Explicit value for an experiment, if any.
+    pub fn get(&self, experiment: Experiment) -> Option<bool> {
+        self.0.get(&experiment).copied()
+    }
+
+    /// Apply per-project overrides on top of these flags.
+    pub fn with_overrides(&self, overrides: &ExperimentFlags) -> ExperimentFlags {
+        let mut merged = self.clone();
+        for (experiment, enabled) in &overrides.0 {
+            merged.set(*experiment, *enabled);
+        }
+        merged
+    }
+}
+
+/// Daemon-wide experiment flags, reloaded when the config file changes.
+pub struct Experiments {
+    config_path: Option<PathBuf>,
+    state: RwLock<ExperimentsState>,
+}
+
+struct ExperimentsState {
+    flags: ExperimentFlags,
+    modified: Option<SystemTime>,
+}
+
+impl Experiments {
+    /// Fixed flags that never reload.
+    pub fn fixed(flags: ExperimentFlags) -> Self {
+        Self {
+            config_path: None,
+            state: RwLock::new(ExperimentsState {
+                flags,
+                modified: None,
+            }),
+        }
+    }
+
+    /// Flags backed by a config file, starting from `flags`.
+    pub fn from_config_file(flags: ExperimentFlags, config_path: PathBuf) -> Self {
+        let modified = modified_time(&config_path);
+        Self {
+            config_path: Some(config_path),
+            state: RwLock::new(ExperimentsState { flags, modified }),
+        }
+    }
+
+    /// Current flags, re-reading the config file if it changed.
+    ///
+    /// A config file that fails to parse keeps the previous flags.
+    pub fn current(&self) -> ExperimentFlags {
+        if let Some(path) = &self.config_path {
+            let modified = modified_time(path);
+            let stale = self.state.read().unwrap().modified != modified;
+            if stale {
+                let mut state = self.state.write().unwrap();
+                match crate::DaemonConfig::load_from(path) {
+                    Ok(config) => {
+                        tracing::info!(path = ?path, "Reloaded experiment flags");
+                        state.flags = config.experiments;
+                    }
+                    Err(e) => {
+                        tracing::warn!(path = ?path, error = %e, "Failed to reload experiment flags");
+                    }
+                }
+                state.modified = modified;
+            }
+        }
+        self.state.read().unwrap().flags.clone()
+    }
+
+    /// Check an experiment, applying per-project overrides.
+    pub fn is_enabled(&self, experiment: Experiment, overrides: &ExperimentFlags) -> bool {
+        overrides
+            .get(experiment)
+            .unwrap_or_else(|| self.current().is_enabled(experiment))
+    }
+}
+
+impl Default for Experiments {
+    fn default() -> Self {
+        Self::fixed(ExperimentFlags::default())
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_flags_default_disabled() {
+        let flags = ExperimentFlags::default();
+        for experiment in Experiment::ALL {
+            assert!(!flags.is_enabled(experiment));
+        }
+    }
+
+    #[test]
+    fn test_project_overrides() {
+        let mut global = ExperimentFlags::default();
+        global.set(Experiment::SemanticRetrieval, true);
+        let mut overrides = ExperimentFlags::default();
+        overrides.set(Experiment::SemanticRetrieval, false);
+        overrides.set(Experiment::IncrementalIndexing, true);
+
+        let merged = global.with_overrides(&overrides);
+        assert!(!merged.is_enabled(Experiment::SemanticRetrieval));
+        assert!(merged.is_enabled(Experiment::IncrementalIndexing));
+
+        let experiments = Experiments::fixed(global);
+        assert!(!experiments.is_enabled(Experiment::SemanticRetrieval, &overrides));
+        assert!(experiments.is_enabled(Experiment::IncrementalIndexing, &overrides));
+    }
+
+    #[test]
+    fn test_flags_yaml_format() {
+        let flags: ExperimentFlags =
+            serde_yaml::from_str("incremental_indexing: true\ngroup_commit_writes: false\n")
+                .unwrap();
+        assert!(flags.is_enabled(Experiment::IncrementalIndexing));
+        assert_eq!(flags.get(Experiment::GroupCommitWrites), Some(false));
+        assert_eq!(flags.get(Experiment::SemanticRetrieval), None);
+    }
+
+    #[test]
+    fn test_reload_on_config_change() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("config.yaml");
+        std::fs::write(&path, "experiments:\n  semantic_retrieval: false\n").unwrap();
+
+        let experiments = Experiments::from_config_file(ExperimentFlags::default(), path.clone());
+        assert!(!experiments
+            .current()
+            .is_enabled(Experiment::SemanticRetrieval));
+
+        std::fs::write(&path, "experiments:\n  semantic_retrieval: true\n").unwrap();
+        // Force a distinct mtime even on coarse-grained filesystems.
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + std::time::Duration::from_secs(5))
+            .unwrap();
+
+        assert!(experiments
+            .current()
+            .is_enabled(Experiment::SemanticRetrieval));
+    }
+}
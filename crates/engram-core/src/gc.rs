@@ -0,0 +1,307 @@
+//! Garbage collection of stored project data.
+//!
+//! A project leaves two directories in the data dir: its stored trees and
+//! logs (`<hash>/`) and its manifest (`projects/<hash>/`). Directories are
+//! grouped by the project root they record. A project is garbage when none
+//! of its files were written for `max_idle_days`; loaded projects flush
+//! their manifest when unloaded and on shutdown, so an idle project is one
+//! nobody used. A project whose root is gone is garbage much sooner, after
+//! `missing_grace_days`: the grace period spares roots on a volume that is
+//! briefly not mounted.
+
+use crate::CoreError;
+use engram_indexer::storage::Storage;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Directory of project manifests in the data dir
+const MANIFESTS_DIR: &str = "projects";
+
+/// Garbage collection configuration
+///
+/// `engram gc` reports and deletes on demand whatever this is set to; with
+/// `enabled`, the daemon also deletes garbage every `interval_secs`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GcConfig {
+    /// Collect garbage periodically in the daemon
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Seconds between periodic collections
+    #[serde(default = "default_gc_interval_secs")]
+    pub interval_secs: u64,
+
+    /// Days without writes after which a project is garbage; 0 keeps every
+    /// project whose root exists
+    #[serde(default = "default_max_idle_days")]
+    pub max_idle_days: u64,
+
+    /// Days without writes after which a project whose root is gone is
+    /// garbage; 0 collects it right away
+    #[serde(default = "default_missing_grace_days")]
+    pub missing_grace_days: u64,
+}
+
+impl Default for GcConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: default_gc_interval_secs(),
+            max_idle_days: default_max_idle_days(),
+            missing_grace_days: default_missing_grace_days(),
+        }
+    }
+}
+
+fn default_gc_interval_secs() -> u64 {
+    24 * 60 * 60 // 1 day
+}
+
+fn default_max_idle_days() -> u64 {
+    90
+}
+
+fn default_missing_grace_days() -> u64 {
+    1
+}
+
+/// Why a stored project is garbage
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GcReason {
+    /// Its project root no longer exists, past the grace period
+    SourceMissing,
+    /// Nothing was written for longer than the idle limit
+    Idle,
+    /// Its project root cannot be read, and it is idle
+    Unreadable,
+}
+
+impl GcReason {
+    /// Display name of the reason.
+    pub fn name(&self) -> &'static str {
+        match self {
+            GcReason::SourceMissing => "source_missing",
+            GcReason::Idle => "idle",
+            GcReason::Unreadable => "unreadable",
+        }
+    }
+}
+
+/// A stored project that can be deleted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GcCandidate {
+    /// Project root recorded in its stored data, if readable
+    pub root: Option<PathBuf>,
+    /// Its directories, relative to the data dir
+    pub dirs: Vec<PathBuf>,
+    pub reason: GcReason,
+    /// Bytes freed by deleting it
+    pub bytes: u64,
+    /// Whole days since any of its files was written
+    pub idle_days: u64,
+}
+
+/// Outcome of [`ProjectManager::collect_garbage`](crate::ProjectManager::collect_garbage)
+#[derive(Debug, Clone, Default)]
+pub struct GcReport {
+    /// Garbage found, by project root
+    pub candidates: Vec<GcCandidate>,
+    /// Candidates deleted (0 for a dry run)
+    pub deleted: usize,
+}
+
+impl GcReport {
+    /// Bytes the candidates take up
+    pub fn reclaimable_bytes(&self) -> u64 {
+        self.candidates.iter().map(|c| c.bytes).sum()
+    }
+}
+
+/// Find the stored projects of `storage`, whose base dir is the data dir,
+/// that are garbage under `config`, keeping those rooted at one of
+/// `in_use`.
+///
+/// Sizes and roots are checked on a blocking thread, since both walk the
+/// file system.
+pub(crate) async fn find_garbage(
+    storage: &Storage,
+    config: GcConfig,
+    in_use: Vec<PathBuf>,
+) -> Result<Vec<GcCandidate>, CoreError> {
+    let mut dirs: Vec<(PathBuf, Option<PathBuf>)> = Vec::new();
+    for hash in storage
+        .stored_projects()
+        .await
+        .map_err(|e| CoreError::Storage(e.to_string()))?
+    {
+        let root = storage.stored_root(&hash).await;
+        dirs.push((PathBuf::from(hash), root));
+    }
+
+    let data_dir = storage.base_dir().to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        for hash in manifest_dirs(&data_dir)? {
+            let rel = Path::new(MANIFESTS_DIR).join(hash);
+            let root = manifest_root(&data_dir.join(&rel));
+            dirs.push((rel, root));
+        }
+        Ok(candidates(&data_dir, dirs, &config, &in_use))
+    })
+    .await
+    .map_err(|e| CoreError::Storage(e.to_string()))?
+}
+
+/// Group `dirs` by the root they record and pick the garbage groups.
+fn candidates(
+    data_dir: &Path,
+    dirs: Vec<(PathBuf, Option<PathBuf>)>,
+    config: &GcConfig,
+    in_use: &[PathBuf],
+) -> Vec<GcCandidate> {
+    let mut by_root: BTreeMap<PathBuf, Vec<PathBuf>> = BTreeMap::new();
+    let mut groups = Vec::new();
+    for (rel, root) in dirs {
+        match root {
+            Some(root) => by_root.entry(root).or_default().push(rel),
+            None => groups.push((None, vec![rel])),
+        }
+    }
+    groups.extend(by_root.into_iter().map(|(root, dirs)| (Some(root), dirs)));
+
+    let now = SystemTime::now();
+    let mut candidates = Vec::new();
+    for (root, dirs) in groups {
+        if root.as_ref().is_some_and(|root| in_use.contains(root)) {
+            continue;
+        }
+        let (mut bytes, mut newest) = (0, SystemTime::UNIX_EPOCH);
+        for dir in &dirs {
+            let (size, modified) = dir_usage(&data_dir.join(dir));
+            bytes += size;
+            newest = newest.max(modified);
+        }
+        let idle_days = now
+            .duration_since(newest)
+            .map(|idle| idle.as_secs() / (24 * 60 * 60))
+            .unwrap_or(0);
+
+        // Only a root known not to exist is missing, not one that cannot
+        // be checked
+        let reason = match &root {
+            Some(root) => match std::fs::metadata(root) {
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => GcReason::SourceMissing,
+                _ => GcReason::Idle,
+            },
+            None => GcReason::Unreadable,
+        };
+        let garbage = match reason {
+            GcReason::SourceMissing => idle_days >= config.missing_grace_days,
+            _ => config.max_idle_days > 0 && idle_days >= config.max_idle_days,
+        };
+        if !garbage {
+            continue;
+        }
+        candidates.push(GcCandidate {
+            root,
+            dirs,
+            reason,
+            bytes,
+            idle_days,
+        });
+    }
+    candidates
+}
+
+/// Move a candidate's directories out of the way, to be deleted by
+/// [`remove_dirs`]. Renaming is quick enough to do under the project
+/// cache's lock; a project loaded afterwards starts afresh.
+pub(crate) fn set_aside(
+    data_dir: &Path,
+    candidate: &GcCandidate,
+) -> Result<Vec<PathBuf>, CoreError> {
+    let mut moved = Vec::new();
+    for dir in &candidate.dirs {
+        let path = data_dir.join(dir);
+        let name = dir.file_name().unwrap_or_default().to_string_lossy();
+        let aside = path.with_file_name(format!(".{}.gc", name));
+        remove_dir(&aside)?;
+        match std::fs::rename(&path, &aside) {
+            Ok(()) => moved.push(aside),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(moved)
+}
+
+/// Delete directories set aside by [`set_aside`].
+pub(crate) async fn remove_dirs(dirs: Vec<PathBuf>) -> Result<(), CoreError> {
+    tokio::task::spawn_blocking(move || dirs.iter().try_for_each(|dir| remove_dir(dir)))
+        .await
+        .map_err(|e| CoreError::Storage(e.to_string()))?
+}
+
+fn remove_dir(dir: &Path) -> Result<(), CoreError> {
+    match std::fs::remove_dir_all(dir) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Names of the manifest directories, sorted.
+fn manifest_dirs(data_dir: &Path) -> Result<Vec<String>, CoreError> {
+    let entries = match std::fs::read_dir(data_dir.join(MANIFESTS_DIR)) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut names: Vec<String> = entries
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_dir()))
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+/// Project root recorded in the manifest of a manifest directory.
+fn manifest_root(dir: &Path) -> Option<PathBuf> {
+    #[derive(Deserialize)]
+    struct Root {
+        project_path: PathBuf,
+    }
+
+    let content = std::fs::read(dir.join("manifest.json")).ok()?;
+    serde_json::from_slice::<Root>(&content)
+        .ok()
+        .map(|root| root.project_path)
+}
+
+/// Total size of the files under `dir`, and when the newest was written.
+fn dir_usage(dir: &Path) -> (u64, SystemTime) {
+    let mut newest = std::fs::metadata(dir)
+        .and_then(|meta| meta.modified())
+        .unwrap_or(SystemTime::UNIX_EPOCH);
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return (0, newest);
+    };
+    let mut bytes = 0;
+    for entry in entries.flatten() {
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        if meta.is_dir() {
+            let (size, modified) = dir_usage(&entry.path());
+            bytes += size;
+            newest = newest.max(modified);
+        } else {
+            bytes += meta.len();
+            if let Ok(modified) = meta.modified() {
+                newest = newest.max(modified);
+            }
+        }
+    }
+    (bytes, newest)
+}
@@ -0,0 +1,267 @@
+//! Registry of long-running background jobs.
+//!
+//! Indexing and enrichment register themselves here while they run, so they
+//! can be listed with their progress and cancelled without stopping the
+//! daemon. Cancellation is cooperative: the job checks its progress handle
+//! and stops at the next safe point.
+//!
+//! The registry also serializes writes to a project's stored files: an
+//! exclusive job waits, listed as queued, until no other writer holds the
+//! project. Readers never take the lock.
+
+use engram_indexer::scanner::{ScanPhase, ScanProgress};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::sync::OwnedMutexGuard;
+
+/// Kind of background job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobKind {
+    /// Scanning and persisting the project tree
+    Indexing,
+    /// Generating summaries for tree nodes
+    Enrichment,
+}
+
+impl JobKind {
+    /// Display name of the job kind.
+    pub fn name(&self) -> &'static str {
+        match self {
+            JobKind::Indexing => "indexing",
+            JobKind::Enrichment => "enrichment",
+        }
+    }
+}
+
+/// Snapshot of a running job.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobStatus {
+    pub id: u64,
+    pub kind: JobKind,
+    pub project: PathBuf,
+    /// Items processed so far
+    pub processed: usize,
+    /// Items to process (0 while still discovering)
+    pub total: usize,
+    pub elapsed_secs: u64,
+    /// Current phase of the job
+    pub phase: ScanPhase,
+    /// Estimated seconds left in the current phase, once measurable
+    pub eta_secs: Option<u64>,
+    /// Cancellation was requested but the job has not stopped yet
+    pub cancelling: bool,
+    /// Waiting for another job writing the same project to finish
+    pub queued: bool,
+}
+
+struct Job {
+    kind: JobKind,
+    project: PathBuf,
+    started: Instant,
+    progress: Arc<ScanProgress>,
+    queued: bool,
+}
+
+/// Running jobs, keyed by id.
+#[derive(Default)]
+pub struct JobRegistry {
+    jobs: Mutex<BTreeMap<u64, Job>>,
+    next_id: AtomicU64,
+    /// Write lock of each project, keyed by its canonical path
+    writers: Mutex<HashMap<PathBuf, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+/// Exclusive write access to a project's stored files, released on drop.
+pub struct ProjectLock {
+    _guard: OwnedMutexGuard<()>,
+}
+
+impl JobRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a job; it is removed when the returned guard is dropped.
+    pub fn start(self: &Arc<Self>, kind: JobKind, project: &Path) -> JobGuard {
+        self.register(kind, project, false)
+    }
+
+    /// Register a job that writes the project's stored files, then wait
+    /// until it holds the project's write lock.
+    ///
+    /// The job is listed as queued while it waits. It holds the lock until
+    /// the returned guard is dropped.
+    pub async fn start_exclusive(self: &Arc<Self>, kind: JobKind, project: &Path) -> JobGuard {
+        let mut job = self.register(kind, project, true);
+        let lock = self.lock_project(project).await;
+        if let Some(entry) = self.jobs.lock().unwrap().get_mut(&job.id) {
+            entry.queued = false;
+        }
+        tracing::debug!(id = job.id, project = ?project, "Job acquired project lock");
+        job.lock = Some(lock);
+        job
+    }
+
+    /// Wait for exclusive write access to a project's stored files.
+    pub async fn lock_project(&self, project: &Path) -> ProjectLock {
+        let writer = {
+            let mut writers = self.writers.lock().unwrap();
+            // Forget locks nobody holds or waits for
+            writers.retain(|_, writer| Arc::strong_count(writer) > 1);
+            writers.entry(project.to_path_buf()).or_default().clone()
+        };
+        ProjectLock {
+            _guard: writer.lock_owned().await,
+        }
+    }
+
+    fn register(self: &Arc<Self>, kind: JobKind, project: &Path, queued: bool) -> JobGuard {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let progress = Arc::new(ScanProgress::new());
+        self.jobs.lock().unwrap().insert(
+            id,
+            Job {
+                kind,
+                project: project.to_path_buf(),
+                started: Instant::now(),
+                progress: progress.clone(),
+                queued,
+            },
+        );
+        tracing::debug!(id, kind = kind.name(), project = ?project, "Job started");
+
+        JobGuard {
+            id,
+            progress,
+            registry: self.clone(),
+            lock: None,
+        }
+    }
+
+    /// Running jobs, oldest first.
+    pub fn list(&self) -> Vec<JobStatus> {
+        self.jobs
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, job)| JobStatus {
+                id: *id,
+                kind: job.kind,
+                project: job.project.clone(),
+                processed: job.progress.processed(),
+                total: job.progress.total(),
+                elapsed_secs: job.started.elapsed().as_secs(),
+                phase: job.progress.phase(),
+                eta_secs: job.progress.eta().map(|eta| eta.as_secs()),
+                cancelling: job.progress.is_cancelled(),
+                queued: job.queued,
+            })
+            .collect()
+    }
+
+    /// Request cancellation of a job. Returns false if no such job is running.
+    pub fn cancel(&self, id: u64) -> bool {
+        match self.jobs.lock().unwrap().get(&id) {
+            Some(job) => {
+                job.progress.cancel();
+                tracing::info!(id, kind = job.kind.name(), project = ?job.project, "Job cancellation requested");
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Keeps a job registered while it runs.
+pub struct JobGuard {
+    id: u64,
+    progress: Arc<ScanProgress>,
+    registry: Arc<JobRegistry>,
+    lock: Option<ProjectLock>,
+}
+
+impl JobGuard {
+    /// Id of the job.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Progress handle to pass to the work being tracked.
+    pub fn progress(&self) -> Arc<ScanProgress> {
+        self.progress.clone()
+    }
+}
+
+impl Drop for JobGuard {
+    fn drop(&mut self) {
+        self.registry.jobs.lock().unwrap().remove(&self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_lifecycle() {
+        let registry = Arc::new(JobRegistry::new());
+        let guard = registry.start(JobKind::Indexing, Path::new("/project"));
+
+        let jobs = registry.list();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].id, guard.id());
+        assert_eq!(jobs[0].kind, JobKind::Indexing);
+        assert!(!jobs[0].cancelling);
+
+        assert!(registry.cancel(guard.id()));
+        assert!(guard.progress().is_cancelled());
+        assert!(registry.list()[0].cancelling);
+
+        drop(guard);
+        assert!(registry.list().is_empty());
+        assert!(!registry.cancel(1));
+    }
+
+    #[tokio::test]
+    async fn test_exclusive_jobs_queue_per_project() {
+        let registry = Arc::new(JobRegistry::new());
+        let first = registry
+            .start_exclusive(JobKind::Indexing, Path::new("/project"))
+            .await;
+        // Other projects are unaffected
+        let other = registry
+            .start_exclusive(JobKind::Indexing, Path::new("/other"))
+            .await;
+
+        let waiting = tokio::spawn({
+            let registry = registry.clone();
+            async move {
+                registry
+                    .start_exclusive(JobKind::Indexing, Path::new("/project"))
+                    .await
+                    .id()
+            }
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        let queued: Vec<_> = registry.list().into_iter().filter(|j| j.queued).collect();
+        assert_eq!(queued.len(), 1);
+        assert_ne!(queued[0].id, first.id());
+        assert!(!waiting.is_finished());
+
+        drop(first);
+        let id = waiting.await.unwrap();
+        assert!(id > other.id());
+        assert!(registry.list().iter().all(|job| !job.queued));
+
+        let wait = std::time::Duration::from_millis(50);
+        let lock = tokio::time::timeout(wait, registry.lock_project(Path::new("/other"))).await;
+        assert!(lock.is_err());
+        drop(other);
+        let lock = tokio::time::timeout(wait, registry.lock_project(Path::new("/other"))).await;
+        assert!(lock.is_ok());
+    }
+}
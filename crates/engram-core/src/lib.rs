@@ -3,14 +3,40 @@
 //! This crate provides the core functionality for the Engram daemon,
 //! including project management, configuration, and storage.
 
+mod archive;
 mod config;
 mod error;
+mod events;
+mod flags;
+mod gc;
+mod jobs;
 mod metrics;
 mod project;
+mod project_config;
 mod project_manager;
+mod slo;
+mod workspace;
 
-pub use config::DaemonConfig;
+pub use archive::{ArchiveInfo, ArchiveReport, ARCHIVE_FORMAT};
+pub use config::{
+    AuthConfig, DaemonConfig, EncryptionConfig, EnrichmentConfig, MemoryConfig, ScopeConfig,
+    ShutdownConfig, SnapshotConfig, StorageBackendKind, StorageConfig, SummarizerApiConfig,
+    SummarizerBackend, SupervisorConfig, WatchConfig, RELOADABLE_SETTINGS,
+};
 pub use error::CoreError;
-pub use metrics::{LatencyTracker, MemoryMonitor, MemoryPressure, Metrics};
+pub use events::{Event, EventKind, EventLog, EventsConfig, EVENTS_FILE};
+pub use flags::{Experiment, ExperimentFlags, Experiments};
+pub use gc::{GcCandidate, GcConfig, GcReason, GcReport};
+pub use jobs::{JobGuard, JobKind, JobRegistry, JobStatus, ProjectLock};
+pub use metrics::{
+    ActionUsage, LatencyHistogram, LatencyTracker, MemoryMonitor, MemoryPressure, Metrics,
+    ResourceUsage, LATENCY_BUCKETS,
+};
 pub use project::Project;
-pub use project_manager::ProjectManager;
+pub use project_config::{
+    ContextDependencies, ContextOverrides, ContextProfile, MemoryOverrides, ProjectConfig,
+    ScanOverrides,
+};
+pub use project_manager::{IndexSummary, ProjectManager, RelinkReport};
+pub use slo::{SloConfig, SloObjective, SloStatus, SloTracker};
+pub use workspace::{merge_scans, Workspace};
@@ -2,7 +2,7 @@
 //!
 //! Provides request tracking, latency measurement, and memory monitoring.
 
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::RwLock;
 use std::time::{Duration, Instant};
@@ -21,10 +21,52 @@ pub struct Metrics {
     pub projects_loaded: AtomicU64,
     /// Current memory usage in bytes (approximate)
     pub memory_bytes: AtomicUsize,
+    /// Resource usage totals keyed by request action
+    usage: RwLock<HashMap<String, ActionUsage>>,
     /// Daemon start time
     start_time: Instant,
 }
 
+/// Resources consumed while serving one request.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceUsage {
+    /// Bytes read from disk
+    pub bytes_read: u64,
+    /// Tree nodes (or symbols) visited
+    pub nodes_touched: u64,
+    /// Approximate bytes allocated for materialized payloads
+    pub alloc_bytes: u64,
+}
+
+impl ResourceUsage {
+    /// Record bytes read from disk (counted as allocated too).
+    pub fn add_read(&mut self, bytes: usize) {
+        self.bytes_read += bytes as u64;
+        self.alloc_bytes += bytes as u64;
+    }
+
+    /// Record visited tree nodes.
+    pub fn add_nodes(&mut self, count: usize) {
+        self.nodes_touched += count as u64;
+    }
+
+    /// Record an in-memory allocation estimate.
+    pub fn add_alloc(&mut self, bytes: usize) {
+        self.alloc_bytes += bytes as u64;
+    }
+}
+
+/// Accumulated resource usage for one request action.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ActionUsage {
+    /// Request action name (e.g. `get_context`)
+    pub action: String,
+    /// Number of requests recorded
+    pub count: u64,
+    /// Summed resource usage
+    pub total: ResourceUsage,
+}
+
 impl Default for Metrics {
     fn default() -> Self {
         Self::new()
@@ -41,6 +83,7 @@ impl Metrics {
             cache_misses: AtomicU64::new(0),
             projects_loaded: AtomicU64::new(0),
             memory_bytes: AtomicUsize::new(0),
+            usage: RwLock::new(HashMap::new()),
             start_time: Instant::now(),
         }
     }
@@ -52,6 +95,34 @@ impl Metrics {
             .fetch_add(latency.as_micros() as u64, Ordering::Relaxed);
     }
 
+    /// Attribute resource usage to a request action.
+    pub fn record_usage(&self, action: &str, usage: ResourceUsage) {
+        let mut by_action = self.usage.write().unwrap();
+        let entry = by_action
+            .entry(action.to_string())
+            .or_insert_with(|| ActionUsage {
+                action: action.to_string(),
+                ..Default::default()
+            });
+        entry.count += 1;
+        entry.total.bytes_read += usage.bytes_read;
+        entry.total.nodes_touched += usage.nodes_touched;
+        entry.total.alloc_bytes += usage.alloc_bytes;
+    }
+
+    /// Snapshot of per-action usage, heaviest readers first.
+    pub fn usage_by_action(&self) -> Vec<ActionUsage> {
+        let mut usage: Vec<ActionUsage> = self.usage.read().unwrap().values().cloned().collect();
+        usage.sort_by(|a, b| {
+            b.total
+                .bytes_read
+                .cmp(&a.total.bytes_read)
+                .then(b.count.cmp(&a.count))
+                .then(a.action.cmp(&b.action))
+        });
+        usage
+    }
+
     /// Record a cache hit.
     pub fn record_cache_hit(&self) {
         self.cache_hits.fetch_add(1, Ordering::Relaxed);
@@ -83,18 +154,38 @@ impl Metrics {
     pub fn avg_latency(&self) -> Duration {
         let total = self.requests_total.load(Ordering::Relaxed);
         let latency_us = self.requests_latency_us.load(Ordering::Relaxed);
-        if total == 0 {
-            Duration::ZERO
-        } else {
-            Duration::from_micros(latency_us / total)
-        }
+        latency_us
+            .checked_div(total)
+            .map_or(Duration::ZERO, Duration::from_micros)
     }
 }
 
+/// Upper bounds of the latency histogram buckets, in seconds
+pub const LATENCY_BUCKETS: [f64; 11] = [
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0,
+];
+
+/// Cumulative latency histogram for one operation.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LatencyHistogram {
+    /// Operation name (e.g. `get_context`)
+    pub operation: String,
+    /// Samples at or below each of [`LATENCY_BUCKETS`]
+    pub buckets: Vec<u64>,
+    /// Number of samples recorded
+    pub count: u64,
+    /// Sum of all samples
+    pub sum: Duration,
+}
+
 /// Tracks latency samples for percentile calculation.
+///
+/// Percentiles cover the most recent samples; histograms count every sample
+/// since startup.
 pub struct LatencyTracker {
     samples: RwLock<VecDeque<(String, Duration)>>,
     max_samples: usize,
+    histograms: RwLock<HashMap<String, LatencyHistogram>>,
 }
 
 impl Default for LatencyTracker {
@@ -109,11 +200,32 @@ impl LatencyTracker {
         Self {
             samples: RwLock::new(VecDeque::with_capacity(max_samples)),
             max_samples,
+            histograms: RwLock::new(HashMap::new()),
         }
     }
 
     /// Record a latency sample for an operation.
     pub fn record(&self, operation: &str, duration: Duration) {
+        {
+            let mut histograms = self.histograms.write().unwrap();
+            let histogram =
+                histograms
+                    .entry(operation.to_string())
+                    .or_insert_with(|| LatencyHistogram {
+                        operation: operation.to_string(),
+                        buckets: vec![0; LATENCY_BUCKETS.len()],
+                        ..Default::default()
+                    });
+            let secs = duration.as_secs_f64();
+            for (bucket, bound) in histogram.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+                if secs <= bound {
+                    *bucket += 1;
+                }
+            }
+            histogram.count += 1;
+            histogram.sum += duration;
+        }
+
         let mut samples = self.samples.write().unwrap();
         samples.push_back((operation.to_string(), duration));
 
@@ -122,11 +234,23 @@ impl LatencyTracker {
         }
     }
 
+    /// Histograms of every operation recorded so far, by operation name.
+    pub fn histograms(&self) -> Vec<LatencyHistogram> {
+        let mut histograms: Vec<_> = self.histograms.read().unwrap().values().cloned().collect();
+        histograms.sort_by(|a, b| a.operation.cmp(&b.operation));
+        histograms
+    }
+
     /// Get P50 latency for an operation.
     pub fn p50(&self, operation: &str) -> Duration {
         self.percentile(operation, 0.50)
     }
 
+    /// Get P95 latency for an operation.
+    pub fn p95(&self, operation: &str) -> Duration {
+        self.percentile(operation, 0.95)
+    }
+
     /// Get P99 latency for an operation.
     pub fn p99(&self, operation: &str) -> Duration {
         self.percentile(operation, 0.99)
@@ -134,6 +258,11 @@ impl LatencyTracker {
 
     /// Get specific percentile for an operation.
     pub fn percentile(&self, operation: &str, p: f64) -> Duration {
+        self.percentiles(operation, &[p])[0]
+    }
+
+    /// Get several percentiles for an operation, sorting its samples once.
+    pub fn percentiles(&self, operation: &str, ps: &[f64]) -> Vec<Duration> {
         let samples = self.samples.read().unwrap();
         let mut durations: Vec<_> = samples
             .iter()
@@ -142,12 +271,16 @@ impl LatencyTracker {
             .collect();
 
         if durations.is_empty() {
-            return Duration::ZERO;
+            return vec![Duration::ZERO; ps.len()];
         }
 
         durations.sort();
-        let idx = ((durations.len() as f64 * p) as usize).min(durations.len() - 1);
-        durations[idx]
+        ps.iter()
+            .map(|p| {
+                let idx = ((durations.len() as f64 * p) as usize).min(durations.len() - 1);
+                durations[idx]
+            })
+            .collect()
     }
 
     /// Get sample count for an operation.
@@ -168,6 +301,17 @@ pub enum MemoryPressure {
     Critical,
 }
 
+impl MemoryPressure {
+    /// Lowercase name of the level
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MemoryPressure::Normal => "normal",
+            MemoryPressure::Warning => "warning",
+            MemoryPressure::Critical => "critical",
+        }
+    }
+}
+
 /// Monitors memory usage and pressure.
 pub struct MemoryMonitor {
     /// Memory limit in bytes
@@ -264,6 +408,29 @@ mod tests {
         assert!((metrics.cache_hit_rate() - 0.75).abs() < 0.01);
     }
 
+    #[test]
+    fn test_metrics_usage_by_action() {
+        let metrics = Metrics::new();
+
+        let mut light = ResourceUsage::default();
+        light.add_nodes(3);
+        metrics.record_usage("ping", light);
+        metrics.record_usage("ping", light);
+
+        let mut heavy = ResourceUsage::default();
+        heavy.add_read(4096);
+        heavy.add_alloc(100);
+        metrics.record_usage("get_context", heavy);
+
+        let usage = metrics.usage_by_action();
+        assert_eq!(usage.len(), 2);
+        assert_eq!(usage[0].action, "get_context");
+        assert_eq!(usage[0].total.bytes_read, 4096);
+        assert_eq!(usage[0].total.alloc_bytes, 4196);
+        assert_eq!(usage[1].count, 2);
+        assert_eq!(usage[1].total.nodes_touched, 6);
+    }
+
     #[test]
     fn test_latency_tracker_percentiles() {
         let tracker = LatencyTracker::new(100);
@@ -275,18 +442,55 @@ mod tests {
         // With 100 samples (1-100ms), idx=50 gives value at position 50 = 51ms
         // idx=99 gives value at position 99 = 100ms
         let p50 = tracker.p50("test");
+        let p95 = tracker.p95("test");
         let p99 = tracker.p99("test");
 
         // P50 should be around median (50-51ms)
         assert!(p50 >= Duration::from_millis(50) && p50 <= Duration::from_millis(51));
+        assert!(p95 >= Duration::from_millis(95) && p95 <= Duration::from_millis(96));
         // P99 should be near top (99-100ms)
         assert!(p99 >= Duration::from_millis(99) && p99 <= Duration::from_millis(100));
+        assert_eq!(
+            tracker.percentiles("test", &[0.50, 0.95, 0.99]),
+            [p50, p95, p99]
+        );
     }
 
     #[test]
     fn test_latency_tracker_empty() {
         let tracker = LatencyTracker::new(100);
         assert_eq!(tracker.p99("nonexistent"), Duration::ZERO);
+        assert_eq!(
+            tracker.percentiles("nonexistent", &[0.5, 0.95]),
+            [Duration::ZERO; 2]
+        );
+    }
+
+    #[test]
+    fn test_latency_tracker_histograms() {
+        let tracker = LatencyTracker::new(2);
+        tracker.record("ping", Duration::from_micros(500));
+        tracker.record("ping", Duration::from_millis(30));
+        tracker.record("ping", Duration::from_secs(10));
+        tracker.record("get_context", Duration::from_millis(1));
+
+        let histograms = tracker.histograms();
+        assert_eq!(histograms.len(), 2);
+        assert_eq!(histograms[0].operation, "get_context");
+        assert_eq!(histograms[0].buckets[0], 1);
+
+        // Unlike percentiles, histograms are not limited to the sample window
+        let ping = &histograms[1];
+        assert_eq!(ping.count, 3);
+        assert_eq!(ping.buckets[0], 1);
+        assert_eq!(ping.buckets[3], 1);
+        assert_eq!(ping.buckets[4], 2);
+        assert_eq!(ping.buckets[LATENCY_BUCKETS.len() - 1], 2);
+        assert_eq!(
+            ping.sum,
+            Duration::from_micros(500) + Duration::from_millis(30) + Duration::from_secs(10)
+        );
+        assert_eq!(tracker.sample_count("ping"), 1);
     }
 
     #[test]
@@ -1,6 +1,6 @@
 //! Project data structure and persistence.
 
-use crate::CoreError;
+use crate::{CoreError, ExperimentFlags, ProjectConfig};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
@@ -19,6 +19,16 @@ pub struct Project {
 
     /// Storage directory for this project
     pub storage_dir: PathBuf,
+
+    /// Overrides from the project's `.engram/config.toml`
+    pub config: ProjectConfig,
+
+    /// Never evicted from the project cache
+    pub pinned: bool,
+
+    /// Indexed once, after which the stored tree and experience log are
+    /// never written
+    pub read_only: bool,
 }
 
 /// Project manifest stored on disk
@@ -53,6 +63,18 @@ pub struct ProjectManifest {
     /// Whether AI enrichment has completed
     #[serde(default)]
     pub enriched: bool,
+
+    /// Directories never auto-excluded as build artifacts
+    #[serde(default)]
+    pub artifact_allowlist: Vec<PathBuf>,
+
+    /// Artifact directories auto-excluded by the last scan
+    #[serde(default)]
+    pub auto_excluded: Vec<PathBuf>,
+
+    /// Per-project experiment flag overrides
+    #[serde(default)]
+    pub experiments: ExperimentFlags,
 }
 
 impl Project {
@@ -75,11 +97,16 @@ impl Project {
             .unwrap_or("unknown")
             .to_string();
 
+        let config = ProjectConfig::load_or_default(&manifest.project_path).await;
+
         Ok(Self {
             path: manifest.project_path.clone(),
             hash,
             manifest,
             storage_dir: storage_dir.to_path_buf(),
+            pinned: config.pinned,
+            read_only: config.read_only,
+            config,
         })
     }
 
@@ -108,13 +135,20 @@ impl Project {
             languages: vec![],
             frameworks: vec![],
             enriched: false,
+            artifact_allowlist: vec![],
+            auto_excluded: vec![],
+            experiments: ExperimentFlags::default(),
         };
 
+        let config = ProjectConfig::load_or_default(project_path).await;
         let project = Self {
             path: project_path.to_path_buf(),
             hash: hash.to_string(),
             manifest,
             storage_dir: storage_dir.to_path_buf(),
+            pinned: config.pinned,
+            read_only: config.read_only,
+            config,
         };
 
         project.save_manifest().await?;
@@ -122,6 +156,17 @@ impl Project {
         Ok(project)
     }
 
+    /// Re-read `.engram/config.toml`
+    pub async fn reload_config(&mut self) {
+        self.config = ProjectConfig::load_or_default(&self.path).await;
+    }
+
+    /// Whether a scan may rewrite the stored tree: a read-only project is
+    /// only scanned until it has been indexed once.
+    pub fn can_write_tree(&self) -> bool {
+        !self.read_only || self.manifest.last_scan.is_none()
+    }
+
     /// Save the manifest to disk
     pub async fn save_manifest(&self) -> Result<(), CoreError> {
         let manifest_path = self.storage_dir.join("manifest.json");
@@ -0,0 +1,328 @@
+//! Per-project configuration.
+//!
+//! Projects can check in `.engram/config.toml` to override daemon defaults
+//! for themselves. Every setting is optional; anything left out falls back
+//! to the daemon configuration.
+//!
+//! ```toml
+//! pinned = true
+//! read_only = false
+//!
+//! [scan]
+//! ignore = ["fixtures/", "*.generated.ts"]
+//! max_file_size = 1048576
+//! partial_index_kb = 64
+//!
+//! [context]
+//! max_content_size = 50000
+//!
+//! [profile.review]
+//! changed_since = "main"
+//! dependencies = "full"
+//! experiences = false
+//!
+//! [memory]
+//! compact_threshold = 1048576
+//! tombstone_retention_days = 7
+//! ```
+
+use crate::{CoreError, MemoryConfig};
+use engram_indexer::scanner::ScanOptions;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Location of the config file relative to the project root
+const CONFIG_PATH: &str = ".engram/config.toml";
+
+/// Project-level overrides loaded from `.engram/config.toml`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectConfig {
+    /// Never evict the project from the daemon's cache
+    #[serde(default)]
+    pub pinned: bool,
+
+    /// Never write the stored tree or experience log once indexed
+    #[serde(default)]
+    pub read_only: bool,
+
+    /// Scanning overrides
+    #[serde(default)]
+    pub scan: ScanOverrides,
+
+    /// Context rendering overrides
+    #[serde(default)]
+    pub context: ContextOverrides,
+
+    /// Memory retention overrides
+    #[serde(default)]
+    pub memory: MemoryOverrides,
+
+    /// Named context profiles, selected per request
+    #[serde(default)]
+    pub profile: BTreeMap<String, ContextProfile>,
+}
+
+/// Scanning overrides
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScanOverrides {
+    /// Additional paths to skip, in `.gitignore` syntax
+    #[serde(default)]
+    pub ignore: Vec<String>,
+
+    /// Files larger than this many bytes are skipped
+    #[serde(default)]
+    pub max_file_size: Option<u64>,
+
+    /// Index the first this many KB of files over `max_file_size`, with
+    /// their top-level symbols, instead of skipping them
+    #[serde(default)]
+    pub partial_index_kb: Option<u64>,
+
+    /// List images, archives and other binary files by size and type
+    #[serde(default)]
+    pub catalog_assets: Option<bool>,
+}
+
+/// Context rendering overrides
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ContextOverrides {
+    /// Maximum size of rendered context in bytes
+    #[serde(default)]
+    pub max_content_size: Option<usize>,
+}
+
+/// Context shape for one kind of task, e.g. reviewing or debugging
+///
+/// Settings left out keep the request's or the project's defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ContextProfile {
+    /// Focus on files changed since the merge-base with this branch
+    #[serde(default)]
+    pub changed_since: Option<String>,
+
+    /// Neighbours of the focus to load alongside it
+    #[serde(default)]
+    pub dependencies: Option<ContextDependencies>,
+
+    /// List recent decisions from the experience log
+    #[serde(default)]
+    pub experiences: Option<bool>,
+
+    /// Summarize the last ended agent session
+    #[serde(default)]
+    pub previous_session: Option<bool>,
+
+    /// Maximum size of rendered context in bytes
+    #[serde(default)]
+    pub max_content_size: Option<usize>,
+}
+
+/// Neighbours of the focus loaded into a context
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContextDependencies {
+    /// Only the focus itself
+    None,
+    /// Files the focus imports
+    #[default]
+    Imports,
+    /// Files the focus imports and files importing it
+    Full,
+}
+
+/// Memory retention overrides
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MemoryOverrides {
+    /// Compact the memory log once it reaches this many bytes (0 disables)
+    #[serde(default)]
+    pub compact_threshold: Option<u64>,
+
+    /// Days a deleted entry's tombstone survives compaction
+    #[serde(default)]
+    pub tombstone_retention_days: Option<u64>,
+}
+
+impl ProjectConfig {
+    /// Path of the config file for a project root
+    pub fn path(project_root: &Path) -> PathBuf {
+        project_root.join(CONFIG_PATH)
+    }
+
+    /// Load the config for a project root; a missing file yields defaults.
+    pub async fn load(project_root: &Path) -> Result<Self, CoreError> {
+        let path = Self::path(project_root);
+        let content = match tokio::fs::read_to_string(&path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e.into()),
+        };
+        Self::parse(&content)
+            .map_err(|e| CoreError::Serialization(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Load the config, logging and falling back to defaults if it is invalid.
+    pub async fn load_or_default(project_root: &Path) -> Self {
+        Self::load(project_root).await.unwrap_or_else(|e| {
+            tracing::warn!(error = %e, "Ignoring invalid project config");
+            Self::default()
+        })
+    }
+
+    /// Parse a config from TOML
+    pub fn parse(content: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(content)
+    }
+
+    /// Apply scan overrides on top of `options`.
+    pub fn apply_scan(&self, options: &mut ScanOptions) {
+        options
+            .ignore_globs
+            .extend(self.scan.ignore.iter().cloned());
+        if let Some(max_file_size) = self.scan.max_file_size {
+            options.max_file_size = max_file_size;
+        }
+        if let Some(partial_index_kb) = self.scan.partial_index_kb {
+            options.partial_index_bytes = partial_index_kb * 1024;
+        }
+        if let Some(catalog_assets) = self.scan.catalog_assets {
+            options.catalog_assets = catalog_assets;
+        }
+    }
+
+    /// Look up a context profile by name.
+    pub fn profile(&self, name: &str) -> Option<&ContextProfile> {
+        self.profile.get(name)
+    }
+
+    /// Size limit for rendered context, preferring the profile's.
+    pub fn max_content_size(&self, profile: Option<&ContextProfile>) -> Option<usize> {
+        profile
+            .and_then(|profile| profile.max_content_size)
+            .or(self.context.max_content_size)
+    }
+
+    /// Memory retention settings with overrides applied to `defaults`.
+    pub fn memory_config(&self, defaults: &MemoryConfig) -> MemoryConfig {
+        MemoryConfig {
+            compact_threshold: self
+                .memory
+                .compact_threshold
+                .unwrap_or(defaults.compact_threshold),
+            tombstone_retention_days: self
+                .memory
+                .tombstone_retention_days
+                .unwrap_or(defaults.tombstone_retention_days),
+            ..defaults.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_and_merge() {
+        let config = ProjectConfig::parse(
+            r#"
+            [scan]
+            ignore = ["fixtures/"]
+            max_file_size = 1024
+            partial_index_kb = 8
+            catalog_assets = false
+
+            [memory]
+            tombstone_retention_days = 7
+            "#,
+        )
+        .unwrap();
+
+        let mut options = ScanOptions {
+            ignore_globs: vec!["*.log".to_string()],
+            ..Default::default()
+        };
+        config.apply_scan(&mut options);
+        assert_eq!(options.ignore_globs, vec!["*.log", "fixtures/"]);
+        assert_eq!(options.max_file_size, 1024);
+        assert_eq!(options.partial_index_bytes, 8 * 1024);
+        assert!(!options.catalog_assets);
+        assert_eq!(config.context.max_content_size, None);
+
+        let memory = config.memory_config(&MemoryConfig::default());
+        assert_eq!(memory.tombstone_retention_days, 7);
+        assert_eq!(
+            memory.compact_threshold,
+            MemoryConfig::default().compact_threshold
+        );
+    }
+
+    #[test]
+    fn test_parse_profiles() {
+        let config = ProjectConfig::parse(
+            r#"
+            [context]
+            max_content_size = 50000
+
+            [profile.review]
+            changed_since = "main"
+            dependencies = "full"
+            experiences = false
+            previous_session = false
+
+            [profile.debug]
+            max_content_size = 200000
+            "#,
+        )
+        .unwrap();
+
+        let review = config.profile("review").unwrap();
+        assert_eq!(review.changed_since.as_deref(), Some("main"));
+        assert_eq!(review.dependencies, Some(ContextDependencies::Full));
+        assert_eq!(review.experiences, Some(false));
+        assert_eq!(review.previous_session, Some(false));
+        assert_eq!(config.max_content_size(Some(review)), Some(50000));
+        assert_eq!(
+            config.max_content_size(config.profile("debug")),
+            Some(200000)
+        );
+        assert!(config.profile("implement").is_none());
+        assert!(ProjectConfig::parse("[profile.x]\ndependencies = \"all\"\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_keys() {
+        assert!(ProjectConfig::parse("[scan]\nignored = []\n").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_load() {
+        let temp_dir = tempdir().unwrap();
+        assert_eq!(
+            ProjectConfig::load(temp_dir.path()).await.unwrap(),
+            ProjectConfig::default()
+        );
+
+        std::fs::create_dir_all(temp_dir.path().join(".engram")).unwrap();
+        std::fs::write(
+            ProjectConfig::path(temp_dir.path()),
+            "[context]\nmax_content_size = 500\n",
+        )
+        .unwrap();
+        let config = ProjectConfig::load(temp_dir.path()).await.unwrap();
+        assert_eq!(config.context.max_content_size, Some(500));
+
+        std::fs::write(ProjectConfig::path(temp_dir.path()), "[context\n").unwrap();
+        assert!(ProjectConfig::load(temp_dir.path()).await.is_err());
+        assert_eq!(
+            ProjectConfig::load_or_default(temp_dir.path()).await,
+            ProjectConfig::default()
+        );
+    }
+}
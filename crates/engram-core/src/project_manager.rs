@@ -1,16 +1,61 @@
 //! Project Manager with LRU caching.
 //!
 //! Manages loaded projects with efficient memory usage through an LRU cache.
+//! Pinned projects are never evicted; read-only projects refuse every write
+//! to their stored tree once indexed.
+//!
+//! Projects are identified by the id in their `.engram/project.toml`, written
+//! on init, so a moved checkout is found again under its new path. Projects
+//! initialized before ids existed keep their path identity until relinked.
 
-use crate::{CoreError, DaemonConfig, Project};
+use crate::archive::{read_archive, write_archive, ArchiveContents};
+use crate::gc::{find_garbage, remove_dirs, set_aside};
+use crate::project::ProjectManifest;
+use crate::{
+    merge_scans, ArchiveInfo, ArchiveReport, CoreError, DaemonConfig, EventKind, EventLog,
+    GcConfig, GcReport, JobGuard, JobKind, JobRegistry, Project, ProjectConfig, SnapshotConfig,
+    Workspace, ARCHIVE_FORMAT,
+};
+use chrono::Utc;
+use engram_indexer::scanner::{ExcludedDir, Framework, Language, ScanOptions, ScanResult, Scanner};
+use engram_indexer::storage::{
+    read_project_id, write_project_id, FilesystemBackend, Storage, StorageBackend,
+};
+use engram_indexer::tree::{
+    compile_globs, FileUpdate, PruneReport, Tree, TreeBuilder, UpdateReport,
+};
+use engram_indexer::{CancelToken, ChangeKind, FileChange, IndexerError};
 use lru::LruCache;
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
-use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
 
+/// Outcome of [`ProjectManager::index_project`]
+#[derive(Debug, Clone)]
+pub struct IndexSummary {
+    /// Files in the stored tree afterwards
+    pub file_count: usize,
+    /// Artifact directories the walk excluded
+    pub excluded_dirs: Vec<ExcludedDir>,
+    /// What changed in the stored tree; `None` after a full scan
+    pub update: Option<UpdateReport>,
+}
+
+/// Outcome of [`ProjectManager::relink`]
+#[derive(Debug, Clone)]
+pub struct RelinkReport {
+    /// Stable id of the project
+    pub id: String,
+    /// Root the project was stored under before relinking
+    pub previous_path: PathBuf,
+    /// Whether storage kept under the previous path's identity was moved
+    pub adopted: bool,
+}
+
 /// Manages project loading and caching
 pub struct ProjectManager {
     /// LRU cache of loaded projects
@@ -19,22 +64,69 @@ pub struct ProjectManager {
     /// Data directory for project storage
     data_dir: PathBuf,
 
-    /// Maximum projects in cache
-    max_projects: usize,
+    /// Maximum projects in cache (changed when the config is reloaded)
+    max_projects: AtomicUsize,
+
+    /// Projects pinned by the daemon config
+    pinned: Vec<PathBuf>,
+
+    /// Projects opened read-only by the daemon config
+    read_only: Vec<PathBuf>,
+
+    /// Running indexing and enrichment jobs
+    jobs: Arc<JobRegistry>,
+
+    /// When to snapshot stored trees
+    snapshots: SnapshotConfig,
+
+    /// Significant events, for `engram logs`
+    events: Arc<EventLog>,
+
+    /// When stored data of other projects is garbage
+    gc: GcConfig,
+
+    /// Where stored trees are kept
+    backend: Arc<dyn StorageBackend>,
 }
 
 impl ProjectManager {
     /// Create a new project manager
     pub fn new(config: &DaemonConfig) -> Self {
-        let capacity = NonZeroUsize::new(config.max_projects).unwrap_or(NonZeroUsize::MIN);
-
         Self {
-            projects: RwLock::new(LruCache::new(capacity)),
+            projects: RwLock::new(LruCache::unbounded()),
             data_dir: config.data_dir.clone(),
-            max_projects: config.max_projects,
+            max_projects: AtomicUsize::new(config.max_projects.max(1)),
+            pinned: config.pinned_projects.clone(),
+            read_only: config.read_only_projects.clone(),
+            jobs: Arc::new(JobRegistry::new()),
+            snapshots: config.snapshots.clone(),
+            events: Arc::new(EventLog::from_config(&config.events, &config.data_dir)),
+            gc: config.gc.clone(),
+            backend: Arc::new(FilesystemBackend::new(config.data_dir.clone())),
         }
     }
 
+    /// Keep stored trees in `backend` instead of files in the data dir.
+    pub fn with_backend(mut self, backend: Arc<dyn StorageBackend>) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Storage of project trees under the data dir.
+    fn storage(&self) -> Storage {
+        Storage::new(self.data_dir.clone()).with_backend(self.backend.clone())
+    }
+
+    /// Registry of running background jobs
+    pub fn jobs(&self) -> &Arc<JobRegistry> {
+        &self.jobs
+    }
+
+    /// Journal of significant events
+    pub fn events(&self) -> &Arc<EventLog> {
+        &self.events
+    }
+
     /// Check if a project is initialized
     pub async fn is_initialized(&self, cwd: &Path) -> bool {
         let canonical = match cwd.canonicalize() {
@@ -61,15 +153,16 @@ impl ProjectManager {
         }
 
         // Load from disk
-        let project = self.load_project(&canonical).await?;
+        let mut project = self.load_project(&canonical).await?;
+        self.apply_flags(&mut project);
         let project = Arc::new(project);
+        self.events.record(
+            EventKind::ProjectLoaded,
+            Some(&project.path),
+            format!("Loaded {} files", project.manifest.file_count),
+        );
 
-        // Add to cache
-        {
-            let mut cache = self.projects.write().await;
-            cache.put(canonical, project.clone());
-        }
-
+        self.cache_project(project.clone()).await;
         Ok(project)
     }
 
@@ -79,25 +172,39 @@ impl ProjectManager {
             .canonicalize()
             .map_err(|_| CoreError::InvalidPath(cwd.display().to_string()))?;
 
+        let _lock = self.jobs.lock_project(&canonical).await;
+        if read_project_id(&canonical).is_none() {
+            // Initialized before projects had ids
+            if self.has_manifest(&Self::path_hash(&canonical)) {
+                return Err(CoreError::AlreadyInitialized(
+                    canonical.display().to_string(),
+                ));
+            }
+            let id = uuid::Uuid::new_v4().to_string();
+            if let Err(e) = write_project_id(&canonical, &id) {
+                tracing::warn!(
+                    project = ?canonical,
+                    error = %e,
+                    "Failed to write project id, identifying project by path"
+                );
+            }
+        }
+
         let hash = Self::compute_hash(&canonical);
         let storage_dir = self.project_storage_dir(&hash);
 
         // Check if already initialized
-        if storage_dir.join("manifest.json").exists() {
+        if self.has_manifest(&hash) {
             return Err(CoreError::AlreadyInitialized(
                 canonical.display().to_string(),
             ));
         }
 
         // Create new project
-        let project = Project::create(&canonical, &storage_dir, &hash).await?;
+        let mut project = Project::create(&canonical, &storage_dir, &hash).await?;
+        self.apply_flags(&mut project);
         let project = Arc::new(project);
-
-        // Add to cache
-        {
-            let mut cache = self.projects.write().await;
-            cache.put(canonical, project.clone());
-        }
+        self.cache_project(project.clone()).await;
 
         tracing::info!(
             project = ?project.path,
@@ -108,19 +215,214 @@ impl ProjectManager {
         Ok(project)
     }
 
+    /// Get the maximum number of projects kept in the cache
+    pub fn max_projects(&self) -> usize {
+        self.max_projects.load(Ordering::Relaxed)
+    }
+
+    /// Change how many projects are kept in the cache, evicting the least
+    /// recently used unpinned projects beyond the new limit.
+    pub async fn set_max_projects(&self, max_projects: usize) {
+        let max_projects = max_projects.max(1);
+        self.max_projects.store(max_projects, Ordering::Relaxed);
+        let mut cache = self.projects.write().await;
+        while cache.len() > max_projects {
+            let Some(evicted) = lru_unpinned(&cache, None) else {
+                break;
+            };
+            cache.pop(&evicted);
+            tracing::debug!(path = ?evicted, "Evicted project from cache");
+        }
+    }
+
     /// Get the number of loaded projects
     pub async fn loaded_count(&self) -> usize {
         self.projects.read().await.len()
     }
 
-    /// Evict the least recently used project from cache
+    /// Root paths of the projects currently in the cache
+    pub async fn loaded_paths(&self) -> Vec<PathBuf> {
+        self.projects
+            .read()
+            .await
+            .iter()
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+
+    /// Projects currently in the cache
+    pub async fn loaded_projects(&self) -> Vec<Arc<Project>> {
+        self.projects
+            .read()
+            .await
+            .iter()
+            .map(|(_, project)| project.clone())
+            .collect()
+    }
+
+    /// Evict the least recently used unpinned project from cache
     pub async fn evict_lru(&self) {
         let mut cache = self.projects.write().await;
-        if let Some((path, _)) = cache.pop_lru() {
+        if let Some(path) = lru_unpinned(&cache, None) {
+            cache.pop(&path);
             tracing::debug!(path = ?path, "Evicted project from cache");
         }
     }
 
+    /// Whether the project containing `cwd` is loaded read-only
+    pub async fn is_read_only(&self, cwd: &Path) -> bool {
+        self.get_project(cwd)
+            .await
+            .is_ok_and(|project| project.read_only)
+    }
+
+    /// Cache a project, evicting the least recently used unpinned projects
+    /// beyond `max_projects`. The cache grows past the limit rather than
+    /// evict a pinned project.
+    async fn cache_project(&self, project: Arc<Project>) {
+        let path = project.path.clone();
+        let mut cache = self.projects.write().await;
+        cache.put(path.clone(), project);
+        while cache.len() > self.max_projects() {
+            let Some(evicted) = lru_unpinned(&cache, Some(&path)) else {
+                break;
+            };
+            cache.pop(&evicted);
+            tracing::debug!(path = ?evicted, "Evicted project from cache");
+        }
+    }
+
+    /// Pin or open a project read-only if its config or the daemon's says so
+    fn apply_flags(&self, project: &mut Project) {
+        let listed = |paths: &[PathBuf]| {
+            paths.iter().any(|path| {
+                path == &project.path || path.canonicalize().is_ok_and(|path| path == project.path)
+            })
+        };
+        project.pinned = project.config.pinned || listed(&self.pinned);
+        project.read_only = project.config.read_only || listed(&self.read_only);
+    }
+
+    /// Remove a project from the cache, persisting its manifest first.
+    ///
+    /// Returns whether the project was loaded.
+    pub async fn unload_project(&self, cwd: &Path) -> Result<bool, CoreError> {
+        let canonical = cwd
+            .canonicalize()
+            .map_err(|_| CoreError::InvalidPath(cwd.display().to_string()))?;
+
+        let mut cache = self.projects.write().await;
+        let Some(project) = cache.peek(&canonical).cloned() else {
+            return Ok(false);
+        };
+        project.save_manifest().await?;
+        cache.pop(&canonical);
+
+        tracing::info!(path = ?canonical, "Unloaded project");
+        Ok(true)
+    }
+
+    /// Persist the manifests of all loaded projects, before shutdown.
+    ///
+    /// Returns the number of projects flushed.
+    pub async fn flush(&self) -> usize {
+        let mut flushed = 0;
+        for project in self.loaded_projects().await {
+            match project.save_manifest().await {
+                Ok(()) => flushed += 1,
+                Err(e) => {
+                    tracing::warn!(path = ?project.path, error = %e, "Failed to flush project")
+                }
+            }
+        }
+        flushed
+    }
+
+    /// Find stored data of projects nobody used for the idle limit, and of
+    /// deleted and moved ones past a short grace period, and delete it
+    /// unless `dry_run`.
+    ///
+    /// `max_idle_days` overrides the configured idle limit. With `only`
+    /// given, just the candidates with one of those directories (relative
+    /// to the data dir) are reported and deleted. Loaded, pinned and
+    /// read-only projects are kept.
+    pub async fn collect_garbage(
+        &self,
+        max_idle_days: Option<u64>,
+        dry_run: bool,
+        only: &[PathBuf],
+    ) -> Result<GcReport, CoreError> {
+        let canonical = |path: &PathBuf| path.canonicalize().unwrap_or_else(|_| path.clone());
+        let in_use: Vec<PathBuf> = self
+            .projects
+            .read()
+            .await
+            .iter()
+            .map(|(path, _)| path.clone())
+            .chain(self.pinned.iter().map(canonical))
+            .chain(self.read_only.iter().map(canonical))
+            .collect();
+
+        let config = GcConfig {
+            max_idle_days: max_idle_days.unwrap_or(self.gc.max_idle_days),
+            ..self.gc.clone()
+        };
+        let mut candidates = find_garbage(&self.storage(), config, in_use).await?;
+        if !only.is_empty() {
+            candidates.retain(|c| c.dirs.iter().any(|dir| only.contains(dir)));
+        }
+
+        let mut report = GcReport {
+            candidates,
+            deleted: 0,
+        };
+        if dry_run {
+            return Ok(report);
+        }
+        for candidate in &report.candidates {
+            // Hold the cache so the project is not loaded while its data is
+            // moved; one loaded since the scan is kept
+            let cache = self.projects.write().await;
+            if candidate
+                .root
+                .as_ref()
+                .is_some_and(|root| cache.contains(root))
+            {
+                continue;
+            }
+            let dirs = set_aside(&self.data_dir, candidate)?;
+            drop(cache);
+
+            remove_dirs(dirs).await?;
+            for dir in &candidate.dirs {
+                // Stored trees are in `<hash>/`, beside `projects/<hash>/`
+                if let (1, Some(hash)) = (dir.components().count(), dir.to_str()) {
+                    self.backend
+                        .remove_project(hash)
+                        .await
+                        .map_err(|e| CoreError::Storage(e.to_string()))?;
+                }
+            }
+            report.deleted += 1;
+            self.events.record(
+                EventKind::GarbageCollected,
+                candidate.root.as_deref(),
+                format!(
+                    "Deleted {} byte(s) of stored data ({})",
+                    candidate.bytes,
+                    candidate.reason.name()
+                ),
+            );
+        }
+
+        tracing::info!(
+            deleted = report.deleted,
+            bytes = report.reclaimable_bytes(),
+            "Collected garbage"
+        );
+        Ok(report)
+    }
+
     /// Evict all projects except the given one
     pub async fn evict_all_except(&self, keep: &Path) {
         let canonical = keep.canonicalize().ok();
@@ -129,7 +431,7 @@ impl ProjectManager {
         // Collect keys to remove
         let to_remove: Vec<_> = cache
             .iter()
-            .filter(|(k, _)| canonical.as_ref() != Some(*k))
+            .filter(|(k, project)| canonical.as_ref() != Some(*k) && !project.pinned)
             .map(|(k, _)| k.clone())
             .collect();
 
@@ -137,11 +439,248 @@ impl ProjectManager {
             cache.pop(&key);
         }
 
-        tracing::info!("Evicted all projects except current");
+        tracing::info!("Evicted all unpinned projects except current");
+    }
+
+    /// Relink a project to its current root, e.g. after the checkout was
+    /// moved.
+    ///
+    /// A project without an id is given one, and storage kept under the
+    /// path identity of `from` (the root it was initialized at, `cwd` if
+    /// not given) is moved to the id's. The manifest and stored trees then
+    /// point at `cwd`.
+    pub async fn relink(&self, cwd: &Path, from: Option<&Path>) -> Result<RelinkReport, CoreError> {
+        let canonical = cwd
+            .canonicalize()
+            .map_err(|_| CoreError::InvalidPath(cwd.display().to_string()))?;
+        let from = from.unwrap_or(&canonical);
+        let _lock = self.jobs.lock_project(&canonical).await;
+
+        let old_hash = Self::path_hash(from);
+        let id = match read_project_id(&canonical) {
+            Some(id) => id,
+            None if self.has_manifest(&old_hash) => {
+                let id = uuid::Uuid::new_v4().to_string();
+                write_project_id(&canonical, &id)?;
+                id
+            }
+            None => return Err(CoreError::NotInitialized(from.display().to_string())),
+        };
+
+        let hash = Self::compute_hash(&canonical);
+        let storage_dir = self.project_storage_dir(&hash);
+        let mut adopted = false;
+        if !self.has_manifest(&hash) {
+            if !self.has_manifest(&old_hash) {
+                return Err(CoreError::NotInitialized(from.display().to_string()));
+            }
+            tokio::fs::rename(self.project_storage_dir(&old_hash), &storage_dir).await?;
+            adopted = true;
+        }
+
+        let storage = self.storage();
+        adopted |= storage
+            .relocate(
+                &storage.path_hash(from),
+                &storage.project_hash(&canonical),
+                &canonical,
+            )
+            .await
+            .map_err(|e| CoreError::Storage(e.to_string()))?;
+
+        let mut project = Project::load(&storage_dir).await?;
+        let previous_path = project.path.clone();
+        if previous_path != canonical {
+            project.manifest.project_path = canonical.clone();
+            project.save_manifest().await?;
+        }
+
+        {
+            let mut cache = self.projects.write().await;
+            cache.pop(&canonical);
+            cache.pop(&previous_path);
+        }
+        self.events.record(
+            EventKind::ProjectRelinked,
+            Some(&canonical),
+            format!("Relinked from {}", previous_path.display()),
+        );
+        tracing::info!(
+            project = ?canonical,
+            previous = ?previous_path,
+            id = %id,
+            adopted,
+            "Project relinked"
+        );
+
+        Ok(RelinkReport {
+            id,
+            previous_path,
+            adopted,
+        })
+    }
+
+    /// Write a project's manifest and stored data to an archive at
+    /// `output`; see [`crate::archive`].
+    pub async fn export_archive(
+        &self,
+        cwd: &Path,
+        output: &Path,
+    ) -> Result<ArchiveReport, CoreError> {
+        let canonical = cwd
+            .canonicalize()
+            .map_err(|_| CoreError::InvalidPath(cwd.display().to_string()))?;
+        let hash = Self::compute_hash(&canonical);
+        if !self.has_manifest(&hash) {
+            return Err(CoreError::NotInitialized(canonical.display().to_string()));
+        }
+        let _lock = self.jobs.lock_project(&canonical).await;
+
+        if let Some(project) = self.projects.read().await.peek(&canonical) {
+            project.save_manifest().await?;
+        }
+        let mut contents = ArchiveContents::default();
+        let mut entries = tokio::fs::read_dir(self.project_storage_dir(&hash)).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if !name.starts_with('.') && entry.file_type().await?.is_file() {
+                contents
+                    .project
+                    .push((name, tokio::fs::read(entry.path()).await?));
+            }
+        }
+        let storage = self.storage();
+        contents.data = storage
+            .stored_files(&storage.project_hash(&canonical))
+            .await
+            .map_err(|e| CoreError::Storage(e.to_string()))?;
+
+        let info = ArchiveInfo {
+            format: ARCHIVE_FORMAT,
+            exported_at: Utc::now(),
+            project_path: canonical.clone(),
+        };
+        let output = output.to_path_buf();
+        let report = tokio::task::spawn_blocking(move || write_archive(&output, &info, &contents))
+            .await
+            .map_err(|e| CoreError::Storage(e.to_string()))??;
+
+        tracing::info!(project = ?canonical, files = report.files, "Project exported");
+        Ok(report)
     }
 
-    /// Compute a hash for a project path
+    /// Replace a project's manifest and stored data with those of the
+    /// archive at `archive`, pointing them at `cwd`.
+    ///
+    /// `cwd` need not be initialized; a project with the same id as the
+    /// exported one is stored under the same identity.
+    pub async fn import_archive(
+        &self,
+        cwd: &Path,
+        archive: &Path,
+    ) -> Result<ArchiveReport, CoreError> {
+        let canonical = cwd
+            .canonicalize()
+            .map_err(|_| CoreError::InvalidPath(cwd.display().to_string()))?;
+        // Held from staging on, so no two imports share a staging directory
+        let _lock = self.jobs.lock_project(&canonical).await;
+        let archive = archive.to_path_buf();
+        let (info, contents) = tokio::task::spawn_blocking(move || read_archive(&archive))
+            .await
+            .map_err(|e| CoreError::Storage(e.to_string()))??;
+        let Some((_, manifest)) = contents
+            .project
+            .iter()
+            .find(|(name, _)| name == "manifest.json")
+        else {
+            return Err(CoreError::Storage(
+                "Archive holds no project manifest".to_string(),
+            ));
+        };
+        let mut manifest: ProjectManifest = serde_json::from_slice(manifest)
+            .map_err(|e| CoreError::Serialization(e.to_string()))?;
+        manifest.project_path = canonical.clone();
+        let manifest = serde_json::to_vec_pretty(&manifest)
+            .map_err(|e| CoreError::Serialization(e.to_string()))?;
+
+        // The manifest directory is staged before anything is replaced
+        let hash = Self::compute_hash(&canonical);
+        let storage_dir = self.project_storage_dir(&hash);
+        let staging = self.project_storage_dir(&format!(".{}.import", hash));
+        if staging.exists() {
+            tokio::fs::remove_dir_all(&staging).await?;
+        }
+        tokio::fs::create_dir_all(&staging).await?;
+        for (name, data) in &contents.project {
+            let data = if name == "manifest.json" {
+                &manifest
+            } else {
+                data
+            };
+            tokio::fs::write(staging.join(name), data).await?;
+        }
+
+        self.projects.write().await.pop(&canonical);
+
+        let storage = self.storage();
+        if let Err(e) = storage
+            .restore_files(
+                &storage.project_hash(&canonical),
+                &contents.data,
+                &canonical,
+            )
+            .await
+        {
+            let _ = tokio::fs::remove_dir_all(&staging).await;
+            return Err(CoreError::Storage(e.to_string()));
+        }
+
+        let replaced = self.project_storage_dir(&format!(".{}.replaced", hash));
+        if replaced.exists() {
+            tokio::fs::remove_dir_all(&replaced).await?;
+        }
+        if storage_dir.exists() {
+            tokio::fs::rename(&storage_dir, &replaced).await?;
+        }
+        tokio::fs::rename(&staging, &storage_dir).await?;
+        if replaced.exists() {
+            tokio::fs::remove_dir_all(&replaced).await?;
+        }
+
+        let report = contents.report(&info);
+        self.events.record(
+            EventKind::ProjectImported,
+            Some(&canonical),
+            format!(
+                "Imported {} file(s) exported from {}",
+                report.files,
+                info.project_path.display()
+            ),
+        );
+        tracing::info!(
+            project = ?canonical,
+            source = ?info.project_path,
+            files = report.files,
+            "Project imported"
+        );
+        Ok(report)
+    }
+
+    /// Compute a hash for a project: of its id if it has one, else of its
+    /// path
     fn compute_hash(path: &Path) -> String {
+        match read_project_id(path) {
+            Some(id) => {
+                let mut hasher = DefaultHasher::new();
+                id.hash(&mut hasher);
+                format!("{:016x}", hasher.finish())
+            }
+            None => Self::path_hash(path),
+        }
+    }
+
+    /// Hash of a project identified by its path alone
+    fn path_hash(path: &Path) -> String {
         let mut hasher = DefaultHasher::new();
         path.hash(&mut hasher);
         format!("{:016x}", hasher.finish())
@@ -152,6 +691,13 @@ impl ProjectManager {
         self.data_dir.join("projects").join(hash)
     }
 
+    /// Whether a project is stored under a hash
+    fn has_manifest(&self, hash: &str) -> bool {
+        self.project_storage_dir(hash)
+            .join("manifest.json")
+            .exists()
+    }
+
     /// Load a project from disk
     async fn load_project(&self, cwd: &Path) -> Result<Project, CoreError> {
         let hash = Self::compute_hash(cwd);
@@ -161,25 +707,525 @@ impl ProjectManager {
             return Err(CoreError::NotInitialized(cwd.display().to_string()));
         }
 
-        Project::load(&storage_dir).await
+        let mut project = Project::load(&storage_dir).await?;
+        if project.path != cwd {
+            // Found by id under a new path: the checkout was moved
+            self.relocate(&mut project, cwd).await?;
+        }
+        Ok(project)
+    }
+
+    /// Point a moved project's manifest and stored trees at its new root.
+    async fn relocate(&self, project: &mut Project, root: &Path) -> Result<(), CoreError> {
+        let previous = std::mem::replace(&mut project.path, root.to_path_buf());
+        project.manifest.project_path = root.to_path_buf();
+        project.config = ProjectConfig::load_or_default(root).await;
+        project.save_manifest().await?;
+
+        let storage = self.storage();
+        let hash = storage.project_hash(root);
+        storage
+            .relocate(&hash, &hash, root)
+            .await
+            .map_err(|e| CoreError::Storage(e.to_string()))?;
+
+        self.events.record(
+            EventKind::ProjectRelinked,
+            Some(root),
+            format!("Moved from {}", previous.display()),
+        );
+        tracing::info!(from = ?previous, to = ?root, "Project moved");
+        Ok(())
+    }
+
+    /// Add directories to a project's artifact allowlist.
+    pub async fn extend_artifact_allowlist(
+        &self,
+        cwd: &Path,
+        dirs: Vec<PathBuf>,
+    ) -> Result<(), CoreError> {
+        if dirs.is_empty() {
+            return Ok(());
+        }
+        let project = self.get_project(cwd).await?;
+        let _lock = self.jobs.lock_project(&project.path).await;
+        let project = self.get_project(cwd).await?;
+        let mut updated = (*project).clone();
+        for dir in dirs {
+            if !updated.manifest.artifact_allowlist.contains(&dir) {
+                updated.manifest.artifact_allowlist.push(dir);
+            }
+        }
+        updated.save_manifest().await?;
+        self.cache_project(Arc::new(updated)).await;
+        Ok(())
+    }
+
+    /// Scan a project, persist its skeleton tree, and update the manifest.
+    ///
+    /// The scan runs as a cancellable indexing job and returns
+    /// `CoreError::Cancelled` if it is cancelled before completing. The
+    /// stored tree is snapshotted afterwards if a snapshot is due. A
+    /// workspace is scanned root by root and stored as one merged tree.
+    pub async fn scan_project(&self, cwd: &Path) -> Result<ScanResult, CoreError> {
+        self.scan_project_cancellable(cwd, &CancelToken::new())
+            .await
+    }
+
+    async fn scan_project_cancellable(
+        &self,
+        cwd: &Path,
+        cancel: &CancelToken,
+    ) -> Result<ScanResult, CoreError> {
+        let project = self.get_project(cwd).await?;
+        check_tree_writable(&project)?;
+        let job = self
+            .jobs
+            .start_exclusive(JobKind::Indexing, &project.path)
+            .await;
+        // The manifest may have changed while the job was queued
+        let project = self.get_project(cwd).await?;
+        self.scan_locked(&project, &job, cancel).await
+    }
+
+    /// Scan a project for a job holding its write lock.
+    async fn scan_locked(
+        &self,
+        project: &Project,
+        job: &JobGuard,
+        cancel: &CancelToken,
+    ) -> Result<ScanResult, CoreError> {
+        // Pick up config edits made since the project was loaded
+        let mut updated = project.clone();
+        updated.reload_config().await;
+        self.apply_flags(&mut updated);
+
+        let started_at = Utc::now();
+        let mut scans = Vec::new();
+        for (root, options) in scan_roots(&updated).await? {
+            let scan = Scanner::with_options(options)
+                .with_progress(job.progress())
+                .with_cancel(cancel.clone())
+                .scan(&root_dir(&project.path, &root))
+                .await
+                .map_err(scan_error)?;
+            scans.push((root, scan));
+        }
+        let scan = merge_scans(&project.path, scans);
+
+        let mut tree = TreeBuilder::new()
+            .with_progress(job.progress())
+            .build(&scan);
+        tree.scanned_at = Some(started_at);
+        let storage = self.storage();
+        let tree_hash = storage.project_hash(&project.path);
+        storage
+            .save_skeleton(&tree, &tree_hash)
+            .await
+            .map_err(|e| CoreError::Storage(e.to_string()))?;
+        storage
+            .save_dependencies(&tree, &tree_hash)
+            .await
+            .map_err(|e| CoreError::Storage(e.to_string()))?;
+
+        self.record_scan(
+            updated,
+            scan.files.len(),
+            &scan.languages,
+            &scan.frameworks,
+            &scan.excluded_dirs,
+        )
+        .await?;
+
+        Ok(scan)
+    }
+
+    /// Bring a project's stored tree up to date, reading only the files
+    /// that changed since it was last scanned.
+    ///
+    /// Runs as a cancellable indexing job like [`ProjectManager::scan_project`],
+    /// which it falls back to when there is no readable stored tree or the
+    /// project is a workspace.
+    pub async fn index_project(&self, cwd: &Path) -> Result<IndexSummary, CoreError> {
+        self.index_project_cancellable(cwd, &CancelToken::new())
+            .await
+    }
+
+    /// Like [`ProjectManager::index_project`], but also stops with
+    /// `CoreError::Cancelled` once `cancel` is cancelled.
+    pub async fn index_project_cancellable(
+        &self,
+        cwd: &Path,
+        cancel: &CancelToken,
+    ) -> Result<IndexSummary, CoreError> {
+        let project = self.get_project(cwd).await?;
+        check_tree_writable(&project)?;
+        let job = self
+            .jobs
+            .start_exclusive(JobKind::Indexing, &project.path)
+            .await;
+        let project = self.get_project(cwd).await?;
+        let mut updated = (*project).clone();
+        updated.reload_config().await;
+        self.apply_flags(&mut updated);
+
+        let storage = self.storage();
+        let tree_hash = storage.project_hash(&project.path);
+        let mut roots = scan_roots(&updated).await?;
+        let single_root = roots.len() == 1 && roots[0].0.as_os_str().is_empty();
+        let previous = match storage.load_skeleton(&tree_hash).await {
+            Ok(tree) if single_root => tree,
+            _ => {
+                let scan = self.scan_locked(&project, &job, cancel).await?;
+                return Ok(IndexSummary {
+                    file_count: scan.files.len(),
+                    excluded_dirs: scan.excluded_dirs,
+                    update: None,
+                });
+            }
+        };
+
+        let (_, options) = roots.remove(0);
+        let rescan = Scanner::with_options(options)
+            .with_progress(job.progress())
+            .with_cancel(cancel.clone())
+            .rescan(&project.path, &previous)
+            .await
+            .map_err(scan_error)?;
+        let (report, file_count) = storage
+            .apply_rescan(&tree_hash, &rescan)
+            .await
+            .map_err(|e| CoreError::Storage(e.to_string()))?;
+
+        self.record_scan(
+            updated,
+            file_count,
+            &rescan.languages,
+            &rescan.frameworks,
+            &rescan.excluded_dirs,
+        )
+        .await?;
+
+        Ok(IndexSummary {
+            file_count,
+            excluded_dirs: rescan.excluded_dirs,
+            update: Some(report),
+        })
+    }
+
+    /// Snapshot a freshly indexed tree if a snapshot is due, and record the
+    /// scan in the project's manifest.
+    async fn record_scan(
+        &self,
+        mut updated: Project,
+        file_count: usize,
+        languages: &[Language],
+        frameworks: &[Framework],
+        excluded_dirs: &[ExcludedDir],
+    ) -> Result<(), CoreError> {
+        if self.snapshots.enabled {
+            let storage = self.storage();
+            let tree_hash = storage.project_hash(&updated.path);
+            let interval = Duration::from_secs(self.snapshots.interval_secs);
+            if let Err(e) = storage
+                .snapshot(&tree_hash, interval, self.snapshots.keep)
+                .await
+            {
+                tracing::warn!(error = %e, "Failed to snapshot tree");
+            }
+        }
+
+        updated.manifest.auto_excluded = excluded_dirs.iter().map(|d| d.path.clone()).collect();
+        updated
+            .update_scan(
+                file_count,
+                languages.iter().map(|l| l.name().to_string()).collect(),
+                frameworks.iter().map(|f| f.name.clone()).collect(),
+            )
+            .await?;
+        self.events.record(
+            EventKind::IndexRun,
+            Some(&updated.path),
+            format!("Indexed {} files", file_count),
+        );
+        self.cache_project(Arc::new(updated)).await;
+        Ok(())
+    }
+
+    /// Remove paths matching `globs` from a project's stored tree without
+    /// re-indexing, and update the manifest file count.
+    pub async fn prune_tree(&self, cwd: &Path, globs: &[String]) -> Result<PruneReport, CoreError> {
+        let project = self.get_project(cwd).await?;
+        check_tree_writable(&project)?;
+        let globs = compile_globs(globs).map_err(|e| CoreError::InvalidGlob(e.to_string()))?;
+        let _lock = self.jobs.lock_project(&project.path).await;
+        let project = self.get_project(cwd).await?;
+
+        let storage = self.storage();
+        let tree_hash = storage.project_hash(&project.path);
+        let report = storage
+            .prune(&tree_hash, &globs)
+            .await
+            .map_err(|e| CoreError::Storage(e.to_string()))?;
+
+        if report.removed_files > 0 {
+            let mut updated = (*project).clone();
+            updated.manifest.file_count = updated
+                .manifest
+                .file_count
+                .saturating_sub(report.removed_files);
+            updated.save_manifest().await?;
+            self.cache_project(Arc::new(updated)).await;
+        }
+
+        Ok(report)
+    }
+
+    /// Re-index only the changed files of a project and update the manifest
+    /// file count.
+    ///
+    /// Paths are absolute. Files that no longer exist or would be skipped by
+    /// a full scan are removed from the tree. Renamed files, including every
+    /// file below a renamed directory, keep their nodes when their content is
+    /// unchanged. In a workspace, files outside its roots are left out.
+    pub async fn update_files(
+        &self,
+        cwd: &Path,
+        changes: &[FileChange],
+    ) -> Result<UpdateReport, CoreError> {
+        let project = self.get_project(cwd).await?;
+        check_tree_writable(&project)?;
+        let _lock = self.jobs.lock_project(&project.path).await;
+        let project = self.get_project(cwd).await?;
+        let scanners: Vec<(PathBuf, Scanner)> = scan_roots(&project)
+            .await?
+            .into_iter()
+            .map(|(root, options)| (root, Scanner::with_options(options)))
+            .collect();
+        // The scanner of the root containing a path relative to the project
+        let scanner_for = |rel_path: &Path| {
+            scanners
+                .iter()
+                .find(|(root, _)| rel_path.starts_with(root))
+                .map(|(root, scanner)| (root, root_dir(&project.path, root), scanner))
+        };
+
+        let mut updates = Vec::with_capacity(changes.len());
+        for change in changes {
+            // Moved in from outside the project counts as created
+            let from = match &change.kind {
+                ChangeKind::Renamed { from } => from
+                    .strip_prefix(&project.path)
+                    .ok()
+                    .filter(|from| scanner_for(from).is_some()),
+                _ => None,
+            };
+            let Some((rel_path, (root, dir, scanner))) = change
+                .path
+                .strip_prefix(&project.path)
+                .ok()
+                .and_then(|rel_path| Some((rel_path, scanner_for(rel_path)?)))
+            else {
+                // Moved out of the project
+                if let Some(from) = from {
+                    updates.push(FileUpdate::Remove(from.to_path_buf()));
+                }
+                continue;
+            };
+
+            if let Some(from) = from.filter(|_| change.path.is_dir()) {
+                let files = scanner
+                    .scan_dir(&dir, &change.path)
+                    .await
+                    .map_err(|e| CoreError::Storage(e.to_string()))?;
+                updates.extend(files.into_iter().map(|mut file| {
+                    file.path = root.join(&file.path);
+                    FileUpdate::Rename {
+                        from: from.join(file.path.strip_prefix(rel_path).unwrap_or(&file.path)),
+                        file,
+                    }
+                }));
+                continue;
+            }
+
+            let scanned = scanner
+                .scan_file(&dir, &change.path)
+                .await
+                .map_err(|e| CoreError::Storage(e.to_string()))?
+                .map(|mut file| {
+                    file.path = root.join(&file.path);
+                    file
+                });
+            if let (None, Some(from)) = (&scanned, from) {
+                updates.push(FileUpdate::Remove(from.to_path_buf()));
+            }
+            updates.push(match (scanned, from) {
+                (Some(file), Some(from)) => FileUpdate::Rename {
+                    from: from.to_path_buf(),
+                    file,
+                },
+                (Some(file), None) => FileUpdate::Upsert(file),
+                (None, _) => FileUpdate::Remove(rel_path.to_path_buf()),
+            });
+        }
+
+        let storage = self.storage();
+        let tree_hash = storage.project_hash(&project.path);
+        let (report, file_count) = storage
+            .update_files(&tree_hash, &updates)
+            .await
+            .map_err(|e| CoreError::Storage(e.to_string()))?;
+
+        if !report.is_empty() && file_count != project.manifest.file_count {
+            let mut updated = (*project).clone();
+            updated.manifest.file_count = file_count;
+            updated.save_manifest().await?;
+            self.cache_project(Arc::new(updated)).await;
+        }
+
+        Ok(report)
+    }
+
+    /// Get the enriched tree of a project, starting from the skeleton if
+    /// it has not been enriched yet
+    pub async fn get_enriched_tree(&self, cwd: &Path) -> Result<Tree, CoreError> {
+        let project = self.get_project(cwd).await?;
+        let storage = self.storage();
+        let tree_hash = storage.project_hash(&project.path);
+        match storage.load_enriched(&tree_hash).await {
+            Ok(tree) => Ok(tree),
+            Err(IndexerError::NotFound(_)) => storage
+                .load_skeleton(&tree_hash)
+                .await
+                .map_err(|e| CoreError::Storage(e.to_string())),
+            Err(e) => Err(CoreError::Storage(e.to_string())),
+        }
+    }
+
+    /// Persist a project's enriched tree, marking the project enriched once
+    /// enrichment is `complete`
+    ///
+    /// A read-only project is enriched until its first enrichment completes.
+    pub async fn save_enriched_tree(
+        &self,
+        cwd: &Path,
+        tree: &Tree,
+        complete: bool,
+    ) -> Result<(), CoreError> {
+        let project = self.get_project(cwd).await?;
+        let _lock = self.jobs.lock_project(&project.path).await;
+        let project = self.get_project(cwd).await?;
+        if project.read_only && project.manifest.enriched {
+            return Err(CoreError::ReadOnly(project.path.display().to_string()));
+        }
+        let storage = self.storage();
+        storage
+            .save_enriched(tree, &storage.project_hash(&project.path))
+            .await
+            .map_err(|e| CoreError::Storage(e.to_string()))?;
+
+        if complete && !project.manifest.enriched {
+            let mut updated = (*project).clone();
+            updated.mark_enriched().await?;
+            self.cache_project(Arc::new(updated)).await;
+        }
+        Ok(())
+    }
+
+    /// Get the tree kept in one of a project's snapshots
+    pub async fn get_snapshot_tree(&self, cwd: &Path, name: &str) -> Result<Tree, CoreError> {
+        let project = self.get_project(cwd).await?;
+        let storage = self.storage();
+        storage
+            .load_snapshot(&storage.project_hash(&project.path), name)
+            .await
+            .map_err(|e| CoreError::Storage(e.to_string()))
     }
 
     /// Get the tree for a project
     pub async fn get_tree(&self, cwd: &Path) -> Result<engram_indexer::tree::Tree, CoreError> {
         let project = self.get_project(cwd).await?;
-        let storage = engram_indexer::storage::Storage::new(self.data_dir.clone());
-        storage.load_tree(&project.path, false).await.map_err(|e| {
-            CoreError::Io(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                e.to_string(),
-            ))
-        })
+        let storage = self.storage();
+        storage
+            .load_tree(&project.path, false)
+            .await
+            .map_err(|e| CoreError::Io(std::io::Error::other(e.to_string())))
+    }
+}
+
+/// Fail with `CoreError::ReadOnly` if a scan must not rewrite the project's
+/// stored tree
+fn check_tree_writable(project: &Project) -> Result<(), CoreError> {
+    if project.can_write_tree() {
+        Ok(())
+    } else {
+        Err(CoreError::ReadOnly(project.path.display().to_string()))
+    }
+}
+
+/// The least recently used unpinned project other than `keep`
+fn lru_unpinned(cache: &LruCache<PathBuf, Arc<Project>>, keep: Option<&Path>) -> Option<PathBuf> {
+    cache
+        .iter()
+        .rev()
+        .find(|(path, project)| !project.pinned && Some(path.as_path()) != keep)
+        .map(|(path, _)| path.clone())
+}
+
+fn scan_error(e: IndexerError) -> CoreError {
+    match e {
+        IndexerError::Cancelled => CoreError::Cancelled,
+        e => CoreError::Storage(e.to_string()),
+    }
+}
+
+/// Roots to scan with the options for each, relative to the project.
+///
+/// A plain project is its own single root (the empty path). Each root of a
+/// workspace gets its own `.engram/config.toml` overrides and the part of
+/// the artifact allowlist below it.
+async fn scan_roots(project: &Project) -> Result<Vec<(PathBuf, ScanOptions)>, CoreError> {
+    let options = |config: &ProjectConfig, artifact_allowlist| {
+        let mut options = ScanOptions {
+            artifact_allowlist,
+            ..Default::default()
+        };
+        config.apply_scan(&mut options);
+        options
+    };
+
+    let Some(workspace) = Workspace::load(&project.path).await? else {
+        let allowlist = project.manifest.artifact_allowlist.clone();
+        return Ok(vec![(PathBuf::new(), options(&project.config, allowlist))]);
+    };
+    let mut roots = Vec::with_capacity(workspace.roots.len());
+    for root in workspace.roots {
+        let config = ProjectConfig::load_or_default(&project.path.join(&root)).await;
+        let allowlist = project
+            .manifest
+            .artifact_allowlist
+            .iter()
+            .filter_map(|dir| dir.strip_prefix(&root).ok())
+            .map(Path::to_path_buf)
+            .collect();
+        roots.push((root, options(&config, allowlist)));
+    }
+    Ok(roots)
+}
+
+/// Absolute directory of a root returned by [`scan_roots`].
+fn root_dir(project_path: &Path, root: &Path) -> PathBuf {
+    if root.as_os_str().is_empty() {
+        project_path.to_path_buf()
+    } else {
+        project_path.join(root)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::GcReason;
     use tempfile::tempdir;
 
     fn test_config(temp_dir: &Path) -> DaemonConfig {
@@ -215,6 +1261,33 @@ mod tests {
         assert_eq!(cached.hash, project.hash);
     }
 
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_sqlite_backend() {
+        let temp_dir = tempdir().unwrap();
+        let mut config = test_config(temp_dir.path());
+        config.storage.backend = crate::StorageBackendKind::Sqlite;
+        let backend = config.storage.open(&config.data_dir).unwrap();
+        let manager = ProjectManager::new(&config).with_backend(backend);
+
+        let project_dir = temp_dir.path().join("test_project");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        std::fs::write(project_dir.join("main.rs"), "fn main() {}").unwrap();
+        let project = manager.init_project(&project_dir).await.unwrap();
+        manager.index_project(&project_dir).await.unwrap();
+
+        // The tree is kept in the database, not as a file
+        let tree = manager.get_tree(&project_dir).await.unwrap();
+        assert_eq!(tree.root_path, project.path);
+        let storage = Storage::new(config.data_dir.clone());
+        let dir = storage.project_dir(&storage.project_hash(&project.path));
+        assert!(!dir.join("skeleton.json").exists());
+        assert!(storage
+            .load_skeleton(&storage.project_hash(&project.path))
+            .await
+            .is_err());
+    }
+
     #[tokio::test]
     async fn test_lru_eviction() {
         let temp_dir = tempdir().unwrap();
@@ -230,6 +1303,15 @@ mod tests {
 
         // Should only have 3 in cache (LRU evicts oldest)
         assert_eq!(manager.loaded_count().await, 3);
+
+        // Shrinking the cache evicts down to the new limit
+        manager.set_max_projects(1).await;
+        assert_eq!(manager.max_projects(), 1);
+        assert_eq!(manager.loaded_count().await, 1);
+        assert_eq!(
+            manager.loaded_paths().await,
+            vec![temp_dir.path().join("project_3").canonicalize().unwrap()]
+        );
     }
 
     #[tokio::test]
@@ -264,6 +1346,236 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_moved_project_found_by_id() {
+        let temp_dir = tempdir().unwrap();
+        let config = test_config(temp_dir.path());
+        let manager = ProjectManager::new(&config);
+
+        let project_dir = temp_dir.path().join("before");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        std::fs::write(project_dir.join("main.rs"), "fn main() {}").unwrap();
+        manager.init_project(&project_dir).await.unwrap();
+        manager.scan_project(&project_dir).await.unwrap();
+        assert!(read_project_id(&project_dir).is_some());
+
+        let moved_dir = temp_dir.path().join("after");
+        std::fs::rename(&project_dir, &moved_dir).unwrap();
+        let moved_dir = moved_dir.canonicalize().unwrap();
+        let project = manager.get_project(&moved_dir).await.unwrap();
+        assert_eq!(project.path, moved_dir);
+        assert_eq!(project.manifest.project_path, moved_dir);
+        assert_eq!(project.manifest.file_count, 1);
+
+        let storage = Storage::new(config.data_dir.clone());
+        let tree = storage.load_tree(&moved_dir, false).await.unwrap();
+        assert_eq!(tree.root_path, moved_dir);
+    }
+
+    #[tokio::test]
+    async fn test_relink_project_without_id() {
+        let temp_dir = tempdir().unwrap();
+        let config = test_config(temp_dir.path());
+        let manager = ProjectManager::new(&config);
+        let storage = Storage::new(config.data_dir.clone());
+
+        // Stored under its path, as before projects had ids
+        let project_dir = temp_dir.path().join("legacy");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        let project_dir = project_dir.canonicalize().unwrap();
+        let hash = ProjectManager::path_hash(&project_dir);
+        Project::create(&project_dir, &manager.project_storage_dir(&hash), &hash)
+            .await
+            .unwrap();
+        let tree = Tree::new(project_dir.clone());
+        storage
+            .save_skeleton(&tree, &storage.path_hash(&project_dir))
+            .await
+            .unwrap();
+
+        let moved_dir = temp_dir.path().join("moved");
+        std::fs::rename(&project_dir, &moved_dir).unwrap();
+        let moved_dir = moved_dir.canonicalize().unwrap();
+        assert!(!manager.is_initialized(&moved_dir).await);
+        assert!(matches!(
+            manager.relink(&moved_dir, None).await,
+            Err(CoreError::NotInitialized(_))
+        ));
+
+        let report = manager
+            .relink(&moved_dir, Some(&project_dir))
+            .await
+            .unwrap();
+        assert!(report.adopted);
+        assert_eq!(report.previous_path, project_dir);
+        assert_eq!(read_project_id(&moved_dir), Some(report.id));
+        assert!(manager.is_initialized(&moved_dir).await);
+        assert_eq!(
+            manager.get_project(&moved_dir).await.unwrap().path,
+            moved_dir
+        );
+        let tree = storage.load_tree(&moved_dir, false).await.unwrap();
+        assert_eq!(tree.root_path, moved_dir);
+
+        // Relinking again is a no-op
+        let report = manager.relink(&moved_dir, None).await.unwrap();
+        assert!(!report.adopted);
+        assert_eq!(report.previous_path, moved_dir);
+    }
+
+    #[tokio::test]
+    async fn test_export_import_archive() {
+        let temp_dir = tempdir().unwrap();
+        let config = test_config(temp_dir.path());
+        let manager = ProjectManager::new(&config);
+        let storage = Storage::new(config.data_dir.clone());
+        let archive = temp_dir.path().join("app.tar.gz");
+
+        let source = temp_dir.path().join("source");
+        std::fs::create_dir_all(&source).unwrap();
+        let source = source.canonicalize().unwrap();
+        assert!(matches!(
+            manager.export_archive(&source, &archive).await,
+            Err(CoreError::NotInitialized(_))
+        ));
+        manager.init_project(&source).await.unwrap();
+        let hash = storage.project_hash(&source);
+        storage
+            .save_skeleton(&Tree::new(source.clone()), &hash)
+            .await
+            .unwrap();
+        storage
+            .append_experience(&source, &serde_json::json!({"note": "kept"}))
+            .await
+            .unwrap();
+
+        let exported = manager.export_archive(&source, &archive).await.unwrap();
+        assert_eq!(exported.source_path, source);
+        assert!(exported.files >= 3);
+
+        // A checkout elsewhere, not yet initialized, takes over the data
+        let target = temp_dir.path().join("target");
+        std::fs::create_dir_all(&target).unwrap();
+        let target = target.canonicalize().unwrap();
+        let imported = manager.import_archive(&target, &archive).await.unwrap();
+        assert_eq!(imported.files, exported.files);
+        assert_eq!(imported.source_path, source);
+        assert!(manager.is_initialized(&target).await);
+        assert_eq!(manager.get_project(&target).await.unwrap().path, target);
+        let tree = storage.load_tree(&target, false).await.unwrap();
+        assert_eq!(tree.root_path, target);
+        let experiences: Vec<serde_json::Value> =
+            storage.load_experiences(&target, 10).await.unwrap();
+        assert_eq!(experiences.len(), 1);
+
+        std::fs::write(&archive, b"garbage").unwrap();
+        assert!(manager.import_archive(&target, &archive).await.is_err());
+        assert!(manager.is_initialized(&target).await);
+    }
+
+    /// Set the modification time of `path` and everything under it.
+    fn backdate(path: &Path, time: std::time::SystemTime) {
+        if path.is_dir() {
+            for entry in std::fs::read_dir(path).unwrap().flatten() {
+                backdate(&entry.path(), time);
+            }
+        }
+        std::fs::File::open(path)
+            .unwrap()
+            .set_modified(time)
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_collect_garbage() {
+        let temp_dir = tempdir().unwrap();
+        let config = test_config(temp_dir.path());
+        let manager = ProjectManager::new(&config);
+        let storage = Storage::new(config.data_dir.clone());
+
+        let mut roots = Vec::new();
+        for name in ["deleted", "idle", "active", "unmounted"] {
+            let dir = temp_dir.path().join(name);
+            std::fs::create_dir_all(&dir).unwrap();
+            let project = manager.init_project(&dir).await.unwrap();
+            storage
+                .save_skeleton(
+                    &Tree::new(project.path.clone()),
+                    &storage.project_hash(&project.path),
+                )
+                .await
+                .unwrap();
+            manager.unload_project(&dir).await.unwrap();
+            roots.push(project.path.clone());
+        }
+        let month_ago = std::time::SystemTime::now() - Duration::from_secs(30 * 24 * 60 * 60);
+        for root in &roots[..2] {
+            backdate(
+                &manager.project_storage_dir(&ProjectManager::compute_hash(root)),
+                month_ago,
+            );
+            backdate(&storage.project_dir(&storage.project_hash(root)), month_ago);
+        }
+        // A root that is gone is garbage after a grace period, in case it
+        // is on a volume that is not mounted right now
+        std::fs::remove_dir_all(&roots[0]).unwrap();
+        std::fs::remove_dir_all(&roots[3]).unwrap();
+        manager.get_project(&roots[2]).await.unwrap();
+
+        let report = manager.collect_garbage(Some(7), true, &[]).await.unwrap();
+        let found: Vec<_> = report
+            .candidates
+            .iter()
+            .map(|c| (c.root.clone().unwrap(), c.reason, c.dirs.len()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (roots[0].clone(), GcReason::SourceMissing, 2),
+                (roots[1].clone(), GcReason::Idle, 2),
+            ]
+        );
+        assert!(report.candidates[1].idle_days >= 29);
+        assert!(report.reclaimable_bytes() > 0);
+        assert_eq!(report.deleted, 0);
+
+        // Without an idle limit only projects whose root is gone are garbage
+        let report = manager.collect_garbage(Some(0), true, &[]).await.unwrap();
+        assert_eq!(report.candidates.len(), 1);
+        assert_eq!(report.candidates[0].reason, GcReason::SourceMissing);
+
+        // Without a grace period they are garbage right away, however
+        // recently used
+        let mut eager = test_config(temp_dir.path());
+        eager.gc.missing_grace_days = 0;
+        let report = ProjectManager::new(&eager)
+            .collect_garbage(Some(0), true, &[])
+            .await
+            .unwrap();
+        let found: Vec<_> = report
+            .candidates
+            .iter()
+            .map(|c| c.root.clone().unwrap())
+            .collect();
+        assert_eq!(found, vec![roots[0].clone(), roots[3].clone()]);
+        assert_eq!(report.candidates[1].idle_days, 0);
+
+        let report = manager.collect_garbage(Some(7), true, &[]).await.unwrap();
+        let only = vec![report.candidates[0].dirs[0].clone()];
+        let report = manager
+            .collect_garbage(Some(7), false, &only)
+            .await
+            .unwrap();
+        assert_eq!(report.deleted, 1);
+        for dir in &report.candidates[0].dirs {
+            assert!(!temp_dir.path().join(dir).exists());
+        }
+        assert!(manager.is_initialized(&roots[1]).await);
+        assert!(manager.is_initialized(&roots[2]).await);
+        let events = manager.events().since(0, usize::MAX);
+        assert_eq!(events.last().unwrap().kind, EventKind::GarbageCollected);
+    }
+
     #[tokio::test]
     async fn test_is_initialized_nonexistent_path() {
         let temp_dir = tempdir().unwrap();
@@ -319,4 +1631,476 @@ mod tests {
 
         assert_eq!(manager.loaded_count().await, 1);
     }
+
+    #[tokio::test]
+    async fn test_pinned_projects_never_evicted() {
+        let temp_dir = tempdir().unwrap();
+        let pinned_dir = temp_dir.path().join("primary");
+        std::fs::create_dir_all(&pinned_dir).unwrap();
+        let config = DaemonConfig {
+            max_projects: 2,
+            pinned_projects: vec![pinned_dir.clone()],
+            ..test_config(temp_dir.path())
+        };
+        let manager = ProjectManager::new(&config);
+
+        let pinned = manager.init_project(&pinned_dir).await.unwrap();
+        assert!(pinned.pinned);
+        for i in 0..3 {
+            let project_dir = temp_dir.path().join(format!("scratch_{}", i));
+            std::fs::create_dir_all(&project_dir).unwrap();
+            manager.init_project(&project_dir).await.unwrap();
+        }
+        let loaded = manager.loaded_paths().await;
+        assert_eq!(loaded.len(), 2);
+        assert!(loaded.contains(&pinned.path));
+
+        manager.evict_lru().await;
+        manager.evict_lru().await;
+        assert_eq!(manager.loaded_paths().await, vec![pinned.path.clone()]);
+
+        // A project config pins too, and the cache grows rather than evict
+        let configured_dir = temp_dir.path().join("configured");
+        std::fs::create_dir_all(configured_dir.join(".engram")).unwrap();
+        std::fs::write(
+            configured_dir.join(".engram/config.toml"),
+            "pinned = true\n",
+        )
+        .unwrap();
+        manager.init_project(&configured_dir).await.unwrap();
+        let scratch_dir = temp_dir.path().join("scratch_3");
+        std::fs::create_dir_all(&scratch_dir).unwrap();
+        manager.init_project(&scratch_dir).await.unwrap();
+        assert_eq!(manager.loaded_count().await, 3);
+
+        manager.evict_all_except(&scratch_dir).await;
+        assert_eq!(manager.loaded_count().await, 3);
+    }
+
+    #[tokio::test]
+    async fn test_read_only_project_indexed_once() {
+        let temp_dir = tempdir().unwrap();
+        let project_dir = temp_dir.path().join("project");
+        std::fs::create_dir_all(project_dir.join("src")).unwrap();
+        std::fs::create_dir_all(project_dir.join(".engram")).unwrap();
+        std::fs::write(
+            project_dir.join(".engram/config.toml"),
+            "read_only = true\n",
+        )
+        .unwrap();
+        std::fs::write(project_dir.join("src/main.rs"), "fn main() {}").unwrap();
+        let manager = ProjectManager::new(&test_config(temp_dir.path()));
+
+        let project = manager.init_project(&project_dir).await.unwrap();
+        assert!(project.read_only);
+        assert!(manager.is_read_only(&project_dir).await);
+
+        // The first scan indexes the project; later writes are refused
+        manager.scan_project(&project_dir).await.unwrap();
+        let result = manager.index_project(&project_dir).await;
+        assert!(matches!(result, Err(CoreError::ReadOnly(_))));
+        let result = manager
+            .prune_tree(&project_dir, &["src/**".to_string()])
+            .await;
+        assert!(matches!(result, Err(CoreError::ReadOnly(_))));
+        let changes = [FileChange {
+            path: project_dir.join("src/main.rs"),
+            kind: ChangeKind::Modified,
+        }];
+        let result = manager.update_files(&project_dir, &changes).await;
+        assert!(matches!(result, Err(CoreError::ReadOnly(_))));
+    }
+
+    #[tokio::test]
+    async fn test_unload_project() {
+        let temp_dir = tempdir().unwrap();
+        let config = test_config(temp_dir.path());
+        let manager = ProjectManager::new(&config);
+
+        let project_dir = temp_dir.path().join("project");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        manager.init_project(&project_dir).await.unwrap();
+
+        assert!(manager.unload_project(&project_dir).await.unwrap());
+        assert_eq!(manager.loaded_count().await, 0);
+        assert!(!manager.unload_project(&project_dir).await.unwrap());
+
+        // Still initialized and loadable again
+        assert!(manager.is_initialized(&project_dir).await);
+        manager.get_project(&project_dir).await.unwrap();
+        assert_eq!(manager.loaded_count().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_scan_project_reports_auto_excluded() {
+        let temp_dir = tempdir().unwrap();
+        let config = test_config(temp_dir.path());
+        let manager = ProjectManager::new(&config);
+
+        let project_dir = temp_dir.path().join("artifact_project");
+        std::fs::create_dir_all(project_dir.join("src")).unwrap();
+        std::fs::create_dir_all(project_dir.join("target/debug")).unwrap();
+        std::fs::create_dir_all(project_dir.join("dist")).unwrap();
+        std::fs::write(project_dir.join("Cargo.toml"), "[package]").unwrap();
+        std::fs::write(project_dir.join("src/main.rs"), "fn main() {}").unwrap();
+        std::fs::write(project_dir.join("target/debug/out.rs"), "fn x() {}").unwrap();
+        std::fs::write(project_dir.join("dist/cli.rs"), "fn y() {}").unwrap();
+
+        manager.init_project(&project_dir).await.unwrap();
+        let scan = manager.scan_project(&project_dir).await.unwrap();
+
+        assert_eq!(scan.files.len(), 3); // Cargo.toml, src/main.rs, dist/cli.rs
+        let project = manager.get_project(&project_dir).await.unwrap();
+        assert_eq!(project.manifest.file_count, 3);
+        assert_eq!(
+            project.manifest.auto_excluded,
+            vec![PathBuf::from("target")]
+        );
+        assert!(manager.get_tree(&project_dir).await.is_ok());
+
+        // Allowlisting brings the directory back on the next scan
+        manager
+            .extend_artifact_allowlist(&project_dir, vec![PathBuf::from("target")])
+            .await
+            .unwrap();
+        let scan = manager.scan_project(&project_dir).await.unwrap();
+        assert_eq!(scan.files.len(), 4);
+        assert!(scan.excluded_dirs.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_update_files() {
+        let temp_dir = tempdir().unwrap();
+        let config = test_config(temp_dir.path());
+        let manager = ProjectManager::new(&config);
+
+        let project_dir = temp_dir.path().join("watched_project");
+        std::fs::create_dir_all(project_dir.join("src")).unwrap();
+        std::fs::write(project_dir.join("src/main.rs"), "fn main() {}").unwrap();
+        std::fs::write(project_dir.join("src/old.rs"), "fn old() {}").unwrap();
+        let project_dir = project_dir.canonicalize().unwrap();
+
+        manager.init_project(&project_dir).await.unwrap();
+        manager.scan_project(&project_dir).await.unwrap();
+
+        std::fs::write(project_dir.join("src/main.rs"), "fn main() { run() }").unwrap();
+        std::fs::write(project_dir.join("src/new.rs"), "fn fresh() {}").unwrap();
+        std::fs::remove_file(project_dir.join("src/old.rs")).unwrap();
+        let change = |path: &str, kind| FileChange {
+            path: project_dir.join(path),
+            kind,
+        };
+
+        let report = manager
+            .update_files(
+                &project_dir,
+                &[
+                    change("src/main.rs", engram_indexer::ChangeKind::Modified),
+                    change("src/new.rs", engram_indexer::ChangeKind::Created),
+                    change("src/old.rs", engram_indexer::ChangeKind::Deleted),
+                ],
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            report,
+            UpdateReport {
+                added_files: 1,
+                updated_files: 1,
+                removed_files: 1,
+                renamed_files: 0,
+            }
+        );
+
+        let tree = manager.get_tree(&project_dir).await.unwrap();
+        assert!(tree.find_by_path(&PathBuf::from("src/new.rs")).is_some());
+        assert!(tree.find_by_path(&PathBuf::from("src/old.rs")).is_none());
+        let project = manager.get_project(&project_dir).await.unwrap();
+        assert_eq!(project.manifest.file_count, 2);
+    }
+
+    #[tokio::test]
+    async fn test_index_project_rescans_changes() {
+        let temp_dir = tempdir().unwrap();
+        let config = test_config(temp_dir.path());
+        let manager = ProjectManager::new(&config);
+
+        let project_dir = temp_dir.path().join("indexed_project");
+        std::fs::create_dir_all(project_dir.join("src")).unwrap();
+        std::fs::write(project_dir.join("src/main.rs"), "fn main() {}").unwrap();
+        std::fs::write(project_dir.join("src/old.rs"), "fn old() {}").unwrap();
+        let project_dir = project_dir.canonicalize().unwrap();
+        manager.init_project(&project_dir).await.unwrap();
+
+        // Nothing stored yet: full scan
+        let summary = manager.index_project(&project_dir).await.unwrap();
+        assert_eq!(summary.file_count, 2);
+        assert!(summary.update.is_none());
+
+        std::fs::write(project_dir.join("src/main.rs"), "fn main() { run() }").unwrap();
+        std::fs::write(project_dir.join("src/new.rs"), "fn fresh() {}").unwrap();
+        std::fs::remove_file(project_dir.join("src/old.rs")).unwrap();
+
+        let summary = manager.index_project(&project_dir).await.unwrap();
+        assert_eq!(summary.file_count, 2);
+        assert_eq!(
+            summary.update,
+            Some(UpdateReport {
+                added_files: 1,
+                updated_files: 1,
+                removed_files: 1,
+                renamed_files: 0,
+            })
+        );
+
+        let tree = manager.get_tree(&project_dir).await.unwrap();
+        assert!(tree.scanned_at.is_some());
+        assert!(tree.find_by_path(&PathBuf::from("src/new.rs")).is_some());
+        assert!(tree.find_by_path(&PathBuf::from("src/old.rs")).is_none());
+        let project = manager.get_project(&project_dir).await.unwrap();
+        assert_eq!(project.manifest.file_count, 2);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_writes_are_serialized() {
+        let temp_dir = tempdir().unwrap();
+        let config = test_config(temp_dir.path());
+        let manager = Arc::new(ProjectManager::new(&config));
+
+        let project_dir = temp_dir.path().join("busy_project");
+        std::fs::create_dir_all(project_dir.join("src")).unwrap();
+        for i in 0..20 {
+            std::fs::write(project_dir.join(format!("src/f{i}.rs")), "fn f() {}").unwrap();
+        }
+        let project_dir = project_dir.canonicalize().unwrap();
+        manager.init_project(&project_dir).await.unwrap();
+        manager.index_project(&project_dir).await.unwrap();
+
+        std::fs::write(project_dir.join("src/added.rs"), "fn added() {}").unwrap();
+        let change = FileChange {
+            path: project_dir.join("src/added.rs"),
+            kind: ChangeKind::Created,
+        };
+        let tasks: Vec<_> = (0..4)
+            .map(|i| {
+                let manager = manager.clone();
+                let project_dir = project_dir.clone();
+                let change = change.clone();
+                tokio::spawn(async move {
+                    if i % 2 == 0 {
+                        manager.scan_project(&project_dir).await.map(|_| ())
+                    } else {
+                        manager
+                            .update_files(&project_dir, &[change])
+                            .await
+                            .map(|_| ())
+                    }
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+
+        assert!(manager.jobs().list().is_empty());
+        let tree = manager.get_tree(&project_dir).await.unwrap();
+        assert_eq!(tree.files().count(), 21);
+        let project = manager.get_project(&project_dir).await.unwrap();
+        assert_eq!(project.manifest.file_count, 21);
+    }
+
+    #[tokio::test]
+    async fn test_scan_workspace() {
+        let temp_dir = tempdir().unwrap();
+        let config = test_config(temp_dir.path());
+        let manager = ProjectManager::new(&config);
+
+        let workspace_dir = temp_dir.path().join("shop");
+        for dir in ["web/src", "api/src/generated", ".engram", "api/.engram"] {
+            std::fs::create_dir_all(workspace_dir.join(dir)).unwrap();
+        }
+        std::fs::write(
+            Workspace::path(&workspace_dir),
+            "roots = [\"web\", \"api\"]\n",
+        )
+        .unwrap();
+        std::fs::write(
+            ProjectConfig::path(&workspace_dir.join("api")),
+            "[scan]\nignore = [\"src/generated/\"]\n",
+        )
+        .unwrap();
+        std::fs::write(
+            workspace_dir.join("web/src/app.ts"),
+            "export const app = 1;",
+        )
+        .unwrap();
+        std::fs::write(workspace_dir.join("api/src/main.rs"), "fn main() {}").unwrap();
+        std::fs::write(workspace_dir.join("api/src/generated/api.rs"), "fn x() {}").unwrap();
+        std::fs::write(workspace_dir.join("notes.md"), "# Notes").unwrap();
+        let workspace_dir = workspace_dir.canonicalize().unwrap();
+
+        manager.init_project(&workspace_dir).await.unwrap();
+        let scan = manager.scan_project(&workspace_dir).await.unwrap();
+        assert_eq!(scan.files.len(), 2);
+
+        let tree = manager.get_tree(&workspace_dir).await.unwrap();
+        assert_eq!(tree.root_path, workspace_dir);
+        let roots: Vec<_> = tree
+            .children(tree.root_id)
+            .into_iter()
+            .map(|node| node.name.clone())
+            .collect();
+        assert_eq!(roots.len(), 2);
+        assert!(roots.contains(&"web".to_string()) && roots.contains(&"api".to_string()));
+        assert!(tree
+            .find_by_path(&PathBuf::from("web/src/app.ts"))
+            .is_some());
+        assert!(tree
+            .find_by_path(&PathBuf::from("api/src/main.rs"))
+            .is_some());
+        assert!(tree.find_by_path(&PathBuf::from("notes.md")).is_none());
+
+        // Changes outside the roots or ignored by a root are left out
+        std::fs::write(workspace_dir.join("todo.md"), "- ship").unwrap();
+        std::fs::write(workspace_dir.join("api/src/generated/more.rs"), "fn y() {}").unwrap();
+        std::fs::write(workspace_dir.join("api/src/lib.rs"), "pub fn lib() {}").unwrap();
+        let change = |path: &str| FileChange {
+            path: workspace_dir.join(path),
+            kind: ChangeKind::Created,
+        };
+        let report = manager
+            .update_files(
+                &workspace_dir,
+                &[
+                    change("todo.md"),
+                    change("api/src/generated/more.rs"),
+                    change("api/src/lib.rs"),
+                ],
+            )
+            .await
+            .unwrap();
+        assert_eq!(report.added_files, 1);
+    }
+
+    #[tokio::test]
+    async fn test_update_files_renames() {
+        let temp_dir = tempdir().unwrap();
+        let config = test_config(temp_dir.path());
+        let manager = ProjectManager::new(&config);
+
+        let project_dir = temp_dir.path().join("renamed_project");
+        std::fs::create_dir_all(project_dir.join("src/net")).unwrap();
+        std::fs::write(project_dir.join("src/main.rs"), "fn main() {}").unwrap();
+        std::fs::write(project_dir.join("src/net/http.rs"), "fn get() {}").unwrap();
+        std::fs::write(project_dir.join("src/net/tcp.rs"), "fn dial() {}").unwrap();
+        let project_dir = project_dir.canonicalize().unwrap();
+
+        manager.init_project(&project_dir).await.unwrap();
+        manager.scan_project(&project_dir).await.unwrap();
+        let tree = manager.get_tree(&project_dir).await.unwrap();
+        let id = |tree: &Tree, path: &str| tree.find_node_by_path(&PathBuf::from(path));
+        let (main, http, tcp) = (
+            id(&tree, "src/main.rs"),
+            id(&tree, "src/net/http.rs"),
+            id(&tree, "src/net/tcp.rs"),
+        );
+
+        std::fs::rename(
+            project_dir.join("src/main.rs"),
+            project_dir.join("src/app.rs"),
+        )
+        .unwrap();
+        std::fs::rename(
+            project_dir.join("src/net"),
+            project_dir.join("src/transport"),
+        )
+        .unwrap();
+        let rename = |from: &str, to: &str| FileChange {
+            path: project_dir.join(to),
+            kind: ChangeKind::Renamed {
+                from: project_dir.join(from),
+            },
+        };
+        let report = manager
+            .update_files(
+                &project_dir,
+                &[
+                    rename("src/main.rs", "src/app.rs"),
+                    rename("src/net", "src/transport"),
+                ],
+            )
+            .await
+            .unwrap();
+        assert_eq!(report.renamed_files, 3);
+        assert_eq!(report.added_files + report.removed_files, 0);
+
+        let tree = manager.get_tree(&project_dir).await.unwrap();
+        assert_eq!(id(&tree, "src/app.rs"), main);
+        assert_eq!(id(&tree, "src/transport/http.rs"), http);
+        assert_eq!(id(&tree, "src/transport/tcp.rs"), tcp);
+        assert!(id(&tree, "src/net").is_none());
+        assert_eq!(tree.file_count, 3);
+    }
+
+    #[tokio::test]
+    async fn test_scan_snapshots_and_recovers_corrupt_tree() {
+        let temp_dir = tempdir().unwrap();
+        let config = test_config(temp_dir.path());
+        let manager = ProjectManager::new(&config);
+
+        let project_dir = temp_dir.path().join("snapshot_project");
+        std::fs::create_dir_all(project_dir.join("src")).unwrap();
+        std::fs::write(project_dir.join("src/main.rs"), "fn main() {}").unwrap();
+        let project_dir = project_dir.canonicalize().unwrap();
+
+        manager.init_project(&project_dir).await.unwrap();
+        manager.scan_project(&project_dir).await.unwrap();
+
+        let storage = Storage::new(temp_dir.path().to_path_buf());
+        let hash = storage.project_hash(&project_dir);
+        assert_eq!(storage.snapshots(&hash).list().await.unwrap().len(), 1);
+
+        // A rescan within the interval takes no new snapshot
+        manager.scan_project(&project_dir).await.unwrap();
+        assert_eq!(storage.snapshots(&hash).list().await.unwrap().len(), 1);
+
+        std::fs::write(
+            storage.project_dir(&hash).join("skeleton.json"),
+            "{ truncated",
+        )
+        .unwrap();
+        let tree = manager.get_tree(&project_dir).await.unwrap();
+        assert!(tree.find_by_path(&PathBuf::from("src/main.rs")).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_scan_project_applies_project_config() {
+        let temp_dir = tempdir().unwrap();
+        let config = test_config(temp_dir.path());
+        let manager = ProjectManager::new(&config);
+
+        let project_dir = temp_dir.path().join("configured_project");
+        std::fs::create_dir_all(project_dir.join("src")).unwrap();
+        std::fs::create_dir_all(project_dir.join("fixtures")).unwrap();
+        std::fs::write(project_dir.join("src/main.rs"), "fn main() {}").unwrap();
+        std::fs::write(project_dir.join("src/big.rs"), "x".repeat(2048)).unwrap();
+        std::fs::write(project_dir.join("fixtures/case.rs"), "fn case() {}").unwrap();
+        let project_dir = project_dir.canonicalize().unwrap();
+
+        manager.init_project(&project_dir).await.unwrap();
+        // Written after init: scanning re-reads the config
+        std::fs::create_dir_all(project_dir.join(".engram")).unwrap();
+        std::fs::write(
+            project_dir.join(".engram/config.toml"),
+            "[scan]\nignore = [\"fixtures/\"]\nmax_file_size = 1024\n",
+        )
+        .unwrap();
+
+        let scan = manager.scan_project(&project_dir).await.unwrap();
+        let paths: Vec<_> = scan.files.iter().map(|f| f.path.clone()).collect();
+        assert_eq!(paths, vec![PathBuf::from("src/main.rs")]);
+
+        let project = manager.get_project(&project_dir).await.unwrap();
+        assert_eq!(project.config.scan.max_file_size, Some(1024));
+    }
 }
@@ -0,0 +1,343 @@
+//! Latency SLO tracking.
+//!
+//! An objective like "get_context p99 < 150ms" allows 1% of requests in the
+//! rolling window to exceed 150ms. The burn rate is the observed fraction of
+//! slow requests divided by that budget: 1.0 means the budget is being spent
+//! exactly as fast as allowed, higher means the SLO will be missed.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Number of buckets each rolling window is split into.
+const WINDOW_BUCKETS: u64 = 60;
+
+/// SLO configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SloConfig {
+    /// Latency objectives to track
+    #[serde(default)]
+    pub objectives: Vec<SloObjective>,
+
+    /// Burn rate at which an objective is considered at risk
+    #[serde(default = "default_alert_burn_rate")]
+    pub alert_burn_rate: f64,
+
+    /// Minimum requests in the window before alerting
+    #[serde(default = "default_min_requests")]
+    pub min_requests: u64,
+
+    /// Optional `http://` URL that receives a JSON POST per alert
+    #[serde(default)]
+    pub webhook: Option<String>,
+}
+
+/// A latency objective for one request action.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SloObjective {
+    /// Request action name (e.g. `get_context`)
+    pub action: String,
+
+    /// Percentile the threshold applies to (e.g. 99)
+    #[serde(default = "default_percentile")]
+    pub percentile: f64,
+
+    /// Latency threshold in milliseconds
+    pub threshold_ms: u64,
+
+    /// Rolling window length in seconds
+    #[serde(default = "default_window_secs")]
+    pub window_secs: u64,
+}
+
+fn default_alert_burn_rate() -> f64 {
+    2.0
+}
+
+fn default_min_requests() -> u64 {
+    20
+}
+
+fn default_percentile() -> f64 {
+    99.0
+}
+
+fn default_window_secs() -> u64 {
+    3600
+}
+
+impl Default for SloConfig {
+    fn default() -> Self {
+        Self {
+            objectives: Vec::new(),
+            alert_burn_rate: default_alert_burn_rate(),
+            min_requests: default_min_requests(),
+            webhook: None,
+        }
+    }
+}
+
+impl SloObjective {
+    /// Display name, e.g. `get_context p99 < 150ms`.
+    pub fn name(&self) -> String {
+        format!(
+            "{} p{} < {}ms",
+            self.action, self.percentile, self.threshold_ms
+        )
+    }
+
+    /// Fraction of requests allowed to exceed the threshold.
+    fn error_budget(&self) -> f64 {
+        (1.0 - self.percentile / 100.0).max(f64::EPSILON)
+    }
+}
+
+/// Compliance of one objective over its current window.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SloStatus {
+    /// Objective display name
+    pub name: String,
+    /// Request action name
+    pub action: String,
+    /// Window length in seconds
+    pub window_secs: u64,
+    /// Requests in the window
+    pub total: u64,
+    /// Requests over the threshold in the window
+    pub slow: u64,
+    /// Fraction of requests within the threshold (1.0 when idle)
+    pub compliance: f64,
+    /// Error budget burn rate
+    pub burn_rate: f64,
+    /// Whether the burn rate exceeds the alert threshold
+    pub at_risk: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    index: u64,
+    total: u64,
+    slow: u64,
+}
+
+struct ObjectiveState {
+    objective: SloObjective,
+    buckets: VecDeque<Bucket>,
+    at_risk: bool,
+}
+
+impl ObjectiveState {
+    fn bucket_secs(&self) -> u64 {
+        (self.objective.window_secs / WINDOW_BUCKETS).max(1)
+    }
+
+    fn bucket_count(&self) -> u64 {
+        self.objective
+            .window_secs
+            .div_ceil(self.bucket_secs())
+            .max(1)
+    }
+
+    /// Drop buckets that fell out of the window ending at `index`.
+    fn expire(&mut self, index: u64) {
+        let oldest = index.saturating_sub(self.bucket_count() - 1);
+        while self.buckets.front().is_some_and(|b| b.index < oldest) {
+            self.buckets.pop_front();
+        }
+    }
+
+    fn record(&mut self, index: u64, slow: bool) {
+        self.expire(index);
+        match self.buckets.back_mut() {
+            Some(bucket) if bucket.index == index => {
+                bucket.total += 1;
+                bucket.slow += slow as u64;
+            }
+            _ => self.buckets.push_back(Bucket {
+                index,
+                total: 1,
+                slow: slow as u64,
+            }),
+        }
+    }
+
+    fn status(&self, alert_burn_rate: f64, min_requests: u64) -> SloStatus {
+        let total: u64 = self.buckets.iter().map(|b| b.total).sum();
+        let slow: u64 = self.buckets.iter().map(|b| b.slow).sum();
+        let slow_ratio = if total == 0 {
+            0.0
+        } else {
+            slow as f64 / total as f64
+        };
+        let burn_rate = slow_ratio / self.objective.error_budget();
+
+        SloStatus {
+            name: self.objective.name(),
+            action: self.objective.action.clone(),
+            window_secs: self.objective.window_secs,
+            total,
+            slow,
+            compliance: 1.0 - slow_ratio,
+            burn_rate,
+            at_risk: total >= min_requests && burn_rate >= alert_burn_rate,
+        }
+    }
+}
+
+/// Tracks request latencies against configured objectives.
+pub struct SloTracker {
+    objectives: Mutex<Vec<ObjectiveState>>,
+    alert_burn_rate: f64,
+    min_requests: u64,
+    start: Instant,
+}
+
+impl Default for SloTracker {
+    fn default() -> Self {
+        Self::new(&SloConfig::default())
+    }
+}
+
+impl SloTracker {
+    /// Create a tracker for the configured objectives.
+    pub fn new(config: &SloConfig) -> Self {
+        let objectives = config
+            .objectives
+            .iter()
+            .map(|objective| ObjectiveState {
+                objective: objective.clone(),
+                buckets: VecDeque::new(),
+                at_risk: false,
+            })
+            .collect();
+
+        Self {
+            objectives: Mutex::new(objectives),
+            alert_burn_rate: config.alert_burn_rate,
+            min_requests: config.min_requests,
+            start: Instant::now(),
+        }
+    }
+
+    /// Record a request latency.
+    ///
+    /// Returns the objectives that just became at risk, so each alert fires
+    /// once per incident rather than on every slow request.
+    pub fn record(&self, action: &str, latency: Duration) -> Vec<SloStatus> {
+        self.record_at(action, latency, Instant::now())
+    }
+
+    fn record_at(&self, action: &str, latency: Duration, now: Instant) -> Vec<SloStatus> {
+        let elapsed = now.saturating_duration_since(self.start).as_secs();
+        let mut alerts = Vec::new();
+
+        let mut objectives = self.objectives.lock().unwrap();
+        for state in objectives
+            .iter_mut()
+            .filter(|s| s.objective.action == action)
+        {
+            let slow = latency > Duration::from_millis(state.objective.threshold_ms);
+            state.record(elapsed / state.bucket_secs(), slow);
+
+            let status = state.status(self.alert_burn_rate, self.min_requests);
+            if status.at_risk && !state.at_risk {
+                alerts.push(status.clone());
+            }
+            state.at_risk = status.at_risk;
+        }
+        alerts
+    }
+
+    /// Current status of every objective.
+    pub fn status(&self) -> Vec<SloStatus> {
+        self.status_at(Instant::now())
+    }
+
+    fn status_at(&self, now: Instant) -> Vec<SloStatus> {
+        let elapsed = now.saturating_duration_since(self.start).as_secs();
+        let mut objectives = self.objectives.lock().unwrap();
+        objectives
+            .iter_mut()
+            .map(|state| {
+                state.expire(elapsed / state.bucket_secs());
+                let status = state.status(self.alert_burn_rate, self.min_requests);
+                state.at_risk = status.at_risk;
+                status
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracker(min_requests: u64) -> SloTracker {
+        SloTracker::new(&SloConfig {
+            objectives: vec![SloObjective {
+                action: "get_context".to_string(),
+                percentile: 99.0,
+                threshold_ms: 150,
+                window_secs: 60,
+            }],
+            min_requests,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_slo_config_from_yaml() {
+        let config: SloConfig =
+            serde_yaml::from_str("objectives:\n  - action: get_context\n    threshold_ms: 150\n")
+                .unwrap();
+        assert_eq!(config.objectives[0].percentile, 99.0);
+        assert_eq!(config.objectives[0].window_secs, 3600);
+        assert_eq!(config.objectives[0].name(), "get_context p99 < 150ms");
+        assert_eq!(config.alert_burn_rate, 2.0);
+    }
+
+    #[test]
+    fn test_slo_burn_rate() {
+        let tracker = tracker(1);
+        for _ in 0..98 {
+            tracker.record("get_context", Duration::from_millis(10));
+        }
+        tracker.record("get_context", Duration::from_millis(500));
+        tracker.record("ping", Duration::from_millis(500));
+
+        let status = &tracker.status()[0];
+        assert_eq!(status.total, 99);
+        assert_eq!(status.slow, 1);
+        assert!((status.burn_rate - 1.0101).abs() < 0.01);
+        assert!(!status.at_risk);
+    }
+
+    #[test]
+    fn test_slo_alerts_once_per_incident() {
+        let tracker = tracker(5);
+        let slow = Duration::from_millis(200);
+
+        for _ in 0..4 {
+            assert!(tracker.record("get_context", slow).is_empty());
+        }
+        let alerts = tracker.record("get_context", slow);
+        assert_eq!(alerts.len(), 1);
+        assert!(alerts[0].at_risk);
+        assert!(tracker.record("get_context", slow).is_empty());
+    }
+
+    #[test]
+    fn test_slo_window_expires() {
+        let tracker = tracker(1);
+        let start = tracker.start;
+        tracker.record_at("get_context", Duration::from_millis(200), start);
+        assert_eq!(tracker.status_at(start)[0].slow, 1);
+
+        let later = start + Duration::from_secs(120);
+        let status = &tracker.status_at(later)[0];
+        assert_eq!(status.total, 0);
+        assert_eq!(status.compliance, 1.0);
+        assert!(!status.at_risk);
+    }
+}
@@ -0,0 +1,242 @@
+//! Multi-root workspaces.
+//!
+//! A directory with `.engram/workspace.toml` is indexed as one project
+//! spanning several roots, e.g. a frontend and a backend repository checked
+//! out side by side. Each root is scanned on its own, with its own
+//! `.engram/config.toml` overrides, and the results are merged into one tree
+//! under the workspace directory, so scopes and rendered context span all
+//! of them.
+//!
+//! ```toml
+//! roots = ["frontend", "backend", "services/auth"]
+//! ```
+//!
+//! Roots are relative to the workspace directory and must lie inside it;
+//! link repositories living elsewhere into the workspace.
+
+use crate::CoreError;
+use engram_indexer::scanner::ScanResult;
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+
+/// Location of the workspace file relative to the workspace directory
+const WORKSPACE_PATH: &str = ".engram/workspace.toml";
+
+/// Roots of a workspace, loaded from `.engram/workspace.toml`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Workspace {
+    /// Member roots relative to the workspace directory
+    pub roots: Vec<PathBuf>,
+}
+
+impl Workspace {
+    /// Path of the workspace file for a directory
+    pub fn path(dir: &Path) -> PathBuf {
+        dir.join(WORKSPACE_PATH)
+    }
+
+    /// Load the workspace of a directory; `None` if it is not a workspace.
+    pub async fn load(dir: &Path) -> Result<Option<Self>, CoreError> {
+        let path = Self::path(dir);
+        let content = match tokio::fs::read_to_string(&path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let workspace = Self::parse(&content)
+            .map_err(|e| CoreError::InvalidWorkspace(format!("{}: {}", path.display(), e)))?;
+        workspace.validate()?;
+        Ok(Some(workspace))
+    }
+
+    /// Parse a workspace from TOML
+    pub fn parse(content: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(content)
+    }
+
+    /// Check that roots are inside the workspace and do not overlap.
+    pub fn validate(&self) -> Result<(), CoreError> {
+        if self.roots.is_empty() {
+            return Err(CoreError::InvalidWorkspace("no roots listed".to_string()));
+        }
+        for root in &self.roots {
+            let inside = root.components().all(|c| matches!(c, Component::Normal(_)));
+            if !inside || root.as_os_str().is_empty() {
+                return Err(CoreError::InvalidWorkspace(format!(
+                    "root must be a subdirectory of the workspace: {}",
+                    root.display()
+                )));
+            }
+        }
+        for (i, root) in self.roots.iter().enumerate() {
+            if let Some(other) = self.roots[i + 1..]
+                .iter()
+                .find(|other| other.starts_with(root) || root.starts_with(other))
+            {
+                return Err(CoreError::InvalidWorkspace(format!(
+                    "roots overlap: {} and {}",
+                    root.display(),
+                    other.display()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// The root containing `rel_path` (relative to the workspace directory).
+    pub fn root_of(&self, rel_path: &Path) -> Option<&Path> {
+        self.roots
+            .iter()
+            .map(PathBuf::as_path)
+            .find(|root| rel_path.starts_with(root))
+    }
+}
+
+/// Merge per-root scans into one scan of the workspace directory.
+///
+/// Paths are re-rooted at the workspace, languages and frameworks are
+/// de-duplicated, and durations and skip counts summed.
+pub fn merge_scans(dir: &Path, scans: Vec<(PathBuf, ScanResult)>) -> ScanResult {
+    let mut merged = ScanResult {
+        root: dir.to_path_buf(),
+        files: Vec::new(),
+        languages: Vec::new(),
+        frameworks: Vec::new(),
+        duration_ms: 0,
+        skipped_count: 0,
+        excluded_dirs: Vec::new(),
+    };
+    for (root, scan) in scans {
+        merged.files.extend(scan.files.into_iter().map(|mut file| {
+            file.path = root.join(&file.path);
+            file
+        }));
+        for language in scan.languages {
+            if !merged.languages.contains(&language) {
+                merged.languages.push(language);
+            }
+        }
+        for framework in scan.frameworks {
+            if !merged.frameworks.contains(&framework) {
+                merged.frameworks.push(framework);
+            }
+        }
+        merged.duration_ms += scan.duration_ms;
+        merged.skipped_count += scan.skipped_count;
+        merged
+            .excluded_dirs
+            .extend(scan.excluded_dirs.into_iter().map(|mut excluded| {
+                excluded.path = root.join(&excluded.path);
+                excluded
+            }));
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engram_indexer::scanner::{Language, ScannedFile};
+    use tempfile::tempdir;
+
+    fn scan(root: &str, files: &[&str], language: Language) -> ScanResult {
+        ScanResult {
+            root: PathBuf::from(root),
+            files: files
+                .iter()
+                .map(|path| ScannedFile {
+                    path: PathBuf::from(path),
+                    language: Some(language),
+                    size: 1,
+                    hash: path.to_string(),
+                    line_count: 1,
+                    symbols: vec![],
+                    imports: vec![],
+                    truncated: false,
+                    asset: None,
+                })
+                .collect(),
+            languages: vec![language],
+            frameworks: vec![],
+            duration_ms: 5,
+            skipped_count: 1,
+            excluded_dirs: vec![],
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        let workspace = |roots: &[&str]| Workspace {
+            roots: roots.iter().map(PathBuf::from).collect(),
+        };
+        assert!(workspace(&["frontend", "services/api"]).validate().is_ok());
+        for invalid in [
+            &[][..],
+            &["../frontend"],
+            &["/srv/frontend"],
+            &["."],
+            &["services", "services/api"],
+        ] {
+            assert!(
+                matches!(
+                    workspace(invalid).validate(),
+                    Err(CoreError::InvalidWorkspace(_))
+                ),
+                "{:?}",
+                invalid
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_load() {
+        let temp_dir = tempdir().unwrap();
+        assert_eq!(Workspace::load(temp_dir.path()).await.unwrap(), None);
+
+        std::fs::create_dir_all(temp_dir.path().join(".engram")).unwrap();
+        std::fs::write(
+            Workspace::path(temp_dir.path()),
+            "roots = [\"web\", \"api\"]\n",
+        )
+        .unwrap();
+        let workspace = Workspace::load(temp_dir.path()).await.unwrap().unwrap();
+        assert_eq!(
+            workspace.root_of(Path::new("api/src/main.rs")),
+            Some(Path::new("api"))
+        );
+        assert_eq!(workspace.root_of(Path::new("notes.md")), None);
+
+        std::fs::write(Workspace::path(temp_dir.path()), "members = []\n").unwrap();
+        assert!(Workspace::load(temp_dir.path()).await.is_err());
+    }
+
+    #[test]
+    fn test_merge_scans() {
+        let merged = merge_scans(
+            Path::new("/ws"),
+            vec![
+                (
+                    PathBuf::from("web"),
+                    scan("/ws/web", &["src/app.ts"], Language::TypeScript),
+                ),
+                (
+                    PathBuf::from("api"),
+                    scan("/ws/api", &["src/main.rs", "src/lib.rs"], Language::Rust),
+                ),
+            ],
+        );
+        assert_eq!(merged.root, PathBuf::from("/ws"));
+        let paths: Vec<_> = merged.files.iter().map(|f| f.path.clone()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("web/src/app.ts"),
+                PathBuf::from("api/src/main.rs"),
+                PathBuf::from("api/src/lib.rs"),
+            ]
+        );
+        assert_eq!(merged.languages, vec![Language::TypeScript, Language::Rust]);
+        assert_eq!((merged.duration_ms, merged.skipped_count), (10, 2));
+    }
+}
@@ -0,0 +1,143 @@
+//! SLO alert delivery.
+//!
+//! Alerts are always logged; when a webhook is configured they are also
+//! POSTed as JSON. Only plain `http://` URLs are supported, which covers
+//! local relays and alert agents without pulling in a TLS stack.
+
+use anyhow::{bail, Context, Result};
+use engram_core::SloStatus;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Give up on a webhook after this long.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Log an at-risk SLO and forward it to the webhook, if any.
+pub fn raise_slo_alert(alert: &SloStatus, webhook: Option<&str>) {
+    tracing::warn!(
+        slo = %alert.name,
+        burn_rate = alert.burn_rate,
+        compliance = alert.compliance,
+        requests = alert.total,
+        "SLO at risk"
+    );
+
+    let Some(url) = webhook else {
+        return;
+    };
+    let url = url.to_string();
+    let payload = serde_json::json!({
+        "event": "slo_at_risk",
+        "slo": alert.name,
+        "action": alert.action,
+        "window_secs": alert.window_secs,
+        "total": alert.total,
+        "slow": alert.slow,
+        "compliance": alert.compliance,
+        "burn_rate": alert.burn_rate,
+        "timestamp": chrono::Utc::now().timestamp(),
+    })
+    .to_string();
+
+    tokio::spawn(async move {
+        match tokio::time::timeout(WEBHOOK_TIMEOUT, post_json(&url, &payload)).await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => tracing::warn!(url = %url, error = %e, "SLO webhook failed"),
+            Err(_) => tracing::warn!(url = %url, "SLO webhook timed out"),
+        }
+    });
+}
+
+/// POST a JSON body to an `http://host[:port]/path` URL.
+async fn post_json(url: &str, body: &str) -> Result<()> {
+    let (authority, path) = split_http_url(url)?;
+    let host = authority.split(':').next().unwrap_or(authority);
+    let address = if authority.contains(':') {
+        authority.to_string()
+    } else {
+        format!("{}:80", authority)
+    };
+
+    let mut stream = TcpStream::connect(&address)
+        .await
+        .with_context(|| format!("connect to {}", address))?;
+    let request = format!(
+        "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        path,
+        host,
+        body.len(),
+        body
+    );
+    stream.write_all(request.as_bytes()).await?;
+
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).await?;
+    let status_line = String::from_utf8_lossy(&response);
+    let status = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse::<u16>().ok())
+        .context("malformed HTTP response")?;
+    if !(200..300).contains(&status) {
+        bail!("HTTP {}", status);
+    }
+    Ok(())
+}
+
+/// Split an `http://` URL into authority and path.
+fn split_http_url(url: &str) -> Result<(&str, &str)> {
+    let Some(rest) = url.strip_prefix("http://") else {
+        bail!("only http:// webhooks are supported: {}", url);
+    };
+    Ok(match rest.find('/') {
+        Some(idx) => (&rest[..idx], &rest[idx..]),
+        None => (rest, "/"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_split_http_url() {
+        assert_eq!(
+            split_http_url("http://127.0.0.1:9000/hooks/engram").unwrap(),
+            ("127.0.0.1:9000", "/hooks/engram")
+        );
+        assert_eq!(
+            split_http_url("http://alerts.local").unwrap(),
+            ("alerts.local", "/")
+        );
+        assert!(split_http_url("https://example.com/hook").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_post_json() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            while !request.ends_with(b"}") {
+                let n = socket.read(&mut buf).await.unwrap();
+                assert!(n > 0, "connection closed before body");
+                request.extend_from_slice(&buf[..n]);
+            }
+            socket
+                .write_all(b"HTTP/1.1 204 No Content\r\n\r\n")
+                .await
+                .unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        post_json(&url, r#"{"event":"slo_at_risk"}"#).await.unwrap();
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /hook HTTP/1.1"));
+        assert!(request.ends_with(r#"{"event":"slo_at_risk"}"#));
+    }
+}
@@ -0,0 +1,251 @@
+//! Latency benchmark behind `engram bench`.
+//!
+//! `engram-daemon --bench` generates a synthetic Rust repository, serves it
+//! with a handler backed by a scratch data directory (never the configured
+//! one), and prints a [`BenchReport`] as JSON for the CLI to compare against
+//! stored baselines.
+
+use crate::handler::{get_memory_usage, DaemonHandler};
+use anyhow::{bail, Context, Result};
+use engram_core::{DaemonConfig, ProjectManager};
+use engram_indexer::storage::Storage;
+use engram_indexer::{ChangeKind, FileChange};
+use engram_ipc::{BenchReport, Request, RequestHandler, Response};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::broadcast;
+
+/// Files per module directory of the synthetic repository
+const FILES_PER_MODULE: usize = 50;
+
+/// Functions per synthetic file
+const FUNCTIONS: usize = 4;
+
+/// Prompt context is rendered for
+const PROMPT: &str = "how are requests validated before they are handled";
+
+/// What to benchmark.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BenchOptions {
+    /// Files in the synthetic repository
+    pub files: usize,
+    /// Timed runs per measurement
+    pub runs: usize,
+}
+
+impl Default for BenchOptions {
+    fn default() -> Self {
+        Self {
+            files: 2_000,
+            runs: 5,
+        }
+    }
+}
+
+impl BenchOptions {
+    /// Parse `--files N` and `--runs N` from the daemon's arguments.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Self> {
+        let mut options = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let value = match arg.as_str() {
+                "--files" => &mut options.files,
+                "--runs" => &mut options.runs,
+                _ => continue,
+            };
+            *value = args
+                .next()
+                .and_then(|v| v.parse().ok())
+                .filter(|v| *v > 0)
+                .with_context(|| format!("{} needs a positive number", arg))?;
+        }
+        Ok(options)
+    }
+}
+
+/// Run the benchmark in a scratch directory and print the report as JSON.
+pub async fn run_standalone(options: BenchOptions) -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("engram-bench-{}", std::process::id()));
+    let report = run(&options, &dir).await;
+    if let Err(e) = std::fs::remove_dir_all(&dir) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            eprintln!("Failed to remove {}: {}", dir.display(), e);
+        }
+    }
+    println!("{}", serde_json::to_string(&report?)?);
+    Ok(())
+}
+
+/// Generate a repository under `dir`, index it, and time updates and
+/// context renders.
+pub async fn run(options: &BenchOptions, dir: &Path) -> Result<BenchReport> {
+    let root = dir.join("repo");
+    generate_repo(&root, options.files)?;
+    let root = root.canonicalize()?;
+
+    let config = DaemonConfig {
+        data_dir: dir.join("data"),
+        ..Default::default()
+    };
+    let project_manager = Arc::new(ProjectManager::new(&config));
+    let storage = Arc::new(Storage::new(config.data_dir.clone()));
+    let (shutdown_tx, _) = broadcast::channel(1);
+    let handler = DaemonHandler::new(
+        project_manager.clone(),
+        storage,
+        shutdown_tx,
+        std::time::Instant::now(),
+    );
+
+    let start = Instant::now();
+    expect_ok(
+        handler
+            .handle(Request::InitProject {
+                cwd: root.clone(),
+                async_mode: false,
+                artifact_allowlist: vec![],
+            })
+            .await,
+        "indexing",
+    )?;
+    let full_index_ms = elapsed_ms(start);
+
+    let mut updates = Vec::with_capacity(options.runs);
+    for run in 0..options.runs {
+        let path = root.join(file_path(run % options.files));
+        std::fs::write(
+            &path,
+            file_source(run % options.files, options.files, run + 1),
+        )?;
+        let start = Instant::now();
+        project_manager
+            .update_files(
+                &root,
+                &[FileChange {
+                    path,
+                    kind: ChangeKind::Modified,
+                }],
+            )
+            .await?;
+        updates.push(elapsed_ms(start));
+    }
+
+    let mut renders = Vec::with_capacity(options.runs);
+    for _ in 0..options.runs {
+        let start = Instant::now();
+        expect_ok(
+            handler
+                .handle(Request::GetContext {
+                    cwd: root.clone(),
+                    prompt: Some(PROMPT.to_string()),
+                    no_cache: true,
+                    cold_start: false,
+                    read_only_subagent: None,
+                    focus: vec![],
+                    changed_since: None,
+                    structured: false,
+                    profile: None,
+                    session_id: None,
+                })
+                .await,
+            "context render",
+        )?;
+        renders.push(elapsed_ms(start));
+    }
+
+    Ok(BenchReport {
+        files: options.files,
+        runs: options.runs,
+        full_index_ms,
+        incremental_update_ms: median(updates),
+        context_render_ms: median(renders),
+        peak_memory_bytes: get_memory_usage() as u64,
+    })
+}
+
+/// Write `files` Rust files importing each other across modules.
+fn generate_repo(root: &Path, files: usize) -> Result<()> {
+    for i in 0..files {
+        let path = root.join(file_path(i));
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&path, file_source(i, files, 0))?;
+    }
+    Ok(())
+}
+
+fn file_path(i: usize) -> PathBuf {
+    PathBuf::from(format!("src/module_{}/file_{}.rs", i / FILES_PER_MODULE, i))
+}
+
+/// Source of file `i`; `revision` adds functions, as an edit would.
+fn file_source(i: usize, files: usize, revision: usize) -> String {
+    let module = i / FILES_PER_MODULE;
+    let next = (i + FILES_PER_MODULE) % files;
+    let mut source = format!(
+        "//! Request handlers of module {}.\n\nuse crate::module_{}::file_{};\n",
+        module,
+        next / FILES_PER_MODULE,
+        next
+    );
+    for j in 0..FUNCTIONS + revision {
+        source.push_str(&format!(
+            "\n/// Validate and handle request kind {} of module {}.\n\
+             pub fn handle_request_{}(input: &str) -> Result<String, String> {{\n    \
+             if input.is_empty() {{\n        return Err(\"empty request\".to_string());\n    }}\n    \
+             Ok(input.to_uppercase())\n}}\n",
+            j, module, j
+        ));
+    }
+    source
+}
+
+fn expect_ok(response: Response, step: &str) -> Result<()> {
+    match response {
+        Response::Error { message, .. } => bail!("{} failed: {}", step, message),
+        _ => Ok(()),
+    }
+}
+
+fn elapsed_ms(start: Instant) -> f64 {
+    start.elapsed().as_secs_f64() * 1000.0
+}
+
+fn median(mut samples: Vec<f64>) -> f64 {
+    samples.sort_by(f64::total_cmp);
+    samples.get(samples.len() / 2).copied().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_bench_options_from_args() {
+        let args = |s: &str| s.split_whitespace().map(str::to_string).collect::<Vec<_>>();
+        assert_eq!(
+            BenchOptions::from_args(args("--bench")).unwrap(),
+            BenchOptions::default()
+        );
+        assert_eq!(
+            BenchOptions::from_args(args("--bench --files 10 --runs 2")).unwrap(),
+            BenchOptions { files: 10, runs: 2 }
+        );
+        assert!(BenchOptions::from_args(args("--bench --files 0")).is_err());
+        assert!(BenchOptions::from_args(args("--bench --runs")).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_bench_run() {
+        let temp_dir = tempdir().unwrap();
+        let options = BenchOptions { files: 60, runs: 2 };
+        let report = run(&options, temp_dir.path()).await.unwrap();
+        assert_eq!(report.files, 60);
+        assert_eq!(report.runs, 2);
+        assert!(report.full_index_ms > 0.0);
+        assert!(report.context_render_ms > 0.0);
+    }
+}
@@ -1,15 +1,39 @@
 //! Daemon lifecycle management.
 
 use anyhow::{Context, Result};
-use engram_core::{DaemonConfig, ProjectManager};
+use engram_core::{DaemonConfig, EventKind, Experiments, ProjectManager};
+use engram_indexer::scanner::{GrammarLoader, GRAMMARS_DIR};
 use engram_indexer::storage::Storage;
-use engram_ipc::IpcServer;
+use engram_ipc::{AccessPolicy, IpcServer};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::broadcast;
 
 use crate::handler::DaemonHandler;
-use crate::signals;
+use crate::recovery::{self, Supervisor};
+use crate::{notify, signals};
+
+/// Storage under the configured data dir, in the configured backend,
+/// encrypting experience logs if a key is configured.
+pub(crate) fn open_storage(config: &DaemonConfig) -> Result<Storage> {
+    let backend = config
+        .storage
+        .open(&config.data_dir)
+        .context("Failed to open the storage backend")?;
+    let storage = Storage::new(config.data_dir.clone()).with_backend(backend);
+    let cipher = config
+        .encryption
+        .cipher()
+        .context("Failed to load the experience log key")?;
+    Ok(match cipher {
+        Some(cipher) => {
+            tracing::info!(key = cipher.key_id(), "Encrypting experience logs");
+            storage.with_cipher(Arc::new(cipher))
+        }
+        None => storage,
+    })
+}
 
 /// The main daemon process
 pub struct Daemon {
@@ -23,6 +47,7 @@ impl Daemon {
     /// Create a new daemon instance
     pub fn new() -> Result<Self> {
         let config = DaemonConfig::load();
+        config.validate()?;
 
         // Ensure data directories exist
         config
@@ -47,44 +72,167 @@ impl Daemon {
         // Mark as running
         self.is_running.store(true, Ordering::SeqCst);
 
+        let supervisor = self.config.supervisor.enabled.then(|| {
+            let (supervisor, previous) = Supervisor::start(&self.config.data_dir);
+            (Arc::new(supervisor), previous)
+        });
+
         tracing::info!(
-            socket = %self.config.socket_path.display(),
+            socket = %engram_ipc::endpoint_display(&self.config.socket_path),
             data_dir = %self.config.data_dir.display(),
             "Daemon starting"
         );
 
+        // Runtime grammars are loaded lazily from the data dir
+        GrammarLoader::init_global(self.config.data_dir.join(GRAMMARS_DIR));
+
         // Initialize components
-        let project_manager = Arc::new(ProjectManager::new(&self.config));
-        let storage = Arc::new(Storage::new(self.config.data_dir.clone()));
+        let storage = Arc::new(open_storage(&self.config)?);
+        let project_manager =
+            Arc::new(ProjectManager::new(&self.config).with_backend(storage.backend()));
+
+        let experiments = Arc::new(Experiments::from_config_file(
+            self.config.experiments.clone(),
+            DaemonConfig::config_path(),
+        ));
+
+        // Repair what an unclean shutdown left behind before serving
+        let recovery = match supervisor
+            .as_ref()
+            .and_then(|(_, previous)| previous.as_ref())
+        {
+            Some(previous) => {
+                tracing::warn!(
+                    pid = previous.pid,
+                    "Previous daemon did not shut down cleanly, recovering"
+                );
+                let info = recovery::recover(&storage, previous).await;
+                project_manager.events().record(
+                    EventKind::Recovered,
+                    None,
+                    format!(
+                        "Recovered from unclean shutdown of PID {}: {} repair(s), {} problem(s)",
+                        info.previous_pid,
+                        info.actions.len(),
+                        info.problems.len()
+                    ),
+                );
+                Some(info)
+            }
+            None => None,
+        };
 
-        let handler = Arc::new(DaemonHandler::new(
+        let mut handler = DaemonHandler::new(
             project_manager.clone(),
             storage,
             self.shutdown_tx.clone(),
             self.start_time,
-        ));
+        )
+        .with_experiments(experiments)
+        .with_skeleton_options(self.config.skeleton.clone())
+        .with_scope_config(&self.config.scopes)
+        .with_slo(&self.config.slo)
+        .with_memory_config(&self.config.memory)
+        .with_enrichment(&self.config.enrichment)
+        .with_config_file(DaemonConfig::config_path(), &self.config)
+        .with_doctor(&self.config);
+        if let Some(recovery) = recovery {
+            handler = handler.with_recovery(recovery);
+        }
+        let handler = Arc::new(handler);
+        tokio::spawn(signals::reload_on_sighup(handler.clone()));
 
-        let ipc_server = IpcServer::new(&self.config.socket_path, handler)
+        if let Some((supervisor, _)) = &supervisor {
+            let interval = Duration::from_secs(self.config.supervisor.heartbeat_secs.max(1));
+            tokio::spawn(supervisor.clone().run_heartbeat(interval));
+        }
+
+        if self.config.gc.enabled {
+            let project_manager = project_manager.clone();
+            let interval = Duration::from_secs(self.config.gc.interval_secs.max(60));
+            tokio::spawn(async move {
+                let start = tokio::time::Instant::now() + interval;
+                let mut ticks = tokio::time::interval_at(start, interval);
+                loop {
+                    ticks.tick().await;
+                    if let Err(e) = project_manager.collect_garbage(None, false, &[]).await {
+                        tracing::warn!("Garbage collection failed: {}", e);
+                    }
+                }
+            });
+        }
+
+        if self.config.watch.enabled {
+            let watch_manager = handler.watch_manager(&self.config.watch);
+            tokio::spawn(watch_manager.run());
+        }
+
+        if let Some(addr) = self.config.metrics_addr {
+            let exporter = handler.metrics_exporter(self.config.max_memory);
+            tokio::spawn(async move {
+                if let Err(e) = exporter.serve(addr).await {
+                    tracing::error!("Metrics endpoint error: {:#}", e);
+                }
+            });
+        }
+
+        let mut access =
+            AccessPolicy::new().with_allowed_uids(self.config.auth.allowed_uids.clone());
+        if let Some(token) = &self.config.auth.token {
+            access = access.with_token(token.clone());
+        }
+
+        #[cfg(feature = "http")]
+        if let Some(addr) = self.config.http_addr {
+            let http_server = engram_ipc::HttpServer::new(addr, handler.clone())
+                .await
+                .context("Failed to create HTTP bridge")?
+                .with_access(access.clone());
+            tokio::spawn(async move {
+                if let Err(e) = http_server.run().await {
+                    tracing::error!("HTTP bridge error: {}", e);
+                }
+            });
+        }
+        #[cfg(not(feature = "http"))]
+        if self.config.http_addr.is_some() {
+            tracing::warn!("http_addr is set but this build has no HTTP bridge");
+        }
+
+        let ipc_server = IpcServer::new(&self.config.socket_path, handler.clone())
             .await
-            .context("Failed to create IPC server")?;
+            .context("Failed to create IPC server")?
+            .with_access(access);
+        notify::ready();
 
         // Set up shutdown signal
         let shutdown_rx = self.shutdown_tx.subscribe();
 
         // Run components
-        tokio::select! {
-            result = ipc_server.run() => {
-                if let Err(e) = result {
-                    tracing::error!("IPC server error: {}", e);
-                }
-            }
-            _ = signals::wait_for_shutdown(shutdown_rx) => {
-                tracing::info!("Shutdown signal received");
-            }
+        match ipc_server
+            .run_until(signals::wait_for_shutdown(shutdown_rx))
+            .await
+        {
+            Ok(()) => tracing::info!("Shutdown signal received"),
+            Err(e) => tracing::error!("IPC server error: {}", e),
         }
 
+        // Turn new connections away while in-flight requests finish
+        let drain = Duration::from_secs(self.config.shutdown.drain_secs);
+        let deadline = tokio::time::Instant::now() + drain;
+        let report = ipc_server.drain(drain).await;
+        tracing::info!(
+            drained = report.drained,
+            aborted = report.aborted,
+            "Drained in-flight requests"
+        );
+        handler.flush(deadline).await;
+
         // Cleanup
         self.cleanup().await?;
+        if let Some((supervisor, _)) = &supervisor {
+            supervisor.stop();
+        }
 
         Ok(())
     }
@@ -118,11 +266,10 @@ impl Daemon {
     /// Cleanup resources on shutdown
     async fn cleanup(&self) -> Result<()> {
         tracing::info!("Cleaning up...");
+        notify::stopping();
 
         // Remove socket file
-        if self.config.socket_path.exists() {
-            let _ = std::fs::remove_file(&self.config.socket_path);
-        }
+        engram_ipc::remove_endpoint(&self.config.socket_path);
 
         // Remove PID file
         if self.config.pid_file.exists() {
@@ -147,12 +294,24 @@ impl Drop for Daemon {
 }
 
 /// Check if a process is running by PID
-fn is_process_running(pid: u32) -> bool {
+#[cfg(unix)]
+pub(crate) fn is_process_running(pid: u32) -> bool {
     // Use kill(pid, 0) to check if process exists
     // This doesn't actually send a signal, just checks existence
     unsafe { libc::kill(pid as i32, 0) == 0 }
 }
 
+/// Check if a process is running by PID
+#[cfg(windows)]
+pub(crate) fn is_process_running(pid: u32) -> bool {
+    // tasklist prints an INFO line instead of a row when nothing matches
+    std::process::Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/NH", "/FO", "CSV"])
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).contains(&format!("\"{}\"", pid)))
+        .unwrap_or(false)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
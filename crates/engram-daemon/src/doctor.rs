@@ -0,0 +1,431 @@
+//! Installation diagnostics behind `engram doctor`.
+//!
+//! The running daemon answers `Request::Doctor` with these checks. When it
+//! will not start, `engram-daemon --doctor` runs them without taking the PID
+//! lock or binding the socket and prints the result as JSON for the CLI.
+
+use engram_core::DaemonConfig;
+use engram_indexer::scanner::{GrammarLoader, GrammarSource, GRAMMARS_DIR};
+use engram_indexer::storage::Storage;
+use engram_indexer::Language;
+use engram_ipc::{CheckStatus, DoctorCheck};
+use std::path::{Path, PathBuf};
+
+use crate::daemon::{is_process_running, open_storage};
+
+/// Languages symbols are extracted from, i.e. those needing a grammar
+const PARSED_LANGUAGES: &[Language] = &[
+    Language::Rust,
+    Language::TypeScript,
+    Language::JavaScript,
+    Language::Python,
+    Language::Go,
+    Language::C,
+    Language::Cpp,
+];
+
+/// Runs diagnostic checks against the daemon's configured paths.
+pub struct Doctor {
+    socket_path: PathBuf,
+    pid_file: PathBuf,
+    config_path: PathBuf,
+}
+
+impl Doctor {
+    pub fn new(config: &DaemonConfig) -> Self {
+        Self {
+            socket_path: config.socket_path.clone(),
+            pid_file: config.pid_file.clone(),
+            config_path: DaemonConfig::config_path(),
+        }
+    }
+
+    /// Run all checks.
+    ///
+    /// `serving` is true inside the running daemon, which owns the socket
+    /// and the PID file; otherwise leftovers of a previous daemon are
+    /// reported as stale.
+    pub async fn run(&self, storage: &Storage, serving: bool) -> Vec<DoctorCheck> {
+        let mut checks = vec![self.check_config(), self.check_pid_file(serving)];
+        #[cfg(unix)]
+        checks.push(self.check_socket(serving));
+        checks.push(check_data_dir(storage.base_dir()));
+        checks.extend(check_storage(storage).await);
+        checks.push(check_grammars(GrammarLoader::global()));
+        checks
+    }
+
+    fn check_config(&self) -> DoctorCheck {
+        let path = &self.config_path;
+        if !path.exists() {
+            return DoctorCheck::new("config", CheckStatus::Ok, "no config file, using defaults");
+        }
+        match DaemonConfig::load_from(path) {
+            Ok(_) => DoctorCheck::new(
+                "config",
+                CheckStatus::Ok,
+                format!("loaded {}", path.display()),
+            ),
+            Err(e) => DoctorCheck::new(
+                "config",
+                CheckStatus::Failed,
+                format!("{} is ignored: {}", path.display(), e),
+            )
+            .with_fix(format!(
+                "Fix the reported field or move {} aside to use defaults",
+                path.display()
+            )),
+        }
+    }
+
+    fn check_pid_file(&self, serving: bool) -> DoctorCheck {
+        let path = &self.pid_file;
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(_) if serving => {
+                return DoctorCheck::new(
+                    "pid_file",
+                    CheckStatus::Warning,
+                    format!("{} is missing; a second daemon could start", path.display()),
+                )
+                .with_fix("Restart the daemon: engram stop && engram start");
+            }
+            Err(_) => return DoctorCheck::new("pid_file", CheckStatus::Ok, "no PID file"),
+        };
+
+        let Ok(pid) = contents.trim().parse::<u32>() else {
+            return DoctorCheck::new(
+                "pid_file",
+                CheckStatus::Warning,
+                format!("{} does not contain a PID", path.display()),
+            )
+            .with_fix(format!("rm {}", path.display()));
+        };
+
+        if pid == std::process::id() {
+            DoctorCheck::new(
+                "pid_file",
+                CheckStatus::Ok,
+                format!("held by this daemon (PID {})", pid),
+            )
+        } else if !is_process_running(pid) {
+            DoctorCheck::new(
+                "pid_file",
+                CheckStatus::Warning,
+                format!("stale: PID {} is no longer running", pid),
+            )
+            .with_fix(format!(
+                "rm {} (the daemon also replaces it on start)",
+                path.display()
+            ))
+        } else if serving {
+            DoctorCheck::new(
+                "pid_file",
+                CheckStatus::Warning,
+                format!("names PID {}, not this daemon", pid),
+            )
+            .with_fix(format!(
+                "Check whether PID {} is another Engram daemon",
+                pid
+            ))
+        } else {
+            DoctorCheck::new(
+                "pid_file",
+                CheckStatus::Failed,
+                format!("PID {} holds the lock; a new daemon refuses to start", pid),
+            )
+            .with_fix(format!(
+                "If PID {} is a hung daemon, kill {} and run engram start",
+                pid, pid
+            ))
+        }
+    }
+
+    #[cfg(unix)]
+    fn check_socket(&self, serving: bool) -> DoctorCheck {
+        use std::os::unix::fs::{FileTypeExt, MetadataExt};
+
+        let path = &self.socket_path;
+        let display = path.display();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            if !parent.is_dir() {
+                return DoctorCheck::new(
+                    "socket",
+                    CheckStatus::Failed,
+                    format!("directory of {} does not exist", display),
+                )
+                .with_fix(format!("mkdir -p {}", parent.display()));
+            }
+        }
+
+        let meta = match std::fs::symlink_metadata(path) {
+            Ok(meta) => meta,
+            Err(_) if serving => {
+                return DoctorCheck::new(
+                    "socket",
+                    CheckStatus::Failed,
+                    format!("{} was removed; clients cannot connect", display),
+                )
+                .with_fix("Restart the daemon: engram stop && engram start");
+            }
+            Err(_) => return DoctorCheck::new("socket", CheckStatus::Ok, "no socket file"),
+        };
+
+        if !meta.file_type().is_socket() {
+            return DoctorCheck::new(
+                "socket",
+                CheckStatus::Failed,
+                format!("{} exists but is not a socket", display),
+            )
+            .with_fix(format!("Move {} aside or change socket_path", display));
+        }
+
+        // SAFETY: geteuid has no preconditions and cannot fail.
+        let uid = unsafe { libc::geteuid() };
+        if meta.uid() != uid {
+            return DoctorCheck::new(
+                "socket",
+                CheckStatus::Failed,
+                format!("{} belongs to uid {}, not {}", display, meta.uid(), uid),
+            )
+            .with_fix(format!(
+                "Stop the other user's daemon or set a different socket_path; then rm {}",
+                display
+            ));
+        }
+
+        if !serving && std::os::unix::net::UnixStream::connect(path).is_err() {
+            return DoctorCheck::new(
+                "socket",
+                CheckStatus::Warning,
+                format!("stale: nothing is listening on {}", display),
+            )
+            .with_fix(format!(
+                "rm {} (the daemon also replaces it on start)",
+                display
+            ));
+        }
+
+        let mode = meta.mode() & 0o777;
+        if mode & 0o077 != 0 {
+            return DoctorCheck::new(
+                "socket",
+                CheckStatus::Warning,
+                format!("{} has mode {:o}; other users can connect", display, mode),
+            )
+            .with_fix(format!("chmod 600 {}", display));
+        }
+
+        DoctorCheck::new(
+            "socket",
+            CheckStatus::Ok,
+            format!("{} (mode {:o})", display, mode),
+        )
+    }
+}
+
+pub(crate) fn check_data_dir(dir: &Path) -> DoctorCheck {
+    let probe = dir.join(".doctor-probe");
+    let writable = std::fs::create_dir_all(dir)
+        .and_then(|_| std::fs::write(&probe, b""))
+        .and_then(|_| std::fs::remove_file(&probe));
+    match writable {
+        Ok(()) => DoctorCheck::new(
+            "data_dir",
+            CheckStatus::Ok,
+            format!("{} is writable", dir.display()),
+        ),
+        Err(e) => DoctorCheck::new(
+            "data_dir",
+            CheckStatus::Failed,
+            format!("cannot write to {}: {}", dir.display(), e),
+        )
+        .with_fix(format!(
+            "Make {} writable by the daemon's user or set data_dir",
+            dir.display()
+        )),
+    }
+}
+
+/// One failed check per project with unreadable files, or a single summary.
+async fn check_storage(storage: &Storage) -> Vec<DoctorCheck> {
+    let hashes = match storage.stored_projects().await {
+        Ok(hashes) => hashes,
+        Err(e) => {
+            return vec![DoctorCheck::new(
+                "storage",
+                CheckStatus::Failed,
+                format!("cannot list {}: {}", storage.base_dir().display(), e),
+            )]
+        }
+    };
+
+    let mut checks = Vec::new();
+    for hash in &hashes {
+        let problems = storage.verify(hash).await;
+        if problems.is_empty() {
+            continue;
+        }
+        let details: Vec<String> = problems
+            .iter()
+            .map(|p| format!("{}: {}", p.path.display(), p.error))
+            .collect();
+        checks.push(
+            DoctorCheck::new(
+                "storage",
+                CheckStatus::Failed,
+                format!("project {}: {}", hash, details.join("; ")),
+            )
+            .with_fix(format!(
+                "Run engram init in the project to re-index it, or delete {}",
+                storage.project_dir(hash).display()
+            )),
+        );
+    }
+
+    if checks.is_empty() {
+        checks.push(DoctorCheck::new(
+            "storage",
+            CheckStatus::Ok,
+            format!("{} stored project(s) readable", hashes.len()),
+        ));
+    }
+    checks
+}
+
+fn check_grammars(loader: &GrammarLoader) -> DoctorCheck {
+    let mut builtin = 0;
+    let mut dynamic = 0;
+    let mut missing = Vec::new();
+    for &language in PARSED_LANGUAGES {
+        match loader.source(language) {
+            Some(GrammarSource::Builtin) => builtin += 1,
+            Some(GrammarSource::Dynamic) => dynamic += 1,
+            None => missing.push(language),
+        }
+    }
+
+    if missing.is_empty() {
+        return DoctorCheck::new(
+            "grammars",
+            CheckStatus::Ok,
+            format!("{} built in, {} loaded at runtime", builtin, dynamic),
+        );
+    }
+
+    let names: Vec<&str> = missing.iter().map(|l| l.name()).collect();
+    let dir = loader
+        .dir()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from(GRAMMARS_DIR));
+    let example = missing
+        .first()
+        .and_then(|l| l.grammar_name())
+        .unwrap_or("go");
+    DoctorCheck::new(
+        "grammars",
+        CheckStatus::Warning,
+        format!(
+            "no grammar for {}; these files are indexed without symbols",
+            names.join(", ")
+        ),
+    )
+    .with_fix(format!(
+        "Install grammars into {} (e.g. libtree-sitter-{}.{})",
+        dir.display(),
+        example,
+        std::env::consts::DLL_EXTENSION
+    ))
+}
+
+/// Run the checks without starting the daemon and print them as JSON.
+pub async fn run_standalone() -> anyhow::Result<()> {
+    let config = DaemonConfig::load();
+    GrammarLoader::init_global(config.data_dir.join(GRAMMARS_DIR));
+    let storage = open_storage(&config)?;
+    let checks = Doctor::new(&config).run(&storage, false).await;
+    println!("{}", serde_json::to_string_pretty(&checks)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engram_indexer::Tree;
+    use tempfile::tempdir;
+
+    fn doctor(dir: &Path) -> Doctor {
+        Doctor {
+            socket_path: dir.join("engram.sock"),
+            pid_file: dir.join("engram.pid"),
+            config_path: dir.join("config.yaml"),
+        }
+    }
+
+    fn find<'a>(checks: &'a [DoctorCheck], name: &str) -> &'a DoctorCheck {
+        checks.iter().find(|c| c.name == name).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_clean_install() {
+        let temp_dir = tempdir().unwrap();
+        let storage = Storage::new(temp_dir.path().join("data"));
+        let checks = doctor(temp_dir.path()).run(&storage, false).await;
+
+        for name in ["config", "pid_file", "data_dir", "storage"] {
+            assert_eq!(find(&checks, name).status, CheckStatus::Ok, "{}", name);
+        }
+        #[cfg(unix)]
+        assert_eq!(find(&checks, "socket").status, CheckStatus::Ok);
+    }
+
+    #[tokio::test]
+    async fn test_reports_stale_files_and_corrupt_storage() {
+        let temp_dir = tempdir().unwrap();
+        let doctor = doctor(temp_dir.path());
+        std::fs::write(&doctor.config_path, "max_projects: many\n").unwrap();
+        // Above the kernel's PID limit, so never running
+        std::fs::write(&doctor.pid_file, "999999999").unwrap();
+
+        let storage = Storage::new(temp_dir.path().join("data"));
+        storage
+            .save_skeleton(&Tree::new(PathBuf::from("/project")), "abc123")
+            .await
+            .unwrap();
+        std::fs::write(
+            storage.project_dir("abc123").join("skeleton.json"),
+            "{ truncated",
+        )
+        .unwrap();
+
+        let checks = doctor.run(&storage, false).await;
+        assert_eq!(find(&checks, "config").status, CheckStatus::Failed);
+        let pid = find(&checks, "pid_file");
+        assert_eq!(pid.status, CheckStatus::Warning);
+        assert!(pid.fix.as_deref().unwrap().starts_with("rm "));
+        let storage_check = find(&checks, "storage");
+        assert_eq!(storage_check.status, CheckStatus::Failed);
+        assert!(storage_check.message.contains("skeleton.json"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_stale_socket() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = tempdir().unwrap();
+        let doctor = doctor(temp_dir.path());
+        let listener = std::os::unix::net::UnixListener::bind(&doctor.socket_path).unwrap();
+        std::fs::set_permissions(&doctor.socket_path, std::fs::Permissions::from_mode(0o666))
+            .unwrap();
+        let socket = doctor.check_socket(false);
+        assert_eq!(socket.status, CheckStatus::Warning);
+        assert!(socket.fix.unwrap().starts_with("chmod 600"));
+
+        // The file outlives the listener, e.g. after a crash
+        drop(listener);
+        let socket = doctor.check_socket(false);
+        assert_eq!(socket.status, CheckStatus::Warning);
+        assert!(socket.message.starts_with("stale"));
+    }
+}
@@ -0,0 +1,167 @@
+//! Summaries from the Anthropic messages API.
+
+use super::api::{self, RateLimiter, UsageMeter};
+use super::{summary_prompt, Summarizer, SummarizerUsage, SummaryInput};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use engram_core::SummarizerApiConfig;
+use serde::Deserialize;
+use serde_json::json;
+
+const DEFAULT_BASE_URL: &str = "https://api.anthropic.com/v1";
+const DEFAULT_MODEL: &str = "claude-3-5-haiku-latest";
+const API_KEY_ENV: &str = "ANTHROPIC_API_KEY";
+const API_VERSION: &str = "2023-06-01";
+
+#[derive(Deserialize)]
+struct MessageResponse {
+    content: Vec<ContentBlock>,
+    #[serde(default)]
+    usage: Usage,
+}
+
+#[derive(Deserialize)]
+struct ContentBlock {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    text: String,
+}
+
+#[derive(Deserialize, Default)]
+struct Usage {
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
+}
+
+/// Summarizer backed by `POST {base_url}/messages`
+pub struct AnthropicSummarizer {
+    client: reqwest::Client,
+    url: String,
+    model: String,
+    api_key: String,
+    max_tokens: u32,
+    limiter: RateLimiter,
+    usage: UsageMeter,
+}
+
+impl AnthropicSummarizer {
+    /// Create a summarizer; fails without an API key
+    pub fn new(config: &SummarizerApiConfig) -> Result<Self> {
+        let api_key = api::api_key(config, API_KEY_ENV).with_context(|| {
+            format!(
+                "{} is not set",
+                config.api_key_env.as_deref().unwrap_or(API_KEY_ENV)
+            )
+        })?;
+        let model = config.model.as_deref().unwrap_or(DEFAULT_MODEL);
+        Ok(Self {
+            client: api::client()?,
+            url: format!("{}/messages", api::base_url(config, DEFAULT_BASE_URL)),
+            model: model.to_string(),
+            api_key,
+            max_tokens: config.max_tokens,
+            limiter: RateLimiter::new(config.requests_per_minute),
+            usage: UsageMeter::new(model, config),
+        })
+    }
+}
+
+#[async_trait]
+impl Summarizer for AnthropicSummarizer {
+    fn name(&self) -> &'static str {
+        "anthropic"
+    }
+
+    async fn summarize(&self, input: &SummaryInput) -> Result<Option<String>> {
+        self.limiter.acquire().await;
+        let request = self
+            .client
+            .post(&self.url)
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", API_VERSION)
+            .json(&json!({
+                "model": self.model,
+                "max_tokens": self.max_tokens,
+                "messages": [{ "role": "user", "content": summary_prompt(input) }],
+            }));
+
+        let response: MessageResponse = match api::send(request).await {
+            Ok(response) => response,
+            Err(e) => {
+                self.usage.record_failure();
+                return Err(e);
+            }
+        };
+        self.usage
+            .record(response.usage.input_tokens, response.usage.output_tokens);
+
+        let summary: String = response
+            .content
+            .into_iter()
+            .filter(|block| block.kind == "text")
+            .map(|block| block.text)
+            .collect();
+        Ok(Some(summary.trim().to_string()).filter(|s| !s.is_empty()))
+    }
+
+    fn usage(&self) -> Option<SummarizerUsage> {
+        Some(self.usage.usage())
+    }
+
+    async fn probe(&self) -> Result<String> {
+        api::probe(&self.client, &self.url).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enrich::api::mock::serve_once;
+    use std::path::PathBuf;
+
+    #[tokio::test]
+    async fn test_anthropic_summarizer() {
+        let (url, request) = serve_once(
+            200,
+            r#"{"content":[{"type":"text","text":"Parses config files."}],"usage":{"input_tokens":90,"output_tokens":6}}"#,
+        )
+        .await;
+        std::env::set_var("ENGRAM_TEST_ANTHROPIC_KEY", "secret");
+        let config = SummarizerApiConfig {
+            base_url: Some(url),
+            api_key_env: Some("ENGRAM_TEST_ANTHROPIC_KEY".to_string()),
+            ..Default::default()
+        };
+        let summarizer = AnthropicSummarizer::new(&config).unwrap();
+        let input = SummaryInput {
+            path: PathBuf::from("config.py"),
+            language: None,
+            symbols: Vec::new(),
+            source: "import yaml".to_string(),
+        };
+        assert_eq!(
+            summarizer.summarize(&input).await.unwrap().as_deref(),
+            Some("Parses config files.")
+        );
+
+        let request = request.await.unwrap().to_ascii_lowercase();
+        assert!(request.starts_with("post /messages "));
+        assert!(request.contains("x-api-key: secret"));
+        assert!(request.contains(&format!("anthropic-version: {}", API_VERSION)));
+        assert!(request.contains(DEFAULT_MODEL));
+
+        let usage = summarizer.usage().unwrap();
+        assert_eq!(usage.model, DEFAULT_MODEL);
+        assert_eq!((usage.input_tokens, usage.output_tokens), (90, 6));
+        assert_eq!(usage.cost, 0.0);
+
+        let config = SummarizerApiConfig {
+            api_key_env: Some("ENGRAM_TEST_UNSET_KEY".to_string()),
+            ..Default::default()
+        };
+        assert!(AnthropicSummarizer::new(&config).is_err());
+    }
+}
@@ -0,0 +1,251 @@
+//! Shared plumbing of the HTTP summarizers: client, rate limit and usage.
+
+use super::SummarizerUsage;
+use anyhow::{bail, Context, Result};
+use engram_core::SummarizerApiConfig;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+/// Time an API request gets per file
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Time a reachability probe gets
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Characters of an error response kept in the error message
+const MAX_ERROR_BODY: usize = 200;
+
+/// HTTP client for summarizer APIs.
+pub(super) fn client() -> Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .context("Failed to build HTTP client")
+}
+
+/// API key from the configured environment variable, or `default_env`.
+pub(super) fn api_key(config: &SummarizerApiConfig, default_env: &str) -> Option<String> {
+    let var = config.api_key_env.as_deref().unwrap_or(default_env);
+    std::env::var(var).ok().filter(|key| !key.is_empty())
+}
+
+/// `base_url` without a trailing slash, so paths can be appended.
+pub(super) fn base_url(config: &SummarizerApiConfig, default: &str) -> String {
+    config
+        .base_url
+        .as_deref()
+        .unwrap_or(default)
+        .trim_end_matches('/')
+        .to_string()
+}
+
+/// Send a request and decode its JSON response, failing on error statuses.
+pub(super) async fn send<T: serde::de::DeserializeOwned>(
+    request: reqwest::RequestBuilder,
+) -> Result<T> {
+    let response = request.send().await.context("Request failed")?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        bail!(
+            "API returned {}: {}",
+            status,
+            super::truncate(body.trim(), MAX_ERROR_BODY)
+        );
+    }
+    response.json().await.context("Invalid API response")
+}
+
+/// Check that `url` answers. Any HTTP status counts: the probe sends no
+/// credentials or payload, so error statuses are expected.
+pub(super) async fn probe(client: &reqwest::Client, url: &str) -> Result<String> {
+    let response = client
+        .head(url)
+        .timeout(PROBE_TIMEOUT)
+        .send()
+        .await
+        .with_context(|| format!("{} is unreachable", url))?;
+    Ok(format!("{} answered {}", url, response.status()))
+}
+
+/// Spaces requests evenly to stay under a per-minute limit
+pub(super) struct RateLimiter {
+    interval: Duration,
+    next: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    /// Allow `per_minute` requests a minute; 0 allows any number
+    pub(super) fn new(per_minute: u32) -> Self {
+        let interval = match per_minute {
+            0 => Duration::ZERO,
+            n => Duration::from_secs(60) / n,
+        };
+        Self {
+            interval,
+            next: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Wait for the next request slot.
+    pub(super) async fn acquire(&self) {
+        if self.interval.is_zero() {
+            return;
+        }
+        // Holding the lock while sleeping queues the waiting workers
+        let mut next = self.next.lock().await;
+        tokio::time::sleep_until(*next).await;
+        *next = Instant::now().max(*next) + self.interval;
+    }
+}
+
+/// Token counts and cost of a summarizer's requests
+pub(super) struct UsageMeter {
+    model: String,
+    input_cost_per_mtok: f64,
+    output_cost_per_mtok: f64,
+    requests: AtomicU64,
+    failed: AtomicU64,
+    input_tokens: AtomicU64,
+    output_tokens: AtomicU64,
+}
+
+impl UsageMeter {
+    pub(super) fn new(model: &str, config: &SummarizerApiConfig) -> Self {
+        Self {
+            model: model.to_string(),
+            input_cost_per_mtok: config.input_cost_per_mtok,
+            output_cost_per_mtok: config.output_cost_per_mtok,
+            requests: AtomicU64::new(0),
+            failed: AtomicU64::new(0),
+            input_tokens: AtomicU64::new(0),
+            output_tokens: AtomicU64::new(0),
+        }
+    }
+
+    /// Record a request and the tokens it used.
+    pub(super) fn record(&self, input_tokens: u64, output_tokens: u64) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        self.input_tokens.fetch_add(input_tokens, Ordering::Relaxed);
+        self.output_tokens
+            .fetch_add(output_tokens, Ordering::Relaxed);
+    }
+
+    /// Record a request that failed.
+    pub(super) fn record_failure(&self) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        self.failed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(super) fn usage(&self) -> SummarizerUsage {
+        let input_tokens = self.input_tokens.load(Ordering::Relaxed);
+        let output_tokens = self.output_tokens.load(Ordering::Relaxed);
+        SummarizerUsage {
+            model: self.model.clone(),
+            requests: self.requests.load(Ordering::Relaxed),
+            failed: self.failed.load(Ordering::Relaxed),
+            input_tokens,
+            output_tokens,
+            cost: (input_tokens as f64 * self.input_cost_per_mtok
+                + output_tokens as f64 * self.output_cost_per_mtok)
+                / 1_000_000.0,
+        }
+    }
+}
+
+/// A one-shot HTTP server for adapter tests.
+#[cfg(test)]
+pub(super) mod mock {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio::task::JoinHandle;
+
+    /// Serve `body` with `status` to one request; the handle yields the
+    /// request as received.
+    pub(crate) async fn serve_once(status: u16, body: &str) -> (String, JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let body = body.to_string();
+        let handle = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            loop {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request);
+                if let Some(end) = text.find("\r\n\r\n") {
+                    let length = text[..end]
+                        .lines()
+                        .find_map(|line| {
+                            line.to_ascii_lowercase()
+                                .strip_prefix("content-length:")
+                                .map(|len| len.trim().parse::<usize>().unwrap())
+                        })
+                        .unwrap_or(0);
+                    if request.len() >= end + 4 + length {
+                        break;
+                    }
+                }
+                if n == 0 {
+                    break;
+                }
+            }
+            let response = format!(
+                "HTTP/1.1 {} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&request).into_owned()
+        });
+        (url, handle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_rate_limiter() {
+        let limiter = RateLimiter::new(1200);
+        let start = Instant::now();
+        for _ in 0..3 {
+            limiter.acquire().await;
+        }
+        // The first request goes out at once, the others 50ms apart
+        assert!(start.elapsed() >= Duration::from_millis(100));
+
+        let unlimited = RateLimiter::new(0);
+        let start = Instant::now();
+        for _ in 0..100 {
+            unlimited.acquire().await;
+        }
+        assert!(start.elapsed() < Duration::from_millis(50));
+    }
+
+    #[test]
+    fn test_usage_meter() {
+        let config = SummarizerApiConfig {
+            input_cost_per_mtok: 1.0,
+            output_cost_per_mtok: 4.0,
+            ..Default::default()
+        };
+        let meter = UsageMeter::new("small", &config);
+        meter.record(1000, 100);
+        meter.record(500, 50);
+        meter.record_failure();
+
+        let usage = meter.usage();
+        assert_eq!(usage.model, "small");
+        assert_eq!(usage.requests, 3);
+        assert_eq!(usage.failed, 1);
+        assert_eq!(usage.input_tokens, 1500);
+        assert_eq!(usage.output_tokens, 150);
+        assert!((usage.cost - 0.0021).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,673 @@
+//! Background enrichment: summaries for indexed files.
+//!
+//! After a project is indexed, each file without a summary is handed to a
+//! [`Summarizer`] by a bounded pool of workers. Progress is tracked as an
+//! enrichment job, so it shows up in `Status` and can be cancelled; the
+//! summaries are persisted to the project's enriched tree.
+//!
+//! The summarizer is picked by `enrichment.backend`: doc comments and
+//! symbols, a command, or an OpenAI-compatible or Anthropic API. A backend
+//! that cannot be set up falls back to the heuristic summaries.
+
+#[cfg(feature = "api-summarizers")]
+mod anthropic;
+#[cfg(feature = "api-summarizers")]
+mod api;
+#[cfg(feature = "api-summarizers")]
+mod openai;
+
+#[cfg(feature = "api-summarizers")]
+pub use anthropic::AnthropicSummarizer;
+#[cfg(feature = "api-summarizers")]
+pub use openai::OpenAiSummarizer;
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use engram_core::{
+    CoreError, EnrichmentConfig, JobKind, ProjectManager, SummarizerApiConfig, SummarizerBackend,
+};
+use engram_indexer::scanner::{Language, ScanPhase};
+use engram_indexer::tree::{NodeContent, NodeId, NodeKind};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// Longest summary kept, in characters
+const MAX_SUMMARY_CHARS: usize = 400;
+
+/// Symbols named in a heuristic summary
+const MAX_LISTED_SYMBOLS: usize = 5;
+
+/// Time a summarizer command gets per file
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// What a summarizer sees of a file
+#[derive(Debug, Clone)]
+pub struct SummaryInput {
+    /// Path relative to the project root
+    pub path: PathBuf,
+    /// Language detected while indexing
+    pub language: Option<Language>,
+    /// Names of the symbols the file declares
+    pub symbols: Vec<String>,
+    /// Start of the file, truncated to the configured size
+    pub source: String,
+}
+
+/// Requests a summarizer sent to a paid API, since the daemon started
+#[derive(Debug, Clone, PartialEq)]
+pub struct SummarizerUsage {
+    pub model: String,
+    pub requests: u64,
+    /// Requests that got no summary back
+    pub failed: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// Estimated cost from the configured token prices
+    pub cost: f64,
+}
+
+/// Produces a one-paragraph summary of a file
+#[async_trait]
+pub trait Summarizer: Send + Sync {
+    /// Name shown in logs
+    fn name(&self) -> &'static str;
+
+    /// Summarize a file, or return `None` if there is nothing to say
+    async fn summarize(&self, input: &SummaryInput) -> Result<Option<String>>;
+
+    /// Token usage, for summarizers that call an API
+    fn usage(&self) -> Option<SummarizerUsage> {
+        None
+    }
+
+    /// Check that the backend can be reached, describing what answered
+    async fn probe(&self) -> Result<String> {
+        Ok("runs in the daemon".to_string())
+    }
+}
+
+/// Summaries from a file's leading doc comment and the symbols it declares
+pub struct HeuristicSummarizer;
+
+#[async_trait]
+impl Summarizer for HeuristicSummarizer {
+    fn name(&self) -> &'static str {
+        "heuristic"
+    }
+
+    async fn summarize(&self, input: &SummaryInput) -> Result<Option<String>> {
+        if let Some(doc) = leading_comment(&input.source) {
+            return Ok(Some(doc));
+        }
+        if input.symbols.is_empty() {
+            return Ok(None);
+        }
+
+        let listed = &input.symbols[..input.symbols.len().min(MAX_LISTED_SYMBOLS)];
+        let mut summary = format!("Defines {}", listed.join(", "));
+        if input.symbols.len() > listed.len() {
+            summary.push_str(&format!(" and {} more", input.symbols.len() - listed.len()));
+        }
+        Ok(Some(summary))
+    }
+}
+
+/// Summaries from an external command, e.g. an LLM CLI.
+///
+/// The command gets a prompt with the file on stdin and prints the summary.
+pub struct CommandSummarizer {
+    program: String,
+    args: Vec<String>,
+}
+
+impl CommandSummarizer {
+    /// Run `command[0]` with the remaining elements as arguments
+    pub fn new(command: &[String]) -> Option<Self> {
+        let (program, args) = command.split_first()?;
+        Some(Self {
+            program: program.clone(),
+            args: args.to_vec(),
+        })
+    }
+}
+
+#[async_trait]
+impl Summarizer for CommandSummarizer {
+    fn name(&self) -> &'static str {
+        "command"
+    }
+
+    async fn probe(&self) -> Result<String> {
+        match find_program(&self.program) {
+            Some(path) => Ok(format!("{} found", path.display())),
+            None => bail!("{} not found", self.program),
+        }
+    }
+
+    async fn summarize(&self, input: &SummaryInput) -> Result<Option<String>> {
+        let mut child = tokio::process::Command::new(&self.program)
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Failed to run {}", self.program))?;
+
+        let mut stdin = child.stdin.take().context("No stdin")?;
+        let prompt = summary_prompt(input);
+        let write = async move {
+            stdin.write_all(prompt.as_bytes()).await?;
+            // Close stdin so the command sees the end of the prompt
+            drop(stdin);
+            child.wait_with_output().await
+        };
+        let output = tokio::time::timeout(COMMAND_TIMEOUT, write)
+            .await
+            .context("Summarizer timed out")??;
+        if !output.status.success() {
+            bail!("{} exited with {}", self.program, output.status);
+        }
+
+        let summary = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        Ok(Some(summary).filter(|s| !s.is_empty()))
+    }
+}
+
+/// Resolve a program the way spawning it would: as a path if it has a
+/// separator, otherwise through `PATH`.
+fn find_program(program: &str) -> Option<PathBuf> {
+    let path = Path::new(program);
+    if path.components().count() > 1 {
+        return path.is_file().then(|| path.to_path_buf());
+    }
+    let suffixes: &[&str] = if cfg!(windows) {
+        &["", ".exe", ".cmd", ".bat"]
+    } else {
+        &[""]
+    };
+    std::env::split_paths(&std::env::var_os("PATH")?).find_map(|dir| {
+        suffixes
+            .iter()
+            .map(|suffix| dir.join(format!("{}{}", program, suffix)))
+            .find(|candidate| candidate.is_file())
+    })
+}
+
+/// Prompt asking a model to summarize a file.
+fn summary_prompt(input: &SummaryInput) -> String {
+    format!(
+        "Summarize what this file does in one or two sentences.\n\nPath: {}\nLanguage: {}\nSymbols: {}\n\n{}",
+        input.path.display(),
+        input.language.map_or("unknown", |language| language.name()),
+        input.symbols.join(", "),
+        input.source
+    )
+}
+
+/// The summarizer `config` asks for, or the heuristic one with the reason
+/// if it cannot be set up.
+fn configured_summarizer(config: &EnrichmentConfig) -> (Arc<dyn Summarizer>, Option<String>) {
+    let backend = config.backend.unwrap_or(if config.command.is_empty() {
+        SummarizerBackend::Heuristic
+    } else {
+        SummarizerBackend::Command
+    });
+    let summarizer: Result<Arc<dyn Summarizer>> = match backend {
+        SummarizerBackend::Heuristic => Ok(Arc::new(HeuristicSummarizer)),
+        SummarizerBackend::Command => CommandSummarizer::new(&config.command)
+            .map(|command| Arc::new(command) as Arc<dyn Summarizer>)
+            .context("No summarizer command configured"),
+        SummarizerBackend::OpenAi | SummarizerBackend::Anthropic => {
+            api_summarizer(backend, &config.api)
+        }
+    };
+    match summarizer {
+        Ok(summarizer) => (summarizer, None),
+        Err(e) => {
+            tracing::warn!(?backend, error = %e, "Using heuristic summaries");
+            (
+                Arc::new(HeuristicSummarizer),
+                Some(format!("{:?}: {}", backend, e)),
+            )
+        }
+    }
+}
+
+#[cfg(feature = "api-summarizers")]
+fn api_summarizer(
+    backend: SummarizerBackend,
+    config: &SummarizerApiConfig,
+) -> Result<Arc<dyn Summarizer>> {
+    Ok(match backend {
+        SummarizerBackend::Anthropic => Arc::new(AnthropicSummarizer::new(config)?),
+        _ => Arc::new(OpenAiSummarizer::new(config)?),
+    })
+}
+
+#[cfg(not(feature = "api-summarizers"))]
+fn api_summarizer(
+    _backend: SummarizerBackend,
+    _config: &SummarizerApiConfig,
+) -> Result<Arc<dyn Summarizer>> {
+    bail!("Built without the api-summarizers feature")
+}
+
+/// Outcome of an enrichment run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EnrichReport {
+    /// Files that got a summary
+    pub summarized: usize,
+    /// Files the summarizer failed on
+    pub failed: usize,
+}
+
+/// Runs summarizers over a project's files with a bounded worker pool
+pub struct Enricher {
+    project_manager: Arc<ProjectManager>,
+    summarizer: Arc<dyn Summarizer>,
+    /// Why the configured summarizer was replaced by the heuristic one
+    fallback: Option<String>,
+    workers: usize,
+    max_input_bytes: usize,
+}
+
+impl Enricher {
+    /// Create an enricher using the configured summarizer
+    pub fn new(project_manager: Arc<ProjectManager>, config: &EnrichmentConfig) -> Self {
+        let (summarizer, fallback) = configured_summarizer(config);
+        Self {
+            project_manager,
+            summarizer,
+            fallback,
+            workers: config.workers.max(1),
+            max_input_bytes: config.max_input_bytes,
+        }
+    }
+
+    /// Name of the summarizer in use, e.g. `openai`
+    pub fn summarizer_name(&self) -> &'static str {
+        self.summarizer.name()
+    }
+
+    /// API usage of the summarizer, if it calls one
+    pub fn usage(&self) -> Option<SummarizerUsage> {
+        self.summarizer.usage()
+    }
+
+    /// Why the configured summarizer could not be set up, if it could not
+    pub fn fallback(&self) -> Option<&str> {
+        self.fallback.as_deref()
+    }
+
+    /// Check that the summarizer's backend can be reached.
+    pub async fn probe(&self) -> Result<String> {
+        self.summarizer.probe().await
+    }
+
+    /// Summarize every file of a project that has no summary yet.
+    ///
+    /// Runs as a cancellable enrichment job. Summaries finished before a
+    /// cancellation are kept, but the project is only marked enriched once
+    /// all files were processed.
+    pub async fn enrich(&self, cwd: &Path) -> Result<EnrichReport, CoreError> {
+        let mut tree = self.project_manager.get_enriched_tree(cwd).await?;
+        let job = self
+            .project_manager
+            .jobs()
+            .start(JobKind::Enrichment, &tree.root_path);
+        let progress = job.progress();
+
+        let pending: Vec<(NodeId, SummaryInput)> = tree
+            .files()
+            .filter(|file| {
+                file.asset().is_none()
+                    && file
+                        .content
+                        .as_ref()
+                        .and_then(|c| c.summary.as_ref())
+                        .is_none()
+            })
+            .map(|file| {
+                let symbols = tree
+                    .children(file.id)
+                    .into_iter()
+                    .filter(|child| matches!(child.kind, NodeKind::Symbol { .. }))
+                    .map(|child| child.name.clone())
+                    .collect();
+                let input = SummaryInput {
+                    path: file.path.clone(),
+                    language: file.language(),
+                    symbols,
+                    source: String::new(),
+                };
+                (file.id, input)
+            })
+            .collect();
+        progress.start_phase(ScanPhase::Enriching, pending.len());
+        tracing::info!(
+            project = ?tree.root_path,
+            files = pending.len(),
+            summarizer = self.summarizer.name(),
+            "Enriching project"
+        );
+
+        let permits = Arc::new(Semaphore::new(self.workers));
+        let mut tasks = JoinSet::new();
+        for (id, mut input) in pending {
+            let permit = permits
+                .clone()
+                .acquire_owned()
+                .await
+                .expect("semaphore is never closed");
+            if progress.is_cancelled() {
+                break;
+            }
+            let summarizer = self.summarizer.clone();
+            let path = tree.root_path.join(&input.path);
+            let max_input_bytes = self.max_input_bytes;
+            tasks.spawn(async move {
+                let _permit = permit;
+                input.source = read_excerpt(&path, max_input_bytes).await;
+                (id, summarizer.summarize(&input).await)
+            });
+        }
+
+        let mut report = EnrichReport::default();
+        while let Some(joined) = tasks.join_next().await {
+            progress.advance();
+            let (id, summary) = match joined {
+                Ok(result) => result,
+                Err(e) => {
+                    tracing::warn!(error = %e, "Summarizer task failed");
+                    report.failed += 1;
+                    continue;
+                }
+            };
+            match summary {
+                Ok(Some(summary)) => {
+                    if let Some(node) = tree.get_mut(id) {
+                        let content = node.content.get_or_insert_with(NodeContent::default);
+                        content.summary = Some(truncate(summary.trim(), MAX_SUMMARY_CHARS));
+                        report.summarized += 1;
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    tracing::debug!(node = id, error = %e, "Failed to summarize file");
+                    report.failed += 1;
+                }
+            }
+        }
+
+        let cancelled = progress.is_cancelled();
+        self.project_manager
+            .save_enriched_tree(cwd, &tree, !cancelled)
+            .await?;
+        tracing::info!(
+            project = ?tree.root_path,
+            summarized = report.summarized,
+            failed = report.failed,
+            cancelled,
+            "Enrichment finished"
+        );
+
+        if cancelled {
+            return Err(CoreError::Cancelled);
+        }
+        Ok(report)
+    }
+}
+
+/// Start of a file, lossily decoded; empty if it cannot be read.
+async fn read_excerpt(path: &Path, max_bytes: usize) -> String {
+    match tokio::fs::read(path).await {
+        Ok(bytes) => String::from_utf8_lossy(&bytes[..bytes.len().min(max_bytes)]).into_owned(),
+        Err(_) => String::new(),
+    }
+}
+
+/// The file's leading comment block, joined into one line.
+fn leading_comment(source: &str) -> Option<String> {
+    let mut lines = Vec::new();
+    for line in source.lines().map(str::trim) {
+        let text = [
+            "//!", "///", "//", "#", "/**", "/*", "*/", "*", "\"\"\"", "--",
+        ]
+        .iter()
+        .find_map(|marker| line.strip_prefix(marker));
+        match text {
+            // Shebangs and editor/lint pragmas are not prose
+            Some(text) if text.starts_with('!') || text.trim_start().starts_with('[') => {}
+            Some(text) => {
+                let text = text
+                    .trim()
+                    .trim_end_matches("*/")
+                    .trim_end_matches("\"\"\"");
+                if !text.is_empty() {
+                    lines.push(text.trim().to_string());
+                } else if !lines.is_empty() {
+                    // The first paragraph is enough
+                    break;
+                }
+            }
+            None if line.is_empty() && lines.is_empty() => {}
+            None => break,
+        }
+    }
+
+    Some(truncate(&lines.join(" "), MAX_SUMMARY_CHARS)).filter(|s| !s.is_empty())
+}
+
+/// Cut `text` to at most `max` characters.
+fn truncate(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engram_core::DaemonConfig;
+    use tempfile::tempdir;
+
+    fn input(source: &str, symbols: &[&str]) -> SummaryInput {
+        SummaryInput {
+            path: PathBuf::from("src/lib.rs"),
+            language: None,
+            symbols: symbols.iter().map(|s| s.to_string()).collect(),
+            source: source.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_leading_comment() {
+        assert_eq!(
+            leading_comment("//! Session store.\n//!\n//! Details.\nuse x;").as_deref(),
+            Some("Session store.")
+        );
+        assert_eq!(
+            leading_comment("#!/usr/bin/env python\n# Build helpers\n# for CI\nimport os")
+                .as_deref(),
+            Some("Build helpers for CI")
+        );
+        assert_eq!(
+            leading_comment("/**\n * Auth middleware.\n */\nexport {}").as_deref(),
+            Some("Auth middleware.")
+        );
+        assert_eq!(leading_comment("fn main() {}\n// trailing"), None);
+    }
+
+    #[tokio::test]
+    async fn test_heuristic_summarizer() {
+        let summarizer = HeuristicSummarizer;
+        assert_eq!(
+            summarizer
+                .summarize(&input("//! Entry point.\nfn main() {}", &["main"]))
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("Entry point.")
+        );
+        assert_eq!(
+            summarizer
+                .summarize(&input("fn a() {}", &["a", "b", "c", "d", "e", "f", "g"]))
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("Defines a, b, c, d, e and 2 more")
+        );
+        assert_eq!(summarizer.summarize(&input("", &[])).await.unwrap(), None);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_command_summarizer() {
+        let summarizer = CommandSummarizer::new(&[
+            "sh".to_string(),
+            "-c".to_string(),
+            "grep -c Path: >/dev/null && echo '  Reads files.  '".to_string(),
+        ])
+        .unwrap();
+        assert_eq!(
+            summarizer
+                .summarize(&input("fn a() {}", &["a"]))
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("Reads files.")
+        );
+
+        let failing = CommandSummarizer::new(&["false".to_string()]).unwrap();
+        assert!(failing.summarize(&input("", &[])).await.is_err());
+        assert!(failing.probe().await.is_ok());
+        assert!(CommandSummarizer::new(&[]).is_none());
+
+        let missing = CommandSummarizer::new(&["engram-no-such-program".to_string()]).unwrap();
+        assert!(missing.probe().await.is_err());
+    }
+
+    #[test]
+    fn test_configured_summarizer() {
+        let mut config = EnrichmentConfig::default();
+        let (summarizer, fallback) = configured_summarizer(&config);
+        assert_eq!(summarizer.name(), "heuristic");
+        assert!(fallback.is_none());
+
+        config.command = vec!["llm".to_string()];
+        assert_eq!(configured_summarizer(&config).0.name(), "command");
+
+        // Without its API key a backend falls back to heuristic summaries
+        config.backend = Some(SummarizerBackend::Anthropic);
+        config.api.api_key_env = Some("ENGRAM_TEST_UNSET_KEY".to_string());
+        let (summarizer, fallback) = configured_summarizer(&config);
+        assert_eq!(summarizer.name(), "heuristic");
+        assert!(summarizer.usage().is_none());
+        assert!(fallback.unwrap().starts_with("Anthropic"));
+
+        #[cfg(feature = "api-summarizers")]
+        {
+            config.backend = Some(SummarizerBackend::OpenAi);
+            config.api.base_url = Some("http://127.0.0.1:11434/v1".to_string());
+            let (summarizer, _) = configured_summarizer(&config);
+            assert_eq!(summarizer.name(), "openai");
+            assert_eq!(summarizer.usage().unwrap().requests, 0);
+        }
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("héllo", 10), "héllo");
+        assert_eq!(truncate("héllo", 2), "hé…");
+    }
+
+    #[tokio::test]
+    async fn test_enrich_project() {
+        let temp_dir = tempdir().unwrap();
+        let config = DaemonConfig {
+            data_dir: temp_dir.path().join("data"),
+            ..Default::default()
+        };
+        let project_manager = Arc::new(ProjectManager::new(&config));
+        let project_dir = temp_dir.path().join("project");
+        std::fs::create_dir_all(project_dir.join("src")).unwrap();
+        std::fs::write(project_dir.join("src/lib.rs"), "//! Core library.\n").unwrap();
+        std::fs::write(project_dir.join("src/empty.rs"), "").unwrap();
+        project_manager.init_project(&project_dir).await.unwrap();
+        project_manager.scan_project(&project_dir).await.unwrap();
+
+        let enricher = Enricher::new(project_manager.clone(), &config.enrichment);
+        let report = enricher.enrich(&project_dir).await.unwrap();
+        assert_eq!(
+            report,
+            EnrichReport {
+                summarized: 1,
+                failed: 0
+            }
+        );
+        assert!(project_manager.jobs().list().is_empty());
+
+        let project = project_manager.get_project(&project_dir).await.unwrap();
+        assert!(project.manifest.enriched);
+        let tree = project_manager
+            .get_enriched_tree(&project_dir)
+            .await
+            .unwrap();
+        let lib = tree
+            .find_by_path(&PathBuf::from("src/lib.rs"))
+            .and_then(|node| node.content.as_ref())
+            .and_then(|content| content.summary.as_deref());
+        assert_eq!(lib, Some("Core library."));
+
+        // Already summarized files are skipped on the next run
+        let report = enricher.enrich(&project_dir).await.unwrap();
+        assert_eq!(report.summarized, 0);
+    }
+
+    struct FailingSummarizer;
+
+    #[async_trait]
+    impl Summarizer for FailingSummarizer {
+        fn name(&self) -> &'static str {
+            "failing"
+        }
+
+        async fn summarize(&self, _input: &SummaryInput) -> Result<Option<String>> {
+            bail!("unavailable")
+        }
+    }
+
+    #[tokio::test]
+    async fn test_enrich_counts_failures() {
+        let temp_dir = tempdir().unwrap();
+        let config = DaemonConfig {
+            data_dir: temp_dir.path().join("data"),
+            ..Default::default()
+        };
+        let project_manager = Arc::new(ProjectManager::new(&config));
+        let project_dir = temp_dir.path().join("project");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        std::fs::write(project_dir.join("a.rs"), "fn a() {}").unwrap();
+        std::fs::write(project_dir.join("b.rs"), "fn b() {}").unwrap();
+        project_manager.init_project(&project_dir).await.unwrap();
+        project_manager.scan_project(&project_dir).await.unwrap();
+
+        let mut enricher = Enricher::new(project_manager.clone(), &config.enrichment);
+        enricher.summarizer = Arc::new(FailingSummarizer);
+        let report = enricher.enrich(&project_dir).await.unwrap();
+        assert_eq!(
+            report,
+            EnrichReport {
+                summarized: 0,
+                failed: 2
+            }
+        );
+    }
+}
@@ -0,0 +1,191 @@
+//! Summaries from an OpenAI-compatible chat completions API.
+//!
+//! Besides OpenAI itself this covers local servers (Ollama, llama.cpp,
+//! vLLM) through `base_url`; those usually need no API key.
+
+use super::api::{self, RateLimiter, UsageMeter};
+use super::{summary_prompt, Summarizer, SummarizerUsage, SummaryInput};
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use engram_core::SummarizerApiConfig;
+use serde::Deserialize;
+use serde_json::json;
+
+const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+const DEFAULT_MODEL: &str = "gpt-4o-mini";
+const API_KEY_ENV: &str = "OPENAI_API_KEY";
+
+#[derive(Deserialize)]
+struct Completion {
+    choices: Vec<Choice>,
+    #[serde(default)]
+    usage: Option<Usage>,
+}
+
+#[derive(Deserialize)]
+struct Choice {
+    message: Message,
+}
+
+#[derive(Deserialize)]
+struct Message {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+struct Usage {
+    #[serde(default)]
+    prompt_tokens: u64,
+    #[serde(default)]
+    completion_tokens: u64,
+}
+
+/// Summarizer backed by `POST {base_url}/chat/completions`
+pub struct OpenAiSummarizer {
+    client: reqwest::Client,
+    url: String,
+    model: String,
+    api_key: Option<String>,
+    max_tokens: u32,
+    limiter: RateLimiter,
+    usage: UsageMeter,
+}
+
+impl OpenAiSummarizer {
+    /// Create a summarizer; an API key is required for the default URL
+    pub fn new(config: &SummarizerApiConfig) -> Result<Self> {
+        let api_key = api::api_key(config, API_KEY_ENV);
+        if api_key.is_none() && config.base_url.is_none() {
+            bail!(
+                "{} is not set",
+                config.api_key_env.as_deref().unwrap_or(API_KEY_ENV)
+            );
+        }
+        let model = config.model.as_deref().unwrap_or(DEFAULT_MODEL);
+        Ok(Self {
+            client: api::client()?,
+            url: format!(
+                "{}/chat/completions",
+                api::base_url(config, DEFAULT_BASE_URL)
+            ),
+            model: model.to_string(),
+            api_key,
+            max_tokens: config.max_tokens,
+            limiter: RateLimiter::new(config.requests_per_minute),
+            usage: UsageMeter::new(model, config),
+        })
+    }
+}
+
+#[async_trait]
+impl Summarizer for OpenAiSummarizer {
+    fn name(&self) -> &'static str {
+        "openai"
+    }
+
+    async fn summarize(&self, input: &SummaryInput) -> Result<Option<String>> {
+        self.limiter.acquire().await;
+        let mut request = self.client.post(&self.url).json(&json!({
+            "model": self.model,
+            "max_tokens": self.max_tokens,
+            "messages": [{ "role": "user", "content": summary_prompt(input) }],
+        }));
+        if let Some(key) = &self.api_key {
+            request = request.bearer_auth(key);
+        }
+
+        let completion: Completion = match api::send(request).await {
+            Ok(completion) => completion,
+            Err(e) => {
+                self.usage.record_failure();
+                return Err(e);
+            }
+        };
+        let usage = completion.usage.unwrap_or_default();
+        self.usage
+            .record(usage.prompt_tokens, usage.completion_tokens);
+
+        let summary = completion
+            .choices
+            .into_iter()
+            .find_map(|choice| choice.message.content)
+            .unwrap_or_default();
+        Ok(Some(summary.trim().to_string()).filter(|s| !s.is_empty()))
+    }
+
+    fn usage(&self) -> Option<SummarizerUsage> {
+        Some(self.usage.usage())
+    }
+
+    async fn probe(&self) -> Result<String> {
+        api::probe(&self.client, &self.url).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enrich::api::mock::serve_once;
+    use std::path::PathBuf;
+
+    fn input() -> SummaryInput {
+        SummaryInput {
+            path: PathBuf::from("src/auth.rs"),
+            language: None,
+            symbols: vec!["login".to_string()],
+            source: "fn login() {}".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_openai_summarizer() {
+        let (url, request) = serve_once(
+            200,
+            r#"{"choices":[{"message":{"role":"assistant","content":" Handles login. "}}],"usage":{"prompt_tokens":120,"completion_tokens":8}}"#,
+        )
+        .await;
+        let config = SummarizerApiConfig {
+            base_url: Some(format!("{}/v1/", url)),
+            model: Some("local-model".to_string()),
+            api_key_env: Some("ENGRAM_TEST_UNSET_KEY".to_string()),
+            input_cost_per_mtok: 1_000_000.0,
+            ..Default::default()
+        };
+        let summarizer = OpenAiSummarizer::new(&config).unwrap();
+        assert_eq!(
+            summarizer.summarize(&input()).await.unwrap().as_deref(),
+            Some("Handles login.")
+        );
+
+        let request = request.await.unwrap();
+        assert!(request.starts_with("POST /v1/chat/completions "));
+        assert!(request.contains("\"model\":\"local-model\""));
+        assert!(request.contains("src/auth.rs"));
+        assert!(!request.to_ascii_lowercase().contains("authorization:"));
+
+        let usage = summarizer.usage().unwrap();
+        assert_eq!((usage.requests, usage.failed), (1, 0));
+        assert_eq!((usage.input_tokens, usage.output_tokens), (120, 8));
+        assert_eq!(usage.cost, 120.0);
+    }
+
+    #[tokio::test]
+    async fn test_openai_summarizer_errors() {
+        let config = SummarizerApiConfig {
+            api_key_env: Some("ENGRAM_TEST_UNSET_KEY".to_string()),
+            ..Default::default()
+        };
+        assert!(OpenAiSummarizer::new(&config).is_err());
+
+        let (url, _request) = serve_once(429, r#"{"error":"slow down"}"#).await;
+        let config = SummarizerApiConfig {
+            base_url: Some(url),
+            ..config
+        };
+        let summarizer = OpenAiSummarizer::new(&config).unwrap();
+        let error = summarizer.summarize(&input()).await.unwrap_err();
+        assert!(error.to_string().contains("429"));
+        assert_eq!(summarizer.usage().unwrap().failed, 1);
+    }
+}
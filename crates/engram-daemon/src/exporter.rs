@@ -0,0 +1,353 @@
+//! Prometheus metrics endpoint.
+//!
+//! Serves `GET /metrics` in the Prometheus text format on a dedicated
+//! loopback port (`metrics_addr`), covering request counts and latency
+//! histograms, render cache effectiveness, the size of each loaded project's
+//! index and the daemon's memory pressure.
+
+use crate::handler::get_memory_usage;
+use anyhow::{bail, Context, Result};
+use engram_core::{LatencyTracker, MemoryMonitor, Metrics, ProjectManager, LATENCY_BUCKETS};
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Path serving the metrics
+const METRICS_PATH: &str = "/metrics";
+
+/// Maximum size of a scrape request
+const MAX_REQUEST_SIZE: usize = 8 * 1024;
+
+/// Timeout for reading a scrape request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Exposes daemon metrics to Prometheus
+pub struct MetricsExporter {
+    metrics: Arc<Metrics>,
+    latency: Arc<LatencyTracker>,
+    project_manager: Arc<ProjectManager>,
+    memory: MemoryMonitor,
+}
+
+impl MetricsExporter {
+    /// Create an exporter; memory pressure is measured against `memory_limit`
+    pub fn new(
+        metrics: Arc<Metrics>,
+        latency: Arc<LatencyTracker>,
+        project_manager: Arc<ProjectManager>,
+        memory_limit: usize,
+    ) -> Self {
+        Self {
+            metrics,
+            latency,
+            project_manager,
+            memory: MemoryMonitor::new(memory_limit),
+        }
+    }
+
+    /// Serve metrics on a loopback address until the task is dropped
+    pub async fn serve(self, addr: SocketAddr) -> Result<()> {
+        if !addr.ip().is_loopback() {
+            bail!("metrics must bind to a loopback address, got {}", addr);
+        }
+        let listener = TcpListener::bind(addr)
+            .await
+            .with_context(|| format!("Failed to bind metrics endpoint on {}", addr))?;
+        tracing::info!("Metrics endpoint listening on {}", listener.local_addr()?);
+
+        let exporter = Arc::new(self);
+        loop {
+            match listener.accept().await {
+                Ok((stream, _addr)) => {
+                    let exporter = exporter.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = exporter.handle_connection(stream).await {
+                            tracing::debug!("Metrics connection error: {}", e);
+                        }
+                    });
+                }
+                Err(e) => tracing::error!("Metrics accept error: {}", e),
+            }
+        }
+    }
+
+    /// Answer a single scrape; connections are not kept alive
+    async fn handle_connection(&self, mut stream: TcpStream) -> Result<()> {
+        let request_line = tokio::time::timeout(REQUEST_TIMEOUT, read_request_line(&mut stream))
+            .await
+            .context("Timed out reading request")??;
+
+        let mut parts = request_line.split_whitespace();
+        let (status, body) = match (parts.next(), parts.next()) {
+            (Some("GET"), Some(METRICS_PATH)) => ("200 OK", self.render().await),
+            (Some(_), Some(METRICS_PATH)) => ("405 Method Not Allowed", String::new()),
+            _ => ("404 Not Found", String::new()),
+        };
+
+        let head = format!(
+            "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            status,
+            body.len()
+        );
+        stream.write_all(head.as_bytes()).await?;
+        stream.write_all(body.as_bytes()).await?;
+        stream.flush().await?;
+        Ok(())
+    }
+
+    /// Render all metrics in the Prometheus text format
+    pub async fn render(&self) -> String {
+        let mut out = String::new();
+
+        let requests_total = self.metrics.requests_total.load(Ordering::Relaxed);
+        family(
+            &mut out,
+            "engram_requests_total",
+            "counter",
+            "Requests served",
+        );
+        let _ = writeln!(out, "engram_requests_total {}", requests_total);
+
+        family(
+            &mut out,
+            "engram_request_duration_seconds",
+            "histogram",
+            "Request latency by action",
+        );
+        for histogram in self.latency.histograms() {
+            let action = escape(&histogram.operation);
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(&histogram.buckets) {
+                let _ = writeln!(
+                    out,
+                    "engram_request_duration_seconds_bucket{{action=\"{}\",le=\"{}\"}} {}",
+                    action, bound, count
+                );
+            }
+            let _ = writeln!(
+                out,
+                "engram_request_duration_seconds_bucket{{action=\"{}\",le=\"+Inf\"}} {}",
+                action, histogram.count
+            );
+            let _ = writeln!(
+                out,
+                "engram_request_duration_seconds_sum{{action=\"{}\"}} {}",
+                action,
+                histogram.sum.as_secs_f64()
+            );
+            let _ = writeln!(
+                out,
+                "engram_request_duration_seconds_count{{action=\"{}\"}} {}",
+                action, histogram.count
+            );
+        }
+
+        family(
+            &mut out,
+            "engram_cache_hits_total",
+            "counter",
+            "Rendered context cache hits",
+        );
+        let _ = writeln!(
+            out,
+            "engram_cache_hits_total {}",
+            self.metrics.cache_hits.load(Ordering::Relaxed)
+        );
+        family(
+            &mut out,
+            "engram_cache_misses_total",
+            "counter",
+            "Rendered context cache misses",
+        );
+        let _ = writeln!(
+            out,
+            "engram_cache_misses_total {}",
+            self.metrics.cache_misses.load(Ordering::Relaxed)
+        );
+        family(
+            &mut out,
+            "engram_cache_hit_ratio",
+            "gauge",
+            "Share of context requests served from cache",
+        );
+        let _ = writeln!(
+            out,
+            "engram_cache_hit_ratio {}",
+            self.metrics.cache_hit_rate()
+        );
+
+        let projects = self.project_manager.loaded_projects().await;
+        family(
+            &mut out,
+            "engram_projects_loaded",
+            "gauge",
+            "Projects in the cache",
+        );
+        let _ = writeln!(out, "engram_projects_loaded {}", projects.len());
+        family(
+            &mut out,
+            "engram_project_files",
+            "gauge",
+            "Files indexed per loaded project",
+        );
+        for project in &projects {
+            let _ = writeln!(
+                out,
+                "engram_project_files{{project=\"{}\"}} {}",
+                escape(&project.path.display().to_string()),
+                project.manifest.file_count
+            );
+        }
+        family(
+            &mut out,
+            "engram_project_index_bytes",
+            "gauge",
+            "On-disk index size per loaded project",
+        );
+        for project in &projects {
+            let storage_dir = project.storage_dir.clone();
+            let bytes = tokio::task::spawn_blocking(move || dir_size(&storage_dir))
+                .await
+                .unwrap_or(0);
+            let _ = writeln!(
+                out,
+                "engram_project_index_bytes{{project=\"{}\"}} {}",
+                escape(&project.path.display().to_string()),
+                bytes
+            );
+        }
+
+        self.memory.update(get_memory_usage());
+        family(
+            &mut out,
+            "engram_memory_usage_bytes",
+            "gauge",
+            "Peak resident memory of the daemon",
+        );
+        let _ = writeln!(out, "engram_memory_usage_bytes {}", self.memory.current());
+        family(
+            &mut out,
+            "engram_memory_limit_bytes",
+            "gauge",
+            "Configured memory limit",
+        );
+        let _ = writeln!(out, "engram_memory_limit_bytes {}", self.memory.limit());
+        family(
+            &mut out,
+            "engram_memory_pressure",
+            "gauge",
+            "Memory pressure level (1 for the current level)",
+        );
+        let pressure = self.memory.check_pressure();
+        for level in ["normal", "warning", "critical"] {
+            let _ = writeln!(
+                out,
+                "engram_memory_pressure{{level=\"{}\"}} {}",
+                level,
+                u8::from(level == pressure.as_str())
+            );
+        }
+
+        out
+    }
+}
+
+/// Read the request line, discarding headers.
+async fn read_request_line(stream: &mut TcpStream) -> Result<String> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 1024];
+    while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+        if buf.len() > MAX_REQUEST_SIZE {
+            bail!("request too large");
+        }
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+
+    let head = String::from_utf8_lossy(&buf);
+    Ok(head.lines().next().unwrap_or_default().to_string())
+}
+
+/// Write the `# HELP` and `# TYPE` lines of a metric family
+fn family(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+/// Escape a label value
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Total size of the files under `dir`
+fn dir_size(dir: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(kind) if kind.is_dir() => dir_size(&entry.path()),
+            Ok(_) => entry.metadata().map(|m| m.len()).unwrap_or(0),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engram_core::DaemonConfig;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_render() {
+        let temp_dir = tempdir().unwrap();
+        let config = DaemonConfig {
+            data_dir: temp_dir.path().join("data"),
+            ..Default::default()
+        };
+        let project_manager = Arc::new(ProjectManager::new(&config));
+        let project_dir = temp_dir.path().join("project");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        project_manager.init_project(&project_dir).await.unwrap();
+
+        let metrics = Arc::new(Metrics::new());
+        metrics.record_request(Duration::from_millis(3));
+        metrics.record_cache_hit();
+        let latency = Arc::new(LatencyTracker::default());
+        latency.record("get_context", Duration::from_millis(3));
+
+        let exporter = MetricsExporter::new(metrics, latency, project_manager, usize::MAX);
+        let text = exporter.render().await;
+
+        assert!(text.contains("# TYPE engram_requests_total counter\nengram_requests_total 1\n"));
+        assert!(text.contains(
+            "engram_request_duration_seconds_bucket{action=\"get_context\",le=\"0.001\"} 0\n"
+        ));
+        assert!(text.contains(
+            "engram_request_duration_seconds_bucket{action=\"get_context\",le=\"0.005\"} 1\n"
+        ));
+        assert!(text.contains("engram_request_duration_seconds_count{action=\"get_context\"} 1\n"));
+        assert!(text.contains("engram_cache_hit_ratio 1\n"));
+        assert!(text.contains("engram_projects_loaded 1\n"));
+        assert!(text.contains("engram_project_index_bytes{project=\""));
+        assert!(text.contains("engram_memory_pressure{level=\"normal\"} 1\n"));
+        assert!(text.contains("engram_memory_pressure{level=\"critical\"} 0\n"));
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape(r#"C:\a "b""#), r#"C:\\a \"b\""#);
+    }
+}
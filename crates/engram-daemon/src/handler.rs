@@ -1,26 +1,124 @@
 //! Request handler for daemon IPC.
 
 use async_trait::async_trait;
-use engram_context::{ContextManager, ContextRenderer, MemoryStore, ScopeRequest};
-use engram_core::{Metrics, ProjectManager};
+use engram_context::{
+    summarize_session, CachedContext, Calibration, CalibrationStore, CompactionPolicy,
+    ConsolidationPolicy, ContextError, ContextKey, ContextManager, ContextRenderer, ContextScope,
+    HybridRouter, MemoryStore, MemoryStoreError, Outcome, PendingMemory, RelevanceStore,
+    RenderCache, RenderKey, ResultSource, RetrievalResult, ScopeRequest, Session, SessionStore,
+    VectorStore, PROJECT_BRIEF_ID, PROJECT_BRIEF_KIND,
+};
+use engram_core::{
+    ContextDependencies, CoreError, DaemonConfig, EnrichmentConfig, Event, EventKind, Experiment,
+    Experiments, JobKind, JobStatus, LatencyTracker, MemoryConfig, MemoryMonitor, Metrics,
+    ProjectManager, ResourceUsage, ScopeConfig, SloConfig, SloTracker, WatchConfig,
+};
+use engram_indexer::scanner::{detect_language, quick_skeleton, Parser, SymbolKind};
 use engram_indexer::storage::Storage;
-use engram_ipc::{ErrorCode, Request, RequestHandler, Response, ResponseData};
+use engram_indexer::tree::{
+    analyze_impact, anonymize_tree, diff_trees, find_files, is_test_path, render_docs,
+    symbol_calls, tree_stats, DocsFormat, NodeId, NodeKind, SkeletonOptions, SymbolChange, Tree,
+};
+use engram_indexer::CancelToken;
+use engram_ipc::{
+    AssetCount, ChangeType, ContextSection, DependentFile, DiffSymbol, DocFile, EndpointLatency,
+    ErrorCode, EventInfo, ExcludedDirInfo, ExperienceOutcome, ExperimentInfo, FileCount, FileMatch,
+    FileSize, GarbageProject, ImpactFile, ImpactSymbol, IndexFile, JobInfo, LoadedProjectInfo,
+    MatchReason, MemoryBundle, MemoryMatch, MemoryQuery, NamedCount, ProjectSummary, RecoveryInfo,
+    RenderCacheInfo, Request, RequestHandler, RequestUsage, Response, ResponseData, SearchHit,
+    SessionInfo, SloInfo, StructureNode, SummarizerInfo, MEMORY_BUNDLE_FORMAT,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 use std::sync::atomic::Ordering;
-use std::sync::Arc;
-use std::time::Instant;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
 use tokio::sync::broadcast;
 use uuid::Uuid;
 
+use crate::alerts;
+use crate::doctor::{check_data_dir, Doctor};
+use crate::enrich::Enricher;
+use crate::exporter::MetricsExporter;
+use crate::health;
+use crate::watch::{WatchHeartbeat, WatchManager};
+
+/// Directory levels listed in a cold-start skeleton
+const COLD_START_DEPTH: usize = 2;
+
+/// Maximum entries listed in a cold-start skeleton
+const COLD_START_MAX_ENTRIES: usize = 200;
+
+/// Related experiences returned with a symbol
+const SYMBOL_EXPERIENCES: usize = 5;
+
+/// Declaration lines read when looking for the start of a symbol's body
+const SIGNATURE_MAX_LINES: usize = 12;
+
+/// Files most worked on lately whose search hits are boosted
+const HOT_FILES: usize = 10;
+
+/// Settings changed by reloading the daemon config
+#[derive(Debug, Clone, Default)]
+pub struct ConfigReload {
+    /// Changed settings now in effect, as dotted names
+    pub applied: Vec<String>,
+    /// Changed settings that take effect after a restart
+    pub restart_required: Vec<String>,
+}
+
 /// Handles incoming IPC requests
 pub struct DaemonHandler {
     project_manager: Arc<ProjectManager>,
+    storage: Arc<Storage>,
     memory_store: Arc<MemoryStore>,
+    /// Memory retention defaults, overridable per project
+    memory_config: MemoryConfig,
+    /// Memory writes from read-only sub-agents awaiting approval
+    pending_memory: PendingMemory,
+    /// Agent sessions per project
+    sessions: Arc<SessionStore>,
+    /// Per-project scoring calibration
+    calibration: CalibrationStore,
+    /// Per-file relevance learned from experience outcomes
+    relevance: Arc<RelevanceStore>,
+    /// Per-project semantic index, rebuilt when the tree changes
+    vectors: VectorStore,
     context_manager: Arc<ContextManager>,
     context_renderer: ContextRenderer,
+    /// Rendered context keyed by tree generation, scope and options
+    render_cache: Arc<RenderCache>,
     shutdown_tx: broadcast::Sender<()>,
     start_time: Instant,
     /// Metrics for request tracking
     metrics: Arc<Metrics>,
+    /// Request latency by action
+    latency: Arc<LatencyTracker>,
+    /// Experiment flags (reloaded when the config changes)
+    experiments: Arc<Experiments>,
+    /// Project structure rendering options
+    skeleton_options: SkeletonOptions,
+    /// Context scope expiry and persistence
+    scope_config: ScopeConfig,
+    /// Latency SLO compliance
+    slo: SloTracker,
+    /// Where SLO alerts are POSTed, if anywhere
+    slo_webhook: Option<String>,
+    /// Summarizes files after background indexing (replaced when the
+    /// enrichment settings are reloaded)
+    enricher: RwLock<Arc<Enricher>>,
+    /// Config file re-read by `ReloadConfig` and SIGHUP
+    config_path: PathBuf,
+    /// Settings as last read from the config file
+    config: Mutex<DaemonConfig>,
+    /// Diagnostics for the daemon's socket, PID file and storage
+    doctor: Doctor,
+    /// Liveness of the watch manager, for health checks
+    watch_heartbeat: Arc<WatchHeartbeat>,
+    /// Cancellation tokens of tracked requests, by request ID
+    in_flight: Mutex<HashMap<String, CancelToken>>,
+    /// Crash recovery report, handed out by the first `Status`
+    recovery: Mutex<Option<Box<RecoveryInfo>>>,
 }
 
 impl DaemonHandler {
@@ -31,486 +129,6342 @@ impl DaemonHandler {
         shutdown_tx: broadcast::Sender<()>,
         start_time: Instant,
     ) -> Self {
-        let context_manager = Arc::new(ContextManager::new(storage.clone()));
+        let memory_store = Arc::new(MemoryStore::new(storage.clone()));
+        let sessions = Arc::new(SessionStore::new(storage.clone()));
+        let relevance = Arc::new(RelevanceStore::new(storage.clone()));
+        let context_manager = Arc::new(
+            ContextManager::new(storage.clone())
+                .with_memory_store(memory_store.clone())
+                .with_session_store(sessions.clone())
+                .with_relevance_store(relevance.clone()),
+        );
         let context_renderer = ContextRenderer::new();
-        let memory_store = Arc::new(MemoryStore::new(storage));
+        let calibration = CalibrationStore::new(storage.clone());
+        let pending_memory = PendingMemory::new(storage.clone());
+        let vectors = VectorStore::new(storage.clone());
+        let enricher = Arc::new(Enricher::new(
+            project_manager.clone(),
+            &EnrichmentConfig::default(),
+        ));
 
         Self {
             project_manager,
+            storage,
             memory_store,
+            memory_config: MemoryConfig::default(),
+            pending_memory,
+            sessions,
+            calibration,
+            relevance,
+            vectors,
             context_manager,
             context_renderer,
+            render_cache: Arc::new(RenderCache::new()),
             shutdown_tx,
             start_time,
             metrics: Arc::new(Metrics::new()),
+            latency: Arc::new(LatencyTracker::default()),
+            experiments: Arc::new(Experiments::default()),
+            skeleton_options: SkeletonOptions::default(),
+            scope_config: ScopeConfig::default(),
+            slo: SloTracker::default(),
+            slo_webhook: None,
+            enricher: RwLock::new(enricher),
+            config_path: DaemonConfig::config_path(),
+            config: Mutex::new(DaemonConfig::default()),
+            doctor: Doctor::new(&DaemonConfig::default()),
+            watch_heartbeat: Arc::new(WatchHeartbeat::default()),
+            in_flight: Mutex::new(HashMap::new()),
+            recovery: Mutex::new(None),
+        }
+    }
+
+    /// Track latency against the configured SLOs.
+    pub fn with_slo(mut self, config: &SloConfig) -> Self {
+        self.slo = SloTracker::new(config);
+        self.slo_webhook = config.webhook.clone();
+        self
+    }
+
+    /// Summarize files with the configured summarizer and worker count.
+    pub fn with_enrichment(mut self, config: &EnrichmentConfig) -> Self {
+        self.enricher = RwLock::new(Arc::new(Enricher::new(
+            self.project_manager.clone(),
+            config,
+        )));
+        self
+    }
+
+    /// Reload settings from the config file at `path`, which `config` was
+    /// read from.
+    pub fn with_config_file(mut self, path: PathBuf, config: &DaemonConfig) -> Self {
+        self.config_path = path;
+        self.config = Mutex::new(config.clone());
+        self
+    }
+
+    /// Compact memory logs according to the given retention settings.
+    pub fn with_memory_config(mut self, config: &MemoryConfig) -> Self {
+        self.memory_store = Arc::new(
+            MemoryStore::new(self.storage.clone()).with_compaction(compaction_policy(config)),
+        );
+        self.memory_config = config.clone();
+        self.context_manager = Arc::new(self.new_context_manager());
+        self
+    }
+
+    /// Use the given options when rendering project structure.
+    pub fn with_skeleton_options(mut self, options: SkeletonOptions) -> Self {
+        self.skeleton_options = options;
+        self.context_manager = Arc::new(self.new_context_manager());
+        self
+    }
+
+    /// Expire and persist context scopes according to the given settings.
+    pub fn with_scope_config(mut self, config: &ScopeConfig) -> Self {
+        self.scope_config = config.clone();
+        self.context_manager = Arc::new(self.new_context_manager());
+        self
+    }
+
+    /// Context manager using the configured skeleton options and scope
+    /// lifecycle.
+    fn new_context_manager(&self) -> ContextManager {
+        ContextManager::new(self.storage.clone())
+            .with_memory_store(self.memory_store.clone())
+            .with_session_store(self.sessions.clone())
+            .with_relevance_store(self.relevance.clone())
+            .with_skeleton_options(self.skeleton_options.clone())
+            .with_scope_ttl(Duration::from_secs(self.scope_config.ttl_secs))
+            .with_scope_persistence(self.scope_config.persist)
+            .with_consolidation(ConsolidationPolicy {
+                every: self.memory_config.consolidate_every,
+                keep_recent: self.memory_config.keep_recent_experiences,
+                ..Default::default()
+            })
+    }
+
+    /// Diagnose the configured socket and PID file in `doctor` requests.
+    pub fn with_doctor(mut self, config: &DaemonConfig) -> Self {
+        self.doctor = Doctor::new(config);
+        self
+    }
+
+    /// Report recovery from an unclean shutdown in the next `Status`.
+    pub fn with_recovery(mut self, recovery: RecoveryInfo) -> Self {
+        self.recovery = Mutex::new(Some(Box::new(recovery)));
+        self
+    }
+
+    /// Use the given experiment flags instead of all-disabled defaults.
+    pub fn with_experiments(mut self, experiments: Arc<Experiments>) -> Self {
+        self.experiments = experiments;
+        self
+    }
+
+    /// Current enricher.
+    fn enricher(&self) -> Arc<Enricher> {
+        self.enricher.read().unwrap().clone()
+    }
+
+    /// Re-read the config file and apply what can change at runtime: the
+    /// project cache size, experiment flags and enrichment settings,
+    /// including the summarizer's rate limit.
+    ///
+    /// Enrichment jobs already running finish with the previous settings,
+    /// and the summarizer's usage counters start over. A config file that
+    /// fails to parse changes nothing.
+    pub async fn reload_config(&self) -> Result<ConfigReload, CoreError> {
+        let config = DaemonConfig::reload_from(&self.config_path)?;
+        let changed = self.config.lock().unwrap().changed_fields(&config);
+        let (applied, restart_required): (Vec<String>, Vec<String>) = changed
+            .into_iter()
+            .partition(|field| DaemonConfig::is_reloadable(field));
+
+        if applied.iter().any(|field| field == "max_projects") {
+            self.project_manager
+                .set_max_projects(config.max_projects)
+                .await;
+        }
+        if applied.iter().any(|field| field.starts_with("enrichment.")) {
+            *self.enricher.write().unwrap() = Arc::new(Enricher::new(
+                self.project_manager.clone(),
+                &config.enrichment,
+            ));
+        }
+        // Re-reads the flags now that the file changed
+        self.experiments.current();
+        *self.config.lock().unwrap() = config;
+
+        tracing::info!(
+            path = ?self.config_path,
+            applied = ?applied,
+            restart_required = ?restart_required,
+            "Reloaded config"
+        );
+        if !applied.is_empty() || !restart_required.is_empty() {
+            self.project_manager.events().record(
+                EventKind::ConfigReloaded,
+                None,
+                format!(
+                    "Reloaded config: {} setting(s) applied, {} need a restart",
+                    applied.len(),
+                    restart_required.len()
+                ),
+            );
+        }
+        Ok(ConfigReload {
+            applied,
+            restart_required,
+        })
+    }
+
+    /// Create a watch manager that keeps this handler's caches in sync with
+    /// file changes.
+    pub fn watch_manager(&self, config: &WatchConfig) -> WatchManager {
+        WatchManager::new(
+            self.project_manager.clone(),
+            self.context_manager.clone(),
+            self.render_cache.clone(),
+            config,
+        )
+        .with_heartbeat(self.watch_heartbeat.clone())
+    }
+
+    /// Create a Prometheus exporter for this handler's metrics.
+    pub fn metrics_exporter(&self, memory_limit: usize) -> MetricsExporter {
+        MetricsExporter::new(
+            self.metrics.clone(),
+            self.latency.clone(),
+            self.project_manager.clone(),
+            memory_limit,
+        )
+    }
+
+    /// Flush state to disk before shutdown.
+    ///
+    /// Running jobs are cancelled and given until `deadline` to finish, so
+    /// enrichment saves the summaries it has so far. Then in-flight memory
+    /// writes are awaited and project manifests saved.
+    pub async fn flush(&self, deadline: tokio::time::Instant) {
+        let jobs = self.project_manager.jobs();
+        for job in jobs.list() {
+            jobs.cancel(job.id);
+        }
+        while !jobs.list().is_empty() && tokio::time::Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        let unfinished = jobs.list().len();
+
+        let memory = self.memory_store.flush().await;
+        let projects = self.project_manager.flush().await;
+        tracing::info!(
+            jobs_unfinished = unfinished,
+            memory_projects = memory,
+            projects,
+            "Flushed caches"
+        );
+    }
+
+    /// Check an experiment for a project, applying its manifest overrides.
+    async fn experiment_enabled(&self, cwd: &Path, experiment: Experiment) -> bool {
+        match self.project_manager.get_project(cwd).await {
+            Ok(project) => self
+                .experiments
+                .is_enabled(experiment, &project.manifest.experiments),
+            Err(_) => self.experiments.current().is_enabled(experiment),
         }
     }
 
+    /// Memory store with the project's retention overrides applied.
+    ///
+    /// Used before writes, which may trigger compaction.
+    async fn project_memory(&self, cwd: &Path) -> &MemoryStore {
+        if let Ok(project) = self.project_manager.get_project(cwd).await {
+            let config = project.config.memory_config(&self.memory_config);
+            self.memory_store
+                .set_project_policy(cwd, compaction_policy(&config));
+        }
+        &self.memory_store
+    }
+
     /// Get uptime in seconds
     fn uptime_secs(&self) -> u64 {
         self.start_time.elapsed().as_secs()
     }
-}
 
-#[async_trait]
-impl RequestHandler for DaemonHandler {
-    async fn handle(&self, request: Request) -> Response {
-        match request {
-            Request::Ping => Response::ok_with(ResponseData::Pong {
-                timestamp: chrono::Utc::now().timestamp(),
-            }),
+    /// Render a scope, serving from the render cache unless `no_cache` is set.
+    ///
+    /// `max_content_size` overrides the renderer's size limit for the project.
+    fn render_context(
+        &self,
+        cwd: &Path,
+        scope: &ContextScope,
+        tree: &Tree,
+        max_content_size: Option<usize>,
+        no_cache: bool,
+    ) -> String {
+        let project_renderer;
+        let renderer = match max_content_size {
+            Some(size) => {
+                project_renderer = ContextRenderer::with_max_size(size);
+                &project_renderer
+            }
+            None => &self.context_renderer,
+        };
 
-            Request::Status => {
-                let projects_loaded = self.project_manager.loaded_count().await;
-                let requests_total = self.metrics.requests_total.load(Ordering::Relaxed);
-                let cache_hit_rate = self.metrics.cache_hit_rate();
-                let avg_latency_ms = self.metrics.avg_latency().as_millis() as u64;
+        if no_cache {
+            return renderer.render(scope, tree);
+        }
 
-                Response::ok_with(ResponseData::Status {
-                    version: env!("CARGO_PKG_VERSION").to_string(),
-                    uptime_secs: self.uptime_secs(),
-                    projects_loaded,
-                    memory_usage_bytes: get_memory_usage(),
-                    requests_total,
-                    cache_hit_rate,
-                    avg_latency_ms,
-                })
+        let key = RenderKey::new(tree, scope, renderer);
+        if let Some(context) = self.render_cache.get(cwd, &key) {
+            self.metrics.record_cache_hit();
+            return context;
+        }
+
+        self.metrics.record_cache_miss();
+        let context = renderer.render(scope, tree);
+        self.render_cache.insert(cwd, key, context.clone());
+        context
+    }
+
+    /// Add a node's importers and imports to a scope and render them.
+    async fn handle_expand_context(&self, cwd: &Path, scope_id: &str, node: &str) -> Response {
+        let expansion = match self
+            .context_manager
+            .expand_dependents(cwd, scope_id, node)
+            .await
+        {
+            Ok(expansion) => expansion,
+            Err(e @ (ContextError::ScopeNotFound(_) | ContextError::NodeNotFound(_))) => {
+                return Response::error(ErrorCode::InvalidRequest, e.to_string())
             }
+            Err(e) => return Response::failure(&e),
+        };
+        let tree = match self.project_manager.get_tree(cwd).await {
+            Ok(tree) => tree,
+            Err(e) => return Response::failure(&e),
+        };
 
-            Request::CheckInit { cwd } => {
-                let initialized = self.project_manager.is_initialized(&cwd).await;
-                Response::ok_with(ResponseData::InitStatus { initialized })
+        let max_content_size = self
+            .project_manager
+            .get_project(cwd)
+            .await
+            .ok()
+            .and_then(|project| project.config.context.max_content_size);
+        let context = match max_content_size {
+            Some(size) => ContextRenderer::with_max_size(size).render_expansion(&expansion, &tree),
+            None => self.context_renderer.render_expansion(&expansion, &tree),
+        };
+        Response::ok_with(ResponseData::Context {
+            context,
+            nodes: expansion.nodes().iter().map(|id| id.to_string()).collect(),
+            scope_id: scope_id.to_string(),
+        })
+    }
+
+    /// Fork a child scope and render it for the sub-agent.
+    async fn handle_fork_scope(&self, cwd: &Path, scope_id: &str, focus: &[PathBuf]) -> Response {
+        let scope = match self.context_manager.fork_scope(cwd, scope_id, focus).await {
+            Ok(scope) => scope,
+            Err(
+                e @ (ContextError::ScopeNotFound(_)
+                | ContextError::NodeNotFound(_)
+                | ContextError::FocusOverlap { .. }),
+            ) => return Response::error(ErrorCode::InvalidRequest, e.to_string()),
+            Err(e) => return Response::failure(&e),
+        };
+        let tree = match self.project_manager.get_tree(cwd).await {
+            Ok(tree) => tree,
+            Err(e) => return Response::failure(&e),
+        };
+
+        let max_content_size = self
+            .project_manager
+            .get_project(cwd)
+            .await
+            .ok()
+            .and_then(|project| project.config.context.max_content_size);
+        let context = match max_content_size {
+            Some(size) => ContextRenderer::with_max_size(size).render(&scope, &tree),
+            None => self.context_renderer.render(&scope, &tree),
+        };
+        Response::ok_with(ResponseData::Context {
+            context,
+            nodes: scope
+                .focus_nodes()
+                .iter()
+                .map(|id| id.to_string())
+                .collect(),
+            scope_id: scope.id,
+        })
+    }
+
+    /// Render a scope as sections with the node ids they came from.
+    fn render_sections(
+        &self,
+        scope: &ContextScope,
+        tree: &Tree,
+        max_content_size: Option<usize>,
+    ) -> Vec<ContextSection> {
+        let rendered = match max_content_size {
+            Some(size) => ContextRenderer::with_max_size(size).render_sections(scope, tree),
+            None => self.context_renderer.render_sections(scope, tree),
+        };
+        rendered
+            .sections
+            .into_iter()
+            .map(|section| ContextSection {
+                title: section.title,
+                source_nodes: section
+                    .source_nodes
+                    .iter()
+                    .map(|id| id.to_string())
+                    .collect(),
+                tokens: section.tokens,
+                text: section.text,
+            })
+            .collect()
+    }
+
+    /// Return a shallow skeleton for an uninitialized project and run the
+    /// full init in the background.
+    async fn handle_cold_start(&self, cwd: &Path) -> Response {
+        if !cwd.is_dir() {
+            return Response::error(
+                ErrorCode::NotInitialized,
+                "Project not initialized. Run /init-project first.",
+            );
+        }
+
+        let root = cwd.to_path_buf();
+        let compress_dirs = self.skeleton_options.compress_dirs;
+        let skeleton = match tokio::task::spawn_blocking(move || {
+            quick_skeleton(
+                &root,
+                COLD_START_DEPTH,
+                COLD_START_MAX_ENTRIES,
+                compress_dirs,
+            )
+        })
+        .await
+        {
+            Ok(skeleton) => skeleton,
+            Err(e) => return Response::error(ErrorCode::InternalError, e.to_string()),
+        };
+
+        let project_manager = self.project_manager.clone();
+        let cwd_owned = cwd.to_path_buf();
+        tokio::spawn(async move {
+            // A concurrent cold start may have initialized it already.
+            if let Err(e) = project_manager.init_project(&cwd_owned).await {
+                tracing::debug!(cwd = ?cwd_owned, error = %e, "Cold-start init skipped");
             }
+            if let Err(e) = project_manager.scan_project(&cwd_owned).await {
+                tracing::warn!(cwd = ?cwd_owned, error = %e, "Cold-start scan failed");
+            }
+        });
 
-            Request::InitProject { cwd, async_mode: _ } => {
-                match self.project_manager.init_project(&cwd).await {
-                    Ok(project) => {
-                        tracing::info!(
-                            project = ?project.path,
-                            "Project initialized"
-                        );
-                        Response::ok()
-                    }
-                    Err(e) => {
-                        tracing::warn!(error = %e, "Failed to init project");
-                        Response::error(ErrorCode::InternalError, e.to_string())
-                    }
-                }
+        let context = format!(
+            "# PROJECT CONTEXT\n\n_(Project is being indexed; showing top-level structure only.)_\n\n## Project Structure (overview)\n\n```\n{}```\n",
+            skeleton
+        );
+        Response::ok_with(ResponseData::Context {
+            context,
+            nodes: vec![],
+            scope_id: String::new(),
+        })
+    }
+
+    /// Create the project manifest and scan the project, re-reading only
+    /// changed files if it was indexed before.
+    ///
+    /// In async mode the scan runs in the background and the request is
+    /// acknowledged immediately; otherwise the scan report is returned.
+    async fn handle_init_project(
+        &self,
+        cwd: &Path,
+        async_mode: bool,
+        artifact_allowlist: Vec<PathBuf>,
+        cancel: &CancelToken,
+    ) -> Response {
+        let project = match self.project_manager.init_project(cwd).await {
+            Ok(project) => project,
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to init project");
+                return Response::failure(&e);
             }
+        };
+        tracing::info!(project = ?project.path, "Project initialized");
 
-            Request::GetContext { cwd, prompt: _ } => {
-                // Check if project is initialized
-                if !self.project_manager.is_initialized(&cwd).await {
-                    return Response::error(
-                        ErrorCode::NotInitialized,
-                        "Project not initialized. Run /init-project first.",
+        if let Err(e) = self
+            .project_manager
+            .extend_artifact_allowlist(cwd, artifact_allowlist)
+            .await
+        {
+            tracing::warn!(error = %e, "Failed to store artifact allowlist");
+        }
+
+        if async_mode {
+            let project_manager = self.project_manager.clone();
+            let context_manager = self.context_manager.clone();
+            let enricher = self.enricher();
+            let cwd = cwd.to_path_buf();
+            tokio::spawn(async move {
+                if let Err(e) = project_manager.index_project(&cwd).await {
+                    tracing::warn!(cwd = ?cwd, error = %e, "Background scan failed");
+                    project_manager.events().record(
+                        EventKind::Error,
+                        Some(&cwd),
+                        format!("Background scan failed: {}", e),
                     );
+                    return;
                 }
-
-                // Create a scope for the project
-                let req = ScopeRequest::new(&cwd);
-                match self.context_manager.create_scope(req).await {
-                    Ok(scope) => {
-                        // Get tree for rendering
-                        match self.project_manager.get_tree(&cwd).await {
-                            Ok(tree) => {
-                                let context = self.context_renderer.render(&scope, &tree);
-                                let nodes: Vec<String> = scope
-                                    .focus
-                                    .primary_nodes
-                                    .iter()
-                                    .map(|id| id.to_string())
-                                    .collect();
-                                Response::ok_with(ResponseData::Context { context, nodes })
-                            }
-                            Err(e) => {
-                                tracing::warn!(error = %e, "Failed to get tree");
-                                // Fall back to compact rendering without tree details
-                                Response::ok_with(ResponseData::Context {
-                                    context: format!("# Project Context\n\nProject: {}\n\n_(Tree unavailable: {})_", cwd.display(), e),
-                                    nodes: vec![],
-                                })
-                            }
+                context_manager.invalidate_tree(&cwd);
+                match enricher.enrich(&cwd).await {
+                    Ok(_) => {
+                        context_manager.invalidate_tree(&cwd);
+                        if let Err(e) = context_manager.refresh_brief(&cwd).await {
+                            tracing::warn!(cwd = ?cwd, error = %e, "Failed to refresh project brief");
                         }
                     }
+                    Err(CoreError::Cancelled) => {}
                     Err(e) => {
-                        tracing::warn!(error = %e, "Failed to create context scope");
-                        Response::error(ErrorCode::InternalError, e.to_string())
+                        tracing::warn!(cwd = ?cwd, error = %e, "Enrichment failed");
+                        project_manager.events().record(
+                            EventKind::Error,
+                            Some(&cwd),
+                            format!("Enrichment failed: {}", e),
+                        );
                     }
                 }
-            }
-
-            Request::PrepareContext { cwd, prompt: _ } => {
-                // Fire-and-forget: prepare context for next request
-                let manager = self.context_manager.clone();
-                let project_manager = self.project_manager.clone();
-                tokio::spawn(async move {
-                    if project_manager.is_initialized(&cwd).await {
-                        // Pre-create a scope to warm the cache
-                        let req = ScopeRequest::new(&cwd);
-                        if let Err(e) = manager.create_scope(req).await {
-                            tracing::debug!(cwd = ?cwd, error = %e, "Failed to prepare context");
-                        } else {
-                            tracing::debug!(cwd = ?cwd, "Context prepared");
-                        }
-                    }
-                });
+            });
+            return Response::ok();
+        }
 
-                Response::ack()
+        let summary = self
+            .project_manager
+            .index_project_cancellable(cwd, cancel)
+            .await;
+        self.context_manager.invalidate_tree(cwd);
+        match summary {
+            Ok(summary) => Response::ok_with(ResponseData::ScanReport {
+                file_count: summary.file_count,
+                auto_excluded: summary
+                    .excluded_dirs
+                    .into_iter()
+                    .map(|dir| ExcludedDirInfo {
+                        path: dir.path,
+                        reason: dir.reason,
+                    })
+                    .collect(),
+            }),
+            Err(CoreError::Cancelled) => cancelled_response(cancel),
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to scan project");
+                Response::failure(&e)
             }
+        }
+    }
 
-            Request::NotifyFileChange {
-                cwd,
-                path,
-                change_type,
-            } => {
-                // Fire-and-forget: handle file change
-                tracing::debug!(
-                    cwd = ?cwd,
-                    path = ?path,
-                    change = ?change_type,
-                    "File change notification"
-                );
+    /// Report the phase of the indexing or enrichment job running for the
+    /// project containing `cwd`, indexing first.
+    fn handle_get_init_progress(&self, cwd: &Path) -> Response {
+        let job = self
+            .project_manager
+            .jobs()
+            .list()
+            .into_iter()
+            .filter(|job| cwd.starts_with(&job.project))
+            .min_by_key(|job| job.kind != JobKind::Indexing);
+        Response::ok_with(match job {
+            Some(job) => ResponseData::InitProgress {
+                phase: Some(job.phase.name().to_string()),
+                processed: job.processed,
+                total: job.total,
+                elapsed_secs: job.elapsed_secs,
+                eta_secs: job.eta_secs,
+            },
+            None => ResponseData::InitProgress {
+                phase: None,
+                processed: 0,
+                total: 0,
+                elapsed_secs: 0,
+                eta_secs: None,
+            },
+        })
+    }
 
-                // TODO: Phase 2 - Trigger incremental re-indexing
-                Response::ack()
+    /// Move staged memory entries into the memory store, or drop them.
+    async fn handle_approve_pending(&self, cwd: &Path, ids: &[String], reject: bool) -> Response {
+        let entries = match self.pending_memory.take(cwd, ids).await {
+            Ok(entries) => entries,
+            Err(e) => return Response::failure(&e),
+        };
+
+        let mut resolved = Vec::with_capacity(entries.len());
+        let mut remaining = entries.into_iter();
+        while let Some(entry) = remaining.next() {
+            let id = entry.id.clone();
+            if !reject {
+                if let Err(e) = self.project_memory(cwd).await.put(cwd, entry.clone()).await {
+                    tracing::warn!(error = %e, cwd = ?cwd, "Failed to persist approved memory entry");
+                    // Keep unresolved entries staged so approval can be retried
+                    for entry in std::iter::once(entry).chain(remaining) {
+                        let _ = self.pending_memory.stage(cwd, entry).await;
+                    }
+                    return Response::failure(&e);
+                }
             }
+            resolved.push(id);
+        }
 
-            Request::GraftExperience { cwd, experience } => {
-                // Convert IPC experience to context experience
-                let mut ctx_experience =
-                    engram_context::Experience::new(&experience.agent_id, &experience.decision)
-                        .with_files(experience.files_touched);
+        if !reject && !resolved.is_empty() {
+            self.context_manager.invalidate_context(cwd);
+        }
+        tracing::info!(cwd = ?cwd, count = resolved.len(), reject, "Resolved pending memory");
+        Response::ok_with(ResponseData::PendingResolved { ids: resolved })
+    }
 
-                // Conditionally add rationale
-                if let Some(rationale) = &experience.rationale {
-                    ctx_experience = ctx_experience.with_rationale(rationale);
-                }
+    /// Start or reopen an agent session.
+    async fn handle_session_start(
+        &self,
+        cwd: &Path,
+        session_id: Option<String>,
+        agent: Option<String>,
+        model: Option<String>,
+        metadata: BTreeMap<String, String>,
+    ) -> Response {
+        if !self.project_manager.is_initialized(cwd).await {
+            return Response::error(
+                ErrorCode::NotInitialized,
+                "Project not initialized. Run /init-project first.",
+            );
+        }
 
-                // Fire-and-forget: graft experience
-                let manager = self.context_manager.clone();
-                let cwd_clone = cwd.clone();
-                tokio::spawn(async move {
-                    if let Err(e) = manager.graft_experience(&cwd_clone, ctx_experience).await {
-                        tracing::warn!(
-                            cwd = ?cwd_clone,
-                            error = %e,
-                            "Failed to graft experience"
-                        );
-                    } else {
-                        tracing::debug!(cwd = ?cwd_clone, "Experience grafted");
-                    }
-                });
+        let id = session_id
+            .filter(|id| !id.trim().is_empty())
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+        let session = Session::new(id)
+            .with_agent(agent, model)
+            .with_metadata(metadata);
+        let session = match self.sessions.start(cwd, session).await {
+            Ok(session) => session,
+            Err(e) => return Response::failure(&e),
+        };
+        tracing::info!(cwd = ?cwd, session = %session.id, "Session started");
+        match self.session_records(cwd).await {
+            Ok((experiences, memory)) => Response::ok_with(ResponseData::Session {
+                session: session_info(&session, &experiences, &memory),
+            }),
+            Err(response) => response,
+        }
+    }
 
-                Response::ack()
+    /// End an agent session, summarizing it unless a summary is given, and
+    /// release its scopes.
+    async fn handle_session_end(
+        &self,
+        cwd: &Path,
+        session_id: &str,
+        summary: Option<String>,
+    ) -> Response {
+        let session = match self.sessions.get(cwd, session_id).await {
+            Ok(Some(session)) => session,
+            Ok(None) => {
+                return Response::error(
+                    ErrorCode::InvalidRequest,
+                    format!("Session not found: {}", session_id),
+                )
             }
+            Err(e) => return Response::failure(&e),
+        };
+        let (experiences, memory) = match self.session_records(cwd).await {
+            Ok(records) => records,
+            Err(response) => return response,
+        };
 
-            Request::MemoryPut { cwd, entry } => {
-                if !self.project_manager.is_initialized(&cwd).await {
-                    return Response::error(
-                        ErrorCode::NotInitialized,
-                        "Project not initialized. Run /init-project first.",
-                    );
-                }
+        let summary = summary
+            .map(|summary| summary.trim().to_string())
+            .filter(|summary| !summary.is_empty())
+            .or_else(|| {
+                let (experiences, memory) = in_session(&session.id, &experiences, &memory);
+                summarize_session(&experiences, &memory)
+            });
+        let session = match self.sessions.end(cwd, session_id, summary).await {
+            Ok(Some(session)) => session,
+            Ok(None) => {
+                return Response::error(
+                    ErrorCode::InvalidRequest,
+                    format!("Session not found: {}", session_id),
+                )
+            }
+            Err(e) => return Response::failure(&e),
+        };
+        for scope_id in &session.scope_ids {
+            self.context_manager.release_scope(cwd, scope_id).await;
+        }
+        // The next anchor summarizes this session
+        self.context_manager.invalidate_context(cwd);
 
-                if entry.kind.trim().is_empty() || entry.content.trim().is_empty() {
-                    return Response::error(
-                        ErrorCode::InvalidRequest,
-                        "Memory entry requires non-empty kind and content",
-                    );
-                }
+        tracing::info!(cwd = ?cwd, session = %session.id, "Session ended");
+        Response::ok_with(ResponseData::Session {
+            session: session_info(&session, &experiences, &memory),
+        })
+    }
 
-                let now = chrono::Utc::now().timestamp();
-                let id = if entry.id.trim().is_empty() {
-                    Uuid::new_v4().to_string()
-                } else {
-                    entry.id
-                };
+    /// List a project's sessions, most recently started first.
+    async fn handle_list_sessions(&self, cwd: &Path, limit: usize) -> Response {
+        let mut sessions = match self.sessions.list(cwd).await {
+            Ok(sessions) => sessions,
+            Err(e) => return Response::failure(&e),
+        };
+        sessions.truncate(limit);
+        match self.session_records(cwd).await {
+            Ok((experiences, memory)) => Response::ok_with(ResponseData::Sessions {
+                sessions: sessions
+                    .iter()
+                    .map(|session| session_info(session, &experiences, &memory))
+                    .collect(),
+            }),
+            Err(response) => response,
+        }
+    }
 
-                let stored_entry = engram_ipc::MemoryEntry {
-                    id: id.clone(),
-                    kind: entry.kind,
-                    content: entry.content,
-                    tags: entry.tags,
-                    created_at: if entry.created_at > 0 {
-                        entry.created_at
-                    } else {
-                        now
-                    },
-                    updated_at: now,
-                    session_id: entry.session_id,
-                    subagent_id: entry.subagent_id,
-                    deleted: entry.deleted,
-                };
-
-                match self.memory_store.put(&cwd, stored_entry).await {
-                    Ok(_) => Response::ok_with(ResponseData::MemoryAck { id }),
-                    Err(e) => {
-                        tracing::warn!(error = %e, cwd = ?cwd, "Failed to persist memory entry");
-                        Response::error(ErrorCode::InternalError, e.to_string())
-                    }
-                }
+    /// A session with the experiences and memory entries recorded in it.
+    async fn handle_get_session(&self, cwd: &Path, session_id: &str) -> Response {
+        let session = match self.sessions.get(cwd, session_id).await {
+            Ok(Some(session)) => session,
+            Ok(None) => {
+                return Response::error(
+                    ErrorCode::InvalidRequest,
+                    format!("Session not found: {}", session_id),
+                )
             }
+            Err(e) => return Response::failure(&e),
+        };
+        let (experiences, memory) = match self.session_records(cwd).await {
+            Ok(records) => records,
+            Err(response) => return response,
+        };
 
-            Request::MemoryPatch { cwd, id, patch } => {
-                if !self.project_manager.is_initialized(&cwd).await {
-                    return Response::error(
-                        ErrorCode::NotInitialized,
-                        "Project not initialized. Run /init-project first.",
-                    );
-                }
-
-                if id.trim().is_empty() {
-                    return Response::error(
-                        ErrorCode::InvalidRequest,
-                        "Memory patch requires a non-empty id",
-                    );
-                }
+        let (mut session_experiences, mut session_memory) =
+            in_session(&session.id, &experiences, &memory);
+        session_experiences.sort_by_key(|experience| experience.timestamp);
+        session_memory.sort_by_key(|entry| entry.created_at);
+        Response::ok_with(ResponseData::SessionDetail {
+            session: session_info(&session, &experiences, &memory),
+            experiences: session_experiences.iter().map(ipc_experience).collect(),
+            memory: session_memory,
+        })
+    }
 
-                if patch.kind.is_none()
-                    && patch.content.is_none()
-                    && patch.tags.is_none()
-                    && patch.session_id.is_none()
-                    && patch.subagent_id.is_none()
-                    && patch.deleted.is_none()
-                    && patch.updated_at.is_none()
-                {
-                    return Response::error(
-                        ErrorCode::InvalidRequest,
-                        "Memory patch requires at least one field update",
-                    );
-                }
+    /// All experiences and live memory entries of a project, to be grouped
+    /// by session.
+    async fn session_records(
+        &self,
+        cwd: &Path,
+    ) -> Result<
+        (
+            Vec<engram_context::Experience>,
+            Vec<engram_ipc::MemoryEntry>,
+        ),
+        Response,
+    > {
+        let experiences = self
+            .storage
+            .load_all_experiences(cwd)
+            .await
+            .map_err(|e| Response::failure(&ContextError::from(e)))?;
+        let mut memory = self
+            .project_memory(cwd)
+            .await
+            .export(cwd)
+            .await
+            .map_err(|e| Response::failure(&e))?;
+        memory.retain(|entry| !entry.deleted);
+        Ok((experiences, memory))
+    }
 
-                if patch
-                    .kind
-                    .as_ref()
-                    .is_some_and(|kind| kind.trim().is_empty())
-                {
-                    return Response::error(
-                        ErrorCode::InvalidRequest,
-                        "Memory patch kind cannot be empty",
-                    );
-                }
+    /// Remove matching paths from the stored tree and drop cached context.
+    async fn handle_unload_project(&self, cwd: &Path) -> Response {
+        if !self.project_manager.is_initialized(cwd).await {
+            return Response::error(
+                ErrorCode::NotInitialized,
+                "Project not initialized. Run /init-project first.",
+            );
+        }
 
-                if patch
-                    .content
-                    .as_ref()
-                    .is_some_and(|content| content.trim().is_empty())
-                {
-                    return Response::error(
-                        ErrorCode::InvalidRequest,
-                        "Memory patch content cannot be empty",
-                    );
-                }
+        // Memory writes finish before the project leaves the cache
+        let memory_loaded = self.memory_store.unload(cwd).await;
+        self.pending_memory.unload(cwd).await;
+        self.sessions.unload(cwd).await;
+        self.relevance.unload(cwd).await;
+        self.context_manager.invalidate_tree(cwd);
+        self.render_cache.invalidate(cwd);
 
-                match self.memory_store.patch(&cwd, &id, patch).await {
-                    Ok(Some(_)) => Response::ok_with(ResponseData::MemoryAck { id }),
-                    Ok(None) => Response::error(
-                        ErrorCode::InvalidRequest,
-                        format!("Memory entry not found: {}", id),
-                    ),
-                    Err(e) => {
-                        tracing::warn!(error = %e, cwd = ?cwd, "Failed to patch memory entry");
-                        Response::error(ErrorCode::InternalError, e.to_string())
-                    }
-                }
+        match self.project_manager.unload_project(cwd).await {
+            Ok(project_loaded) => Response::ok_with(ResponseData::Unloaded {
+                was_loaded: project_loaded || memory_loaded,
+            }),
+            Err(e) => {
+                tracing::warn!(error = %e, cwd = ?cwd, "Failed to unload project");
+                Response::failure(&e)
             }
+        }
+    }
 
-            Request::MemoryDelete { cwd, id } => {
-                if !self.project_manager.is_initialized(&cwd).await {
-                    return Response::error(
-                        ErrorCode::NotInitialized,
-                        "Project not initialized. Run /init-project first.",
-                    );
-                }
+    /// Relink a moved project to its current root, dropping state cached
+    /// under its previous identity first.
+    async fn handle_relink_project(&self, cwd: &Path, from: Option<&Path>) -> Response {
+        self.memory_store.unload(cwd).await;
+        self.pending_memory.unload(cwd).await;
+        self.sessions.unload(cwd).await;
+        self.relevance.unload(cwd).await;
+        self.context_manager.invalidate_tree(cwd);
+        self.render_cache.invalidate(cwd);
 
-                if id.trim().is_empty() {
-                    return Response::error(
-                        ErrorCode::InvalidRequest,
-                        "Memory delete requires a non-empty id",
-                    );
-                }
+        match self.project_manager.relink(cwd, from).await {
+            Ok(report) => Response::ok_with(ResponseData::ProjectRelinked {
+                id: report.id,
+                previous_path: report.previous_path,
+                adopted: report.adopted,
+            }),
+            Err(e) => {
+                tracing::warn!(error = %e, cwd = ?cwd, "Failed to relink project");
+                Response::failure(&e)
+            }
+        }
+    }
 
-                match self.memory_store.delete(&cwd, &id, None).await {
-                    Ok(Some(_)) => Response::ok_with(ResponseData::MemoryAck { id }),
-                    Ok(None) => Response::error(
-                        ErrorCode::InvalidRequest,
-                        format!("Memory entry not found: {}", id),
-                    ),
-                    Err(e) => {
-                        tracing::warn!(error = %e, cwd = ?cwd, "Failed to delete memory entry");
-                        Response::error(ErrorCode::InternalError, e.to_string())
-                    }
-                }
+    /// Write a project's stored data to an archive on the daemon's machine.
+    async fn handle_export_project(&self, cwd: &Path, output: &Path) -> Response {
+        if !self.project_manager.is_initialized(cwd).await {
+            return Response::error(
+                ErrorCode::NotInitialized,
+                "Project not initialized. Run /init-project first.",
+            );
+        }
+
+        // Pending memory writes land in the log before it is read
+        self.memory_store.unload(cwd).await;
+        match self.project_manager.export_archive(cwd, output).await {
+            Ok(report) => Response::ok_with(ResponseData::ProjectArchive {
+                path: output.to_path_buf(),
+                files: report.files,
+                bytes: report.bytes,
+                source_path: report.source_path,
+            }),
+            Err(e) => {
+                tracing::warn!(error = %e, cwd = ?cwd, "Failed to export project");
+                Response::failure(&e)
             }
+        }
+    }
 
-            Request::MemoryGet { cwd, id } => {
-                if !self.project_manager.is_initialized(&cwd).await {
-                    return Response::error(
-                        ErrorCode::NotInitialized,
-                        "Project not initialized. Run /init-project first.",
-                    );
-                }
+    /// Replace a project's stored data with an archive, dropping every
+    /// state cached from the data it replaces.
+    async fn handle_import_project(&self, cwd: &Path, archive: &Path) -> Response {
+        self.memory_store.unload(cwd).await;
+        self.pending_memory.unload(cwd).await;
+        self.sessions.unload(cwd).await;
+        self.relevance.unload(cwd).await;
 
-                match self.memory_store.get(&cwd, &id).await {
-                    Ok(Some(entry)) => Response::ok_with(ResponseData::MemoryEntry { entry }),
-                    Ok(None) => Response::error(
-                        ErrorCode::InvalidRequest,
-                        format!("Memory entry not found: {}", id),
-                    ),
-                    Err(e) => {
-                        tracing::warn!(error = %e, cwd = ?cwd, "Failed to load memories");
-                        Response::error(ErrorCode::InternalError, e.to_string())
-                    }
-                }
+        let result = self.project_manager.import_archive(cwd, archive).await;
+        self.context_manager.invalidate_tree(cwd);
+        self.render_cache.invalidate(cwd);
+        match result {
+            Ok(report) => Response::ok_with(ResponseData::ProjectArchive {
+                path: archive.to_path_buf(),
+                files: report.files,
+                bytes: report.bytes,
+                source_path: report.source_path,
+            }),
+            Err(e) => {
+                tracing::warn!(error = %e, cwd = ?cwd, "Failed to import project");
+                Response::failure(&e)
             }
+        }
+    }
 
-            Request::MemoryList { cwd, limit } => {
-                if !self.project_manager.is_initialized(&cwd).await {
-                    return Response::error(
-                        ErrorCode::NotInitialized,
-                        "Project not initialized. Run /init-project first.",
-                    );
-                }
+    /// Describe every loaded project, sorted by path.
+    async fn handle_list_projects(&self) -> Response {
+        let scopes = self.context_manager.active_scopes_by_project();
+        let caches: HashMap<PathBuf, (u64, u64)> = self
+            .render_cache
+            .stats()
+            .into_iter()
+            .map(|s| (s.project, (s.hits, s.misses)))
+            .collect();
 
-                match self.memory_store.list(&cwd, limit).await {
-                    Ok(entries) => Response::ok_with(ResponseData::MemoryEntries { entries }),
-                    Err(e) => {
-                        tracing::warn!(error = %e, cwd = ?cwd, "Failed to list memories");
-                        Response::error(ErrorCode::InternalError, e.to_string())
-                    }
+        let mut projects = Vec::new();
+        for project in self.project_manager.loaded_projects().await {
+            let memory_entries = match self
+                .memory_store
+                .list(&project.path, &MemoryQuery::default(), usize::MAX)
+                .await
+            {
+                Ok(entries) => entries.len(),
+                Err(e) => {
+                    tracing::debug!(error = %e, cwd = ?project.path, "Failed to list memories");
+                    0
                 }
-            }
+            };
+            let (hits, misses) = caches.get(&project.path).copied().unwrap_or_default();
+            projects.push(LoadedProjectInfo {
+                path: project.path.clone(),
+                name: project.manifest.name.clone(),
+                file_count: project.manifest.file_count,
+                last_index: project.manifest.last_scan.map(|t| t.timestamp()),
+                scopes_active: scopes.get(&project.path).copied().unwrap_or(0),
+                memory_entries,
+                cache_hit_rate: if hits + misses == 0 {
+                    0.0
+                } else {
+                    hits as f64 / (hits + misses) as f64
+                },
+                pinned: project.pinned,
+                read_only: project.read_only,
+            });
+        }
+        projects.sort_by(|a, b| a.path.cmp(&b.path));
 
-            Request::MemorySync { cwd } => {
-                if !self.project_manager.is_initialized(&cwd).await {
+        Response::ok_with(ResponseData::Projects { projects })
+    }
+
+    async fn handle_encrypt_logs(&self) -> Response {
+        if !self.storage.encrypts_experiences() {
+            return Response::error(
+                ErrorCode::InvalidRequest,
+                "No experience log key is configured; set encryption.key_file in the daemon config",
+            );
+        }
+        let hashes = match self.storage.stored_projects().await {
+            Ok(hashes) => hashes,
+            Err(e) => return Response::error(ErrorCode::InternalError, e.to_string()),
+        };
+
+        let (mut projects, mut lines) = (0, 0);
+        for hash in &hashes {
+            match self.storage.encrypt_experiences(hash).await {
+                Ok(0) => {}
+                Ok(encrypted) => {
+                    projects += 1;
+                    lines += encrypted;
+                }
+                Err(e) => {
                     return Response::error(
-                        ErrorCode::NotInitialized,
-                        "Project not initialized. Run /init-project first.",
-                    );
+                        ErrorCode::InternalError,
+                        format!("project {}: {}", hash, e),
+                    )
                 }
+            }
+        }
+        Response::ok_with(ResponseData::LogsEncrypted { projects, lines })
+    }
 
-                match self.memory_store.sync(&cwd).await {
-                    Ok(_) => Response::ok(),
-                    Err(e) => {
-                        tracing::warn!(error = %e, cwd = ?cwd, "Failed to sync memories");
-                        Response::error(ErrorCode::InternalError, e.to_string())
-                    }
+    async fn handle_prune_tree(&self, cwd: &Path, globs: &[String]) -> Response {
+        if !self.project_manager.is_initialized(cwd).await {
+            return Response::error(
+                ErrorCode::NotInitialized,
+                "Project not initialized. Run /init-project first.",
+            );
+        }
+        if globs.is_empty() {
+            return Response::error(ErrorCode::InvalidRequest, "No globs given");
+        }
+
+        match self.project_manager.prune_tree(cwd, globs).await {
+            Ok(report) => {
+                if report.removed_nodes() > 0 {
+                    self.context_manager.invalidate_tree(cwd);
+                    self.render_cache.invalidate(cwd);
                 }
+                Response::ok_with(ResponseData::PruneReport {
+                    removed_dirs: report.removed_dirs,
+                    removed_files: report.removed_files,
+                    removed_symbols: report.removed_symbols,
+                    removed_edges: report.removed_edges,
+                })
             }
-
-            Request::Shutdown => {
-                tracing::info!("Shutdown requested");
-                let _ = self.shutdown_tx.send(());
-                Response::ack()
+            Err(CoreError::InvalidGlob(message)) => Response::error(
+                ErrorCode::InvalidRequest,
+                format!("Invalid glob: {}", message),
+            ),
+            Err(e) => {
+                tracing::warn!(error = %e, cwd = ?cwd, "Failed to prune tree");
+                Response::failure(&e)
             }
         }
     }
-}
 
-/// Get current memory usage in bytes
-fn get_memory_usage() -> usize {
-    // On macOS, we can use rusage
-    #[cfg(unix)]
-    {
-        let mut rusage = std::mem::MaybeUninit::uninit();
-        unsafe {
-            if libc::getrusage(libc::RUSAGE_SELF, rusage.as_mut_ptr()) == 0 {
-                let rusage = rusage.assume_init();
-                // maxrss is in bytes on macOS, kilobytes on Linux
-                #[cfg(target_os = "macos")]
-                return rusage.ru_maxrss as usize;
-                #[cfg(not(target_os = "macos"))]
-                return (rusage.ru_maxrss * 1024) as usize;
-            }
+    /// Run a query through the hybrid router and return ranked hits.
+    async fn handle_search(
+        &self,
+        cwd: &Path,
+        query: &str,
+        (limit, explain): (usize, bool),
+        usage: &mut ResourceUsage,
+    ) -> Response {
+        if !self.project_manager.is_initialized(cwd).await {
+            return Response::error(
+                ErrorCode::NotInitialized,
+                "Project not initialized. Run /init-project first.",
+            );
+        }
+        if query.trim().is_empty() {
+            return Response::error(ErrorCode::InvalidRequest, "Empty query");
         }
-    }
 
-    0
-}
+        let tree = match self.project_manager.get_tree(cwd).await {
+            Ok(tree) => tree,
+            Err(e) => return Response::failure(&e),
+        };
+        usage.add_nodes(tree.nodes.len());
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use engram_core::DaemonConfig;
-    use engram_ipc::{MemoryEntry, MemoryPatch};
-    use std::collections::HashSet;
-    use std::path::PathBuf;
-    use tempfile::tempdir;
+        let calibration = self.calibration.load(cwd).await.unwrap_or_default();
+        let relevance = self.relevance.load(cwd).await.unwrap_or_default();
+        let vectors = self.vectors.load_or_build(cwd, &tree).await;
+        let mut scope = ContextScope::new(cwd.to_path_buf());
+        scope.horizon.hot_nodes = relevance
+            .hot_files(HOT_FILES)
+            .into_iter()
+            .filter_map(|path| tree.find_node_by_path(path))
+            .collect();
+        let tree = Arc::new(tree);
+        let mut router = HybridRouter::new(tree.clone())
+            .with_calibration(calibration)
+            .with_relevance(relevance);
+        match vectors {
+            Ok(index) => router = router.with_vector_index(Arc::new(index)),
+            Err(e) => tracing::warn!(error = %e, cwd = ?cwd, "Searching without vector index"),
+        }
 
-    fn test_handler() -> DaemonHandler {
-        let temp_dir = tempdir().unwrap();
-        let config = DaemonConfig {
-            data_dir: temp_dir.path().to_path_buf(),
-            ..Default::default()
-        };
-        let manager = Arc::new(ProjectManager::new(&config));
-        let storage = Arc::new(Storage::new(temp_dir.path().to_path_buf()));
-        let (shutdown_tx, _) = broadcast::channel(1);
-        DaemonHandler::new(manager, storage, shutdown_tx, std::time::Instant::now())
+        let hits = router
+            .query(query, &scope)
+            .iter()
+            .filter_map(|result| search_hit(&tree, result, explain))
+            .take(limit)
+            .collect();
+
+        Response::ok_with(ResponseData::SearchResults { hits })
     }
 
-    fn extract_memory_ack(response: Response) -> String {
-        if let Response::Ok {
-            data: Some(ResponseData::MemoryAck { id }),
-        } = response
-        {
-            id
-        } else {
-            panic!("Expected MemoryAck response");
-        }
+    async fn handle_health_check(&self) -> Response {
+        let now = chrono::Utc::now().timestamp();
+        let uptime = self.start_time.elapsed();
+        let memory = MemoryMonitor::new(self.config.lock().unwrap().max_memory);
+        memory.update(get_memory_usage());
+
+        let components = vec![
+            check_data_dir(self.storage.base_dir()),
+            health::check_watcher(&self.watch_heartbeat, uptime, now),
+            health::check_enrichment(&self.enricher()).await,
+            health::check_memory(&memory),
+        ];
+        Response::ok_with(ResponseData::Health {
+            status: health::overall(&components),
+            timestamp: now,
+            uptime_secs: uptime.as_secs(),
+            components,
+        })
     }
 
-    fn extract_memory_entry(response: Response) -> MemoryEntry {
-        if let Response::Ok {
-            data: Some(ResponseData::MemoryEntry { entry }),
-        } = response
-        {
-            entry
-        } else {
-            panic!("Expected MemoryEntry response");
+    async fn handle_find_files(
+        &self,
+        cwd: &Path,
+        pattern: &str,
+        limit: usize,
+        usage: &mut ResourceUsage,
+    ) -> Response {
+        if !self.project_manager.is_initialized(cwd).await {
+            return Response::error(
+                ErrorCode::NotInitialized,
+                "Project not initialized. Run /init-project first.",
+            );
         }
+        if pattern.trim().is_empty() {
+            return Response::error(ErrorCode::InvalidRequest, "Empty pattern");
+        }
+
+        let tree = match self.project_manager.get_tree(cwd).await {
+            Ok(tree) => tree,
+            Err(e) => return Response::failure(&e),
+        };
+        usage.add_nodes(tree.nodes.len());
+
+        let matches = find_files(&tree, pattern, limit)
+            .into_iter()
+            .filter_map(|m| {
+                tree.get(m.file).map(|node| FileMatch {
+                    path: node.path.clone(),
+                    score: m.score,
+                })
+            })
+            .collect();
+
+        Response::ok_with(ResponseData::FileMatches { matches })
     }
 
-    fn extract_memory_entries(response: Response) -> Vec<MemoryEntry> {
-        if let Response::Ok {
-            data: Some(ResponseData::MemoryEntries { entries }),
-        } = response
-        {
-            entries
-        } else {
-            panic!("Expected MemoryEntries response");
+    /// Run batched requests in order, linking experiences and memory
+    /// entries recorded together for the same project.
+    async fn handle_batch(
+        &self,
+        mut requests: Vec<Request>,
+        usage: &mut ResourceUsage,
+        cancel: &CancelToken,
+    ) -> Response {
+        link_batch_records(&mut requests);
+
+        let mut responses = Vec::with_capacity(requests.len());
+        for request in requests {
+            let response = if matches!(request, Request::Batch { .. }) {
+                Response::error(
+                    ErrorCode::InvalidRequest,
+                    "Nested batches are not supported",
+                )
+            } else {
+                Box::pin(self.dispatch(request, usage, cancel)).await
+            };
+            responses.push(response);
         }
+
+        Response::ok_with(ResponseData::Batch { responses })
     }
 
-    #[tokio::test]
-    async fn test_ping() {
-        let handler = test_handler();
-        let response = handler.handle(Request::Ping).await;
+    /// Project stats and a name-hashed structure export for debug bundles.
+    async fn handle_debug_snapshot(&self, cwd: &Path, usage: &mut ResourceUsage) -> Response {
+        let project = match self.project_manager.get_project(cwd).await {
+            Ok(project) => project,
+            Err(_) => {
+                return Response::ok_with(ResponseData::DebugSnapshot {
+                    project: None,
+                    structure: vec![],
+                })
+            }
+        };
 
-        if let Response::Ok {
-            data: Some(ResponseData::Pong { .. }),
-        } = response
+        let manifest = &project.manifest;
+        let summary = ProjectSummary {
+            file_count: manifest.file_count,
+            languages: manifest.languages.clone(),
+            frameworks: manifest.frameworks.clone(),
+            last_scan: manifest.last_scan.map(|t| t.timestamp()),
+            enriched: manifest.enriched,
+            auto_excluded_count: manifest.auto_excluded.len(),
+        };
+
+        let structure = match self.project_manager.get_tree(cwd).await {
+            Ok(tree) => {
+                usage.add_nodes(tree.nodes.len());
+                let salt = Uuid::new_v4().to_string();
+                anonymize_tree(&tree, &salt)
+                    .into_iter()
+                    .map(|node| StructureNode {
+                        id: node.id,
+                        parent: node.parent,
+                        kind: node.kind,
+                        name: node.name,
+                        language: node.language,
+                        size: node.size,
+                        line_count: node.line_count,
+                        symbol_count: node.symbol_count,
+                    })
+                    .collect()
+            }
+            Err(e) => {
+                tracing::debug!(error = %e, "No tree for debug snapshot");
+                vec![]
+            }
+        };
+
+        Response::ok_with(ResponseData::DebugSnapshot {
+            project: Some(summary),
+            structure,
+        })
+    }
+
+    /// Markdown documentation for `engram export docs`, from the enriched
+    /// tree when there is one.
+    async fn handle_export_docs(
+        &self,
+        cwd: &Path,
+        mdbook: bool,
+        usage: &mut ResourceUsage,
+    ) -> Response {
+        if !self.project_manager.is_initialized(cwd).await {
+            return Response::error(
+                ErrorCode::NotInitialized,
+                "Project not initialized. Run /init-project first.",
+            );
+        }
+
+        let project = match self.project_manager.get_project(cwd).await {
+            Ok(project) => project,
+            Err(e) => return Response::failure(&e),
+        };
+        let tree = match self.project_manager.get_enriched_tree(cwd).await {
+            Ok(tree) => tree,
+            Err(e) => return Response::failure(&e),
+        };
+        usage.add_nodes(tree.nodes.len());
+
+        let format = if mdbook {
+            DocsFormat::MdBook
+        } else {
+            DocsFormat::Markdown
+        };
+        let files: Vec<DocFile> = render_docs(&tree, format)
+            .into_iter()
+            .map(|page| DocFile {
+                path: page.path,
+                content: page.content,
+            })
+            .collect();
+        usage.add_alloc(files.iter().map(|file| file.content.len()).sum());
+        Response::ok_with(ResponseData::Docs {
+            files,
+            enriched: project.manifest.enriched,
+        })
+    }
+
+    /// Index statistics for `engram stats`.
+    async fn handle_project_stats(
+        &self,
+        cwd: &Path,
+        top: usize,
+        usage: &mut ResourceUsage,
+    ) -> Response {
+        if !self.project_manager.is_initialized(cwd).await {
+            return Response::error(
+                ErrorCode::NotInitialized,
+                "Project not initialized. Run /init-project first.",
+            );
+        }
+
+        let project = match self.project_manager.get_project(cwd).await {
+            Ok(project) => project,
+            Err(e) => return Response::failure(&e),
+        };
+        let tree = match self.project_manager.get_tree(cwd).await {
+            Ok(tree) => tree,
+            Err(e) => return Response::failure(&e),
+        };
+        usage.add_nodes(tree.nodes.len());
+        let stats = tree_stats(&tree, top);
+
+        let mut index_files = Vec::new();
+        collect_index_files(&project.storage_dir, &project.storage_dir, &mut index_files);
+        index_files.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+        let index_bytes = index_files.iter().map(|f| f.size).sum();
+
+        let file_counts = |files: Vec<(PathBuf, usize)>| {
+            files
+                .into_iter()
+                .map(|(path, count)| FileCount { path, count })
+                .collect()
+        };
+        let manifest = &project.manifest;
+        Response::ok_with(ResponseData::ProjectStats {
+            files: stats.files,
+            symbols: stats.symbols,
+            lines: stats.lines,
+            bytes: stats.bytes,
+            dependency_edges: stats.dependency_edges,
+            languages: stats
+                .files_by_language
+                .into_iter()
+                .map(|(name, count)| NamedCount { name, count })
+                .collect(),
+            assets: stats
+                .assets_by_kind
+                .into_iter()
+                .map(|(kind, count, bytes)| AssetCount {
+                    kind: kind.name().to_string(),
+                    count,
+                    bytes,
+                })
+                .collect(),
+            symbol_kinds: stats
+                .symbols_by_kind
+                .into_iter()
+                .map(|(kind, count)| NamedCount {
+                    name: symbol_kind_name(&kind),
+                    count,
+                })
+                .collect(),
+            largest_files: stats
+                .largest_files
+                .into_iter()
+                .map(|(path, size, line_count)| FileSize {
+                    path,
+                    size,
+                    line_count,
+                })
+                .collect(),
+            most_imported: file_counts(stats.most_imported),
+            most_importing: file_counts(stats.most_importing),
+            index_files,
+            index_bytes,
+            created_at: manifest.created_at.timestamp(),
+            last_scan: manifest.last_scan.map(|t| t.timestamp()),
+            enriched: manifest.enriched,
+        })
+    }
+
+    /// Build gutter hints for one file: symbols, memories, last experience.
+    /// Report import cycles, a build order, or what depends on a file.
+    async fn handle_analyze_dependencies(
+        &self,
+        cwd: &Path,
+        (cycles, order): (bool, bool),
+        dependents_of: Option<&Path>,
+        depth: Option<usize>,
+        usage: &mut ResourceUsage,
+    ) -> Response {
+        if !self.project_manager.is_initialized(cwd).await {
+            return Response::error(
+                ErrorCode::NotInitialized,
+                "Project not initialized. Run /init-project first.",
+            );
+        }
+
+        let tree = match self.project_manager.get_tree(cwd).await {
+            Ok(tree) => tree,
+            Err(e) => return Response::failure(&e),
+        };
+        usage.add_nodes(tree.nodes.len());
+        let graph = &tree.dependencies;
+        let path_of = |id| tree.get(id).map(|node| node.path.clone());
+
+        let dependents = match dependents_of {
+            Some(path) => {
+                let rel_path = path.strip_prefix(cwd).unwrap_or(path).to_path_buf();
+                let Some(node) = tree.find_node_by_path(&rel_path) else {
+                    return Response::error(
+                        ErrorCode::InvalidRequest,
+                        format!("File not indexed: {}", rel_path.display()),
+                    );
+                };
+                graph
+                    .transitive_dependents(node, depth.unwrap_or(usize::MAX))
+                    .into_iter()
+                    .filter_map(|(id, depth)| {
+                        Some(DependentFile {
+                            path: path_of(id)?,
+                            depth,
+                        })
+                    })
+                    .collect()
+            }
+            None => vec![],
+        };
+
+        let cycles = if cycles {
+            graph
+                .find_cycles()
+                .into_iter()
+                .map(|cycle| {
+                    let mut paths: Vec<PathBuf> = cycle.into_iter().filter_map(path_of).collect();
+                    paths.sort();
+                    paths
+                })
+                .collect()
+        } else {
+            vec![]
+        };
+        let order = if order {
+            graph
+                .topological_order()
+                .map(|ids| ids.into_iter().filter_map(path_of).collect())
+        } else {
+            None
+        };
+
+        Response::ok_with(ResponseData::Dependencies {
+            cycles,
+            order,
+            dependents,
+        })
+    }
+
+    /// Estimate what renaming or changing `symbol` affects.
+    async fn handle_impact_analysis(
+        &self,
+        cwd: &Path,
+        symbol: &str,
+        usage: &mut ResourceUsage,
+    ) -> Response {
+        if !self.project_manager.is_initialized(cwd).await {
+            return Response::error(
+                ErrorCode::NotInitialized,
+                "Project not initialized. Run /init-project first.",
+            );
+        }
+
+        let tree = match self.project_manager.get_tree(cwd).await {
+            Ok(tree) => tree,
+            Err(e) => return Response::failure(&e),
+        };
+        usage.add_nodes(tree.nodes.len());
+
+        let report = analyze_impact(&tree, symbol, |path| {
+            let text = std::fs::read_to_string(cwd.join(path)).ok()?;
+            usage.add_read(text.len());
+            Some(text)
+        });
+        let definitions = report
+            .definitions
+            .iter()
+            .filter_map(|id| impact_symbol(&tree, *id))
+            .collect();
+        let files = report
+            .files
+            .into_iter()
+            .filter_map(|file| {
+                Some(ImpactFile {
+                    path: tree.get(file.file)?.path.clone(),
+                    depth: file.depth,
+                    lines: file.lines,
+                    symbols: file
+                        .symbols
+                        .iter()
+                        .filter_map(|id| impact_symbol(&tree, *id))
+                        .collect(),
+                })
+            })
+            .collect();
+
+        Response::ok_with(ResponseData::Impact { definitions, files })
+    }
+
+    /// Describe a symbol with its callers, callees and related experiences.
+    async fn handle_get_symbol(
+        &self,
+        cwd: &Path,
+        path: &Path,
+        symbol: &str,
+        usage: &mut ResourceUsage,
+    ) -> Response {
+        if !self.project_manager.is_initialized(cwd).await {
+            return Response::error(
+                ErrorCode::NotInitialized,
+                "Project not initialized. Run /init-project first.",
+            );
+        }
+
+        let tree = match self.project_manager.get_tree(cwd).await {
+            Ok(tree) => tree,
+            Err(e) => return Response::failure(&e),
+        };
+        usage.add_nodes(tree.nodes.len());
+
+        let rel_path = path.strip_prefix(cwd).unwrap_or(path).to_path_buf();
+        let Some((file, id)) = tree
+            .find_node_by_path(&rel_path)
+            .zip(tree.find_symbol(&rel_path, symbol))
+        else {
+            return Response::error(
+                ErrorCode::InvalidRequest,
+                format!("Symbol not indexed: {} in {}", symbol, rel_path.display()),
+            );
+        };
+        let Some(info) = impact_symbol(&tree, id) else {
+            return Response::error(
+                ErrorCode::InternalError,
+                format!("Not a symbol node: {}", symbol),
+            );
+        };
+
+        let mut own_text = None;
+        let calls = symbol_calls(&tree, id, |path| {
+            let text = std::fs::read_to_string(cwd.join(path)).ok()?;
+            usage.add_read(text.len());
+            if path == rel_path {
+                own_text = Some(text.clone());
+            }
+            Some(text)
+        });
+        let signature = own_text
+            .as_deref()
+            .map(|text| signature_lines(text, info.line))
+            .unwrap_or_default();
+        // Symbol docs are only stored once enriched, so parse them from source
+        let doc = own_text
+            .as_deref()
+            .zip(detect_language(&cwd.join(&rel_path)))
+            .and_then(|(text, language)| Parser::new().parse(text, &language).ok())
+            .and_then(|parsed| {
+                parsed
+                    .symbols
+                    .into_iter()
+                    .find(|s| s.name == info.name && s.start_line == info.line)?
+                    .doc
+            })
+            .or_else(|| tree.content(id).and_then(|content| content.summary.clone()));
+        let file_summary = tree
+            .content(file)
+            .and_then(|content| content.summary.clone());
+        let describe = |ids: &[NodeId]| -> Vec<ImpactSymbol> {
+            ids.iter()
+                .filter_map(|id| impact_symbol(&tree, *id))
+                .collect()
+        };
+        let callers = describe(&calls.callers);
+        let callees = describe(&calls.callees);
+
+        let mut experiences: Vec<engram_context::Experience> =
+            match self.storage.load_all_experiences(cwd).await {
+                Ok(experiences) => experiences,
+                Err(e) => {
+                    tracing::debug!(error = %e, cwd = ?cwd, "Failed to load experiences");
+                    Vec::new()
+                }
+            };
+        let absolute = cwd.join(&rel_path);
+        experiences.retain(|exp| {
+            exp.files_touched
+                .iter()
+                .any(|f| *f == rel_path || *f == absolute)
+                || exp.decision.contains(&info.name)
+                || exp
+                    .rationale
+                    .as_deref()
+                    .is_some_and(|rationale| rationale.contains(&info.name))
+        });
+        experiences.sort_by_key(|exp| std::cmp::Reverse(exp.timestamp));
+        let experiences = experiences
+            .iter()
+            .take(SYMBOL_EXPERIENCES)
+            .map(ipc_experience)
+            .collect();
+
+        Response::ok_with(ResponseData::Symbol {
+            symbol: info,
+            signature,
+            doc,
+            file_summary,
+            callers,
+            callees,
+            experiences,
+        })
+    }
+
+    /// List the tests of a file, or the files a test covers.
+    async fn handle_list_tests(
+        &self,
+        cwd: &Path,
+        path: &Path,
+        usage: &mut ResourceUsage,
+    ) -> Response {
+        if !self.project_manager.is_initialized(cwd).await {
+            return Response::error(
+                ErrorCode::NotInitialized,
+                "Project not initialized. Run /init-project first.",
+            );
+        }
+
+        let tree = match self.project_manager.get_tree(cwd).await {
+            Ok(tree) => tree,
+            Err(e) => return Response::failure(&e),
+        };
+        usage.add_nodes(tree.nodes.len());
+
+        let rel_path = path.strip_prefix(cwd).unwrap_or(path).to_path_buf();
+        let Some(node) = tree.find_node_by_path(&rel_path) else {
+            return Response::error(
+                ErrorCode::InvalidRequest,
+                format!("File not indexed: {}", rel_path.display()),
+            );
+        };
+
+        let is_test = is_test_path(&rel_path);
+        let linked: Vec<NodeId> = if is_test {
+            tree.tests.sources_of(node).collect()
+        } else {
+            tree.tests.tests_of(node).collect()
+        };
+        let inline = linked.contains(&node);
+        let mut files: Vec<PathBuf> = linked
+            .into_iter()
+            .filter(|id| *id != node)
+            .filter_map(|id| tree.get(id).map(|node| node.path.clone()))
+            .collect();
+        files.sort();
+
+        Response::ok_with(ResponseData::Tests {
+            is_test,
+            inline,
+            files,
+        })
+    }
+
+    async fn handle_diff_snapshots(
+        &self,
+        cwd: &Path,
+        from: &str,
+        to: Option<&str>,
+        usage: &mut ResourceUsage,
+    ) -> Response {
+        if !self.project_manager.is_initialized(cwd).await {
+            return Response::error(
+                ErrorCode::NotInitialized,
+                "Project not initialized. Run /init-project first.",
+            );
+        }
+
+        let old = match self.project_manager.get_snapshot_tree(cwd, from).await {
+            Ok(tree) => tree,
+            Err(e) => return Response::error(ErrorCode::InvalidRequest, e.to_string()),
+        };
+        let new = match to {
+            Some(name) => self.project_manager.get_snapshot_tree(cwd, name).await,
+            None => self.project_manager.get_tree(cwd).await,
+        };
+        let new = match new {
+            Ok(tree) => tree,
+            Err(e) => return Response::error(ErrorCode::InvalidRequest, e.to_string()),
+        };
+        usage.add_nodes(old.nodes.len() + new.nodes.len());
+
+        let diff = diff_trees(&old, &new);
+        let symbols = |changes: Vec<SymbolChange>| {
+            changes
+                .into_iter()
+                .map(|change| DiffSymbol {
+                    path: change.file,
+                    name: change.name,
+                    kind: symbol_kind_name(&change.kind),
+                    line: change.line,
+                })
+                .collect()
+        };
+        Response::ok_with(ResponseData::TreeDiff {
+            added_files: diff.added_files,
+            removed_files: diff.removed_files,
+            modified_files: diff.modified_files,
+            added_symbols: symbols(diff.added_symbols),
+            removed_symbols: symbols(diff.removed_symbols),
+            modified_symbols: symbols(diff.modified_symbols),
+        })
+    }
+
+    /// Dump a project's memory entries and experiences into a bundle.
+    async fn handle_export_memory(&self, cwd: &Path) -> Response {
+        let entries = match self.project_memory(cwd).await.export(cwd).await {
+            Ok(entries) => entries,
+            Err(e) => return Response::failure(&e),
+        };
+        let experiences: Vec<engram_context::Experience> =
+            match self.storage.load_all_experiences(cwd).await {
+                Ok(experiences) => experiences,
+                Err(e) => return Response::failure(&ContextError::from(e)),
+            };
+
+        Response::ok_with(ResponseData::MemoryBundle {
+            bundle: MemoryBundle {
+                format: MEMORY_BUNDLE_FORMAT,
+                exported_at: chrono::Utc::now().timestamp(),
+                entries,
+                experiences: experiences.iter().map(ipc_experience).collect(),
+            },
+        })
+    }
+
+    /// Ingest a bundle exported from another machine.
+    async fn handle_import_memory(&self, cwd: &Path, bundle: MemoryBundle) -> Response {
+        if bundle.format > MEMORY_BUNDLE_FORMAT {
+            return Response::error(
+                ErrorCode::Unsupported,
+                format!(
+                    "Memory bundle format {} is newer than this daemon understands ({})",
+                    bundle.format, MEMORY_BUNDLE_FORMAT
+                ),
+            );
+        }
+
+        let entries = match self
+            .project_memory(cwd)
+            .await
+            .import(cwd, bundle.entries)
+            .await
+        {
+            Ok(stats) => stats,
+            Err(e @ MemoryStoreError::InvalidEntry(_)) => {
+                return Response::error(ErrorCode::InvalidRequest, e.to_string())
+            }
+            Err(e) => return Response::failure(&e),
+        };
+
+        let total = bundle.experiences.len();
+        let experiences = bundle
+            .experiences
+            .into_iter()
+            .map(|experience| {
+                let mut imported =
+                    engram_context::Experience::new(experience.agent_id, experience.decision)
+                        .with_files(experience.files_touched)
+                        .with_memory_ids(experience.memory_ids)
+                        .with_id(experience.id.unwrap_or_default());
+                imported.timestamp = experience.timestamp;
+                imported.rationale = experience.rationale;
+                imported.summarizes = experience.summarizes;
+                if let Some(session_id) = experience.session_id {
+                    imported.session_id = session_id;
+                }
+                imported
+            })
+            .collect();
+        let experiences_imported = match self
+            .context_manager
+            .import_experiences(cwd, experiences)
+            .await
+        {
+            Ok(imported) => imported,
+            Err(e) => return Response::failure(&e),
+        };
+
+        self.project_manager.events().record(
+            EventKind::MemoryImported,
+            Some(cwd),
+            format!(
+                "Imported {} memory entries and {} experiences",
+                entries.imported, experiences_imported
+            ),
+        );
+        Response::ok_with(ResponseData::MemoryImported {
+            entries_imported: entries.imported,
+            entries_skipped: entries.skipped,
+            experiences_imported,
+            experiences_skipped: total - experiences_imported,
+        })
+    }
+
+    async fn handle_line_hints(
+        &self,
+        cwd: &Path,
+        path: &Path,
+        usage: &mut ResourceUsage,
+    ) -> Response {
+        if !self.project_manager.is_initialized(cwd).await {
+            return Response::error(
+                ErrorCode::NotInitialized,
+                "Project not initialized. Run /init-project first.",
+            );
+        }
+
+        let rel_path = if path.is_absolute() {
+            match path.strip_prefix(cwd) {
+                Ok(rel) => rel.to_path_buf(),
+                Err(_) => {
+                    return Response::error(
+                        ErrorCode::InvalidRequest,
+                        format!("Path is outside the project: {}", path.display()),
+                    )
+                }
+            }
+        } else {
+            path.to_path_buf()
+        };
+        let abs_path = cwd.join(&rel_path);
+
+        let content = match tokio::fs::read_to_string(&abs_path).await {
+            Ok(content) => content,
+            Err(e) => {
+                return Response::error(
+                    ErrorCode::InvalidRequest,
+                    format!("Failed to read {}: {}", rel_path.display(), e),
+                )
+            }
+        };
+
+        usage.add_read(content.len());
+
+        let symbols = detect_language(&abs_path)
+            .and_then(|language| Parser::new().parse(&content, &language).ok())
+            .map(|parsed| parsed.symbols)
+            .unwrap_or_default();
+        usage.add_nodes(symbols.len());
+
+        let memories = match self
+            .memory_store
+            .list(cwd, &MemoryQuery::default(), usize::MAX)
+            .await
+        {
+            Ok(entries) => entries,
+            Err(e) => {
+                tracing::warn!(error = %e, cwd = ?cwd, "Failed to list memories");
+                return Response::failure(&e);
+            }
+        };
+
+        let experiences: Vec<engram_context::Experience> =
+            match self.storage.load_all_experiences(cwd).await {
+                Ok(experiences) => experiences,
+                Err(e) => {
+                    tracing::debug!(error = %e, cwd = ?cwd, "Failed to load experiences");
+                    Vec::new()
+                }
+            };
+
+        let hints = engram_context::build_line_hints(&rel_path, &symbols, &memories);
+        usage.add_alloc(hints.iter().map(|h| h.label.len()).sum());
+        let last_experience =
+            engram_context::last_experience_for(cwd, &rel_path, &experiences).map(ipc_experience);
+
+        Response::ok_with(ResponseData::LineHints {
+            path: rel_path,
+            hints,
+            last_experience,
+        })
+    }
+}
+
+impl DaemonHandler {
+    /// Route a request to its handler, accumulating resource usage.
+    async fn dispatch(
+        &self,
+        request: Request,
+        usage: &mut ResourceUsage,
+        cancel: &CancelToken,
+    ) -> Response {
+        if cancel.is_cancelled() {
+            return cancelled_response(cancel);
+        }
+        if let Some(cwd) = project_written(&request) {
+            if self.project_manager.is_read_only(cwd).await {
+                return Response::error(
+                    ErrorCode::ReadOnly,
+                    format!("Project is read-only: {}", cwd.display()),
+                );
+            }
+        }
+        match request {
+            Request::Ping => Response::ok_with(ResponseData::Pong {
+                timestamp: chrono::Utc::now().timestamp(),
+            }),
+
+            Request::HealthCheck => self.handle_health_check().await,
+
+            Request::Hello {
+                client_version,
+                protocol_version,
+                supported_features,
+            } => {
+                tracing::debug!(
+                    "Client {} speaks protocol {} with features {:?}",
+                    client_version,
+                    protocol_version,
+                    supported_features
+                );
+                Response::ok_with(ResponseData::Hello {
+                    daemon_version: env!("CARGO_PKG_VERSION").to_string(),
+                    protocol_version: engram_ipc::PROTOCOL_VERSION,
+                    features: engram_ipc::FEATURES.iter().map(|f| f.to_string()).collect(),
+                })
+            }
+
+            Request::Status => {
+                let projects_loaded = self.project_manager.loaded_count().await;
+                let requests_total = self.metrics.requests_total.load(Ordering::Relaxed);
+                let cache_hit_rate = self.metrics.cache_hit_rate();
+                let avg_latency_ms = self.metrics.avg_latency().as_millis() as u64;
+                let request_usage = self
+                    .metrics
+                    .usage_by_action()
+                    .into_iter()
+                    .map(|u| RequestUsage {
+                        action: u.action,
+                        count: u.count,
+                        bytes_read: u.total.bytes_read,
+                        nodes_touched: u.total.nodes_touched,
+                        alloc_bytes: u.total.alloc_bytes,
+                    })
+                    .collect();
+                let render_cache = self
+                    .render_cache
+                    .stats()
+                    .into_iter()
+                    .map(|s| RenderCacheInfo {
+                        project: s.project,
+                        hits: s.hits,
+                        misses: s.misses,
+                        evictions: s.evictions,
+                        entries: s.entries,
+                    })
+                    .collect();
+
+                let slos = self
+                    .slo
+                    .status()
+                    .into_iter()
+                    .map(|s| SloInfo {
+                        name: s.name,
+                        window_secs: s.window_secs,
+                        total: s.total,
+                        slow: s.slow,
+                        compliance: s.compliance,
+                        burn_rate: s.burn_rate,
+                        at_risk: s.at_risk,
+                    })
+                    .collect();
+
+                let enrichment = self
+                    .project_manager
+                    .jobs()
+                    .list()
+                    .into_iter()
+                    .filter(|job| job.kind == JobKind::Enrichment)
+                    .map(job_info)
+                    .collect();
+
+                let enricher = self.enricher();
+                let summarizer = enricher.usage().map(|usage| {
+                    Box::new(SummarizerInfo {
+                        backend: enricher.summarizer_name().to_string(),
+                        model: usage.model,
+                        requests: usage.requests,
+                        failed: usage.failed,
+                        input_tokens: usage.input_tokens,
+                        output_tokens: usage.output_tokens,
+                        cost: usage.cost,
+                    })
+                });
+
+                let latency = self
+                    .latency
+                    .histograms()
+                    .into_iter()
+                    .map(|histogram| {
+                        let percentiles = self
+                            .latency
+                            .percentiles(&histogram.operation, &[0.50, 0.95, 0.99]);
+                        EndpointLatency {
+                            p50_ms: millis(percentiles[0]),
+                            p95_ms: millis(percentiles[1]),
+                            p99_ms: millis(percentiles[2]),
+                            count: histogram.count,
+                            action: histogram.operation,
+                        }
+                    })
+                    .collect();
+
+                let prefetch = self.context_manager.prefetch_stats();
+                let flags = self.experiments.current();
+                let experiments = Experiment::ALL
+                    .iter()
+                    .map(|experiment| ExperimentInfo {
+                        name: experiment.name().to_string(),
+                        enabled: flags.is_enabled(*experiment),
+                    })
+                    .collect();
+
+                Response::ok_with(ResponseData::Status {
+                    version: env!("CARGO_PKG_VERSION").to_string(),
+                    uptime_secs: self.uptime_secs(),
+                    projects_loaded,
+                    memory_usage_bytes: get_memory_usage(),
+                    requests_total,
+                    cache_hit_rate,
+                    avg_latency_ms,
+                    request_usage,
+                    render_cache,
+                    experiments,
+                    slos,
+                    enrichment,
+                    summarizer,
+                    latency,
+                    scopes_active: self.context_manager.active_scope_count(),
+                    prefetches: prefetch.prefetched,
+                    prefetch_hits: prefetch.hits,
+                    recovery: self.recovery.lock().unwrap().take(),
+                })
+            }
+
+            Request::CheckInit { cwd } => {
+                let initialized = self.project_manager.is_initialized(&cwd).await;
+                Response::ok_with(ResponseData::InitStatus { initialized })
+            }
+
+            Request::InitProject {
+                cwd,
+                async_mode,
+                artifact_allowlist,
+            } => {
+                self.handle_init_project(&cwd, async_mode, artifact_allowlist, cancel)
+                    .await
+            }
+
+            Request::GetContext {
+                cwd,
+                prompt,
+                no_cache,
+                cold_start,
+                read_only_subagent,
+                focus,
+                changed_since,
+                structured,
+                profile,
+                session_id,
+            } => {
+                // Check if project is initialized
+                if !self.project_manager.is_initialized(&cwd).await {
+                    if cold_start {
+                        return self.handle_cold_start(&cwd).await;
+                    }
+                    return Response::error(
+                        ErrorCode::NotInitialized,
+                        "Project not initialized. Run /init-project first.",
+                    );
+                }
+
+                let project = self.project_manager.get_project(&cwd).await.ok();
+                let config = project.as_ref().map(|project| &project.config);
+                let context_profile = match &profile {
+                    Some(name) => match config.and_then(|config| config.profile(name)) {
+                        Some(context_profile) => Some(context_profile),
+                        None => {
+                            return Response::error(
+                                ErrorCode::InvalidRequest,
+                                format!("Unknown context profile: {}", name),
+                            )
+                        }
+                    },
+                    None => None,
+                };
+                let max_content_size =
+                    config.and_then(|config| config.max_content_size(context_profile));
+                let changed_since = changed_since
+                    .or_else(|| context_profile.and_then(|profile| profile.changed_since.clone()));
+
+                // Review contexts depend on git state outside the watched tree
+                let cacheable = !no_cache && !structured && changed_since.is_none();
+                let cache_key = cacheable.then(|| {
+                    ContextKey::new(prompt.as_deref(), &focus)
+                        .with_agent(read_only_subagent.clone())
+                        .with_max_content_size(max_content_size)
+                        .with_profile(profile.clone())
+                        .with_session(session_id.clone())
+                });
+                if let Some(key) = &cache_key {
+                    if let Some(cached) = self.context_manager.cached_context(&cwd, key) {
+                        self.metrics.record_cache_hit();
+                        usage.add_alloc(cached.context.len());
+                        return Response::ok_with(ResponseData::Context {
+                            context: cached.context,
+                            nodes: cached.nodes,
+                            scope_id: cached.scope_id,
+                        });
+                    }
+                }
+                let generation = self.context_manager.context_generation(&cwd);
+
+                // Create a scope for the project
+                let mut req = ScopeRequest::new(&cwd).with_focus_refs(&focus);
+                if let Some(base) = changed_since {
+                    req = req.with_changed_since(base);
+                }
+                if let Some(agent_id) = read_only_subagent {
+                    req = req.with_read_only_memory(agent_id);
+                }
+                if let Some(session_id) = session_id {
+                    req = req.with_session(session_id);
+                }
+                if let Some(profile) = context_profile {
+                    req = match profile.dependencies {
+                        Some(ContextDependencies::None) => req.with_dependencies(false, false),
+                        Some(ContextDependencies::Full) => req.with_dependencies(true, true),
+                        Some(ContextDependencies::Imports) | None => req,
+                    };
+                    if profile.experiences == Some(false) {
+                        req = req.without_experiences();
+                    }
+                    if profile.previous_session == Some(false) {
+                        req = req.without_previous_session();
+                    }
+                }
+                req = req.with_cancel(cancel.clone());
+                match self.context_manager.create_scope(req).await {
+                    Ok(scope) => {
+                        // Get tree for rendering
+                        match self.project_manager.get_tree(&cwd).await {
+                            Ok(tree) => {
+                                usage.add_nodes(tree.nodes.len());
+                                let nodes: Vec<String> = scope
+                                    .focus
+                                    .primary_nodes
+                                    .iter()
+                                    .map(|id| id.to_string())
+                                    .collect();
+                                if structured {
+                                    let sections =
+                                        self.render_sections(&scope, &tree, max_content_size);
+                                    usage.add_alloc(
+                                        sections.iter().map(|section| section.text.len()).sum(),
+                                    );
+                                    return Response::ok_with(ResponseData::StructuredContext {
+                                        sections,
+                                        nodes,
+                                        scope_id: scope.id.clone(),
+                                    });
+                                }
+                                let context = self.render_context(
+                                    &cwd,
+                                    &scope,
+                                    &tree,
+                                    max_content_size,
+                                    no_cache,
+                                );
+                                usage.add_alloc(context.len());
+                                if let Some(key) = cache_key {
+                                    self.context_manager.cache_context(
+                                        &cwd,
+                                        key,
+                                        CachedContext {
+                                            context: context.clone(),
+                                            nodes: nodes.clone(),
+                                            scope_id: scope.id.clone(),
+                                        },
+                                        generation,
+                                    );
+                                }
+                                Response::ok_with(ResponseData::Context {
+                                    context,
+                                    nodes,
+                                    scope_id: scope.id.clone(),
+                                })
+                            }
+                            Err(e) => {
+                                tracing::warn!(error = %e, "Failed to get tree");
+                                // Fall back to compact rendering without tree details
+                                Response::ok_with(ResponseData::Context {
+                                    context: format!("# Project Context\n\nProject: {}\n\n_(Tree unavailable: {})_", cwd.display(), e),
+                                    nodes: vec![],
+                                    scope_id: scope.id.clone(),
+                                })
+                            }
+                        }
+                    }
+                    Err(ContextError::Cancelled) => cancelled_response(cancel),
+                    Err(e) => {
+                        tracing::warn!(error = %e, "Failed to create context scope");
+                        Response::failure(&e)
+                    }
+                }
+            }
+
+            Request::PrepareContext { cwd, prompt: _ } => {
+                // Fire-and-forget: prepare context for next request
+                let manager = self.context_manager.clone();
+                let project_manager = self.project_manager.clone();
+                tokio::spawn(async move {
+                    if project_manager.is_initialized(&cwd).await {
+                        // Pre-create a scope to warm the cache
+                        let req = ScopeRequest::new(&cwd);
+                        if let Err(e) = manager.create_scope(req).await {
+                            tracing::debug!(cwd = ?cwd, error = %e, "Failed to prepare context");
+                        } else {
+                            tracing::debug!(cwd = ?cwd, "Context prepared");
+                        }
+                    }
+                });
+
+                Response::ack()
+            }
+
+            Request::ExpandContext {
+                cwd,
+                scope_id,
+                node,
+            } => self.handle_expand_context(&cwd, &scope_id, &node).await,
+
+            Request::ForkScope {
+                cwd,
+                scope_id,
+                focus,
+            } => self.handle_fork_scope(&cwd, &scope_id, &focus).await,
+
+            Request::ReleaseScope { cwd, scope_id } => {
+                match self.context_manager.release_scope(&cwd, &scope_id).await {
+                    Some(_) => Response::ok(),
+                    None => Response::error(
+                        ErrorCode::InvalidRequest,
+                        format!("Scope not found: {}", scope_id),
+                    ),
+                }
+            }
+
+            Request::NotifyFileChange {
+                cwd,
+                path,
+                change_type,
+            } => {
+                // Fire-and-forget: handle file change
+                tracing::debug!(
+                    cwd = ?cwd,
+                    path = ?path,
+                    change = ?change_type,
+                    "File change notification"
+                );
+
+                let reindex = self
+                    .experiment_enabled(&cwd, Experiment::IncrementalIndexing)
+                    .await;
+                let prefetch = self.scope_config.prefetch && change_type != ChangeType::Deleted;
+                if reindex || prefetch {
+                    let project_manager = self.project_manager.clone();
+                    let context_manager = self.context_manager.clone();
+                    tokio::spawn(async move {
+                        if reindex {
+                            match project_manager.index_project(&cwd).await {
+                                Ok(_) => context_manager.invalidate_tree(&cwd),
+                                Err(e) => {
+                                    tracing::debug!(cwd = ?cwd, error = %e, "Re-index after change failed");
+                                    return;
+                                }
+                            }
+                        }
+                        if !prefetch {
+                            return;
+                        }
+                        let Ok(project) = project_manager.get_project(&cwd).await else {
+                            return;
+                        };
+                        let max_content_size = project.config.context.max_content_size;
+                        if let Err(e) = context_manager
+                            .prefetch(&cwd, &path, max_content_size)
+                            .await
+                        {
+                            tracing::debug!(cwd = ?cwd, error = %e, "Context prefetch failed");
+                        }
+                    });
+                }
+
+                Response::ack()
+            }
+
+            Request::GraftExperience { cwd, experience } => {
+                // Convert IPC experience to context experience
+                let mut ctx_experience =
+                    engram_context::Experience::new(&experience.agent_id, &experience.decision)
+                        .with_files(experience.files_touched)
+                        .with_memory_ids(experience.memory_ids);
+
+                // Conditionally add rationale and caller-provided id
+                if let Some(rationale) = &experience.rationale {
+                    ctx_experience = ctx_experience.with_rationale(rationale);
+                }
+                if let Some(id) = experience.id {
+                    ctx_experience = ctx_experience.with_id(id);
+                }
+                if let Some(outcome) = experience.outcome {
+                    ctx_experience = ctx_experience.with_outcome(context_outcome(outcome));
+                }
+                // Experiences without a session join the active one
+                match experience.session_id {
+                    Some(session_id) => ctx_experience.session_id = session_id,
+                    None => {
+                        if let Ok(Some(session)) = self.sessions.active(&cwd).await {
+                            ctx_experience.session_id = session.id;
+                        }
+                    }
+                }
+
+                // Fire-and-forget: graft experience
+                let manager = self.context_manager.clone();
+                let cwd_clone = cwd.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = manager.graft_experience(&cwd_clone, ctx_experience).await {
+                        tracing::warn!(
+                            cwd = ?cwd_clone,
+                            error = %e,
+                            "Failed to graft experience"
+                        );
+                    } else {
+                        tracing::debug!(cwd = ?cwd_clone, "Experience grafted");
+                    }
+                });
+
+                Response::ack()
+            }
+
+            Request::MemoryPut { cwd, entry } => {
+                if !self.project_manager.is_initialized(&cwd).await {
+                    return Response::error(
+                        ErrorCode::NotInitialized,
+                        "Project not initialized. Run /init-project first.",
+                    );
+                }
+
+                if entry.kind.trim().is_empty() || entry.content.trim().is_empty() {
+                    return Response::error(
+                        ErrorCode::InvalidRequest,
+                        "Memory entry requires non-empty kind and content",
+                    );
+                }
+
+                let now = chrono::Utc::now().timestamp();
+                // A project has one brief, replaced by each put
+                let id = if entry.kind == PROJECT_BRIEF_KIND {
+                    PROJECT_BRIEF_ID.to_string()
+                } else if entry.id.trim().is_empty() {
+                    Uuid::new_v4().to_string()
+                } else {
+                    entry.id
+                };
+                let updated_at = if id == PROJECT_BRIEF_ID {
+                    self.context_manager.next_brief_update(&cwd).await
+                } else {
+                    now
+                };
+
+                let stored_entry = engram_ipc::MemoryEntry {
+                    id: id.clone(),
+                    kind: entry.kind,
+                    content: entry.content,
+                    tags: entry.tags,
+                    created_at: if entry.created_at > 0 {
+                        entry.created_at
+                    } else {
+                        now
+                    },
+                    updated_at,
+                    session_id: entry.session_id,
+                    subagent_id: entry.subagent_id,
+                    deleted: entry.deleted,
+                    experience_id: entry.experience_id,
+                    visibility: entry.visibility,
+                };
+
+                if let Some(agent_id) = &stored_entry.subagent_id {
+                    self.context_manager.restore_scopes(&cwd).await;
+                    if self.context_manager.is_memory_read_only(&cwd, agent_id) {
+                        return match self.pending_memory.stage(&cwd, stored_entry).await {
+                            Ok(()) => Response::ok_with(ResponseData::MemoryStaged { id }),
+                            Err(e) => {
+                                tracing::warn!(error = %e, cwd = ?cwd, "Failed to stage memory entry");
+                                Response::failure(&e)
+                            }
+                        };
+                    }
+                }
+
+                match self
+                    .project_memory(&cwd)
+                    .await
+                    .put(&cwd, stored_entry)
+                    .await
+                {
+                    Ok(_) => {
+                        self.context_manager.invalidate_context(&cwd);
+                        Response::ok_with(ResponseData::MemoryAck { id })
+                    }
+                    Err(e @ MemoryStoreError::InvalidEntry(_)) => {
+                        Response::error(ErrorCode::InvalidRequest, e.to_string())
+                    }
+                    Err(e) => {
+                        tracing::warn!(error = %e, cwd = ?cwd, "Failed to persist memory entry");
+                        Response::failure(&e)
+                    }
+                }
+            }
+
+            Request::MemoryPatch { cwd, id, patch } => {
+                if !self.project_manager.is_initialized(&cwd).await {
+                    return Response::error(
+                        ErrorCode::NotInitialized,
+                        "Project not initialized. Run /init-project first.",
+                    );
+                }
+
+                if id.trim().is_empty() {
+                    return Response::error(
+                        ErrorCode::InvalidRequest,
+                        "Memory patch requires a non-empty id",
+                    );
+                }
+
+                if patch.kind.is_none()
+                    && patch.content.is_none()
+                    && patch.tags.is_none()
+                    && patch.session_id.is_none()
+                    && patch.subagent_id.is_none()
+                    && patch.deleted.is_none()
+                    && patch.updated_at.is_none()
+                    && patch.visibility.is_none()
+                {
+                    return Response::error(
+                        ErrorCode::InvalidRequest,
+                        "Memory patch requires at least one field update",
+                    );
+                }
+
+                if patch
+                    .kind
+                    .as_ref()
+                    .is_some_and(|kind| kind.trim().is_empty())
+                {
+                    return Response::error(
+                        ErrorCode::InvalidRequest,
+                        "Memory patch kind cannot be empty",
+                    );
+                }
+
+                if patch
+                    .content
+                    .as_ref()
+                    .is_some_and(|content| content.trim().is_empty())
+                {
+                    return Response::error(
+                        ErrorCode::InvalidRequest,
+                        "Memory patch content cannot be empty",
+                    );
+                }
+
+                match self
+                    .project_memory(&cwd)
+                    .await
+                    .patch(&cwd, &id, patch)
+                    .await
+                {
+                    Ok(Some(_)) => {
+                        self.context_manager.invalidate_context(&cwd);
+                        Response::ok_with(ResponseData::MemoryAck { id })
+                    }
+                    Ok(None) => Response::error(
+                        ErrorCode::InvalidRequest,
+                        format!("Memory entry not found: {}", id),
+                    ),
+                    Err(e @ MemoryStoreError::InvalidEntry(_)) => {
+                        Response::error(ErrorCode::InvalidRequest, e.to_string())
+                    }
+                    Err(e) => {
+                        tracing::warn!(error = %e, cwd = ?cwd, "Failed to patch memory entry");
+                        Response::failure(&e)
+                    }
+                }
+            }
+
+            Request::MemoryDelete { cwd, id } => {
+                if !self.project_manager.is_initialized(&cwd).await {
+                    return Response::error(
+                        ErrorCode::NotInitialized,
+                        "Project not initialized. Run /init-project first.",
+                    );
+                }
+
+                if id.trim().is_empty() {
+                    return Response::error(
+                        ErrorCode::InvalidRequest,
+                        "Memory delete requires a non-empty id",
+                    );
+                }
+
+                match self
+                    .project_memory(&cwd)
+                    .await
+                    .delete(&cwd, &id, None)
+                    .await
+                {
+                    Ok(Some(_)) => {
+                        self.context_manager.invalidate_context(&cwd);
+                        Response::ok_with(ResponseData::MemoryAck { id })
+                    }
+                    Ok(None) => Response::error(
+                        ErrorCode::InvalidRequest,
+                        format!("Memory entry not found: {}", id),
+                    ),
+                    Err(e) => {
+                        tracing::warn!(error = %e, cwd = ?cwd, "Failed to delete memory entry");
+                        Response::failure(&e)
+                    }
+                }
+            }
+
+            Request::MemoryGet { cwd, id } => {
+                if !self.project_manager.is_initialized(&cwd).await {
+                    return Response::error(
+                        ErrorCode::NotInitialized,
+                        "Project not initialized. Run /init-project first.",
+                    );
+                }
+
+                match self.memory_store.get(&cwd, &id).await {
+                    Ok(Some(entry)) => {
+                        usage.add_alloc(entry.content.len());
+                        Response::ok_with(ResponseData::MemoryEntry { entry })
+                    }
+                    Ok(None) => Response::error(
+                        ErrorCode::InvalidRequest,
+                        format!("Memory entry not found: {}", id),
+                    ),
+                    Err(e) => {
+                        tracing::warn!(error = %e, cwd = ?cwd, "Failed to load memories");
+                        Response::failure(&e)
+                    }
+                }
+            }
+
+            Request::MemoryList { cwd, limit, query } => {
+                if !self.project_manager.is_initialized(&cwd).await {
+                    return Response::error(
+                        ErrorCode::NotInitialized,
+                        "Project not initialized. Run /init-project first.",
+                    );
+                }
+
+                match self.memory_store.list(&cwd, &query, limit).await {
+                    Ok(entries) => {
+                        usage.add_alloc(entries.iter().map(|e| e.content.len()).sum());
+                        Response::ok_with(ResponseData::MemoryEntries { entries })
+                    }
+                    Err(e) => {
+                        tracing::warn!(error = %e, cwd = ?cwd, "Failed to list memories");
+                        Response::failure(&e)
+                    }
+                }
+            }
+
+            Request::MemorySearch {
+                cwd,
+                query,
+                limit,
+                viewer,
+            } => {
+                if !self.project_manager.is_initialized(&cwd).await {
+                    return Response::error(
+                        ErrorCode::NotInitialized,
+                        "Project not initialized. Run /init-project first.",
+                    );
+                }
+
+                match self
+                    .memory_store
+                    .search(&cwd, &query, limit, viewer.as_ref())
+                    .await
+                {
+                    Ok(matches) => {
+                        usage.add_alloc(matches.iter().map(|(e, _)| e.content.len()).sum());
+                        let matches = matches
+                            .into_iter()
+                            .map(|(entry, score)| MemoryMatch { entry, score })
+                            .collect();
+                        Response::ok_with(ResponseData::MemoryMatches { matches })
+                    }
+                    Err(e) => {
+                        tracing::warn!(error = %e, cwd = ?cwd, "Failed to search memories");
+                        Response::failure(&e)
+                    }
+                }
+            }
+
+            Request::MemorySync { cwd } => {
+                if !self.project_manager.is_initialized(&cwd).await {
+                    return Response::error(
+                        ErrorCode::NotInitialized,
+                        "Project not initialized. Run /init-project first.",
+                    );
+                }
+
+                match self.memory_store.sync(&cwd).await {
+                    Ok(_) => Response::ok(),
+                    Err(e) => {
+                        tracing::warn!(error = %e, cwd = ?cwd, "Failed to sync memories");
+                        Response::failure(&e)
+                    }
+                }
+            }
+
+            Request::MemoryCompact { cwd } => {
+                if !self.project_manager.is_initialized(&cwd).await {
+                    return Response::error(
+                        ErrorCode::NotInitialized,
+                        "Project not initialized. Run /init-project first.",
+                    );
+                }
+
+                match self.project_memory(&cwd).await.compact(&cwd).await {
+                    Ok(stats) => {
+                        self.project_manager.events().record(
+                            EventKind::MemoryCompacted,
+                            Some(&cwd),
+                            format!(
+                                "Compacted memory log from {} to {} lines",
+                                stats.lines_before, stats.lines_after
+                            ),
+                        );
+                        Response::ok_with(ResponseData::MemoryCompacted {
+                            lines_before: stats.lines_before,
+                            lines_after: stats.lines_after,
+                            dropped_versions: stats.dropped_versions,
+                            dropped_tombstones: stats.dropped_tombstones,
+                        })
+                    }
+                    Err(e) => {
+                        tracing::warn!(error = %e, cwd = ?cwd, "Failed to compact memories");
+                        Response::failure(&e)
+                    }
+                }
+            }
+
+            Request::ConsolidateExperiences { cwd } => {
+                if !self.project_manager.is_initialized(&cwd).await {
+                    return Response::error(
+                        ErrorCode::NotInitialized,
+                        "Project not initialized. Run /init-project first.",
+                    );
+                }
+
+                match self.context_manager.consolidate_experiences(&cwd).await {
+                    Ok(stats) => {
+                        self.project_manager.events().record(
+                            EventKind::ExperiencesConsolidated,
+                            Some(&cwd),
+                            format!(
+                                "Consolidated {} experiences into {}",
+                                stats.experiences_before, stats.experiences_after
+                            ),
+                        );
+                        Response::ok_with(ResponseData::ExperiencesConsolidated {
+                            experiences_before: stats.experiences_before,
+                            experiences_after: stats.experiences_after,
+                            merged: stats.merged,
+                            summarized: stats.summarized,
+                            summaries: stats.summaries,
+                        })
+                    }
+                    Err(e) => {
+                        tracing::warn!(error = %e, cwd = ?cwd, "Failed to consolidate experiences");
+                        Response::failure(&e)
+                    }
+                }
+            }
+
+            Request::SessionStart {
+                cwd,
+                session_id,
+                agent,
+                model,
+                metadata,
+            } => {
+                self.handle_session_start(&cwd, session_id, agent, model, metadata)
+                    .await
+            }
+
+            Request::SessionEnd {
+                cwd,
+                session_id,
+                summary,
+            } => self.handle_session_end(&cwd, &session_id, summary).await,
+
+            Request::ListSessions { cwd, limit } => self.handle_list_sessions(&cwd, limit).await,
+
+            Request::GetSession { cwd, session_id } => {
+                self.handle_get_session(&cwd, &session_id).await
+            }
+
+            Request::Batch { requests } => self.handle_batch(requests, usage, cancel).await,
+
+            Request::LineHints { cwd, path } => self.handle_line_hints(&cwd, &path, usage).await,
+
+            Request::DebugSnapshot { cwd } => self.handle_debug_snapshot(&cwd, usage).await,
+
+            Request::CalibrationExport { ref cwd }
+            | Request::CalibrationImport { ref cwd, .. }
+            | Request::ExportMemory { ref cwd }
+            | Request::ImportMemory { ref cwd, .. }
+            | Request::CalibrationReset { ref cwd }
+            | Request::ListPending { ref cwd }
+            | Request::ApprovePending { ref cwd, .. }
+                if !self.project_manager.is_initialized(cwd).await =>
+            {
+                Response::error(
+                    ErrorCode::NotInitialized,
+                    "Project not initialized. Run /init-project first.",
+                )
+            }
+
+            Request::ListPending { cwd } => match self.pending_memory.list(&cwd).await {
+                Ok(entries) => Response::ok_with(ResponseData::MemoryEntries { entries }),
+                Err(e) => Response::failure(&e),
+            },
+
+            Request::ApprovePending { cwd, ids, reject } => {
+                self.handle_approve_pending(&cwd, &ids, reject).await
+            }
+
+            Request::CalibrationExport { cwd } => match self.calibration.load(&cwd).await {
+                Ok(calibration) => calibration_response(&calibration),
+                Err(e) => Response::failure(&e),
+            },
+
+            Request::CalibrationImport { cwd, calibration } => {
+                match self.calibration.import(&cwd, &calibration).await {
+                    Ok(calibration) => calibration_response(&calibration),
+                    Err(e @ (ContextError::Calibration(_) | ContextError::Json(_))) => {
+                        Response::error(ErrorCode::InvalidRequest, e.to_string())
+                    }
+                    Err(e) => Response::failure(&e),
+                }
+            }
+
+            Request::ExportMemory { cwd } => self.handle_export_memory(&cwd).await,
+
+            Request::ImportMemory { cwd, bundle } => self.handle_import_memory(&cwd, bundle).await,
+
+            Request::CalibrationReset { cwd } => match self.calibration.reset(&cwd).await {
+                Ok(()) => Response::ok(),
+                Err(e) => Response::failure(&e),
+            },
+
+            Request::PruneTree { cwd, globs } => self.handle_prune_tree(&cwd, &globs).await,
+
+            Request::UnloadProject { cwd } => self.handle_unload_project(&cwd).await,
+
+            Request::RelinkProject { cwd, from } => {
+                self.handle_relink_project(&cwd, from.as_deref()).await
+            }
+
+            Request::ExportProject { cwd, output } => {
+                self.handle_export_project(&cwd, &output).await
+            }
+
+            Request::ImportProject { cwd, archive } => {
+                self.handle_import_project(&cwd, &archive).await
+            }
+
+            Request::Search {
+                cwd,
+                query,
+                limit,
+                explain,
+            } => {
+                self.handle_search(&cwd, &query, (limit, explain), usage)
+                    .await
+            }
+
+            Request::FindFiles {
+                cwd,
+                pattern,
+                limit,
+            } => self.handle_find_files(&cwd, &pattern, limit, usage).await,
+
+            Request::AnalyzeDependencies {
+                cwd,
+                cycles,
+                order,
+                dependents_of,
+                depth,
+            } => {
+                self.handle_analyze_dependencies(
+                    &cwd,
+                    (cycles, order),
+                    dependents_of.as_deref(),
+                    depth,
+                    usage,
+                )
+                .await
+            }
+
+            Request::ImpactAnalysis { cwd, symbol } => {
+                self.handle_impact_analysis(&cwd, &symbol, usage).await
+            }
+
+            Request::ListTests { cwd, path } => self.handle_list_tests(&cwd, &path, usage).await,
+
+            Request::GetSymbol { cwd, path, symbol } => {
+                self.handle_get_symbol(&cwd, &path, &symbol, usage).await
+            }
+
+            Request::DiffSnapshots { cwd, from, to } => {
+                self.handle_diff_snapshots(&cwd, &from, to.as_deref(), usage)
+                    .await
+            }
+
+            Request::GetInitProgress { cwd } => self.handle_get_init_progress(&cwd),
+
+            Request::ProjectStats { cwd, top } => {
+                self.handle_project_stats(&cwd, top.unwrap_or(10), usage)
+                    .await
+            }
+
+            Request::ExportDocs { cwd, mdbook } => {
+                self.handle_export_docs(&cwd, mdbook, usage).await
+            }
+
+            Request::GetEvents { since, limit } => {
+                let events = self
+                    .project_manager
+                    .events()
+                    .since(since, limit)
+                    .into_iter()
+                    .map(event_info)
+                    .collect();
+                Response::ok_with(ResponseData::Events { events })
+            }
+
+            Request::ListJobs => {
+                let jobs = self
+                    .project_manager
+                    .jobs()
+                    .list()
+                    .into_iter()
+                    .map(job_info)
+                    .collect();
+                Response::ok_with(ResponseData::Jobs { jobs })
+            }
+
+            Request::ListProjects => self.handle_list_projects().await,
+
+            Request::CollectGarbage {
+                max_idle_days,
+                dry_run,
+                only,
+            } => {
+                match self
+                    .project_manager
+                    .collect_garbage(max_idle_days, dry_run, &only)
+                    .await
+                {
+                    Ok(report) => Response::ok_with(ResponseData::Garbage {
+                        reclaimable_bytes: report.reclaimable_bytes(),
+                        deleted: report.deleted,
+                        projects: report
+                            .candidates
+                            .into_iter()
+                            .map(|c| GarbageProject {
+                                root: c.root,
+                                dirs: c.dirs,
+                                reason: c.reason.name().to_string(),
+                                bytes: c.bytes,
+                                idle_days: c.idle_days,
+                            })
+                            .collect(),
+                    }),
+                    Err(e) => Response::failure(&e),
+                }
+            }
+
+            Request::Doctor => Response::ok_with(ResponseData::Doctor {
+                checks: self.doctor.run(&self.storage, true).await,
+            }),
+
+            Request::CancelJob { id } => {
+                if self.project_manager.jobs().cancel(id) {
+                    Response::ok()
+                } else {
+                    Response::error(ErrorCode::InvalidRequest, format!("No running job: {}", id))
+                }
+            }
+
+            Request::EncryptLogs => self.handle_encrypt_logs().await,
+
+            Request::ReloadConfig => match self.reload_config().await {
+                Ok(reload) => Response::ok_with(ResponseData::ConfigReloaded {
+                    path: self.config_path.clone(),
+                    applied: reload.applied,
+                    restart_required: reload.restart_required,
+                }),
+                Err(e) => Response::failure(&e),
+            },
+
+            Request::Shutdown => {
+                tracing::info!("Shutdown requested");
+                let _ = self.shutdown_tx.send(());
+                Response::ack()
+            }
+
+            // Consumed by the IPC server before dispatch
+            Request::Authenticate { .. } => Response::error(
+                ErrorCode::InvalidRequest,
+                "Authenticate must precede a request on the same connection",
+            ),
+
+            Request::Tracked { .. } => Response::error(
+                ErrorCode::InvalidRequest,
+                "Tracked requests cannot be nested",
+            ),
+
+            Request::Cancel { request_id } => {
+                let in_flight = self.in_flight.lock().unwrap();
+                match in_flight.get(&request_id) {
+                    Some(token) => {
+                        token.cancel();
+                        Response::ok()
+                    }
+                    None => Response::error(
+                        ErrorCode::InvalidRequest,
+                        format!("No request in flight: {}", request_id),
+                    ),
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl RequestHandler for DaemonHandler {
+    async fn handle(&self, request: Request) -> Response {
+        // Tracked requests register a token so a `Cancel` on another
+        // connection, or their deadline, stops them early
+        let (request, request_id, cancel) = match request {
+            Request::Tracked {
+                request_id,
+                deadline_ms,
+                request,
+            } => {
+                let cancel = match deadline_ms {
+                    Some(ms) => CancelToken::with_timeout(Duration::from_millis(ms)),
+                    None => CancelToken::new(),
+                };
+                let mut in_flight = self.in_flight.lock().unwrap();
+                if in_flight.contains_key(&request_id) {
+                    return Response::error(
+                        ErrorCode::InvalidRequest,
+                        format!("Request already in flight: {}", request_id),
+                    );
+                }
+                in_flight.insert(request_id.clone(), cancel.clone());
+                (*request, Some(request_id), cancel)
+            }
+            request => (request, None, CancelToken::new()),
+        };
+        let action = request.action_name();
+        let start = Instant::now();
+        let mut usage = ResourceUsage::default();
+
+        let response = self.dispatch(request, &mut usage, &cancel).await;
+        if let Some(request_id) = request_id {
+            self.in_flight.lock().unwrap().remove(&request_id);
+        }
+
+        if let Response::Error {
+            code: ErrorCode::InternalError,
+            message,
+            ..
+        } = &response
+        {
+            self.project_manager.events().record(
+                EventKind::Error,
+                None,
+                format!("{}: {}", action, message),
+            );
+        }
+
+        let latency = start.elapsed();
+        self.metrics.record_request(latency);
+        self.metrics.record_usage(action, usage);
+        self.latency.record(action, latency);
+        for alert in self.slo.record(action, latency) {
+            alerts::raise_slo_alert(&alert, self.slo_webhook.as_deref());
+        }
+        response
+    }
+}
+
+/// Error for a request stopped by its token.
+fn cancelled_response(cancel: &CancelToken) -> Response {
+    if cancel.is_expired() {
+        Response::error(ErrorCode::Timeout, "Deadline exceeded")
+    } else {
+        Response::error(ErrorCode::Cancelled, "Request cancelled")
+    }
+}
+
+/// Duration in fractional milliseconds.
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Describe a background job for the IPC response.
+fn job_info(job: JobStatus) -> JobInfo {
+    JobInfo {
+        id: job.id,
+        kind: job.kind.name().to_string(),
+        project: job.project,
+        processed: job.processed,
+        total: job.total,
+        elapsed_secs: job.elapsed_secs,
+        phase: job.phase.name().to_string(),
+        eta_secs: job.eta_secs,
+        cancelling: job.cancelling,
+        queued: job.queued,
+    }
+}
+
+/// Describe a journaled event for the IPC response.
+fn event_info(event: Event) -> EventInfo {
+    EventInfo {
+        seq: event.seq,
+        time: event.time.timestamp(),
+        kind: event.kind.name().to_string(),
+        project: event.project,
+        message: event.message,
+    }
+}
+
+/// Serialize a calibration into its export format.
+fn calibration_response(calibration: &Calibration) -> Response {
+    match serde_json::to_string_pretty(calibration) {
+        Ok(calibration) => Response::ok_with(ResponseData::Calibration { calibration }),
+        Err(e) => Response::error(ErrorCode::InternalError, e.to_string()),
+    }
+}
+
+/// Describe a symbol for the impact report.
+fn impact_symbol(tree: &Tree, id: NodeId) -> Option<ImpactSymbol> {
+    let node = tree.get(id)?;
+    let NodeKind::Symbol {
+        symbol_kind,
+        start_line,
+        ..
+    } = &node.kind
+    else {
+        return None;
+    };
+    Some(ImpactSymbol {
+        path: tree.get(node.parent?)?.path.clone(),
+        name: node.name.clone(),
+        kind: symbol_kind_name(symbol_kind),
+        line: *start_line,
+    })
+}
+
+/// Declaration of the symbol starting at `line` (1-based): its lines up to
+/// the one opening the body, without the body itself.
+fn signature_lines(text: &str, line: usize) -> String {
+    let mut signature = Vec::new();
+    for line in text
+        .lines()
+        .skip(line.saturating_sub(1))
+        .take(SIGNATURE_MAX_LINES)
+    {
+        if let Some(open) = line.find('{') {
+            signature.push(line[..open].trim_end());
+            break;
+        }
+        signature.push(line.trim_end());
+        if line.trim_end().ends_with([';', ':']) {
+            break;
+        }
+    }
+    signature.join("\n").trim_end().to_string()
+}
+
+/// Files under `dir` with their sizes, named relative to `root`.
+fn collect_index_files(root: &Path, dir: &Path, files: &mut Vec<IndexFile>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        let path = entry.path();
+        if metadata.is_dir() {
+            collect_index_files(root, &path, files);
+            continue;
+        }
+        files.push(IndexFile {
+            name: path
+                .strip_prefix(root)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .into_owned(),
+            size: metadata.len(),
+            modified: metadata
+                .modified()
+                .ok()
+                .map(|t| chrono::DateTime::<chrono::Utc>::from(t).timestamp()),
+        });
+    }
+}
+
+/// Serialized name of a symbol kind, e.g. `function`.
+fn symbol_kind_name(kind: &SymbolKind) -> String {
+    serde_json::to_value(kind)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// Describe a router result for the search response.
+fn search_hit(tree: &Tree, result: &RetrievalResult, explain: bool) -> Option<SearchHit> {
+    let node = tree.get(result.node_id)?;
+    let (kind, line) = match &node.kind {
+        NodeKind::Directory => ("directory".to_string(), None),
+        NodeKind::File { .. } => ("file".to_string(), None),
+        NodeKind::Symbol {
+            symbol_kind,
+            start_line,
+            ..
+        } => (symbol_kind_name(symbol_kind), Some(*start_line)),
+    };
+    let source = match result.source {
+        ResultSource::Tree => "tree",
+        ResultSource::Vector => "vector",
+        ResultSource::Merged => "merged",
+    };
+
+    // Symbols report the file they are defined in
+    let mut file = node;
+    while file.is_symbol() {
+        match file.parent.and_then(|id| tree.get(id)) {
+            Some(parent) => file = parent,
+            None => break,
+        }
+    }
+
+    Some(SearchHit {
+        path: file.path.clone(),
+        name: node.name.clone(),
+        kind,
+        line,
+        score: result.score,
+        source: source.to_string(),
+        snippet: result.snippet.clone(),
+        explanation: explain.then(|| {
+            result
+                .reasons
+                .iter()
+                .filter_map(|reason| match_reason(tree, reason))
+                .collect()
+        }),
+    })
+}
+
+/// Describe a router match signal by paths rather than node ids.
+fn match_reason(tree: &Tree, reason: &engram_context::MatchReason) -> Option<MatchReason> {
+    use engram_context::MatchReason as Signal;
+    Some(match reason {
+        Signal::NameMatch { name } => MatchReason::NameMatch { name: name.clone() },
+        Signal::DependencyEdge { target } => MatchReason::DependencyEdge {
+            imports: tree.get(*target)?.path.clone(),
+        },
+        Signal::VectorSimilarity { similarity } => MatchReason::VectorSimilarity {
+            similarity: *similarity,
+        },
+        Signal::Calibration { weight } => MatchReason::Calibration { weight: *weight },
+        Signal::Relevance { weight } => MatchReason::Relevance { weight: *weight },
+        Signal::HotNode { boost } => MatchReason::HotNode { boost: *boost },
+    })
+}
+
+/// Outcome as recorded in the experience log.
+fn context_outcome(outcome: ExperienceOutcome) -> Outcome {
+    match outcome {
+        ExperienceOutcome::Success => Outcome::Success,
+        ExperienceOutcome::Failure { error } => Outcome::Failure { error },
+        ExperienceOutcome::Reverted => Outcome::Reverted,
+    }
+}
+
+/// Outcome as sent over IPC.
+fn ipc_outcome(outcome: &Outcome) -> ExperienceOutcome {
+    match outcome {
+        Outcome::Success => ExperienceOutcome::Success,
+        Outcome::Failure { error } => ExperienceOutcome::Failure {
+            error: error.clone(),
+        },
+        Outcome::Reverted => ExperienceOutcome::Reverted,
+    }
+}
+
+/// Experience as sent over IPC.
+fn ipc_experience(experience: &engram_context::Experience) -> engram_ipc::Experience {
+    engram_ipc::Experience {
+        agent_id: experience.agent_id.clone(),
+        decision: experience.decision.clone(),
+        rationale: experience.rationale.clone(),
+        files_touched: experience.files_touched.clone(),
+        timestamp: experience.timestamp,
+        id: Some(experience.id.clone()).filter(|id| !id.is_empty()),
+        memory_ids: experience.memory_ids.clone(),
+        summarizes: experience.summarizes,
+        session_id: Some(experience.session_id.clone()),
+        outcome: experience.outcome.as_ref().map(ipc_outcome),
+    }
+}
+
+/// The experiences and memory entries recorded in a session.
+fn in_session(
+    session_id: &str,
+    experiences: &[engram_context::Experience],
+    memory: &[engram_ipc::MemoryEntry],
+) -> (
+    Vec<engram_context::Experience>,
+    Vec<engram_ipc::MemoryEntry>,
+) {
+    (
+        experiences
+            .iter()
+            .filter(|experience| experience.session_id == session_id)
+            .cloned()
+            .collect(),
+        memory
+            .iter()
+            .filter(|entry| entry.session_id.as_deref() == Some(session_id))
+            .cloned()
+            .collect(),
+    )
+}
+
+/// Session as sent over IPC, counting what was recorded in it.
+fn session_info(
+    session: &Session,
+    experiences: &[engram_context::Experience],
+    memory: &[engram_ipc::MemoryEntry],
+) -> SessionInfo {
+    SessionInfo {
+        id: session.id.clone(),
+        agent: session.agent.clone(),
+        model: session.model.clone(),
+        metadata: session.metadata.clone(),
+        started_at: session.started_at,
+        ended_at: session.ended_at,
+        scope_ids: session.scope_ids.clone(),
+        summary: session.summary.clone(),
+        experiences: experiences
+            .iter()
+            .filter(|experience| experience.session_id == session.id)
+            .count(),
+        memory_entries: memory
+            .iter()
+            .filter(|entry| entry.session_id.as_deref() == Some(session.id.as_str()))
+            .count(),
+    }
+}
+
+/// Project whose experience log or stored tree a request writes, if any.
+fn project_written(request: &Request) -> Option<&Path> {
+    match request {
+        Request::GraftExperience { cwd, .. }
+        | Request::MemoryPut { cwd, .. }
+        | Request::MemoryPatch { cwd, .. }
+        | Request::MemoryDelete { cwd, .. }
+        | Request::MemoryCompact { cwd }
+        | Request::ConsolidateExperiences { cwd }
+        | Request::ImportMemory { cwd, .. }
+        | Request::ApprovePending { cwd, .. }
+        | Request::PruneTree { cwd, .. } => Some(cwd),
+        _ => None,
+    }
+}
+
+/// Cross-link `GraftExperience` and `MemoryPut` requests within a batch.
+///
+/// Missing ids are assigned up front so both sides can reference each other.
+/// A memory entry without an explicit `experience_id` links to the closest
+/// preceding experience for the same project, or else the first following one.
+fn link_batch_records(requests: &mut [Request]) {
+    for request in requests.iter_mut() {
+        match request {
+            Request::GraftExperience { experience, .. } if experience.id.is_none() => {
+                experience.id = Some(Uuid::new_v4().to_string());
+            }
+            Request::MemoryPut { entry, .. } if entry.kind == PROJECT_BRIEF_KIND => {
+                entry.id = PROJECT_BRIEF_ID.to_string();
+            }
+            Request::MemoryPut { entry, .. } if entry.id.trim().is_empty() => {
+                entry.id = Uuid::new_v4().to_string();
+            }
+            _ => {}
+        }
+    }
+
+    let experiences: Vec<(usize, PathBuf, String)> = requests
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, request)| match request {
+            Request::GraftExperience { cwd, experience } => {
+                Some((idx, cwd.clone(), experience.id.clone()?))
+            }
+            _ => None,
+        })
+        .collect();
+    if experiences.is_empty() {
+        return;
+    }
+
+    let mut links: Vec<(String, String)> = Vec::new();
+    for (idx, request) in requests.iter_mut().enumerate() {
+        let Request::MemoryPut { cwd, entry } = request else {
+            continue;
+        };
+        // Invalid entries are rejected by MemoryPut; don't link them.
+        if entry.kind.trim().is_empty() || entry.content.trim().is_empty() {
+            continue;
+        }
+
+        if entry.experience_id.is_none() {
+            let mut same_project = experiences.iter().filter(|(_, exp_cwd, _)| exp_cwd == cwd);
+            let preceding = same_project.clone().rfind(|(i, _, _)| *i < idx);
+            entry.experience_id = preceding
+                .or_else(|| same_project.next())
+                .map(|(_, _, id)| id.clone());
+        }
+
+        if let Some(experience_id) = &entry.experience_id {
+            links.push((experience_id.clone(), entry.id.clone()));
+        }
+    }
+
+    for request in requests.iter_mut() {
+        let Request::GraftExperience { experience, .. } = request else {
+            continue;
+        };
+        let Some(id) = experience.id.as_deref() else {
+            continue;
+        };
+        for (experience_id, memory_id) in &links {
+            if experience_id == id && !experience.memory_ids.contains(memory_id) {
+                experience.memory_ids.push(memory_id.clone());
+            }
+        }
+    }
+}
+
+/// Get current memory usage in bytes
+pub(crate) fn get_memory_usage() -> usize {
+    // On macOS, we can use rusage
+    #[cfg(unix)]
+    {
+        let mut rusage = std::mem::MaybeUninit::uninit();
+        unsafe {
+            if libc::getrusage(libc::RUSAGE_SELF, rusage.as_mut_ptr()) == 0 {
+                let rusage = rusage.assume_init();
+                // maxrss is in bytes on macOS, kilobytes on Linux
+                #[cfg(target_os = "macos")]
+                return rusage.ru_maxrss as usize;
+                #[cfg(not(target_os = "macos"))]
+                return (rusage.ru_maxrss * 1024) as usize;
+            }
+        }
+    }
+
+    0
+}
+
+/// Compaction policy for memory retention settings
+fn compaction_policy(config: &MemoryConfig) -> CompactionPolicy {
+    CompactionPolicy {
+        size_threshold: config.compact_threshold,
+        tombstone_retention_secs: (config.tombstone_retention_days * 24 * 60 * 60) as i64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engram_core::{DaemonConfig, JobKind};
+    use engram_indexer::scanner::ScanPhase;
+    use engram_ipc::{CheckStatus, MemoryEntry, MemoryPatch, MemoryViewer, MemoryVisibility};
+    use std::collections::HashSet;
+    use tempfile::tempdir;
+
+    fn test_handler() -> DaemonHandler {
+        let temp_dir = tempdir().unwrap();
+        let config = DaemonConfig {
+            data_dir: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        let manager = Arc::new(ProjectManager::new(&config));
+        let storage = Arc::new(Storage::new(temp_dir.path().to_path_buf()));
+        let (shutdown_tx, _) = broadcast::channel(1);
+        DaemonHandler::new(manager, storage, shutdown_tx, std::time::Instant::now())
+    }
+
+    #[tokio::test]
+    async fn test_encrypt_logs() {
+        let temp_dir = tempdir().unwrap();
+        let project = temp_dir.path().join("project");
+        let handler = |storage: Storage| {
+            let config = DaemonConfig {
+                data_dir: temp_dir.path().to_path_buf(),
+                ..Default::default()
+            };
+            let (shutdown_tx, _) = broadcast::channel(1);
+            DaemonHandler::new(
+                Arc::new(ProjectManager::new(&config)),
+                Arc::new(storage),
+                shutdown_tx,
+                std::time::Instant::now(),
+            )
+        };
+
+        let plain = Storage::new(temp_dir.path().to_path_buf());
+        for note in ["first", "second"] {
+            plain
+                .append_experience(&project, &serde_json::json!({ "note": note }))
+                .await
+                .unwrap();
+        }
+        // Without a key there is nothing to encrypt with
+        let response = handler(plain).handle(Request::EncryptLogs).await;
+        assert!(matches!(
+            response,
+            Response::Error {
+                code: ErrorCode::InvalidRequest,
+                ..
+            }
+        ));
+
+        let cipher = engram_indexer::storage::LogCipher::from_hex(
+            &engram_indexer::storage::LogCipher::generate_key(),
+        )
+        .unwrap();
+        let handler =
+            handler(Storage::new(temp_dir.path().to_path_buf()).with_cipher(Arc::new(cipher)));
+        for lines in [2, 0] {
+            let response = handler.handle(Request::EncryptLogs).await;
+            let Response::Ok {
+                data:
+                    Some(ResponseData::LogsEncrypted {
+                        projects,
+                        lines: encrypted,
+                    }),
+            } = response
+            else {
+                panic!("Expected LogsEncrypted response, got {:?}", response);
+            };
+            assert_eq!((projects, encrypted), (lines / 2, lines));
+        }
+        let notes: Vec<serde_json::Value> = handler
+            .storage
+            .load_experiences(&project, 10)
+            .await
+            .unwrap();
+        assert_eq!(notes.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_memory_uses_project_retention_config() {
+        let temp_dir = tempdir().unwrap();
+        let handler = test_handler();
+
+        let project_dir = temp_dir.path().join("configured_project");
+        std::fs::create_dir_all(project_dir.join(".engram")).unwrap();
+        std::fs::write(
+            project_dir.join(".engram/config.toml"),
+            "[memory]\ncompact_threshold = 1\n",
+        )
+        .unwrap();
+        handler
+            .handle(Request::InitProject {
+                cwd: project_dir.clone(),
+                async_mode: false,
+                artifact_allowlist: vec![],
+            })
+            .await;
+
+        for _ in 0..3 {
+            let response = handler
+                .handle(Request::MemoryPut {
+                    cwd: project_dir.clone(),
+                    entry: MemoryEntry {
+                        id: "mem-1".to_string(),
+                        kind: "note".to_string(),
+                        content: "Same note".to_string(),
+                        tags: vec![],
+                        created_at: 0,
+                        updated_at: 0,
+                        session_id: None,
+                        subagent_id: None,
+                        deleted: false,
+                        experience_id: None,
+                        visibility: MemoryVisibility::Project,
+                    },
+                })
+                .await;
+            assert_eq!(extract_memory_ack(response), "mem-1");
+        }
+
+        // Every write already compacted the log under the project threshold
+        let response = handler
+            .handle(Request::MemoryCompact { cwd: project_dir })
+            .await;
+        assert!(matches!(
+            response,
+            Response::Ok {
+                data: Some(ResponseData::MemoryCompacted {
+                    lines_before: 1,
+                    dropped_versions: 0,
+                    ..
+                })
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_read_only_project_rejects_writes() {
+        let temp_dir = tempdir().unwrap();
+        let handler = test_handler();
+        let project_dir = temp_dir.path().join("project");
+        std::fs::create_dir_all(project_dir.join(".engram")).unwrap();
+        std::fs::write(
+            project_dir.join(".engram/config.toml"),
+            "read_only = true\n",
+        )
+        .unwrap();
+        handler
+            .handle(Request::InitProject {
+                cwd: project_dir.clone(),
+                async_mode: false,
+                artifact_allowlist: vec![],
+            })
+            .await;
+
+        let response = handler
+            .handle(Request::ConsolidateExperiences {
+                cwd: project_dir.clone(),
+            })
+            .await;
+        assert!(matches!(
+            response,
+            Response::Error {
+                code: ErrorCode::ReadOnly,
+                ..
+            }
+        ));
+
+        // Reads still work
+        let response = handler
+            .handle(Request::CheckInit { cwd: project_dir })
+            .await;
+        assert!(matches!(response, Response::Ok { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_consolidate_experiences() {
+        let temp_dir = tempdir().unwrap();
+        let handler = test_handler();
+        let project_dir = temp_dir.path().join("project");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        handler
+            .handle(Request::InitProject {
+                cwd: project_dir.clone(),
+                async_mode: false,
+                artifact_allowlist: vec![],
+            })
+            .await;
+
+        for decision in ["Split the parser", "Split the parser", "Added a cache"] {
+            handler
+                .storage
+                .append_experience(
+                    &project_dir,
+                    &engram_context::Experience::new("agent", decision),
+                )
+                .await
+                .unwrap();
+        }
+
+        let response = handler
+            .handle(Request::ConsolidateExperiences { cwd: project_dir })
+            .await;
+        assert!(matches!(
+            response,
+            Response::Ok {
+                data: Some(ResponseData::ExperiencesConsolidated {
+                    experiences_before: 3,
+                    experiences_after: 2,
+                    merged: 1,
+                    summaries: 0,
+                    ..
+                })
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_memory_export_import() {
+        let temp_dir = tempdir().unwrap();
+        let handler = test_handler();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+        for project_dir in [&source, &target] {
+            std::fs::create_dir_all(project_dir).unwrap();
+            handler
+                .handle(Request::InitProject {
+                    cwd: project_dir.clone(),
+                    async_mode: false,
+                    artifact_allowlist: vec![],
+                })
+                .await;
+        }
+
+        handler
+            .handle(Request::MemoryPut {
+                cwd: source.clone(),
+                entry: MemoryEntry {
+                    id: "mem-1".to_string(),
+                    kind: "decision".to_string(),
+                    content: "Tokens expire after an hour".to_string(),
+                    tags: vec![],
+                    created_at: 0,
+                    updated_at: 0,
+                    session_id: None,
+                    subagent_id: None,
+                    deleted: false,
+                    experience_id: Some("exp-1".to_string()),
+                    visibility: MemoryVisibility::Project,
+                },
+            })
+            .await;
+        let mut experience =
+            engram_context::Experience::new("agent", "Switched to JWT").with_id("exp-1");
+        experience.timestamp = 1_700_000_000;
+        handler
+            .storage
+            .append_experience(&source, &experience)
+            .await
+            .unwrap();
+
+        let Response::Ok {
+            data: Some(ResponseData::MemoryBundle { bundle }),
+        } = handler
+            .handle(Request::ExportMemory {
+                cwd: source.clone(),
+            })
+            .await
+        else {
+            panic!("Expected MemoryBundle response");
+        };
+        assert_eq!(bundle.entries.len(), 1);
+        assert_eq!(bundle.experiences[0].id.as_deref(), Some("exp-1"));
+
+        // As written to and read back from a file
+        let bundle: MemoryBundle =
+            serde_json::from_str(&serde_json::to_string(&bundle).unwrap()).unwrap();
+        let import = |bundle| Request::ImportMemory {
+            cwd: target.clone(),
+            bundle,
+        };
+        let response = handler.handle(import(bundle.clone())).await;
+        assert!(matches!(
+            response,
+            Response::Ok {
+                data: Some(ResponseData::MemoryImported {
+                    entries_imported: 1,
+                    entries_skipped: 0,
+                    experiences_imported: 1,
+                    experiences_skipped: 0,
+                })
+            }
+        ));
+        let response = handler.handle(import(bundle.clone())).await;
+        assert!(matches!(
+            response,
+            Response::Ok {
+                data: Some(ResponseData::MemoryImported {
+                    entries_imported: 0,
+                    entries_skipped: 1,
+                    experiences_imported: 0,
+                    experiences_skipped: 1,
+                })
+            }
+        ));
+
+        let entry = handler
+            .memory_store
+            .get(&target, "mem-1")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(entry, bundle.entries[0]);
+        let experiences: Vec<engram_context::Experience> =
+            handler.storage.load_all_experiences(&target).await.unwrap();
+        assert_eq!(experiences[0].session_id, experience.session_id);
+        assert_eq!(experiences[0].timestamp, 1_700_000_000);
+
+        let newer = MemoryBundle {
+            format: MEMORY_BUNDLE_FORMAT + 1,
+            ..bundle
+        };
+        assert!(matches!(
+            handler.handle(import(newer)).await,
+            Response::Error {
+                code: ErrorCode::Unsupported,
+                ..
+            }
+        ));
+    }
+
+    fn extract_memory_ack(response: Response) -> String {
+        if let Response::Ok {
+            data: Some(ResponseData::MemoryAck { id }),
+        } = response
+        {
+            id
+        } else {
+            panic!("Expected MemoryAck response");
+        }
+    }
+
+    fn extract_memory_entry(response: Response) -> MemoryEntry {
+        if let Response::Ok {
+            data: Some(ResponseData::MemoryEntry { entry }),
+        } = response
+        {
+            entry
+        } else {
+            panic!("Expected MemoryEntry response");
+        }
+    }
+
+    fn extract_memory_entries(response: Response) -> Vec<MemoryEntry> {
+        if let Response::Ok {
+            data: Some(ResponseData::MemoryEntries { entries }),
+        } = response
+        {
+            entries
+        } else {
+            panic!("Expected MemoryEntries response");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ping() {
+        let handler = test_handler();
+        let response = handler.handle(Request::Ping).await;
+
+        if let Response::Ok {
+            data: Some(ResponseData::Pong { .. }),
+        } = response
+        {
+            // Success
+        } else {
+            panic!("Expected Pong response");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_health_check() {
+        let handler = test_handler();
+        let response = handler.handle(Request::HealthCheck).await;
+        let Response::Ok {
+            data: Some(ResponseData::Health {
+                status, components, ..
+            }),
+        } = response
+        else {
+            panic!("Expected Health response, got {:?}", response);
+        };
+
+        let names: Vec<&str> = components.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["data_dir", "watcher", "enrichment", "memory"]);
+        assert_eq!(status, health::overall(&components));
+        // No watch manager reports to a bare handler
+        assert_eq!(components[1].message, "file watching is disabled");
+        assert_eq!(components[2].status, CheckStatus::Ok);
+
+        // Once one does, it is expected to report
+        let _watch = handler.watch_manager(&WatchConfig::default());
+        let response = handler.handle(Request::HealthCheck).await;
+        let Response::Ok {
+            data: Some(ResponseData::Health { components, .. }),
+        } = response
+        else {
+            panic!("Expected Health response, got {:?}", response);
+        };
+        assert_eq!(components[1].message, "starting");
+    }
+
+    #[tokio::test]
+    async fn test_recovery_reported_once() {
+        let handler = test_handler().with_recovery(RecoveryInfo {
+            previous_pid: 42,
+            last_heartbeat: 0,
+            actions: vec!["Completed the last entry of experience.jsonl".to_string()],
+            problems: vec![],
+        });
+        let recovery = |response: Response| match response {
+            Response::Ok {
+                data: Some(ResponseData::Status { recovery, .. }),
+            } => recovery,
+            _ => panic!("Expected Status response"),
+        };
+
+        let first = recovery(handler.handle(Request::Status).await).unwrap();
+        assert_eq!(first.previous_pid, 42);
+        assert!(recovery(handler.handle(Request::Status).await).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_hello() {
+        let handler = test_handler();
+        let response = handler
+            .handle(Request::Hello {
+                client_version: "0.0.1".to_string(),
+                protocol_version: 0,
+                supported_features: vec![],
+            })
+            .await;
+
+        let Response::Ok {
+            data:
+                Some(ResponseData::Hello {
+                    protocol_version,
+                    features,
+                    ..
+                }),
+        } = response
+        else {
+            panic!("Expected Hello response");
+        };
+        assert_eq!(protocol_version, engram_ipc::PROTOCOL_VERSION);
+        assert!(features.iter().any(|f| f == "batch"));
+    }
+
+    #[tokio::test]
+    async fn test_status() {
+        let handler = test_handler();
+        let response = handler.handle(Request::Status).await;
+
+        if let Response::Ok {
+            data: Some(ResponseData::Status { version, .. }),
+        } = response
+        {
+            assert_eq!(version, env!("CARGO_PKG_VERSION"));
+        } else {
+            panic!("Expected Status response");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_status_reports_experiments() {
+        let mut flags = engram_core::ExperimentFlags::default();
+        flags.set(Experiment::IncrementalIndexing, true);
+        let handler = test_handler().with_experiments(Arc::new(Experiments::fixed(flags)));
+
+        let response = handler.handle(Request::Status).await;
+        if let Response::Ok {
+            data: Some(ResponseData::Status { experiments, .. }),
+        } = response
+        {
+            assert_eq!(experiments.len(), Experiment::ALL.len());
+            let enabled: Vec<_> = experiments.iter().filter(|e| e.enabled).collect();
+            assert_eq!(enabled.len(), 1);
+            assert_eq!(enabled[0].name, "incremental_indexing");
+        } else {
+            panic!("Expected Status response");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_status_reports_request_usage() {
+        let handler = test_handler();
+        handler.handle(Request::Ping).await;
+        handler.handle(Request::Ping).await;
+
+        let response = handler.handle(Request::Status).await;
+        if let Response::Ok {
+            data:
+                Some(ResponseData::Status {
+                    requests_total,
+                    request_usage,
+                    latency,
+                    ..
+                }),
+        } = response
+        {
+            assert_eq!(requests_total, 2);
+            let ping = request_usage.iter().find(|u| u.action == "ping").unwrap();
+            assert_eq!(ping.count, 2);
+            let ping = latency.iter().find(|l| l.action == "ping").unwrap();
+            assert_eq!(ping.count, 2);
+            assert!(ping.p50_ms <= ping.p95_ms && ping.p95_ms <= ping.p99_ms);
+        } else {
+            panic!("Expected Status response");
+        }
+    }
+
+    /// Status response of `handler`.
+    async fn status(handler: &DaemonHandler) -> (u64, Vec<RequestUsage>, Vec<EndpointLatency>) {
+        let response = handler.handle(Request::Status).await;
+        let Response::Ok {
+            data:
+                Some(ResponseData::Status {
+                    requests_total,
+                    request_usage,
+                    latency,
+                    ..
+                }),
+        } = response
+        else {
+            panic!("Expected Status response, got {:?}", response);
+        };
+        (requests_total, request_usage, latency)
+    }
+
+    #[tokio::test]
+    async fn test_status_reports_latency_per_operation() {
+        let temp_dir = tempdir().unwrap();
+        let handler = test_handler();
+        let project_dir = temp_dir.path().join("project");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        std::fs::write(project_dir.join("main.rs"), "fn main() {}\n").unwrap();
+
+        handler
+            .handle(Request::InitProject {
+                cwd: project_dir.clone(),
+                async_mode: false,
+                artifact_allowlist: vec![],
+            })
+            .await;
+        for _ in 0..2 {
+            handler
+                .handle(Request::GetContext {
+                    cwd: project_dir.clone(),
+                    prompt: None,
+                    no_cache: true,
+                    cold_start: false,
+                    read_only_subagent: None,
+                    focus: vec![],
+                    changed_since: None,
+                    structured: false,
+                    profile: None,
+                    session_id: None,
+                })
+                .await;
+        }
+        for i in 0..3 {
+            handler
+                .handle(Request::MemoryPut {
+                    cwd: project_dir.clone(),
+                    entry: MemoryEntry {
+                        id: format!("mem-{}", i),
+                        kind: "note".to_string(),
+                        content: "A note".to_string(),
+                        tags: vec![],
+                        created_at: 0,
+                        updated_at: 0,
+                        session_id: None,
+                        subagent_id: None,
+                        deleted: false,
+                        experience_id: None,
+                        visibility: MemoryVisibility::Project,
+                    },
+                })
+                .await;
+        }
+
+        // Each operation gets its own figures, keyed by request name
+        let (_, _, latency) = status(&handler).await;
+        let count = |action: &str| {
+            let endpoint = latency.iter().find(|l| l.action == action).unwrap();
+            assert!(endpoint.p50_ms <= endpoint.p95_ms && endpoint.p95_ms <= endpoint.p99_ms);
+            endpoint.count
+        };
+        assert_eq!(count("init_project"), 1);
+        assert_eq!(count("get_context"), 2);
+        assert_eq!(count("memory_put"), 3);
+        assert!(latency.iter().all(|l| l.action != "status"));
+
+        // Percentiles come from that operation's samples alone
+        for ms in 1..=100 {
+            handler
+                .latency
+                .record("get_context", Duration::from_millis(ms));
+        }
+        handler
+            .latency
+            .record("memory_put", Duration::from_millis(500));
+        let (_, _, latency) = status(&handler).await;
+        let get_context = latency.iter().find(|l| l.action == "get_context").unwrap();
+        assert_eq!(get_context.count, 102);
+        assert!(
+            (49.0..=51.0).contains(&get_context.p50_ms),
+            "{:?}",
+            get_context
+        );
+        assert!(
+            (94.0..=96.0).contains(&get_context.p95_ms),
+            "{:?}",
+            get_context
+        );
+        assert!(
+            (98.0..=100.0).contains(&get_context.p99_ms),
+            "{:?}",
+            get_context
+        );
+        let memory_put = latency.iter().find(|l| l.action == "memory_put").unwrap();
+        assert_eq!(memory_put.p99_ms, 500.0);
+    }
+
+    #[tokio::test]
+    async fn test_handle_records_every_request() {
+        let handler = test_handler();
+        let (total, _, _) = status(&handler).await;
+        assert_eq!(total, 0);
+
+        handler.handle(Request::Ping).await;
+        handler.handle(Request::Ping.tracked("r1", None)).await;
+        handler
+            .handle(Request::CheckInit {
+                cwd: PathBuf::from("/nonexistent/project"),
+            })
+            .await;
+        // Failed requests are measured too
+        let response = handler
+            .handle(Request::ProjectStats {
+                cwd: PathBuf::from("/nonexistent/project"),
+                top: None,
+            })
+            .await;
+        assert!(matches!(response, Response::Error { .. }));
+
+        let (total, usage, latency) = status(&handler).await;
+        // The first status request, then four more
+        assert_eq!(total, 5);
+        let used = |action: &str| usage.iter().find(|u| u.action == action).map(|u| u.count);
+        assert_eq!(used("ping"), Some(2));
+        assert_eq!(used("tracked"), None);
+        assert_eq!(used("check_init"), Some(1));
+        assert_eq!(used("project_stats"), Some(1));
+        assert_eq!(used("status"), Some(1));
+
+        // Latency is kept per endpoint, tracked requests by their inner action
+        let endpoint = |action: &str| latency.iter().find(|l| l.action == action);
+        assert_eq!(endpoint("ping").unwrap().count, 2);
+        assert_eq!(endpoint("check_init").unwrap().count, 1);
+        assert_eq!(endpoint("project_stats").unwrap().count, 1);
+        assert!(endpoint("tracked").is_none());
+        for endpoint in &latency {
+            assert!(endpoint.p50_ms <= endpoint.p99_ms, "{:?}", endpoint);
+        }
+        assert_eq!(
+            handler.metrics.requests_total.load(Ordering::Relaxed),
+            total + 1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_status_reports_slo_burn_rate() {
+        let slo = SloConfig {
+            objectives: vec![
+                engram_core::SloObjective {
+                    action: "ping".to_string(),
+                    percentile: 99.0,
+                    threshold_ms: 60_000,
+                    window_secs: 60,
+                },
+                engram_core::SloObjective {
+                    action: "ping".to_string(),
+                    percentile: 50.0,
+                    threshold_ms: 0,
+                    window_secs: 60,
+                },
+            ],
+            min_requests: 1,
+            ..Default::default()
+        };
+        let handler = test_handler().with_slo(&slo);
+        handler.handle(Request::Ping).await;
+
+        let response = handler.handle(Request::Status).await;
+        if let Response::Ok {
+            data: Some(ResponseData::Status { slos, .. }),
+        } = response
+        {
+            assert_eq!(slos.len(), 2);
+            assert_eq!(slos[0].name, "ping p99 < 60000ms");
+            assert_eq!(slos[0].total, 1);
+            assert_eq!(slos[0].burn_rate, 0.0);
+            assert!(!slos[0].at_risk);
+            assert_eq!(slos[1].slow, 1);
+            assert!(slos[1].at_risk);
+        } else {
+            panic!("Expected Status response");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_only_subagent_memory_is_staged() {
+        let temp_dir = tempdir().unwrap();
+        let handler = test_handler();
+
+        let project_dir = temp_dir.path().join("read_only_project");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        std::fs::write(project_dir.join("main.rs"), "fn main() {}").unwrap();
+        handler
+            .handle(Request::InitProject {
+                cwd: project_dir.clone(),
+                async_mode: false,
+                artifact_allowlist: vec![],
+            })
+            .await;
+
+        let response = handler
+            .handle(Request::GetContext {
+                cwd: project_dir.clone(),
+                prompt: None,
+                no_cache: false,
+                cold_start: false,
+                read_only_subagent: Some("sub-1".to_string()),
+                focus: vec![],
+                changed_since: None,
+                structured: false,
+                profile: None,
+                session_id: None,
+            })
+            .await;
+        if let Response::Ok {
+            data: Some(ResponseData::Context { context, .. }),
+        } = response
+        {
+            assert!(context.contains("Memory is read-only"));
+        } else {
+            panic!("Expected Context response");
+        }
+
+        let put = |subagent_id: &str, id: &str| Request::MemoryPut {
+            cwd: project_dir.clone(),
+            entry: MemoryEntry {
+                id: id.to_string(),
+                kind: "note".to_string(),
+                content: "Subagent finding".to_string(),
+                tags: vec![],
+                created_at: 0,
+                updated_at: 0,
+                session_id: None,
+                subagent_id: Some(subagent_id.to_string()),
+                deleted: false,
+                experience_id: None,
+                visibility: MemoryVisibility::Project,
+            },
+        };
+
+        let response = handler.handle(put("sub-1", "staged")).await;
+        assert!(matches!(
+            response,
+            Response::Ok {
+                data: Some(ResponseData::MemoryStaged { .. })
+            }
+        ));
+        // Other sub-agents still write directly
+        assert_eq!(
+            extract_memory_ack(handler.handle(put("sub-2", "direct")).await),
+            "direct"
+        );
+
+        let get_staged = Request::MemoryGet {
+            cwd: project_dir.clone(),
+            id: "staged".to_string(),
+        };
+        assert!(matches!(
+            handler.handle(get_staged.clone()).await,
+            Response::Error { .. }
+        ));
+
+        let response = handler
+            .handle(Request::ListPending {
+                cwd: project_dir.clone(),
+            })
+            .await;
+        if let Response::Ok {
+            data: Some(ResponseData::MemoryEntries { entries }),
+        } = response
+        {
+            assert_eq!(entries.len(), 1);
+            assert_eq!(entries[0].id, "staged");
+        } else {
+            panic!("Expected MemoryEntries response");
+        }
+
+        let response = handler
+            .handle(Request::ApprovePending {
+                cwd: project_dir.clone(),
+                ids: vec![],
+                reject: false,
+            })
+            .await;
+        assert!(matches!(
+            response,
+            Response::Ok {
+                data: Some(ResponseData::PendingResolved { ref ids })
+            } if ids == &["staged".to_string()]
+        ));
+        assert_eq!(
+            extract_memory_entry(handler.handle(get_staged).await).id,
+            "staged"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_batch_links_experience_and_memory() {
+        let temp_dir = tempdir().unwrap();
+        let handler = test_handler();
+
+        let project_dir = temp_dir.path().join("batch_project");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        handler
+            .handle(Request::InitProject {
+                cwd: project_dir.clone(),
+                async_mode: true,
+                artifact_allowlist: vec![],
+            })
+            .await;
+
+        let response = handler
+            .handle(Request::Batch {
+                requests: vec![
+                    Request::GraftExperience {
+                        cwd: project_dir.clone(),
+                        experience: engram_ipc::Experience {
+                            agent_id: "agent-1".to_string(),
+                            decision: "Split the parser module".to_string(),
+                            rationale: None,
+                            files_touched: vec![],
+                            timestamp: 0,
+                            id: None,
+                            memory_ids: vec![],
+                            summarizes: 0,
+                            session_id: None,
+                            outcome: None,
+                        },
+                    },
+                    Request::MemoryPut {
+                        cwd: project_dir.clone(),
+                        entry: MemoryEntry {
+                            id: String::new(),
+                            kind: "session_summary".to_string(),
+                            content: "Parser now lives in parser/".to_string(),
+                            tags: vec![],
+                            created_at: 0,
+                            updated_at: 0,
+                            session_id: None,
+                            subagent_id: None,
+                            deleted: false,
+                            experience_id: None,
+                            visibility: MemoryVisibility::Project,
+                        },
+                    },
+                ],
+            })
+            .await;
+
+        let responses = if let Response::Ok {
+            data: Some(ResponseData::Batch { responses }),
+        } = response
+        {
+            responses
+        } else {
+            panic!("Expected Batch response");
+        };
+        assert_eq!(responses.len(), 2);
+        assert!(matches!(responses[0], Response::Ack));
+        let memory_id = extract_memory_ack(responses[1].clone());
+
+        let entry = extract_memory_entry(
+            handler
+                .handle(Request::MemoryGet {
+                    cwd: project_dir.clone(),
+                    id: memory_id.clone(),
+                })
+                .await,
+        );
+        let experience_id = entry.experience_id.expect("memory should be linked");
+
+        // Experience grafting is fire-and-forget; wait for it to land.
+        let mut linked = None;
+        for _ in 0..50 {
+            let experiences: Vec<engram_context::Experience> = handler
+                .storage
+                .load_all_experiences(&project_dir)
+                .await
+                .unwrap_or_default();
+            linked = experiences.into_iter().find(|exp| exp.id == experience_id);
+            if linked.is_some() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert_eq!(
+            linked.expect("experience persisted").memory_ids,
+            vec![memory_id]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_batch_rejects_nested_batch() {
+        let handler = test_handler();
+        let response = handler
+            .handle(Request::Batch {
+                requests: vec![Request::Ping, Request::Batch { requests: vec![] }],
+            })
+            .await;
+
+        if let Response::Ok {
+            data: Some(ResponseData::Batch { responses }),
+        } = response
+        {
+            assert!(matches!(responses[0], Response::Ok { .. }));
+            assert!(matches!(
+                responses[1],
+                Response::Error {
+                    code: ErrorCode::InvalidRequest,
+                    ..
+                }
+            ));
+        } else {
+            panic!("Expected Batch response");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_init_project_reports_auto_excluded_dirs() {
+        let temp_dir = tempdir().unwrap();
+        let handler = test_handler();
+
+        let project_dir = temp_dir.path().join("artifact_project");
+        std::fs::create_dir_all(project_dir.join("node_modules/dep")).unwrap();
+        std::fs::create_dir_all(project_dir.join("dist")).unwrap();
+        std::fs::write(project_dir.join("package.json"), "{}").unwrap();
+        std::fs::write(project_dir.join("index.ts"), "export const a = 1;").unwrap();
+        std::fs::write(project_dir.join("node_modules/dep/index.js"), "").unwrap();
+        std::fs::write(project_dir.join("dist/index.js"), "").unwrap();
+
+        let response = handler
+            .handle(Request::InitProject {
+                cwd: project_dir.clone(),
+                async_mode: false,
+                artifact_allowlist: vec![PathBuf::from("dist")],
+            })
+            .await;
+
+        if let Response::Ok {
+            data:
+                Some(ResponseData::ScanReport {
+                    file_count,
+                    auto_excluded,
+                }),
+        } = response
+        {
+            assert_eq!(file_count, 3);
+            assert_eq!(auto_excluded.len(), 1);
+            assert_eq!(auto_excluded[0].path, PathBuf::from("node_modules"));
+        } else {
+            panic!("Expected ScanReport response");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unload_project() {
+        let temp_dir = tempdir().unwrap();
+        let handler = test_handler();
+
+        let project_dir = temp_dir.path().join("unload_project");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        std::fs::write(project_dir.join("main.rs"), "fn main() {}").unwrap();
+        handler
+            .handle(Request::InitProject {
+                cwd: project_dir.clone(),
+                async_mode: false,
+                artifact_allowlist: vec![],
+            })
+            .await;
+        handler
+            .handle(Request::MemoryPut {
+                cwd: project_dir.clone(),
+                entry: MemoryEntry {
+                    id: "mem-1".to_string(),
+                    kind: "note".to_string(),
+                    content: "Survives unload".to_string(),
+                    tags: vec![],
+                    created_at: 0,
+                    updated_at: 0,
+                    session_id: None,
+                    subagent_id: None,
+                    deleted: false,
+                    experience_id: None,
+                    visibility: MemoryVisibility::Project,
+                },
+            })
+            .await;
+
+        for expected in [true, false] {
+            let response = handler
+                .handle(Request::UnloadProject {
+                    cwd: project_dir.clone(),
+                })
+                .await;
+            assert!(matches!(
+                response,
+                Response::Ok {
+                    data: Some(ResponseData::Unloaded { was_loaded })
+                } if was_loaded == expected
+            ));
+            assert_eq!(handler.project_manager.loaded_count().await, 0);
+        }
+
+        let response = handler
+            .handle(Request::MemoryGet {
+                cwd: project_dir.clone(),
+                id: "mem-1".to_string(),
+            })
+            .await;
+        assert!(matches!(
+            response,
+            Response::Ok {
+                data: Some(ResponseData::MemoryEntry { .. })
+            }
+        ));
+
+        let response = handler
+            .handle(Request::UnloadProject {
+                cwd: temp_dir.path().join("missing"),
+            })
+            .await;
+        assert!(matches!(
+            response,
+            Response::Error {
+                code: ErrorCode::NotInitialized,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_moved_project_keeps_memory() {
+        let temp_dir = tempdir().unwrap();
+        let handler = test_handler();
+
+        let project_dir = temp_dir.path().join("checkout");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        std::fs::write(project_dir.join("main.rs"), "fn main() {}").unwrap();
+        handler
+            .handle(Request::InitProject {
+                cwd: project_dir.clone(),
+                async_mode: false,
+                artifact_allowlist: vec![],
+            })
+            .await;
+        handler
+            .handle(Request::MemoryPut {
+                cwd: project_dir.clone(),
+                entry: MemoryEntry {
+                    id: "mem-1".to_string(),
+                    kind: "note".to_string(),
+                    content: "Survives the move".to_string(),
+                    tags: vec![],
+                    created_at: 0,
+                    updated_at: 0,
+                    session_id: None,
+                    subagent_id: None,
+                    deleted: false,
+                    experience_id: None,
+                    visibility: MemoryVisibility::Project,
+                },
+            })
+            .await;
+
+        let moved_dir = temp_dir.path().join("moved");
+        std::fs::rename(&project_dir, &moved_dir).unwrap();
+        let moved_dir = moved_dir.canonicalize().unwrap();
+        let response = handler
+            .handle(Request::RelinkProject {
+                cwd: moved_dir.clone(),
+                from: None,
+            })
+            .await;
+        assert!(matches!(
+            response,
+            Response::Ok {
+                data: Some(ResponseData::ProjectRelinked { previous_path, adopted: false, .. })
+            } if previous_path == temp_dir.path().canonicalize().unwrap().join("checkout")
+        ));
+
+        let response = handler
+            .handle(Request::MemoryGet {
+                cwd: moved_dir.clone(),
+                id: "mem-1".to_string(),
+            })
+            .await;
+        assert!(matches!(
+            response,
+            Response::Ok {
+                data: Some(ResponseData::MemoryEntry { .. })
+            }
+        ));
+
+        let response = handler
+            .handle(Request::RelinkProject {
+                cwd: temp_dir.path().to_path_buf(),
+                from: None,
+            })
+            .await;
+        assert!(matches!(
+            response,
+            Response::Error {
+                code: ErrorCode::NotInitialized,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_export_import_project() {
+        let temp_dir = tempdir().unwrap();
+        let handler = test_handler();
+        let archive = temp_dir.path().join("project.tar.gz");
+
+        let project_dir = temp_dir.path().join("exported");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        std::fs::write(project_dir.join("main.rs"), "fn main() {}").unwrap();
+        handler
+            .handle(Request::InitProject {
+                cwd: project_dir.clone(),
+                async_mode: false,
+                artifact_allowlist: vec![],
+            })
+            .await;
+        handler
+            .handle(Request::MemoryPut {
+                cwd: project_dir.clone(),
+                entry: MemoryEntry {
+                    id: "mem-1".to_string(),
+                    kind: "note".to_string(),
+                    content: "Travels in the archive".to_string(),
+                    tags: vec![],
+                    created_at: 0,
+                    updated_at: 0,
+                    session_id: None,
+                    subagent_id: None,
+                    deleted: false,
+                    experience_id: None,
+                    visibility: MemoryVisibility::Project,
+                },
+            })
+            .await;
+
+        let response = handler
+            .handle(Request::ExportProject {
+                cwd: project_dir.clone(),
+                output: archive.clone(),
+            })
+            .await;
+        assert!(matches!(
+            response,
+            Response::Ok {
+                data: Some(ResponseData::ProjectArchive { files, .. })
+            } if files > 0
+        ));
+
+        // A fresh checkout without the project id, as on another machine
+        let imported_dir = temp_dir.path().join("imported");
+        std::fs::create_dir_all(&imported_dir).unwrap();
+        std::fs::write(imported_dir.join("main.rs"), "fn main() {}").unwrap();
+        let response = handler
+            .handle(Request::ImportProject {
+                cwd: imported_dir.clone(),
+                archive: archive.clone(),
+            })
+            .await;
+        assert!(matches!(
+            response,
+            Response::Ok {
+                data: Some(ResponseData::ProjectArchive { source_path, .. })
+            } if source_path == project_dir.canonicalize().unwrap()
+        ));
+
+        let response = handler
+            .handle(Request::MemoryGet {
+                cwd: imported_dir.clone(),
+                id: "mem-1".to_string(),
+            })
+            .await;
+        assert!(matches!(
+            response,
+            Response::Ok {
+                data: Some(ResponseData::MemoryEntry { .. })
+            }
+        ));
+        let tree = handler
+            .project_manager
+            .get_tree(&imported_dir)
+            .await
+            .unwrap();
+        assert_eq!(tree.root_path, imported_dir.canonicalize().unwrap());
+        assert_eq!(tree.file_count, 1);
+
+        let response = handler
+            .handle(Request::ExportProject {
+                cwd: temp_dir.path().join("missing"),
+                output: archive,
+            })
+            .await;
+        assert!(matches!(
+            response,
+            Response::Error {
+                code: ErrorCode::NotInitialized,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_prune_tree_removes_paths() {
+        let temp_dir = tempdir().unwrap();
+        let handler = test_handler();
+
+        let project_dir = temp_dir.path().join("prune_project");
+        std::fs::create_dir_all(project_dir.join("vendor/sdk")).unwrap();
+        std::fs::write(project_dir.join("main.rs"), "fn main() {}").unwrap();
+        std::fs::write(project_dir.join("vendor/sdk/big.rs"), "fn big() {}").unwrap();
+
+        handler
+            .handle(Request::InitProject {
+                cwd: project_dir.clone(),
+                async_mode: false,
+                artifact_allowlist: vec![],
+            })
+            .await;
+
+        let response = handler
+            .handle(Request::PruneTree {
+                cwd: project_dir.clone(),
+                globs: vec!["vendor".to_string()],
+            })
+            .await;
+        if let Response::Ok {
+            data:
+                Some(ResponseData::PruneReport {
+                    removed_dirs,
+                    removed_files,
+                    ..
+                }),
+        } = response
+        {
+            assert_eq!((removed_dirs, removed_files), (2, 1));
+        } else {
+            panic!("Expected PruneReport response");
+        }
+
+        let tree = handler
+            .project_manager
+            .get_tree(&project_dir)
+            .await
+            .unwrap();
+        assert!(tree.find_node_by_path(&PathBuf::from("vendor")).is_none());
+        assert_eq!(tree.file_count, 1);
+
+        let response = handler
+            .handle(Request::PruneTree {
+                cwd: project_dir,
+                globs: vec!["src/[".to_string()],
+            })
+            .await;
+        assert!(matches!(
+            response,
+            Response::Error {
+                code: ErrorCode::InvalidRequest,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_list_and_cancel_jobs() {
+        let handler = test_handler();
+
+        let guard = handler
+            .project_manager
+            .jobs()
+            .start(JobKind::Indexing, Path::new("/project"));
+
+        let response = handler.handle(Request::ListJobs).await;
+        if let Response::Ok {
+            data: Some(ResponseData::Jobs { jobs }),
+        } = response
+        {
+            assert_eq!(jobs.len(), 1);
+            assert_eq!(jobs[0].id, guard.id());
+            assert_eq!(jobs[0].kind, "indexing");
+        } else {
+            panic!("Expected Jobs response");
+        }
+
+        let response = handler.handle(Request::CancelJob { id: guard.id() }).await;
+        assert!(matches!(response, Response::Ok { .. }));
+        assert!(guard.progress().is_cancelled());
+
+        drop(guard);
+        let response = handler.handle(Request::CancelJob { id: 1 }).await;
+        assert!(matches!(response, Response::Error { .. }));
+    }
+
+    async fn init_progress(handler: &DaemonHandler, cwd: &str) -> (Option<String>, usize, usize) {
+        let request = Request::GetInitProgress {
+            cwd: PathBuf::from(cwd),
+        };
+        match handler.handle(request).await {
+            Response::Ok {
+                data:
+                    Some(ResponseData::InitProgress {
+                        phase,
+                        processed,
+                        total,
+                        ..
+                    }),
+            } => (phase, processed, total),
+            other => panic!("Expected InitProgress response, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_events() {
+        let temp_dir = tempdir().unwrap();
+        let handler = test_handler();
+
+        let project_dir = temp_dir.path().join("project");
+        std::fs::create_dir_all(project_dir.join("src")).unwrap();
+        std::fs::write(project_dir.join("src/main.rs"), "fn main() {}").unwrap();
+        handler
+            .handle(Request::InitProject {
+                cwd: project_dir.clone(),
+                async_mode: false,
+                artifact_allowlist: vec![],
+            })
+            .await;
+        handler
+            .handle(Request::MemoryCompact {
+                cwd: project_dir.clone(),
+            })
+            .await;
+
+        let events = match handler
+            .handle(Request::GetEvents {
+                since: 0,
+                limit: 100,
+            })
+            .await
+        {
+            Response::Ok {
+                data: Some(ResponseData::Events { events }),
+            } => events,
+            other => panic!("Expected Events response, got {:?}", other),
+        };
+        let kinds: Vec<_> = events.iter().map(|e| e.kind.as_str()).collect();
+        assert!(kinds.contains(&"index_run"));
+        assert_eq!(kinds.last(), Some(&"memory_compacted"));
+        assert!(events.windows(2).all(|w| w[0].seq < w[1].seq));
+
+        let last = events.last().unwrap().seq;
+        let response = handler
+            .handle(Request::GetEvents {
+                since: last,
+                limit: 100,
+            })
+            .await;
+        assert!(matches!(
+            response,
+            Response::Ok {
+                data: Some(ResponseData::Events { events }),
+            } if events.is_empty()
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_get_init_progress() {
+        let handler = test_handler();
+
+        assert_eq!(init_progress(&handler, "/project").await, (None, 0, 0));
+
+        let enrichment = handler
+            .project_manager
+            .jobs()
+            .start(JobKind::Enrichment, Path::new("/project"));
+        enrichment.progress().start_phase(ScanPhase::Enriching, 5);
+        let indexing = handler
+            .project_manager
+            .jobs()
+            .start(JobKind::Indexing, Path::new("/project"));
+        indexing.progress().start_phase(ScanPhase::Parsing, 10);
+        indexing.progress().advance();
+
+        assert_eq!(
+            init_progress(&handler, "/project/src").await,
+            (Some("parsing".to_string()), 1, 10)
+        );
+        assert_eq!(init_progress(&handler, "/other").await, (None, 0, 0));
+
+        drop(indexing);
+        assert_eq!(
+            init_progress(&handler, "/project").await,
+            (Some("enriching".to_string()), 0, 5)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_debug_snapshot_hides_names() {
+        let temp_dir = tempdir().unwrap();
+        let handler = test_handler();
+
+        let project_dir = temp_dir.path().join("confidential_project");
+        std::fs::create_dir_all(project_dir.join("payroll")).unwrap();
+        std::fs::write(project_dir.join("payroll/salaries.rs"), "fn secret_fn() {}").unwrap();
+
+        handler
+            .handle(Request::InitProject {
+                cwd: project_dir.clone(),
+                async_mode: false,
+                artifact_allowlist: vec![],
+            })
+            .await;
+
+        let response = handler
+            .handle(Request::DebugSnapshot {
+                cwd: project_dir.clone(),
+            })
+            .await;
+
+        if let Response::Ok {
+            data: Some(ResponseData::DebugSnapshot { project, structure }),
+        } = response
+        {
+            assert_eq!(project.unwrap().file_count, 1);
+            let json = serde_json::to_string(&structure).unwrap();
+            assert!(!json.contains("payroll"));
+            assert!(!json.contains("salaries"));
+            assert!(!json.contains("secret_fn"));
+            assert!(structure.iter().any(|n| n.name.ends_with(".rs")));
+        } else {
+            panic!("Expected DebugSnapshot response");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_returns_ranked_hits() {
+        let temp_dir = tempdir().unwrap();
+        let handler = test_handler();
+
+        let project_dir = temp_dir.path().join("project");
+        std::fs::create_dir_all(project_dir.join("src")).unwrap();
+        std::fs::write(
+            project_dir.join("src/billing.rs"),
+            "fn compute_invoice_total() {}\nfn send_reminder_email() {}\n",
+        )
+        .unwrap();
+
+        let search = |query: &str, limit| Request::Search {
+            cwd: project_dir.clone(),
+            query: query.to_string(),
+            limit,
+            explain: false,
+        };
+
+        let response = handler.handle(search("invoice total", 5)).await;
+        assert!(matches!(
+            response,
+            Response::Error {
+                code: ErrorCode::NotInitialized,
+                ..
+            }
+        ));
+
+        handler
+            .handle(Request::InitProject {
+                cwd: project_dir.clone(),
+                async_mode: false,
+                artifact_allowlist: vec![],
+            })
+            .await;
+
+        let response = handler.handle(search("invoice total", 1)).await;
+        if let Response::Ok {
+            data: Some(ResponseData::SearchResults { hits }),
+        } = response
+        {
+            assert_eq!(hits.len(), 1);
+            assert_eq!(hits[0].name, "compute_invoice_total");
+            assert_eq!(hits[0].kind, "function");
+            assert_eq!(hits[0].line, Some(1));
+            assert_eq!(hits[0].path, PathBuf::from("src/billing.rs"));
+            assert_eq!(hits[0].explanation, None);
+        } else {
+            panic!("Expected SearchResults response, got {:?}", response);
+        }
+
+        let response = handler
+            .handle(Request::Search {
+                cwd: project_dir.clone(),
+                query: "invoice total".to_string(),
+                limit: 1,
+                explain: true,
+            })
+            .await;
+        if let Response::Ok {
+            data: Some(ResponseData::SearchResults { hits }),
+        } = response
+        {
+            assert!(matches!(
+                hits[0].explanation.as_deref(),
+                Some([MatchReason::VectorSimilarity { similarity }]) if *similarity > 0.0
+            ));
+        } else {
+            panic!("Expected SearchResults response, got {:?}", response);
+        }
+
+        let response = handler.handle(search("  ", 5)).await;
+        assert!(matches!(
+            response,
+            Response::Error {
+                code: ErrorCode::InvalidRequest,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_search_explains_hits() {
+        let temp_dir = tempdir().unwrap();
+        let handler = test_handler();
+
+        let project_dir = temp_dir.path().join("project");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        std::fs::write(project_dir.join("app.py"), "import auth\n").unwrap();
+        std::fs::write(
+            project_dir.join("auth.py"),
+            "import db\n\ndef login_user():\n    pass\n",
+        )
+        .unwrap();
+        std::fs::write(
+            project_dir.join("db.py"),
+            "def connect_database():\n    pass\n",
+        )
+        .unwrap();
+        handler
+            .handle(Request::InitProject {
+                cwd: project_dir.clone(),
+                async_mode: false,
+                artifact_allowlist: vec![],
+            })
+            .await;
+
+        let explain = |query: &str| {
+            let request = Request::Search {
+                cwd: project_dir.clone(),
+                query: query.to_string(),
+                limit: 10,
+                explain: true,
+            };
+            async {
+                let response = handler.handle(request).await;
+                let Response::Ok {
+                    data: Some(ResponseData::SearchResults { hits }),
+                } = response
+                else {
+                    panic!("Expected SearchResults response, got {:?}", response);
+                };
+                hits.into_iter()
+                    .map(|hit| (hit.name, hit.explanation.unwrap()))
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        // Name match
+        let hits = explain("Where is `connect_database`?").await;
+        assert_eq!(hits[0].0, "connect_database");
+        assert!(hits[0].1.contains(&MatchReason::NameMatch {
+            name: "connect_database".to_string()
+        }));
+
+        // Dependency edge, named by the imported file's path
+        let hits = explain("What imports `db.py`?").await;
+        assert_eq!(hits[0].0, "auth.py");
+        assert!(hits[0].1.contains(&MatchReason::DependencyEdge {
+            imports: PathBuf::from("db.py")
+        }));
+
+        // Vector similarity
+        let hits = explain("database connection").await;
+        assert!(hits.iter().any(|(_, reasons)| reasons
+            .iter()
+            .any(|reason| matches!(reason, MatchReason::VectorSimilarity { .. }))));
+
+        // Hot node boost for files most worked on lately
+        handler
+            .relevance
+            .record(&project_dir, &[PathBuf::from("db.py")], &Outcome::Success)
+            .await
+            .unwrap();
+        let hits = explain("Where is `connect_database`?").await;
+        assert!(matches!(
+            hits[0].1.as_slice(),
+            [
+                MatchReason::NameMatch { .. },
+                ..,
+                MatchReason::Relevance { .. },
+                MatchReason::HotNode { boost },
+            ] if *boost > 1.0
+        ));
+
+        // Without the flag no reasons are sent
+        let response = handler
+            .handle(Request::Search {
+                cwd: project_dir.clone(),
+                query: "Where is `connect_database`?".to_string(),
+                limit: 10,
+                explain: false,
+            })
+            .await;
+        let Response::Ok {
+            data: Some(ResponseData::SearchResults { hits }),
+        } = response
+        else {
+            panic!("Expected SearchResults response");
+        };
+        assert!(hits.iter().all(|hit| hit.explanation.is_none()));
+    }
+
+    #[tokio::test]
+    async fn test_find_files() {
+        let temp_dir = tempdir().unwrap();
+        let handler = test_handler();
+
+        let project_dir = temp_dir.path().join("project");
+        std::fs::create_dir_all(project_dir.join("src/middleware")).unwrap();
+        std::fs::write(project_dir.join("src/middleware/auth.ts"), "export {};\n").unwrap();
+        std::fs::write(project_dir.join("src/middleware/cors.ts"), "export {};\n").unwrap();
+        std::fs::write(project_dir.join("src/login.ts"), "export {};\n").unwrap();
+        handler
+            .handle(Request::InitProject {
+                cwd: project_dir.clone(),
+                async_mode: false,
+                artifact_allowlist: vec![],
+            })
+            .await;
+
+        let find = |pattern: &str| Request::FindFiles {
+            cwd: project_dir.clone(),
+            pattern: pattern.to_string(),
+            limit: 10,
+        };
+        let response = handler.handle(find("auth middleware")).await;
+        if let Response::Ok {
+            data: Some(ResponseData::FileMatches { matches }),
+        } = response
+        {
+            assert_eq!(matches.len(), 1);
+            assert_eq!(matches[0].path, PathBuf::from("src/middleware/auth.ts"));
+            assert!(matches[0].score > 0);
+        } else {
+            panic!("Expected FileMatches response, got {:?}", response);
+        }
+
+        let response = handler.handle(find("")).await;
+        assert!(matches!(
+            response,
+            Response::Error {
+                code: ErrorCode::InvalidRequest,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_get_context_uses_render_cache() {
+        let temp_dir = tempdir().unwrap();
+        let handler = test_handler();
+
+        let project_dir = temp_dir.path().join("cached_project");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        std::fs::write(project_dir.join("main.rs"), "fn main() {}").unwrap();
+        handler
+            .handle(Request::InitProject {
+                cwd: project_dir.clone(),
+                async_mode: false,
+                artifact_allowlist: vec![],
+            })
+            .await;
+
+        // A new prompt misses the context cache but renders the same scope
+        for (prompt, no_cache) in [(None, false), (Some("again"), false), (None, true)] {
+            let response = handler
+                .handle(Request::GetContext {
+                    cwd: project_dir.clone(),
+                    prompt: prompt.map(str::to_string),
+                    no_cache,
+                    cold_start: false,
+                    read_only_subagent: None,
+                    focus: vec![],
+                    changed_since: None,
+                    structured: false,
+                    profile: None,
+                    session_id: None,
+                })
+                .await;
+            assert!(matches!(
+                response,
+                Response::Ok {
+                    data: Some(ResponseData::Context { .. })
+                }
+            ));
+        }
+
+        let response = handler.handle(Request::Status).await;
+        if let Response::Ok {
+            data: Some(ResponseData::Status { render_cache, .. }),
+        } = response
         {
-            // Success
+            assert_eq!(render_cache.len(), 1);
+            assert_eq!(render_cache[0].hits, 1);
+            assert_eq!(render_cache[0].misses, 1);
+            assert_eq!(render_cache[0].entries, 1);
         } else {
-            panic!("Expected Pong response");
+            panic!("Expected Status response");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_context_uses_context_cache() {
+        let temp_dir = tempdir().unwrap();
+        let handler = test_handler();
+
+        let project_dir = temp_dir.path().join("context_cached_project");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        std::fs::write(project_dir.join("main.rs"), "fn main() {}").unwrap();
+        handler
+            .handle(Request::InitProject {
+                cwd: project_dir.clone(),
+                async_mode: false,
+                artifact_allowlist: vec![],
+            })
+            .await;
+
+        let get_context = || Request::GetContext {
+            cwd: project_dir.clone(),
+            prompt: Some("explain main".to_string()),
+            no_cache: false,
+            cold_start: false,
+            read_only_subagent: None,
+            focus: vec!["main.rs".to_string()],
+            changed_since: None,
+            structured: false,
+            profile: None,
+            session_id: None,
+        };
+        let scope_id = |response: Response| match response {
+            Response::Ok {
+                data: Some(ResponseData::Context { scope_id, .. }),
+            } => scope_id,
+            other => panic!("Expected Context response, got {:?}", other),
+        };
+
+        let first = scope_id(handler.handle(get_context()).await);
+        assert_eq!(scope_id(handler.handle(get_context()).await), first);
+        assert_eq!(handler.metrics.cache_hit_rate(), 0.5);
+
+        // Memory writes drop cached contexts
+        handler
+            .handle(Request::MemoryPut {
+                cwd: project_dir.clone(),
+                entry: MemoryEntry {
+                    id: String::new(),
+                    kind: "note".to_string(),
+                    content: "main is the entry point".to_string(),
+                    tags: vec![],
+                    created_at: 0,
+                    updated_at: 0,
+                    session_id: None,
+                    subagent_id: None,
+                    deleted: false,
+                    experience_id: None,
+                    visibility: MemoryVisibility::Project,
+                },
+            })
+            .await;
+        let second = scope_id(handler.handle(get_context()).await);
+        assert_ne!(second, first);
+
+        // as does releasing the scope they were built from
+        handler
+            .handle(Request::ReleaseScope {
+                cwd: project_dir.clone(),
+                scope_id: second.clone(),
+            })
+            .await;
+        assert_ne!(scope_id(handler.handle(get_context()).await), second);
+    }
+
+    #[tokio::test]
+    async fn test_release_scope() {
+        let temp_dir = tempdir().unwrap();
+        let handler = test_handler();
+
+        let project_dir = temp_dir.path().join("scoped_project");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        std::fs::write(project_dir.join("main.rs"), "fn main() {}").unwrap();
+        handler
+            .handle(Request::InitProject {
+                cwd: project_dir.clone(),
+                async_mode: false,
+                artifact_allowlist: vec![],
+            })
+            .await;
+
+        let scopes_active = |response: Response| match response {
+            Response::Ok {
+                data: Some(ResponseData::Status { scopes_active, .. }),
+            } => scopes_active,
+            other => panic!("Expected Status response, got {:?}", other),
+        };
+
+        let response = handler
+            .handle(Request::GetContext {
+                cwd: project_dir.clone(),
+                prompt: None,
+                no_cache: false,
+                cold_start: false,
+                read_only_subagent: None,
+                focus: vec![],
+                changed_since: None,
+                structured: false,
+                profile: None,
+                session_id: None,
+            })
+            .await;
+        let Response::Ok {
+            data: Some(ResponseData::Context { scope_id, .. }),
+        } = response
+        else {
+            panic!("Expected Context response");
+        };
+        assert!(!scope_id.is_empty());
+        assert_eq!(scopes_active(handler.handle(Request::Status).await), 1);
+
+        let release = Request::ReleaseScope {
+            cwd: project_dir.clone(),
+            scope_id,
+        };
+        assert!(matches!(
+            handler.handle(release.clone()).await,
+            Response::Ok { data: None }
+        ));
+        assert_eq!(scopes_active(handler.handle(Request::Status).await), 0);
+        assert!(matches!(
+            handler.handle(release).await,
+            Response::Error {
+                code: ErrorCode::InvalidRequest,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_get_context_structured() {
+        let temp_dir = tempdir().unwrap();
+        let handler = test_handler();
+
+        let project_dir = temp_dir.path().join("structured_project");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        std::fs::write(project_dir.join("main.rs"), "fn main() {}").unwrap();
+        handler
+            .handle(Request::InitProject {
+                cwd: project_dir.clone(),
+                async_mode: false,
+                artifact_allowlist: vec![],
+            })
+            .await;
+
+        let get_context = |structured| Request::GetContext {
+            cwd: project_dir.clone(),
+            prompt: None,
+            no_cache: false,
+            cold_start: false,
+            read_only_subagent: None,
+            focus: vec!["main.rs".to_string()],
+            changed_since: None,
+            structured,
+            profile: None,
+            session_id: None,
+        };
+        let Response::Ok {
+            data: Some(ResponseData::Context { context, .. }),
+        } = handler.handle(get_context(false)).await
+        else {
+            panic!("Expected Context response");
+        };
+        let Response::Ok {
+            data:
+                Some(ResponseData::StructuredContext {
+                    sections, nodes, ..
+                }),
+        } = handler.handle(get_context(true)).await
+        else {
+            panic!("Expected StructuredContext response");
+        };
+
+        let focus = sections
+            .iter()
+            .find(|section| section.title == "Focus Area")
+            .unwrap();
+        assert_eq!(focus.source_nodes, nodes);
+        assert!(focus.tokens > 0);
+        let joined: String = sections.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(format!("# PROJECT CONTEXT\n\n{}", joined), context);
+    }
+
+    #[tokio::test]
+    async fn test_get_context_profiles() {
+        let temp_dir = tempdir().unwrap();
+        let handler = test_handler();
+
+        let project_dir = temp_dir.path().join("profile_project");
+        std::fs::create_dir_all(project_dir.join(".engram")).unwrap();
+        std::fs::write(project_dir.join("app.py"), "import auth\n").unwrap();
+        std::fs::write(project_dir.join("auth.py"), "import db\n").unwrap();
+        std::fs::write(project_dir.join("db.py"), "def connect():\n    pass\n").unwrap();
+        std::fs::write(
+            project_dir.join(".engram/config.toml"),
+            "[profile.review]\ndependencies = \"full\"\nexperiences = false\n\n\
+             [profile.lean]\ndependencies = \"none\"\n",
+        )
+        .unwrap();
+        handler
+            .handle(Request::InitProject {
+                cwd: project_dir.clone(),
+                async_mode: false,
+                artifact_allowlist: vec![],
+            })
+            .await;
+        // Graft directly: the GraftExperience request grafts in the background
+        handler
+            .context_manager
+            .graft_experience(
+                &project_dir,
+                engram_context::Experience::new("agent-1", "Pool database connections"),
+            )
+            .await
+            .unwrap();
+
+        let get_context = |profile: Option<&str>| Request::GetContext {
+            cwd: project_dir.clone(),
+            prompt: None,
+            no_cache: false,
+            cold_start: false,
+            read_only_subagent: None,
+            focus: vec!["auth.py".to_string()],
+            changed_since: None,
+            structured: false,
+            profile: profile.map(str::to_string),
+            session_id: None,
+        };
+        let context = |response| match response {
+            Response::Ok {
+                data: Some(ResponseData::Context { context, .. }),
+            } => context,
+            other => panic!("Expected Context response, got {:?}", other),
+        };
+
+        let default = context(handler.handle(get_context(None)).await);
+        assert!(default.contains("#### db.py"));
+        assert!(!default.contains("#### app.py"));
+        assert!(default.contains("Pool database connections"));
+
+        let review = context(handler.handle(get_context(Some("review"))).await);
+        assert!(review.contains("#### db.py"));
+        assert!(review.contains("#### app.py"));
+        assert!(!review.contains("Pool database connections"));
+
+        let lean = context(handler.handle(get_context(Some("lean"))).await);
+        assert!(!lean.contains("### Dependencies"));
+        assert!(lean.contains("Pool database connections"));
+
+        match handler.handle(get_context(Some("missing"))).await {
+            Response::Error { code, message, .. } => {
+                assert_eq!(code, ErrorCode::InvalidRequest);
+                assert!(message.contains("missing"));
+            }
+            other => panic!("Expected error, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_project_brief() {
+        let temp_dir = tempdir().unwrap();
+        let handler = test_handler();
+
+        let project_dir = temp_dir.path().join("brief_project");
+        std::fs::create_dir_all(project_dir.join("src")).unwrap();
+        std::fs::write(project_dir.join("src/lib.rs"), "//! Core library.\n").unwrap();
+        handler
+            .handle(Request::InitProject {
+                cwd: project_dir.clone(),
+                async_mode: false,
+                artifact_allowlist: vec![],
+            })
+            .await;
+        let get_context = || Request::GetContext {
+            cwd: project_dir.clone(),
+            prompt: None,
+            no_cache: true,
+            cold_start: false,
+            read_only_subagent: None,
+            focus: vec![],
+            changed_since: None,
+            structured: false,
+            profile: None,
+            session_id: None,
+        };
+        let context = |response| match response {
+            Response::Ok {
+                data: Some(ResponseData::Context { context, .. }),
+            } => context,
+            other => panic!("Expected Context response, got {:?}", other),
+        };
+        assert!(!context(handler.handle(get_context()).await).contains("## Project Brief"));
+
+        // Enrichment generates a brief
+        handler.enricher().enrich(&project_dir).await.unwrap();
+        handler.context_manager.invalidate_tree(&project_dir);
+        assert!(handler
+            .context_manager
+            .refresh_brief(&project_dir)
+            .await
+            .unwrap());
+        let generated = context(handler.handle(get_context()).await);
+        assert!(generated.starts_with(
+            "# PROJECT CONTEXT\n\n## Project Brief\nLanguages: Rust\n\n\
+             - `src/` (1 file): Core library.\n"
+        ));
+
+        // A curated brief replaces it and is kept by later refreshes
+        let response = handler
+            .handle(Request::MemoryPut {
+                cwd: project_dir.clone(),
+                entry: MemoryEntry {
+                    id: String::new(),
+                    kind: PROJECT_BRIEF_KIND.to_string(),
+                    content: "Glossary: a scope is one agent's context.".to_string(),
+                    tags: vec![],
+                    created_at: 0,
+                    updated_at: 0,
+                    session_id: None,
+                    subagent_id: None,
+                    deleted: false,
+                    experience_id: None,
+                    visibility: MemoryVisibility::Project,
+                },
+            })
+            .await;
+        assert_eq!(extract_memory_ack(response), PROJECT_BRIEF_ID);
+        assert!(!handler
+            .context_manager
+            .refresh_brief(&project_dir)
+            .await
+            .unwrap());
+        let curated = context(handler.handle(get_context()).await);
+        assert!(curated.contains("## Project Brief\nGlossary: a scope is one agent's context.\n"));
+        assert!(!curated.contains("Core library."));
+    }
+
+    #[tokio::test]
+    async fn test_list_tests() {
+        let temp_dir = tempdir().unwrap();
+        let handler = test_handler();
+
+        let project_dir = temp_dir.path().join("tests_project");
+        std::fs::create_dir_all(project_dir.join("app")).unwrap();
+        std::fs::create_dir_all(project_dir.join("tests")).unwrap();
+        std::fs::write(project_dir.join("app/auth.py"), "def login():\n    pass\n").unwrap();
+        std::fs::write(project_dir.join("app/db.py"), "def connect():\n    pass\n").unwrap();
+        std::fs::write(
+            project_dir.join("tests/test_auth.py"),
+            "from app.auth import login\n\ndef test_login():\n    login()\n",
+        )
+        .unwrap();
+        handler
+            .handle(Request::InitProject {
+                cwd: project_dir.clone(),
+                async_mode: false,
+                artifact_allowlist: vec![],
+            })
+            .await;
+
+        let list = |path: PathBuf| Request::ListTests {
+            cwd: project_dir.clone(),
+            path,
+        };
+        let tests = |response| match response {
+            Response::Ok {
+                data:
+                    Some(ResponseData::Tests {
+                        is_test,
+                        inline,
+                        files,
+                    }),
+            } => (is_test, inline, files),
+            other => panic!("Expected Tests response, got {:?}", other),
+        };
+
+        assert_eq!(
+            tests(handler.handle(list(project_dir.join("app/auth.py"))).await),
+            (false, false, vec![PathBuf::from("tests/test_auth.py")])
+        );
+        assert_eq!(
+            tests(
+                handler
+                    .handle(list(PathBuf::from("tests/test_auth.py")))
+                    .await
+            ),
+            (true, false, vec![PathBuf::from("app/auth.py")])
+        );
+        assert_eq!(
+            tests(handler.handle(list(PathBuf::from("app/db.py"))).await),
+            (false, false, vec![])
+        );
+
+        match handler.handle(list(PathBuf::from("missing.py"))).await {
+            Response::Error { code, .. } => assert_eq!(code, ErrorCode::InvalidRequest),
+            other => panic!("Expected error, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_file_change_prefetches_context() {
+        let temp_dir = tempdir().unwrap();
+        let handler = test_handler();
+
+        let project_dir = temp_dir.path().join("prefetch_project");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        std::fs::write(project_dir.join("app.py"), "import db\n").unwrap();
+        std::fs::write(project_dir.join("db.py"), "def connect():\n    pass\n").unwrap();
+        handler
+            .handle(Request::InitProject {
+                cwd: project_dir.clone(),
+                async_mode: false,
+                artifact_allowlist: vec![],
+            })
+            .await;
+
+        handler
+            .handle(Request::NotifyFileChange {
+                cwd: project_dir.clone(),
+                path: project_dir.join("db.py"),
+                change_type: ChangeType::Modified,
+            })
+            .await;
+        let prefetch_counts = || async {
+            match handler.handle(Request::Status).await {
+                Response::Ok {
+                    data:
+                        Some(ResponseData::Status {
+                            prefetches,
+                            prefetch_hits,
+                            ..
+                        }),
+                } => (prefetches, prefetch_hits),
+                other => panic!("Expected Status response, got {:?}", other),
+            }
+        };
+        for _ in 0..100 {
+            if prefetch_counts().await.0 == 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(prefetch_counts().await, (2, 0));
+
+        // The importer's context is served from the cache, whatever the prompt
+        let response = handler
+            .handle(Request::GetContext {
+                cwd: project_dir.clone(),
+                prompt: Some("why does login hang".to_string()),
+                no_cache: false,
+                cold_start: false,
+                read_only_subagent: None,
+                focus: vec!["app.py".to_string()],
+                changed_since: None,
+                structured: false,
+                profile: None,
+                session_id: None,
+            })
+            .await;
+        assert!(matches!(
+            response,
+            Response::Ok {
+                data: Some(ResponseData::Context { .. })
+            }
+        ));
+        assert_eq!(handler.metrics.cache_hits.load(Ordering::Relaxed), 1);
+        assert_eq!(prefetch_counts().await, (2, 1));
+    }
+
+    #[tokio::test]
+    async fn test_fork_scope() {
+        let temp_dir = tempdir().unwrap();
+        let handler = test_handler();
+
+        let project_dir = temp_dir.path().join("fork_project");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        for name in ["app.py", "auth.py", "db.py"] {
+            std::fs::write(project_dir.join(name), "def f():\n    pass\n").unwrap();
+        }
+        handler
+            .handle(Request::InitProject {
+                cwd: project_dir.clone(),
+                async_mode: false,
+                artifact_allowlist: vec![],
+            })
+            .await;
+
+        let response = handler
+            .handle(Request::GetContext {
+                cwd: project_dir.clone(),
+                prompt: None,
+                no_cache: false,
+                cold_start: false,
+                read_only_subagent: None,
+                focus: vec!["app.py".to_string()],
+                changed_since: None,
+                structured: false,
+                profile: None,
+                session_id: None,
+            })
+            .await;
+        let Response::Ok {
+            data: Some(ResponseData::Context {
+                scope_id: parent, ..
+            }),
+        } = response
+        else {
+            panic!("Expected Context response");
+        };
+
+        let fork = |focus: &str| Request::ForkScope {
+            cwd: project_dir.clone(),
+            scope_id: parent.clone(),
+            focus: vec![PathBuf::from(focus)],
+        };
+        let Response::Ok {
+            data: Some(ResponseData::Context {
+                context, scope_id, ..
+            }),
+        } = handler.handle(fork("auth.py")).await
+        else {
+            panic!("Expected Context response");
+        };
+        assert_ne!(scope_id, parent);
+        assert!(context.contains("auth.py"));
+
+        assert!(matches!(
+            handler.handle(fork("auth.py")).await,
+            Response::Error {
+                code: ErrorCode::InvalidRequest,
+                ..
+            }
+        ));
+        assert!(matches!(
+            handler.handle(fork("db.py")).await,
+            Response::Ok { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_expand_context() {
+        let temp_dir = tempdir().unwrap();
+        let handler = test_handler();
+
+        let project_dir = temp_dir.path().join("expand_project");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        std::fs::write(project_dir.join("app.py"), "import auth\n").unwrap();
+        std::fs::write(
+            project_dir.join("auth.py"),
+            "import db\n\ndef login():\n    pass\n",
+        )
+        .unwrap();
+        std::fs::write(project_dir.join("db.py"), "def connect():\n    pass\n").unwrap();
+        handler
+            .handle(Request::InitProject {
+                cwd: project_dir.clone(),
+                async_mode: false,
+                artifact_allowlist: vec![],
+            })
+            .await;
+
+        let response = handler
+            .handle(Request::GetContext {
+                cwd: project_dir.clone(),
+                prompt: None,
+                no_cache: false,
+                cold_start: false,
+                read_only_subagent: None,
+                focus: vec!["auth.py".to_string()],
+                changed_since: None,
+                structured: false,
+                profile: None,
+                session_id: None,
+            })
+            .await;
+        let Response::Ok {
+            data: Some(ResponseData::Context { scope_id, .. }),
+        } = response
+        else {
+            panic!("Expected Context response");
+        };
+
+        let expand = |node: &str| Request::ExpandContext {
+            cwd: project_dir.clone(),
+            scope_id: scope_id.clone(),
+            node: node.to_string(),
+        };
+        // db.py was auto-loaded with the focus, so only the importer is new
+        let Response::Ok {
+            data: Some(ResponseData::Context { context, nodes, .. }),
+        } = handler.handle(expand("auth.py::login")).await
+        else {
+            panic!("Expected Context response");
+        };
+        assert_eq!(nodes.len(), 1);
+        assert!(context.contains("## Dependencies of auth.py"));
+        assert!(context.contains("### Imported by\n\n#### app.py"));
+        assert!(!context.contains("db.py"));
+
+        // Expanded nodes are not pulled in twice
+        let Response::Ok {
+            data: Some(ResponseData::Context { nodes, .. }),
+        } = handler.handle(expand("auth.py")).await
+        else {
+            panic!("Expected Context response");
+        };
+        assert!(nodes.is_empty());
+
+        assert!(matches!(
+            handler.handle(expand("missing.py")).await,
+            Response::Error {
+                code: ErrorCode::InvalidRequest,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_project_stats() {
+        let temp_dir = tempdir().unwrap();
+        let handler = test_handler();
+
+        let project_dir = temp_dir.path().join("stats_project");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        std::fs::write(project_dir.join("app.py"), "import db\n").unwrap();
+        std::fs::write(project_dir.join("db.py"), "def connect():\n    pass\n").unwrap();
+        std::fs::write(project_dir.join("logo.png"), [0x89, b'P', b'N', b'G', 0, 0]).unwrap();
+        handler
+            .handle(Request::InitProject {
+                cwd: project_dir.clone(),
+                async_mode: false,
+                artifact_allowlist: vec![],
+            })
+            .await;
+
+        let Response::Ok {
+            data:
+                Some(ResponseData::ProjectStats {
+                    files,
+                    languages,
+                    assets,
+                    symbol_kinds,
+                    most_imported,
+                    index_files,
+                    index_bytes,
+                    last_scan,
+                    ..
+                }),
+        } = handler
+            .handle(Request::ProjectStats {
+                cwd: project_dir.clone(),
+                top: None,
+            })
+            .await
+        else {
+            panic!("Expected ProjectStats response");
+        };
+        assert_eq!(files, 3);
+        assert_eq!(
+            languages,
+            vec![NamedCount {
+                name: "Python".to_string(),
+                count: 2
+            }]
+        );
+        assert_eq!(
+            assets,
+            vec![AssetCount {
+                kind: "image".to_string(),
+                count: 1,
+                bytes: 6
+            }]
+        );
+        assert!(symbol_kinds.iter().any(|k| k.name == "function"));
+        assert_eq!(most_imported[0].path, PathBuf::from("db.py"));
+        assert!(!index_files.is_empty());
+        assert!(index_bytes > 0);
+        assert!(last_scan.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_export_docs() {
+        let temp_dir = tempdir().unwrap();
+        let handler = test_handler();
+
+        let project_dir = temp_dir.path().join("docs_project");
+        std::fs::create_dir_all(project_dir.join("lib")).unwrap();
+        std::fs::write(project_dir.join("app.py"), "import lib.db\n").unwrap();
+        std::fs::write(project_dir.join("lib/db.py"), "def connect():\n    pass\n").unwrap();
+        handler
+            .handle(Request::InitProject {
+                cwd: project_dir.clone(),
+                async_mode: false,
+                artifact_allowlist: vec![],
+            })
+            .await;
+
+        let Response::Ok {
+            data: Some(ResponseData::Docs { files, enriched }),
+        } = handler
+            .handle(Request::ExportDocs {
+                cwd: project_dir.clone(),
+                mdbook: false,
+            })
+            .await
+        else {
+            panic!("Expected Docs response");
+        };
+        assert!(!enriched);
+        assert_eq!(files[0].path, PathBuf::from("README.md"));
+        assert!(files[0].content.contains("[lib/](lib/README.md)"));
+        let lib = files
+            .iter()
+            .find(|file| file.path == Path::new("lib/README.md"))
+            .unwrap();
+        assert!(lib.content.contains("`connect` (function, lines 1-2)"));
+
+        let Response::Ok {
+            data: Some(ResponseData::Docs { files, .. }),
+        } = handler
+            .handle(Request::ExportDocs {
+                cwd: project_dir,
+                mdbook: true,
+            })
+            .await
+        else {
+            panic!("Expected Docs response");
+        };
+        assert!(files
+            .iter()
+            .any(|file| file.path == Path::new("src/SUMMARY.md")));
+    }
+    #[tokio::test]
+    async fn test_analyze_dependencies() {
+        let temp_dir = tempdir().unwrap();
+        let handler = test_handler();
+
+        let project_dir = temp_dir.path().join("deps_project");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        std::fs::write(project_dir.join("app.py"), "import auth\n").unwrap();
+        std::fs::write(project_dir.join("auth.py"), "import db\n").unwrap();
+        std::fs::write(project_dir.join("db.py"), "def connect():\n    pass\n").unwrap();
+        handler
+            .handle(Request::InitProject {
+                cwd: project_dir.clone(),
+                async_mode: false,
+                artifact_allowlist: vec![],
+            })
+            .await;
+
+        let analyze = |dependents_of: Option<&str>, depth| Request::AnalyzeDependencies {
+            cwd: project_dir.clone(),
+            cycles: true,
+            order: true,
+            dependents_of: dependents_of.map(PathBuf::from),
+            depth,
+        };
+        let Response::Ok {
+            data:
+                Some(ResponseData::Dependencies {
+                    cycles,
+                    order,
+                    dependents,
+                }),
+        } = handler.handle(analyze(Some("db.py"), None)).await
+        else {
+            panic!("Expected Dependencies response");
+        };
+        assert!(cycles.is_empty());
+        let order = order.unwrap();
+        let position = |path: &str| order.iter().position(|p| p == Path::new(path));
+        assert!(position("db.py") < position("auth.py"));
+        assert!(position("auth.py") < position("app.py"));
+        assert_eq!(
+            dependents,
+            vec![
+                DependentFile {
+                    path: PathBuf::from("auth.py"),
+                    depth: 1,
+                },
+                DependentFile {
+                    path: PathBuf::from("app.py"),
+                    depth: 2,
+                },
+            ]
+        );
+
+        let absolute = project_dir.join("db.py");
+        let Response::Ok {
+            data: Some(ResponseData::Dependencies { dependents, .. }),
+        } = handler.handle(analyze(absolute.to_str(), Some(1))).await
+        else {
+            panic!("Expected Dependencies response");
+        };
+        assert_eq!(dependents.len(), 1);
+
+        assert!(matches!(
+            handler.handle(analyze(Some("missing.py"), None)).await,
+            Response::Error {
+                code: ErrorCode::InvalidRequest,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_diff_snapshots() {
+        let temp_dir = tempdir().unwrap();
+        let handler = test_handler();
+
+        let project_dir = temp_dir.path().join("diff_project");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        std::fs::write(project_dir.join("a.py"), "def a():\n    pass\n").unwrap();
+        std::fs::write(project_dir.join("b.py"), "def b():\n    pass\n").unwrap();
+        handler
+            .handle(Request::InitProject {
+                cwd: project_dir.clone(),
+                async_mode: false,
+                artifact_allowlist: vec![],
+            })
+            .await;
+        let hash = handler.storage.project_hash(&project_dir);
+        let snapshots = handler.storage.snapshots(&hash).list().await.unwrap();
+        let from = snapshots[0].name.clone();
+
+        std::fs::write(
+            project_dir.join("a.py"),
+            "def a():\n    pass\n\ndef extra():\n    pass\n",
+        )
+        .unwrap();
+        std::fs::remove_file(project_dir.join("b.py")).unwrap();
+        handler
+            .project_manager
+            .index_project(&project_dir)
+            .await
+            .unwrap();
+
+        let Response::Ok {
+            data:
+                Some(ResponseData::TreeDiff {
+                    added_files,
+                    removed_files,
+                    modified_files,
+                    added_symbols,
+                    ..
+                }),
+        } = handler
+            .handle(Request::DiffSnapshots {
+                cwd: project_dir.clone(),
+                from: from.clone(),
+                to: None,
+            })
+            .await
+        else {
+            panic!("Expected TreeDiff response");
+        };
+        assert!(added_files.is_empty());
+        assert_eq!(removed_files, vec![PathBuf::from("b.py")]);
+        assert_eq!(modified_files, vec![PathBuf::from("a.py")]);
+        assert_eq!(added_symbols.len(), 1);
+        assert_eq!(added_symbols[0].name, "extra");
+        assert_eq!(added_symbols[0].kind, "function");
+
+        assert!(matches!(
+            handler
+                .handle(Request::DiffSnapshots {
+                    cwd: project_dir.clone(),
+                    from: "missing".to_string(),
+                    to: None,
+                })
+                .await,
+            Response::Error {
+                code: ErrorCode::InvalidRequest,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_impact_analysis() {
+        let temp_dir = tempdir().unwrap();
+        let handler = test_handler();
+
+        let project_dir = temp_dir.path().join("impact_project");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        std::fs::write(project_dir.join("app.py"), "import auth\n").unwrap();
+        std::fs::write(
+            project_dir.join("auth.py"),
+            "import db\n\ndef login():\n    db.connect()\n",
+        )
+        .unwrap();
+        std::fs::write(project_dir.join("db.py"), "def connect():\n    pass\n").unwrap();
+
+        let impact = Request::ImpactAnalysis {
+            cwd: project_dir.clone(),
+            symbol: "connect".to_string(),
+        };
+        assert!(matches!(
+            handler.handle(impact.clone()).await,
+            Response::Error {
+                code: ErrorCode::NotInitialized,
+                ..
+            }
+        ));
+
+        handler
+            .handle(Request::InitProject {
+                cwd: project_dir.clone(),
+                async_mode: false,
+                artifact_allowlist: vec![],
+            })
+            .await;
+        let Response::Ok {
+            data: Some(ResponseData::Impact { definitions, files }),
+        } = handler.handle(impact).await
+        else {
+            panic!("Expected Impact response");
+        };
+        assert_eq!(definitions.len(), 1);
+        assert_eq!(definitions[0].path, PathBuf::from("db.py"));
+        assert_eq!(definitions[0].line, 1);
+
+        let paths: Vec<_> = files.iter().map(|file| file.path.clone()).collect();
+        assert_eq!(
+            paths,
+            vec![PathBuf::from("db.py"), PathBuf::from("auth.py")]
+        );
+        assert_eq!(files[1].depth, Some(1));
+        assert_eq!(files[1].lines, vec![4]);
+        assert_eq!(
+            files[1]
+                .symbols
+                .iter()
+                .map(|symbol| symbol.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["login"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reload_config() {
+        let temp_dir = tempdir().unwrap();
+        let config_path = temp_dir.path().join("config.yaml");
+        let config = DaemonConfig::reload_from(&config_path).unwrap();
+        let handler = test_handler().with_config_file(config_path.clone(), &config);
+
+        std::fs::write(
+            &config_path,
+            "max_projects: 1\nlog_level: debug\nenrichment:\n  workers: 2\n",
+        )
+        .unwrap();
+        let Response::Ok {
+            data:
+                Some(ResponseData::ConfigReloaded {
+                    path,
+                    applied,
+                    restart_required,
+                }),
+        } = handler.handle(Request::ReloadConfig).await
+        else {
+            panic!("Expected ConfigReloaded response");
+        };
+        assert_eq!(path, config_path);
+        assert_eq!(applied, vec!["enrichment.workers", "max_projects"]);
+        assert_eq!(restart_required, vec!["log_level"]);
+        assert_eq!(handler.project_manager.max_projects(), 1);
+        let events = handler.project_manager.events().since(0, 10);
+        assert_eq!(events.last().unwrap().kind, EventKind::ConfigReloaded);
+
+        // Reloading again finds nothing new
+        let reload = handler.reload_config().await.unwrap();
+        assert!(reload.applied.is_empty() && reload.restart_required.is_empty());
+
+        // An invalid file keeps the running settings
+        std::fs::write(&config_path, "max_projects: many\n").unwrap();
+        let response = handler.handle(Request::ReloadConfig).await;
+        assert!(matches!(response, Response::Error { .. }));
+        assert_eq!(handler.project_manager.max_projects(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_list_projects() {
+        let temp_dir = tempdir().unwrap();
+        let handler = test_handler();
+
+        let project_dir = temp_dir.path().join("listed_project");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        std::fs::write(project_dir.join("main.rs"), "fn main() {}").unwrap();
+        handler
+            .handle(Request::InitProject {
+                cwd: project_dir.clone(),
+                async_mode: false,
+                artifact_allowlist: vec![],
+            })
+            .await;
+        extract_memory_ack(
+            handler
+                .handle(Request::MemoryPut {
+                    cwd: project_dir.clone(),
+                    entry: MemoryEntry {
+                        id: String::new(),
+                        kind: "note".to_string(),
+                        content: "main is the entry point".to_string(),
+                        tags: vec![],
+                        created_at: 0,
+                        updated_at: 0,
+                        session_id: None,
+                        subagent_id: None,
+                        deleted: false,
+                        experience_id: None,
+                        visibility: MemoryVisibility::Project,
+                    },
+                })
+                .await,
+        );
+        // A new prompt misses the context cache but hits the render cache
+        for prompt in [None, Some("again")] {
+            handler
+                .handle(Request::GetContext {
+                    cwd: project_dir.clone(),
+                    prompt: prompt.map(str::to_string),
+                    no_cache: false,
+                    cold_start: false,
+                    read_only_subagent: None,
+                    focus: vec![],
+                    changed_since: None,
+                    structured: false,
+                    profile: None,
+                    session_id: None,
+                })
+                .await;
         }
+
+        let Response::Ok {
+            data: Some(ResponseData::Projects { projects }),
+        } = handler.handle(Request::ListProjects).await
+        else {
+            panic!("Expected Projects response");
+        };
+        assert_eq!(projects.len(), 1);
+        let project = &projects[0];
+        assert_eq!(project.path, project_dir.canonicalize().unwrap());
+        assert_eq!(project.name, "listed_project");
+        assert_eq!(project.file_count, 1);
+        assert!(project.last_index.is_some());
+        assert_eq!(project.scopes_active, 2);
+        assert_eq!(project.memory_entries, 1);
+        assert_eq!(project.cache_hit_rate, 0.5);
+        assert!(!project.pinned && !project.read_only);
     }
 
     #[tokio::test]
-    async fn test_status() {
+    async fn test_get_symbol() {
+        let temp_dir = tempdir().unwrap();
+        let config = DaemonConfig {
+            data_dir: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        let storage = Arc::new(Storage::new(temp_dir.path().to_path_buf()));
+        let (shutdown_tx, _) = broadcast::channel(1);
+        let handler = DaemonHandler::new(
+            Arc::new(ProjectManager::new(&config)),
+            storage.clone(),
+            shutdown_tx,
+            std::time::Instant::now(),
+        );
+
+        let project_dir = temp_dir.path().join("symbol_project");
+        std::fs::create_dir_all(project_dir.join("src")).unwrap();
+        std::fs::write(
+            project_dir.join("src/db.rs"),
+            "/// Open a connection.\npub fn connect(\n    url: &str,\n) -> bool {\n    check(url)\n}\n\nfn check(url: &str) -> bool {\n    !url.is_empty()\n}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            project_dir.join("src/main.rs"),
+            "mod db;\n\nfn main() {\n    db::connect(\"x\");\n}\n",
+        )
+        .unwrap();
+
+        handler
+            .handle(Request::InitProject {
+                cwd: project_dir.clone(),
+                async_mode: false,
+                artifact_allowlist: vec![],
+            })
+            .await;
+        for (decision, file, timestamp) in [
+            ("Retry connect on timeout", "README.md", 1),
+            ("Tidy db module", "src/db.rs", 2),
+            ("Rename main", "src/main.rs", 3),
+        ] {
+            let mut experience = engram_context::Experience::new("agent-1", decision)
+                .with_files(vec![PathBuf::from(file)]);
+            experience.timestamp = timestamp;
+            storage
+                .append_experience(&project_dir, &experience)
+                .await
+                .unwrap();
+        }
+
+        let Response::Ok {
+            data:
+                Some(ResponseData::Symbol {
+                    symbol,
+                    signature,
+                    doc,
+                    callers,
+                    callees,
+                    experiences,
+                    ..
+                }),
+        } = handler
+            .handle(Request::GetSymbol {
+                cwd: project_dir.clone(),
+                path: project_dir.join("src/db.rs"),
+                symbol: "connect".to_string(),
+            })
+            .await
+        else {
+            panic!("Expected Symbol response");
+        };
+        assert_eq!(symbol.path, PathBuf::from("src/db.rs"));
+        assert_eq!(signature, "pub fn connect(\n    url: &str,\n) -> bool");
+        assert!(doc.unwrap().contains("Open a connection"));
+        let names = |symbols: &[ImpactSymbol]| {
+            symbols
+                .iter()
+                .map(|symbol| symbol.name.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&callers), vec!["main"]);
+        assert_eq!(names(&callees), vec!["check"]);
+        let decisions: Vec<_> = experiences.iter().map(|e| e.decision.as_str()).collect();
+        assert_eq!(
+            decisions,
+            vec!["Tidy db module", "Retry connect on timeout"]
+        );
+
+        assert!(matches!(
+            handler
+                .handle(Request::GetSymbol {
+                    cwd: project_dir.clone(),
+                    path: PathBuf::from("src/db.rs"),
+                    symbol: "missing".to_string(),
+                })
+                .await,
+            Response::Error {
+                code: ErrorCode::InvalidRequest,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_doctor_reports_corrupt_storage() {
+        let temp_dir = tempdir().unwrap();
         let handler = test_handler();
-        let response = handler.handle(Request::Status).await;
+
+        let project_dir = temp_dir.path().join("doctor_project");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        std::fs::write(project_dir.join("main.rs"), "fn main() {}").unwrap();
+        handler
+            .handle(Request::InitProject {
+                cwd: project_dir.clone(),
+                async_mode: false,
+                artifact_allowlist: vec![],
+            })
+            .await;
+
+        let storage_checks = |response: Response| match response {
+            Response::Ok {
+                data: Some(ResponseData::Doctor { checks }),
+            } => checks
+                .into_iter()
+                .filter(|check| check.name == "storage")
+                .collect::<Vec<_>>(),
+            other => panic!("Expected Doctor response, got {:?}", other),
+        };
+
+        let checks = storage_checks(handler.handle(Request::Doctor).await);
+        assert_eq!(checks.len(), 1);
+        assert_eq!(checks[0].status, engram_ipc::CheckStatus::Ok);
+
+        let hashes = handler.storage.stored_projects().await.unwrap();
+        let skeleton = handler
+            .storage
+            .project_dir(&hashes[0])
+            .join("skeleton.json");
+        std::fs::write(&skeleton, "{ truncated").unwrap();
+
+        let checks = storage_checks(handler.handle(Request::Doctor).await);
+        assert_eq!(checks[0].status, engram_ipc::CheckStatus::Failed);
+        assert!(checks[0].message.contains(&hashes[0]));
+        assert!(checks[0].fix.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_get_context_cold_start() {
+        let temp_dir = tempdir().unwrap();
+        let handler = test_handler();
+
+        let project_dir = temp_dir.path().join("cold_project");
+        std::fs::create_dir_all(project_dir.join("src")).unwrap();
+        std::fs::write(project_dir.join("src/main.rs"), "fn main() {}").unwrap();
+
+        let response = handler
+            .handle(Request::GetContext {
+                cwd: project_dir.clone(),
+                prompt: None,
+                no_cache: false,
+                cold_start: true,
+                read_only_subagent: None,
+                focus: vec![],
+                changed_since: None,
+                structured: false,
+                profile: None,
+                session_id: None,
+            })
+            .await;
 
         if let Response::Ok {
-            data: Some(ResponseData::Status { version, .. }),
+            data: Some(ResponseData::Context { context, .. }),
         } = response
         {
-            assert_eq!(version, env!("CARGO_PKG_VERSION"));
+            assert!(context.contains("cold_project/"));
+            assert!(context.contains("main.rs"));
         } else {
-            panic!("Expected Status response");
+            panic!("Expected Context response");
+        }
+
+        // Full init proceeds in the background.
+        let mut initialized = false;
+        for _ in 0..50 {
+            if handler.project_manager.is_initialized(&project_dir).await {
+                initialized = true;
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
         }
+        assert!(initialized);
     }
 
     #[tokio::test]
@@ -520,6 +6474,14 @@ mod tests {
             .handle(Request::GetContext {
                 cwd: PathBuf::from("/nonexistent"),
                 prompt: None,
+                no_cache: false,
+                cold_start: false,
+                read_only_subagent: None,
+                focus: vec![],
+                changed_since: None,
+                structured: false,
+                profile: None,
+                session_id: None,
             })
             .await;
 
@@ -555,6 +6517,7 @@ mod tests {
             .handle(Request::InitProject {
                 cwd: project_dir.clone(),
                 async_mode: false,
+                artifact_allowlist: vec![],
             })
             .await;
         assert!(matches!(init_response, Response::Ok { .. }));
@@ -572,6 +6535,8 @@ mod tests {
                     session_id: Some("session-abc".to_string()),
                     subagent_id: None,
                     deleted: false,
+                    experience_id: None,
+                    visibility: MemoryVisibility::Project,
                 },
             })
             .await;
@@ -601,6 +6566,7 @@ mod tests {
             .handle(Request::MemoryList {
                 cwd: project_dir,
                 limit: 10,
+                query: MemoryQuery::default(),
             })
             .await;
         let entries = extract_memory_entries(list_response);
@@ -608,6 +6574,135 @@ mod tests {
         assert_eq!(entries[0].id, memory_id);
     }
 
+    #[tokio::test]
+    async fn test_memory_search() {
+        let temp_dir = tempdir().unwrap();
+        let handler = test_handler();
+        let project_dir = temp_dir.path().join("memory_search_project");
+        std::fs::create_dir_all(&project_dir).unwrap();
+
+        let search = |query: &str| Request::MemorySearch {
+            cwd: project_dir.clone(),
+            query: query.to_string(),
+            limit: 5,
+            viewer: None,
+        };
+        assert!(matches!(
+            handler.handle(search("auth")).await,
+            Response::Error {
+                code: ErrorCode::NotInitialized,
+                ..
+            }
+        ));
+
+        handler
+            .handle(Request::InitProject {
+                cwd: project_dir.clone(),
+                async_mode: false,
+                artifact_allowlist: vec![],
+            })
+            .await;
+        for content in [
+            "Refresh tokens rotate on every login",
+            "Database migrations run before deploy",
+        ] {
+            handler
+                .handle(Request::MemoryPut {
+                    cwd: project_dir.clone(),
+                    entry: MemoryEntry {
+                        id: String::new(),
+                        kind: "decision".to_string(),
+                        content: content.to_string(),
+                        tags: vec![],
+                        created_at: 0,
+                        updated_at: 0,
+                        session_id: None,
+                        subagent_id: None,
+                        deleted: false,
+                        experience_id: None,
+                        visibility: MemoryVisibility::Project,
+                    },
+                })
+                .await;
+        }
+
+        let Response::Ok {
+            data: Some(ResponseData::MemoryMatches { matches }),
+        } = handler.handle(search("token rotation")).await
+        else {
+            panic!("Expected MemoryMatches response");
+        };
+        assert_eq!(
+            matches[0].entry.content,
+            "Refresh tokens rotate on every login"
+        );
+        assert!(matches[0].score > 0.0);
+
+        // Private entries are hidden from other sub-agents
+        let private = |subagent_id: Option<&str>| MemoryEntry {
+            id: String::new(),
+            kind: "note".to_string(),
+            content: "Token rotation is flaky in CI".to_string(),
+            tags: vec![],
+            created_at: 0,
+            updated_at: 0,
+            session_id: Some("session-1".to_string()),
+            subagent_id: subagent_id.map(str::to_string),
+            deleted: false,
+            experience_id: None,
+            visibility: MemoryVisibility::Private,
+        };
+        extract_memory_ack(
+            handler
+                .handle(Request::MemoryPut {
+                    cwd: project_dir.clone(),
+                    entry: private(Some("sub-a")),
+                })
+                .await,
+        );
+        let search_as = |subagent_id: &str| Request::MemorySearch {
+            cwd: project_dir.clone(),
+            query: "token rotation".to_string(),
+            limit: 5,
+            viewer: Some(MemoryViewer {
+                session_id: Some("session-1".to_string()),
+                subagent_id: Some(subagent_id.to_string()),
+            }),
+        };
+        let count_private = |response: Response| {
+            let Response::Ok {
+                data: Some(ResponseData::MemoryMatches { matches }),
+            } = response
+            else {
+                panic!("Expected MemoryMatches response");
+            };
+            matches
+                .iter()
+                .filter(|m| m.entry.visibility == MemoryVisibility::Private)
+                .count()
+        };
+        assert_eq!(count_private(handler.handle(search_as("sub-a")).await), 1);
+        assert_eq!(count_private(handler.handle(search_as("sub-b")).await), 0);
+
+        // Private entries need an owner
+        let orphan = MemoryEntry {
+            session_id: None,
+            ..private(None)
+        };
+        assert!(matches!(
+            handler
+                .handle(Request::MemoryPut {
+                    cwd: project_dir.clone(),
+                    entry: orphan,
+                })
+                .await,
+            Response::Error {
+                code: ErrorCode::InvalidRequest,
+                ..
+            }
+        ));
+    }
+
     #[tokio::test]
     async fn test_memory_patch_delete_sync_roundtrip() {
         let temp_dir = tempdir().unwrap();
@@ -628,6 +6723,7 @@ mod tests {
             .handle(Request::InitProject {
                 cwd: project_dir.clone(),
                 async_mode: false,
+                artifact_allowlist: vec![],
             })
             .await;
         assert!(matches!(init_response, Response::Ok { .. }));
@@ -646,6 +6742,8 @@ mod tests {
                         session_id: Some("session-a".to_string()),
                         subagent_id: None,
                         deleted: false,
+                        experience_id: None,
+                        visibility: MemoryVisibility::Project,
                     },
                 })
                 .await,
@@ -709,11 +6807,29 @@ mod tests {
             .await;
         assert!(matches!(sync_response, Response::Ok { .. }));
 
+        // Put and patch versions are superseded; the fresh tombstone stays
+        let compact_response = handler
+            .handle(Request::MemoryCompact {
+                cwd: project_dir.clone(),
+            })
+            .await;
+        assert!(matches!(
+            compact_response,
+            Response::Ok {
+                data: Some(ResponseData::MemoryCompacted {
+                    dropped_versions: 2,
+                    dropped_tombstones: 0,
+                    ..
+                })
+            }
+        ));
+
         let entries = extract_memory_entries(
             handler
                 .handle(Request::MemoryList {
                     cwd: project_dir,
                     limit: 10,
+                    query: MemoryQuery::default(),
                 })
                 .await,
         );
@@ -742,6 +6858,7 @@ mod tests {
             .handle(Request::InitProject {
                 cwd: project_dir.clone(),
                 async_mode: false,
+                artifact_allowlist: vec![],
             })
             .await;
         assert!(matches!(init_response, Response::Ok { .. }));
@@ -760,6 +6877,8 @@ mod tests {
                         session_id: None,
                         subagent_id: None,
                         deleted: false,
+                        experience_id: None,
+                        visibility: MemoryVisibility::Project,
                     },
                 })
                 .await,
@@ -778,6 +6897,8 @@ mod tests {
                         session_id: None,
                         subagent_id: None,
                         deleted: false,
+                        experience_id: None,
+                        visibility: MemoryVisibility::Project,
                     },
                 })
                 .await,
@@ -807,6 +6928,7 @@ mod tests {
                 .handle(Request::MemoryList {
                     cwd: project_dir,
                     limit: 10,
+                    query: MemoryQuery::default(),
                 })
                 .await,
         );
@@ -841,6 +6963,7 @@ mod tests {
             .handle(Request::InitProject {
                 cwd: project_dir.clone(),
                 async_mode: false,
+                artifact_allowlist: vec![],
             })
             .await;
         assert!(matches!(init_response, Response::Ok { .. }));
@@ -865,6 +6988,8 @@ mod tests {
                                 session_id: None,
                                 subagent_id: Some(format!("subagent-{idx}")),
                                 deleted: false,
+                                experience_id: None,
+                                visibility: MemoryVisibility::Project,
                             },
                         })
                         .await,
@@ -885,6 +7010,7 @@ mod tests {
                 .handle(Request::MemoryList {
                     cwd: project_dir,
                     limit: writes + 10,
+                    query: MemoryQuery::default(),
                 })
                 .await,
         );
@@ -893,4 +7019,379 @@ mod tests {
         let listed_ids: HashSet<String> = entries.into_iter().map(|entry| entry.id).collect();
         assert_eq!(listed_ids, ack_ids);
     }
+
+    #[tokio::test]
+    async fn test_line_hints_symbols_memories_experience() {
+        let temp_dir = tempdir().unwrap();
+        let config = DaemonConfig {
+            data_dir: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        let manager = Arc::new(ProjectManager::new(&config));
+        let storage = Arc::new(Storage::new(temp_dir.path().to_path_buf()));
+        let (shutdown_tx, _) = broadcast::channel(1);
+        let handler = DaemonHandler::new(
+            manager,
+            storage.clone(),
+            shutdown_tx,
+            std::time::Instant::now(),
+        );
+
+        let project_dir = temp_dir.path().join("line_hints_project");
+        std::fs::create_dir_all(project_dir.join("src")).unwrap();
+        std::fs::write(
+            project_dir.join("src/lib.rs"),
+            "pub fn load() {}\n\npub fn save() {\n    load();\n}\n",
+        )
+        .unwrap();
+
+        let init_response = handler
+            .handle(Request::InitProject {
+                cwd: project_dir.clone(),
+                async_mode: false,
+                artifact_allowlist: vec![],
+            })
+            .await;
+        assert!(matches!(init_response, Response::Ok { .. }));
+
+        let memory_id = extract_memory_ack(
+            handler
+                .handle(Request::MemoryPut {
+                    cwd: project_dir.clone(),
+                    entry: MemoryEntry {
+                        id: String::new(),
+                        kind: "decision".to_string(),
+                        content: "src/lib.rs:3-5 save must call load first".to_string(),
+                        tags: vec![],
+                        created_at: 0,
+                        updated_at: 0,
+                        session_id: None,
+                        subagent_id: None,
+                        deleted: false,
+                        experience_id: None,
+                        visibility: MemoryVisibility::Project,
+                    },
+                })
+                .await,
+        );
+
+        let experience = engram_context::Experience::new("agent-1", "Split save from load")
+            .with_files(vec![PathBuf::from("src/lib.rs")]);
+        storage
+            .append_experience(&project_dir, &experience)
+            .await
+            .unwrap();
+
+        let response = handler
+            .handle(Request::LineHints {
+                cwd: project_dir.clone(),
+                path: project_dir.join("src/lib.rs"),
+            })
+            .await;
+
+        if let Response::Ok {
+            data:
+                Some(ResponseData::LineHints {
+                    path,
+                    hints,
+                    last_experience,
+                }),
+        } = response
+        {
+            assert_eq!(path, PathBuf::from("src/lib.rs"));
+            let symbol_lines: Vec<_> = hints
+                .iter()
+                .filter(|h| h.kind == engram_ipc::LineHintKind::Symbol)
+                .map(|h| (h.line, h.end_line))
+                .collect();
+            assert_eq!(symbol_lines, vec![(1, 1), (3, 5)]);
+
+            let memory_hint = hints
+                .iter()
+                .find(|h| h.kind == engram_ipc::LineHintKind::Memory)
+                .unwrap();
+            assert_eq!((memory_hint.line, memory_hint.end_line), (3, 5));
+            assert_eq!(memory_hint.ref_id.as_deref(), Some(memory_id.as_str()));
+
+            assert_eq!(last_experience.unwrap().decision, "Split save from load");
+        } else {
+            panic!("Expected LineHints response");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_line_hints_outside_project() {
+        let temp_dir = tempdir().unwrap();
+        let handler = test_handler();
+        let project_dir = temp_dir.path().join("p");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        handler
+            .handle(Request::InitProject {
+                cwd: project_dir.clone(),
+                async_mode: false,
+                artifact_allowlist: vec![],
+            })
+            .await;
+
+        let response = handler
+            .handle(Request::LineHints {
+                cwd: project_dir,
+                path: PathBuf::from("/etc/hosts"),
+            })
+            .await;
+        assert!(matches!(
+            response,
+            Response::Error {
+                code: ErrorCode::InvalidRequest,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_tracked_requests() {
+        let temp_dir = tempdir().unwrap();
+        let handler = test_handler();
+
+        let response = handler
+            .handle(Request::Ping.tracked("ping-1", Some(Duration::from_secs(60))))
+            .await;
+        assert!(matches!(
+            response,
+            Response::Ok {
+                data: Some(ResponseData::Pong { .. })
+            }
+        ));
+        assert!(handler.in_flight.lock().unwrap().is_empty());
+
+        let get_context = Request::GetContext {
+            cwd: temp_dir.path().to_path_buf(),
+            prompt: None,
+            no_cache: false,
+            cold_start: false,
+            read_only_subagent: None,
+            focus: vec![],
+            changed_since: None,
+            structured: false,
+            profile: None,
+            session_id: None,
+        };
+        assert!(matches!(
+            handler
+                .handle(get_context.tracked("ctx-1", Some(Duration::ZERO)))
+                .await,
+            Response::Error {
+                code: ErrorCode::Timeout,
+                ..
+            }
+        ));
+
+        let token = CancelToken::new();
+        handler
+            .in_flight
+            .lock()
+            .unwrap()
+            .insert("scan-1".to_string(), token.clone());
+        let cancel = |request_id: &str| Request::Cancel {
+            request_id: request_id.to_string(),
+        };
+        assert!(matches!(
+            handler.handle(cancel("scan-1")).await,
+            Response::Ok { .. }
+        ));
+        assert!(token.is_cancelled());
+        assert!(matches!(
+            handler.handle(cancel("missing")).await,
+            Response::Error {
+                code: ErrorCode::InvalidRequest,
+                ..
+            }
+        ));
+
+        let nested = Request::Ping.tracked("inner", None).tracked("outer", None);
+        assert!(matches!(
+            handler.handle(nested).await,
+            Response::Error {
+                code: ErrorCode::InvalidRequest,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_session_lifecycle() {
+        let temp_dir = tempdir().unwrap();
+        let handler = test_handler();
+        let project_dir = temp_dir.path().join("session_project");
+        std::fs::create_dir_all(project_dir.join("src")).unwrap();
+        std::fs::write(project_dir.join("src/auth.rs"), "pub fn login() {}\n").unwrap();
+        handler
+            .handle(Request::InitProject {
+                cwd: project_dir.clone(),
+                async_mode: false,
+                artifact_allowlist: vec![],
+            })
+            .await;
+
+        let response = handler
+            .handle(Request::SessionStart {
+                cwd: project_dir.clone(),
+                session_id: Some("s1".to_string()),
+                agent: Some("claude".to_string()),
+                model: None,
+                metadata: BTreeMap::new(),
+            })
+            .await;
+        let Response::Ok {
+            data: Some(ResponseData::Session { session }),
+        } = response
+        else {
+            panic!("Expected Session response, got {:?}", response);
+        };
+        assert_eq!(session.agent.as_deref(), Some("claude"));
+        assert!(session.ended_at.is_none());
+
+        let get_context = |session_id: &str| Request::GetContext {
+            cwd: project_dir.clone(),
+            prompt: None,
+            no_cache: false,
+            cold_start: false,
+            read_only_subagent: None,
+            focus: vec!["src/auth.rs".to_string()],
+            changed_since: None,
+            structured: false,
+            profile: None,
+            session_id: Some(session_id.to_string()),
+        };
+        let Response::Ok {
+            data: Some(ResponseData::Context { scope_id, .. }),
+        } = handler.handle(get_context("s1")).await
+        else {
+            panic!("Expected Context response");
+        };
+
+        // Experiences without a session join the active one
+        handler
+            .handle(Request::GraftExperience {
+                cwd: project_dir.clone(),
+                experience: engram_ipc::Experience {
+                    agent_id: "claude".to_string(),
+                    decision: "Hashed passwords with argon2".to_string(),
+                    rationale: None,
+                    files_touched: vec![PathBuf::from("src/auth.rs")],
+                    timestamp: 0,
+                    id: None,
+                    memory_ids: vec![],
+                    summarizes: 0,
+                    session_id: None,
+                    outcome: None,
+                },
+            })
+            .await;
+        handler
+            .handle(Request::MemoryPut {
+                cwd: project_dir.clone(),
+                entry: MemoryEntry {
+                    id: String::new(),
+                    kind: "note".to_string(),
+                    content: "Passwords are hashed with argon2".to_string(),
+                    tags: vec![],
+                    created_at: 0,
+                    updated_at: 0,
+                    session_id: Some("s1".to_string()),
+                    subagent_id: None,
+                    deleted: false,
+                    experience_id: None,
+                    visibility: MemoryVisibility::Project,
+                },
+            })
+            .await;
+        // Experience grafting is fire-and-forget; wait for it to land.
+        for _ in 0..50 {
+            let experiences: Vec<engram_context::Experience> = handler
+                .storage
+                .load_all_experiences(&project_dir)
+                .await
+                .unwrap_or_default();
+            if !experiences.is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+
+        let response = handler
+            .handle(Request::GetSession {
+                cwd: project_dir.clone(),
+                session_id: "s1".to_string(),
+            })
+            .await;
+        let Response::Ok {
+            data:
+                Some(ResponseData::SessionDetail {
+                    session,
+                    experiences,
+                    memory,
+                }),
+        } = response
+        else {
+            panic!("Expected SessionDetail response, got {:?}", response);
+        };
+        assert_eq!(session.scope_ids, vec![scope_id.clone()]);
+        assert_eq!((session.experiences, session.memory_entries), (1, 1));
+        assert_eq!(experiences[0].decision, "Hashed passwords with argon2");
+        assert_eq!(memory[0].content, "Passwords are hashed with argon2");
+
+        let response = handler
+            .handle(Request::SessionEnd {
+                cwd: project_dir.clone(),
+                session_id: "s1".to_string(),
+                summary: None,
+            })
+            .await;
+        let Response::Ok {
+            data: Some(ResponseData::Session { session }),
+        } = response
+        else {
+            panic!("Expected Session response, got {:?}", response);
+        };
+        assert!(session.ended_at.is_some());
+        let summary = session.summary.unwrap();
+        assert!(summary.contains("- Hashed passwords with argon2"));
+        assert!(summary.contains("Memory written: 1 note"));
+        // Ending the session released its scope
+        assert!(handler.context_manager.get_scope(&scope_id).is_none());
+        assert!(matches!(
+            handler
+                .handle(Request::SessionEnd {
+                    cwd: project_dir.clone(),
+                    session_id: "missing".to_string(),
+                    summary: None,
+                })
+                .await,
+            Response::Error { .. }
+        ));
+
+        // The next session starts from the summary
+        let Response::Ok {
+            data: Some(ResponseData::Context { context, .. }),
+        } = handler.handle(get_context("s2")).await
+        else {
+            panic!("Expected Context response");
+        };
+        assert!(context.contains("## Previous Session\n- Hashed passwords with argon2"));
+
+        let Response::Ok {
+            data: Some(ResponseData::Sessions { sessions }),
+        } = handler
+            .handle(Request::ListSessions {
+                cwd: project_dir.clone(),
+                limit: 10,
+            })
+            .await
+        else {
+            panic!("Expected Sessions response");
+        };
+        let ids: HashSet<&str> = sessions.iter().map(|session| session.id.as_str()).collect();
+        assert_eq!(ids, HashSet::from(["s1", "s2"]));
+    }
 }
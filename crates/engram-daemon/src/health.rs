@@ -0,0 +1,182 @@
+//! Component probes behind `Request::HealthCheck`.
+//!
+//! Unlike the doctor's installation checks, these are cheap enough for an
+//! orchestrator to poll. Each probes something a serving daemon relies on,
+//! so a degraded daemon can be told apart from a healthy one while it
+//! still answers `Ping`.
+
+use engram_core::{MemoryMonitor, MemoryPressure};
+use engram_ipc::{CheckStatus, DoctorCheck};
+use std::time::Duration;
+
+use crate::enrich::Enricher;
+use crate::watch::{WatchHeartbeat, RECONCILE_INTERVAL};
+
+/// Reconcile passes the watch manager may miss before it counts as stalled
+const MISSED_PASSES: u32 = 5;
+
+/// Worst status of the components, `Ok` if there are none.
+pub fn overall(components: &[DoctorCheck]) -> CheckStatus {
+    components
+        .iter()
+        .map(|check| check.status)
+        .max()
+        .unwrap_or(CheckStatus::Ok)
+}
+
+/// Whether the watch manager still reconciles and its watchers run.
+///
+/// `uptime` gives a starting daemon time for the first pass; `now` is in
+/// unix seconds.
+pub fn check_watcher(heartbeat: &WatchHeartbeat, uptime: Duration, now: i64) -> DoctorCheck {
+    let stall = RECONCILE_INTERVAL * MISSED_PASSES;
+    if !heartbeat.enabled() {
+        return DoctorCheck::new("watcher", CheckStatus::Ok, "file watching is disabled");
+    }
+
+    let Some(last) = heartbeat.last() else {
+        return if uptime < stall {
+            DoctorCheck::new("watcher", CheckStatus::Ok, "starting")
+        } else {
+            DoctorCheck::new("watcher", CheckStatus::Failed, "never ran")
+                .with_fix("Restart the daemon: engram stop && engram start")
+        };
+    };
+    let age = now.saturating_sub(last);
+    if age > stall.as_secs() as i64 {
+        return DoctorCheck::new(
+            "watcher",
+            CheckStatus::Failed,
+            format!("stalled, last pass {}s ago", age),
+        )
+        .with_fix("Restart the daemon: engram stop && engram start");
+    }
+
+    let (watched, stopped) = (heartbeat.watched(), heartbeat.stopped());
+    if stopped > 0 {
+        return DoctorCheck::new(
+            "watcher",
+            CheckStatus::Warning,
+            format!("{} of {} project watcher(s) stopped", stopped, watched),
+        )
+        .with_fix(
+            "See \"Failed to watch project\" in the daemon log; on Linux, raise \
+             fs.inotify.max_user_watches",
+        );
+    }
+    DoctorCheck::new(
+        "watcher",
+        CheckStatus::Ok,
+        format!("watching {} project(s)", watched),
+    )
+}
+
+/// Whether the configured summarizer was set up and its backend answers.
+///
+/// An unreachable backend leaves files unsummarized but the daemon
+/// serving, so it is a warning.
+pub async fn check_enrichment(enricher: &Enricher) -> DoctorCheck {
+    let name = enricher.summarizer_name();
+    if let Some(reason) = enricher.fallback() {
+        return DoctorCheck::new(
+            "enrichment",
+            CheckStatus::Warning,
+            format!("using {} summaries, {}", name, reason),
+        )
+        .with_fix("Fix the enrichment settings, then run engram reload");
+    }
+    match enricher.probe().await {
+        Ok(message) => DoctorCheck::new(
+            "enrichment",
+            CheckStatus::Ok,
+            format!("{}: {}", name, message),
+        ),
+        Err(e) => DoctorCheck::new(
+            "enrichment",
+            CheckStatus::Warning,
+            format!("{}: {:#}", name, e),
+        )
+        .with_fix("Check the summarizer's base_url, network access and command"),
+    }
+}
+
+/// Memory use against the configured limit.
+pub fn check_memory(monitor: &MemoryMonitor) -> DoctorCheck {
+    let status = match monitor.check_pressure() {
+        MemoryPressure::Normal => CheckStatus::Ok,
+        MemoryPressure::Warning => CheckStatus::Warning,
+        MemoryPressure::Critical => CheckStatus::Failed,
+    };
+    let mib = |bytes: usize| bytes as f64 / (1024.0 * 1024.0);
+    let check = DoctorCheck::new(
+        "memory",
+        status,
+        format!(
+            "{} pressure, peak {:.1} MiB of {:.1} MiB",
+            monitor.check_pressure().as_str(),
+            mib(monitor.current()),
+            mib(monitor.limit())
+        ),
+    );
+    if status == CheckStatus::Ok {
+        check
+    } else {
+        check.with_fix("Unload idle projects (engram unload) or raise max_memory")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_watcher() {
+        let heartbeat = WatchHeartbeat::default();
+        let now = 1_700_000_000;
+        let check = |heartbeat: &WatchHeartbeat, uptime| check_watcher(heartbeat, uptime, now);
+        assert_eq!(
+            check(&heartbeat, Duration::ZERO).message,
+            "file watching is disabled"
+        );
+
+        heartbeat.enable();
+        assert_eq!(check(&heartbeat, Duration::ZERO).status, CheckStatus::Ok);
+        assert_eq!(
+            check(&heartbeat, Duration::from_secs(3600)).status,
+            CheckStatus::Failed
+        );
+
+        heartbeat.beat_at(now - 1, 3, 0);
+        assert_eq!(
+            check(&heartbeat, Duration::ZERO).message,
+            "watching 3 project(s)"
+        );
+        heartbeat.beat_at(now - 1, 3, 1);
+        assert_eq!(
+            check(&heartbeat, Duration::ZERO).status,
+            CheckStatus::Warning
+        );
+        heartbeat.beat_at(now - 60, 3, 0);
+        assert_eq!(
+            check(&heartbeat, Duration::ZERO).status,
+            CheckStatus::Failed
+        );
+    }
+
+    #[test]
+    fn test_check_memory() {
+        let monitor = MemoryMonitor::new(100 * 1024 * 1024);
+        monitor.update(10 * 1024 * 1024);
+        let check = check_memory(&monitor);
+        assert_eq!(check.status, CheckStatus::Ok);
+        assert_eq!(check.message, "normal pressure, peak 10.0 MiB of 100.0 MiB");
+
+        monitor.update(95 * 1024 * 1024);
+        let check = check_memory(&monitor);
+        assert_eq!(check.status, CheckStatus::Failed);
+        assert!(check.fix.is_some());
+
+        assert_eq!(overall(&[]), CheckStatus::Ok);
+        assert_eq!(overall(&[check_memory(&monitor)]), CheckStatus::Failed);
+    }
+}
@@ -2,9 +2,18 @@
 //!
 //! Background process that manages project context for AI coding assistants.
 
+mod alerts;
+mod bench;
 mod daemon;
+mod doctor;
+mod enrich;
+mod exporter;
 mod handler;
+mod health;
+mod notify;
+mod recovery;
 mod signals;
+mod watch;
 
 use anyhow::Result;
 use tracing_subscriber::EnvFilter;
@@ -18,6 +27,23 @@ pub async fn run() -> Result<()> {
 }
 
 fn main() -> Result<()> {
+    // Diagnose why the daemon won't start; JSON on stdout for `engram doctor`
+    if std::env::args().skip(1).any(|arg| arg == "--doctor") {
+        return tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?
+            .block_on(doctor::run_standalone());
+    }
+
+    // Benchmark against a synthetic repository; JSON on stdout for `engram bench`
+    if std::env::args().skip(1).any(|arg| arg == "--bench") {
+        let options = bench::BenchOptions::from_args(std::env::args().skip(1))?;
+        return tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()?
+            .block_on(bench::run_standalone(options));
+    }
+
     // Initialize logging
     tracing_subscriber::fmt()
         .with_env_filter(
@@ -0,0 +1,75 @@
+//! Readiness notification for systemd (`sd_notify`).
+//!
+//! Under a `Type=notify` unit, systemd passes a datagram socket in
+//! `NOTIFY_SOCKET` and treats the daemon as started once it sends
+//! `READY=1`. Outside systemd the variable is unset and nothing is sent.
+
+use std::io;
+use std::path::Path;
+
+/// Environment variable naming the service manager's notification socket
+const NOTIFY_SOCKET_ENV: &str = "NOTIFY_SOCKET";
+
+/// Report that the daemon accepts connections.
+pub fn ready() {
+    notify("READY=1");
+}
+
+/// Report that the daemon is shutting down.
+pub fn stopping() {
+    notify("STOPPING=1");
+}
+
+fn notify(state: &str) {
+    let Some(socket) = std::env::var_os(NOTIFY_SOCKET_ENV) else {
+        return;
+    };
+    if let Err(e) = send(Path::new(&socket), state) {
+        tracing::warn!(error = %e, state, "Failed to notify service manager");
+    }
+}
+
+/// Send one state datagram; names starting with `@` are abstract sockets.
+#[cfg(unix)]
+fn send(socket: &Path, state: &str) -> io::Result<()> {
+    use std::os::unix::net::UnixDatagram;
+
+    let datagram = UnixDatagram::unbound()?;
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::linux::net::SocketAddrExt;
+        use std::os::unix::ffi::OsStrExt;
+        use std::os::unix::net::SocketAddr;
+
+        if let Some(name) = socket.as_os_str().as_bytes().strip_prefix(b"@") {
+            let addr = SocketAddr::from_abstract_name(name)?;
+            datagram.send_to_addr(state.as_bytes(), &addr)?;
+            return Ok(());
+        }
+    }
+    datagram.send_to(state.as_bytes(), socket)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn send(_socket: &Path, _state: &str) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixDatagram;
+
+    #[test]
+    fn test_send_state() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("notify.sock");
+        let receiver = UnixDatagram::bind(&path).unwrap();
+
+        send(&path, "READY=1").unwrap();
+        let mut buf = [0u8; 32];
+        let len = receiver.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"READY=1");
+    }
+}
@@ -0,0 +1,200 @@
+//! Heartbeat and crash recovery.
+//!
+//! While running, the daemon keeps `daemon.state` in the data dir with its
+//! PID and a heartbeat, and marks it closed on a clean shutdown. Finding it
+//! still open on the next start means the last daemon crashed or was
+//! killed: stored projects are then verified and their experience logs
+//! repaired before the socket comes up, and the first `Status` reports what
+//! was done.
+
+use engram_indexer::storage::Storage;
+use engram_ipc::RecoveryInfo;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// State file in the data dir
+const STATE_FILE: &str = "daemon.state";
+
+/// What the state file records about a daemon run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DaemonState {
+    pub pid: u32,
+    /// Unix timestamps
+    pub started_at: i64,
+    pub heartbeat: i64,
+    /// Set by a clean shutdown
+    pub clean: bool,
+}
+
+/// Keeps the state file of the running daemon.
+pub struct Supervisor {
+    path: PathBuf,
+    state: Mutex<DaemonState>,
+}
+
+impl Supervisor {
+    /// Take over the state file in `data_dir`.
+    ///
+    /// Returns the previous run's state if it did not shut down cleanly.
+    pub fn start(data_dir: &Path) -> (Self, Option<DaemonState>) {
+        let path = data_dir.join(STATE_FILE);
+        let previous = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<DaemonState>(&content).ok())
+            .filter(|state| !state.clean);
+
+        let now = chrono::Utc::now().timestamp();
+        let supervisor = Self {
+            path,
+            state: Mutex::new(DaemonState {
+                pid: std::process::id(),
+                started_at: now,
+                heartbeat: now,
+                clean: false,
+            }),
+        };
+        supervisor.write();
+        (supervisor, previous)
+    }
+
+    /// Record that the daemon is alive.
+    pub fn beat(&self) {
+        self.state.lock().unwrap().heartbeat = chrono::Utc::now().timestamp();
+        self.write();
+    }
+
+    /// Beat every `interval` until the daemon exits.
+    pub async fn run_heartbeat(self: Arc<Self>, interval: Duration) {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            self.beat();
+        }
+    }
+
+    /// Mark the run as shut down cleanly.
+    pub fn stop(&self) {
+        self.state.lock().unwrap().clean = true;
+        self.write();
+    }
+
+    /// Replace the state file; a crash mid-write leaves the old one.
+    fn write(&self) {
+        let state = self.state.lock().unwrap().clone();
+        let temp_path = self.path.with_extension("state.tmp");
+        let result = serde_json::to_vec(&state)
+            .map_err(std::io::Error::other)
+            .and_then(|json| std::fs::write(&temp_path, json))
+            .and_then(|()| std::fs::rename(&temp_path, &self.path));
+        if let Err(e) = result {
+            tracing::warn!(path = %self.path.display(), "Failed to write daemon state: {}", e);
+        }
+    }
+}
+
+/// Repair experience logs torn by the crash and verify every stored
+/// project.
+pub async fn recover(storage: &Storage, previous: &DaemonState) -> RecoveryInfo {
+    let mut info = RecoveryInfo {
+        previous_pid: previous.pid,
+        last_heartbeat: previous.heartbeat,
+        actions: Vec::new(),
+        problems: Vec::new(),
+    };
+
+    let hashes = match storage.stored_projects().await {
+        Ok(hashes) => hashes,
+        Err(e) => {
+            info.problems
+                .push(format!("Failed to list stored projects: {}", e));
+            return info;
+        }
+    };
+
+    for hash in hashes {
+        let log = storage.experience_log(&hash);
+        match log.repair().await {
+            Ok(repair) => {
+                if repair.terminated {
+                    info.actions.push(format!(
+                        "Completed the last entry of {}",
+                        log.path().display()
+                    ));
+                }
+                if repair.quarantined > 0 {
+                    info.actions.push(format!(
+                        "Moved {} corrupt line(s) of {} to {}",
+                        repair.quarantined,
+                        log.path().display(),
+                        log.quarantine_path().display()
+                    ));
+                }
+            }
+            Err(e) => info
+                .problems
+                .push(format!("{}: {}", log.path().display(), e)),
+        }
+
+        for problem in storage.verify(&hash).await {
+            info.problems
+                .push(format!("{}: {}", problem.path.display(), problem.error));
+        }
+    }
+
+    info
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_detects_unclean_shutdown() {
+        let temp_dir = tempdir().unwrap();
+
+        let (supervisor, previous) = Supervisor::start(temp_dir.path());
+        assert!(previous.is_none());
+        supervisor.beat();
+
+        // Never stopped: the next start sees the open state
+        let (supervisor, previous) = Supervisor::start(temp_dir.path());
+        let previous = previous.unwrap();
+        assert_eq!(previous.pid, std::process::id());
+        assert!(!previous.clean);
+
+        supervisor.stop();
+        let (_, previous) = Supervisor::start(temp_dir.path());
+        assert!(previous.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_recover_repairs_experience_logs() {
+        let temp_dir = tempdir().unwrap();
+        let storage = Storage::new(temp_dir.path().to_path_buf());
+        let log = storage.experience_log("abc123");
+        std::fs::create_dir_all(log.path().parent().unwrap()).unwrap();
+        std::fs::write(log.path(), "{\"a\":1}\n{\"b\":").unwrap();
+
+        let previous = DaemonState {
+            pid: 42,
+            started_at: 0,
+            heartbeat: 10,
+            clean: false,
+        };
+        let info = recover(&storage, &previous).await;
+        assert_eq!(info.previous_pid, 42);
+        assert_eq!(info.last_heartbeat, 10);
+        assert_eq!(info.actions.len(), 1);
+        assert!(info.actions[0].contains("1 corrupt line"));
+        assert!(info.problems.is_empty());
+        assert!(log.invalid_lines().await.unwrap().is_empty());
+        assert_eq!(
+            std::fs::read_to_string(log.quarantine_path()).unwrap(),
+            "{\"b\":\n"
+        );
+    }
+}
@@ -1,7 +1,10 @@
-//! Signal handling for graceful shutdown.
+//! Signal handling for graceful shutdown and config reloads.
 
+use std::sync::Arc;
 use tokio::sync::broadcast;
 
+use crate::handler::DaemonHandler;
+
 /// Wait for shutdown signal (Ctrl+C or explicit shutdown)
 pub async fn wait_for_shutdown(mut shutdown_rx: broadcast::Receiver<()>) {
     tokio::select! {
@@ -32,3 +35,30 @@ async fn wait_for_sigterm() {
     // On non-Unix platforms, just wait forever
     std::future::pending::<()>().await;
 }
+
+/// Reload the daemon config on every SIGHUP
+#[cfg(unix)]
+pub async fn reload_on_sighup(handler: Arc<DaemonHandler>) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut sighup = match signal(SignalKind::hangup()) {
+        Ok(sighup) => sighup,
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to register SIGHUP handler");
+            return;
+        }
+    };
+
+    while sighup.recv().await.is_some() {
+        tracing::info!("Received SIGHUP");
+        if let Err(e) = handler.reload_config().await {
+            tracing::warn!(error = %e, "Failed to reload config");
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub async fn reload_on_sighup(_handler: Arc<DaemonHandler>) {
+    // No SIGHUP outside Unix; reload with `engram reload` instead
+    std::future::pending::<()>().await;
+}
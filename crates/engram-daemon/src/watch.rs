@@ -0,0 +1,285 @@
+//! File watching for loaded projects.
+//!
+//! Every project in the project manager's cache gets a debounced watcher.
+//! Changes are batched per project and applied as incremental tree updates,
+//! after which the cached tree and rendered context for that project are
+//! dropped. Watchers follow the cache: evicted projects stop being watched.
+
+use engram_context::{ContextManager, RenderCache};
+use engram_core::{ProjectManager, WatchConfig};
+use engram_indexer::{ChangeBatcher, FileChange, FileWatcher, WatcherOptions};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// How often the set of watched projects is reconciled with the cache
+pub const RECONCILE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Liveness of the watch manager, recorded on every reconcile pass
+#[derive(Debug, Default)]
+pub struct WatchHeartbeat {
+    /// A watch manager reports here
+    enabled: AtomicBool,
+    /// Unix seconds of the last pass (0 before the first)
+    last: AtomicI64,
+    watched: AtomicUsize,
+    /// Watchers that failed to start or whose event stream ended
+    stopped: AtomicUsize,
+}
+
+impl WatchHeartbeat {
+    /// Expect regular passes from now on
+    pub(crate) fn enable(&self) {
+        self.enabled.store(true, Ordering::Relaxed);
+    }
+
+    /// Record a pass at `now` (unix seconds)
+    pub(crate) fn beat_at(&self, now: i64, watched: usize, stopped: usize) {
+        self.watched.store(watched, Ordering::Relaxed);
+        self.stopped.store(stopped, Ordering::Relaxed);
+        self.last.store(now, Ordering::Relaxed);
+    }
+
+    /// Whether a watch manager reports here
+    pub fn enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Unix seconds of the last pass, if any
+    pub fn last(&self) -> Option<i64> {
+        Some(self.last.load(Ordering::Relaxed)).filter(|&last| last > 0)
+    }
+
+    /// Projects watched as of the last pass
+    pub fn watched(&self) -> usize {
+        self.watched.load(Ordering::Relaxed)
+    }
+
+    /// Watchers no longer running as of the last pass
+    pub fn stopped(&self) -> usize {
+        self.stopped.load(Ordering::Relaxed)
+    }
+}
+
+/// Keeps loaded projects up to date as their files change
+pub struct WatchManager {
+    project_manager: Arc<ProjectManager>,
+    context_manager: Arc<ContextManager>,
+    render_cache: Arc<RenderCache>,
+    debounce: Duration,
+    batch: Duration,
+    heartbeat: Arc<WatchHeartbeat>,
+}
+
+impl WatchManager {
+    /// Create a watch manager
+    pub fn new(
+        project_manager: Arc<ProjectManager>,
+        context_manager: Arc<ContextManager>,
+        render_cache: Arc<RenderCache>,
+        config: &WatchConfig,
+    ) -> Self {
+        Self {
+            project_manager,
+            context_manager,
+            render_cache,
+            debounce: Duration::from_millis(config.debounce_ms),
+            batch: Duration::from_millis(config.batch_ms),
+            heartbeat: Arc::new(WatchHeartbeat::default()),
+        }
+    }
+
+    /// Record liveness in `heartbeat`, which then expects regular passes
+    pub fn with_heartbeat(mut self, heartbeat: Arc<WatchHeartbeat>) -> Self {
+        heartbeat.enable();
+        self.heartbeat = heartbeat;
+        self
+    }
+
+    /// Watch loaded projects until the task is dropped
+    pub async fn run(self) {
+        let manager = Arc::new(self);
+        let mut watchers = HashMap::new();
+        let mut interval = tokio::time::interval(RECONCILE_INTERVAL);
+
+        loop {
+            interval.tick().await;
+            manager.reconcile(&mut watchers).await;
+            let stopped = watchers.values().filter(|task| task.is_finished()).count();
+            manager
+                .heartbeat
+                .beat_at(chrono::Utc::now().timestamp(), watchers.len(), stopped);
+        }
+    }
+
+    /// Start watchers for newly loaded projects and stop those of evicted ones.
+    ///
+    /// A watcher that failed to start is kept until its project is evicted,
+    /// so it is not retried (and logged) on every pass. Read-only projects
+    /// keep the tree they were indexed with and are not watched.
+    async fn reconcile(self: &Arc<Self>, watchers: &mut HashMap<PathBuf, JoinHandle<()>>) {
+        let loaded: Vec<PathBuf> = self
+            .project_manager
+            .loaded_projects()
+            .await
+            .into_iter()
+            .filter(|project| !project.read_only)
+            .map(|project| project.path.clone())
+            .collect();
+
+        watchers.retain(|path, task| {
+            let keep = loaded.contains(path);
+            if !keep {
+                tracing::debug!(path = ?path, "Stopped watching project");
+                task.abort();
+            }
+            keep
+        });
+
+        for path in loaded {
+            if let Entry::Vacant(entry) = watchers.entry(path) {
+                let task = tokio::spawn(self.clone().watch_project(entry.key().clone()));
+                entry.insert(task);
+            }
+        }
+    }
+
+    /// Watch one project, applying changes in batches.
+    async fn watch_project(self: Arc<Self>, root: PathBuf) {
+        let mut watcher = FileWatcher::new(WatcherOptions {
+            debounce_duration: self.debounce,
+            recursive: true,
+        });
+        if let Err(e) = watcher.watch(&root) {
+            tracing::warn!(path = ?root, error = %e, "Failed to watch project");
+            return;
+        }
+
+        let mut batcher = ChangeBatcher::new(self.batch);
+        let mut interval = tokio::time::interval(self.batch);
+
+        loop {
+            tokio::select! {
+                change = watcher.next() => match change {
+                    Some(change) => batcher.add(change),
+                    None => return,
+                },
+                _ = interval.tick() => {
+                    if batcher.is_ready() {
+                        self.apply(&root, batcher.take()).await;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Apply a batch of changes and drop cached state if the tree changed.
+    async fn apply(&self, root: &Path, changes: Vec<FileChange>) {
+        match self.project_manager.update_files(root, &changes).await {
+            Ok(report) if !report.is_empty() => {
+                tracing::info!(
+                    path = ?root,
+                    added = report.added_files,
+                    updated = report.updated_files,
+                    removed = report.removed_files,
+                    renamed = report.renamed_files,
+                    "Applied file changes"
+                );
+                self.context_manager.invalidate_tree(root);
+                self.render_cache.invalidate(root);
+            }
+            Ok(_) => {}
+            // Typically a project that has not finished its first scan
+            Err(e) => tracing::debug!(path = ?root, error = %e, "Skipped file changes"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engram_core::DaemonConfig;
+    use engram_indexer::storage::Storage;
+    use engram_indexer::ChangeKind;
+    use tempfile::tempdir;
+
+    fn test_manager(data_dir: &Path) -> WatchManager {
+        let config = DaemonConfig {
+            data_dir: data_dir.to_path_buf(),
+            ..Default::default()
+        };
+        let storage = Arc::new(Storage::new(data_dir.to_path_buf()));
+        WatchManager::new(
+            Arc::new(ProjectManager::new(&config)),
+            Arc::new(ContextManager::new(storage)),
+            Arc::new(RenderCache::new()),
+            &config.watch,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_apply_updates_tree() {
+        let temp_dir = tempdir().unwrap();
+        let manager = test_manager(&temp_dir.path().join("data"));
+
+        let project_dir = temp_dir.path().join("project");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        std::fs::write(project_dir.join("main.rs"), "fn main() {}").unwrap();
+        let project_dir = project_dir.canonicalize().unwrap();
+        manager
+            .project_manager
+            .init_project(&project_dir)
+            .await
+            .unwrap();
+        manager
+            .project_manager
+            .scan_project(&project_dir)
+            .await
+            .unwrap();
+
+        std::fs::write(project_dir.join("lib.rs"), "pub fn lib() {}").unwrap();
+        manager
+            .apply(
+                &project_dir,
+                vec![FileChange {
+                    path: project_dir.join("lib.rs"),
+                    kind: ChangeKind::Created,
+                }],
+            )
+            .await;
+
+        let tree = manager
+            .project_manager
+            .get_tree(&project_dir)
+            .await
+            .unwrap();
+        assert!(tree.find_by_path(&PathBuf::from("lib.rs")).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_follows_loaded_projects() {
+        let temp_dir = tempdir().unwrap();
+        let manager = Arc::new(test_manager(&temp_dir.path().join("data")));
+
+        let project_dir = temp_dir.path().join("project");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        let project_dir = project_dir.canonicalize().unwrap();
+        manager
+            .project_manager
+            .init_project(&project_dir)
+            .await
+            .unwrap();
+
+        let mut watchers = HashMap::new();
+        manager.reconcile(&mut watchers).await;
+        assert!(watchers.contains_key(&project_dir));
+
+        manager.project_manager.evict_lru().await;
+        manager.reconcile(&mut watchers).await;
+        assert!(watchers.is_empty());
+    }
+}
@@ -14,9 +14,28 @@ fn test_config(temp_dir: &std::path::Path) -> DaemonConfig {
         data_dir: temp_dir.to_path_buf(),
         max_memory: 100 * 1024 * 1024,
         max_projects: 5,
+        pinned_projects: vec![],
+        read_only_projects: vec![],
         log_level: "debug".to_string(),
         pid_file: temp_dir.join("test.pid"),
         auto_init: Default::default(),
+        experiments: Default::default(),
+        skeleton: Default::default(),
+        slo: Default::default(),
+        http_addr: None,
+        metrics_addr: None,
+        watch: Default::default(),
+        memory: Default::default(),
+        auth: Default::default(),
+        enrichment: Default::default(),
+        snapshots: Default::default(),
+        scopes: Default::default(),
+        events: Default::default(),
+        supervisor: Default::default(),
+        shutdown: Default::default(),
+        gc: Default::default(),
+        encryption: Default::default(),
+        storage: Default::default(),
     }
 }
 
@@ -30,6 +49,30 @@ async fn test_project_manager_creation() {
     assert_eq!(manager.loaded_count().await, 0);
 }
 
+/// Test that a socket and PID file configured outside the runtime dir, in
+/// shared or pre-existing directories, do not stop the daemon from starting
+#[cfg(unix)]
+#[test]
+fn test_ensure_dirs_accepts_configured_shared_dirs() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = tempdir().unwrap();
+    let legacy = temp_dir.path().join(".engram");
+    std::fs::create_dir(&legacy).unwrap();
+    std::fs::set_permissions(&legacy, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let mut config = test_config(temp_dir.path());
+    config.pid_file = legacy.join("engram.pid");
+    assert_eq!(
+        config.socket_path.parent(),
+        Some(std::path::Path::new("/tmp"))
+    );
+    config.ensure_dirs().unwrap();
+
+    let mode = std::fs::metadata(&legacy).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o755);
+}
+
 /// Test project initialization and loading
 #[tokio::test]
 async fn test_project_lifecycle_init_and_load() {
@@ -0,0 +1,120 @@
+//! Size and load time of enriched trees by compression level.
+//!
+//! Run with `cargo bench -p engram-indexer --bench enriched_load`.
+
+use engram_indexer::scanner::{Language, ScanResult, ScannedFile, Symbol, SymbolKind};
+use engram_indexer::storage::{Storage, StorageOptions};
+use engram_indexer::tree::{NodeContent, Tree, TreeBuilder};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// Files in the synthetic project
+const FILES: usize = 20_000;
+
+/// Symbols per file
+const SYMBOLS: usize = 8;
+
+/// Timed loads per level; the median is reported
+const RUNS: usize = 5;
+
+fn enriched_tree() -> Tree {
+    let files = (0..FILES)
+        .map(|i| ScannedFile {
+            path: PathBuf::from(format!("src/module_{}/file_{}.rs", i / 100, i)),
+            language: Some(Language::Rust),
+            size: 4096,
+            hash: format!("{:016x}", i),
+            line_count: 200,
+            symbols: (0..SYMBOLS)
+                .map(|j| Symbol {
+                    name: format!("handle_request_{}", j),
+                    kind: SymbolKind::Function,
+                    start_line: j * 20 + 1,
+                    end_line: j * 20 + 18,
+                    parent: None,
+                    doc: Some(format!(
+                        "Handles request kind {} for module {}.",
+                        j,
+                        i / 100
+                    )),
+                })
+                .collect(),
+            imports: vec!["crate::config".to_string(), "std::sync::Arc".to_string()],
+            truncated: false,
+            asset: None,
+        })
+        .collect();
+    let scan = ScanResult {
+        root: PathBuf::from("/bench/project"),
+        files,
+        languages: vec![Language::Rust],
+        frameworks: vec![],
+        duration_ms: 0,
+        skipped_count: 0,
+        excluded_dirs: vec![],
+    };
+
+    let mut tree = TreeBuilder::new().build(&scan);
+    for node in tree.nodes.values_mut().filter(|node| node.is_file()) {
+        node.content = Some(NodeContent {
+            summary: Some(format!("Request handlers for {}.", node.name)),
+            tags: vec!["handlers".to_string()],
+            line_count: 200,
+            ..Default::default()
+        });
+    }
+    tree
+}
+
+fn median(mut samples: Vec<Duration>) -> Duration {
+    samples.sort();
+    samples[samples.len() / 2]
+}
+
+#[tokio::main]
+async fn main() {
+    let tree = enriched_tree();
+    let temp_dir = tempfile::tempdir().unwrap();
+
+    println!(
+        "{} files, {} nodes; median of {} loads",
+        FILES,
+        tree.nodes.len(),
+        RUNS
+    );
+    println!(
+        "{:>5}  {:>10}  {:>10}  {:>10}",
+        "Level", "Size (KB)", "Save (ms)", "Load (ms)"
+    );
+    for level in [0, 1, 3, 9, 19] {
+        let storage = Storage::with_options(StorageOptions {
+            base_dir: temp_dir.path().to_path_buf(),
+            compression_level: level,
+            ..Default::default()
+        });
+        let hash = format!("level{}", level);
+
+        let start = Instant::now();
+        storage.save_enriched(&tree, &hash).await.unwrap();
+        let save = start.elapsed();
+        let size = std::fs::metadata(storage.project_dir(&hash).join("enriched.msgpack"))
+            .unwrap()
+            .len();
+
+        let mut loads = Vec::with_capacity(RUNS);
+        for _ in 0..RUNS {
+            let start = Instant::now();
+            let loaded = storage.load_enriched(&hash).await.unwrap();
+            loads.push(start.elapsed());
+            assert_eq!(loaded.nodes.len(), tree.nodes.len());
+        }
+
+        println!(
+            "{:>5}  {:>10}  {:>10.1}  {:>10.1}",
+            level,
+            size / 1024,
+            save.as_secs_f64() * 1000.0,
+            median(loads).as_secs_f64() * 1000.0
+        );
+    }
+}
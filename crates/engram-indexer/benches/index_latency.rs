@@ -0,0 +1,106 @@
+//! Full index and incremental update latency by repository size.
+//!
+//! Run with `cargo bench -p engram-indexer --bench index_latency`. The
+//! end-to-end numbers, context rendering included, come from `engram bench`.
+
+use engram_indexer::scanner::Scanner;
+use engram_indexer::storage::Storage;
+use engram_indexer::tree::{apply_updates, FileUpdate, TreeBuilder};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Repository sizes benchmarked, in files
+const SIZES: &[usize] = &[1_000, 5_000, 20_000];
+
+/// Files per module directory
+const FILES_PER_MODULE: usize = 50;
+
+/// Timed incremental updates per size; the median is reported
+const RUNS: usize = 5;
+
+fn file_path(i: usize) -> PathBuf {
+    PathBuf::from(format!("src/module_{}/file_{}.rs", i / FILES_PER_MODULE, i))
+}
+
+fn file_source(i: usize, files: usize, functions: usize) -> String {
+    let next = (i + FILES_PER_MODULE) % files;
+    let mut source = format!(
+        "use crate::module_{}::file_{};\n",
+        next / FILES_PER_MODULE,
+        next
+    );
+    for j in 0..functions {
+        source.push_str(&format!(
+            "\n/// Handles request kind {}.\npub fn handle_request_{}(input: &str) -> usize {{\n    input.len() + {}\n}}\n",
+            j, j, j
+        ));
+    }
+    source
+}
+
+fn generate_repo(root: &Path, files: usize) {
+    for i in 0..files {
+        let path = root.join(file_path(i));
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, file_source(i, files, 4)).unwrap();
+    }
+}
+
+fn median(mut samples: Vec<Duration>) -> Duration {
+    samples.sort();
+    samples[samples.len() / 2]
+}
+
+fn ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[tokio::main]
+async fn main() {
+    println!("Median of {} incremental updates", RUNS);
+    println!(
+        "{:>6}  {:>10}  {:>10}  {:>10}  {:>12}",
+        "Files", "Scan (ms)", "Build (ms)", "Save (ms)", "Update (ms)"
+    );
+    for &files in SIZES {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path().join("repo");
+        generate_repo(&root, files);
+        let root = root.canonicalize().unwrap();
+        let scanner = Scanner::new();
+
+        let start = Instant::now();
+        let scan = scanner.scan(&root).await.unwrap();
+        let scan_time = start.elapsed();
+
+        let start = Instant::now();
+        let mut tree = TreeBuilder::new().build(&scan);
+        let build_time = start.elapsed();
+        assert_eq!(tree.file_count, files);
+
+        let storage = Storage::new(temp_dir.path().join("data"));
+        let start = Instant::now();
+        storage.save_skeleton(&tree, "bench").await.unwrap();
+        let save_time = start.elapsed();
+
+        let mut updates = Vec::with_capacity(RUNS);
+        for run in 0..RUNS {
+            let path = root.join(file_path(run));
+            std::fs::write(&path, file_source(run, files, 5 + run)).unwrap();
+            let start = Instant::now();
+            let file = scanner.scan_file(&root, &path).await.unwrap().unwrap();
+            let report = apply_updates(&mut tree, &[FileUpdate::Upsert(file)]);
+            updates.push(start.elapsed());
+            assert_eq!(report.updated_files, 1);
+        }
+
+        println!(
+            "{:>6}  {:>10.1}  {:>10.1}  {:>10.1}  {:>12.2}",
+            files,
+            ms(scan_time),
+            ms(build_time),
+            ms(save_time),
+            ms(median(updates))
+        );
+    }
+}
@@ -0,0 +1,77 @@
+//! Cooperative cancellation for long-running requests.
+//!
+//! A token is cancelled explicitly or once its deadline passes. Work that
+//! accepts one checks it at natural boundaries (between files, between
+//! context layers) and stops early; nothing is interrupted mid-step.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Shared cancellation flag with an optional deadline.
+///
+/// Clones observe the same flag. The default token is never cancelled.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    inner: Arc<CancelState>,
+}
+
+#[derive(Debug, Default)]
+struct CancelState {
+    cancelled: AtomicBool,
+    deadline: Option<Instant>,
+}
+
+impl CancelToken {
+    /// Create a token that is only cancelled explicitly.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a token that is also cancelled once `timeout` has elapsed.
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Self {
+            inner: Arc::new(CancelState {
+                cancelled: AtomicBool::new(false),
+                deadline: Instant::now().checked_add(timeout),
+            }),
+        }
+    }
+
+    /// Request cancellation.
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Check whether cancellation was requested or the deadline passed.
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::Relaxed) || self.is_expired()
+    }
+
+    /// Check whether the deadline passed.
+    pub fn is_expired(&self) -> bool {
+        self.inner
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancel_and_deadline() {
+        let token = CancelToken::new();
+        let clone = token.clone();
+        assert!(!clone.is_cancelled());
+        token.cancel();
+        assert!(clone.is_cancelled());
+        assert!(!clone.is_expired());
+
+        assert!(!CancelToken::with_timeout(Duration::from_secs(60)).is_cancelled());
+        let expired = CancelToken::with_timeout(Duration::ZERO);
+        assert!(expired.is_cancelled());
+        assert!(expired.is_expired());
+    }
+}
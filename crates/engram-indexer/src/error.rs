@@ -30,9 +30,25 @@ pub enum IndexerError {
     #[error("Path not found: {0}")]
     NotFound(PathBuf),
 
+    /// Failed to load a runtime grammar
+    #[error("Grammar error in {path}: {message}")]
+    Grammar { path: PathBuf, message: String },
+
     /// Invalid language
     #[error("Unsupported language: {0}")]
     UnsupportedLanguage(String),
+
+    /// Invalid glob pattern
+    #[error("Invalid glob: {0}")]
+    InvalidGlob(String),
+
+    /// Git command failed
+    #[error("Git error: {0}")]
+    Git(String),
+
+    /// Operation was cancelled
+    #[error("Cancelled")]
+    Cancelled,
 }
 
 impl From<serde_json::Error> for IndexerError {
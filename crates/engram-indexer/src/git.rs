@@ -0,0 +1,189 @@
+//! Git integration.
+//!
+//! Detects the branch and commit a project is checked out at, and which
+//! files differ from `HEAD` or from the merge-base with another branch.
+//! Shells out to the `git` binary; projects outside a repository (or
+//! machines without git) simply have no git state.
+//!
+//! All paths are relative to the project root, which may be a subdirectory
+//! of the repository. Files outside the project are left out.
+
+use crate::IndexerError;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Where a project's working tree is checked out.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GitState {
+    /// Current branch, `None` on a detached `HEAD`
+    pub branch: Option<String>,
+
+    /// Full hash of the `HEAD` commit
+    pub commit: String,
+}
+
+/// Detect the branch and commit of the repository containing `root`.
+///
+/// Returns `None` outside a repository, before the first commit, or when
+/// git is not installed.
+pub fn state(root: &Path) -> Option<GitState> {
+    let commit = git(root, &["rev-parse", "--verify", "HEAD"]).ok()?;
+    let branch = git(root, &["symbolic-ref", "--quiet", "--short", "HEAD"]).ok();
+    Some(GitState {
+        branch: branch.map(|b| b.trim().to_string()),
+        commit: commit.trim().to_string(),
+    })
+}
+
+/// Files with uncommitted changes, including untracked ones.
+pub fn dirty_files(root: &Path) -> Result<Vec<PathBuf>, IndexerError> {
+    changed_since(root, "HEAD")
+}
+
+/// Files changed since the merge-base of `HEAD` and `base`.
+///
+/// Covers commits on the current branch as well as uncommitted and
+/// untracked files, i.e. everything a review of the branch would see.
+/// Deleted files are left out.
+pub fn changed_since_merge_base(root: &Path, base: &str) -> Result<Vec<PathBuf>, IndexerError> {
+    let merge_base = git(root, &["merge-base", "HEAD", base])?;
+    changed_since(root, merge_base.trim())
+}
+
+/// Files differing from `revision` in the working tree, plus untracked ones.
+fn changed_since(root: &Path, revision: &str) -> Result<Vec<PathBuf>, IndexerError> {
+    let diff = git(
+        root,
+        &[
+            "diff",
+            "--name-only",
+            "-z",
+            "--relative",
+            "--diff-filter=d",
+            revision,
+            "--",
+        ],
+    )?;
+    let untracked = git(root, &["ls-files", "--others", "--exclude-standard", "-z"])?;
+
+    let mut files: Vec<PathBuf> = diff
+        .split('\0')
+        .chain(untracked.split('\0'))
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+        .collect();
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+/// Run git in `root` and return its stdout.
+fn git(root: &Path, args: &[&str]) -> Result<String, IndexerError> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(args)
+        .output()
+        .map_err(|e| IndexerError::Git(format!("failed to run git: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(IndexerError::Git(format!(
+            "git {} failed: {}",
+            args.first().copied().unwrap_or_default(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    String::from_utf8(output.stdout)
+        .map_err(|_| IndexerError::Git("git output is not UTF-8".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn run(root: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(root)
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    fn repo() -> tempfile::TempDir {
+        let dir = tempdir().unwrap();
+        run(dir.path(), &["init", "--quiet", "--initial-branch=main"]);
+        fs::create_dir_all(dir.path().join("app/src")).unwrap();
+        fs::write(dir.path().join("app/src/lib.rs"), "fn a() {}").unwrap();
+        fs::write(dir.path().join("app/src/old.rs"), "fn b() {}").unwrap();
+        fs::write(dir.path().join("README.md"), "readme").unwrap();
+        run(dir.path(), &["add", "."]);
+        run(dir.path(), &["commit", "--quiet", "-m", "initial"]);
+        dir
+    }
+
+    #[test]
+    fn test_state() {
+        let dir = repo();
+        let state = state(dir.path()).unwrap();
+        assert_eq!(state.branch.as_deref(), Some("main"));
+        assert_eq!(state.commit.len(), 40);
+
+        run(dir.path(), &["checkout", "--quiet", "--detach"]);
+        assert_eq!(super::state(dir.path()).unwrap().branch, None);
+    }
+
+    #[test]
+    fn test_state_outside_repository() {
+        let dir = tempdir().unwrap();
+        assert_eq!(state(dir.path()), None);
+    }
+
+    #[test]
+    fn test_dirty_files_relative_to_project() {
+        let dir = repo();
+        let project = dir.path().join("app");
+        fs::write(project.join("src/lib.rs"), "fn a() { changed() }").unwrap();
+        fs::write(project.join("src/new.rs"), "fn c() {}").unwrap();
+        fs::write(dir.path().join("README.md"), "outside the project").unwrap();
+
+        assert_eq!(
+            dirty_files(&project).unwrap(),
+            vec![PathBuf::from("src/lib.rs"), PathBuf::from("src/new.rs")]
+        );
+    }
+
+    #[test]
+    fn test_changed_since_merge_base() {
+        let dir = repo();
+        run(dir.path(), &["checkout", "--quiet", "-b", "feature"]);
+        fs::write(dir.path().join("app/src/lib.rs"), "fn a() { changed() }").unwrap();
+        run(dir.path(), &["rm", "--quiet", "app/src/old.rs"]);
+        run(dir.path(), &["commit", "--quiet", "-am", "change"]);
+        fs::write(dir.path().join("app/src/wip.rs"), "fn d() {}").unwrap();
+
+        // Work on main after branching is not part of the review
+        run(dir.path(), &["checkout", "--quiet", "main"]);
+        fs::write(dir.path().join("README.md"), "main moved on").unwrap();
+        run(dir.path(), &["commit", "--quiet", "-am", "main"]);
+        run(dir.path(), &["checkout", "--quiet", "feature"]);
+
+        assert_eq!(
+            changed_since_merge_base(dir.path(), "main").unwrap(),
+            vec![
+                PathBuf::from("app/src/lib.rs"),
+                PathBuf::from("app/src/wip.rs")
+            ]
+        );
+        assert!(matches!(
+            changed_since_merge_base(dir.path(), "no-such-branch"),
+            Err(IndexerError::Git(_))
+        ));
+    }
+}
@@ -6,15 +6,22 @@
 //! - Tree structure building and dependency tracking
 //! - Persistence with memory-mapped file access
 //! - File watching with debounced incremental updates
+//! - Git branch detection and diff-scoped file lists
 
+mod cancel;
 mod error;
+pub mod git;
 pub mod scanner;
 pub mod storage;
 pub mod tree;
 pub mod watcher;
 
+pub use cancel::CancelToken;
 pub use error::IndexerError;
-pub use scanner::{Language, ScanOptions, ScanResult, ScannedFile, Scanner};
-pub use storage::{ExperienceLog, SnapshotManager, Storage, StorageOptions};
-pub use tree::{DependencyGraph, Node, NodeId, NodeKind, Tree, TreeBuilder};
+pub use git::GitState;
+pub use scanner::{Language, RescanResult, ScanOptions, ScanResult, ScannedFile, Scanner};
+pub use storage::{
+    ExperienceLog, LogCipher, MappedTree, SnapshotManager, Storage, StorageOptions, StorageProblem,
+};
+pub use tree::{DependencyGraph, Node, NodeId, NodeKind, TestLinks, Tree, TreeBuilder};
 pub use watcher::{ChangeBatcher, ChangeKind, FileChange, FileWatcher, WatcherOptions};
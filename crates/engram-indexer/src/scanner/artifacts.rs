@@ -0,0 +1,155 @@
+//! Detection of well-known build artifact directories.
+//!
+//! Some repositories don't gitignore their build output (`target/`,
+//! `dist/`, virtualenvs, ...). Scanning those directories is slow and
+//! pollutes the tree, so they are skipped unless explicitly allowlisted.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// A directory skipped during scanning because it looks like build output.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExcludedDir {
+    /// Path relative to the scan root
+    pub path: PathBuf,
+    /// Why the directory was treated as an artifact directory
+    pub reason: String,
+}
+
+/// Name-based artifact rule.
+struct ArtifactRule {
+    /// Directory name
+    name: &'static str,
+    /// Human-readable reason
+    reason: &'static str,
+    /// Sibling files, one of which must exist in the parent directory
+    /// (empty = the name alone is conclusive)
+    markers: &'static [&'static str],
+}
+
+const ARTIFACT_RULES: &[ArtifactRule] = &[
+    ArtifactRule {
+        name: "node_modules",
+        reason: "npm dependencies",
+        markers: &[],
+    },
+    ArtifactRule {
+        name: "__pycache__",
+        reason: "Python bytecode cache",
+        markers: &[],
+    },
+    ArtifactRule {
+        name: "target",
+        reason: "Cargo/Maven build output",
+        markers: &["Cargo.toml", "pom.xml"],
+    },
+    ArtifactRule {
+        name: "dist",
+        reason: "JavaScript/Python distribution output",
+        markers: &["package.json", "setup.py", "pyproject.toml"],
+    },
+    ArtifactRule {
+        name: "build",
+        reason: "build output",
+        markers: &[
+            "package.json",
+            "build.gradle",
+            "build.gradle.kts",
+            "setup.py",
+            "pyproject.toml",
+            "CMakeLists.txt",
+        ],
+    },
+    ArtifactRule {
+        name: "out",
+        reason: "compiler output",
+        markers: &["package.json", "tsconfig.json"],
+    },
+    ArtifactRule {
+        name: "coverage",
+        reason: "test coverage reports",
+        markers: &["package.json"],
+    },
+];
+
+/// Check whether `dir` is a well-known artifact directory.
+///
+/// Returns the reason it was detected, or `None` for regular directories.
+pub fn detect_artifact_dir(dir: &Path) -> Option<&'static str> {
+    if dir.join("CACHEDIR.TAG").is_file() {
+        return Some("cache directory (CACHEDIR.TAG)");
+    }
+    if dir.join("pyvenv.cfg").is_file() {
+        return Some("Python virtualenv");
+    }
+
+    let name = dir.file_name()?.to_str()?;
+    let rule = ARTIFACT_RULES.iter().find(|rule| rule.name == name)?;
+    if rule.markers.is_empty() {
+        return Some(rule.reason);
+    }
+
+    let parent = dir.parent()?;
+    rule.markers
+        .iter()
+        .any(|marker| parent.join(marker).is_file())
+        .then_some(rule.reason)
+}
+
+/// Check whether a directory is allowlisted.
+///
+/// Allowlist entries match either the relative path (`packages/app/dist`)
+/// or, for single-component entries, any directory with that name (`dist`).
+pub fn is_allowlisted(rel_path: &Path, allowlist: &[PathBuf]) -> bool {
+    allowlist.iter().any(|allowed| {
+        rel_path == allowed
+            || (allowed.components().count() == 1 && rel_path.file_name() == allowed.file_name())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_node_modules_always_detected() {
+        let temp_dir = tempdir().unwrap();
+        let dir = temp_dir.path().join("node_modules");
+        fs::create_dir(&dir).unwrap();
+
+        assert_eq!(detect_artifact_dir(&dir), Some("npm dependencies"));
+    }
+
+    #[test]
+    fn test_target_requires_marker() {
+        let temp_dir = tempdir().unwrap();
+        let dir = temp_dir.path().join("target");
+        fs::create_dir(&dir).unwrap();
+        assert_eq!(detect_artifact_dir(&dir), None);
+
+        fs::write(temp_dir.path().join("Cargo.toml"), "[package]").unwrap();
+        assert!(detect_artifact_dir(&dir).is_some());
+    }
+
+    #[test]
+    fn test_virtualenv_detected_by_any_name() {
+        let temp_dir = tempdir().unwrap();
+        let dir = temp_dir.path().join("myenv");
+        fs::create_dir(&dir).unwrap();
+        fs::write(dir.join("pyvenv.cfg"), "home = /usr/bin").unwrap();
+
+        assert_eq!(detect_artifact_dir(&dir), Some("Python virtualenv"));
+    }
+
+    #[test]
+    fn test_allowlist_matching() {
+        let allowlist = vec![PathBuf::from("dist"), PathBuf::from("packages/app/build/")];
+
+        assert!(is_allowlisted(Path::new("dist"), &allowlist));
+        assert!(is_allowlisted(Path::new("web/dist"), &allowlist));
+        assert!(is_allowlisted(Path::new("packages/app/build"), &allowlist));
+        assert!(!is_allowlisted(Path::new("build"), &allowlist));
+    }
+}
@@ -0,0 +1,106 @@
+//! Detection of binary and asset files.
+//!
+//! Assets are cataloged by size and type instead of being parsed, so the
+//! tree can tell that a project ships images, archives or model weights
+//! without reading them.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Bytes inspected to tell a binary file from text
+pub const SNIFF_BYTES: usize = 8 * 1024;
+
+/// Type of a non-source file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AssetKind {
+    Image,
+    /// Audio and video
+    Media,
+    Font,
+    Archive,
+    /// PDFs and office documents
+    Document,
+    /// Machine learning weights and serialized data
+    Model,
+    /// Databases
+    Database,
+    /// Executables, libraries and anything else not text
+    Binary,
+}
+
+impl AssetKind {
+    /// Get the display name for this kind.
+    pub fn name(&self) -> &'static str {
+        match self {
+            AssetKind::Image => "image",
+            AssetKind::Media => "media",
+            AssetKind::Font => "font",
+            AssetKind::Archive => "archive",
+            AssetKind::Document => "document",
+            AssetKind::Model => "model",
+            AssetKind::Database => "database",
+            AssetKind::Binary => "binary",
+        }
+    }
+}
+
+/// Detect an asset from its file extension.
+pub fn detect_asset(path: &Path) -> Option<AssetKind> {
+    let ext = path.extension()?.to_str()?.to_lowercase();
+
+    match ext.as_str() {
+        "png" | "jpg" | "jpeg" | "gif" | "bmp" | "ico" | "icns" | "webp" | "tif" | "tiff"
+        | "svg" | "psd" | "avif" | "heic" => Some(AssetKind::Image),
+        "mp3" | "wav" | "ogg" | "flac" | "aac" | "m4a" | "mp4" | "mov" | "avi" | "mkv" | "webm" => {
+            Some(AssetKind::Media)
+        }
+        "ttf" | "otf" | "woff" | "woff2" | "eot" => Some(AssetKind::Font),
+        "zip" | "tar" | "gz" | "tgz" | "bz2" | "xz" | "zst" | "7z" | "rar" | "jar" | "war"
+        | "whl" | "deb" | "rpm" => Some(AssetKind::Archive),
+        "pdf" | "doc" | "docx" | "xls" | "xlsx" | "ppt" | "pptx" | "odt" => {
+            Some(AssetKind::Document)
+        }
+        "onnx" | "pt" | "pth" | "ckpt" | "safetensors" | "gguf" | "h5" | "pb" | "tflite"
+        | "npy" | "npz" | "pkl" | "parquet" => Some(AssetKind::Model),
+        "db" | "sqlite" | "sqlite3" => Some(AssetKind::Database),
+        "exe" | "dll" | "so" | "dylib" | "a" | "lib" | "o" | "obj" | "class" | "wasm" | "bin"
+        | "dat" => Some(AssetKind::Binary),
+        _ => None,
+    }
+}
+
+/// Whether the start of a file looks binary rather than text.
+pub fn looks_binary(start: &[u8]) -> bool {
+    start[..start.len().min(SNIFF_BYTES)].contains(&0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_asset() {
+        assert_eq!(
+            detect_asset(Path::new("assets/logo.PNG")),
+            Some(AssetKind::Image)
+        );
+        assert_eq!(
+            detect_asset(Path::new("models/model.onnx")),
+            Some(AssetKind::Model)
+        );
+        assert_eq!(
+            detect_asset(Path::new("dist/app.tar.gz")),
+            Some(AssetKind::Archive)
+        );
+        assert_eq!(detect_asset(Path::new("src/main.rs")), None);
+        assert_eq!(detect_asset(Path::new("Makefile")), None);
+    }
+
+    #[test]
+    fn test_looks_binary() {
+        assert!(looks_binary(b"\x7fELF\x02\x01\x01\0\0"));
+        assert!(!looks_binary("fn main() {}\n// é".as_bytes()));
+        assert!(!looks_binary(b""));
+    }
+}
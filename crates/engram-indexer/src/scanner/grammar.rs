@@ -0,0 +1,229 @@
+//! Grammar resolution for tree-sitter parsing.
+//!
+//! Grammars come from two places: the ones compiled in via the `lang-*`
+//! cargo features, and (with `dynamic-grammars`) shared objects dropped into
+//! a grammars directory. Dynamic grammars are loaded lazily the first time a
+//! file of that language is parsed, and the result is cached per language.
+
+use super::Language;
+use crate::IndexerError;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tracing::warn;
+
+/// Directory name for runtime grammars, relative to the data dir.
+pub const GRAMMARS_DIR: &str = "grammars";
+
+static GLOBAL: OnceLock<GrammarLoader> = OnceLock::new();
+
+/// Where a grammar was resolved from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GrammarSource {
+    /// Compiled into the binary
+    Builtin,
+    /// Loaded from a shared object in the grammars dir
+    Dynamic,
+}
+
+/// Resolves and caches tree-sitter grammars.
+pub struct GrammarLoader {
+    dir: Option<PathBuf>,
+    cache: Mutex<HashMap<Language, Option<(tree_sitter::Language, GrammarSource)>>>,
+    /// Loaded libraries are never unloaded: grammars point into their data.
+    #[cfg(feature = "dynamic-grammars")]
+    libraries: Mutex<Vec<libloading::Library>>,
+}
+
+impl GrammarLoader {
+    /// Create a loader that looks for shared objects in `dir`.
+    pub fn new(dir: Option<PathBuf>) -> Self {
+        Self {
+            dir,
+            cache: Mutex::new(HashMap::new()),
+            #[cfg(feature = "dynamic-grammars")]
+            libraries: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Install the process-wide loader. Returns false if it was already set.
+    pub fn init_global(dir: PathBuf) -> bool {
+        GLOBAL.set(Self::new(Some(dir))).is_ok()
+    }
+
+    /// The process-wide loader (builtin grammars only unless initialized).
+    pub fn global() -> &'static GrammarLoader {
+        GLOBAL.get_or_init(|| Self::new(None))
+    }
+
+    /// The grammars directory, if any.
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    /// Get the grammar for a language, loading it on first use.
+    pub fn get(&self, language: Language) -> Option<tree_sitter::Language> {
+        self.resolve(language).map(|(grammar, _)| grammar)
+    }
+
+    /// Where the grammar for a language comes from, if one is available.
+    pub fn source(&self, language: Language) -> Option<GrammarSource> {
+        self.resolve(language).map(|(_, source)| source)
+    }
+
+    fn resolve(&self, language: Language) -> Option<(tree_sitter::Language, GrammarSource)> {
+        if let Some(cached) = self.cache.lock().get(&language) {
+            return cached.clone();
+        }
+
+        let resolved = builtin(language)
+            .map(|grammar| (grammar, GrammarSource::Builtin))
+            .or_else(|| {
+                let name = language.grammar_name()?;
+                match self.load_dynamic(name) {
+                    Ok(grammar) => grammar.map(|g| (g, GrammarSource::Dynamic)),
+                    Err(e) => {
+                        warn!(language = name, error = %e, "Failed to load grammar");
+                        None
+                    }
+                }
+            });
+
+        self.cache.lock().insert(language, resolved.clone());
+        resolved
+    }
+
+    #[cfg(feature = "dynamic-grammars")]
+    fn load_dynamic(&self, name: &str) -> Result<Option<tree_sitter::Language>, IndexerError> {
+        let Some(dir) = &self.dir else {
+            return Ok(None);
+        };
+        let Some(path) = library_candidates(dir, name)
+            .into_iter()
+            .find(|p| p.is_file())
+        else {
+            return Ok(None);
+        };
+
+        let grammar_error = |message: String| IndexerError::Grammar {
+            path: path.clone(),
+            message,
+        };
+        let symbol = format!("tree_sitter_{}", name.replace('-', "_"));
+
+        // SAFETY: tree-sitter grammar libraries export `tree_sitter_<name>`,
+        // a C function returning a pointer to a static TSLanguage. The library
+        // is kept loaded for the lifetime of the loader.
+        let grammar = unsafe {
+            let library =
+                libloading::Library::new(&path).map_err(|e| grammar_error(e.to_string()))?;
+            let constructor = *library
+                .get::<unsafe extern "C" fn() -> *const ()>(symbol.as_bytes())
+                .map_err(|e| grammar_error(e.to_string()))?;
+            let grammar =
+                tree_sitter::Language::new(tree_sitter_language::LanguageFn::from_raw(constructor));
+            self.libraries.lock().push(library);
+            grammar
+        };
+
+        let version = grammar.version();
+        if !(tree_sitter::MIN_COMPATIBLE_LANGUAGE_VERSION..=tree_sitter::LANGUAGE_VERSION)
+            .contains(&version)
+        {
+            return Err(grammar_error(format!(
+                "incompatible ABI version {} (supported {}..={})",
+                version,
+                tree_sitter::MIN_COMPATIBLE_LANGUAGE_VERSION,
+                tree_sitter::LANGUAGE_VERSION
+            )));
+        }
+
+        tracing::debug!(language = name, path = %path.display(), "Loaded grammar");
+        Ok(Some(grammar))
+    }
+
+    #[cfg(not(feature = "dynamic-grammars"))]
+    fn load_dynamic(&self, _name: &str) -> Result<Option<tree_sitter::Language>, IndexerError> {
+        Ok(None)
+    }
+}
+
+/// Grammars compiled into this build.
+fn builtin(language: Language) -> Option<tree_sitter::Language> {
+    match language {
+        #[cfg(feature = "lang-rust")]
+        Language::Rust => Some(tree_sitter_rust::LANGUAGE.into()),
+        #[cfg(feature = "lang-typescript")]
+        Language::TypeScript => Some(tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into()),
+        #[cfg(feature = "lang-typescript")]
+        Language::JavaScript => Some(tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into()), // TS parser handles JS
+        #[cfg(feature = "lang-python")]
+        Language::Python => Some(tree_sitter_python::LANGUAGE.into()),
+        #[cfg(feature = "lang-go")]
+        Language::Go => Some(tree_sitter_go::LANGUAGE.into()),
+        #[cfg(feature = "lang-c")]
+        Language::C => Some(tree_sitter_c::LANGUAGE.into()),
+        #[cfg(feature = "lang-cpp")]
+        Language::Cpp => Some(tree_sitter_cpp::LANGUAGE.into()),
+        _ => None,
+    }
+}
+
+/// File names checked for a grammar, in order of preference.
+#[cfg_attr(not(feature = "dynamic-grammars"), allow(dead_code))]
+fn library_candidates(dir: &Path, name: &str) -> Vec<PathBuf> {
+    let ext = std::env::consts::DLL_EXTENSION;
+    [
+        format!("libtree-sitter-{name}.{ext}"),
+        format!("tree-sitter-{name}.{ext}"),
+        format!("{name}.{ext}"),
+    ]
+    .into_iter()
+    .map(|file| dir.join(file))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "lang-rust")]
+    #[test]
+    fn test_builtin_grammar() {
+        let loader = GrammarLoader::new(None);
+        assert!(loader.get(Language::Rust).is_some());
+        assert_eq!(loader.source(Language::Rust), Some(GrammarSource::Builtin));
+    }
+
+    #[test]
+    fn test_missing_grammar() {
+        let dir = tempfile::tempdir().unwrap();
+        let loader = GrammarLoader::new(Some(dir.path().to_path_buf()));
+        assert!(loader.get(Language::Json).is_none());
+        assert!(loader.source(Language::Unknown).is_none());
+    }
+
+    #[cfg(feature = "dynamic-grammars")]
+    #[test]
+    fn test_invalid_library_is_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let name = Language::Go.grammar_name().unwrap();
+        std::fs::write(&library_candidates(dir.path(), name)[0], b"not a library").unwrap();
+
+        let loader = GrammarLoader::new(Some(dir.path().to_path_buf()));
+        assert!(matches!(
+            loader.load_dynamic(name),
+            Err(IndexerError::Grammar { .. })
+        ));
+    }
+
+    #[test]
+    fn test_library_candidates() {
+        let candidates = library_candidates(Path::new("/g"), "go");
+        assert_eq!(candidates.len(), 3);
+        assert!(candidates[0]
+            .to_string_lossy()
+            .starts_with("/g/libtree-sitter-go."));
+    }
+}
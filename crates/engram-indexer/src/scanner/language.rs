@@ -12,12 +12,15 @@ pub enum Language {
     JavaScript,
     Python,
     Go,
+    C,
+    Cpp,
     Json,
     Yaml,
     Toml,
     Markdown,
     Html,
     Css,
+    Makefile,
     Unknown,
 }
 
@@ -30,17 +33,40 @@ impl Language {
             Language::JavaScript => "JavaScript",
             Language::Python => "Python",
             Language::Go => "Go",
+            Language::C => "C",
+            Language::Cpp => "C++",
             Language::Json => "JSON",
             Language::Yaml => "YAML",
             Language::Toml => "TOML",
             Language::Markdown => "Markdown",
             Language::Html => "HTML",
             Language::Css => "CSS",
+            Language::Makefile => "Makefile",
             Language::Unknown => "Unknown",
         }
     }
 
+    /// Name of the tree-sitter grammar used for symbol extraction.
+    ///
+    /// Runtime grammars are looked up by this name, e.g.
+    /// `libtree-sitter-go.so` exporting `tree_sitter_go`.
+    pub fn grammar_name(&self) -> Option<&'static str> {
+        match self {
+            Language::Rust => Some("rust"),
+            Language::TypeScript => Some("typescript"),
+            Language::JavaScript => Some("javascript"),
+            Language::Python => Some("python"),
+            Language::Go => Some("go"),
+            Language::C => Some("c"),
+            Language::Cpp => Some("cpp"),
+            _ => None,
+        }
+    }
+
     /// Check if this language has tree-sitter support.
+    ///
+    /// Parsing also needs a grammar, either compiled in or installed in the
+    /// grammars dir; see [`GrammarLoader`](super::GrammarLoader).
     pub fn has_parser(&self) -> bool {
         matches!(
             self,
@@ -49,12 +75,19 @@ impl Language {
                 | Language::JavaScript
                 | Language::Python
                 | Language::Go
+                | Language::C
+                | Language::Cpp
         )
     }
 }
 
 /// Detect the language of a file based on its extension.
 pub fn detect_language(path: &Path) -> Option<Language> {
+    // Build files named without an extension
+    if let Some("Makefile" | "makefile" | "GNUmakefile") = path.file_name()?.to_str() {
+        return Some(Language::Makefile);
+    }
+
     let ext = path.extension()?.to_str()?.to_lowercase();
 
     match ext.as_str() {
@@ -71,6 +104,12 @@ pub fn detect_language(path: &Path) -> Option<Language> {
         // Go
         "go" => Some(Language::Go),
 
+        // C/C++ (`.h` headers are assumed to be C)
+        "c" | "h" => Some(Language::C),
+        "cc" | "cpp" | "cxx" | "c++" | "hh" | "hpp" | "hxx" | "h++" | "ipp" | "tpp" => {
+            Some(Language::Cpp)
+        }
+
         // Config/Data
         "json" => Some(Language::Json),
         "yaml" | "yml" => Some(Language::Yaml),
@@ -83,6 +122,9 @@ pub fn detect_language(path: &Path) -> Option<Language> {
         // Documentation
         "md" | "markdown" => Some(Language::Markdown),
 
+        // Build
+        "mk" => Some(Language::Makefile),
+
         _ => None,
     }
 }
@@ -174,6 +216,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_detect_c_cpp() {
+        assert_eq!(detect_language(&PathBuf::from("main.c")), Some(Language::C));
+        assert_eq!(detect_language(&PathBuf::from("util.h")), Some(Language::C));
+        assert_eq!(
+            detect_language(&PathBuf::from("widget.cpp")),
+            Some(Language::Cpp)
+        );
+        assert_eq!(
+            detect_language(&PathBuf::from("widget.hpp")),
+            Some(Language::Cpp)
+        );
+    }
+
     #[test]
     fn test_detect_config_files() {
         assert_eq!(
@@ -218,6 +274,22 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_detect_makefile() {
+        assert_eq!(
+            detect_language(&PathBuf::from("Makefile")),
+            Some(Language::Makefile)
+        );
+        assert_eq!(
+            detect_language(&PathBuf::from("build/GNUmakefile")),
+            Some(Language::Makefile)
+        );
+        assert_eq!(
+            detect_language(&PathBuf::from("rules.mk")),
+            Some(Language::Makefile)
+        );
+    }
+
     #[test]
     fn test_detect_unknown() {
         assert_eq!(detect_language(&PathBuf::from("file.xyz")), None);
@@ -248,6 +320,8 @@ mod tests {
         assert!(Language::TypeScript.has_parser());
         assert!(Language::Python.has_parser());
         assert!(Language::Go.has_parser());
+        assert!(Language::C.has_parser());
+        assert!(Language::Cpp.has_parser());
         assert!(!Language::Json.has_parser());
         assert!(!Language::Markdown.has_parser());
     }
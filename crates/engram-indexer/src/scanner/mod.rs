@@ -2,19 +2,43 @@
 //!
 //! Provides fast, parallel file scanning with gitignore support,
 //! language detection, and AST parsing.
+//!
+//! Files are processed in a bounded pipeline: up to
+//! [`ScanOptions::parallelism`] files are read concurrently, and hashing and
+//! tree-sitter parsing run on the blocking pool so they use every core.
+//! Results keep the walk order, so scans are deterministic.
+//!
+//! [`Scanner::rescan`] re-indexes a previously scanned project, skipping
+//! files that have not changed since its stored tree was built.
 
+mod artifacts;
+mod assets;
 mod framework;
+mod grammar;
 mod language;
+mod outline;
 mod parser;
+mod progress;
+mod quick;
 mod walker;
 
+pub use artifacts::{detect_artifact_dir, ExcludedDir};
+pub use assets::{detect_asset, looks_binary, AssetKind};
 pub use framework::{detect_frameworks, Framework};
-pub use language::{detect_language, Language};
+pub use grammar::{GrammarLoader, GrammarSource, GRAMMARS_DIR};
+pub use language::{detect_language, detect_language_from_content, Language};
 pub use parser::{ParsedFile, Parser, Symbol, SymbolKind};
-pub use walker::{FileEntry, Walker};
+pub use progress::{ScanPhase, ScanProgress};
+pub use quick::quick_skeleton;
+pub use walker::{FileEntry, Walker, IGNORE_FILE};
 
-use crate::IndexerError;
+use crate::tree::{FileUpdate, NodeKind, Tree};
+use crate::{CancelToken, IndexerError};
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Instant;
 use tracing::{debug, info, warn};
 
@@ -25,12 +49,30 @@ pub struct ScanOptions {
     pub max_files: usize,
     /// Maximum file size to parse in bytes (larger files are skipped)
     pub max_file_size: u64,
-    /// Whether to follow symlinks
+    /// Bytes indexed from the start of files over `max_file_size`, which
+    /// are then kept with their top-level symbols only (0 = skip them)
+    pub partial_index_bytes: u64,
+    /// List binary and asset files by size and type instead of skipping
+    /// them; they are never read beyond their start, whatever their size
+    pub catalog_assets: bool,
+    /// Whether to follow symlinks (each directory is entered once, so
+    /// link cycles end)
     pub follow_symlinks: bool,
+    /// Directory levels scanned below the root (0 = unlimited)
+    pub max_depth: usize,
+    /// Total size of the files kept, in path order, after which the rest
+    /// are skipped (0 = unlimited)
+    pub max_total_bytes: u64,
     /// Whether to parse files for symbols
     pub parse_symbols: bool,
-    /// Number of parallel threads for walking
+    /// Number of files read and parsed concurrently
     pub parallelism: usize,
+    /// Skip well-known artifact directories even if not gitignored
+    pub exclude_artifact_dirs: bool,
+    /// Directories never auto-excluded (relative paths or bare names)
+    pub artifact_allowlist: Vec<PathBuf>,
+    /// Additional paths to skip, in `.gitignore` syntax
+    pub ignore_globs: Vec<String>,
 }
 
 impl Default for ScanOptions {
@@ -38,9 +80,16 @@ impl Default for ScanOptions {
         Self {
             max_files: 0,                    // unlimited
             max_file_size: 10 * 1024 * 1024, // 10MB
+            partial_index_bytes: 0,
+            catalog_assets: true,
             follow_symlinks: false,
+            max_depth: 64,
+            max_total_bytes: 16 * 1024 * 1024 * 1024, // 16GB
             parse_symbols: true,
             parallelism: num_cpus(),
+            exclude_artifact_dirs: true,
+            artifact_allowlist: Vec::new(),
+            ignore_globs: Vec::new(),
         }
     }
 }
@@ -60,6 +109,8 @@ pub struct ScanResult {
     pub duration_ms: u64,
     /// Number of files skipped (errors, too large, etc.)
     pub skipped_count: usize,
+    /// Artifact directories that were automatically excluded
+    pub excluded_dirs: Vec<ExcludedDir>,
 }
 
 /// A scanned file with its metadata and parsed content.
@@ -77,24 +128,50 @@ pub struct ScannedFile {
     pub line_count: usize,
     /// Extracted symbols (if parsing enabled)
     pub symbols: Vec<Symbol>,
+    /// Import specifiers, unresolved (if parsing enabled)
+    pub imports: Vec<String>,
+    /// Only the start of the file was indexed
+    pub truncated: bool,
+    /// Type of a binary or asset file, which is not parsed
+    pub asset: Option<AssetKind>,
 }
 
 /// The main scanner that orchestrates file discovery and parsing.
 pub struct Scanner {
     options: ScanOptions,
+    progress: Option<Arc<ScanProgress>>,
+    cancel: CancelToken,
 }
 
 impl Scanner {
     /// Create a new scanner with default options.
     pub fn new() -> Self {
-        Self {
-            options: ScanOptions::default(),
-        }
+        Self::with_options(ScanOptions::default())
     }
 
     /// Create a scanner with custom options.
     pub fn with_options(options: ScanOptions) -> Self {
-        Self { options }
+        Self {
+            options,
+            progress: None,
+            cancel: CancelToken::new(),
+        }
+    }
+
+    /// Report progress to `progress` and stop when it is cancelled.
+    pub fn with_progress(mut self, progress: Arc<ScanProgress>) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Also stop when `cancel` is cancelled, e.g. by the requesting client.
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled() || self.progress.as_ref().is_some_and(|p| p.is_cancelled())
     }
 
     /// Scan a directory and return results.
@@ -103,13 +180,15 @@ impl Scanner {
 
         let root = root
             .canonicalize()
-            .map_err(|e| IndexerError::NotFound(root.to_path_buf()))?;
+            .map_err(|_| IndexerError::NotFound(root.to_path_buf()))?;
 
         info!(path = ?root, "Starting scan");
 
         // Step 1: Walk the file system
-        let walker = Walker::new(&root, self.options.follow_symlinks);
-        let entries = walker.walk()?;
+        if let Some(progress) = &self.progress {
+            progress.start_phase(ScanPhase::Scanning, 0);
+        }
+        let (entries, excluded_dirs) = self.walker(&root)?.walk_with_report()?;
 
         debug!(count = entries.len(), "Files discovered");
 
@@ -125,66 +204,22 @@ impl Scanner {
         let mut skipped = 0;
         let mut language_set = std::collections::HashSet::new();
 
-        let parser = Parser::new();
-
-        for entry in entries {
-            // Skip files that are too large
-            if entry.size > self.options.max_file_size {
-                debug!(path = ?entry.path, size = entry.size, "Skipping large file");
-                skipped += 1;
-                continue;
-            }
-
-            let rel_path = entry
-                .path
-                .strip_prefix(&root)
-                .unwrap_or(&entry.path)
-                .to_path_buf();
-
-            let language = detect_language(&entry.path);
-
-            if let Some(lang) = &language {
-                language_set.insert(lang.clone());
-            }
-
-            // Read file content for hashing and parsing
-            let content = match tokio::fs::read_to_string(&entry.path).await {
-                Ok(c) => c,
-                Err(e) => {
-                    debug!(path = ?entry.path, error = %e, "Failed to read file");
-                    skipped += 1;
-                    continue;
-                }
-            };
-
-            let hash = compute_hash(&content);
-            let line_count = content.lines().count();
-
-            // Parse symbols if enabled and language is supported
-            let symbols = if self.options.parse_symbols {
-                if let Some(lang) = &language {
-                    match parser.parse(&content, lang) {
-                        Ok(parsed) => parsed.symbols,
-                        Err(e) => {
-                            warn!(path = ?entry.path, error = %e, "Parse failed");
-                            vec![]
-                        }
+        let options = self.file_options(None);
+        let results = self
+            .process_all(&root, entries, |path, rel_path, entry| {
+                process_file(options, path, rel_path, entry.size, None)
+            })
+            .await?;
+        for result in results {
+            match result {
+                Some(Processed::Scanned(file)) => {
+                    if let Some(lang) = file.language {
+                        language_set.insert(lang);
                     }
-                } else {
-                    vec![]
+                    files.push(file);
                 }
-            } else {
-                vec![]
-            };
-
-            files.push(ScannedFile {
-                path: rel_path,
-                language,
-                size: entry.size,
-                hash,
-                line_count,
-                symbols,
-            });
+                _ => skipped += 1,
+            }
         }
 
         // Step 3: Detect frameworks
@@ -197,6 +232,7 @@ impl Scanner {
             skipped = skipped,
             languages = language_set.len(),
             frameworks = frameworks.len(),
+            excluded_dirs = excluded_dirs.len(),
             duration_ms = duration.as_millis(),
             "Scan complete"
         );
@@ -208,8 +244,450 @@ impl Scanner {
             frameworks,
             duration_ms: duration.as_millis() as u64,
             skipped_count: skipped,
+            excluded_dirs,
+        })
+    }
+
+    /// Scan a previously indexed directory, reading only what changed.
+    ///
+    /// Files whose size matches `previous` and that were not modified since
+    /// it was scanned are trusted without being read. Other files are
+    /// hashed, and only those whose hash changed are parsed. The result
+    /// lists new, changed and removed files as updates for
+    /// [`apply_updates`](crate::tree::apply_updates).
+    pub async fn rescan(&self, root: &Path, previous: &Tree) -> Result<RescanResult, IndexerError> {
+        let start = Instant::now();
+        let started_at = Utc::now();
+
+        let root = root
+            .canonicalize()
+            .map_err(|_| IndexerError::NotFound(root.to_path_buf()))?;
+
+        info!(path = ?root, "Starting rescan");
+
+        if let Some(progress) = &self.progress {
+            progress.start_phase(ScanPhase::Scanning, 0);
+        }
+        let (entries, excluded_dirs) = self.walker(&root)?.walk_with_report()?;
+        let entries: Vec<_> = if self.options.max_files > 0 {
+            entries.into_iter().take(self.options.max_files).collect()
+        } else {
+            entries
+        };
+
+        let mut indexed: HashMap<PathBuf, Indexed> = previous
+            .nodes
+            .values()
+            .filter_map(|node| match &node.kind {
+                NodeKind::File {
+                    language,
+                    size,
+                    hash,
+                    ..
+                } => Some((
+                    node.path.clone(),
+                    Indexed {
+                        size: *size,
+                        hash: hash.clone(),
+                        language: *language,
+                    },
+                )),
+                _ => None,
+            })
+            .collect();
+
+        // Modification times have a granularity of up to two seconds
+        let trusted_before = previous
+            .scanned_at
+            .map(|at| at.timestamp() - MTIME_GRANULARITY_SECS);
+        let options = self.file_options(trusted_before);
+
+        let mut rel_paths = Vec::with_capacity(entries.len());
+        let results = self
+            .process_all(&root, entries, |path, rel_path, entry| {
+                let known = indexed.remove(&rel_path);
+                rel_paths.push(rel_path.clone());
+                let mtime = i64::try_from(entry.mtime).unwrap_or(i64::MAX);
+                process_file(
+                    options,
+                    path,
+                    rel_path,
+                    entry.size,
+                    known.map(|k| (k, mtime)),
+                )
+            })
+            .await?;
+
+        let mut updates = Vec::new();
+        let mut language_set = std::collections::HashSet::new();
+        let (mut unchanged_count, mut rehashed_count, mut skipped) = (0, 0, 0);
+        for (rel_path, result) in rel_paths.into_iter().zip(results) {
+            match result {
+                Some(Processed::Unchanged { language, read }) => {
+                    language_set.extend(language);
+                    unchanged_count += 1;
+                    rehashed_count += usize::from(read);
+                }
+                Some(Processed::Scanned(file)) => {
+                    language_set.extend(file.language);
+                    updates.push(FileUpdate::Upsert(file));
+                }
+                None => {
+                    skipped += 1;
+                    if previous.find_by_path(&rel_path).is_some() {
+                        updates.push(FileUpdate::Remove(rel_path));
+                    }
+                }
+            }
+        }
+        // Gone, or no longer walked (e.g. newly ignored)
+        let mut removed: Vec<PathBuf> = indexed.into_keys().collect();
+        removed.sort();
+        updates.extend(removed.into_iter().map(FileUpdate::Remove));
+
+        let frameworks = detect_frameworks(&root).await?;
+        let duration = start.elapsed();
+
+        info!(
+            updates = updates.len(),
+            unchanged = unchanged_count,
+            rehashed = rehashed_count,
+            skipped = skipped,
+            duration_ms = duration.as_millis(),
+            "Rescan complete"
+        );
+
+        Ok(RescanResult {
+            root,
+            updates,
+            unchanged_count,
+            rehashed_count,
+            languages: language_set.into_iter().collect(),
+            frameworks,
+            duration_ms: duration.as_millis() as u64,
+            skipped_count: skipped,
+            excluded_dirs,
+            started_at,
         })
     }
+
+    /// Process walked files with at most `parallelism` in flight.
+    ///
+    /// `task` gets each file's absolute and relative path; results come back
+    /// in walk order, `None` where a file was skipped. Reports progress and
+    /// stops with [`IndexerError::Cancelled`] when cancelled.
+    async fn process_all<T, F, Fut>(
+        &self,
+        root: &Path,
+        entries: Vec<FileEntry>,
+        mut task: F,
+    ) -> Result<Vec<Option<T>>, IndexerError>
+    where
+        F: FnMut(PathBuf, PathBuf, &FileEntry) -> Fut,
+        Fut: Future<Output = Option<T>> + Send + 'static,
+        T: Send + 'static,
+    {
+        if let Some(progress) = &self.progress {
+            progress.start_phase(ScanPhase::Parsing, entries.len());
+        }
+
+        let parallelism = self.options.parallelism.max(1);
+        let mut results = Vec::with_capacity(entries.len());
+        let mut entries = entries.into_iter();
+        let mut in_flight = VecDeque::with_capacity(parallelism);
+        loop {
+            while in_flight.len() < parallelism {
+                let Some(entry) = entries.next() else {
+                    break;
+                };
+                if self.is_cancelled() {
+                    in_flight.iter().for_each(tokio::task::JoinHandle::abort);
+                    info!(path = ?root, processed = results.len(), "Scan cancelled");
+                    return Err(IndexerError::Cancelled);
+                }
+                let rel_path = entry.path.strip_prefix(root).unwrap_or(&entry.path);
+                let rel_path = rel_path.to_path_buf();
+                in_flight.push_back(tokio::spawn(task(entry.path.clone(), rel_path, &entry)));
+            }
+
+            // Oldest first, so results come out in walk order
+            let Some(handle) = in_flight.pop_front() else {
+                break;
+            };
+            if let Some(progress) = &self.progress {
+                progress.advance();
+            }
+            results.push(handle.await.ok().flatten());
+        }
+        Ok(results)
+    }
+
+    /// Walker for `root` configured from the scan options.
+    fn walker(&self, root: &Path) -> Result<Walker, IndexerError> {
+        let mut walker = Walker::new(root, self.options.follow_symlinks)
+            .with_ignore_globs(&self.options.ignore_globs)?
+            .with_max_depth((self.options.max_depth > 0).then_some(self.options.max_depth))
+            .with_max_total_bytes(self.options.max_total_bytes);
+        if self.options.exclude_artifact_dirs {
+            walker = walker.with_artifact_exclusion(self.options.artifact_allowlist.clone());
+        }
+        Ok(walker)
+    }
+
+    /// Scan a single file after a change, without walking the project.
+    ///
+    /// `path` is absolute. Returns `None` when the file is gone, would be
+    /// skipped by a full scan (ignored, artifact dir, too large, a symlink
+    /// when links are not followed), or cannot be read.
+    pub async fn scan_file(
+        &self,
+        root: &Path,
+        path: &Path,
+    ) -> Result<Option<ScannedFile>, IndexerError> {
+        let root = root
+            .canonicalize()
+            .map_err(|_| IndexerError::NotFound(root.to_path_buf()))?;
+
+        if !self.walker(&root)?.includes(path) {
+            return Ok(None);
+        }
+
+        let metadata = if self.options.follow_symlinks {
+            tokio::fs::metadata(path).await
+        } else {
+            tokio::fs::symlink_metadata(path).await
+        };
+        let size = match metadata {
+            Ok(metadata) if metadata.is_file() => metadata.len(),
+            _ => return Ok(None),
+        };
+        let rel_path = path.strip_prefix(&root).unwrap_or(path).to_path_buf();
+        let processed = process_file(
+            self.file_options(None),
+            path.to_path_buf(),
+            rel_path,
+            size,
+            None,
+        )
+        .await;
+        Ok(match processed {
+            Some(Processed::Scanned(file)) => Some(file),
+            _ => None,
+        })
+    }
+
+    /// Scan every file below a directory after a change, e.g. one that was
+    /// moved into place.
+    ///
+    /// `dir` is absolute; files are filtered like [`Scanner::scan_file`].
+    pub async fn scan_dir(
+        &self,
+        root: &Path,
+        dir: &Path,
+    ) -> Result<Vec<ScannedFile>, IndexerError> {
+        let entries = Walker::new(dir, self.options.follow_symlinks).walk()?;
+        let mut files = Vec::with_capacity(entries.len());
+        for entry in entries {
+            if let Some(file) = self.scan_file(root, &entry.path).await? {
+                files.push(file);
+            }
+        }
+        Ok(files)
+    }
+
+    fn file_options(&self, trusted_before: Option<i64>) -> FileOptions {
+        FileOptions {
+            max_file_size: self.options.max_file_size,
+            partial_index_bytes: self.options.partial_index_bytes,
+            catalog_assets: self.options.catalog_assets,
+            parse_symbols: self.options.parse_symbols,
+            trusted_before,
+        }
+    }
+}
+
+/// Changes found by [`Scanner::rescan`].
+#[derive(Debug, Clone)]
+pub struct RescanResult {
+    /// Root path that was scanned
+    pub root: PathBuf,
+    /// New and changed files, then removed ones
+    pub updates: Vec<FileUpdate>,
+    /// Files left as indexed
+    pub unchanged_count: usize,
+    /// Unchanged files that had to be read to tell (modified or resized)
+    pub rehashed_count: usize,
+    /// Detected languages, including those of unchanged files
+    pub languages: Vec<Language>,
+    /// Detected frameworks
+    pub frameworks: Vec<Framework>,
+    /// Rescan duration in milliseconds
+    pub duration_ms: u64,
+    /// Number of files skipped (errors, too large, etc.)
+    pub skipped_count: usize,
+    /// Artifact directories that were automatically excluded
+    pub excluded_dirs: Vec<ExcludedDir>,
+    /// When the walk started; store it as the tree's `scanned_at`
+    pub started_at: DateTime<Utc>,
+}
+
+/// Slack for file systems that store modification times coarsely.
+const MTIME_GRANULARITY_SECS: i64 = 2;
+
+/// Bytes of an asset file hashed to tell whether it changed
+const ASSET_HASH_BYTES: u64 = 64 * 1024;
+
+/// A file as recorded in the previous tree.
+#[derive(Debug, Clone)]
+struct Indexed {
+    size: u64,
+    hash: String,
+    language: Option<Language>,
+}
+
+/// Outcome of processing one file.
+enum Processed {
+    /// Same as indexed; `read` if it had to be hashed to tell
+    Unchanged {
+        language: Option<Language>,
+        read: bool,
+    },
+    Scanned(ScannedFile),
+}
+
+/// The scan options needed per file, cheap to move into tasks.
+#[derive(Debug, Clone, Copy)]
+struct FileOptions {
+    max_file_size: u64,
+    partial_index_bytes: u64,
+    catalog_assets: bool,
+    parse_symbols: bool,
+    /// Files last modified before this Unix time are trusted unread
+    trusted_before: Option<i64>,
+}
+
+/// Read one file, then hash and parse it on the blocking pool.
+///
+/// `known` is the file as previously indexed, with its current
+/// modification time. It is trusted without reading if its size matches
+/// and it was not modified since, and it is not re-parsed if its hash
+/// matches. Files over the size limit are skipped, or indexed in part if
+/// `partial_index_bytes` is set. Binary and asset files are cataloged
+/// without being parsed if `catalog_assets` is set. `None` if the file is
+/// skipped: too large, unreadable, or its parse task panicked.
+async fn process_file(
+    options: FileOptions,
+    path: PathBuf,
+    rel_path: PathBuf,
+    size: u64,
+    known: Option<(Indexed, i64)>,
+) -> Option<Processed> {
+    let mut asset = None;
+    if options.catalog_assets {
+        asset = detect_asset(&path);
+    }
+
+    // Skip files that are too large, unless their start is indexed or they
+    // are binary
+    let truncated = asset.is_none() && size > options.max_file_size;
+    if truncated && options.partial_index_bytes == 0 {
+        if !(options.catalog_assets && is_binary(&path).await) {
+            debug!(path = ?path, size = size, "Skipping large file");
+            return None;
+        }
+        asset = Some(AssetKind::Binary);
+    }
+
+    if let (Some((indexed, mtime)), Some(trusted_before)) = (&known, options.trusted_before) {
+        // A zero mtime means it could not be read
+        if indexed.size == size && *mtime > 0 && *mtime < trusted_before {
+            return Some(Processed::Unchanged {
+                language: indexed.language,
+                read: false,
+            });
+        }
+    }
+    let known = known.map(|(indexed, _)| indexed);
+
+    if let Some(asset) = asset {
+        return catalog_asset(&path, rel_path, size, asset, known).await;
+    }
+
+    let language = detect_language(&path);
+
+    // Read file content for hashing and parsing
+    let content = if truncated {
+        read_start(&path, options.partial_index_bytes).await
+    } else {
+        tokio::fs::read_to_string(&path).await
+    };
+    let content = match content {
+        Ok(c) => c,
+        // Not UTF-8, so not a source file
+        Err(e) if e.kind() == std::io::ErrorKind::InvalidData && options.catalog_assets => {
+            return catalog_asset(&path, rel_path, size, AssetKind::Binary, known).await;
+        }
+        Err(e) => {
+            debug!(path = ?path, error = %e, "Failed to read file");
+            return None;
+        }
+    };
+    if options.catalog_assets && looks_binary(content.as_bytes()) {
+        return catalog_asset(&path, rel_path, size, AssetKind::Binary, known).await;
+    }
+
+    let task = tokio::task::spawn_blocking(move || {
+        // The size stands in for the part of a truncated file not read
+        let hash = if truncated {
+            compute_hash(format!("{}\n{}", size, content).as_bytes())
+        } else {
+            compute_hash(content.as_bytes())
+        };
+        if let Some(indexed) = known.filter(|indexed| indexed.hash == hash) {
+            return Processed::Unchanged {
+                language: indexed.language,
+                read: true,
+            };
+        }
+        let line_count = content.lines().count();
+
+        // Parse symbols if enabled and language is supported
+        let parsed = match &language {
+            Some(lang) if options.parse_symbols => match Parser::new().parse(&content, lang) {
+                Ok(parsed) => parsed,
+                Err(e) => {
+                    warn!(path = ?rel_path, error = %e, "Parse failed");
+                    ParsedFile::default()
+                }
+            },
+            _ => ParsedFile::default(),
+        };
+
+        let mut symbols = parsed.symbols;
+        if truncated {
+            symbols.retain(|symbol| symbol.parent.is_none());
+        }
+
+        Processed::Scanned(ScannedFile {
+            path: rel_path,
+            language,
+            size,
+            hash,
+            line_count,
+            symbols,
+            imports: parsed.imports,
+            truncated,
+            asset: None,
+        })
+    });
+
+    match task.await {
+        Ok(processed) => Some(processed),
+        Err(e) => {
+            warn!(path = ?path, error = %e, "Parse task failed");
+            None
+        }
+    }
 }
 
 impl Default for Scanner {
@@ -218,11 +696,82 @@ impl Default for Scanner {
     }
 }
 
+/// List a binary or asset file by size and type.
+///
+/// Only its start is hashed, along with its size, so large files stay
+/// cheap to rescan.
+async fn catalog_asset(
+    path: &Path,
+    rel_path: PathBuf,
+    size: u64,
+    asset: AssetKind,
+    known: Option<Indexed>,
+) -> Option<Processed> {
+    let start = match read_bytes(path, ASSET_HASH_BYTES).await {
+        Ok(start) => start,
+        Err(e) => {
+            debug!(path = ?path, error = %e, "Failed to read file");
+            return None;
+        }
+    };
+    let mut hashed = format!("{}\n", size).into_bytes();
+    hashed.extend_from_slice(&start);
+    let hash = compute_hash(&hashed);
+    if known.is_some_and(|indexed| indexed.hash == hash) {
+        return Some(Processed::Unchanged {
+            language: None,
+            read: true,
+        });
+    }
+
+    Some(Processed::Scanned(ScannedFile {
+        path: rel_path,
+        language: None,
+        size,
+        hash,
+        line_count: 0,
+        symbols: Vec::new(),
+        imports: Vec::new(),
+        truncated: false,
+        asset: Some(asset),
+    }))
+}
+
+/// Whether a file looks binary, judging by its start.
+async fn is_binary(path: &Path) -> bool {
+    read_bytes(path, assets::SNIFF_BYTES as u64)
+        .await
+        .is_ok_and(|start| looks_binary(&start))
+}
+
+/// Read up to `max_bytes` from the start of a file.
+async fn read_bytes(path: &Path, max_bytes: u64) -> std::io::Result<Vec<u8>> {
+    use tokio::io::AsyncReadExt;
+
+    let mut bytes = Vec::new();
+    tokio::fs::File::open(path)
+        .await?
+        .take(max_bytes)
+        .read_to_end(&mut bytes)
+        .await?;
+    Ok(bytes)
+}
+
+/// Read up to `max_bytes` from the start of a file, cut after its last
+/// complete line.
+async fn read_start(path: &Path, max_bytes: u64) -> std::io::Result<String> {
+    let mut bytes = read_bytes(path, max_bytes).await?;
+    if let Some(end) = bytes.iter().rposition(|b| *b == b'\n') {
+        bytes.truncate(end + 1);
+    }
+    String::from_utf8(bytes).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
 /// Compute SHA256 hash of content.
-fn compute_hash(content: &str) -> String {
+fn compute_hash(content: &[u8]) -> String {
     use sha2::{Digest, Sha256};
     let mut hasher = Sha256::new();
-    hasher.update(content.as_bytes());
+    hasher.update(content);
     format!("{:x}", hasher.finalize())
 }
 
@@ -237,6 +786,7 @@ fn num_cpus() -> usize {
 mod tests {
     use super::*;
     use std::fs;
+    use std::time::Duration;
     use tempfile::tempdir;
 
     #[tokio::test]
@@ -266,6 +816,319 @@ mod tests {
         assert!(result.languages.contains(&Language::Rust));
     }
 
+    #[tokio::test]
+    async fn test_scan_progress_and_cancel() {
+        let temp_dir = tempdir().unwrap();
+        fs::write(temp_dir.path().join("main.rs"), "fn main() {}").unwrap();
+        fs::write(temp_dir.path().join("lib.rs"), "pub fn hello() {}").unwrap();
+
+        let progress = Arc::new(ScanProgress::new());
+        Scanner::new()
+            .with_progress(progress.clone())
+            .scan(temp_dir.path())
+            .await
+            .unwrap();
+        assert_eq!((progress.processed(), progress.total()), (2, 2));
+
+        let cancelled = Arc::new(ScanProgress::new());
+        cancelled.cancel();
+        let result = Scanner::new()
+            .with_progress(cancelled)
+            .scan(temp_dir.path())
+            .await;
+        assert!(matches!(result, Err(IndexerError::Cancelled)));
+
+        let result = Scanner::new()
+            .with_cancel(CancelToken::with_timeout(Duration::ZERO))
+            .scan(temp_dir.path())
+            .await;
+        assert!(matches!(result, Err(IndexerError::Cancelled)));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_parallel_scan_matches_sequential() {
+        let temp_dir = tempdir().unwrap();
+        for i in 0..40 {
+            let dir = temp_dir.path().join(format!("mod{}", i % 4));
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(
+                dir.join(format!("file{}.rs", i)),
+                format!("pub fn f{}() {{}}\npub struct S{};\n", i, i),
+            )
+            .unwrap();
+        }
+
+        let scanner = |parallelism| {
+            Scanner::with_options(ScanOptions {
+                parallelism,
+                ..Default::default()
+            })
+        };
+        let sequential = scanner(1).scan(temp_dir.path()).await.unwrap();
+        let parallel = scanner(8).scan(temp_dir.path()).await.unwrap();
+
+        assert_eq!(parallel.files.len(), 40);
+        let summary = |result: &ScanResult| -> Vec<_> {
+            result
+                .files
+                .iter()
+                .map(|f| (f.path.clone(), f.hash.clone(), f.symbols.len()))
+                .collect()
+        };
+        assert_eq!(summary(&parallel), summary(&sequential));
+        assert!(parallel.files.iter().all(|f| f.symbols.len() == 2));
+    }
+
+    #[tokio::test]
+    async fn test_rescan_reads_only_changed_files() {
+        use crate::tree::{apply_updates, TreeBuilder};
+        use std::time::{Duration, SystemTime};
+
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        for name in ["kept.rs", "edited.rs", "touched.rs", "deleted.rs"] {
+            fs::write(root.join(name), format!("pub fn {}() {{}}\n", &name[..4])).unwrap();
+        }
+        let scanner = Scanner::new();
+        let mut tree = TreeBuilder::new().build(&scanner.scan(root).await.unwrap());
+        tree.scanned_at = Some(Utc::now());
+
+        // Untouched since well before the scan
+        fs::File::options()
+            .write(true)
+            .open(root.join("kept.rs"))
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(3600))
+            .unwrap();
+        fs::write(root.join("edited.rs"), "pub fn edited_more() {}\n").unwrap();
+        fs::write(root.join("touched.rs"), "pub fn touc() {}\n").unwrap();
+        fs::remove_file(root.join("deleted.rs")).unwrap();
+        fs::write(root.join("added.rs"), "pub fn added() {}\n").unwrap();
+
+        let rescan = scanner.rescan(root, &tree).await.unwrap();
+        assert_eq!((rescan.unchanged_count, rescan.rehashed_count), (2, 1));
+        let updates: Vec<_> = rescan
+            .updates
+            .iter()
+            .map(|update| match update {
+                FileUpdate::Upsert(file) => format!("upsert {}", file.path.display()),
+                FileUpdate::Remove(path) => format!("remove {}", path.display()),
+                FileUpdate::Rename { .. } => unreachable!(),
+            })
+            .collect();
+        assert_eq!(
+            updates,
+            vec!["upsert added.rs", "upsert edited.rs", "remove deleted.rs"]
+        );
+        assert_eq!(rescan.languages, vec![Language::Rust]);
+
+        apply_updates(&mut tree, &rescan.updates);
+        let full = TreeBuilder::new().build(&scanner.scan(root).await.unwrap());
+        let files = |tree: &Tree| {
+            let mut files: Vec<_> = tree
+                .nodes
+                .values()
+                .filter_map(|node| match &node.kind {
+                    NodeKind::File { hash, .. } => Some((node.path.clone(), hash.clone())),
+                    _ => None,
+                })
+                .collect();
+            files.sort();
+            files
+        };
+        assert_eq!(files(&tree), files(&full));
+    }
+
+    #[tokio::test]
+    async fn test_rescan_without_scan_time_hashes_everything() {
+        use crate::tree::TreeBuilder;
+
+        let temp_dir = tempdir().unwrap();
+        fs::write(temp_dir.path().join("main.rs"), "fn main() {}").unwrap();
+        fs::write(temp_dir.path().join("big.rs"), "fn big() { let x = 1; }").unwrap();
+        let tree = TreeBuilder::new().build(&Scanner::new().scan(temp_dir.path()).await.unwrap());
+        assert!(tree.scanned_at.is_none());
+
+        // Now over the size limit, so dropped like a full scan would
+        let scanner = Scanner::with_options(ScanOptions {
+            max_file_size: 12,
+            ..Default::default()
+        });
+        let rescan = scanner.rescan(temp_dir.path(), &tree).await.unwrap();
+        assert_eq!((rescan.unchanged_count, rescan.rehashed_count), (1, 1));
+        assert!(matches!(
+            &rescan.updates[..],
+            [FileUpdate::Remove(path)] if path == Path::new("big.rs")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_partial_index_of_large_files() {
+        use crate::tree::TreeBuilder;
+
+        let temp_dir = tempdir().unwrap();
+        let mut generated = String::from("pub struct Generated {\n    field: u32,\n}\n\nimpl Generated {\n    pub fn get(&self) -> u32 {\n        self.field\n    }\n}\n");
+        for i in 0..200 {
+            generated.push_str(&format!("pub fn f{}() {{}}\n", i));
+        }
+        fs::write(temp_dir.path().join("generated.rs"), &generated).unwrap();
+        fs::write(temp_dir.path().join("main.rs"), "fn main() {}").unwrap();
+
+        let options = ScanOptions {
+            max_file_size: 1024,
+            ..Default::default()
+        };
+        let skipped = Scanner::with_options(options.clone())
+            .scan(temp_dir.path())
+            .await
+            .unwrap();
+        assert_eq!(skipped.files.len(), 1);
+
+        let result = Scanner::with_options(ScanOptions {
+            partial_index_bytes: 300,
+            ..options
+        })
+        .scan(temp_dir.path())
+        .await
+        .unwrap();
+        let file = result
+            .files
+            .iter()
+            .find(|file| file.path == Path::new("generated.rs"))
+            .unwrap();
+        assert!(file.truncated);
+        assert_eq!(file.size, generated.len() as u64);
+        // Cut after the last whole line within the first 300 bytes
+        assert!(file.line_count < 30);
+        // Top-level symbols only: the method `get` is dropped
+        let names: Vec<&str> = file.symbols.iter().map(|s| s.name.as_str()).collect();
+        assert!(names.contains(&"Generated") && names.contains(&"f0"));
+        assert!(!names.contains(&"get") && !names.contains(&"f199"));
+
+        let tree = TreeBuilder::new().build(&result);
+        let node = tree.find_by_path(&PathBuf::from("generated.rs")).unwrap();
+        assert!(node.is_truncated());
+        assert!(!tree
+            .find_by_path(&PathBuf::from("main.rs"))
+            .unwrap()
+            .is_truncated());
+        assert!(tree
+            .to_skeleton_string(&[])
+            .contains("generated.rs (truncated)"));
+    }
+
+    #[tokio::test]
+    async fn test_catalog_assets() {
+        use crate::tree::TreeBuilder;
+
+        let temp_dir = tempdir().unwrap();
+        fs::create_dir_all(temp_dir.path().join("assets")).unwrap();
+        fs::write(temp_dir.path().join("main.rs"), "fn main() {}").unwrap();
+        fs::write(temp_dir.path().join("assets/logo.png"), b"\x89PNG\r\n").unwrap();
+        fs::write(temp_dir.path().join("model.onnx"), vec![7u8; 4096]).unwrap();
+        fs::write(temp_dir.path().join("tool"), b"\x7fELF\x02\0\0\0").unwrap();
+        fs::write(temp_dir.path().join("blob"), vec![0u8; 4096]).unwrap();
+        fs::write(temp_dir.path().join("big.txt"), "x".repeat(4096)).unwrap();
+
+        let options = ScanOptions {
+            max_file_size: 1024,
+            ..Default::default()
+        };
+        let scanner = Scanner::with_options(options.clone());
+        let result = scanner.scan(temp_dir.path()).await.unwrap();
+        let asset = |path: &str| {
+            result
+                .files
+                .iter()
+                .find(|file| file.path == Path::new(path))
+                .map(|file| (file.asset, file.size))
+        };
+        assert_eq!(asset("main.rs"), Some((None, 12)));
+        assert_eq!(asset("assets/logo.png"), Some((Some(AssetKind::Image), 6)));
+        // Over the size limit, but never read beyond its start
+        assert_eq!(asset("model.onnx"), Some((Some(AssetKind::Model), 4096)));
+        // Not UTF-8, or over the limit and binary
+        assert_eq!(asset("tool"), Some((Some(AssetKind::Binary), 8)));
+        assert_eq!(asset("blob"), Some((Some(AssetKind::Binary), 4096)));
+        // Large text is still skipped
+        assert_eq!(asset("big.txt"), None);
+        assert!(result.files.iter().all(
+            |file| file.asset.is_none() || (file.symbols.is_empty() && file.language.is_none())
+        ));
+
+        let tree = TreeBuilder::new().build(&result);
+        let skeleton = tree.to_skeleton_string(&[]);
+        assert!(skeleton.contains("model.onnx [model, 4.0 KB]"));
+        assert!(skeleton.contains("logo.png [image, 6 B]"));
+
+        // Unchanged assets are not rescanned
+        let rescan = scanner.rescan(temp_dir.path(), &tree).await.unwrap();
+        assert!(rescan.updates.is_empty());
+
+        let skipped = Scanner::with_options(ScanOptions {
+            catalog_assets: false,
+            ..options
+        })
+        .scan(temp_dir.path())
+        .await
+        .unwrap();
+        assert!(skipped.files.iter().all(|file| file.asset.is_none()));
+        assert!(!skipped
+            .files
+            .iter()
+            .any(|file| file.path == Path::new("model.onnx")));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_scan_file_skips_symlinks() {
+        let temp_dir = tempdir().unwrap();
+        fs::write(temp_dir.path().join("real.rs"), "fn real() {}").unwrap();
+        std::os::unix::fs::symlink(
+            temp_dir.path().join("real.rs"),
+            temp_dir.path().join("link.rs"),
+        )
+        .unwrap();
+        let root = temp_dir.path().canonicalize().unwrap();
+
+        // Same as a full scan, which does not follow links by default
+        let scanner = Scanner::new();
+        assert_eq!(scanner.scan(&root).await.unwrap().files.len(), 1);
+        assert!(scanner
+            .scan_file(&root, &root.join("link.rs"))
+            .await
+            .unwrap()
+            .is_none());
+
+        let following = Scanner::with_options(ScanOptions {
+            follow_symlinks: true,
+            ..Default::default()
+        });
+        let file = following
+            .scan_file(&root, &root.join("link.rs"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(file.path, PathBuf::from("link.rs"));
+    }
+
+    #[tokio::test]
+    async fn test_scan_dir() {
+        let temp_dir = tempdir().unwrap();
+        fs::create_dir_all(temp_dir.path().join("src/net/.cache")).unwrap();
+        fs::write(temp_dir.path().join("src/net/http.rs"), "fn get() {}").unwrap();
+        fs::write(temp_dir.path().join("src/net/.cache/x.rs"), "").unwrap();
+        fs::write(temp_dir.path().join("src/main.rs"), "fn main() {}").unwrap();
+        let root = temp_dir.path().canonicalize().unwrap();
+
+        let files = Scanner::new()
+            .scan_dir(&root, &root.join("src/net"))
+            .await
+            .unwrap();
+        let paths: Vec<_> = files.iter().map(|f| f.path.clone()).collect();
+        assert_eq!(paths, vec![PathBuf::from("src/net/http.rs")]);
+    }
+
     #[tokio::test]
     async fn test_scan_respects_gitignore() {
         let temp_dir = tempdir().unwrap();
@@ -308,9 +1171,9 @@ mod tests {
 
     #[test]
     fn test_compute_hash() {
-        let hash1 = compute_hash("hello world");
-        let hash2 = compute_hash("hello world");
-        let hash3 = compute_hash("different");
+        let hash1 = compute_hash(b"hello world");
+        let hash2 = compute_hash(b"hello world");
+        let hash3 = compute_hash(b"different");
 
         assert_eq!(hash1, hash2);
         assert_ne!(hash1, hash3);
@@ -0,0 +1,411 @@
+//! Outlines of documentation and configuration files.
+//!
+//! These formats have no tree-sitter grammar here, and their structure is
+//! shallow enough to read line by line:
+//! - Markdown: ATX headings (`## Setup`), nested under the enclosing
+//!   heading; fenced code blocks are skipped
+//! - YAML: top-level mapping keys
+//! - TOML: top-level keys and `[table]` / `[[array]]` headers
+//! - JSON: keys of the root object
+//! - Makefile: rule targets, except special targets such as `.PHONY`
+//!
+//! Each symbol spans up to the next symbol at the same level, so focus and
+//! horizon context can point at a section instead of the whole file.
+
+use super::{Language, Symbol, SymbolKind};
+
+/// Extract the outline of a structured non-code file.
+///
+/// Returns `None` for languages without an outline.
+pub(crate) fn extract_outline(content: &str, language: &Language) -> Option<Vec<Symbol>> {
+    let symbols = match language {
+        Language::Markdown => markdown_headings(content),
+        Language::Yaml => yaml_keys(content),
+        Language::Toml => toml_keys(content),
+        Language::Json => json_keys(content),
+        Language::Makefile => make_targets(content),
+        _ => return None,
+    };
+    Some(symbols)
+}
+
+fn symbol(name: &str, kind: SymbolKind, start_line: usize, end_line: usize) -> Symbol {
+    Symbol {
+        name: name.to_string(),
+        kind,
+        start_line,
+        end_line,
+        parent: None,
+        doc: None,
+    }
+}
+
+/// Last line (1-based) before `next` that is not blank, but not before
+/// `start`.
+fn end_before(lines: &[&str], start: usize, next: usize) -> usize {
+    (start..next)
+        .rev()
+        .find(|&line| !lines[line - 1].trim().is_empty())
+        .unwrap_or(start)
+}
+
+/// Top-level entries of a file, each ending where the next begins.
+///
+/// `entry` names the entry starting on a line, if any; `boundary` marks
+/// lines that end the previous entry without starting one.
+fn top_level(
+    content: &str,
+    kind: SymbolKind,
+    mut entry: impl FnMut(&str) -> Option<String>,
+    boundary: impl Fn(&str) -> bool,
+) -> Vec<Symbol> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut symbols: Vec<Symbol> = Vec::new();
+    let mut open = false;
+    for (i, line) in lines.iter().enumerate() {
+        let name = entry(line);
+        if name.is_some() || boundary(line) {
+            if let Some(last) = symbols.last_mut().filter(|_| open) {
+                last.end_line = end_before(&lines, last.start_line, i + 1);
+            }
+            open = false;
+        }
+        if let Some(name) = name {
+            symbols.push(symbol(&name, kind, i + 1, i + 1));
+            open = true;
+        }
+    }
+    if let Some(last) = symbols.last_mut().filter(|_| open) {
+        last.end_line = end_before(&lines, last.start_line, lines.len() + 1);
+    }
+    symbols
+}
+
+fn markdown_headings(content: &str) -> Vec<Symbol> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut symbols = Vec::new();
+    // Open headings as (level, index into `symbols`)
+    let mut open: Vec<(usize, usize)> = Vec::new();
+    let mut fence: Option<&str> = None;
+
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim_start();
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+            continue;
+        }
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            fence = Some(&trimmed[..3]);
+            continue;
+        }
+        if line.len() - trimmed.len() > 3 {
+            continue;
+        }
+
+        let level = trimmed.chars().take_while(|&c| c == '#').count();
+        let rest = &trimmed[level..];
+        if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with([' ', '\t'])) {
+            continue;
+        }
+        let title = rest.trim().trim_end_matches('#').trim_end();
+        if title.is_empty() {
+            continue;
+        }
+
+        while let Some(&(open_level, index)) = open.last() {
+            if open_level < level {
+                break;
+            }
+            let heading: &mut Symbol = &mut symbols[index];
+            heading.end_line = end_before(&lines, heading.start_line, i + 1);
+            open.pop();
+        }
+        let mut heading = symbol(title, SymbolKind::Heading, i + 1, i + 1);
+        heading.parent = open.last().map(|&(_, index)| symbols[index].name.clone());
+        open.push((level, symbols.len()));
+        symbols.push(heading);
+    }
+    for (_, index) in open {
+        let heading: &mut Symbol = &mut symbols[index];
+        heading.end_line = end_before(&lines, heading.start_line, lines.len() + 1);
+    }
+    symbols
+}
+
+/// Strip matching quotes from a key.
+fn unquote(key: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(inner) = key
+            .strip_prefix(quote)
+            .and_then(|key| key.strip_suffix(quote))
+        {
+            return inner;
+        }
+    }
+    key
+}
+
+fn yaml_keys(content: &str) -> Vec<Symbol> {
+    top_level(
+        content,
+        SymbolKind::Key,
+        |line| {
+            if line.starts_with([' ', '\t', '#', '-', '[', '{']) {
+                return None;
+            }
+            let (key, _) = line.split_once(':')?;
+            let key = unquote(key.trim());
+            (!key.is_empty()).then(|| key.to_string())
+        },
+        |line| line.starts_with("---") || line.starts_with("..."),
+    )
+}
+
+fn toml_keys(content: &str) -> Vec<Symbol> {
+    let mut in_table = false;
+    top_level(
+        content,
+        SymbolKind::Key,
+        |line| {
+            let indented = line.starts_with([' ', '\t']);
+            let line = line.trim();
+            if let Some(header) = line.strip_prefix('[') {
+                let header = header.trim_start_matches('[');
+                let (name, rest) = header.split_once(']')?;
+                // Skip array values such as `["a", "b"],`
+                let rest = rest.trim_start_matches(']').trim_start();
+                if !(rest.is_empty() || rest.starts_with('#')) {
+                    return None;
+                }
+                in_table = true;
+                let name = name
+                    .split('.')
+                    .map(|part| unquote(part.trim()))
+                    .collect::<Vec<_>>()
+                    .join(".");
+                return (!name.is_empty()).then_some(name);
+            }
+            if in_table || indented || line.starts_with('#') {
+                return None;
+            }
+            let (key, _) = line.split_once('=')?;
+            let key = unquote(key.trim());
+            (!key.is_empty()).then(|| key.to_string())
+        },
+        |_| false,
+    )
+}
+
+/// Keys of the root object, found by tracking nesting and strings.
+fn json_keys(content: &str) -> Vec<Symbol> {
+    let mut symbols = Vec::new();
+    let mut depth = 0usize;
+    let mut line = 1;
+    // Line of the last value token seen inside the open key
+    let mut value_line = 1;
+    let mut open = false;
+    let mut chars = content.char_indices();
+
+    while let Some((start, c)) = chars.next() {
+        match c {
+            '\n' => line += 1,
+            '"' => {
+                let first_line = line;
+                let mut escaped = false;
+                let mut end = content.len();
+                for (i, c) in chars.by_ref() {
+                    match c {
+                        '\n' => line += 1,
+                        '\\' if !escaped => {
+                            escaped = true;
+                            continue;
+                        }
+                        '"' if !escaped => {
+                            end = i;
+                            break;
+                        }
+                        _ => {}
+                    }
+                    escaped = false;
+                }
+                let is_key =
+                    content[end..].chars().skip(1).find(|c| !c.is_whitespace()) == Some(':');
+                if depth == 1 && is_key {
+                    symbols.push(symbol(
+                        &content[start + 1..end],
+                        SymbolKind::Key,
+                        first_line,
+                        first_line,
+                    ));
+                    open = true;
+                }
+                value_line = line;
+            }
+            '{' | '[' => {
+                depth += 1;
+                value_line = line;
+            }
+            '}' | ']' => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    break;
+                }
+                value_line = line;
+            }
+            ',' if depth == 1 => {
+                if let Some(key) = symbols.last_mut().filter(|_| open) {
+                    key.end_line = value_line;
+                }
+                open = false;
+            }
+            c if !c.is_whitespace() && c != ':' => value_line = line,
+            _ => {}
+        }
+    }
+    if let Some(key) = symbols.last_mut().filter(|_| open) {
+        key.end_line = value_line;
+    }
+    symbols
+}
+
+fn make_targets(content: &str) -> Vec<Symbol> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut symbols: Vec<Symbol> = Vec::new();
+    // Targets of the rule whose recipe is being read
+    let mut rule: Option<usize> = None;
+
+    for (i, line) in lines.iter().enumerate() {
+        if line.starts_with('\t') {
+            if let Some(first) = rule {
+                for target in &mut symbols[first..] {
+                    target.end_line = i + 1;
+                }
+            }
+            continue;
+        }
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        rule = None;
+
+        let Some((targets, rest)) = line.split_once(':') else {
+            continue;
+        };
+        // `x := y`, `x ::= y` and `x = a:b` are assignments
+        if rest.starts_with('=')
+            || rest.starts_with(":=")
+            || targets.contains(['=', '$', '%'])
+            || line.starts_with(' ')
+        {
+            continue;
+        }
+        let first = symbols.len();
+        symbols.extend(
+            targets
+                .split_whitespace()
+                .filter(|target| !target.starts_with('.'))
+                .map(|target| symbol(target, SymbolKind::Target, i + 1, i + 1)),
+        );
+        if symbols.len() > first {
+            rule = Some(first);
+        }
+    }
+    symbols
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outline(content: &str, language: Language) -> Vec<(String, usize, usize)> {
+        extract_outline(content, &language)
+            .unwrap()
+            .into_iter()
+            .map(|s| (s.name, s.start_line, s.end_line))
+            .collect()
+    }
+
+    fn spans(expected: &[(&str, usize, usize)]) -> Vec<(String, usize, usize)> {
+        expected
+            .iter()
+            .map(|(name, start, end)| (name.to_string(), *start, *end))
+            .collect()
+    }
+
+    #[test]
+    fn test_markdown_headings() {
+        let content = "# Engram\n\nIntro\n\n## Install\n\n```sh\n# not a heading\n```\n\n### From source ###\nsteps\n\n## Usage\ntext\n#hashtag\n";
+        let symbols = extract_outline(content, &Language::Markdown).unwrap();
+
+        assert_eq!(
+            outline(content, Language::Markdown),
+            spans(&[
+                ("Engram", 1, 16),
+                ("Install", 5, 12),
+                ("From source", 11, 12),
+                ("Usage", 14, 16),
+            ])
+        );
+        assert!(symbols.iter().all(|s| s.kind == SymbolKind::Heading));
+        assert_eq!(symbols[0].parent, None);
+        assert_eq!(symbols[2].parent.as_deref(), Some("Install"));
+        assert_eq!(symbols[3].parent.as_deref(), Some("Engram"));
+    }
+
+    #[test]
+    fn test_yaml_keys() {
+        let content = "# config\nname: engram\nservices:\n  web:\n    port: 80\n\n\"quoted key\": 1\n---\nother: 2\n";
+        assert_eq!(
+            outline(content, Language::Yaml),
+            spans(&[
+                ("name", 2, 2),
+                ("services", 3, 5),
+                ("quoted key", 7, 7),
+                ("other", 9, 9),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_toml_keys() {
+        let content = "title = \"x\"\n\n[package]\nname = \"a\"\n\n[dependencies.serde]\nversion = \"1\"\n\n[[bin]]\nname = \"b\"\n";
+        assert_eq!(
+            outline(content, Language::Toml),
+            spans(&[
+                ("title", 1, 1),
+                ("package", 3, 4),
+                ("dependencies.serde", 6, 7),
+                ("bin", 9, 10),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_json_keys() {
+        let content = "{\n  \"name\": \"app\",\n  \"scripts\": {\n    \"build\": \"tsc\"\n  },\n  \"a\\\"b\": [1,\n    2]\n}\n";
+        assert_eq!(
+            outline(content, Language::Json),
+            spans(&[("name", 2, 2), ("scripts", 3, 5), ("a\\\"b", 6, 7)])
+        );
+        assert!(outline("[{\"a\": 1}]", Language::Json).is_empty());
+    }
+
+    #[test]
+    fn test_make_targets() {
+        let content = "CC := gcc\nFLAGS = -O2\n.PHONY: all test\n\nall: build test\n\nbuild test: deps\n\t$(CC) main.c\n\t./run\n\n%.o: %.c\n\t$(CC) -c $<\n";
+        let symbols = extract_outline(content, &Language::Makefile).unwrap();
+
+        assert_eq!(
+            outline(content, Language::Makefile),
+            spans(&[("all", 5, 5), ("build", 7, 9), ("test", 7, 9)])
+        );
+        assert!(symbols.iter().all(|s| s.kind == SymbolKind::Target));
+    }
+
+    #[test]
+    fn test_no_outline_for_code() {
+        assert!(extract_outline("fn main() {}", &Language::Rust).is_none());
+    }
+}
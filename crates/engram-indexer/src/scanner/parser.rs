@@ -1,15 +1,18 @@
 //! AST parsing with tree-sitter.
 
-use super::Language;
+use super::outline::extract_outline;
+use super::{GrammarLoader, Language};
 use crate::IndexerError;
 use serde::{Deserialize, Serialize};
 use tracing::debug;
 
 /// A parsed file with extracted symbols.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct ParsedFile {
     /// Extracted symbols
     pub symbols: Vec<Symbol>,
+    /// Import specifiers as written in the source, unresolved
+    pub imports: Vec<String>,
 }
 
 /// A code symbol (function, class, etc.)
@@ -30,7 +33,7 @@ pub struct Symbol {
 }
 
 /// Kind of symbol.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum SymbolKind {
     Function,
@@ -44,6 +47,14 @@ pub enum SymbolKind {
     Constant,
     Variable,
     Import,
+    /// Markdown heading
+    Heading,
+    /// Top-level key of a YAML, TOML or JSON file
+    Key,
+    /// Makefile target
+    Target,
+    /// Inline test module (`#[cfg(test)] mod tests`)
+    Test,
 }
 
 /// AST parser using tree-sitter.
@@ -58,25 +69,29 @@ impl Parser {
     }
 
     /// Parse source code and extract symbols.
+    ///
+    /// Documentation and configuration files get an outline of headings,
+    /// keys or targets instead.
     pub fn parse(&self, content: &str, language: &Language) -> Result<ParsedFile, IndexerError> {
+        if let Some(symbols) = extract_outline(content, language) {
+            return Ok(ParsedFile {
+                symbols,
+                imports: Vec::new(),
+            });
+        }
         if !language.has_parser() {
-            return Ok(ParsedFile { symbols: vec![] });
+            return Ok(ParsedFile::default());
         }
 
-        let mut parser = tree_sitter::Parser::new();
-
-        // Get the language grammar
-        let ts_language = match language {
-            Language::Rust => tree_sitter_rust::LANGUAGE,
-            Language::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT,
-            Language::JavaScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT, // TS parser handles JS
-            Language::Python => tree_sitter_python::LANGUAGE,
-            Language::Go => tree_sitter_go::LANGUAGE,
-            _ => return Ok(ParsedFile { symbols: vec![] }),
+        let Some(ts_language) = GrammarLoader::global().get(*language) else {
+            debug!(language = language.name(), "No grammar available");
+            return Ok(ParsedFile::default());
         };
 
+        let mut parser = tree_sitter::Parser::new();
+
         parser
-            .set_language(&ts_language.into())
+            .set_language(&ts_language)
             .map_err(|e| IndexerError::Parse {
                 path: std::path::PathBuf::new(),
                 message: format!("Failed to set language: {}", e),
@@ -90,10 +105,16 @@ impl Parser {
             })?;
 
         let symbols = extract_symbols(&tree, content, language);
+        let mut imports = Vec::new();
+        extract_imports(tree.root_node(), content, language, &mut imports);
 
-        debug!(symbol_count = symbols.len(), "Extracted symbols");
+        debug!(
+            symbol_count = symbols.len(),
+            import_count = imports.len(),
+            "Extracted symbols"
+        );
 
-        Ok(ParsedFile { symbols })
+        Ok(ParsedFile { symbols, imports })
     }
 }
 
@@ -130,6 +151,7 @@ fn extract_symbols_recursive(
         (Language::Rust, "struct_item") => Some(SymbolKind::Struct),
         (Language::Rust, "enum_item") => Some(SymbolKind::Enum),
         (Language::Rust, "trait_item") => Some(SymbolKind::Trait),
+        (Language::Rust, "mod_item") if is_cfg_test(node, content) => Some(SymbolKind::Test),
         (Language::Rust, "mod_item") => Some(SymbolKind::Module),
         (Language::Rust, "const_item") => Some(SymbolKind::Constant),
         (Language::Rust, "static_item") => Some(SymbolKind::Constant),
@@ -162,6 +184,9 @@ fn extract_symbols_recursive(
         (Language::Go, "type_declaration") => None, // Handle nested type_spec
         (Language::Go, "type_spec") => Some(SymbolKind::Struct),
 
+        // C/C++
+        (Language::C | Language::Cpp, _) => c_symbol_kind(node),
+
         _ => None,
     };
 
@@ -194,8 +219,38 @@ fn extract_symbols_recursive(
     }
 }
 
+/// Check whether a Rust item is preceded by a `#[cfg(test)]` attribute.
+fn is_cfg_test(node: tree_sitter::Node, content: &str) -> bool {
+    let mut prev = node.prev_sibling();
+    while let Some(sibling) = prev {
+        match sibling.kind() {
+            "attribute_item" => {
+                let text: String = content
+                    .get(sibling.byte_range())
+                    .unwrap_or_default()
+                    .chars()
+                    .filter(|c| !c.is_whitespace())
+                    .collect();
+                if text == "#[cfg(test)]" {
+                    return true;
+                }
+            }
+            "line_comment" | "block_comment" => {}
+            _ => return false,
+        }
+        prev = sibling.prev_sibling();
+    }
+    false
+}
+
 /// Extract the name of a symbol node.
-fn extract_name(node: tree_sitter::Node, content: &str, _language: &Language) -> Option<String> {
+fn extract_name(node: tree_sitter::Node, content: &str, language: &Language) -> Option<String> {
+    if matches!(language, Language::C | Language::Cpp) {
+        return c_symbol_name(node)
+            .and_then(|name| content.get(name.byte_range()))
+            .map(str::to_string);
+    }
+
     // Look for 'name' or 'identifier' child
     let mut cursor = node.walk();
     for child in node.children(&mut cursor) {
@@ -211,6 +266,193 @@ fn extract_name(node: tree_sitter::Node, content: &str, _language: &Language) ->
     None
 }
 
+/// Map a C/C++ node to a symbol kind.
+///
+/// Structs, unions, enums and classes only count when they have a body, so
+/// `struct point *p;` is not a symbol. Function prototypes are kept since
+/// headers consist of little else; out-of-line definitions such as
+/// `void Widget::draw()` and declarations inside a class body are methods.
+fn c_symbol_kind(node: tree_sitter::Node) -> Option<SymbolKind> {
+    let has_body = || node.child_by_field_name("body").is_some();
+    match node.kind() {
+        "function_definition" | "declaration" | "field_declaration" => {
+            let declarator = c_function_declarator(node)?;
+            let qualified = declarator
+                .child_by_field_name("declarator")
+                .is_some_and(|name| {
+                    matches!(name.kind(), "field_identifier" | "qualified_identifier")
+                });
+            let in_class = node
+                .parent()
+                .is_some_and(|parent| parent.kind() == "field_declaration_list");
+            if qualified || in_class {
+                Some(SymbolKind::Method)
+            } else {
+                Some(SymbolKind::Function)
+            }
+        }
+        "struct_specifier" | "union_specifier" if has_body() => Some(SymbolKind::Struct),
+        "enum_specifier" if has_body() => Some(SymbolKind::Enum),
+        "class_specifier" if has_body() => Some(SymbolKind::Class),
+        "namespace_definition" => Some(SymbolKind::Module),
+        // `typedef struct { ... } name;` names an otherwise anonymous type
+        "type_definition" => {
+            let ty = node.child_by_field_name("type")?;
+            if ty.child_by_field_name("name").is_some() || ty.child_by_field_name("body").is_none()
+            {
+                return None;
+            }
+            match ty.kind() {
+                "struct_specifier" | "union_specifier" => Some(SymbolKind::Struct),
+                "enum_specifier" => Some(SymbolKind::Enum),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// The function declarator of a definition or prototype, looking through
+/// pointer and reference return types.
+fn c_function_declarator(node: tree_sitter::Node) -> Option<tree_sitter::Node> {
+    let mut declarator = node.child_by_field_name("declarator")?;
+    loop {
+        match declarator.kind() {
+            "function_declarator" => return Some(declarator),
+            "pointer_declarator" | "reference_declarator" | "parenthesized_declarator" => {
+                declarator = declarator
+                    .child_by_field_name("declarator")
+                    .or_else(|| declarator.named_child(0))?;
+            }
+            _ => return None,
+        }
+    }
+}
+
+/// The name node of a C/C++ symbol; qualified names keep only the last part.
+fn c_symbol_name(node: tree_sitter::Node) -> Option<tree_sitter::Node> {
+    let mut name = match node.kind() {
+        "function_definition" | "declaration" | "field_declaration" => {
+            c_function_declarator(node)?.child_by_field_name("declarator")?
+        }
+        "type_definition" => node.child_by_field_name("declarator")?,
+        _ => node.child_by_field_name("name")?,
+    };
+    while name.kind() == "qualified_identifier" {
+        name = name.child_by_field_name("name")?;
+    }
+    Some(name)
+}
+
+/// Collect import specifiers, unresolved.
+///
+/// Specifiers keep the language's own syntax so they can be resolved against
+/// the tree later:
+/// - Rust: `use` arguments (`crate::a::{b, c::D}`) and `mod x;` as `self::x`
+/// - TypeScript/JavaScript: `import`/`export ... from` sources and
+///   `require()`/`import()` arguments
+/// - Python: dotted module paths; `from m import n` yields `m.n`, and relative
+///   imports keep their leading dots
+/// - Go: import paths
+/// - C/C++: `#include` paths; `<system>` includes keep their angle brackets
+fn extract_imports(
+    node: tree_sitter::Node,
+    content: &str,
+    language: &Language,
+    imports: &mut Vec<String>,
+) {
+    let text = |node: tree_sitter::Node| content.get(node.byte_range()).map(str::to_string);
+    let unquote = |node: tree_sitter::Node| {
+        text(node).map(|s| {
+            s.trim_matches(|c| matches!(c, '"' | '\'' | '`'))
+                .to_string()
+        })
+    };
+
+    match (language, node.kind()) {
+        (Language::Rust, "use_declaration") => {
+            imports.extend(node.child_by_field_name("argument").and_then(text));
+            return;
+        }
+        (Language::Rust, "mod_item") if node.child_by_field_name("body").is_none() => {
+            if let Some(name) = node.child_by_field_name("name").and_then(text) {
+                imports.push(format!("self::{}", name));
+            }
+            return;
+        }
+        (Language::TypeScript | Language::JavaScript, "import_statement" | "export_statement") => {
+            imports.extend(node.child_by_field_name("source").and_then(unquote));
+        }
+        (Language::TypeScript | Language::JavaScript, "call_expression") => {
+            let is_import = node
+                .child_by_field_name("function")
+                .is_some_and(|f| f.kind() == "import" || text(f).as_deref() == Some("require"));
+            let argument = node
+                .child_by_field_name("arguments")
+                .and_then(|args| args.named_child(0))
+                .filter(|arg| arg.kind() == "string");
+            if let (true, Some(argument)) = (is_import, argument) {
+                imports.extend(unquote(argument));
+            }
+        }
+        (Language::Python, "import_statement") => {
+            let mut cursor = node.walk();
+            for name in node.children_by_field_name("name", &mut cursor) {
+                imports.extend(python_module_name(name).and_then(text));
+            }
+            return;
+        }
+        (Language::Python, "import_from_statement") => {
+            let Some(module) = node.child_by_field_name("module_name").and_then(text) else {
+                return;
+            };
+            let mut cursor = node.walk();
+            let names: Vec<_> = node
+                .children_by_field_name("name", &mut cursor)
+                .filter_map(|name| python_module_name(name).and_then(text))
+                .collect();
+            let separator = if module.ends_with('.') { "" } else { "." };
+            if names.is_empty() {
+                imports.push(module);
+            } else {
+                imports.extend(
+                    names
+                        .into_iter()
+                        .map(|name| format!("{}{}{}", module, separator, name)),
+                );
+            }
+            return;
+        }
+        (Language::Go, "import_spec") => {
+            imports.extend(node.child_by_field_name("path").and_then(unquote));
+            return;
+        }
+        (Language::C | Language::Cpp, "preproc_include") => {
+            if let Some(path) = node.child_by_field_name("path") {
+                match path.kind() {
+                    "string_literal" => imports.extend(unquote(path)),
+                    "system_lib_string" => imports.extend(text(path)),
+                    _ => {}
+                }
+            }
+            return;
+        }
+        _ => {}
+    }
+
+    for child in node.children(&mut node.walk()) {
+        extract_imports(child, content, language, imports);
+    }
+}
+
+/// The dotted name of a Python import, looking through `as` aliases.
+fn python_module_name(node: tree_sitter::Node) -> Option<tree_sitter::Node> {
+    match node.kind() {
+        "aliased_import" => node.child_by_field_name("name"),
+        _ => Some(node),
+    }
+}
+
 /// Extract documentation comment above a node.
 fn extract_doc_comment(node: tree_sitter::Node, content: &str) -> Option<String> {
     // Look for preceding comment siblings
@@ -239,6 +481,7 @@ fn extract_doc_comment(node: tree_sitter::Node, content: &str) -> Option<String>
 mod tests {
     use super::*;
 
+    #[cfg(feature = "lang-rust")]
     #[test]
     fn test_parse_rust_function() {
         let parser = Parser::new();
@@ -256,6 +499,7 @@ fn hello_world() {
         assert_eq!(func.kind, SymbolKind::Function);
     }
 
+    #[cfg(feature = "lang-rust")]
     #[test]
     fn test_parse_rust_struct() {
         let parser = Parser::new();
@@ -276,6 +520,32 @@ struct Point {
         assert_eq!(s.name, "Point");
     }
 
+    #[cfg(feature = "lang-rust")]
+    #[test]
+    fn test_parse_rust_test_module() {
+        let parser = Parser::new();
+        let code = r#"
+mod api {}
+
+#[cfg(test)]
+#[allow(unused)]
+mod tests {
+    #[test]
+    fn works() {}
+}
+"#;
+        let result = parser.parse(code, &Language::Rust).unwrap();
+
+        let kinds: Vec<_> = result
+            .symbols
+            .iter()
+            .map(|s| (s.name.as_str(), s.kind))
+            .collect();
+        assert!(kinds.contains(&("api", SymbolKind::Module)));
+        assert!(kinds.contains(&("tests", SymbolKind::Test)));
+    }
+
+    #[cfg(feature = "lang-typescript")]
     #[test]
     fn test_parse_typescript_class() {
         let parser = Parser::new();
@@ -295,6 +565,7 @@ class MyClass {
         assert_eq!(class.unwrap().name, "MyClass");
     }
 
+    #[cfg(feature = "lang-python")]
     #[test]
     fn test_parse_python_function() {
         let parser = Parser::new();
@@ -309,6 +580,7 @@ def greet(name):
         assert_eq!(result.symbols[0].kind, SymbolKind::Function);
     }
 
+    #[cfg(feature = "lang-go")]
     #[test]
     fn test_parse_go_function() {
         let parser = Parser::new();
@@ -324,14 +596,173 @@ func main() {
         assert_eq!(result.symbols[0].kind, SymbolKind::Function);
     }
 
+    #[cfg(feature = "lang-c")]
+    #[test]
+    fn test_parse_c() {
+        let parser = Parser::new();
+        let code = r#"
+#include <stdio.h>
+#include "util/list.h"
+
+struct point { int x; int y; };
+struct point *origin;
+
+typedef struct {
+    int len;
+} buffer;
+
+enum color { RED, GREEN };
+
+int area(int w, int h);
+
+/* Print a greeting */
+static char *greet(const char *name) {
+    return NULL;
+}
+"#;
+        let result = parser.parse(code, &Language::C).unwrap();
+
+        let symbols: Vec<_> = result
+            .symbols
+            .iter()
+            .map(|s| (s.name.as_str(), s.kind))
+            .collect();
+        assert_eq!(
+            symbols,
+            vec![
+                ("point", SymbolKind::Struct),
+                ("buffer", SymbolKind::Struct),
+                ("color", SymbolKind::Enum),
+                ("area", SymbolKind::Function),
+                ("greet", SymbolKind::Function),
+            ]
+        );
+        assert_eq!(result.imports, vec!["<stdio.h>", "util/list.h"]);
+    }
+
+    #[cfg(feature = "lang-cpp")]
+    #[test]
+    fn test_parse_cpp() {
+        let parser = Parser::new();
+        let code = r#"
+#include "widget.hpp"
+
+namespace ui {
+class Widget {
+public:
+    void draw();
+    int width() const { return w; }
+};
+
+void Widget::draw() {}
+}
+"#;
+        let result = parser.parse(code, &Language::Cpp).unwrap();
+
+        let symbols: Vec<_> = result
+            .symbols
+            .iter()
+            .map(|s| (s.name.as_str(), s.kind, s.parent.as_deref()))
+            .collect();
+        assert_eq!(
+            symbols,
+            vec![
+                ("ui", SymbolKind::Module, None),
+                ("Widget", SymbolKind::Class, Some("ui")),
+                ("draw", SymbolKind::Method, Some("Widget")),
+                ("width", SymbolKind::Method, Some("Widget")),
+                ("draw", SymbolKind::Method, Some("ui")),
+            ]
+        );
+        assert_eq!(result.imports, vec!["widget.hpp"]);
+    }
+
+    #[cfg(feature = "lang-rust")]
+    #[test]
+    fn test_extract_rust_imports() {
+        let parser = Parser::new();
+        let code = "use crate::tree::{Node, builder::TreeBuilder};\nuse std::io;\nmod walker;\nmod inline {}\n";
+        let result = parser.parse(code, &Language::Rust).unwrap();
+
+        assert_eq!(
+            result.imports,
+            vec![
+                "crate::tree::{Node, builder::TreeBuilder}",
+                "std::io",
+                "self::walker"
+            ]
+        );
+    }
+
+    #[cfg(feature = "lang-typescript")]
+    #[test]
+    fn test_extract_typescript_imports() {
+        let parser = Parser::new();
+        let code = r#"
+import { a } from "./a";
+import type { B } from '../b';
+export * from "./c";
+const d = require("./d");
+const e = await import("./e");
+"#;
+        let result = parser.parse(code, &Language::TypeScript).unwrap();
+
+        assert_eq!(result.imports, vec!["./a", "../b", "./c", "./d", "./e"]);
+    }
+
+    #[cfg(feature = "lang-python")]
+    #[test]
+    fn test_extract_python_imports() {
+        let parser = Parser::new();
+        let code = "import os.path as p\nfrom . import util\nfrom ..core import models, views\nfrom app.db import *\n";
+        let result = parser.parse(code, &Language::Python).unwrap();
+
+        assert_eq!(
+            result.imports,
+            vec![
+                "os.path",
+                ".util",
+                "..core.models",
+                "..core.views",
+                "app.db"
+            ]
+        );
+    }
+
+    #[cfg(feature = "lang-go")]
+    #[test]
+    fn test_extract_go_imports() {
+        let parser = Parser::new();
+        let code =
+            "package main\n\nimport (\n\t\"fmt\"\n\tutil \"example.com/app/internal/util\"\n)\n";
+        let result = parser.parse(code, &Language::Go).unwrap();
+
+        assert_eq!(result.imports, vec!["fmt", "example.com/app/internal/util"]);
+    }
+
     #[test]
     fn test_parse_unsupported_language() {
         let parser = Parser::new();
-        let result = parser.parse("{}", &Language::Json).unwrap();
+        let result = parser
+            .parse("body { color: red; }", &Language::Css)
+            .unwrap();
 
         assert!(result.symbols.is_empty());
     }
 
+    #[test]
+    fn test_parse_outline() {
+        let parser = Parser::new();
+        let result = parser
+            .parse("{\"name\": \"app\"}", &Language::Json)
+            .unwrap();
+
+        assert_eq!(result.symbols.len(), 1);
+        assert_eq!(result.symbols[0].name, "name");
+        assert_eq!(result.symbols[0].kind, SymbolKind::Key);
+    }
+
+    #[cfg(feature = "lang-rust")]
     #[test]
     fn test_symbol_line_numbers() {
         let parser = Parser::new();
@@ -0,0 +1,159 @@
+//! Progress reporting and cooperative cancellation for scans.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// Stage of indexing a project.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScanPhase {
+    /// Walking the file system
+    #[default]
+    Scanning,
+    /// Detecting languages, hashing and parsing files
+    Parsing,
+    /// Building the tree from scanned files
+    Building,
+    /// Generating summaries for tree nodes
+    Enriching,
+}
+
+impl ScanPhase {
+    /// Display name of the phase.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScanPhase::Scanning => "scanning",
+            ScanPhase::Parsing => "parsing",
+            ScanPhase::Building => "building",
+            ScanPhase::Enriching => "enriching",
+        }
+    }
+
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => ScanPhase::Parsing,
+            2 => ScanPhase::Building,
+            3 => ScanPhase::Enriching,
+            _ => ScanPhase::Scanning,
+        }
+    }
+}
+
+/// Shared progress of a running scan.
+///
+/// The scanner updates the counters as it goes and checks the cancel flag
+/// between files, so a cancelled scan stops at the next file boundary.
+/// Counters are per phase: starting a phase resets them.
+#[derive(Debug)]
+pub struct ScanProgress {
+    total: AtomicUsize,
+    processed: AtomicUsize,
+    cancelled: AtomicBool,
+    phase: AtomicU8,
+    created: Instant,
+    /// When the current phase started, in milliseconds since `created`
+    phase_started_ms: AtomicU64,
+}
+
+impl Default for ScanProgress {
+    fn default() -> Self {
+        Self {
+            total: AtomicUsize::new(0),
+            processed: AtomicUsize::new(0),
+            cancelled: AtomicBool::new(false),
+            phase: AtomicU8::new(ScanPhase::Scanning as u8),
+            created: Instant::now(),
+            phase_started_ms: AtomicU64::new(0),
+        }
+    }
+}
+
+impl ScanProgress {
+    /// Create progress with zeroed counters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request cancellation.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Check whether cancellation was requested.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Files processed so far.
+    pub fn processed(&self) -> usize {
+        self.processed.load(Ordering::Relaxed)
+    }
+
+    /// Files to process (0 until discovery finishes).
+    pub fn total(&self) -> usize {
+        self.total.load(Ordering::Relaxed)
+    }
+
+    /// Set the number of items to process.
+    pub fn set_total(&self, total: usize) {
+        self.total.store(total, Ordering::Relaxed);
+    }
+
+    /// Count one more item as processed.
+    pub fn advance(&self) {
+        self.processed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Current phase.
+    pub fn phase(&self) -> ScanPhase {
+        ScanPhase::from_u8(self.phase.load(Ordering::Relaxed))
+    }
+
+    /// Enter `phase` with `total` items to process, resetting the count.
+    pub fn start_phase(&self, phase: ScanPhase, total: usize) {
+        self.processed.store(0, Ordering::Relaxed);
+        self.total.store(total, Ordering::Relaxed);
+        self.phase_started_ms
+            .store(self.created.elapsed().as_millis() as u64, Ordering::Relaxed);
+        self.phase.store(phase as u8, Ordering::Relaxed);
+    }
+
+    /// Estimated time left in the current phase, from its rate so far;
+    /// `None` until an item has been processed.
+    pub fn eta(&self) -> Option<Duration> {
+        let (processed, total) = (self.processed(), self.total());
+        if processed == 0 || total == 0 {
+            return None;
+        }
+        let started = Duration::from_millis(self.phase_started_ms.load(Ordering::Relaxed));
+        let elapsed = self.created.elapsed().saturating_sub(started);
+        let remaining = total.saturating_sub(processed) as u32;
+        Some(elapsed / processed as u32 * remaining)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phases() {
+        let progress = ScanProgress::new();
+        assert_eq!(progress.phase(), ScanPhase::Scanning);
+        assert_eq!(progress.eta(), None);
+
+        progress.start_phase(ScanPhase::Parsing, 4);
+        progress.advance();
+        progress.advance();
+        assert_eq!(progress.phase(), ScanPhase::Parsing);
+        assert!(progress.eta().is_some());
+
+        progress.start_phase(ScanPhase::Building, 10);
+        assert_eq!(progress.phase(), ScanPhase::Building);
+        assert_eq!((progress.processed(), progress.total()), (0, 10));
+        assert_eq!(progress.eta(), None);
+
+        progress.advance();
+        progress.set_total(1);
+        assert_eq!(progress.eta(), Some(Duration::ZERO));
+    }
+}
@@ -0,0 +1,168 @@
+//! Bounded structure-only listing for cold starts.
+//!
+//! Lists the top levels of a project without parsing or hashing anything,
+//! so a best-effort skeleton can be returned while a full scan runs.
+
+use super::artifacts::detect_artifact_dir;
+use super::walker::IGNORE_FILE;
+use ignore::WalkBuilder;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+#[derive(Default)]
+struct Dir {
+    dirs: BTreeMap<String, Dir>,
+    files: Vec<String>,
+}
+
+/// Render a skeleton of the top `max_depth` directory levels of `root`.
+///
+/// Respects gitignore rules, skips hidden files and artifact directories, and
+/// stops after `max_entries` entries (noting how many were omitted). With
+/// `compress_dirs`, chains of single-child directories render as one line.
+pub fn quick_skeleton(
+    root: &Path,
+    max_depth: usize,
+    max_entries: usize,
+    compress_dirs: bool,
+) -> String {
+    let mut top = Dir::default();
+    let mut listed = 0usize;
+    let mut omitted = 0usize;
+
+    let walker = WalkBuilder::new(root)
+        .max_depth(Some(max_depth))
+        .hidden(true)
+        .git_ignore(true)
+        .git_exclude(true)
+        .parents(true)
+        .add_custom_ignore_filename(IGNORE_FILE)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .filter_entry(|entry| {
+            entry.depth() == 0
+                || !entry.file_type().is_some_and(|ft| ft.is_dir())
+                || detect_artifact_dir(entry.path()).is_none()
+        })
+        .build();
+
+    for entry in walker.flatten() {
+        if entry.depth() == 0 {
+            continue;
+        }
+        if listed >= max_entries {
+            omitted += 1;
+            continue;
+        }
+        let Ok(rel_path) = entry.path().strip_prefix(root) else {
+            continue;
+        };
+        let components: Vec<String> = rel_path
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        let Some((name, parents)) = components.split_last() else {
+            continue;
+        };
+
+        let mut dir = &mut top;
+        for parent in parents {
+            dir = dir.dirs.entry(parent.clone()).or_default();
+        }
+        if entry.file_type().is_some_and(|ft| ft.is_dir()) {
+            dir.dirs.entry(name.clone()).or_default();
+        } else {
+            dir.files.push(name.clone());
+        }
+        listed += 1;
+    }
+
+    let root_name = root.file_name().and_then(|n| n.to_str()).unwrap_or("root");
+    let mut output = format!("{}/\n", root_name);
+    render_dir(&top, "", compress_dirs, &mut output);
+    if omitted > 0 {
+        output.push_str(&format!("… ({} more entries)\n", omitted));
+    }
+    output
+}
+
+fn render_dir(dir: &Dir, prefix: &str, compress_dirs: bool, output: &mut String) {
+    let count = dir.dirs.len() + dir.files.len();
+    let entries = dir
+        .dirs
+        .iter()
+        .map(|(name, sub)| (name.as_str(), Some(sub)))
+        .chain(dir.files.iter().map(|name| (name.as_str(), None)));
+
+    for (idx, (name, sub)) in entries.enumerate() {
+        let is_last = idx + 1 == count;
+        let connector = if is_last { "└── " } else { "├── " };
+        match sub {
+            Some(mut sub) => {
+                let mut name = name.to_string();
+                while compress_dirs && sub.files.is_empty() && sub.dirs.len() == 1 {
+                    let Some((child_name, child)) = sub.dirs.iter().next() else {
+                        break;
+                    };
+                    name = format!("{}/{}", name, child_name);
+                    sub = child;
+                }
+                output.push_str(&format!("{}{}{}/\n", prefix, connector, name));
+                let child_prefix = format!("{}{}", prefix, if is_last { "    " } else { "│   " });
+                render_dir(sub, &child_prefix, compress_dirs, output);
+            }
+            None => output.push_str(&format!("{}{}{}\n", prefix, connector, name)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_quick_skeleton_is_depth_bounded() {
+        let temp_dir = tempdir().unwrap();
+        fs::create_dir_all(temp_dir.path().join("src/deep/deeper")).unwrap();
+        fs::write(temp_dir.path().join("README.md"), "").unwrap();
+        fs::write(temp_dir.path().join("src/lib.rs"), "").unwrap();
+        fs::write(temp_dir.path().join("src/deep/hidden_by_depth.rs"), "").unwrap();
+        fs::create_dir_all(temp_dir.path().join("node_modules/pkg")).unwrap();
+
+        let skeleton = quick_skeleton(temp_dir.path(), 2, 100, false);
+
+        assert!(skeleton.contains("├── src/"));
+        assert!(skeleton.contains("│   ├── deep/"));
+        assert!(skeleton.contains("│   └── lib.rs"));
+        assert!(skeleton.contains("└── README.md"));
+        assert!(!skeleton.contains("hidden_by_depth.rs"));
+        assert!(!skeleton.contains("node_modules"));
+    }
+
+    #[test]
+    fn test_quick_skeleton_entry_limit() {
+        let temp_dir = tempdir().unwrap();
+        for i in 0..5 {
+            fs::write(temp_dir.path().join(format!("file{}.txt", i)), "").unwrap();
+        }
+
+        let skeleton = quick_skeleton(temp_dir.path(), 2, 3, false);
+
+        assert!(skeleton.contains("file2.txt"));
+        assert!(!skeleton.contains("file3.txt"));
+        assert!(skeleton.contains("… (2 more entries)"));
+    }
+
+    #[test]
+    fn test_quick_skeleton_compresses_dir_chains() {
+        let temp_dir = tempdir().unwrap();
+        fs::create_dir_all(temp_dir.path().join("src/com/example")).unwrap();
+        fs::write(temp_dir.path().join("src/com/example/App.java"), "").unwrap();
+
+        let skeleton = quick_skeleton(temp_dir.path(), 4, 100, true);
+
+        assert!(skeleton.contains("└── src/com/example/"));
+        assert!(skeleton.contains("    └── App.java"));
+    }
+}
@@ -1,10 +1,31 @@
 //! File system walker with gitignore support.
-
+//!
+//! Besides `.gitignore`, a `.engramignore` file (same syntax) in any
+//! directory excludes paths from indexing only, without touching git. As
+//! with `.gitignore`, patterns are relative to the file's directory and
+//! deeper files take precedence; at the same level `.engramignore` wins.
+//!
+//! When following symlinks, every directory is entered once: a link back
+//! into the project or to a directory already walked is skipped, so link
+//! cycles end. Depth and total size limits bound what a walk can reach; a
+//! walk with a size limit visits each directory's entries in name order and
+//! stops at the limit, so it never reads past it.
+
+use super::artifacts::{detect_artifact_dir, is_allowlisted, ExcludedDir};
 use crate::IndexerError;
-use ignore::{WalkBuilder, WalkState};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::{DirEntry, WalkBuilder, WalkState};
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
-use std::sync::mpsc;
-use tracing::debug;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use tracing::{debug, info, warn};
+
+/// Ignore file excluding paths from indexing, in `.gitignore` syntax
+pub const IGNORE_FILE: &str = ".engramignore";
+
+/// Ignore files read in each directory, lowest precedence first
+const IGNORE_FILES: &[&str] = &[".gitignore", IGNORE_FILE];
 
 /// A discovered file entry.
 #[derive(Debug, Clone)]
@@ -21,6 +42,14 @@ pub struct FileEntry {
 pub struct Walker {
     root: PathBuf,
     follow_symlinks: bool,
+    exclude_artifacts: bool,
+    artifact_allowlist: Vec<PathBuf>,
+    /// User-configured patterns, matched like `.gitignore` lines
+    ignore_globs: Option<Gitignore>,
+    /// Directory levels entered below the root
+    max_depth: Option<usize>,
+    /// Total size of the files kept, in path order (0 = no limit)
+    max_total_bytes: u64,
 }
 
 impl Walker {
@@ -29,14 +58,68 @@ impl Walker {
         Self {
             root: root.to_path_buf(),
             follow_symlinks,
+            exclude_artifacts: false,
+            artifact_allowlist: Vec::new(),
+            ignore_globs: None,
+            max_depth: None,
+            max_total_bytes: 0,
+        }
+    }
+
+    /// Enter at most `max_depth` directory levels below the root; deeper
+    /// entries are skipped.
+    pub fn with_max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Keep the files found, in path order, until they add up to more than
+    /// `max_total_bytes` (0 for no limit). Such walks run on one thread,
+    /// sorting each directory as they go, and descend no further once the
+    /// limit is reached.
+    pub fn with_max_total_bytes(mut self, max_total_bytes: u64) -> Self {
+        self.max_total_bytes = max_total_bytes;
+        self
+    }
+
+    /// Skip paths matching any of `globs`, using `.gitignore` syntax
+    /// relative to the root (e.g. `vendor/`, `*.pb.go`, `/docs/generated`).
+    pub fn with_ignore_globs(mut self, globs: &[String]) -> Result<Self, IndexerError> {
+        if globs.is_empty() {
+            return Ok(self);
         }
+        let mut builder = GitignoreBuilder::new(&self.root);
+        for glob in globs {
+            builder
+                .add_line(None, glob)
+                .map_err(|e| IndexerError::InvalidGlob(e.to_string()))?;
+        }
+        let ignore = builder
+            .build()
+            .map_err(|e| IndexerError::InvalidGlob(e.to_string()))?;
+        self.ignore_globs = Some(ignore);
+        Ok(self)
+    }
+
+    /// Skip well-known artifact directories (`target/`, `dist/`, virtualenvs)
+    /// even when they are not gitignored, except for allowlisted paths.
+    pub fn with_artifact_exclusion(mut self, allowlist: Vec<PathBuf>) -> Self {
+        self.exclude_artifacts = true;
+        self.artifact_allowlist = allowlist;
+        self
     }
 
     /// Walk the directory tree and return all discovered files.
     pub fn walk(&self) -> Result<Vec<FileEntry>, IndexerError> {
-        let (tx, rx) = mpsc::channel();
+        self.walk_with_report().map(|(entries, _)| entries)
+    }
 
-        let walker = WalkBuilder::new(&self.root)
+    /// Walk the directory tree, also reporting auto-excluded artifact directories.
+    pub fn walk_with_report(&self) -> Result<(Vec<FileEntry>, Vec<ExcludedDir>), IndexerError> {
+        let excluded = Arc::new(Mutex::new(Vec::new()));
+
+        let mut builder = WalkBuilder::new(&self.root);
+        builder
             .follow_links(self.follow_symlinks)
             .hidden(true) // Skip hidden files by default
             .git_ignore(true)
@@ -44,61 +127,287 @@ impl Walker {
             .git_exclude(true)
             .ignore(true)
             .parents(true)
-            .build_parallel();
-
-        walker.run(|| {
-            let tx = tx.clone();
-            Box::new(move |result| {
-                match result {
-                    Ok(entry) => {
-                        // Only process files, not directories
-                        if entry.file_type().map(|ft| ft.is_file()).unwrap_or(false) {
-                            if let Ok(metadata) = entry.metadata() {
-                                let mtime = metadata
-                                    .modified()
-                                    .ok()
-                                    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-                                    .map(|d| d.as_secs())
-                                    .unwrap_or(0);
-
-                                let file_entry = FileEntry {
-                                    path: entry.path().to_path_buf(),
-                                    size: metadata.len(),
-                                    mtime,
-                                };
-
-                                let _ = tx.send(Ok(file_entry));
-                            }
+            .add_custom_ignore_filename(IGNORE_FILE);
+
+        let too_deep = Arc::new(AtomicBool::new(false));
+        {
+            let root = self.root.clone();
+            let exclude_artifacts = self.exclude_artifacts;
+            let allowlist = self.artifact_allowlist.clone();
+            let ignore_globs = self.ignore_globs.clone();
+            let excluded = excluded.clone();
+            let max_depth = self.max_depth;
+            let too_deep = too_deep.clone();
+            let visited = self.follow_symlinks.then(|| VisitedDirs::new(&self.root));
+            builder.filter_entry(move |entry| {
+                if entry.depth() == 0 {
+                    return true;
+                }
+                if max_depth.is_some_and(|max_depth| entry.depth() > max_depth) {
+                    too_deep.store(true, Ordering::Relaxed);
+                    return false;
+                }
+                let is_dir = entry.file_type().is_some_and(|ft| ft.is_dir());
+                if is_dir {
+                    if let Some(visited) = &visited {
+                        if !visited.enter(entry.path(), entry.path_is_symlink()) {
+                            return false;
                         }
                     }
-                    Err(e) => {
-                        debug!(error = %e, "Walk error");
-                        // Don't fail the entire walk for individual errors
-                    }
                 }
-                WalkState::Continue
-            })
-        });
-
-        // Drop the original sender so the receiver knows when we're done
-        drop(tx);
+                let rel_path = entry.path().strip_prefix(&root).unwrap_or(entry.path());
+                if ignore_globs
+                    .as_ref()
+                    .is_some_and(|globs| globs.matched(rel_path, is_dir).is_ignore())
+                {
+                    return false;
+                }
+                if !exclude_artifacts || !is_dir {
+                    return true;
+                }
+                let Some(reason) = detect_artifact_dir(entry.path()) else {
+                    return true;
+                };
+                if is_allowlisted(rel_path, &allowlist) {
+                    return true;
+                }
+                excluded.lock().unwrap().push(ExcludedDir {
+                    path: rel_path.to_path_buf(),
+                    reason: reason.to_string(),
+                });
+                false
+            });
+        }
 
-        // Collect results
         let mut entries = Vec::new();
-        for result in rx {
-            match result {
-                Ok(entry) => entries.push(entry),
-                Err(e) => return Err(e),
+        let mut over_budget = false;
+        if self.max_total_bytes > 0 {
+            // Path order is name order in every directory, so the files
+            // kept are known without seeing the rest of the tree
+            builder.sort_by_file_name(|a, b| a.cmp(b));
+            let mut total_bytes = 0u64;
+            for entry in builder.build().filter_map(walk_entry) {
+                total_bytes += entry.size;
+                if total_bytes > self.max_total_bytes {
+                    over_budget = true;
+                    break;
+                }
+                entries.push(entry);
             }
+        } else {
+            let (tx, rx) = mpsc::channel();
+            builder.build_parallel().run(|| {
+                let tx = tx.clone();
+                Box::new(move |result| {
+                    if let Some(entry) = walk_entry(result) {
+                        let _ = tx.send(entry);
+                    }
+                    WalkState::Continue
+                })
+            });
+            // Drop the original sender so the receiver knows when we're done
+            drop(tx);
+            entries.extend(rx);
+
+            // Sort by path for deterministic ordering
+            entries.sort_by(|a, b| a.path.cmp(&b.path));
+        }
+
+        if too_deep.load(Ordering::Relaxed) {
+            warn!(
+                root = ?self.root,
+                max_depth = self.max_depth,
+                "Skipped entries nested deeper than the depth limit"
+            );
+        }
+        if over_budget {
+            warn!(
+                root = ?self.root,
+                max_total_bytes = self.max_total_bytes,
+                files = entries.len(),
+                "Stopped at the size limit; remaining files are not indexed"
+            );
         }
 
-        // Sort by path for deterministic ordering
-        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        let mut excluded = std::mem::take(&mut *excluded.lock().unwrap());
+        excluded.sort_by(|a, b| a.path.cmp(&b.path));
+        if !excluded.is_empty() {
+            info!(count = excluded.len(), "Auto-excluded artifact directories");
+        }
+
+        Ok((entries, excluded))
+    }
 
-        Ok(entries)
+    /// Whether a walk would visit `path` (absolute, under the root).
+    ///
+    /// Used to filter single-file change events without re-walking: hidden
+    /// components, ignore globs, artifact directories and the `.gitignore`
+    /// and `.engramignore` files from the root down to the file's directory
+    /// are honored. Global and `.git/info` excludes are not.
+    pub fn includes(&self, path: &Path) -> bool {
+        let Ok(rel_path) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+        let hidden = rel_path
+            .components()
+            .any(|c| c.as_os_str().to_string_lossy().starts_with('.'));
+        if hidden || rel_path.as_os_str().is_empty() {
+            return false;
+        }
+        if self
+            .max_depth
+            .is_some_and(|max_depth| rel_path.components().count() > max_depth)
+        {
+            return false;
+        }
+        if self.ignore_globs.as_ref().is_some_and(|globs| {
+            globs
+                .matched_path_or_any_parents(rel_path, false)
+                .is_ignore()
+        }) {
+            return false;
+        }
+
+        // Each directory's ignore files, from the root down; a directory a
+        // walk would skip hides everything below it
+        let mut dir = self.root.clone();
+        let mut matchers = dir_ignores(&dir);
+        for component in rel_path.parent().into_iter().flat_map(|p| p.components()) {
+            dir.push(component);
+            if is_ignored(&matchers, &dir, true) {
+                return false;
+            }
+            if self.exclude_artifacts
+                && detect_artifact_dir(&dir).is_some()
+                && !is_allowlisted(
+                    dir.strip_prefix(&self.root).unwrap_or(&dir),
+                    &self.artifact_allowlist,
+                )
+            {
+                return false;
+            }
+            matchers.extend(dir_ignores(&dir));
+        }
+        !is_ignored(&matchers, path, false)
+    }
+}
+
+/// The file a walk reached, with its metadata; `None` for directories and
+/// entries that could not be read.
+fn walk_entry(result: Result<DirEntry, ignore::Error>) -> Option<FileEntry> {
+    let entry = match result {
+        Ok(entry) => entry,
+        Err(e) => {
+            // Don't fail the entire walk for individual errors
+            debug!(error = %e, "Walk error");
+            return None;
+        }
+    };
+    if !entry.file_type().is_some_and(|ft| ft.is_file()) {
+        return None;
+    }
+    let metadata = entry.metadata().ok()?;
+    let mtime = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    Some(FileEntry {
+        path: entry.into_path(),
+        size: metadata.len(),
+        mtime,
+    })
+}
+
+/// Identity of a directory, the same through every symlink to it.
+#[cfg(unix)]
+type DirId = (u64, u64);
+
+#[cfg(not(unix))]
+type DirId = PathBuf;
+
+#[cfg(unix)]
+fn dir_id(path: &Path) -> Option<DirId> {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::metadata(path)
+        .ok()
+        .map(|metadata| (metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn dir_id(path: &Path) -> Option<DirId> {
+    path.canonicalize().ok()
+}
+
+/// Directories entered by a walk that follows symlinks.
+struct VisitedDirs {
+    /// Canonical root; links into it are walked through their real path
+    root: Option<PathBuf>,
+    seen: Mutex<HashSet<DirId>>,
+}
+
+impl VisitedDirs {
+    fn new(root: &Path) -> Self {
+        Self {
+            root: root.canonicalize().ok(),
+            seen: Mutex::new(dir_id(root).into_iter().collect()),
+        }
+    }
+
+    /// Whether to enter the directory at `path`: not when it links back
+    /// into the root or was entered before, which ends link cycles.
+    fn enter(&self, path: &Path, is_link: bool) -> bool {
+        if is_link {
+            let target = path.canonicalize().ok();
+            if let (Some(root), Some(target)) = (&self.root, &target) {
+                if target.starts_with(root) {
+                    debug!(path = ?path, "Skipping symlink into the walked tree");
+                    return false;
+                }
+            }
+        }
+        let Some(id) = dir_id(path) else {
+            return false;
+        };
+        if !self.seen.lock().unwrap().insert(id) {
+            debug!(path = ?path, "Skipping directory already walked");
+            return false;
+        }
+        true
     }
 }
 
+/// Ignore files of a directory, rooted at it, lowest precedence first.
+fn dir_ignores(dir: &Path) -> Vec<Gitignore> {
+    IGNORE_FILES
+        .iter()
+        .map(|name| dir.join(name))
+        .filter(|path| path.is_file())
+        .filter_map(|path| {
+            let (matcher, error) = Gitignore::new(&path);
+            if let Some(e) = error {
+                debug!(path = ?path, error = %e, "Invalid ignore file");
+            }
+            (!matcher.is_empty()).then_some(matcher)
+        })
+        .collect()
+}
+
+/// Whether the most specific matching pattern ignores `path`.
+fn is_ignored(matchers: &[Gitignore], path: &Path, is_dir: bool) -> bool {
+    for matcher in matchers.iter().rev() {
+        let matched = matcher.matched(path, is_dir);
+        if matched.is_ignore() {
+            return true;
+        }
+        if matched.is_whitelist() {
+            return false;
+        }
+    }
+    false
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -214,6 +523,49 @@ mod tests {
         assert!(entries[0].mtime > 0);
     }
 
+    #[test]
+    fn test_walker_excludes_artifact_dirs() {
+        let temp_dir = tempdir().unwrap();
+
+        fs::write(temp_dir.path().join("package.json"), "{}").unwrap();
+        fs::create_dir_all(temp_dir.path().join("dist")).unwrap();
+        fs::create_dir_all(temp_dir.path().join("node_modules/left-pad")).unwrap();
+        File::create(temp_dir.path().join("dist/bundle.js")).unwrap();
+        File::create(temp_dir.path().join("node_modules/left-pad/index.js")).unwrap();
+        File::create(temp_dir.path().join("index.js")).unwrap();
+
+        let walker = Walker::new(temp_dir.path(), false).with_artifact_exclusion(vec![]);
+        let (entries, excluded) = walker.walk_with_report().unwrap();
+
+        let names: Vec<_> = entries
+            .iter()
+            .map(|e| e.path.file_name().unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["index.js", "package.json"]);
+
+        let excluded: Vec<_> = excluded.iter().map(|d| d.path.clone()).collect();
+        assert_eq!(
+            excluded,
+            vec![PathBuf::from("dist"), PathBuf::from("node_modules")]
+        );
+    }
+
+    #[test]
+    fn test_walker_artifact_allowlist() {
+        let temp_dir = tempdir().unwrap();
+
+        fs::write(temp_dir.path().join("package.json"), "{}").unwrap();
+        fs::create_dir_all(temp_dir.path().join("dist")).unwrap();
+        File::create(temp_dir.path().join("dist/bundle.js")).unwrap();
+
+        let walker = Walker::new(temp_dir.path(), false)
+            .with_artifact_exclusion(vec![PathBuf::from("dist")]);
+        let (entries, excluded) = walker.walk_with_report().unwrap();
+
+        assert_eq!(entries.len(), 2);
+        assert!(excluded.is_empty());
+    }
+
     #[test]
     fn test_walker_results_are_sorted() {
         let temp_dir = tempdir().unwrap();
@@ -232,4 +584,187 @@ mod tests {
 
         assert_eq!(names, vec!["a.txt", "b.txt", "c.txt"]);
     }
+
+    #[test]
+    fn test_walker_includes() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().canonicalize().unwrap();
+        fs::create_dir_all(root.join("src/generated")).unwrap();
+        fs::create_dir_all(root.join("target/debug")).unwrap();
+        File::create(root.join("target/CACHEDIR.TAG")).unwrap();
+        fs::write(root.join(".gitignore"), "*.log\n").unwrap();
+        fs::write(root.join("src/.gitignore"), "generated/\n").unwrap();
+
+        let walker = Walker::new(&root, false).with_artifact_exclusion(vec![]);
+        assert!(walker.includes(&root.join("src/lib.rs")));
+        assert!(!walker.includes(&root.join("debug.log")));
+        assert!(!walker.includes(&root.join("src/generated/api.rs")));
+        assert!(!walker.includes(&root.join("target/debug/build.rs")));
+        assert!(!walker.includes(&root.join(".git/HEAD")));
+        assert!(!walker.includes(Path::new("/elsewhere/lib.rs")));
+    }
+
+    #[test]
+    fn test_walker_engramignore() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().canonicalize().unwrap();
+        for dir in ["assets/video", "assets/icons", "src/fixtures", "docs"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for file in [
+            "assets/video/intro.rs",
+            "assets/icons/logo.rs",
+            "src/fixtures/big.json",
+            "src/fixtures/small.json",
+            "src/main.rs",
+            "docs/main.rs",
+        ] {
+            fs::write(root.join(file), "").unwrap();
+        }
+        // No .git: the ignore file applies without a repository
+        fs::write(root.join(".engramignore"), "assets/*\n!assets/icons/\n").unwrap();
+        // Relative to its own directory, not the root
+        fs::write(root.join("src/.engramignore"), "/fixtures/*.json\n").unwrap();
+        fs::write(root.join("src/fixtures/.engramignore"), "!small.json\n").unwrap();
+        fs::write(root.join(".gitignore"), "main.rs\n").unwrap();
+        fs::write(root.join("docs/.engramignore"), "!main.rs\n").unwrap();
+
+        let walker = Walker::new(&root, false);
+        let mut files: Vec<_> = walker
+            .walk()
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.path.strip_prefix(&root).unwrap().to_path_buf())
+            .collect();
+        files.sort();
+        let expected = [
+            "assets/icons/logo.rs",
+            "docs/main.rs",
+            "src/fixtures/small.json",
+            "src/main.rs",
+        ];
+        assert_eq!(files, expected.map(PathBuf::from));
+
+        // Without .git the walk skips .gitignore; change events honor it
+        assert!(walker.includes(&root.join("assets/icons/logo.rs")));
+        assert!(!walker.includes(&root.join("assets/video/intro.rs")));
+        assert!(!walker.includes(&root.join("src/fixtures/big.json")));
+        assert!(walker.includes(&root.join("src/fixtures/small.json")));
+        assert!(!walker.includes(&root.join("src/main.rs")));
+        assert!(walker.includes(&root.join("docs/main.rs")));
+    }
+
+    #[test]
+    fn test_walker_ignore_globs() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().canonicalize().unwrap();
+        fs::create_dir_all(root.join("vendor/sdk")).unwrap();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("vendor/sdk/lib.go"), "package sdk").unwrap();
+        fs::write(root.join("src/api.pb.go"), "package src").unwrap();
+        fs::write(root.join("src/main.go"), "package main").unwrap();
+
+        let walker = Walker::new(&root, false)
+            .with_ignore_globs(&["vendor/".to_string(), "*.pb.go".to_string()])
+            .unwrap();
+        let files: Vec<_> = walker
+            .walk()
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.path.strip_prefix(&root).unwrap().to_path_buf())
+            .collect();
+        assert_eq!(files, [PathBuf::from("src/main.go")]);
+        assert!(!walker.includes(&root.join("vendor/sdk/lib.go")));
+        assert!(!walker.includes(&root.join("src/api.pb.go")));
+        assert!(walker.includes(&root.join("src/main.go")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_walker_symlink_cycles() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().join("project");
+        let shared = temp_dir.path().join("shared");
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(&shared).unwrap();
+        fs::write(root.join("src/main.rs"), "").unwrap();
+        fs::write(shared.join("util.rs"), "").unwrap();
+        // A loop back to the root, and two links to the same outside dir,
+        // one of which loops back to itself
+        std::os::unix::fs::symlink(&root, root.join("src/loop")).unwrap();
+        std::os::unix::fs::symlink(&shared, root.join("a")).unwrap();
+        std::os::unix::fs::symlink(&shared, root.join("b")).unwrap();
+        std::os::unix::fs::symlink(&shared, shared.join("again")).unwrap();
+
+        let files: Vec<_> = Walker::new(&root, true)
+            .walk()
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.path.strip_prefix(&root).unwrap().to_path_buf())
+            .collect();
+        assert_eq!(files.len(), 2, "{:?}", files);
+        assert_eq!(files[1], PathBuf::from("src/main.rs"));
+        assert!(files[0].ends_with("util.rs"));
+    }
+
+    #[test]
+    fn test_walker_limits() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().canonicalize().unwrap();
+        fs::create_dir_all(root.join("a/b/c")).unwrap();
+        fs::write(root.join("top.txt"), "1234").unwrap();
+        fs::write(root.join("a/one.txt"), "1234").unwrap();
+        fs::write(root.join("a/b/two.txt"), "1234").unwrap();
+        fs::write(root.join("a/b/c/three.txt"), "1234").unwrap();
+
+        let walker = Walker::new(&root, false).with_max_depth(Some(2));
+        let names: Vec<_> = walker
+            .walk()
+            .unwrap()
+            .iter()
+            .map(|e| e.path.file_name().unwrap().to_str().unwrap().to_string())
+            .collect();
+        assert_eq!(names, vec!["one.txt", "top.txt"]);
+        assert!(walker.includes(&root.join("a/one.txt")));
+        assert!(!walker.includes(&root.join("a/b/two.txt")));
+
+        // Three files fit into 12 bytes; the first three by path are kept
+        for _ in 0..3 {
+            let names: Vec<_> = Walker::new(&root, false)
+                .with_max_total_bytes(12)
+                .walk()
+                .unwrap()
+                .iter()
+                .map(|e| e.path.file_name().unwrap().to_str().unwrap().to_string())
+                .collect();
+            assert_eq!(names, vec!["three.txt", "two.txt", "one.txt"]);
+        }
+        assert_eq!(
+            Walker::new(&root, false)
+                .with_max_total_bytes(0)
+                .walk()
+                .unwrap()
+                .len(),
+            4
+        );
+
+        // Past the budget nothing more is visited, so a later artifact
+        // directory is never reached
+        fs::create_dir_all(root.join("z/node_modules")).unwrap();
+        fs::write(root.join("z/package.json"), "{}").unwrap();
+        fs::write(root.join("z/node_modules/x.js"), "").unwrap();
+        let walk = |max_total_bytes| {
+            Walker::new(&root, false)
+                .with_artifact_exclusion(vec![])
+                .with_max_total_bytes(max_total_bytes)
+                .walk_with_report()
+                .unwrap()
+        };
+        let (entries, excluded) = walk(12);
+        assert_eq!(entries.len(), 3);
+        assert!(excluded.is_empty());
+        let (entries, excluded) = walk(0);
+        assert_eq!(entries.len(), 5);
+        assert_eq!(excluded.len(), 1);
+    }
 }
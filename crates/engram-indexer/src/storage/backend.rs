@@ -0,0 +1,156 @@
+//! Where stored trees are kept.
+//!
+//! Trees and their dependency graphs are saved as named blobs per project
+//! (`skeleton.json`, `enriched.msgpack`, `dependencies.log`).
+//! [`FilesystemBackend`] keeps each blob as a file in the project's
+//! storage directory; other backends keep them elsewhere, e.g.
+//! `SqliteBackend` in a database (with the `sqlite` feature). Experience logs, snapshots, the memory-mapped tree and the
+//! files of other components stay in the storage directory whatever the
+//! backend, since they are appended to or mapped in place.
+
+use crate::IndexerError;
+use async_trait::async_trait;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// Blob store for a project's trees, keyed by project hash and name.
+#[async_trait]
+pub trait StorageBackend: Send + Sync {
+    /// Read a blob; `None` if it does not exist.
+    async fn read(&self, hash: &str, name: &str) -> Result<Option<Vec<u8>>, IndexerError>;
+
+    /// Replace a blob atomically: readers see the old or the new data.
+    async fn write(&self, hash: &str, name: &str, data: &[u8]) -> Result<(), IndexerError>;
+
+    /// Append to a blob, creating it if it does not exist.
+    ///
+    /// Backends without a cheaper way read and rewrite the whole blob.
+    async fn append(&self, hash: &str, name: &str, data: &[u8]) -> Result<(), IndexerError> {
+        let mut blob = self.read(hash, name).await?.unwrap_or_default();
+        blob.extend_from_slice(data);
+        self.write(hash, name, &blob).await
+    }
+
+    /// Remove a blob; removing a missing blob is not an error.
+    async fn remove(&self, hash: &str, name: &str) -> Result<(), IndexerError>;
+
+    /// Remove every blob of a project kept outside its storage directory,
+    /// once that directory is deleted.
+    ///
+    /// Backends keeping blobs in the directory have nothing to do.
+    async fn remove_project(&self, _hash: &str) -> Result<(), IndexerError> {
+        Ok(())
+    }
+
+    /// When a blob was last written; `None` if it does not exist.
+    async fn modified(&self, hash: &str, name: &str) -> Option<SystemTime>;
+
+    /// Where a blob is kept, for diagnostics.
+    fn location(&self, hash: &str, name: &str) -> PathBuf;
+}
+
+/// Blobs as files in `<dir>/<hash>/<name>`.
+#[derive(Debug, Clone)]
+pub struct FilesystemBackend {
+    dir: PathBuf,
+}
+
+impl FilesystemBackend {
+    /// Store blobs below `dir`, one directory per project.
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+}
+
+#[async_trait]
+impl StorageBackend for FilesystemBackend {
+    async fn read(&self, hash: &str, name: &str) -> Result<Option<Vec<u8>>, IndexerError> {
+        match tokio::fs::read(self.location(hash, name)).await {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn write(&self, hash: &str, name: &str, data: &[u8]) -> Result<(), IndexerError> {
+        let dir = self.dir.join(hash);
+        tokio::fs::create_dir_all(&dir).await?;
+
+        // Write to a temp file, then rename over the blob
+        let temp_path = dir.join(format!(".{}.tmp", name));
+        tokio::fs::write(&temp_path, data).await?;
+        tokio::fs::rename(&temp_path, dir.join(name)).await?;
+        Ok(())
+    }
+
+    async fn append(&self, hash: &str, name: &str, data: &[u8]) -> Result<(), IndexerError> {
+        use tokio::io::AsyncWriteExt;
+
+        tokio::fs::create_dir_all(self.dir.join(hash)).await?;
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.location(hash, name))
+            .await?;
+        file.write_all(data).await?;
+        file.flush().await?;
+        Ok(())
+    }
+
+    async fn remove(&self, hash: &str, name: &str) -> Result<(), IndexerError> {
+        match tokio::fs::remove_file(self.location(hash, name)).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    async fn modified(&self, hash: &str, name: &str) -> Option<SystemTime> {
+        tokio::fs::metadata(self.location(hash, name))
+            .await
+            .and_then(|meta| meta.modified())
+            .ok()
+    }
+
+    fn location(&self, hash: &str, name: &str) -> PathBuf {
+        self.dir.join(hash).join(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_filesystem_backend() {
+        let temp_dir = tempdir().unwrap();
+        let backend = FilesystemBackend::new(temp_dir.path().to_path_buf());
+
+        assert_eq!(backend.read("abc", "skeleton.json").await.unwrap(), None);
+        assert!(backend.modified("abc", "skeleton.json").await.is_none());
+
+        backend.write("abc", "skeleton.json", b"{}").await.unwrap();
+        assert_eq!(
+            backend.read("abc", "skeleton.json").await.unwrap(),
+            Some(b"{}".to_vec())
+        );
+        assert!(temp_dir.path().join("abc/skeleton.json").exists());
+        assert!(!temp_dir.path().join("abc/.skeleton.json.tmp").exists());
+        assert!(backend.modified("abc", "skeleton.json").await.is_some());
+
+        backend.append("abc", "skeleton.json", b"\n").await.unwrap();
+        backend.append("abc", "new.log", b"+1 2\n").await.unwrap();
+        assert_eq!(
+            backend.read("abc", "skeleton.json").await.unwrap(),
+            Some(b"{}\n".to_vec())
+        );
+        assert_eq!(
+            backend.read("abc", "new.log").await.unwrap(),
+            Some(b"+1 2\n".to_vec())
+        );
+
+        backend.remove("abc", "skeleton.json").await.unwrap();
+        backend.remove("abc", "skeleton.json").await.unwrap();
+        assert_eq!(backend.read("abc", "skeleton.json").await.unwrap(), None);
+    }
+}
@@ -0,0 +1,223 @@
+//! Encryption of experience log lines at rest.
+//!
+//! An encrypted line is `enc:<key id>:<base64 of nonce and ciphertext>`,
+//! sealed with AES-256-GCM under a random 96-bit nonce; the GCM tag takes
+//! the place of the checksum plaintext lines carry. The key id, the start of
+//! the key's SHA-256, tells a line encrypted with another key from a corrupt
+//! one, so a log read with the wrong key, or none, fails instead of being
+//! moved aside as corrupt.
+
+use crate::IndexerError;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+/// Prefix of an encrypted line
+const ENCRYPTED_PREFIX: &str = "enc:";
+
+/// Bytes of a key
+pub const KEY_LEN: usize = 32;
+
+/// Bytes of a nonce
+const NONCE_LEN: usize = 12;
+
+/// Encrypts and decrypts experience log lines with one key.
+pub struct LogCipher {
+    cipher: Aes256Gcm,
+    key_id: String,
+}
+
+impl std::fmt::Debug for LogCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LogCipher")
+            .field("key_id", &self.key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl LogCipher {
+    /// Cipher for a 256-bit key.
+    pub fn new(key: &[u8; KEY_LEN]) -> Self {
+        let digest = Sha256::digest(key);
+        Self {
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)),
+            key_id: to_hex(&digest[..4]),
+        }
+    }
+
+    /// A random 256-bit key, as 64 hex digits.
+    pub fn generate_key() -> String {
+        to_hex(&Aes256Gcm::generate_key(OsRng))
+    }
+
+    /// Cipher for a key given as 64 hex digits.
+    pub fn from_hex(hex: &str) -> Result<Self, IndexerError> {
+        let hex = hex.trim();
+        let invalid = || IndexerError::Storage("Encryption key must be 64 hex digits".to_string());
+        if hex.len() != 2 * KEY_LEN {
+            return Err(invalid());
+        }
+        let mut key = [0u8; KEY_LEN];
+        for (byte, pair) in key.iter_mut().zip(hex.as_bytes().chunks(2)) {
+            let pair = std::str::from_utf8(pair).map_err(|_| invalid())?;
+            *byte = u8::from_str_radix(pair, 16).map_err(|_| invalid())?;
+        }
+        Ok(Self::new(&key))
+    }
+
+    /// Cipher for the key in the file at `path`, first writing a random key
+    /// to it, readable by its owner only, if it does not exist.
+    pub fn load_or_create(path: &Path) -> Result<Self, IndexerError> {
+        match std::fs::read_to_string(path) {
+            Ok(hex) => return Self::from_hex(&hex),
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            Err(_) => {}
+        }
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let key = Self::generate_key();
+        let mut file = options.open(path)?;
+        std::io::Write::write_all(&mut file, format!("{}\n", key).as_bytes())?;
+        file.sync_all()?;
+        tracing::warn!(path = ?path, "Generated an experience log key; back it up to keep the logs readable");
+        Self::from_hex(&key)
+    }
+
+    /// Id of the key, recorded in each line it encrypts.
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    /// `plaintext` as an encrypted line.
+    pub(crate) fn encrypt(&self, plaintext: &str) -> String {
+        let nonce = Aes256Gcm::generate_nonce(OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext.as_bytes())
+            .expect("AES-GCM encrypts messages of any length a line has");
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        format!(
+            "{}{}:{}",
+            ENCRYPTED_PREFIX,
+            self.key_id,
+            BASE64.encode(sealed)
+        )
+    }
+
+    /// The plaintext of an encrypted line, or `None` if it is corrupt.
+    ///
+    /// Fails if the line was encrypted with another key.
+    pub(crate) fn decrypt(&self, line: &str) -> Result<Option<String>, IndexerError> {
+        let Some((key_id, data)) = line
+            .strip_prefix(ENCRYPTED_PREFIX)
+            .and_then(|rest| rest.split_once(':'))
+        else {
+            return Ok(None);
+        };
+        if key_id != self.key_id {
+            return Err(other_key(key_id));
+        }
+        let Some(sealed) = BASE64.decode(data).ok().filter(|s| s.len() > NONCE_LEN) else {
+            return Ok(None);
+        };
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        Ok(self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .ok()
+            .and_then(|plaintext| String::from_utf8(plaintext).ok()))
+    }
+}
+
+/// Whether `line` was written encrypted.
+pub(crate) fn is_encrypted(line: &str) -> bool {
+    line.starts_with(ENCRYPTED_PREFIX)
+}
+
+/// Error for a line encrypted with a key that is not configured.
+pub(crate) fn other_key(key_id: &str) -> IndexerError {
+    IndexerError::Storage(format!(
+        "Experience log is encrypted with key {}, which is not configured",
+        key_id
+    ))
+}
+
+/// Id of the key an encrypted line was written with.
+pub(crate) fn line_key_id(line: &str) -> &str {
+    line.strip_prefix(ENCRYPTED_PREFIX)
+        .and_then(|rest| rest.split_once(':'))
+        .map_or("", |(key_id, _)| key_id)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    use std::fmt::Write;
+    bytes.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{:02x}", byte);
+        hex
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_encrypt_round_trip() {
+        let cipher = LogCipher::from_hex(&LogCipher::generate_key()).unwrap();
+        let line = cipher.encrypt(r#"{"id":"a"}"#);
+        assert!(is_encrypted(&line));
+        assert!(!line.contains("\"id\""));
+        assert_eq!(
+            cipher.decrypt(&line).unwrap().as_deref(),
+            Some(r#"{"id":"a"}"#)
+        );
+        // Each line has its own nonce
+        assert_ne!(line, cipher.encrypt(r#"{"id":"a"}"#));
+
+        // A tampered line is corrupt, one of another key an error
+        let mut tampered = line.clone().into_bytes();
+        let last = tampered.len() - 3;
+        tampered[last] = if tampered[last] == b'A' { b'B' } else { b'A' };
+        let tampered = String::from_utf8(tampered).unwrap();
+        assert_eq!(cipher.decrypt(&tampered).unwrap(), None);
+        let other = LogCipher::from_hex(&LogCipher::generate_key()).unwrap();
+        assert!(other.decrypt(&line).is_err());
+        assert_eq!(line_key_id(&line), cipher.key_id());
+
+        assert!(LogCipher::from_hex("abc").is_err());
+        assert!(LogCipher::from_hex(&"zz".repeat(KEY_LEN)).is_err());
+    }
+
+    #[test]
+    fn test_load_or_create_key_file() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("keys/log.key");
+
+        let created = LogCipher::load_or_create(&path).unwrap();
+        let loaded = LogCipher::load_or_create(&path).unwrap();
+        assert_eq!(created.key_id(), loaded.key_id());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        std::fs::write(&path, "not a key").unwrap();
+        assert!(LogCipher::load_or_create(&path).is_err());
+    }
+}
@@ -0,0 +1,213 @@
+//! Dependency graph stored as an edge log.
+//!
+//! Each line of the log adds (`+<from> <to>`) or removes (`-<from> <to>`)
+//! one import edge. Saving a graph appends only its difference from the
+//! stored one, so an update after a single-file change writes a few lines
+//! instead of the whole graph. Once the log holds more than twice as many
+//! lines as the graph has edges, it is compacted into one `+` line per edge.
+
+use crate::tree::{DependencyGraph, NodeId};
+use std::collections::BTreeSet;
+use tracing::warn;
+
+/// Blob holding the dependency edge log
+pub(super) const EDGE_LOG_FILE: &str = "dependencies.log";
+
+/// Lines a log may hold before compaction, however small the graph
+const MIN_COMPACT_LINES: usize = 1024;
+
+/// Edges replayed from a stored log.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(super) struct EdgeLog {
+    /// Edges present after the last line
+    pub edges: BTreeSet<(NodeId, NodeId)>,
+    /// Lines in the log
+    pub lines: usize,
+    /// The last line is unterminated, so the log cannot be appended to
+    pub torn: bool,
+}
+
+/// How to bring a stored log up to date with a graph.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum EdgeLogWrite {
+    /// The log already matches
+    Unchanged,
+    /// Append these lines
+    Append(String),
+    /// Replace the log with this compacted one
+    Rewrite(String),
+}
+
+impl EdgeLog {
+    /// Replay a stored log. Lines that do not parse are skipped, as is a
+    /// final line without a newline: torn by a crash, it may still parse as
+    /// an edge that was never written.
+    pub fn parse(data: &[u8]) -> Self {
+        let complete = data
+            .iter()
+            .rposition(|&byte| byte == b'\n')
+            .map_or(&[][..], |end| &data[..=end]);
+        let mut log = Self {
+            torn: complete.len() < data.len(),
+            ..Self::default()
+        };
+        let mut skipped = 0;
+        for line in String::from_utf8_lossy(complete).lines() {
+            if line.is_empty() {
+                continue;
+            }
+            log.lines += 1;
+            match parse_line(line) {
+                Some((true, edge)) => {
+                    log.edges.insert(edge);
+                }
+                Some((false, edge)) => {
+                    log.edges.remove(&edge);
+                }
+                None => skipped += 1,
+            }
+        }
+        if skipped > 0 {
+            warn!(skipped, "Skipped unreadable dependency log lines");
+        }
+        log
+    }
+
+    /// The graph the log describes.
+    pub fn graph(&self) -> DependencyGraph {
+        let mut graph = DependencyGraph::new();
+        for &(from, to) in &self.edges {
+            graph.add_edge(from, to);
+        }
+        graph
+    }
+
+    /// What to write so the log describes `graph`.
+    pub fn write_for(&self, graph: &DependencyGraph) -> EdgeLogWrite {
+        let edges: BTreeSet<(NodeId, NodeId)> = graph.edges().collect();
+        let mut changes = String::new();
+        let mut changed = 0;
+        for edge in self.edges.difference(&edges) {
+            push_line(&mut changes, '-', *edge);
+            changed += 1;
+        }
+        for edge in edges.difference(&self.edges) {
+            push_line(&mut changes, '+', *edge);
+            changed += 1;
+        }
+
+        if self.torn || self.lines + changed > (2 * edges.len()).max(MIN_COMPACT_LINES) {
+            EdgeLogWrite::Rewrite(compacted(&edges))
+        } else if changed == 0 {
+            EdgeLogWrite::Unchanged
+        } else {
+            EdgeLogWrite::Append(changes)
+        }
+    }
+}
+
+/// A log describing `graph` from scratch.
+pub(super) fn full_log(graph: &DependencyGraph) -> String {
+    compacted(&graph.edges().collect())
+}
+
+/// A log with one `+` line per edge.
+fn compacted(edges: &BTreeSet<(NodeId, NodeId)>) -> String {
+    let mut data = String::new();
+    for edge in edges {
+        push_line(&mut data, '+', *edge);
+    }
+    data
+}
+
+fn push_line(data: &mut String, op: char, (from, to): (NodeId, NodeId)) {
+    use std::fmt::Write;
+    let _ = writeln!(data, "{}{} {}", op, from, to);
+}
+
+/// Parse a line into whether it adds the edge, and the edge.
+fn parse_line(line: &str) -> Option<(bool, (NodeId, NodeId))> {
+    let add = match line.as_bytes().first()? {
+        b'+' => true,
+        b'-' => false,
+        _ => return None,
+    };
+    let (from, to) = line[1..].split_once(' ')?;
+    Some((add, (from.parse().ok()?, to.parse().ok()?)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(edges: &[(NodeId, NodeId)]) -> DependencyGraph {
+        let mut graph = DependencyGraph::new();
+        for &(from, to) in edges {
+            graph.add_edge(from, to);
+        }
+        graph
+    }
+
+    #[test]
+    fn test_edge_log_appends_changes() {
+        let empty = EdgeLog::default();
+        let EdgeLogWrite::Append(first) = empty.write_for(&graph(&[(1, 2), (1, 3)])) else {
+            panic!("Expected an append");
+        };
+        assert_eq!(first, "+1 2\n+1 3\n");
+
+        let log = EdgeLog::parse(first.as_bytes());
+        assert_eq!(log.lines, 2);
+        assert_eq!(log.write_for(&log.graph()), EdgeLogWrite::Unchanged);
+
+        // Only the difference is written
+        let EdgeLogWrite::Append(changes) = log.write_for(&graph(&[(1, 2), (2, 3)])) else {
+            panic!("Expected an append");
+        };
+        assert_eq!(changes, "-1 3\n+2 3\n");
+
+        // A torn last line is skipped
+        let data = format!("{}{}+4", first, changes);
+        let log = EdgeLog::parse(data.as_bytes());
+        assert_eq!(log.edges, BTreeSet::from([(1, 2), (2, 3)]));
+        assert_eq!(log.graph().imports(2).collect::<Vec<_>>(), vec![3]);
+        // and the log rewritten rather than appended to
+        assert_eq!(
+            log.write_for(&log.graph()),
+            EdgeLogWrite::Rewrite("+1 2\n+2 3\n".to_string())
+        );
+
+        // even when what is left of it parses, as `+41 2` cut from `+41 23`
+        let data = format!("{}{}+41 2", first, changes);
+        let log = EdgeLog::parse(data.as_bytes());
+        assert_eq!(log.edges, BTreeSet::from([(1, 2), (2, 3)]));
+        assert_eq!(log.lines, 4);
+        assert!(matches!(
+            log.write_for(&log.graph()),
+            EdgeLogWrite::Rewrite(_)
+        ));
+    }
+
+    #[test]
+    fn test_edge_log_compacts() {
+        // Updates so far left many lines for one live edge
+        let log = EdgeLog {
+            edges: BTreeSet::from([(0, 1)]),
+            lines: MIN_COMPACT_LINES - 2,
+            torn: false,
+        };
+        assert!(matches!(
+            log.write_for(&graph(&[(1, 2)])),
+            EdgeLogWrite::Append(_)
+        ));
+
+        let log = EdgeLog {
+            lines: MIN_COMPACT_LINES,
+            ..log
+        };
+        assert_eq!(
+            log.write_for(&graph(&[(1, 2)])),
+            EdgeLogWrite::Rewrite("+1 2\n".to_string())
+        );
+    }
+}
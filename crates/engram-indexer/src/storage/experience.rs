@@ -1,12 +1,33 @@
 //! Experience log for recording agent decisions.
-
+//!
+//! Each JSON object appended to the log is sealed with a trailing `crc32`
+//! field holding the checksum of the line without it, so the lines stay
+//! valid JSON for other tools. Replay stops at the first line that is torn,
+//! unparseable or fails its checksum: that line and everything after it are
+//! the corrupt suffix, which is moved aside to `<log>.corrupt` instead of
+//! being skipped. Lines written before checksums existed are accepted if
+//! they parse.
+//!
+//! With a [`LogCipher`], lines are appended encrypted instead (see
+//! [`cipher`](super::cipher)); plaintext lines written before are still read,
+//! and [`ExperienceLog::encrypt`] rewrites them encrypted. Corrupt lines moved
+//! aside are then encrypted too.
+
+use super::cipher::{is_encrypted, line_key_id, other_key, LogCipher};
 use crate::IndexerError;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use tokio::fs::OpenOptions;
 use tokio::io::AsyncWriteExt;
-use tracing::debug;
+use tracing::{debug, warn};
+
+/// Field appended to each object holding the checksum of the rest of it
+const CHECKSUM_FIELD: &str = "\"crc32\":\"";
+
+/// Length of the sealed suffix: the field, 8 hex digits and `"}`
+const CHECKSUM_SUFFIX_LEN: usize = CHECKSUM_FIELD.len() + 8 + 2;
 
 /// An entry in the experience log.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,16 +46,64 @@ pub struct ExperienceEntry {
     pub score: Option<f32>,
 }
 
+/// What [`ExperienceLog::repair`] changed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LogRepair {
+    /// A valid last entry was missing its newline and got one
+    pub terminated: bool,
+    /// Lines of the corrupt suffix moved to the `.corrupt` file next to
+    /// the log
+    pub quarantined: usize,
+}
+
+impl LogRepair {
+    /// Whether the log was left untouched.
+    pub fn is_empty(&self) -> bool {
+        !self.terminated && self.quarantined == 0
+    }
+}
+
+/// Entries read by [`ExperienceLog::replay`].
+#[derive(Debug, Clone)]
+pub struct LogReplay<E> {
+    /// Entries of the verified lines, oldest first
+    pub entries: Vec<E>,
+    /// Lines of the corrupt suffix moved to the `.corrupt` file
+    pub quarantined: usize,
+}
+
 /// Append-only experience log.
 pub struct ExperienceLog {
     path: PathBuf,
     max_size: u64,
+    /// Encrypts appended and rewritten lines when set
+    cipher: Option<Arc<LogCipher>>,
 }
 
 impl ExperienceLog {
     /// Create a new experience log.
     pub fn new(path: PathBuf, max_size: u64) -> Self {
-        Self { path, max_size }
+        Self {
+            path,
+            max_size,
+            cipher: None,
+        }
+    }
+
+    /// Encrypt appended and rewritten lines with `cipher`.
+    pub fn with_cipher(mut self, cipher: Arc<LogCipher>) -> Self {
+        self.cipher = Some(cipher);
+        self
+    }
+
+    /// Location of the log file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Where the corrupt suffix of the log is moved.
+    pub fn quarantine_path(&self) -> PathBuf {
+        self.path.with_extension("jsonl.corrupt")
     }
 
     /// Append an entry to the log.
@@ -66,10 +135,8 @@ impl ExperienceLog {
         }
 
         // Append to file with newline
-        let mut line = json.to_string();
-        if !line.ends_with('\n') {
-            line.push('\n');
-        }
+        let mut line = self.encode(json);
+        line.push('\n');
 
         let mut file = OpenOptions::new()
             .create(true)
@@ -89,18 +156,24 @@ impl ExperienceLog {
     }
 
     /// Read all entries from the log.
+    ///
+    /// Fails on a corrupt line or one that is not an [`ExperienceEntry`].
     pub async fn read_all(&self) -> Result<Vec<ExperienceEntry>, IndexerError> {
         if !self.path.exists() {
             return Ok(Vec::new());
         }
 
         let content = tokio::fs::read_to_string(&self.path).await?;
-        let mut entries = Vec::new();
+        let verified = self.verify(&content)?;
+        if let Some((line, _)) = verified.corrupt.first() {
+            return Err(IndexerError::Serialization(format!(
+                "Corrupt experience log line {}",
+                line
+            )));
+        }
 
-        for line in content.lines() {
-            if line.trim().is_empty() {
-                continue;
-            }
+        let mut entries = Vec::new();
+        for line in &verified.valid {
             let entry: ExperienceEntry = serde_json::from_str(line)
                 .map_err(|e| IndexerError::Serialization(e.to_string()))?;
             entries.push(entry);
@@ -109,17 +182,37 @@ impl ExperienceLog {
         Ok(entries)
     }
 
-    /// Get the number of entries in the log.
+    /// Get the number of verified entries in the log.
     pub async fn count(&self) -> Result<usize, IndexerError> {
         if !self.path.exists() {
             return Ok(0);
         }
 
         let content = tokio::fs::read_to_string(&self.path).await?;
-        Ok(content.lines().filter(|l| !l.trim().is_empty()).count())
+        Ok(self.verify(&content)?.valid.len())
+    }
+
+    /// Line numbers (1-based) of the corrupt suffix of the log.
+    ///
+    /// Readers ignore these lines; they usually come from an interrupted
+    /// write.
+    pub async fn invalid_lines(&self) -> Result<Vec<usize>, IndexerError> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = tokio::fs::read_to_string(&self.path).await?;
+        Ok(self
+            .verify(&content)?
+            .corrupt
+            .into_iter()
+            .map(|(line, _)| line)
+            .collect())
     }
 
     /// Read recent entries from the log (generic deserialization).
+    ///
+    /// Only lines before the corrupt suffix are read.
     pub async fn read_recent<E: serde::de::DeserializeOwned>(
         &self,
         limit: usize,
@@ -133,29 +226,229 @@ impl ExperienceLog {
         }
 
         let content = tokio::fs::read_to_string(&self.path).await?;
-        let lines: Vec<&str> = content.lines().filter(|l| !l.trim().is_empty()).collect();
-
-        // Walk backwards so we can return "last N valid entries"
-        // even when recent lines include unrelated schemas.
-        let mut entries_rev = Vec::new();
-        for line in lines.iter().rev() {
-            match serde_json::from_str(line) {
-                Ok(entry) => {
-                    entries_rev.push(entry);
-                    if entries_rev.len() >= limit {
-                        break;
-                    }
-                }
-                Err(e) => {
-                    debug!(error = %e, "Skipping malformed experience entry");
-                }
+        let verified = self.verify(&content)?;
+        if !verified.corrupt.is_empty() {
+            debug!(
+                path = ?self.path,
+                lines = verified.corrupt.len(),
+                "Ignoring corrupt suffix of experience log"
+            );
+        }
+
+        Ok(parse_recent(&verified.valid, limit))
+    }
+
+    /// Read every entry of type `E`, first moving a corrupt suffix to
+    /// [`quarantine_path`](Self::quarantine_path).
+    pub async fn replay<E: serde::de::DeserializeOwned>(
+        &self,
+    ) -> Result<LogReplay<E>, IndexerError> {
+        if !self.path.exists() {
+            return Ok(LogReplay {
+                entries: Vec::new(),
+                quarantined: 0,
+            });
+        }
+
+        let mut content = tokio::fs::read_to_string(&self.path).await?;
+        let mut quarantined = 0;
+        if !self.verify(&content)?.corrupt.is_empty() {
+            quarantined = self.rewrite_inner(keep_all).await?.2;
+            content = tokio::fs::read_to_string(&self.path).await?;
+        }
+
+        Ok(LogReplay {
+            entries: parse_recent(&self.verify(&content)?.valid, usize::MAX),
+            quarantined,
+        })
+    }
+
+    /// Size of the log in bytes (0 when it does not exist).
+    pub async fn size(&self) -> u64 {
+        tokio::fs::metadata(&self.path)
+            .await
+            .map(|meta| meta.len())
+            .unwrap_or(0)
+    }
+
+    /// Rewrite the log with the lines returned by `compact`.
+    ///
+    /// `compact` receives every verified line without its checksum, oldest
+    /// first; the lines it returns are sealed again. A corrupt suffix is
+    /// moved to [`quarantine_path`](Self::quarantine_path). The new log is
+    /// written to a temporary file and renamed over the old one. Lines
+    /// appended by other writers while `compact` ran are carried over.
+    /// Returns the number of lines before and after.
+    pub async fn rewrite<F>(&self, compact: F) -> Result<(usize, usize), IndexerError>
+    where
+        F: FnOnce(Vec<&str>) -> Vec<String>,
+    {
+        let (before, after, _) = self.rewrite_inner(compact).await?;
+        Ok((before, after))
+    }
+
+    /// [`rewrite`](Self::rewrite), also returning the number of lines
+    /// quarantined.
+    async fn rewrite_inner<F>(&self, compact: F) -> Result<(usize, usize, usize), IndexerError>
+    where
+        F: FnOnce(Vec<&str>) -> Vec<String>,
+    {
+        if !self.path.exists() {
+            return Ok((0, 0, 0));
+        }
+
+        let content = tokio::fs::read_to_string(&self.path).await?;
+        let verified = self.verify(&content)?;
+        let before = verified.valid.len() + verified.corrupt.len();
+        let kept = compact(verified.valid.iter().map(String::as_str).collect());
+        let after = kept.len();
+
+        if !verified.corrupt.is_empty() {
+            let mut quarantine = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(self.quarantine_path())
+                .await?;
+            for (_, line) in &verified.corrupt {
+                quarantine
+                    .write_all(self.encode_quarantined(line).as_bytes())
+                    .await?;
+                quarantine.write_all(b"\n").await?;
             }
+            quarantine.sync_all().await?;
+            warn!(
+                path = ?self.path,
+                first_line = verified.corrupt[0].0,
+                lines = verified.corrupt.len(),
+                "Moved corrupt suffix of experience log aside"
+            );
         }
 
-        entries_rev.reverse();
-        let entries = entries_rev;
+        let mut output = String::with_capacity(content.len());
+        for line in &kept {
+            output.push_str(&self.encode(line));
+            output.push('\n');
+        }
 
-        Ok(entries)
+        let current = tokio::fs::read_to_string(&self.path).await?;
+        let appended = current.get(content.len()..).unwrap_or_default();
+        output.push_str(appended);
+
+        replace(&self.path, &self.path.with_extension("jsonl.tmp"), &output).await?;
+
+        debug!(path = ?self.path, before, after, "Rewrote experience log");
+
+        Ok((before, after, verified.corrupt.len()))
+    }
+
+    /// Repair the log after an interrupted write.
+    ///
+    /// A torn last line would otherwise swallow the next append. A valid but
+    /// unterminated last entry is kept and terminated; a corrupt suffix is
+    /// moved to [`quarantine_path`](Self::quarantine_path) for inspection.
+    pub async fn repair(&self) -> Result<LogRepair, IndexerError> {
+        if !self.path.exists() {
+            return Ok(LogRepair::default());
+        }
+
+        let content = tokio::fs::read_to_string(&self.path).await?;
+        let unterminated = !content.is_empty() && !content.ends_with('\n');
+        let verified = self.verify(&content)?;
+        let mut repair = LogRepair {
+            terminated: unterminated && verified.corrupt.is_empty(),
+            quarantined: 0,
+        };
+
+        if !verified.corrupt.is_empty() {
+            repair.quarantined = self.rewrite_inner(keep_all).await?.2;
+        } else if unterminated {
+            let mut file = OpenOptions::new().append(true).open(&self.path).await?;
+            file.write_all(b"\n").await?;
+            file.sync_all().await?;
+        }
+
+        if !repair.is_empty() {
+            debug!(path = ?self.path, ?repair, "Repaired experience log");
+        }
+        Ok(repair)
+    }
+
+    /// Rewrite the log and its quarantined lines so every line is
+    /// encrypted, returning the number of plaintext lines encrypted.
+    pub async fn encrypt(&self) -> Result<usize, IndexerError> {
+        let Some(cipher) = &self.cipher else {
+            return Err(IndexerError::Storage(
+                "No experience log key is configured".to_string(),
+            ));
+        };
+
+        let mut plaintext = 0;
+        if self.path.exists() {
+            let content = tokio::fs::read_to_string(&self.path).await?;
+            plaintext = content
+                .lines()
+                .filter(|line| !line.trim().is_empty() && !is_encrypted(line))
+                .count();
+            if plaintext > 0 {
+                self.rewrite_inner(keep_all).await?;
+                debug!(path = ?self.path, lines = plaintext, "Encrypted experience log");
+            }
+        }
+        Ok(plaintext + self.encrypt_quarantine(cipher).await?)
+    }
+
+    /// Encrypt the plaintext lines of the quarantine file, returning how
+    /// many there were.
+    async fn encrypt_quarantine(&self, cipher: &LogCipher) -> Result<usize, IndexerError> {
+        let path = self.quarantine_path();
+        let content = match tokio::fs::read_to_string(&path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut plaintext = 0;
+        let mut output = String::with_capacity(content.len());
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            if is_encrypted(line) {
+                output.push_str(line);
+            } else {
+                output.push_str(&cipher.encrypt(line));
+                plaintext += 1;
+            }
+            output.push('\n');
+        }
+        if plaintext > 0 {
+            replace(
+                &path,
+                &self.path.with_extension("jsonl.corrupt.tmp"),
+                &output,
+            )
+            .await?;
+            debug!(path = ?path, lines = plaintext, "Encrypted quarantined experience lines");
+        }
+        Ok(plaintext)
+    }
+
+    /// `json` as a line of the log, without its newline.
+    fn encode(&self, json: &str) -> String {
+        match &self.cipher {
+            Some(cipher) => cipher.encrypt(json.trim_end()),
+            None => seal(json),
+        }
+    }
+
+    /// A corrupt line as moved to the quarantine file: encrypted if a key
+    /// is configured and it is not already.
+    fn encode_quarantined(&self, line: &str) -> String {
+        match &self.cipher {
+            Some(cipher) if !is_encrypted(line) => cipher.encrypt(line),
+            _ => line.to_string(),
+        }
+    }
+
+    fn verify<'a>(&self, content: &'a str) -> Result<Verified<'a>, IndexerError> {
+        verify(content, self.cipher.as_deref())
     }
 
     /// Check if the log needs rotation.
@@ -195,6 +488,127 @@ impl ExperienceLog {
     }
 }
 
+/// Replace `path` with `content`, written to `temp_path` first.
+async fn replace(path: &Path, temp_path: &Path, content: &str) -> Result<(), IndexerError> {
+    let mut file = tokio::fs::File::create(temp_path).await?;
+    file.write_all(content.as_bytes()).await?;
+    file.sync_all().await?;
+    tokio::fs::rename(temp_path, path).await?;
+    Ok(())
+}
+
+/// Lines of a log, split at the first corrupt one.
+struct Verified<'a> {
+    /// Lines before it, without their checksums
+    valid: Vec<String>,
+    /// It and every non-empty line after it, with 1-based line numbers
+    corrupt: Vec<(usize, &'a str)>,
+}
+
+/// Split `content` at its first corrupt line, decrypting lines with
+/// `cipher`. Fails on a line encrypted with another key, or with none
+/// configured.
+fn verify<'a>(content: &'a str, cipher: Option<&LogCipher>) -> Result<Verified<'a>, IndexerError> {
+    let mut verified = Verified {
+        valid: Vec::new(),
+        corrupt: Vec::new(),
+    };
+    for (idx, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let payload = if !verified.corrupt.is_empty() {
+            None
+        } else if is_encrypted(line) {
+            match cipher {
+                Some(cipher) => cipher.decrypt(line)?,
+                None => return Err(other_key(line_key_id(line))),
+            }
+        } else {
+            unseal(line)
+        };
+        match payload {
+            Some(payload) => verified.valid.push(payload),
+            None => verified.corrupt.push((idx + 1, line)),
+        }
+    }
+    Ok(verified)
+}
+
+/// `json` with its checksum appended; JSON other than an object is kept
+/// as-is.
+fn seal(json: &str) -> String {
+    let json = json.trim_end();
+    let Some(body) = json.strip_suffix('}') else {
+        return json.to_string();
+    };
+    let separator = if body.trim_end().ends_with('{') {
+        ""
+    } else {
+        ","
+    };
+    format!(
+        "{}{}{}{:08x}\"}}",
+        body,
+        separator,
+        CHECKSUM_FIELD,
+        crc32fast::hash(json.as_bytes())
+    )
+}
+
+/// A line without its checksum, or `None` if it is corrupt.
+///
+/// Lines without a checksum are accepted if they parse.
+fn unseal(line: &str) -> Option<String> {
+    let suffix = line
+        .len()
+        .checked_sub(CHECKSUM_SUFFIX_LEN)
+        .and_then(|start| Some((line.get(..start)?, line.get(start..)?)));
+    if let Some((body, suffix)) = suffix {
+        if let Some(checksum) = suffix
+            .strip_prefix(CHECKSUM_FIELD)
+            .and_then(|rest| rest.strip_suffix("\"}"))
+        {
+            let body = body.strip_suffix(',').unwrap_or(body);
+            let payload = format!("{}}}", body);
+            let expected = u32::from_str_radix(checksum, 16).ok()?;
+            return (crc32fast::hash(payload.as_bytes()) == expected).then_some(payload);
+        }
+    }
+
+    serde_json::from_str::<serde::de::IgnoredAny>(line)
+        .is_ok()
+        .then(|| line.to_string())
+}
+
+/// Rewrite that keeps every verified line.
+fn keep_all(lines: Vec<&str>) -> Vec<String> {
+    lines.into_iter().map(str::to_string).collect()
+}
+
+/// The last `limit` lines that parse as `E`, oldest first.
+fn parse_recent<E: serde::de::DeserializeOwned>(lines: &[String], limit: usize) -> Vec<E> {
+    // Walk backwards so we can return "last N valid entries"
+    // even when recent lines include unrelated schemas.
+    let mut entries_rev = Vec::new();
+    for line in lines.iter().rev() {
+        match serde_json::from_str(line) {
+            Ok(entry) => {
+                entries_rev.push(entry);
+                if entries_rev.len() >= limit {
+                    break;
+                }
+            }
+            Err(e) => {
+                debug!(error = %e, "Skipping experience entry of another schema");
+            }
+        }
+    }
+
+    entries_rev.reverse();
+    entries_rev
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -282,7 +696,123 @@ mod tests {
             .collect();
 
         // Should have original file and at least one rotated file
-        assert!(entries.len() >= 1);
+        assert!(!entries.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rewrite() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("experience.jsonl");
+        let log = ExperienceLog::new(path.clone(), 1024 * 1024);
+
+        for id in ["a", "b", "a"] {
+            log.append_raw(&format!(r#"{{"id":"{}"}}"#, id))
+                .await
+                .unwrap();
+        }
+
+        let counts = log
+            .rewrite(|lines| lines.into_iter().skip(1).map(str::to_string).collect())
+            .await
+            .unwrap();
+        assert_eq!(counts, (3, 2));
+
+        let content = tokio::fs::read_to_string(&path).await.unwrap();
+        assert_eq!(
+            content,
+            format!("{}\n{}\n", seal(r#"{"id":"b"}"#), seal(r#"{"id":"a"}"#))
+        );
+        assert_eq!(log.size().await, content.len() as u64);
+    }
+
+    #[tokio::test]
+    async fn test_repair_torn_lines() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("experience.jsonl");
+        let log = ExperienceLog::new(path.clone(), 1024 * 1024);
+
+        // A valid entry cut off before its newline is kept
+        std::fs::write(&path, "{\"a\":1}\n{\"b\":2}").unwrap();
+        let repair = log.repair().await.unwrap();
+        assert!(repair.terminated);
+        assert_eq!(repair.quarantined, 0);
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "{\"a\":1}\n{\"b\":2}\n"
+        );
+
+        // A torn entry is moved aside
+        std::fs::write(&path, "{\"a\":1}\n{\"b\":").unwrap();
+        let repair = log.repair().await.unwrap();
+        assert!(!repair.terminated);
+        assert_eq!(repair.quarantined, 1);
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            format!("{}\n", seal("{\"a\":1}"))
+        );
+        let corrupt = std::fs::read_to_string(log.quarantine_path()).unwrap();
+        assert_eq!(corrupt, "{\"b\":\n");
+
+        assert!(log.repair().await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_seal_roundtrip() {
+        for json in [r#"{"id":"a","n":1}"#, "{}", r#"{"nested":{"x":[1,2]}}"#] {
+            let sealed = seal(json);
+            assert!(sealed.contains("\"crc32\":\""));
+            serde_json::from_str::<serde_json::Value>(&sealed).unwrap();
+            assert_eq!(unseal(&sealed).as_deref(), Some(json));
+        }
+
+        // Not an object: written as-is
+        assert_eq!(seal("[1,2]"), "[1,2]");
+        // Legacy lines are accepted if they parse
+        assert_eq!(unseal(r#"{"id":"a"}"#).as_deref(), Some(r#"{"id":"a"}"#));
+        assert!(unseal(r#"{"id":"#).is_none());
+
+        let tampered = seal(r#"{"id":"a"}"#).replace(r#""a""#, r#""b""#);
+        assert!(unseal(&tampered).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_corrupt_suffix_is_quarantined() {
+        #[derive(Debug, serde::Deserialize)]
+        struct SimpleEntry {
+            id: String,
+        }
+
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("experience.jsonl");
+        let log = ExperienceLog::new(path.clone(), 1024 * 1024);
+        for id in ["a", "b", "c"] {
+            log.append_raw(&format!(r#"{{"id":"{}"}}"#, id))
+                .await
+                .unwrap();
+        }
+
+        // Flip a byte of the second entry; the third follows it into the
+        // corrupt suffix
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, content.replacen(r#""b""#, r#""x""#, 1)).unwrap();
+        assert_eq!(log.invalid_lines().await.unwrap(), vec![2, 3]);
+        assert_eq!(log.count().await.unwrap(), 1);
+        let recent: Vec<SimpleEntry> = log.read_recent(10).await.unwrap();
+        assert_eq!(recent.len(), 1);
+        assert!(log.read_all().await.is_err());
+
+        let replay: LogReplay<SimpleEntry> = log.replay().await.unwrap();
+        assert_eq!(replay.quarantined, 2);
+        assert_eq!(replay.entries.len(), 1);
+        assert_eq!(replay.entries[0].id, "a");
+        let corrupt = std::fs::read_to_string(log.quarantine_path()).unwrap();
+        assert_eq!(corrupt.lines().count(), 2);
+        assert!(corrupt.contains(r#""x""#));
+
+        // Nothing left to quarantine
+        let replay: LogReplay<SimpleEntry> = log.replay().await.unwrap();
+        assert_eq!(replay.quarantined, 0);
+        assert!(log.invalid_lines().await.unwrap().is_empty());
     }
 
     #[tokio::test]
@@ -336,6 +866,70 @@ mod tests {
         assert_eq!(entries[1].id, "old-2");
     }
 
+    #[tokio::test]
+    async fn test_encrypted_log() {
+        #[derive(Debug, serde::Deserialize)]
+        struct SimpleEntry {
+            id: String,
+        }
+
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("experience.jsonl");
+        let cipher = Arc::new(LogCipher::from_hex(&LogCipher::generate_key()).unwrap());
+        let plain = ExperienceLog::new(path.clone(), 1024 * 1024);
+        let log = ExperienceLog::new(path.clone(), 1024 * 1024).with_cipher(cipher.clone());
+
+        // Plaintext lines written before a key was configured stay readable
+        plain.append_raw(r#"{"id":"a"}"#).await.unwrap();
+        log.append_raw(r#"{"id":"b"}"#).await.unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.contains(r#""a""#));
+        assert!(!content.contains(r#""b""#));
+        let entries: Vec<SimpleEntry> = log.read_recent(10).await.unwrap();
+        let ids: Vec<_> = entries.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "b"]);
+
+        // and are encrypted by the migration
+        assert_eq!(log.encrypt().await.unwrap(), 1);
+        assert_eq!(log.encrypt().await.unwrap(), 0);
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.lines().all(is_encrypted));
+        assert_eq!(log.count().await.unwrap(), 2);
+        assert!(plain.encrypt().await.is_err());
+
+        // Without the key, or with another, the log fails to read rather
+        // than being moved aside as corrupt
+        assert!(plain.read_recent::<SimpleEntry>(10).await.is_err());
+        let other = ExperienceLog::new(path.clone(), 1024 * 1024).with_cipher(Arc::new(
+            LogCipher::from_hex(&LogCipher::generate_key()).unwrap(),
+        ));
+        assert!(other.replay::<SimpleEntry>().await.is_err());
+        assert!(!log.quarantine_path().exists());
+
+        // A torn encrypted line is corrupt like a plaintext one
+        let torn = &cipher.encrypt(r#"{"id":"c"}"#)[..30];
+        std::fs::write(&path, format!("{}{}", content, torn)).unwrap();
+        let replay: LogReplay<SimpleEntry> = log.replay().await.unwrap();
+        assert_eq!(replay.entries.len(), 2);
+        assert_eq!(replay.quarantined, 1);
+
+        // Quarantined lines are not left in plaintext, whether moved aside
+        // with the key configured or before it was
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, format!("{}{{\"id\":\"secret", content)).unwrap();
+        assert_eq!(log.repair().await.unwrap().quarantined, 1);
+        let quarantine = log.quarantine_path();
+        let mut lines = std::fs::read_to_string(&quarantine).unwrap();
+        assert!(lines.lines().all(is_encrypted));
+        lines.push_str("{\"id\":\"secret plan\n");
+        std::fs::write(&quarantine, lines).unwrap();
+        assert_eq!(log.encrypt().await.unwrap(), 1);
+        let lines = std::fs::read_to_string(&quarantine).unwrap();
+        assert_eq!(lines.lines().count(), 3);
+        assert!(lines.lines().all(is_encrypted));
+        assert!(!lines.contains("secret"));
+    }
+
     #[test]
     fn test_entry_serialization() {
         let entry = test_entry();
@@ -0,0 +1,99 @@
+//! Stable project identity.
+//!
+//! Stored data is keyed by a hash of the project path unless the project
+//! root holds `.engram/project.toml` with a project id, in which case it is
+//! keyed by the id. A checkout that is moved or reached through a symlink
+//! keeps its id, and so finds the same stored data.
+//!
+//! ```toml
+//! id = "5f0c6a8e-3b7d-4e21-9a43-0d2f6f1c9b7e"
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Location of the project file relative to the project root
+pub const PROJECT_ID_PATH: &str = ".engram/project.toml";
+
+/// Contents of `.engram/project.toml`
+#[derive(Debug, Serialize, Deserialize)]
+struct ProjectFile {
+    /// Stable project id
+    id: String,
+}
+
+/// Path of the project file for a project root
+pub fn project_id_path(root: &Path) -> PathBuf {
+    root.join(PROJECT_ID_PATH)
+}
+
+/// Read the id of the project rooted at `root`, if it has one.
+///
+/// An unreadable project file is logged and treated as absent, so the
+/// project falls back to its path identity.
+pub fn read_project_id(root: &Path) -> Option<String> {
+    let path = project_id_path(root);
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+        Err(e) => {
+            warn!(path = ?path, error = %e, "Failed to read project file");
+            return None;
+        }
+    };
+    match toml::from_str::<ProjectFile>(&content) {
+        Ok(file) if !file.id.trim().is_empty() => Some(file.id.trim().to_string()),
+        Ok(_) => None,
+        Err(e) => {
+            warn!(path = ?path, error = %e, "Invalid project file");
+            None
+        }
+    }
+}
+
+/// Write `id` as the id of the project rooted at `root`.
+pub fn write_project_id(root: &Path, id: &str) -> std::io::Result<()> {
+    let path = project_id_path(root);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let content = toml::to_string(&ProjectFile { id: id.to_string() })
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    std::fs::write(path, content)
+}
+
+/// Key stored data of the project rooted at `root` is hashed from: its id
+/// if it has one, else its path.
+pub fn project_key(root: &Path) -> String {
+    match read_project_id(root) {
+        Some(id) => id_key(&id),
+        None => root.to_string_lossy().into_owned(),
+    }
+}
+
+/// Key of a project id; never equal to an absolute path.
+pub fn id_key(id: &str) -> String {
+    format!("id:{}", id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_project_id_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        assert_eq!(read_project_id(root), None);
+        assert_eq!(project_key(root), root.to_string_lossy());
+
+        write_project_id(root, "abc-123").unwrap();
+        assert_eq!(read_project_id(root).as_deref(), Some("abc-123"));
+        assert_eq!(project_key(root), "id:abc-123");
+
+        std::fs::write(project_id_path(root), "id = [").unwrap();
+        assert_eq!(read_project_id(root), None);
+    }
+}
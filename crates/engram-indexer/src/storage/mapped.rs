@@ -0,0 +1,477 @@
+//! Memory-mapped tree format.
+//!
+//! Layout (all integers little-endian):
+//!
+//! ```text
+//! magic        8 bytes   "ENGRTREE"
+//! version      u32
+//! node_count   u32
+//! header_len   u64
+//! header       MessagePack tree metadata (everything but the nodes)
+//! offset table node_count x 32-byte entries, sorted by node id:
+//!              id u64 | node offset u64 | node len u32 | content offset u64 | content len u32
+//! records      MessagePack nodes (without content) and node contents
+//! ```
+//!
+//! Opening a mapped tree only decodes the header; nodes and their content
+//! (summaries, symbols) are decoded on access, so resident memory stays
+//! proportional to what is actually read. Files are replaced by rename, so
+//! an open map keeps reading the version it was opened on.
+
+use crate::git::GitState;
+use crate::scanner::Framework;
+use crate::tree::{
+    ContentHandle, ContentSource, DependencyGraph, Node, NodeContent, NodeId, TestLinks, Tree,
+};
+use crate::{IndexerError, Language};
+use chrono::{DateTime, Utc};
+use memmap2::Mmap;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// File magic
+const MAGIC: &[u8; 8] = b"ENGRTREE";
+
+/// Format version, bumped on layout changes
+const FORMAT_VERSION: u32 = 1;
+
+/// Size of the fixed preamble before the header
+const PREAMBLE_LEN: usize = 24;
+
+/// Size of one offset table entry
+const ENTRY_LEN: usize = 32;
+
+/// Tree metadata stored in the header
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Header {
+    version: u32,
+    root_path: PathBuf,
+    root_id: NodeId,
+    dependencies: DependencyGraph,
+    languages: Vec<Language>,
+    frameworks: Vec<Framework>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    file_count: usize,
+    symbol_count: usize,
+    #[serde(default)]
+    git: Option<GitState>,
+    #[serde(default)]
+    scanned_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    tests: TestLinks,
+}
+
+/// Location of one node's records
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    id: NodeId,
+    node: (u64, u32),
+    content: (u64, u32),
+}
+
+/// A tree backed by a memory-mapped file.
+pub struct MappedTree {
+    mmap: Mmap,
+    header: Header,
+    node_count: usize,
+    table_offset: usize,
+}
+
+impl MappedTree {
+    /// Write a tree in the mapped format.
+    ///
+    /// The file is written next to `path` and renamed into place.
+    pub fn write(tree: &Tree, path: &Path) -> Result<(), IndexerError> {
+        if tree.is_lazy() {
+            return Self::write(&tree.materialized(), path);
+        }
+        let header = rmp_serde::to_vec(&Header {
+            version: tree.version,
+            root_path: tree.root_path.clone(),
+            root_id: tree.root_id,
+            dependencies: tree.dependencies.clone(),
+            languages: tree.languages.clone(),
+            frameworks: tree.frameworks.clone(),
+            created_at: tree.created_at,
+            updated_at: tree.updated_at,
+            file_count: tree.file_count,
+            symbol_count: tree.symbol_count,
+            git: tree.git.clone(),
+            scanned_at: tree.scanned_at,
+            tests: tree.tests.clone(),
+        })?;
+
+        let mut ids: Vec<NodeId> = tree.nodes.keys().copied().collect();
+        ids.sort_unstable();
+
+        let records_offset = PREAMBLE_LEN + header.len() + ids.len() * ENTRY_LEN;
+        let mut table = Vec::with_capacity(ids.len() * ENTRY_LEN);
+        let mut records = Vec::new();
+        for id in &ids {
+            let node = &tree.nodes[id];
+            let node_record = rmp_serde::to_vec(&Node {
+                content: None,
+                ..node.clone()
+            })?;
+            let content_record = match &node.content {
+                Some(content) => rmp_serde::to_vec(content)?,
+                None => Vec::new(),
+            };
+
+            let node_offset = (records_offset + records.len()) as u64;
+            records.extend_from_slice(&node_record);
+            let content_offset = (records_offset + records.len()) as u64;
+            records.extend_from_slice(&content_record);
+
+            table.extend_from_slice(&id.to_le_bytes());
+            table.extend_from_slice(&node_offset.to_le_bytes());
+            table.extend_from_slice(&(node_record.len() as u32).to_le_bytes());
+            table.extend_from_slice(&content_offset.to_le_bytes());
+            table.extend_from_slice(&(content_record.len() as u32).to_le_bytes());
+        }
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // Concurrent writers of the same tree each rename their own file
+        static WRITES: AtomicU64 = AtomicU64::new(0);
+        let temp_path = path.with_extension(format!(
+            "mmap.{}.{}.tmp",
+            std::process::id(),
+            WRITES.fetch_add(1, Ordering::Relaxed)
+        ));
+        let mut file = File::create(&temp_path)?;
+        file.write_all(MAGIC)?;
+        file.write_all(&FORMAT_VERSION.to_le_bytes())?;
+        file.write_all(&(ids.len() as u32).to_le_bytes())?;
+        file.write_all(&(header.len() as u64).to_le_bytes())?;
+        file.write_all(&header)?;
+        file.write_all(&table)?;
+        file.write_all(&records)?;
+        file.sync_all()?;
+        std::fs::rename(&temp_path, path)?;
+
+        Ok(())
+    }
+
+    /// Map a tree file, decoding only its header.
+    pub fn open(path: &Path) -> Result<Self, IndexerError> {
+        if !path.exists() {
+            return Err(IndexerError::NotFound(path.to_path_buf()));
+        }
+        let file = File::open(path)?;
+        // SAFETY: tree files are only ever replaced by rename, never
+        // modified in place, so the mapped bytes do not change under us.
+        let mmap = unsafe { Mmap::map(&file)? };
+
+        if mmap.len() < PREAMBLE_LEN || &mmap[..8] != MAGIC {
+            return Err(corrupt("bad magic"));
+        }
+        let version = u32::from_le_bytes(mmap[8..12].try_into().unwrap());
+        if version != FORMAT_VERSION {
+            return Err(corrupt(&format!("unsupported format version {}", version)));
+        }
+        let node_count = u32::from_le_bytes(mmap[12..16].try_into().unwrap()) as usize;
+        let header_len = u64::from_le_bytes(mmap[16..24].try_into().unwrap()) as usize;
+
+        let table_offset = PREAMBLE_LEN
+            .checked_add(header_len)
+            .ok_or_else(|| corrupt("header length"))?;
+        let table_end = node_count
+            .checked_mul(ENTRY_LEN)
+            .and_then(|len| len.checked_add(table_offset))
+            .ok_or_else(|| corrupt("node count"))?;
+        if table_end > mmap.len() {
+            return Err(corrupt("truncated offset table"));
+        }
+        let header: Header = rmp_serde::from_slice(&mmap[PREAMBLE_LEN..table_offset])?;
+
+        Ok(Self {
+            mmap,
+            header,
+            node_count,
+            table_offset,
+        })
+    }
+
+    /// Number of nodes.
+    pub fn len(&self) -> usize {
+        self.node_count
+    }
+
+    /// Whether the tree has no nodes.
+    pub fn is_empty(&self) -> bool {
+        self.node_count == 0
+    }
+
+    /// Project root path.
+    pub fn root_path(&self) -> &Path {
+        &self.header.root_path
+    }
+
+    /// Root node ID.
+    pub fn root_id(&self) -> NodeId {
+        self.header.root_id
+    }
+
+    /// Total file count.
+    pub fn file_count(&self) -> usize {
+        self.header.file_count
+    }
+
+    /// Total symbol count.
+    pub fn symbol_count(&self) -> usize {
+        self.header.symbol_count
+    }
+
+    /// Dependency graph.
+    pub fn dependencies(&self) -> &DependencyGraph {
+        &self.header.dependencies
+    }
+
+    /// All node IDs, ascending.
+    pub fn node_ids(&self) -> impl Iterator<Item = NodeId> + '_ {
+        (0..self.node_count).map(|index| self.entry(index).id)
+    }
+
+    /// Decode a node without its content.
+    pub fn node(&self, id: NodeId) -> Result<Option<Node>, IndexerError> {
+        let Some(entry) = self.find(id) else {
+            return Ok(None);
+        };
+        Ok(Some(rmp_serde::from_slice(self.record(entry.node)?)?))
+    }
+
+    /// Decode a node's content (summary, tags, symbols), if it has any.
+    pub fn content(&self, id: NodeId) -> Result<Option<NodeContent>, IndexerError> {
+        let Some(entry) = self.find(id) else {
+            return Ok(None);
+        };
+        if entry.content.1 == 0 {
+            return Ok(None);
+        }
+        Ok(Some(rmp_serde::from_slice(self.record(entry.content)?)?))
+    }
+
+    /// Decode the whole tree into memory.
+    pub fn to_tree(&self) -> Result<Tree, IndexerError> {
+        self.decode(true)
+    }
+
+    /// Decode the tree's structure, loading node content on first access
+    /// and keeping up to `cache_capacity` contents decoded.
+    pub fn into_lazy_tree(self: Arc<Self>, cache_capacity: usize) -> Result<Tree, IndexerError> {
+        let tree = self.decode(false)?;
+        Ok(tree.with_content_handle(Arc::new(ContentHandle::new(self, cache_capacity))))
+    }
+
+    fn decode(&self, with_content: bool) -> Result<Tree, IndexerError> {
+        let mut nodes = HashMap::with_capacity(self.node_count);
+        for index in 0..self.node_count {
+            let entry = self.entry(index);
+            let mut node: Node = rmp_serde::from_slice(self.record(entry.node)?)?;
+            if with_content && entry.content.1 > 0 {
+                node.content = Some(rmp_serde::from_slice(self.record(entry.content)?)?);
+            }
+            nodes.insert(entry.id, node);
+        }
+
+        let header = self.header.clone();
+        Ok(Tree {
+            version: header.version,
+            root_path: header.root_path,
+            nodes,
+            root_id: header.root_id,
+            dependencies: header.dependencies,
+            tests: header.tests,
+            languages: header.languages,
+            frameworks: header.frameworks,
+            created_at: header.created_at,
+            updated_at: header.updated_at,
+            file_count: header.file_count,
+            symbol_count: header.symbol_count,
+            git: header.git,
+            scanned_at: header.scanned_at,
+            content_handle: None,
+        })
+    }
+
+    /// Offset table entry at `index` (bounds checked in `open`).
+    fn entry(&self, index: usize) -> Entry {
+        let start = self.table_offset + index * ENTRY_LEN;
+        let bytes = &self.mmap[start..start + ENTRY_LEN];
+        let u64_at = |at: usize| u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap());
+        let u32_at = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+        Entry {
+            id: u64_at(0),
+            node: (u64_at(8), u32_at(16)),
+            content: (u64_at(20), u32_at(28)),
+        }
+    }
+
+    /// Binary search the offset table.
+    fn find(&self, id: NodeId) -> Option<Entry> {
+        let (mut low, mut high) = (0, self.node_count);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let entry = self.entry(mid);
+            match entry.id.cmp(&id) {
+                std::cmp::Ordering::Equal => return Some(entry),
+                std::cmp::Ordering::Less => low = mid + 1,
+                std::cmp::Ordering::Greater => high = mid,
+            }
+        }
+        None
+    }
+
+    fn record(&self, (offset, len): (u64, u32)) -> Result<&[u8], IndexerError> {
+        let start = offset as usize;
+        start
+            .checked_add(len as usize)
+            .and_then(|end| self.mmap.get(start..end))
+            .ok_or_else(|| corrupt("record out of bounds"))
+    }
+}
+
+impl ContentSource for MappedTree {
+    fn load(&self, id: NodeId) -> Result<Option<NodeContent>, IndexerError> {
+        self.content(id)
+    }
+}
+
+fn corrupt(message: &str) -> IndexerError {
+    IndexerError::Storage(format!("corrupt mapped tree: {}", message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::{ScanResult, ScannedFile, Symbol, SymbolKind};
+    use crate::tree::TreeBuilder;
+    use tempfile::tempdir;
+
+    fn test_tree() -> Tree {
+        let mut tree = TreeBuilder::new().build(&ScanResult {
+            root: PathBuf::from("/project"),
+            files: vec![ScannedFile {
+                path: PathBuf::from("src/lib.rs"),
+                language: Some(Language::Rust),
+                size: 10,
+                hash: "a".to_string(),
+                line_count: 3,
+                symbols: vec![Symbol {
+                    name: "run".to_string(),
+                    kind: SymbolKind::Function,
+                    start_line: 1,
+                    end_line: 3,
+                    parent: None,
+                    doc: None,
+                }],
+                imports: vec![],
+                truncated: true,
+                asset: None,
+            }],
+            languages: vec![Language::Rust],
+            frameworks: vec![],
+            duration_ms: 0,
+            skipped_count: 0,
+            excluded_dirs: vec![],
+        });
+        let file_id = tree
+            .find_node_by_path(&PathBuf::from("src/lib.rs"))
+            .unwrap();
+        tree.get_mut(file_id).unwrap().content = Some(NodeContent {
+            summary: Some("Entry point".to_string()),
+            ..Default::default()
+        });
+        tree
+    }
+
+    #[test]
+    fn test_mapped_roundtrip() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("tree.mmap");
+        let tree = test_tree();
+        MappedTree::write(&tree, &path).unwrap();
+
+        let mapped = MappedTree::open(&path).unwrap();
+        assert_eq!(mapped.len(), tree.nodes.len());
+        assert_eq!(mapped.file_count(), 1);
+        assert_eq!(mapped.root_path(), Path::new("/project"));
+
+        let file_id = tree
+            .find_node_by_path(&PathBuf::from("src/lib.rs"))
+            .unwrap();
+        let node = mapped.node(file_id).unwrap().unwrap();
+        assert_eq!(node.name, "lib.rs");
+        assert!(node.is_truncated());
+        assert!(node.content.is_none());
+        let content = mapped.content(file_id).unwrap().unwrap();
+        assert_eq!(content.summary.as_deref(), Some("Entry point"));
+        assert!(mapped.content(tree.root_id).unwrap().is_none());
+        assert!(mapped.node(9999).unwrap().is_none());
+
+        let loaded = mapped.to_tree().unwrap();
+        assert_eq!(loaded.nodes.len(), tree.nodes.len());
+        assert!(loaded.find_node_by_name("run").is_some());
+        assert_eq!(loaded.generation(), tree.generation());
+    }
+
+    #[test]
+    fn test_lazy_tree() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("tree.mmap");
+        let tree = test_tree();
+        MappedTree::write(&tree, &path).unwrap();
+        let file_id = tree
+            .find_node_by_path(&PathBuf::from("src/lib.rs"))
+            .unwrap();
+
+        let mut lazy = Arc::new(MappedTree::open(&path).unwrap())
+            .into_lazy_tree(8)
+            .unwrap();
+        assert!(lazy.is_lazy());
+        assert!(lazy.nodes.values().all(|node| node.content.is_none()));
+        let content = lazy.content(file_id).unwrap();
+        assert_eq!(content.summary.as_deref(), Some("Entry point"));
+        assert!(lazy.content(tree.root_id).is_none());
+
+        // Materializing keeps lazily loaded content
+        let full = lazy.materialized();
+        assert!(!full.is_lazy());
+        assert!(full.get(file_id).unwrap().content.is_some());
+        let rewritten = temp_dir.path().join("rewritten.mmap");
+        MappedTree::write(&lazy, &rewritten).unwrap();
+        assert!(MappedTree::open(&rewritten)
+            .unwrap()
+            .content(file_id)
+            .unwrap()
+            .is_some());
+
+        lazy.content_mut(file_id)
+            .unwrap()
+            .tags
+            .push("core".to_string());
+        assert_eq!(lazy.content(file_id).unwrap().tags, vec!["core"]);
+    }
+
+    #[test]
+    fn test_mapped_rejects_corrupt_files() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("tree.mmap");
+
+        std::fs::write(&path, b"not a tree").unwrap();
+        assert!(MappedTree::open(&path).is_err());
+
+        MappedTree::write(&test_tree(), &path).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes[..PREAMBLE_LEN + 4]).unwrap();
+        assert!(MappedTree::open(&path).is_err());
+    }
+}
@@ -3,17 +3,95 @@
 //! Provides storage operations for saving and loading tree data,
 //! including fast skeleton loading and memory-mapped access.
 
+mod backend;
+mod cipher;
+mod edges;
 mod experience;
+mod identity;
+mod mapped;
 mod snapshot;
-
-pub use experience::ExperienceLog;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+pub use backend::{FilesystemBackend, StorageBackend};
+pub use cipher::LogCipher;
+pub use experience::{ExperienceLog, LogRepair, LogReplay};
+pub use identity::{
+    project_id_path, project_key, read_project_id, write_project_id, PROJECT_ID_PATH,
+};
+pub use mapped::MappedTree;
 pub use snapshot::SnapshotManager;
-
-use crate::tree::Tree;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteBackend;
+
+use crate::scanner::RescanResult;
+use crate::tree::{
+    apply_updates, prune_tree, DependencyGraph, FileUpdate, PruneReport, Tree, UpdateReport,
+    DEFAULT_CONTENT_CACHE,
+};
 use crate::IndexerError;
+use chrono::Utc;
+use edges::{full_log, EdgeLog, EdgeLogWrite, EDGE_LOG_FILE};
+use flate2::read::GzDecoder;
+use globset::GlobSet;
 use sha2::{Digest, Sha256};
+use std::io::Read;
 use std::path::{Path, PathBuf};
-use tracing::{debug, info};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// File name of a project's experience log
+const EXPERIENCE_FILE: &str = "experience.jsonl";
+
+/// File name of the memory-mapped tree
+const MAPPED_FILE: &str = "tree.mmap";
+
+/// Zstandard level of enriched trees unless configured
+const DEFAULT_COMPRESSION_LEVEL: u32 = 1;
+
+/// Highest Zstandard level
+const MAX_COMPRESSION_LEVEL: u32 = 22;
+
+/// Leading bytes of Zstandard data; JSON and MessagePack trees never start so
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Leading bytes of gzip data, as trees were compressed before Zstandard
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Blob holding the whole dependency graph as JSON, before the edge log
+const LEGACY_DEPENDENCIES_FILE: &str = "dependencies.json";
+
+/// Blob holding the tree as of the end of the last session
+const BASELINE_FILE: &str = "baseline.msgpack";
+
+/// Blobs copied into a snapshot
+const SNAPSHOT_FILES: &[&str] = &[
+    "skeleton.json",
+    EDGE_LOG_FILE,
+    LEGACY_DEPENDENCIES_FILE,
+    "enriched.msgpack",
+    "enriched.json",
+];
+
+/// A stored version of a project's tree.
+#[derive(Debug, Clone, Copy)]
+enum TreeFile {
+    Skeleton,
+    Enriched,
+    Baseline,
+}
+
+impl TreeFile {
+    /// Files the tree may be stored in, in the order they are tried
+    fn names(self) -> &'static [&'static str] {
+        match self {
+            TreeFile::Skeleton => &["skeleton.json"],
+            TreeFile::Enriched => &["enriched.msgpack", "enriched.json"],
+            TreeFile::Baseline => &[BASELINE_FILE],
+        }
+    }
+}
 
 /// Storage options.
 #[derive(Debug, Clone)]
@@ -24,6 +102,9 @@ pub struct StorageOptions {
     pub use_msgpack: bool,
     /// Maximum experience log size before rotation (bytes)
     pub max_experience_size: u64,
+    /// Zstandard level for enriched trees: 0 stores them uncompressed, 1
+    /// (fastest) to 22 (smallest)
+    pub compression_level: u32,
 }
 
 impl Default for StorageOptions {
@@ -35,37 +116,88 @@ impl Default for StorageOptions {
                 .join("projects"),
             use_msgpack: true,
             max_experience_size: 10 * 1024 * 1024, // 10MB
+            compression_level: DEFAULT_COMPRESSION_LEVEL,
         }
     }
 }
 
+/// A stored file that cannot be read back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageProblem {
+    pub path: PathBuf,
+    pub error: String,
+}
+
 /// Manages storage for project trees.
 pub struct Storage {
     options: StorageOptions,
+    /// Where trees and dependency graphs are kept
+    backend: Arc<dyn StorageBackend>,
+    /// Serializes experience log rewrites
+    rewrites: tokio::sync::Mutex<()>,
+    /// Encrypts experience logs when set
+    cipher: Option<Arc<LogCipher>>,
 }
 
 impl Storage {
     /// Create a new storage manager with default options.
     pub fn new(base_dir: PathBuf) -> Self {
-        Self {
-            options: StorageOptions {
-                base_dir,
-                ..Default::default()
-            },
-        }
+        Self::with_options(StorageOptions {
+            base_dir,
+            ..Default::default()
+        })
     }
 
     /// Create a storage manager with custom options.
+    ///
+    /// Trees are stored as files in the base directory.
     pub fn with_options(options: StorageOptions) -> Self {
-        Self { options }
+        let backend = Arc::new(FilesystemBackend::new(options.base_dir.clone()));
+        Self {
+            options,
+            backend,
+            rewrites: tokio::sync::Mutex::new(()),
+            cipher: None,
+        }
+    }
+
+    /// Keep trees and dependency graphs in `backend` instead of files.
+    pub fn with_backend(mut self, backend: Arc<dyn StorageBackend>) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Where trees and dependency graphs are kept.
+    pub fn backend(&self) -> Arc<dyn StorageBackend> {
+        self.backend.clone()
+    }
+
+    /// Encrypt experience logs with `cipher`; see [`ExperienceLog`].
+    pub fn with_cipher(mut self, cipher: Arc<LogCipher>) -> Self {
+        self.cipher = Some(cipher);
+        self
+    }
+
+    /// Whether experience logs are encrypted.
+    pub fn encrypts_experiences(&self) -> bool {
+        self.cipher.is_some()
+    }
+
+    /// Directory holding one storage directory per project.
+    pub fn base_dir(&self) -> &Path {
+        &self.options.base_dir
     }
 
-    /// Compute a hash for a project path.
+    /// Compute a hash for a project: of its id if it has one, else of its
+    /// path.
     pub fn project_hash(&self, project_path: &Path) -> String {
-        let mut hasher = Sha256::new();
-        hasher.update(project_path.to_string_lossy().as_bytes());
-        let result = hasher.finalize();
-        format!("{:x}", result)[..16].to_string()
+        hash_key(&project_key(project_path))
+    }
+
+    /// Hash of a project identified by its path alone, as before it had an
+    /// id.
+    pub fn path_hash(&self, project_path: &Path) -> String {
+        hash_key(&project_path.to_string_lossy())
     }
 
     /// Get the storage directory for a project hash.
@@ -128,6 +260,42 @@ impl Storage {
         log.read_recent(limit).await
     }
 
+    /// Size of the project's experience log in bytes.
+    pub async fn experience_size(&self, project_path: &Path) -> u64 {
+        let hash = self.project_hash(project_path);
+        self.experience_log(&hash).size().await
+    }
+
+    /// Rewrite the project's experience log; see [`ExperienceLog::rewrite`].
+    ///
+    /// Only one rewrite runs at a time, so a rewrite never replaces the
+    /// output of another with stale lines.
+    pub async fn rewrite_experiences<F>(
+        &self,
+        project_path: &Path,
+        compact: F,
+    ) -> Result<(usize, usize), IndexerError>
+    where
+        F: FnOnce(Vec<&str>) -> Vec<String>,
+    {
+        let _guard = self.rewrites.lock().await;
+        let hash = self.project_hash(project_path);
+        self.experience_log(&hash).rewrite(compact).await
+    }
+
+    /// Replay the project's experience log; see [`ExperienceLog::replay`].
+    ///
+    /// Runs exclusively with rewrites, since moving a corrupt suffix aside
+    /// rewrites the log.
+    pub async fn replay_experiences<E: serde::de::DeserializeOwned>(
+        &self,
+        project_path: &Path,
+    ) -> Result<LogReplay<E>, IndexerError> {
+        let _guard = self.rewrites.lock().await;
+        let hash = self.project_hash(project_path);
+        self.experience_log(&hash).replay().await
+    }
+
     /// Load all parseable experiences from the log (oldest first).
     pub async fn load_all_experiences<E: serde::de::DeserializeOwned>(
         &self,
@@ -140,53 +308,42 @@ impl Storage {
 
     /// Save a tree skeleton (structure only, fast).
     pub async fn save_skeleton(&self, tree: &Tree, hash: &str) -> Result<(), IndexerError> {
-        let dir = self.project_dir(hash);
-        tokio::fs::create_dir_all(&dir).await?;
-
-        let skeleton_path = dir.join("skeleton.json");
-
         // Create skeleton version (no content/symbols)
         let skeleton = create_skeleton(tree);
 
         let json = serde_json::to_string_pretty(&skeleton)
             .map_err(|e| IndexerError::Serialization(e.to_string()))?;
 
-        // Atomic write: write to temp file, then rename
-        let temp_path = dir.join(".skeleton.json.tmp");
-        tokio::fs::write(&temp_path, &json).await?;
-        tokio::fs::rename(&temp_path, &skeleton_path).await?;
+        let name = TreeFile::Skeleton.names()[0];
+        self.backend.write(hash, name, json.as_bytes()).await?;
 
-        debug!(path = ?skeleton_path, size = json.len(), "Saved skeleton");
+        debug!(path = ?self.backend.location(hash, name), size = json.len(), "Saved skeleton");
 
         Ok(())
     }
 
     /// Load a tree skeleton (fast initial load).
+    ///
+    /// A skeleton that cannot be deserialized is restored from the newest
+    /// readable snapshot.
     pub async fn load_skeleton(&self, hash: &str) -> Result<Tree, IndexerError> {
-        let skeleton_path = self.project_dir(hash).join("skeleton.json");
-
-        if !skeleton_path.exists() {
-            return Err(IndexerError::NotFound(skeleton_path));
-        }
-
-        let json = tokio::fs::read_to_string(&skeleton_path).await?;
-        let tree: Tree =
-            serde_json::from_str(&json).map_err(|e| IndexerError::Serialization(e.to_string()))?;
-
-        debug!(path = ?skeleton_path, nodes = tree.nodes.len(), "Loaded skeleton");
-
-        Ok(tree)
+        self.load(hash, TreeFile::Skeleton).await
     }
 
     /// Save a full enriched tree.
     pub async fn save_enriched(&self, tree: &Tree, hash: &str) -> Result<(), IndexerError> {
-        let dir = self.project_dir(hash);
-        tokio::fs::create_dir_all(&dir).await?;
-
-        let enriched_path = if self.options.use_msgpack {
-            dir.join("enriched.msgpack")
+        // Content only loaded on access must be saved too
+        let materialized;
+        let tree = if tree.is_lazy() {
+            materialized = tree.materialized();
+            &materialized
         } else {
-            dir.join("enriched.json")
+            tree
+        };
+        let name = if self.options.use_msgpack {
+            "enriched.msgpack"
+        } else {
+            "enriched.json"
         };
 
         let data = if self.options.use_msgpack {
@@ -195,79 +352,500 @@ impl Storage {
             serde_json::to_vec_pretty(tree)
                 .map_err(|e| IndexerError::Serialization(e.to_string()))?
         };
+        let data = compress(data, self.options.compression_level)?;
 
-        // Atomic write
-        let temp_path = dir.join(".enriched.tmp");
-        tokio::fs::write(&temp_path, &data).await?;
-        tokio::fs::rename(&temp_path, &enriched_path).await?;
+        self.backend.write(hash, name, &data).await?;
 
-        info!(path = ?enriched_path, size = data.len(), "Saved enriched tree");
+        info!(path = ?self.backend.location(hash, name), size = data.len(), "Saved enriched tree");
 
         Ok(())
     }
 
     /// Load a full enriched tree.
+    ///
+    /// An enriched tree that cannot be deserialized is restored from the
+    /// newest readable snapshot.
     pub async fn load_enriched(&self, hash: &str) -> Result<Tree, IndexerError> {
-        let dir = self.project_dir(hash);
+        self.load(hash, TreeFile::Enriched).await
+    }
 
-        // Try MessagePack first, then JSON
-        let msgpack_path = dir.join("enriched.msgpack");
-        let json_path = dir.join("enriched.json");
+    /// Load a stored tree, rolling back to a snapshot if it is corrupt.
+    async fn load(&self, hash: &str, file: TreeFile) -> Result<Tree, IndexerError> {
+        match read_tree(&*self.backend, hash, file).await {
+            Err(IndexerError::Serialization(error)) => self.roll_back(hash, file, error).await,
+            result => result,
+        }
+    }
 
-        if msgpack_path.exists() {
-            let data = tokio::fs::read(&msgpack_path).await?;
-            let tree: Tree = rmp_serde::from_slice(&data)
-                .map_err(|e| IndexerError::Serialization(e.to_string()))?;
-            debug!(path = ?msgpack_path, "Loaded enriched (msgpack)");
+    /// Restore a corrupt tree from the newest snapshot holding a readable
+    /// copy, or return the original error if there is none.
+    async fn roll_back(
+        &self,
+        hash: &str,
+        file: TreeFile,
+        error: String,
+    ) -> Result<Tree, IndexerError> {
+        // Snapshots are directories of files named like the blobs
+        let snapshots = self.snapshots(hash);
+        let saved = FilesystemBackend::new(snapshots.dir().to_path_buf());
+
+        for snapshot in snapshots.list().await.unwrap_or_default() {
+            let Ok(tree) = read_tree(&saved, &snapshot.name, file).await else {
+                continue;
+            };
+
+            for name in file.names() {
+                match saved.read(&snapshot.name, name).await? {
+                    Some(data) => self.backend.write(hash, name, &data).await?,
+                    None => self.backend.remove(hash, name).await?,
+                }
+            }
+
+            warn!(
+                hash = %hash,
+                snapshot = %snapshot.name,
+                error = %error,
+                "Stored tree is corrupt, rolled back to snapshot"
+            );
             return Ok(tree);
         }
 
-        if json_path.exists() {
-            let json = tokio::fs::read_to_string(&json_path).await?;
-            let tree: Tree = serde_json::from_str(&json)
-                .map_err(|e| IndexerError::Serialization(e.to_string()))?;
-            debug!(path = ?json_path, "Loaded enriched (json)");
-            return Ok(tree);
+        Err(IndexerError::Serialization(error))
+    }
+
+    /// Load the tree kept in a snapshot: enriched if it has one, else the
+    /// skeleton.
+    pub async fn load_snapshot(&self, hash: &str, name: &str) -> Result<Tree, IndexerError> {
+        if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+            return Err(IndexerError::NotFound(PathBuf::from(name)));
         }
+        let snapshots = self.snapshots(hash);
+        let saved = FilesystemBackend::new(snapshots.dir().to_path_buf());
+        match read_tree(&saved, name, TreeFile::Enriched).await {
+            Err(IndexerError::NotFound(_)) => read_tree(&saved, name, TreeFile::Skeleton).await,
+            result => result,
+        }
+    }
 
-        Err(IndexerError::NotFound(dir))
+    /// Keep `tree` as the baseline the next session is compared against.
+    ///
+    /// Only structure and file hashes are kept, as in the skeleton.
+    pub async fn save_baseline(&self, tree: &Tree, hash: &str) -> Result<(), IndexerError> {
+        let data = rmp_serde::to_vec(&create_skeleton(tree))
+            .map_err(|e| IndexerError::Serialization(e.to_string()))?;
+        let data = compress(data, self.options.compression_level)?;
+        self.backend.write(hash, BASELINE_FILE, &data).await
     }
 
-    /// Load tree with memory mapping (lazy access).
+    /// Load the session baseline, if one was saved.
+    pub async fn load_baseline(&self, hash: &str) -> Result<Option<Tree>, IndexerError> {
+        match read_tree(&*self.backend, hash, TreeFile::Baseline).await {
+            Ok(tree) => Ok(Some(tree)),
+            Err(IndexerError::NotFound(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Snapshot a project's stored tree unless the newest snapshot is less
+    /// than `interval` old, then prune all but the `keep` most recent.
     ///
-    /// Note: For now, this loads the full tree into memory.
-    /// Full mmap implementation would require a custom format with offset tables.
-    pub async fn load_tree_mmap(&self, hash: &str) -> Result<Tree, IndexerError> {
-        // For initial implementation, just load enriched or skeleton
-        if let Ok(tree) = self.load_enriched(hash).await {
-            return Ok(tree);
+    /// Returns the name of the new snapshot, if one was taken.
+    pub async fn snapshot(
+        &self,
+        hash: &str,
+        interval: Duration,
+        keep: usize,
+    ) -> Result<Option<String>, IndexerError> {
+        let snapshots = self.snapshots(hash);
+        let newest = snapshots.list().await?.into_iter().next();
+        let due = newest.is_none_or(|newest| {
+            Utc::now().signed_duration_since(newest.timestamp)
+                >= chrono::Duration::from_std(interval).unwrap_or(chrono::Duration::MAX)
+        });
+        if !due {
+            return Ok(None);
+        }
+
+        let mut files = Vec::new();
+        for name in SNAPSHOT_FILES {
+            if let Some(data) = self.backend.read(hash, name).await? {
+                files.push((*name, data));
+            }
+        }
+        let name = snapshots.create_with(&files).await?;
+        snapshots.prune(keep.max(1)).await?;
+        Ok(Some(name))
+    }
+
+    /// Open a project's tree as a memory-mapped file (lazy access).
+    ///
+    /// The mapped file is (re)written from the enriched tree, or the
+    /// skeleton if there is none, whenever either was saved after it.
+    pub async fn load_tree_mmap(&self, hash: &str) -> Result<MappedTree, IndexerError> {
+        let dir = self.project_dir(hash);
+        let mapped_path = dir.join(MAPPED_FILE);
+
+        let mut newest_source = None;
+        for name in ["enriched.msgpack", "enriched.json", "skeleton.json"] {
+            if let Some(modified) = self.backend.modified(hash, name).await {
+                newest_source = newest_source.max(Some(modified));
+            }
+        }
+        let Some(newest_source) = newest_source else {
+            return Err(IndexerError::NotFound(dir));
+        };
+
+        if modified_at(&mapped_path).await <= Some(newest_source) {
+            let tree = match self.load_enriched(hash).await {
+                Ok(tree) => tree,
+                Err(_) => self.load_skeleton(hash).await?,
+            };
+            let path = mapped_path.clone();
+            tokio::task::spawn_blocking(move || MappedTree::write(&tree, &path))
+                .await
+                .map_err(|e| IndexerError::Storage(e.to_string()))??;
+            debug!(path = ?mapped_path, "Wrote mapped tree");
         }
 
-        self.load_skeleton(hash).await
+        tokio::task::spawn_blocking(move || MappedTree::open(&mapped_path))
+            .await
+            .map_err(|e| IndexerError::Storage(e.to_string()))?
+    }
+
+    /// Load a project's tree with node content read lazily from the mapped
+    /// file; see [`Tree::content`].
+    pub async fn load_tree_lazy(&self, hash: &str) -> Result<Tree, IndexerError> {
+        let mapped = Arc::new(self.load_tree_mmap(hash).await?);
+        tokio::task::spawn_blocking(move || mapped.into_lazy_tree(DEFAULT_CONTENT_CACHE))
+            .await
+            .map_err(|e| IndexerError::Storage(e.to_string()))?
     }
 
     /// Save dependencies separately (for faster updates).
+    ///
+    /// Only the edges that changed since the last save are appended to the
+    /// project's edge log, which is compacted once it holds more than twice
+    /// as many lines as the graph has edges.
     pub async fn save_dependencies(&self, tree: &Tree, hash: &str) -> Result<(), IndexerError> {
-        let dir = self.project_dir(hash);
-        tokio::fs::create_dir_all(&dir).await?;
+        let write = match self.backend.read(hash, EDGE_LOG_FILE).await? {
+            Some(data) => EdgeLog::parse(&data).write_for(&tree.dependencies),
+            None => EdgeLogWrite::Rewrite(full_log(&tree.dependencies)),
+        };
 
-        let path = dir.join("dependencies.json");
-        let json = serde_json::to_string_pretty(&tree.dependencies)
-            .map_err(|e| IndexerError::Serialization(e.to_string()))?;
+        match write {
+            EdgeLogWrite::Unchanged => {}
+            EdgeLogWrite::Append(lines) => {
+                self.backend
+                    .append(hash, EDGE_LOG_FILE, lines.as_bytes())
+                    .await?;
+                debug!(hash = %hash, size = lines.len(), "Appended dependency changes");
+            }
+            EdgeLogWrite::Rewrite(log) => {
+                self.backend
+                    .write(hash, EDGE_LOG_FILE, log.as_bytes())
+                    .await?;
+                debug!(hash = %hash, size = log.len(), "Wrote dependency log");
+            }
+        }
+        self.backend.remove(hash, LEGACY_DEPENDENCIES_FILE).await
+    }
+
+    /// Load a project's stored dependency graph.
+    ///
+    /// Falls back to the JSON graph written before the edge log.
+    pub async fn load_dependencies(&self, hash: &str) -> Result<DependencyGraph, IndexerError> {
+        if let Some(data) = self.backend.read(hash, EDGE_LOG_FILE).await? {
+            return Ok(EdgeLog::parse(&data).graph());
+        }
+        match self.backend.read(hash, LEGACY_DEPENDENCIES_FILE).await? {
+            Some(data) => serde_json::from_slice(&data)
+                .map_err(|e| IndexerError::Serialization(e.to_string())),
+            None => Err(IndexerError::NotFound(
+                self.backend.location(hash, EDGE_LOG_FILE),
+            )),
+        }
+    }
 
-        tokio::fs::write(&path, json).await?;
+    /// Remove paths matching `globs` from the stored skeleton, dependency
+    /// and enriched data of a project, without re-scanning.
+    ///
+    /// The report describes what was removed from the skeleton.
+    pub async fn prune(&self, hash: &str, globs: &GlobSet) -> Result<PruneReport, IndexerError> {
+        let mut skeleton = self.load_skeleton(hash).await?;
+        let report = prune_tree(&mut skeleton, globs);
+        if report.removed_nodes() == 0 {
+            return Ok(report);
+        }
+        self.save_skeleton(&skeleton, hash).await?;
+        self.save_dependencies(&skeleton, hash).await?;
+
+        match self.load_enriched(hash).await {
+            Ok(mut enriched) => {
+                prune_tree(&mut enriched, globs);
+                self.save_enriched(&enriched, hash).await?;
+            }
+            Err(IndexerError::NotFound(_)) => {}
+            Err(e) => return Err(e),
+        }
 
-        Ok(())
+        info!(
+            hash = %hash,
+            files = report.removed_files,
+            dirs = report.removed_dirs,
+            edges = report.removed_edges,
+            "Pruned tree"
+        );
+        Ok(report)
+    }
+
+    /// Apply file updates to the stored skeleton, dependency and enriched
+    /// data of a project.
+    ///
+    /// The report describes what changed in the skeleton. Returns the
+    /// updated skeleton's file count alongside it.
+    pub async fn update_files(
+        &self,
+        hash: &str,
+        updates: &[FileUpdate],
+    ) -> Result<(UpdateReport, usize), IndexerError> {
+        self.update_trees(hash, updates, None).await
+    }
+
+    /// Apply a rescan to the stored skeleton, dependency and enriched data
+    /// of a project.
+    ///
+    /// Like [`Storage::update_files`], but also records the rescan's
+    /// languages, frameworks and start time, even if no file changed.
+    pub async fn apply_rescan(
+        &self,
+        hash: &str,
+        rescan: &RescanResult,
+    ) -> Result<(UpdateReport, usize), IndexerError> {
+        self.update_trees(hash, &rescan.updates, Some(rescan)).await
+    }
+
+    async fn update_trees(
+        &self,
+        hash: &str,
+        updates: &[FileUpdate],
+        rescan: Option<&RescanResult>,
+    ) -> Result<(UpdateReport, usize), IndexerError> {
+        let mut skeleton = self.load_skeleton(hash).await?;
+        let report = apply_updates(&mut skeleton, updates);
+        if report.is_empty() && rescan.is_none() {
+            return Ok((report, skeleton.file_count));
+        }
+        if let Some(rescan) = rescan {
+            record_rescan(&mut skeleton, rescan);
+        }
+        self.save_skeleton(&skeleton, hash).await?;
+        self.save_dependencies(&skeleton, hash).await?;
+
+        match self.load_enriched(hash).await {
+            Ok(mut enriched) => {
+                apply_updates(&mut enriched, updates);
+                if let Some(rescan) = rescan {
+                    record_rescan(&mut enriched, rescan);
+                }
+                self.save_enriched(&enriched, hash).await?;
+            }
+            Err(IndexerError::NotFound(_)) => {}
+            Err(e) => return Err(e),
+        }
+
+        debug!(
+            hash = %hash,
+            added = report.added_files,
+            updated = report.updated_files,
+            removed = report.removed_files,
+            renamed = report.renamed_files,
+            "Applied file updates"
+        );
+        Ok((report, skeleton.file_count))
     }
 
     /// Check if a project has stored data.
     pub async fn exists(&self, hash: &str) -> bool {
+        for name in ["skeleton.json", "enriched.msgpack"] {
+            if self.backend.modified(hash, name).await.is_some() {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Move a project's stored data from hash `from` to hash `to`, unless
+    /// `to` already has stored data, and point its stored trees at `root`.
+    ///
+    /// Returns whether data was moved.
+    pub async fn relocate(&self, from: &str, to: &str, root: &Path) -> Result<bool, IndexerError> {
+        let mut moved = false;
+        if from != to && !self.exists(to).await {
+            let (old_dir, new_dir) = (self.project_dir(from), self.project_dir(to));
+            if old_dir.exists() && !new_dir.exists() {
+                tokio::fs::rename(&old_dir, &new_dir).await?;
+                moved = true;
+            }
+            // Blobs a backend keeps outside the storage directory
+            for name in SNAPSHOT_FILES.iter().chain([&BASELINE_FILE]) {
+                if let Some(data) = self.backend.read(from, name).await? {
+                    self.backend.write(to, name, &data).await?;
+                    self.backend.remove(from, name).await?;
+                    moved = true;
+                }
+            }
+        }
+
+        self.reroot(to, root).await?;
+
+        if moved {
+            info!(from = %from, to = %to, root = ?root, "Relocated project storage");
+        }
+        Ok(moved)
+    }
+
+    /// Point a project's stored trees at `root`.
+    async fn reroot(&self, hash: &str, root: &Path) -> Result<(), IndexerError> {
+        match self.load_skeleton(hash).await {
+            Ok(mut tree) if tree.root_path != root => {
+                tree.root_path = root.to_path_buf();
+                self.save_skeleton(&tree, hash).await?;
+            }
+            Ok(_) | Err(IndexerError::NotFound(_)) => {}
+            Err(e) => return Err(e),
+        }
+        match self.load_enriched(hash).await {
+            Ok(mut tree) if tree.root_path != root => {
+                tree.root_path = root.to_path_buf();
+                self.save_enriched(&tree, hash).await?;
+            }
+            Ok(_) | Err(IndexerError::NotFound(_)) => {}
+            Err(e) => return Err(e),
+        }
+        if let Some(mut tree) = self.load_baseline(hash).await? {
+            if tree.root_path != root {
+                tree.root_path = root.to_path_buf();
+                self.save_baseline(&tree, hash).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Every stored file of a project, by `/` separated path relative to its
+    /// storage directory, including the blobs a backend keeps outside it.
+    ///
+    /// The memory-mapped tree is derived from the others and left out, as
+    /// are partly written temp files.
+    pub async fn stored_files(&self, hash: &str) -> Result<Vec<(String, Vec<u8>)>, IndexerError> {
+        let mut files = Vec::new();
         let dir = self.project_dir(hash);
-        dir.join("skeleton.json").exists() || dir.join("enriched.msgpack").exists()
+        if dir.exists() {
+            collect_files(&dir, "", &mut files).await?;
+        }
+        for name in SNAPSHOT_FILES.iter().chain([&BASELINE_FILE]) {
+            if files.iter().any(|(path, _)| path == name) {
+                continue;
+            }
+            if let Some(data) = self.backend.read(hash, name).await? {
+                files.push((name.to_string(), data));
+            }
+        }
+        files.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(files)
+    }
+
+    /// Replace a project's stored data with `files`, as returned by
+    /// [`Storage::stored_files`], and point its stored trees at `root`.
+    ///
+    /// Paths leaving the storage directory are rejected, and the files are
+    /// written to a staging directory that only replaces the project's once
+    /// complete, so a failed restore leaves the stored data as it was.
+    pub async fn restore_files(
+        &self,
+        hash: &str,
+        files: &[(String, Vec<u8>)],
+        root: &Path,
+    ) -> Result<(), IndexerError> {
+        for (path, _) in files {
+            if !is_relative_file(path) {
+                return Err(IndexerError::Storage(format!(
+                    "invalid stored path: {}",
+                    path
+                )));
+            }
+        }
+
+        let staging = format!(".{}.restore", hash);
+        self.delete(&staging).await?;
+        if let Err(e) = self.stage_files(&staging, files, root).await {
+            let _ = self.delete(&staging).await;
+            return Err(e);
+        }
+
+        // Blobs a backend keeps outside the storage directory are moved
+        // one by one; the directory, with any blobs inside it, in one rename
+        let dir = self.project_dir(hash);
+        let staging_dir = self.project_dir(&staging);
+        for name in SNAPSHOT_FILES.iter().chain([&BASELINE_FILE]) {
+            if self
+                .backend
+                .location(&staging, name)
+                .starts_with(&staging_dir)
+            {
+                continue;
+            }
+            match self.backend.read(&staging, name).await? {
+                Some(data) => {
+                    self.backend.write(hash, name, &data).await?;
+                    self.backend.remove(&staging, name).await?;
+                }
+                None => self.backend.remove(hash, name).await?,
+            }
+        }
+        let replaced = self.project_dir(&format!(".{}.replaced", hash));
+        if replaced.exists() {
+            tokio::fs::remove_dir_all(&replaced).await?;
+        }
+        if dir.exists() {
+            tokio::fs::rename(&dir, &replaced).await?;
+        }
+        tokio::fs::rename(&staging_dir, &dir).await?;
+        if replaced.exists() {
+            tokio::fs::remove_dir_all(&replaced).await?;
+        }
+
+        info!(hash = %hash, files = files.len(), root = ?root, "Restored project storage");
+        Ok(())
+    }
+
+    /// Write `files` as the stored data of `hash`, pointed at `root`.
+    async fn stage_files(
+        &self,
+        hash: &str,
+        files: &[(String, Vec<u8>)],
+        root: &Path,
+    ) -> Result<(), IndexerError> {
+        let dir = self.project_dir(hash);
+        tokio::fs::create_dir_all(&dir).await?;
+        for (path, data) in files {
+            if SNAPSHOT_FILES.contains(&path.as_str()) || path == BASELINE_FILE {
+                self.backend.write(hash, path, data).await?;
+                continue;
+            }
+            let target = dir.join(path);
+            if let Some(parent) = target.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            tokio::fs::write(&target, data).await?;
+        }
+        self.reroot(hash, root).await
     }
 
     /// Delete all stored data for a project.
     pub async fn delete(&self, hash: &str) -> Result<(), IndexerError> {
+        for name in SNAPSHOT_FILES.iter().chain([&BASELINE_FILE]) {
+            self.backend.remove(hash, name).await?;
+        }
         let dir = self.project_dir(hash);
         if dir.exists() {
             tokio::fs::remove_dir_all(&dir).await?;
@@ -275,10 +853,127 @@ impl Storage {
         Ok(())
     }
 
+    /// Root path a project's stored skeleton was indexed at, read without
+    /// building the tree; `None` if there is no readable skeleton.
+    pub async fn stored_root(&self, hash: &str) -> Option<PathBuf> {
+        #[derive(serde::Deserialize)]
+        struct Root {
+            root_path: PathBuf,
+        }
+
+        let name = TreeFile::Skeleton.names()[0];
+        let data = self.backend.read(hash, name).await.ok()??;
+        serde_json::from_slice::<Root>(&data)
+            .ok()
+            .map(|root| root.root_path)
+    }
+
+    /// Hashes of all projects with a storage directory, sorted.
+    pub async fn stored_projects(&self) -> Result<Vec<String>, IndexerError> {
+        let mut entries = match tokio::fs::read_dir(&self.options.base_dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut hashes = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            // Other entries of the data dir (grammars, config) are not hex
+            let is_hash = !name.is_empty() && name.chars().all(|c| c.is_ascii_hexdigit());
+            if is_hash && entry.file_type().await?.is_dir() {
+                hashes.push(name);
+            }
+        }
+        hashes.sort();
+        Ok(hashes)
+    }
+
+    /// Check that a project's stored trees and experience log can be read.
+    ///
+    /// Unlike loading, nothing is rolled back; corrupt files are reported.
+    pub async fn verify(&self, hash: &str) -> Vec<StorageProblem> {
+        let dir = self.project_dir(hash);
+        let mut problems = Vec::new();
+
+        for file in [TreeFile::Skeleton, TreeFile::Enriched] {
+            match read_tree(&*self.backend, hash, file).await {
+                Ok(_) | Err(IndexerError::NotFound(_)) => {}
+                Err(e) => {
+                    let mut path = dir.clone();
+                    for name in file.names() {
+                        if self.backend.modified(hash, name).await.is_some() {
+                            path = self.backend.location(hash, name);
+                            break;
+                        }
+                    }
+                    problems.push(StorageProblem {
+                        path,
+                        error: e.to_string(),
+                    });
+                }
+            }
+        }
+
+        let log = self.experience_log(hash);
+        match log.invalid_lines().await {
+            Ok(lines) if lines.is_empty() => {}
+            Ok(lines) => problems.push(StorageProblem {
+                path: log.path().to_path_buf(),
+                error: format!(
+                    "{} corrupt line(s), first at line {}",
+                    lines.len(),
+                    lines[0]
+                ),
+            }),
+            Err(e) => problems.push(StorageProblem {
+                path: log.path().to_path_buf(),
+                error: e.to_string(),
+            }),
+        }
+
+        problems
+    }
+
     /// Get an experience log for a project.
     pub fn experience_log(&self, hash: &str) -> ExperienceLog {
-        let path = self.project_dir(hash).join("experience.jsonl");
-        ExperienceLog::new(path, self.options.max_experience_size)
+        let path = self.project_dir(hash).join(EXPERIENCE_FILE);
+        self.open_experience_log(path)
+    }
+
+    fn open_experience_log(&self, path: PathBuf) -> ExperienceLog {
+        let log = ExperienceLog::new(path, self.options.max_experience_size);
+        match &self.cipher {
+            Some(cipher) => log.with_cipher(cipher.clone()),
+            None => log,
+        }
+    }
+
+    /// Encrypt the plaintext lines of a project's experience log and of the
+    /// logs rotated out of it, returning the number of lines encrypted.
+    pub async fn encrypt_experiences(&self, hash: &str) -> Result<usize, IndexerError> {
+        let _guard = self.rewrites.lock().await;
+        let mut entries = match tokio::fs::read_dir(self.project_dir(hash)).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut lines = 0;
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            // The log, or one rotated to `<log>.<timestamp>`
+            let is_log = name.strip_prefix(EXPERIENCE_FILE).is_some_and(|rest| {
+                rest.is_empty()
+                    || rest.strip_prefix('.').is_some_and(|stamp| {
+                        !stamp.is_empty() && stamp.chars().all(|c| c.is_ascii_digit() || c == '_')
+                    })
+            });
+            if is_log {
+                lines += self.open_experience_log(entry.path()).encrypt().await?;
+            }
+        }
+        Ok(lines)
     }
 
     /// Get a snapshot manager for a project.
@@ -294,6 +989,113 @@ impl Default for Storage {
     }
 }
 
+/// Read a stored tree of a project from `backend`.
+async fn read_tree(
+    backend: &dyn StorageBackend,
+    hash: &str,
+    file: TreeFile,
+) -> Result<Tree, IndexerError> {
+    // Enriched trees: MessagePack first, then JSON
+    for name in file.names() {
+        let Some(data) = backend.read(hash, name).await? else {
+            continue;
+        };
+        let data = decompress(data)?;
+        let tree: Tree = if name.ends_with(".msgpack") {
+            rmp_serde::from_slice(&data).map_err(|e| IndexerError::Serialization(e.to_string()))?
+        } else {
+            serde_json::from_slice(&data).map_err(|e| IndexerError::Serialization(e.to_string()))?
+        };
+        debug!(path = ?backend.location(hash, name), nodes = tree.nodes.len(), "Loaded tree");
+        return Ok(tree);
+    }
+    Err(IndexerError::NotFound(
+        backend.location(hash, file.names()[0]),
+    ))
+}
+
+/// Gzip `data` at `level`; level 0 leaves it as is.
+/// Short hex digest naming a project's storage directory
+fn hash_key(key: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(key.as_bytes());
+    let result = hasher.finalize();
+    format!("{:x}", result)[..16].to_string()
+}
+
+fn compress(data: Vec<u8>, level: u32) -> Result<Vec<u8>, IndexerError> {
+    if level == 0 {
+        return Ok(data);
+    }
+    let level = level.min(MAX_COMPRESSION_LEVEL) as i32;
+    Ok(zstd::encode_all(data.as_slice(), level)?)
+}
+
+/// Decompress Zstandard or gzip data, told apart by their magic bytes;
+/// anything else, e.g. trees saved before compression, is returned as is.
+fn decompress(data: Vec<u8>) -> Result<Vec<u8>, IndexerError> {
+    if data.starts_with(&ZSTD_MAGIC) {
+        return zstd::decode_all(data.as_slice())
+            .map_err(|e| IndexerError::Serialization(format!("corrupt zstd data: {}", e)));
+    }
+    if !data.starts_with(&GZIP_MAGIC) {
+        return Ok(data);
+    }
+    let mut decoded = Vec::new();
+    GzDecoder::new(data.as_slice())
+        .read_to_end(&mut decoded)
+        .map_err(|e| IndexerError::Serialization(format!("corrupt gzip data: {}", e)))?;
+    Ok(decoded)
+}
+
+/// Add the files below `dir` to `files`, by path relative to the storage
+/// directory, skipping the memory-mapped tree and temp files.
+async fn collect_files(
+    dir: &Path,
+    prefix: &str,
+    files: &mut Vec<(String, Vec<u8>)>,
+) -> Result<(), IndexerError> {
+    let mut entries = tokio::fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with('.') || (prefix.is_empty() && name == MAPPED_FILE) {
+            continue;
+        }
+        let path = format!("{}{}", prefix, name);
+        if entry.file_type().await?.is_dir() {
+            Box::pin(collect_files(&entry.path(), &format!("{}/", path), files)).await?;
+        } else {
+            files.push((path, tokio::fs::read(entry.path()).await?));
+        }
+    }
+    Ok(())
+}
+
+/// Whether `path` names a file below a directory without leaving it.
+fn is_relative_file(path: &str) -> bool {
+    !path.is_empty()
+        && !path.contains('\\')
+        && path
+            .split('/')
+            .all(|part| !part.is_empty() && part != "." && part != ".." && !part.contains(':'))
+}
+
+/// Modification time of a file, if it exists.
+async fn modified_at(path: &Path) -> Option<std::time::SystemTime> {
+    tokio::fs::metadata(path)
+        .await
+        .and_then(|meta| meta.modified())
+        .ok()
+}
+
+/// Record what a rescan saw of the whole project.
+fn record_rescan(tree: &mut Tree, rescan: &RescanResult) {
+    tree.languages = rescan.languages.clone();
+    tree.frameworks = rescan.frameworks.clone();
+    tree.git = crate::git::state(&tree.root_path);
+    tree.scanned_at = Some(rescan.started_at);
+}
+
 /// Create a skeleton version of a tree (no content).
 fn create_skeleton(tree: &Tree) -> Tree {
     let mut skeleton = tree.clone();
@@ -316,6 +1118,7 @@ mod tests {
             base_dir: temp_dir.to_path_buf(),
             use_msgpack: true,
             max_experience_size: 1024,
+            compression_level: 1,
         })
     }
 
@@ -352,6 +1155,126 @@ mod tests {
         assert_eq!(tree.root_path, loaded.root_path);
     }
 
+    #[tokio::test]
+    async fn test_save_dependencies_appends_changes() {
+        let temp_dir = tempdir().unwrap();
+        let storage = test_storage(temp_dir.path());
+        let hash = "deps01";
+        let path = storage.project_dir(hash).join(EDGE_LOG_FILE);
+        assert!(matches!(
+            storage.load_dependencies(hash).await,
+            Err(IndexerError::NotFound(_))
+        ));
+
+        // A graph written before the edge log still loads, and is replaced
+        let mut tree = test_tree();
+        tree.dependencies.add_edge(1, 2);
+        tree.dependencies.add_edge(1, 3);
+        let legacy = serde_json::to_vec(&tree.dependencies).unwrap();
+        storage
+            .backend
+            .write(hash, LEGACY_DEPENDENCIES_FILE, &legacy)
+            .await
+            .unwrap();
+        assert_eq!(
+            storage.load_dependencies(hash).await.unwrap().edge_count(),
+            2
+        );
+        storage.save_dependencies(&tree, hash).await.unwrap();
+        assert!(!storage
+            .project_dir(hash)
+            .join(LEGACY_DEPENDENCIES_FILE)
+            .exists());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "+1 2\n+1 3\n");
+
+        // A changed import appends two lines; an unchanged graph none
+        tree.dependencies.remove_edge(1, 3);
+        tree.dependencies.add_edge(3, 1);
+        storage.save_dependencies(&tree, hash).await.unwrap();
+        storage.save_dependencies(&tree, hash).await.unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "+1 2\n+1 3\n-1 3\n+3 1\n"
+        );
+
+        let loaded = storage.load_dependencies(hash).await.unwrap();
+        assert_eq!(loaded.edge_count(), 2);
+        assert_eq!(loaded.imports(3).collect::<Vec<_>>(), vec![1]);
+        assert_eq!(loaded.imported_by(3).count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_enriched_compression() {
+        let temp_dir = tempdir().unwrap();
+        let storage = test_storage(temp_dir.path());
+        let path = storage.project_dir("zip").join("enriched.msgpack");
+        let mut tree = test_tree();
+        tree.file_count = 3;
+
+        storage.save_enriched(&tree, "zip").await.unwrap();
+        let zstd_data = std::fs::read(&path).unwrap();
+        assert!(zstd_data.starts_with(&ZSTD_MAGIC));
+        assert_eq!(storage.load_enriched("zip").await.unwrap().file_count, 3);
+
+        // Trees saved uncompressed still load
+        let plain = Storage::with_options(StorageOptions {
+            compression_level: 0,
+            ..storage.options.clone()
+        });
+        plain.save_enriched(&tree, "zip").await.unwrap();
+        let plain_data = std::fs::read(&path).unwrap();
+        assert!(!plain_data.starts_with(&ZSTD_MAGIC));
+        assert!(!plain_data.starts_with(&GZIP_MAGIC));
+        assert_eq!(storage.load_enriched("zip").await.unwrap().file_count, 3);
+
+        // So do trees gzipped by earlier versions
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        std::io::Write::write_all(&mut encoder, &plain_data).unwrap();
+        std::fs::write(&path, encoder.finish().unwrap()).unwrap();
+        assert_eq!(storage.load_enriched("zip").await.unwrap().file_count, 3);
+
+        // Levels past the highest are clamped
+        let smallest = Storage::with_options(StorageOptions {
+            compression_level: 99,
+            ..storage.options.clone()
+        });
+        smallest.save_enriched(&tree, "zip").await.unwrap();
+        assert_eq!(storage.load_enriched("zip").await.unwrap().file_count, 3);
+
+        for corrupt in [&[0x1f, 0x8b, 0x00][..], &ZSTD_MAGIC[..]] {
+            std::fs::write(&path, corrupt).unwrap();
+            assert!(matches!(
+                storage.load_enriched("zip").await,
+                Err(IndexerError::Serialization(_))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_load_tree_mmap_follows_source() {
+        let temp_dir = tempdir().unwrap();
+        let storage = test_storage(temp_dir.path());
+        let hash = "mmap01";
+        assert!(storage.load_tree_mmap(hash).await.is_err());
+
+        let mut tree = test_tree();
+        storage.save_skeleton(&tree, hash).await.unwrap();
+        let mapped = storage.load_tree_mmap(hash).await.unwrap();
+        assert_eq!(mapped.len(), 1);
+        assert_eq!(mapped.file_count(), 0);
+
+        // Make sure the new source gets a later mtime than the mapped file
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        tree.file_count = 7;
+        storage.save_enriched(&tree, hash).await.unwrap();
+        let mapped = storage.load_tree_mmap(hash).await.unwrap();
+        assert_eq!(mapped.file_count(), 7);
+
+        let lazy = storage.load_tree_lazy(hash).await.unwrap();
+        assert!(lazy.is_lazy());
+        assert_eq!(lazy.file_count, 7);
+    }
+
     #[tokio::test]
     async fn test_exists() {
         let temp_dir = tempdir().unwrap();
@@ -380,6 +1303,118 @@ mod tests {
         assert!(!storage.exists(hash).await);
     }
 
+    #[tokio::test]
+    async fn test_relocate() {
+        let temp_dir = tempdir().unwrap();
+        let storage = test_storage(temp_dir.path());
+        let project = temp_dir.path().join("checkout");
+        std::fs::create_dir_all(&project).unwrap();
+
+        let old_hash = storage.project_hash(&project);
+        assert_eq!(old_hash, storage.path_hash(&project));
+        storage
+            .save_skeleton(&test_tree(), &old_hash)
+            .await
+            .unwrap();
+        storage
+            .save_enriched(&test_tree(), &old_hash)
+            .await
+            .unwrap();
+
+        write_project_id(&project, "stable-id").unwrap();
+        let new_hash = storage.project_hash(&project);
+        assert_ne!(new_hash, old_hash);
+
+        assert!(storage
+            .relocate(&old_hash, &new_hash, &project)
+            .await
+            .unwrap());
+        assert!(!storage.exists(&old_hash).await);
+        assert_eq!(storage.stored_root(&old_hash).await, None);
+        assert_eq!(storage.stored_root(&new_hash).await, Some(project.clone()));
+        assert_eq!(
+            storage.load_skeleton(&new_hash).await.unwrap().root_path,
+            project
+        );
+        assert_eq!(
+            storage.load_enriched(&new_hash).await.unwrap().root_path,
+            project
+        );
+
+        // Data already at the target is kept
+        assert!(!storage
+            .relocate(&old_hash, &new_hash, &project)
+            .await
+            .unwrap());
+        assert!(storage.exists(&new_hash).await);
+    }
+
+    #[tokio::test]
+    async fn test_stored_files_restore() {
+        let temp_dir = tempdir().unwrap();
+        let storage = test_storage(temp_dir.path());
+        let mut tree = test_tree();
+        tree.dependencies.add_edge(1, 2);
+        storage.save_skeleton(&tree, "src001").await.unwrap();
+        storage.save_dependencies(&tree, "src001").await.unwrap();
+        storage.save_enriched(&tree, "src001").await.unwrap();
+        storage.load_tree_mmap("src001").await.unwrap();
+        storage
+            .snapshot("src001", Duration::ZERO, 3)
+            .await
+            .unwrap()
+            .unwrap();
+        std::fs::write(storage.project_dir("src001").join(".scopes.json.tmp"), "{").unwrap();
+
+        let files = storage.stored_files("src001").await.unwrap();
+        let names: Vec<&str> = files.iter().map(|(name, _)| name.as_str()).collect();
+        assert!(names.contains(&"skeleton.json"));
+        assert!(names.contains(&EDGE_LOG_FILE));
+        assert!(names.iter().any(|name| name.starts_with("snapshots/")));
+        assert!(!names.contains(&MAPPED_FILE));
+        assert!(!names.iter().any(|name| name.starts_with('.')));
+
+        // Restoring replaces what was stored and re-roots the trees
+        let root = temp_dir.path().join("moved");
+        storage.save_baseline(&test_tree(), "dst001").await.unwrap();
+        storage
+            .restore_files("dst001", &files, &root)
+            .await
+            .unwrap();
+        assert!(storage.load_baseline("dst001").await.unwrap().is_none());
+        assert_eq!(storage.stored_root("dst001").await, Some(root.clone()));
+        assert!(!std::fs::read_dir(temp_dir.path())
+            .unwrap()
+            .any(|entry| entry
+                .unwrap()
+                .file_name()
+                .to_string_lossy()
+                .starts_with('.')));
+        assert_eq!(
+            storage.load_enriched("dst001").await.unwrap().root_path,
+            root
+        );
+        assert_eq!(
+            storage
+                .load_dependencies("dst001")
+                .await
+                .unwrap()
+                .edge_count(),
+            1
+        );
+        assert_eq!(storage.snapshots("dst001").list().await.unwrap().len(), 1);
+
+        // Paths leaving the storage directory are refused
+        for path in ["../escape", "/etc/passwd", "a//b", ""] {
+            let files = vec![(path.to_string(), Vec::new())];
+            assert!(storage
+                .restore_files("dst001", &files, &root)
+                .await
+                .is_err());
+        }
+        assert!(storage.exists("dst001").await);
+    }
+
     #[tokio::test]
     async fn test_load_not_found() {
         let temp_dir = tempdir().unwrap();
@@ -389,6 +1424,132 @@ mod tests {
         assert!(matches!(result, Err(IndexerError::NotFound(_))));
     }
 
+    #[tokio::test]
+    async fn test_snapshot_respects_interval() {
+        let temp_dir = tempdir().unwrap();
+        let storage = test_storage(temp_dir.path());
+        let hash = "snap";
+        storage.save_skeleton(&test_tree(), hash).await.unwrap();
+
+        let hour = Duration::from_secs(3600);
+        let name = storage.snapshot(hash, hour, 3).await.unwrap().unwrap();
+        assert!(storage
+            .snapshots(hash)
+            .path(&name)
+            .join("skeleton.json")
+            .exists());
+        assert_eq!(storage.snapshot(hash, hour, 3).await.unwrap(), None);
+
+        // A zero interval snapshots every time, within the retention limit
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+        assert!(storage
+            .snapshot(hash, Duration::ZERO, 1)
+            .await
+            .unwrap()
+            .is_some());
+        assert_eq!(storage.snapshots(hash).list().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_load_snapshot_and_baseline() {
+        let temp_dir = tempdir().unwrap();
+        let storage = test_storage(temp_dir.path());
+        let hash = "baseline";
+        let mut tree = test_tree();
+        tree.file_count = 7;
+        storage.save_skeleton(&tree, hash).await.unwrap();
+
+        let name = storage
+            .snapshot(hash, Duration::ZERO, 3)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            storage.load_snapshot(hash, &name).await.unwrap().file_count,
+            7
+        );
+        assert!(storage.load_snapshot(hash, "missing").await.is_err());
+        assert!(storage.load_snapshot(hash, "../other").await.is_err());
+
+        assert!(storage.load_baseline(hash).await.unwrap().is_none());
+        storage.save_baseline(&tree, hash).await.unwrap();
+        let baseline = storage.load_baseline(hash).await.unwrap().unwrap();
+        assert_eq!(baseline.file_count, 7);
+    }
+
+    #[tokio::test]
+    async fn test_corrupt_tree_rolls_back_to_snapshot() {
+        let temp_dir = tempdir().unwrap();
+        let storage = test_storage(temp_dir.path());
+        let hash = "corrupt";
+        let tree = test_tree();
+        storage.save_skeleton(&tree, hash).await.unwrap();
+        storage.save_enriched(&tree, hash).await.unwrap();
+        storage
+            .snapshot(hash, Duration::ZERO, 3)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let dir = storage.project_dir(hash);
+        std::fs::write(dir.join("skeleton.json"), "{ truncated").unwrap();
+        std::fs::write(dir.join("enriched.msgpack"), [0xc1]).unwrap();
+
+        let skeleton = storage.load_skeleton(hash).await.unwrap();
+        assert_eq!(skeleton.root_path, tree.root_path);
+        let enriched = storage.load_enriched(hash).await.unwrap();
+        assert_eq!(enriched.root_path, tree.root_path);
+
+        // The good copies were restored in place
+        let json = std::fs::read_to_string(dir.join("skeleton.json")).unwrap();
+        assert!(serde_json::from_str::<Tree>(&json).is_ok());
+        storage.load_enriched(hash).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_corrupt_tree_without_snapshot_fails() {
+        let temp_dir = tempdir().unwrap();
+        let storage = test_storage(temp_dir.path());
+        let hash = "corrupt";
+        let dir = storage.project_dir(hash);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("skeleton.json"), "{ truncated").unwrap();
+
+        let result = storage.load_skeleton(hash).await;
+        assert!(matches!(result, Err(IndexerError::Serialization(_))));
+    }
+
+    #[tokio::test]
+    async fn test_verify_reports_corrupt_files() {
+        let temp_dir = tempdir().unwrap();
+        let storage = test_storage(temp_dir.path());
+        storage.save_skeleton(&test_tree(), "abc123").await.unwrap();
+        storage.save_enriched(&test_tree(), "def456").await.unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("grammars")).unwrap();
+
+        assert_eq!(
+            storage.stored_projects().await.unwrap(),
+            vec!["abc123".to_string(), "def456".to_string()]
+        );
+        assert!(storage.verify("abc123").await.is_empty());
+
+        let dir = storage.project_dir("def456");
+        std::fs::write(dir.join("enriched.msgpack"), b"not msgpack").unwrap();
+        std::fs::write(
+            dir.join("experience.jsonl"),
+            "{\"id\":1}\n{\"id\":\n{\"id\":3}\n",
+        )
+        .unwrap();
+
+        let problems = storage.verify("def456").await;
+        let paths: Vec<_> = problems.iter().map(|p| p.path.clone()).collect();
+        assert_eq!(
+            paths,
+            vec![dir.join("enriched.msgpack"), dir.join("experience.jsonl")]
+        );
+        assert!(problems[1].error.contains("first at line 2"));
+    }
+
     #[tokio::test]
     async fn test_skeleton_removes_content() {
         let mut tree = test_tree();
@@ -403,6 +1564,133 @@ mod tests {
         assert!(skeleton.nodes.get(&0).unwrap().content.is_none());
     }
 
+    #[tokio::test]
+    async fn test_prune_updates_skeleton_and_enriched() {
+        let temp_dir = tempdir().unwrap();
+        let storage = test_storage(temp_dir.path());
+        let hash = "prune_test";
+
+        let scan = crate::scanner::ScanResult {
+            root: PathBuf::from("/test/project"),
+            files: vec![crate::scanner::ScannedFile {
+                path: PathBuf::from("vendor/lib.rs"),
+                language: None,
+                size: 1,
+                hash: "h".to_string(),
+                line_count: 1,
+                symbols: vec![],
+                imports: vec![],
+                truncated: false,
+                asset: None,
+            }],
+            languages: vec![],
+            frameworks: vec![],
+            duration_ms: 0,
+            skipped_count: 0,
+            excluded_dirs: vec![],
+        };
+        let tree = crate::tree::TreeBuilder::new().build(&scan);
+        storage.save_skeleton(&tree, hash).await.unwrap();
+        storage.save_enriched(&tree, hash).await.unwrap();
+
+        let globs = crate::tree::compile_globs(&["vendor".to_string()]).unwrap();
+        let report = storage.prune(hash, &globs).await.unwrap();
+        assert_eq!(report.removed_files, 1);
+
+        assert_eq!(storage.load_skeleton(hash).await.unwrap().nodes.len(), 1);
+        assert_eq!(storage.load_enriched(hash).await.unwrap().nodes.len(), 1);
+    }
+
+    /// Blobs kept in memory, to check nothing bypasses the backend
+    #[derive(Default)]
+    struct MemoryBackend {
+        blobs: parking_lot::Mutex<std::collections::HashMap<String, Vec<u8>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl StorageBackend for MemoryBackend {
+        async fn read(&self, hash: &str, name: &str) -> Result<Option<Vec<u8>>, IndexerError> {
+            Ok(self
+                .blobs
+                .lock()
+                .get(&format!("{}/{}", hash, name))
+                .cloned())
+        }
+
+        async fn write(&self, hash: &str, name: &str, data: &[u8]) -> Result<(), IndexerError> {
+            self.blobs
+                .lock()
+                .insert(format!("{}/{}", hash, name), data.to_vec());
+            Ok(())
+        }
+
+        async fn remove(&self, hash: &str, name: &str) -> Result<(), IndexerError> {
+            self.blobs.lock().remove(&format!("{}/{}", hash, name));
+            Ok(())
+        }
+
+        async fn modified(&self, hash: &str, name: &str) -> Option<std::time::SystemTime> {
+            self.blobs
+                .lock()
+                .contains_key(&format!("{}/{}", hash, name))
+                .then(std::time::SystemTime::now)
+        }
+
+        fn location(&self, hash: &str, name: &str) -> PathBuf {
+            PathBuf::from("memory").join(hash).join(name)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_backend() {
+        let temp_dir = tempdir().unwrap();
+        let backend = Arc::new(MemoryBackend::default());
+        let storage = test_storage(temp_dir.path()).with_backend(backend.clone());
+        let hash = "custom";
+        let tree = test_tree();
+
+        storage.save_skeleton(&tree, hash).await.unwrap();
+        storage.save_enriched(&tree, hash).await.unwrap();
+        storage.save_dependencies(&tree, hash).await.unwrap();
+        assert!(storage.exists(hash).await);
+        assert_eq!(backend.blobs.lock().len(), 3);
+        assert!(!storage.project_dir(hash).join("skeleton.json").exists());
+        assert_eq!(
+            storage.load_enriched(hash).await.unwrap().root_path,
+            tree.root_path
+        );
+
+        // Snapshots copy blobs out of the backend and restore them into it
+        storage
+            .snapshot(hash, Duration::ZERO, 3)
+            .await
+            .unwrap()
+            .unwrap();
+        backend
+            .write(hash, "skeleton.json", b"{ truncated")
+            .await
+            .unwrap();
+        assert_eq!(
+            storage.load_skeleton(hash).await.unwrap().root_path,
+            tree.root_path
+        );
+        assert!(serde_json::from_slice::<Tree>(
+            &backend.read(hash, "skeleton.json").await.unwrap().unwrap()
+        )
+        .is_ok());
+
+        // Restored blobs land in the backend, and staged ones leave it
+        let files = storage.stored_files(hash).await.unwrap();
+        let root = PathBuf::from("/restored");
+        storage.restore_files(hash, &files, &root).await.unwrap();
+        assert_eq!(storage.load_skeleton(hash).await.unwrap().root_path, root);
+        assert_eq!(backend.blobs.lock().len(), 3);
+
+        storage.delete(hash).await.unwrap();
+        assert!(!storage.exists(hash).await);
+        assert!(backend.blobs.lock().is_empty());
+    }
+
     #[test]
     fn test_project_dir() {
         let storage = Storage::with_options(StorageOptions {
@@ -450,4 +1738,41 @@ mod tests {
         let loaded: Vec<Record> = storage.load_all_experiences(&project).await.unwrap();
         assert_eq!(loaded, vec![first, second]);
     }
+
+    #[tokio::test]
+    async fn test_encrypt_experiences() {
+        let temp_dir = tempdir().unwrap();
+        let project = temp_dir.path().join("project");
+        let plain = test_storage(temp_dir.path());
+        let hash = plain.project_hash(&project);
+        let note = serde_json::json!({"note": "secret plan"});
+        plain.append_experience(&project, &note).await.unwrap();
+        let dir = plain.project_dir(&hash);
+        std::fs::copy(
+            dir.join(EXPERIENCE_FILE),
+            dir.join("experience.jsonl.20260101_000000"),
+        )
+        .unwrap();
+        std::fs::write(dir.join("experience.jsonl.corrupt"), "secret plan\n").unwrap();
+        assert!(plain.encrypt_experiences(&hash).await.is_err());
+        assert_eq!(plain.stored_projects().await.unwrap(), vec![hash.clone()]);
+
+        let cipher = LogCipher::from_hex(&LogCipher::generate_key()).unwrap();
+        let storage = test_storage(temp_dir.path()).with_cipher(Arc::new(cipher));
+        assert_eq!(storage.encrypt_experiences(&hash).await.unwrap(), 3);
+        for name in [
+            EXPERIENCE_FILE,
+            "experience.jsonl.20260101_000000",
+            "experience.jsonl.corrupt",
+        ] {
+            let content = std::fs::read_to_string(dir.join(name)).unwrap();
+            assert!(!content.contains("secret plan"));
+        }
+        let loaded: Vec<serde_json::Value> = storage.load_experiences(&project, 10).await.unwrap();
+        assert_eq!(loaded, vec![note]);
+        assert!(plain
+            .load_experiences::<serde_json::Value>(&project, 10)
+            .await
+            .is_err());
+    }
 }
@@ -2,7 +2,7 @@
 
 use crate::IndexerError;
 use chrono::{DateTime, Utc};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tracing::{debug, info};
 
 /// Manages snapshots of tree data.
@@ -31,6 +31,49 @@ impl SnapshotManager {
         Ok(timestamp)
     }
 
+    /// Create a snapshot of the named files in `source_dir`; missing files
+    /// are skipped.
+    pub async fn create_from(
+        &self,
+        source_dir: &Path,
+        files: &[&str],
+    ) -> Result<String, IndexerError> {
+        let mut contents = Vec::new();
+        for name in files {
+            match tokio::fs::read(source_dir.join(name)).await {
+                Ok(data) => contents.push((*name, data)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        self.create_with(&contents).await
+    }
+
+    /// Create a snapshot holding the given files.
+    pub async fn create_with(&self, files: &[(&str, Vec<u8>)]) -> Result<String, IndexerError> {
+        let timestamp = Utc::now().format("%Y%m%d_%H%M%S").to_string();
+        let snapshot_dir = self.dir.join(&timestamp);
+        tokio::fs::create_dir_all(&snapshot_dir).await?;
+
+        for (name, data) in files {
+            tokio::fs::write(snapshot_dir.join(name), data).await?;
+        }
+
+        info!(snapshot = %timestamp, path = ?snapshot_dir, "Created snapshot");
+
+        Ok(timestamp)
+    }
+
+    /// Directory holding all snapshots.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Directory holding a snapshot's files.
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(name)
+    }
+
     /// List all available snapshots.
     pub async fn list(&self) -> Result<Vec<SnapshotInfo>, IndexerError> {
         if !self.dir.exists() {
@@ -46,7 +89,6 @@ impl SnapshotManager {
 
                 // Parse timestamp from name
                 if let Some(timestamp) = parse_snapshot_timestamp(&name) {
-                    let metadata = entry.metadata().await?;
                     snapshots.push(SnapshotInfo {
                         name,
                         timestamp,
@@ -57,7 +99,7 @@ impl SnapshotManager {
         }
 
         // Sort by timestamp descending (newest first)
-        snapshots.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+        snapshots.sort_by_key(|s| std::cmp::Reverse(s.timestamp));
 
         Ok(snapshots)
     }
@@ -270,6 +312,31 @@ mod tests {
         assert_eq!(remaining.len(), 1);
     }
 
+    #[tokio::test]
+    async fn test_create_from_named_files() {
+        let temp_dir = tempdir().unwrap();
+        let source_dir = temp_dir.path().join("source");
+        // Snapshots living inside the source directory are not copied
+        let manager = SnapshotManager::new(source_dir.join("snapshots"));
+
+        fs::create_dir_all(&source_dir).unwrap();
+        fs::write(source_dir.join("tree.json"), "tree").unwrap();
+        fs::write(source_dir.join("other.txt"), "other").unwrap();
+
+        let name = manager
+            .create_from(&source_dir, &["tree.json", "missing.json"])
+            .await
+            .unwrap();
+
+        let snapshot = manager.path(&name);
+        assert_eq!(
+            fs::read_to_string(snapshot.join("tree.json")).unwrap(),
+            "tree"
+        );
+        assert!(!snapshot.join("other.txt").exists());
+        assert!(!snapshot.join("missing.json").exists());
+    }
+
     #[tokio::test]
     async fn test_delete_snapshot() {
         let temp_dir = tempdir().unwrap();
@@ -0,0 +1,245 @@
+//! Blobs in an SQLite database.
+//!
+//! Every project's trees and dependency log live as rows of one `blobs`
+//! table instead of files, so a data dir holding many projects, or huge
+//! ones, does not fill up with loose files. The database runs in WAL mode;
+//! statements run on the blocking thread pool.
+
+use super::backend::StorageBackend;
+use crate::IndexerError;
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Schema of the blob table, keyed by project hash and blob name
+const SCHEMA: &str = "CREATE TABLE IF NOT EXISTS blobs (
+    hash TEXT NOT NULL,
+    name TEXT NOT NULL,
+    data BLOB NOT NULL,
+    modified_ms INTEGER NOT NULL,
+    PRIMARY KEY (hash, name)
+)";
+
+/// Blobs as rows of an SQLite database.
+#[derive(Clone)]
+pub struct SqliteBackend {
+    path: PathBuf,
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteBackend {
+    /// Open the database at `path`, creating it if it does not exist.
+    pub fn open(path: &Path) -> Result<Self, IndexerError> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let conn = Connection::open(path).map_err(sqlite_error)?;
+        conn.pragma_update(None, "journal_mode", "WAL")
+            .map_err(sqlite_error)?;
+        conn.busy_timeout(Duration::from_secs(5))
+            .map_err(sqlite_error)?;
+        conn.execute(SCHEMA, []).map_err(sqlite_error)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// Run `f` on the connection off the async runtime.
+    async fn with_conn<T, F>(&self, f: F) -> Result<T, IndexerError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> rusqlite::Result<T> + Send + 'static,
+    {
+        let conn = self.conn.clone();
+        tokio::task::spawn_blocking(move || f(&mut conn.lock()))
+            .await
+            .map_err(|e| IndexerError::Storage(e.to_string()))?
+            .map_err(sqlite_error)
+    }
+}
+
+#[async_trait]
+impl StorageBackend for SqliteBackend {
+    async fn read(&self, hash: &str, name: &str) -> Result<Option<Vec<u8>>, IndexerError> {
+        let (hash, name) = (hash.to_string(), name.to_string());
+        self.with_conn(move |conn| {
+            conn.query_row(
+                "SELECT data FROM blobs WHERE hash = ?1 AND name = ?2",
+                params![hash, name],
+                |row| row.get(0),
+            )
+            .optional()
+        })
+        .await
+    }
+
+    async fn write(&self, hash: &str, name: &str, data: &[u8]) -> Result<(), IndexerError> {
+        let (hash, name, data) = (hash.to_string(), name.to_string(), data.to_vec());
+        self.with_conn(move |conn| {
+            conn.execute(
+                "INSERT INTO blobs (hash, name, data, modified_ms) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT (hash, name)
+                 DO UPDATE SET data = excluded.data, modified_ms = excluded.modified_ms",
+                params![hash, name, data, now_ms()],
+            )
+            .map(drop)
+        })
+        .await
+    }
+
+    async fn append(&self, hash: &str, name: &str, data: &[u8]) -> Result<(), IndexerError> {
+        let (hash, name, data) = (hash.to_string(), name.to_string(), data.to_vec());
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()?;
+            let mut blob: Vec<u8> = tx
+                .query_row(
+                    "SELECT data FROM blobs WHERE hash = ?1 AND name = ?2",
+                    params![hash, name],
+                    |row| row.get(0),
+                )
+                .optional()?
+                .unwrap_or_default();
+            blob.extend_from_slice(&data);
+            tx.execute(
+                "INSERT OR REPLACE INTO blobs (hash, name, data, modified_ms)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![hash, name, blob, now_ms()],
+            )?;
+            tx.commit()
+        })
+        .await
+    }
+
+    async fn remove(&self, hash: &str, name: &str) -> Result<(), IndexerError> {
+        let (hash, name) = (hash.to_string(), name.to_string());
+        self.with_conn(move |conn| {
+            conn.execute(
+                "DELETE FROM blobs WHERE hash = ?1 AND name = ?2",
+                params![hash, name],
+            )
+            .map(drop)
+        })
+        .await
+    }
+
+    async fn remove_project(&self, hash: &str) -> Result<(), IndexerError> {
+        let hash = hash.to_string();
+        self.with_conn(move |conn| {
+            conn.execute("DELETE FROM blobs WHERE hash = ?1", params![hash])
+                .map(drop)
+        })
+        .await
+    }
+
+    async fn modified(&self, hash: &str, name: &str) -> Option<SystemTime> {
+        let (hash, name) = (hash.to_string(), name.to_string());
+        let modified_ms: i64 = self
+            .with_conn(move |conn| {
+                conn.query_row(
+                    "SELECT modified_ms FROM blobs WHERE hash = ?1 AND name = ?2",
+                    params![hash, name],
+                    |row| row.get(0),
+                )
+                .optional()
+            })
+            .await
+            .ok()??;
+        Some(UNIX_EPOCH + Duration::from_millis(modified_ms.max(0) as u64))
+    }
+
+    fn location(&self, hash: &str, name: &str) -> PathBuf {
+        PathBuf::from(format!("{}#{}/{}", self.path.display(), hash, name))
+    }
+}
+
+/// Milliseconds since the Unix epoch.
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as i64)
+}
+
+fn sqlite_error(e: rusqlite::Error) -> IndexerError {
+    IndexerError::Storage(format!("SQLite: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_sqlite_backend() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("storage.db");
+        let backend = SqliteBackend::open(&path).unwrap();
+
+        assert_eq!(backend.read("abc", "skeleton.json").await.unwrap(), None);
+        assert!(backend.modified("abc", "skeleton.json").await.is_none());
+
+        backend.write("abc", "skeleton.json", b"{}").await.unwrap();
+        backend.write("abc", "skeleton.json", b"{ }").await.unwrap();
+        assert_eq!(
+            backend.read("abc", "skeleton.json").await.unwrap(),
+            Some(b"{ }".to_vec())
+        );
+        assert!(backend.modified("abc", "skeleton.json").await.is_some());
+        assert!(!temp_dir.path().join("abc").exists());
+
+        backend
+            .append("abc", "dependencies.log", b"+1 2\n")
+            .await
+            .unwrap();
+        backend
+            .append("abc", "dependencies.log", b"-1 2\n")
+            .await
+            .unwrap();
+        assert_eq!(
+            backend.read("abc", "dependencies.log").await.unwrap(),
+            Some(b"+1 2\n-1 2\n".to_vec())
+        );
+
+        backend.remove("abc", "skeleton.json").await.unwrap();
+        backend.remove("abc", "skeleton.json").await.unwrap();
+        assert_eq!(backend.read("abc", "skeleton.json").await.unwrap(), None);
+
+        // Blobs outlive the connection, and go with their project
+        backend.write("def", "skeleton.json", b"{}").await.unwrap();
+        drop(backend);
+        let backend = SqliteBackend::open(&path).unwrap();
+        assert!(backend
+            .read("abc", "dependencies.log")
+            .await
+            .unwrap()
+            .is_some());
+        backend.remove_project("abc").await.unwrap();
+        assert_eq!(backend.read("abc", "dependencies.log").await.unwrap(), None);
+        assert!(backend
+            .read("def", "skeleton.json")
+            .await
+            .unwrap()
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_storage_with_sqlite_backend() {
+        use crate::storage::Storage;
+        use crate::tree::Tree;
+
+        let temp_dir = tempdir().unwrap();
+        let backend = SqliteBackend::open(&temp_dir.path().join("storage.db")).unwrap();
+        let storage =
+            Storage::new(temp_dir.path().join("projects")).with_backend(Arc::new(backend));
+        let tree = Tree::new(temp_dir.path().join("project"));
+        let hash = storage.project_hash(&tree.root_path);
+
+        storage.save_skeleton(&tree, &hash).await.unwrap();
+        let loaded = storage.load_skeleton(&hash).await.unwrap();
+        assert_eq!(loaded.root_path, tree.root_path);
+        assert!(!storage.project_dir(&hash).join("skeleton.json").exists());
+    }
+}
@@ -0,0 +1,154 @@
+//! Structure-only, name-hashed tree export for bug reports.
+//!
+//! Keeps the shape of the tree (directories, files, sizes, languages and
+//! symbol counts) while replacing every name with a salted hash. File
+//! extensions are kept so language detection issues stay diagnosable.
+
+use super::{NodeId, NodeKind, Tree};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+/// A directory or file with its name hashed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnonymizedNode {
+    pub id: NodeId,
+    pub parent: Option<NodeId>,
+    /// `dir` or `file`
+    pub kind: String,
+    /// Salted hash of the name, plus the original extension for files
+    pub name: String,
+    /// Language name for files
+    pub language: Option<String>,
+    pub size: u64,
+    pub line_count: usize,
+    /// Number of symbols in the file
+    pub symbol_count: usize,
+}
+
+/// Export the directory/file structure of `tree` with hashed names.
+///
+/// Symbol nodes are folded into their file's `symbol_count`. The same salt
+/// always yields the same hashes, so use a fresh salt per export.
+pub fn anonymize_tree(tree: &Tree, salt: &str) -> Vec<AnonymizedNode> {
+    let mut nodes: Vec<AnonymizedNode> = tree
+        .nodes
+        .values()
+        .filter_map(|node| {
+            let (kind, language, size, line_count) = match &node.kind {
+                NodeKind::Directory => ("dir", None, 0, 0),
+                NodeKind::File {
+                    language,
+                    size,
+                    line_count,
+                    ..
+                } => (
+                    "file",
+                    language.map(|l| l.name().to_string()),
+                    *size,
+                    *line_count,
+                ),
+                NodeKind::Symbol { .. } => return None,
+            };
+
+            let symbol_count = node
+                .children
+                .iter()
+                .filter(|id| tree.get(**id).is_some_and(|child| child.is_symbol()))
+                .count();
+
+            Some(AnonymizedNode {
+                id: node.id,
+                parent: node.parent,
+                kind: kind.to_string(),
+                name: hash_name(&node.name, node.is_file(), salt),
+                language,
+                size,
+                line_count,
+                symbol_count,
+            })
+        })
+        .collect();
+
+    nodes.sort_by_key(|node| node.id);
+    nodes
+}
+
+fn hash_name(name: &str, keep_extension: bool, salt: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(salt.as_bytes());
+    hasher.update(name.as_bytes());
+    let digest = format!("{:x}", hasher.finalize());
+    let short = &digest[..12];
+
+    let extension = keep_extension
+        .then(|| Path::new(name).extension().and_then(|e| e.to_str()))
+        .flatten();
+    match extension {
+        Some(ext) => format!("{}.{}", short, ext),
+        None => short.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::{Language, ScanResult, ScannedFile, Symbol, SymbolKind};
+    use crate::tree::TreeBuilder;
+    use std::path::PathBuf;
+
+    fn test_tree() -> Tree {
+        let scan = ScanResult {
+            root: PathBuf::from("/secret/project"),
+            files: vec![ScannedFile {
+                path: PathBuf::from("billing/invoice.rs"),
+                language: Some(Language::Rust),
+                size: 120,
+                hash: "abc".to_string(),
+                line_count: 12,
+                symbols: vec![Symbol {
+                    name: "charge_customer".to_string(),
+                    kind: SymbolKind::Function,
+                    start_line: 1,
+                    end_line: 5,
+                    parent: None,
+                    doc: Some("Charges the customer".to_string()),
+                }],
+                imports: vec![],
+                truncated: false,
+                asset: None,
+            }],
+            languages: vec![Language::Rust],
+            frameworks: vec![],
+            duration_ms: 0,
+            skipped_count: 0,
+            excluded_dirs: vec![],
+        };
+        TreeBuilder::new().build(&scan)
+    }
+
+    #[test]
+    fn test_anonymize_hides_names() {
+        let nodes = anonymize_tree(&test_tree(), "salt");
+        let json = serde_json::to_string(&nodes).unwrap();
+
+        assert!(!json.contains("billing"));
+        assert!(!json.contains("invoice"));
+        assert!(!json.contains("charge_customer"));
+        assert!(!json.contains("secret"));
+
+        let file = nodes.iter().find(|n| n.kind == "file").unwrap();
+        assert!(file.name.ends_with(".rs"));
+        assert_eq!(file.symbol_count, 1);
+        assert_eq!(file.line_count, 12);
+        // root + billing dir + file; symbols are folded in
+        assert_eq!(nodes.len(), 3);
+    }
+
+    #[test]
+    fn test_salt_changes_hashes() {
+        let a = anonymize_tree(&test_tree(), "a");
+        let b = anonymize_tree(&test_tree(), "b");
+        assert_ne!(a[1].name, b[1].name);
+    }
+}
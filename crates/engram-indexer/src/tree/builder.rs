@@ -1,20 +1,56 @@
 //! Tree builder from scan results.
 
-use super::{Node, NodeContent, NodeId, NodeKind, Tree};
-use crate::scanner::ScanResult;
+use super::{link_tests, resolve_imports, Node, NodeContent, NodeId, NodeKind, Tree};
+use crate::git;
+use crate::scanner::{ScanPhase, ScanProgress, ScanResult, ScannedFile};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use tracing::debug;
 
 /// Builds a tree from scan results.
 pub struct TreeBuilder {
     next_id: NodeId,
+    /// Directory nodes by path, for efficient lookup
+    dirs: HashMap<PathBuf, NodeId>,
+    progress: Option<Arc<ScanProgress>>,
 }
 
 impl TreeBuilder {
     /// Create a new tree builder.
     pub fn new() -> Self {
-        Self { next_id: 1 } // 0 is reserved for root
+        Self {
+            next_id: 1, // 0 is reserved for root
+            dirs: HashMap::new(),
+            progress: None,
+        }
+    }
+
+    /// Create a builder that adds nodes to an existing tree.
+    pub fn extending(tree: &Tree) -> Self {
+        let dirs = tree
+            .nodes
+            .values()
+            .filter(|node| node.is_directory())
+            .map(|node| (node.path.clone(), node.id))
+            .collect();
+        Self {
+            next_id: tree.nodes.keys().max().map_or(1, |id| id + 1),
+            dirs,
+            progress: None,
+        }
+    }
+
+    /// Allocate new ids starting at `id` or later.
+    pub fn with_first_id(mut self, id: NodeId) -> Self {
+        self.next_id = self.next_id.max(id);
+        self
+    }
+
+    /// Report building as a phase of `progress`, one item per file.
+    pub fn with_progress(mut self, progress: Arc<ScanProgress>) -> Self {
+        self.progress = Some(progress);
+        self
     }
 
     /// Build a tree from scan results.
@@ -22,110 +58,121 @@ impl TreeBuilder {
         let mut tree = Tree::new(scan.root.clone());
         tree.languages = scan.languages.clone();
         tree.frameworks = scan.frameworks.clone();
+        tree.git = git::state(&scan.root);
 
-        // Track directory nodes by path for efficient lookup
-        let mut dir_map: HashMap<PathBuf, NodeId> = HashMap::new();
-        dir_map.insert(PathBuf::new(), tree.root_id);
-
-        let mut file_count = 0;
-        let mut symbol_count = 0;
+        self.dirs.clear();
+        self.dirs.insert(PathBuf::new(), tree.root_id);
 
+        if let Some(progress) = &self.progress {
+            progress.start_phase(ScanPhase::Building, scan.files.len());
+        }
         for file in &scan.files {
-            // Ensure parent directories exist
-            let parent_id = self.ensure_directories(&file.path, &mut tree, &mut dir_map);
-
-            // Create file node
-            let file_id = self.next_id();
-            let file_node = Node {
-                id: file_id,
-                name: file
-                    .path
-                    .file_name()
-                    .and_then(|n| n.to_str())
-                    .unwrap_or("unknown")
-                    .to_string(),
-                path: file.path.clone(),
-                kind: NodeKind::File {
-                    language: file.language,
-                    size: file.size,
-                    hash: file.hash.clone(),
-                    line_count: file.line_count,
+            self.add_file(&mut tree, file);
+            if let Some(progress) = &self.progress {
+                progress.advance();
+            }
+        }
+        resolve_imports(&mut tree);
+        link_tests(&mut tree);
+
+        debug!(
+            files = tree.file_count,
+            symbols = tree.symbol_count,
+            nodes = tree.nodes.len(),
+            "Tree built"
+        );
+
+        tree
+    }
+
+    /// Add a file and its symbols, creating parent directories as needed.
+    ///
+    /// The caller must make sure the path is not already in the tree. Its
+    /// imports are not resolved until [`resolve_imports`] runs.
+    pub fn add_file(&mut self, tree: &mut Tree, file: &ScannedFile) -> NodeId {
+        // Ensure parent directories exist
+        let parent_id = self.ensure_directories(&file.path, tree);
+
+        // Create file node
+        let file_id = self.next_id();
+        let file_node = Node {
+            id: file_id,
+            name: file
+                .path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("unknown")
+                .to_string(),
+            path: file.path.clone(),
+            kind: NodeKind::File {
+                language: file.language,
+                size: file.size,
+                hash: file.hash.clone(),
+                line_count: file.line_count,
+                truncated: file.truncated,
+                asset: file.asset,
+            },
+            parent: Some(parent_id),
+            children: Vec::new(),
+            content: Some(NodeContent {
+                summary: None,
+                tags: Vec::new(),
+                symbols: file.symbols.clone(),
+                line_count: file.line_count,
+                hash: file.hash.clone(),
+                imports: file.imports.clone(),
+            }),
+        };
+
+        // Add file to tree and parent's children
+        tree.nodes.insert(file_id, file_node);
+        if let Some(parent) = tree.nodes.get_mut(&parent_id) {
+            parent.children.push(file_id);
+        }
+
+        tree.file_count += 1;
+
+        // Create symbol nodes as children of the file
+        for symbol in &file.symbols {
+            let symbol_id = self.next_id();
+            let symbol_node = Node {
+                id: symbol_id,
+                name: symbol.name.clone(),
+                path: file.path.join(&symbol.name),
+                kind: NodeKind::Symbol {
+                    symbol_kind: symbol.kind,
+                    start_line: symbol.start_line,
+                    end_line: symbol.end_line,
                 },
-                parent: Some(parent_id),
+                parent: Some(file_id),
                 children: Vec::new(),
-                content: Some(NodeContent {
-                    summary: None,
+                content: symbol.doc.as_ref().map(|doc| NodeContent {
+                    summary: Some(doc.clone()),
                     tags: Vec::new(),
-                    symbols: file.symbols.clone(),
-                    line_count: file.line_count,
-                    hash: file.hash.clone(),
+                    symbols: Vec::new(),
+                    line_count: 0,
+                    hash: String::new(),
+                    imports: Vec::new(),
                 }),
             };
 
-            // Add file to tree and parent's children
-            tree.nodes.insert(file_id, file_node);
-            if let Some(parent) = tree.nodes.get_mut(&parent_id) {
-                parent.children.push(file_id);
+            tree.nodes.insert(symbol_id, symbol_node);
+            if let Some(file) = tree.nodes.get_mut(&file_id) {
+                file.children.push(symbol_id);
             }
 
-            file_count += 1;
-
-            // Create symbol nodes as children of the file
-            for symbol in &file.symbols {
-                let symbol_id = self.next_id();
-                let symbol_node = Node {
-                    id: symbol_id,
-                    name: symbol.name.clone(),
-                    path: file.path.join(&symbol.name),
-                    kind: NodeKind::Symbol {
-                        symbol_kind: symbol.kind,
-                        start_line: symbol.start_line,
-                        end_line: symbol.end_line,
-                    },
-                    parent: Some(file_id),
-                    children: Vec::new(),
-                    content: symbol.doc.as_ref().map(|doc| NodeContent {
-                        summary: Some(doc.clone()),
-                        tags: Vec::new(),
-                        symbols: Vec::new(),
-                        line_count: 0,
-                        hash: String::new(),
-                    }),
-                };
-
-                tree.nodes.insert(symbol_id, symbol_node);
-                if let Some(file) = tree.nodes.get_mut(&file_id) {
-                    file.children.push(symbol_id);
-                }
-
-                symbol_count += 1;
-            }
+            tree.symbol_count += 1;
         }
 
-        tree.file_count = file_count;
-        tree.symbol_count = symbol_count;
-
-        debug!(
-            files = file_count,
-            symbols = symbol_count,
-            nodes = tree.nodes.len(),
-            "Tree built"
-        );
-
-        tree
+        file_id
     }
 
     /// Ensure all parent directories exist for a path.
-    fn ensure_directories(
-        &mut self,
-        path: &Path,
-        tree: &mut Tree,
-        dir_map: &mut HashMap<PathBuf, NodeId>,
-    ) -> NodeId {
+    pub(super) fn ensure_directories(&mut self, path: &Path, tree: &mut Tree) -> NodeId {
         let parent_path = path.parent().unwrap_or(Path::new(""));
 
         // If parent already exists, return its ID
-        if let Some(&id) = dir_map.get(parent_path) {
+        if let Some(&id) = self.dirs.get(parent_path) {
             return id;
         }
 
@@ -136,7 +183,7 @@ impl TreeBuilder {
         for component in parent_path.components() {
             current_path.push(component);
 
-            if let Some(&id) = dir_map.get(&current_path) {
+            if let Some(&id) = self.dirs.get(&current_path) {
                 current_parent = id;
             } else {
                 // Create new directory node
@@ -160,7 +207,7 @@ impl TreeBuilder {
                     parent.children.push(dir_id);
                 }
 
-                dir_map.insert(current_path.clone(), dir_id);
+                self.dirs.insert(current_path.clone(), dir_id);
                 current_parent = dir_id;
             }
         }
@@ -204,6 +251,9 @@ mod tests {
                         parent: None,
                         doc: Some("Entry point".to_string()),
                     }],
+                    imports: vec![],
+                    truncated: false,
+                    asset: None,
                 },
                 ScannedFile {
                     path: PathBuf::from("src/lib.rs"),
@@ -212,12 +262,16 @@ mod tests {
                     hash: "def456".to_string(),
                     line_count: 20,
                     symbols: vec![],
+                    imports: vec![],
+                    truncated: false,
+                    asset: None,
                 },
             ],
             languages: vec![Language::Rust],
             frameworks: vec![],
             duration_ms: 100,
             skipped_count: 0,
+            excluded_dirs: vec![],
         }
     }
 
@@ -293,6 +347,7 @@ mod tests {
             frameworks: vec![],
             duration_ms: 0,
             skipped_count: 0,
+            excluded_dirs: vec![],
         };
 
         let mut builder = TreeBuilder::new();
@@ -314,11 +369,15 @@ mod tests {
                 hash: "xyz".to_string(),
                 line_count: 5,
                 symbols: vec![],
+                imports: vec![],
+                truncated: false,
+                asset: None,
             }],
             languages: vec![Language::Rust],
             frameworks: vec![],
             duration_ms: 10,
             skipped_count: 0,
+            excluded_dirs: vec![],
         };
 
         let mut builder = TreeBuilder::new();
@@ -0,0 +1,226 @@
+//! Callers and callees of a symbol.
+//!
+//! Read from source text rather than a parsed call graph: callers are the
+//! symbols enclosing a whole-word mention of the name, callees the names
+//! called (`name(`) within the symbol's lines that resolve to a declared
+//! symbol. As with [`analyze_impact`](super::analyze_impact), a same-named
+//! symbol in an unrelated file may be matched.
+
+use super::impact::{enclosing_symbols, reference_lines};
+use super::{NodeId, NodeKind, Tree};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Direct call relationships of a symbol.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SymbolCalls {
+    /// Symbols mentioning it, by file path and line
+    pub callers: Vec<NodeId>,
+    /// Symbols it calls, in order of first call
+    pub callees: Vec<NodeId>,
+}
+
+/// Find the direct callers and callees of the symbol node `symbol`.
+///
+/// `source` returns a file's text by its path relative to the project
+/// root; files it cannot read contribute no callers. A called name is
+/// resolved to a symbol of the same file first, then of a file it imports,
+/// then to the only symbol of that name in the project.
+pub fn symbol_calls(
+    tree: &Tree,
+    symbol: NodeId,
+    mut source: impl FnMut(&Path) -> Option<String>,
+) -> SymbolCalls {
+    let Some(node) = tree.get(symbol) else {
+        return SymbolCalls::default();
+    };
+    let (
+        Some(file),
+        NodeKind::Symbol {
+            start_line,
+            end_line,
+            ..
+        },
+    ) = (node.parent, &node.kind)
+    else {
+        return SymbolCalls::default();
+    };
+
+    let mut callers = Vec::new();
+    let mut own_text = None;
+    for file_node in tree.files() {
+        let Some(text) = source(&file_node.path) else {
+            continue;
+        };
+        let lines = reference_lines(&text, &node.name);
+        callers.extend(
+            enclosing_symbols(tree, file_node.id, &lines)
+                .into_iter()
+                .filter(|id| *id != symbol),
+        );
+        if file_node.id == file {
+            own_text = Some(text);
+        }
+    }
+    callers.sort_by_key(|id| {
+        let node = tree.get(*id);
+        let line = match node.map(|node| &node.kind) {
+            Some(NodeKind::Symbol { start_line, .. }) => *start_line,
+            _ => 0,
+        };
+        (
+            node.and_then(|node| tree.get(node.parent?))
+                .map(|file| file.path.clone()),
+            line,
+        )
+    });
+
+    let mut callees = Vec::new();
+    let mut seen = HashSet::new();
+    let body = own_text
+        .iter()
+        .flat_map(|text| text.lines().skip(start_line.saturating_sub(1)))
+        .take(end_line.saturating_sub(*start_line) + 1);
+    for line in body {
+        for name in called_names(line) {
+            if let Some(id) = resolve_call(tree, file, name) {
+                if id != symbol && seen.insert(id) {
+                    callees.push(id);
+                }
+            }
+        }
+    }
+
+    SymbolCalls { callers, callees }
+}
+
+/// Identifiers in `line` followed by an opening parenthesis.
+fn called_names(line: &str) -> Vec<&str> {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    let mut names = Vec::new();
+    let mut start = None;
+    for (index, c) in line.char_indices() {
+        match (start, is_ident(c)) {
+            (None, true) => start = Some(index),
+            (Some(from), false) => {
+                if line[index..].trim_start().starts_with('(')
+                    && !line[from..].starts_with(|c: char| c.is_ascii_digit())
+                {
+                    names.push(&line[from..index]);
+                }
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    names
+}
+
+/// Resolve a called name from `file`: its own symbols first, then those of
+/// files it imports, then a symbol unique to the project.
+fn resolve_call(tree: &Tree, file: NodeId, name: &str) -> Option<NodeId> {
+    let in_file = |file: NodeId| {
+        tree.children(file)
+            .into_iter()
+            .find(|node| node.is_symbol() && node.name == name)
+            .map(|node| node.id)
+    };
+    if let Some(id) = in_file(file) {
+        return Some(id);
+    }
+    if let Some(id) = tree.dependencies.imports(file).find_map(in_file) {
+        return Some(id);
+    }
+    let mut matches = tree.symbols().filter(|node| node.name == name);
+    match (matches.next(), matches.next()) {
+        (Some(node), None) => Some(node.id),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::{Language, ScanResult, ScannedFile};
+    use crate::tree::TreeBuilder;
+    use std::path::PathBuf;
+
+    fn file(path: &str, symbols: &[(&str, usize, usize)]) -> ScannedFile {
+        ScannedFile::for_test(path).with_functions(symbols)
+    }
+
+    fn source(path: &Path) -> Option<String> {
+        let text = match path.to_str()? {
+            "src/main.rs" => "fn main() {\n    run(1);\n}\n\nfn run(n: u32) {\n    let x = load (n);\n    helper();\n    println!(\"{}\", x);\n}\n\nfn helper() {}\n",
+            "src/config.rs" => "pub fn load(n: u32) -> u32 {\n    n\n}\n",
+            "src/other.rs" => "fn load() {}\n\nfn reload() {\n    run(2);\n}\n",
+            _ => return None,
+        };
+        Some(text.to_string())
+    }
+
+    fn test_tree() -> Tree {
+        let scan = ScanResult {
+            root: PathBuf::from("/project"),
+            files: vec![
+                file(
+                    "src/main.rs",
+                    &[("main", 1, 3), ("run", 5, 9), ("helper", 11, 11)],
+                ),
+                file("src/config.rs", &[("load", 1, 3)]),
+                file("src/other.rs", &[("load", 1, 1), ("reload", 3, 5)]),
+            ],
+            languages: vec![Language::Rust],
+            frameworks: vec![],
+            duration_ms: 0,
+            skipped_count: 0,
+            excluded_dirs: vec![],
+        };
+        let mut tree = TreeBuilder::new().build(&scan);
+        let id = |path: &str| tree.find_node_by_path(&PathBuf::from(path)).unwrap();
+        let (main, config) = (id("src/main.rs"), id("src/config.rs"));
+        tree.dependencies.add_edge(main, config);
+        tree
+    }
+
+    #[test]
+    fn test_symbol_calls() {
+        let tree = test_tree();
+        let run = tree
+            .find_symbol(&PathBuf::from("src/main.rs"), "run")
+            .unwrap();
+        let calls = symbol_calls(&tree, run, source);
+        let describe = |ids: &[NodeId]| {
+            ids.iter()
+                .map(|id| tree.get(*id).unwrap().path.to_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            describe(&calls.callers),
+            vec!["src/main.rs/main", "src/other.rs/reload"]
+        );
+        // `load` resolves through the import, not to the unrelated `load`
+        assert_eq!(
+            describe(&calls.callees),
+            vec!["src/config.rs/load", "src/main.rs/helper"]
+        );
+    }
+
+    #[test]
+    fn test_symbol_calls_not_a_symbol() {
+        let tree = test_tree();
+        let file = tree
+            .find_node_by_path(&PathBuf::from("src/main.rs"))
+            .unwrap();
+        assert_eq!(symbol_calls(&tree, file, source), SymbolCalls::default());
+    }
+
+    #[test]
+    fn test_called_names() {
+        assert_eq!(
+            called_names("let x = a.load (n) + f(g(1)) + 2(3); mac!(y)"),
+            vec!["load", "f", "g"]
+        );
+    }
+}
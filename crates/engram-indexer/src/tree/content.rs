@@ -0,0 +1,129 @@
+//! Lazily loaded node content.
+//!
+//! A tree loaded with a [`ContentHandle`] keeps only node structure in
+//! memory; summaries, tags and symbols are decoded from storage on first
+//! access. Recently used contents are kept in an LRU, so rendering the same
+//! focus twice does not decode it twice.
+
+use super::{NodeContent, NodeId};
+use crate::IndexerError;
+use lru::LruCache;
+use parking_lot::Mutex;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+
+/// Contents kept decoded by default.
+pub const DEFAULT_CONTENT_CACHE: usize = 1024;
+
+/// Storage that node contents are loaded from.
+pub trait ContentSource: Send + Sync {
+    /// Decode a node's content, if it has any.
+    fn load(&self, id: NodeId) -> Result<Option<NodeContent>, IndexerError>;
+}
+
+/// Loads node contents on demand, keeping the most recently used.
+pub struct ContentHandle {
+    source: Arc<dyn ContentSource>,
+    cache: Mutex<LruCache<NodeId, Option<Arc<NodeContent>>>>,
+}
+
+impl ContentHandle {
+    /// Create a handle keeping up to `capacity` contents decoded.
+    pub fn new(source: Arc<dyn ContentSource>, capacity: usize) -> Self {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+        Self {
+            source,
+            cache: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    /// Content of a node, loading it on first access.
+    ///
+    /// Content that fails to decode is logged and treated as missing.
+    pub fn get(&self, id: NodeId) -> Option<Arc<NodeContent>> {
+        if let Some(content) = self.cache.lock().get(&id) {
+            return content.clone();
+        }
+        let content = match self.source.load(id) {
+            Ok(content) => content.map(Arc::new),
+            Err(e) => {
+                tracing::warn!(node = id, error = %e, "Failed to load node content");
+                None
+            }
+        };
+        self.cache.lock().put(id, content.clone());
+        content
+    }
+
+    /// Number of contents currently decoded.
+    pub fn loaded(&self) -> usize {
+        self.cache.lock().len()
+    }
+}
+
+/// Content of a node, held by the tree or loaded through a handle.
+#[derive(Debug, Clone)]
+pub enum ContentRef<'a> {
+    Borrowed(&'a NodeContent),
+    Loaded(Arc<NodeContent>),
+}
+
+impl std::ops::Deref for ContentRef<'_> {
+    type Target = NodeContent;
+
+    fn deref(&self) -> &NodeContent {
+        match self {
+            ContentRef::Borrowed(content) => content,
+            ContentRef::Loaded(content) => content,
+        }
+    }
+}
+
+impl std::fmt::Debug for ContentHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ContentHandle")
+            .field("loaded", &self.loaded())
+            .field("capacity", &self.cache.lock().cap())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Content with a summary naming the node; counts loads.
+    #[derive(Default)]
+    struct CountingSource {
+        loads: AtomicUsize,
+    }
+
+    impl ContentSource for CountingSource {
+        fn load(&self, id: NodeId) -> Result<Option<NodeContent>, IndexerError> {
+            self.loads.fetch_add(1, Ordering::SeqCst);
+            Ok((id != 0).then(|| NodeContent {
+                summary: Some(format!("node {}", id)),
+                ..Default::default()
+            }))
+        }
+    }
+
+    #[test]
+    fn test_content_handle_caches() {
+        let source = Arc::new(CountingSource::default());
+        let handle = ContentHandle::new(source.clone(), 2);
+
+        assert_eq!(handle.get(1).unwrap().summary.as_deref(), Some("node 1"));
+        assert_eq!(handle.get(1).unwrap().summary.as_deref(), Some("node 1"));
+        assert!(handle.get(0).is_none());
+        assert!(handle.get(0).is_none());
+        assert_eq!(source.loads.load(Ordering::SeqCst), 2);
+
+        // Node 1 is evicted by node 2
+        handle.get(2);
+        assert_eq!(handle.loaded(), 2);
+        handle.get(1);
+        assert_eq!(source.loads.load(Ordering::SeqCst), 4);
+    }
+}
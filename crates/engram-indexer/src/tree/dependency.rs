@@ -2,7 +2,7 @@
 
 use super::NodeId;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 
 /// Tracks dependencies between files in the project.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -95,46 +95,131 @@ impl DependencyGraph {
         false
     }
 
-    /// Find all nodes involved in cycles.
+    /// Find groups of nodes that import each other, directly or through
+    /// other members.
+    ///
+    /// Each group is a strongly connected component with more than one
+    /// node, or a node importing itself. Members are sorted, and groups are
+    /// ordered by their first member.
     pub fn find_cycles(&self) -> Vec<Vec<NodeId>> {
-        let mut cycles = Vec::new();
-        let mut visited = HashSet::new();
-
-        for &node in self.imports.keys() {
-            if !visited.contains(&node) {
-                let mut path = Vec::new();
-                self.find_cycles_dfs(node, &mut visited, &mut path, &mut cycles);
+        let mut state = Tarjan::default();
+        for node in self.sorted_nodes() {
+            if !state.index.contains_key(&node) {
+                self.strong_connect(node, &mut state);
             }
         }
 
+        let mut cycles: Vec<Vec<NodeId>> = state
+            .components
+            .into_iter()
+            .filter(|component| {
+                component.len() > 1 || self.imports(component[0]).any(|n| n == component[0])
+            })
+            .map(|mut component| {
+                component.sort_unstable();
+                component
+            })
+            .collect();
+        cycles.sort();
         cycles
     }
 
-    fn find_cycles_dfs(
-        &self,
-        node: NodeId,
-        visited: &mut HashSet<NodeId>,
-        path: &mut Vec<NodeId>,
-        cycles: &mut Vec<Vec<NodeId>>,
-    ) {
-        if let Some(pos) = path.iter().position(|&n| n == node) {
-            // Found a cycle
-            cycles.push(path[pos..].to_vec());
-            return;
+    fn strong_connect(&self, node: NodeId, state: &mut Tarjan) {
+        let index = state.index.len();
+        state.index.insert(node, index);
+        state.lowlink.insert(node, index);
+        state.stack.push(node);
+        state.on_stack.insert(node);
+
+        for dep in self.imports(node) {
+            if !state.index.contains_key(&dep) {
+                self.strong_connect(dep, state);
+                let low = state.lowlink[&node].min(state.lowlink[&dep]);
+                state.lowlink.insert(node, low);
+            } else if state.on_stack.contains(&dep) {
+                let low = state.lowlink[&node].min(state.index[&dep]);
+                state.lowlink.insert(node, low);
+            }
         }
 
-        if visited.contains(&node) {
-            return;
+        if state.lowlink[&node] == state.index[&node] {
+            let mut component = Vec::new();
+            while let Some(member) = state.stack.pop() {
+                state.on_stack.remove(&member);
+                component.push(member);
+                if member == node {
+                    break;
+                }
+            }
+            state.components.push(component);
         }
+    }
 
-        path.push(node);
+    /// Order nodes so every node comes after the nodes it imports.
+    ///
+    /// Covers nodes with at least one edge; ties are broken by node id.
+    /// `None` if the graph has a cycle.
+    pub fn topological_order(&self) -> Option<Vec<NodeId>> {
+        let mut remaining: HashMap<NodeId, usize> = self
+            .sorted_nodes()
+            .into_iter()
+            .map(|node| (node, self.import_count(node)))
+            .collect();
+        let mut ready: BTreeSet<NodeId> = remaining
+            .iter()
+            .filter(|(_, count)| **count == 0)
+            .map(|(node, _)| *node)
+            .collect();
+
+        let mut order = Vec::with_capacity(remaining.len());
+        while let Some(node) = ready.pop_first() {
+            order.push(node);
+            for importer in self.imported_by(node) {
+                if let Some(count) = remaining.get_mut(&importer) {
+                    *count -= 1;
+                    if *count == 0 {
+                        ready.insert(importer);
+                    }
+                }
+            }
+        }
 
-        for dep in self.imports(node) {
-            self.find_cycles_dfs(dep, visited, path, cycles);
+        (order.len() == remaining.len()).then_some(order)
+    }
+
+    /// Nodes importing `node` directly or through up to `max_depth` import
+    /// hops, with their distance; nearest first, then by node id.
+    pub fn transitive_dependents(&self, node: NodeId, max_depth: usize) -> Vec<(NodeId, usize)> {
+        let mut seen = HashSet::from([node]);
+        let mut frontier = vec![node];
+        let mut dependents = Vec::new();
+
+        for depth in 1..=max_depth {
+            let mut next: Vec<NodeId> = frontier
+                .iter()
+                .flat_map(|&n| self.imported_by(n))
+                .filter(|&n| seen.insert(n))
+                .collect();
+            if next.is_empty() {
+                break;
+            }
+            next.sort_unstable();
+            dependents.extend(next.iter().map(|&n| (n, depth)));
+            frontier = next;
         }
 
-        path.pop();
-        visited.insert(node);
+        dependents
+    }
+
+    /// All nodes with edges, sorted.
+    fn sorted_nodes(&self) -> Vec<NodeId> {
+        let nodes: BTreeSet<NodeId> = self
+            .imports
+            .keys()
+            .chain(self.imported_by.keys())
+            .copied()
+            .collect();
+        nodes.into_iter().collect()
     }
 
     /// Remove all edges involving a node (when file is deleted).
@@ -170,6 +255,13 @@ impl DependencyGraph {
         }
     }
 
+    /// All edges as `(importer, imported)` pairs, in no particular order.
+    pub fn edges(&self) -> impl Iterator<Item = (NodeId, NodeId)> + '_ {
+        self.imports
+            .iter()
+            .flat_map(|(&from, targets)| targets.iter().map(move |&to| (from, to)))
+    }
+
     /// Get total number of edges.
     pub fn edge_count(&self) -> usize {
         self.imports.values().map(|s| s.len()).sum()
@@ -183,6 +275,16 @@ impl DependencyGraph {
     }
 }
 
+/// Working state of Tarjan's strongly connected components search.
+#[derive(Default)]
+struct Tarjan {
+    index: HashMap<NodeId, usize>,
+    lowlink: HashMap<NodeId, usize>,
+    stack: Vec<NodeId>,
+    on_stack: HashSet<NodeId>,
+    components: Vec<Vec<NodeId>>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -284,6 +386,51 @@ mod tests {
         assert_eq!(graph.edge_count(), 3);
     }
 
+    #[test]
+    fn test_find_cycles() {
+        let mut graph = DependencyGraph::new();
+        // 1 -> 2 -> 3 -> 1, sharing 2 with 2 -> 4 -> 2
+        graph.add_edge(1, 2);
+        graph.add_edge(2, 3);
+        graph.add_edge(3, 1);
+        graph.add_edge(2, 4);
+        graph.add_edge(4, 2);
+        graph.add_edge(5, 5);
+        graph.add_edge(6, 1);
+
+        assert_eq!(graph.find_cycles(), vec![vec![1, 2, 3, 4], vec![5]]);
+        assert!(graph.topological_order().is_none());
+    }
+
+    #[test]
+    fn test_topological_order() {
+        let mut graph = DependencyGraph::new();
+        graph.add_edge(1, 2);
+        graph.add_edge(1, 3);
+        graph.add_edge(2, 3);
+        graph.add_edge(4, 3);
+
+        assert!(graph.find_cycles().is_empty());
+        assert_eq!(graph.topological_order(), Some(vec![3, 2, 1, 4]));
+    }
+
+    #[test]
+    fn test_transitive_dependents() {
+        let mut graph = DependencyGraph::new();
+        graph.add_edge(2, 1);
+        graph.add_edge(3, 1);
+        graph.add_edge(4, 2);
+        graph.add_edge(5, 4);
+        graph.add_edge(1, 5); // cycle back to the start
+
+        assert_eq!(
+            graph.transitive_dependents(1, usize::MAX),
+            vec![(2, 1), (3, 1), (4, 2), (5, 3)]
+        );
+        assert_eq!(graph.transitive_dependents(1, 1), vec![(2, 1), (3, 1)]);
+        assert!(graph.transitive_dependents(1, 0).is_empty());
+    }
+
     #[test]
     fn test_serialization() {
         let mut graph = DependencyGraph::new();
@@ -0,0 +1,243 @@
+//! Differences between two trees.
+//!
+//! Files are matched by path and compared by content hash. Symbols are
+//! matched by file, name and kind, and compared only within modified files:
+//! a symbol counts as modified when its span grew or shrank. Results are
+//! sorted by path, then by position in the file, so the same two trees
+//! always give the same diff.
+
+use super::{NodeKind, Tree};
+use crate::scanner::SymbolKind;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// Files and symbols that differ between two trees.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TreeDiff {
+    pub added_files: Vec<PathBuf>,
+    pub removed_files: Vec<PathBuf>,
+    /// Files present in both trees whose content changed
+    pub modified_files: Vec<PathBuf>,
+    /// Symbols of modified or added files that are new
+    pub added_symbols: Vec<SymbolChange>,
+    /// Symbols of modified or removed files that are gone
+    pub removed_symbols: Vec<SymbolChange>,
+    /// Symbols of modified files whose span changed
+    pub modified_symbols: Vec<SymbolChange>,
+}
+
+impl TreeDiff {
+    /// Check whether the trees hold the same files.
+    pub fn is_empty(&self) -> bool {
+        self.added_files.is_empty()
+            && self.removed_files.is_empty()
+            && self.modified_files.is_empty()
+    }
+}
+
+/// A symbol that was added, removed or modified.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolChange {
+    /// File declaring the symbol, relative to the project root
+    pub file: PathBuf,
+    pub name: String,
+    pub kind: SymbolKind,
+    /// First line (1-based) in the tree that has the symbol; the new tree
+    /// for added and modified symbols
+    pub line: usize,
+}
+
+/// A file's hash and symbols, in file order.
+struct FileEntry {
+    hash: String,
+    symbols: Vec<(String, SymbolKind, usize, usize)>,
+}
+
+/// Compare `old` against `new`.
+pub fn diff_trees(old: &Tree, new: &Tree) -> TreeDiff {
+    let old_files = file_entries(old);
+    let new_files = file_entries(new);
+    let mut diff = TreeDiff::default();
+
+    for (path, old_entry) in &old_files {
+        match new_files.get(path) {
+            None => {
+                diff.removed_files.push(path.clone());
+                diff.removed_symbols.extend(
+                    old_entry
+                        .symbols
+                        .iter()
+                        .map(|symbol| symbol_change(path, symbol)),
+                );
+            }
+            Some(new_entry) if new_entry.hash != old_entry.hash => {
+                diff.modified_files.push(path.clone());
+                diff_symbols(path, old_entry, new_entry, &mut diff);
+            }
+            Some(_) => {}
+        }
+    }
+    for (path, new_entry) in &new_files {
+        if !old_files.contains_key(path) {
+            diff.added_files.push(path.clone());
+            diff.added_symbols.extend(
+                new_entry
+                    .symbols
+                    .iter()
+                    .map(|symbol| symbol_change(path, symbol)),
+            );
+        }
+    }
+
+    for symbols in [
+        &mut diff.added_symbols,
+        &mut diff.removed_symbols,
+        &mut diff.modified_symbols,
+    ] {
+        symbols.sort_by(|a, b| (&a.file, a.line, &a.name).cmp(&(&b.file, b.line, &b.name)));
+    }
+    diff
+}
+
+/// Files of a tree by path; `BTreeMap` keeps the diff ordered.
+fn file_entries(tree: &Tree) -> BTreeMap<PathBuf, FileEntry> {
+    tree.files()
+        .map(|file| {
+            let hash = match &file.kind {
+                NodeKind::File { hash, .. } => hash.clone(),
+                _ => String::new(),
+            };
+            let mut symbols: Vec<_> = tree
+                .children(file.id)
+                .into_iter()
+                .filter_map(|node| match node.kind {
+                    NodeKind::Symbol {
+                        symbol_kind,
+                        start_line,
+                        end_line,
+                    } => Some((node.name.clone(), symbol_kind, start_line, end_line)),
+                    _ => None,
+                })
+                .collect();
+            symbols.sort_by_key(|(name, _, start_line, _)| (*start_line, name.clone()));
+            (file.path.clone(), FileEntry { hash, symbols })
+        })
+        .collect()
+}
+
+/// Match the symbols of a modified file by name, kind and occurrence.
+fn diff_symbols(path: &Path, old: &FileEntry, new: &FileEntry, diff: &mut TreeDiff) {
+    let keyed = |entry: &FileEntry| {
+        let mut seen: HashMap<(String, SymbolKind), usize> = HashMap::new();
+        entry
+            .symbols
+            .iter()
+            .map(|symbol| {
+                let key = (symbol.0.clone(), symbol.1);
+                let nth = seen.entry(key.clone()).or_default();
+                *nth += 1;
+                ((key, *nth), symbol.clone())
+            })
+            .collect::<HashMap<_, _>>()
+    };
+    let old_symbols = keyed(old);
+    let new_symbols = keyed(new);
+
+    for (key, symbol) in &old_symbols {
+        if !new_symbols.contains_key(key) {
+            diff.removed_symbols.push(symbol_change(path, symbol));
+        }
+    }
+    for (key, symbol) in &new_symbols {
+        match old_symbols.get(key) {
+            None => diff.added_symbols.push(symbol_change(path, symbol)),
+            Some(old) if old.3 - old.2 != symbol.3 - symbol.2 => {
+                diff.modified_symbols.push(symbol_change(path, symbol))
+            }
+            Some(_) => {}
+        }
+    }
+}
+
+fn symbol_change(path: &Path, symbol: &(String, SymbolKind, usize, usize)) -> SymbolChange {
+    SymbolChange {
+        file: path.to_path_buf(),
+        name: symbol.0.clone(),
+        kind: symbol.1,
+        line: symbol.2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::{ScanResult, ScannedFile};
+    use crate::tree::TreeBuilder;
+
+    fn file(path: &str, hash: &str, symbols: &[(&str, usize, usize)]) -> ScannedFile {
+        ScannedFile {
+            language: None,
+            hash: hash.to_string(),
+            ..ScannedFile::for_test(path).with_functions(symbols)
+        }
+    }
+
+    fn tree(files: Vec<ScannedFile>) -> Tree {
+        TreeBuilder::new().build(&ScanResult {
+            root: PathBuf::from("/project"),
+            files,
+            languages: vec![],
+            frameworks: vec![],
+            duration_ms: 0,
+            skipped_count: 0,
+            excluded_dirs: vec![],
+        })
+    }
+
+    #[test]
+    fn test_diff_trees() {
+        let old = tree(vec![
+            file(
+                "src/a.rs",
+                "a1",
+                &[("keep", 1, 3), ("grow", 5, 6), ("gone", 8, 9)],
+            ),
+            file("src/b.rs", "b1", &[("b", 1, 2)]),
+            file("src/old.rs", "o1", &[("old", 1, 2)]),
+        ]);
+        let new = tree(vec![
+            file("src/new.rs", "n1", &[("new", 1, 2)]),
+            file(
+                "src/a.rs",
+                "a2",
+                &[("keep", 2, 4), ("grow", 6, 10), ("fresh", 12, 13)],
+            ),
+            file("src/b.rs", "b1", &[("b", 1, 2)]),
+        ]);
+
+        let diff = diff_trees(&old, &new);
+        assert_eq!(diff.added_files, vec![PathBuf::from("src/new.rs")]);
+        assert_eq!(diff.removed_files, vec![PathBuf::from("src/old.rs")]);
+        assert_eq!(diff.modified_files, vec![PathBuf::from("src/a.rs")]);
+
+        let names = |symbols: &[SymbolChange]| {
+            symbols
+                .iter()
+                .map(|s| format!("{}::{}", s.file.display(), s.name))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            names(&diff.added_symbols),
+            vec!["src/a.rs::fresh", "src/new.rs::new"]
+        );
+        assert_eq!(
+            names(&diff.removed_symbols),
+            vec!["src/a.rs::gone", "src/old.rs::old"]
+        );
+        assert_eq!(names(&diff.modified_symbols), vec!["src/a.rs::grow"]);
+        assert_eq!(diff.modified_symbols[0].line, 6);
+
+        assert!(diff_trees(&new, &new).is_empty());
+        assert_eq!(diff_trees(&new, &new), TreeDiff::default());
+    }
+}
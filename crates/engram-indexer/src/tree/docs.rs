@@ -0,0 +1,308 @@
+//! Markdown documentation generated from a tree.
+//!
+//! Renders one `README.md` page per directory, linking its subdirectories
+//! and listing each file with its summary and symbols. Enriched trees give
+//! the most useful pages; files without a summary are listed by name and
+//! symbols only. The mdBook layout puts the pages under `src/` and adds a
+//! `SUMMARY.md` table of contents and a `book.toml`.
+
+use super::{Node, NodeId, NodeKind, Tree};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// Name of the page rendered for each directory
+const PAGE_NAME: &str = "README.md";
+
+/// Layout of generated documentation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DocsFormat {
+    /// Plain Markdown pages, browsable on any code host
+    #[default]
+    Markdown,
+    /// An mdBook source tree
+    MdBook,
+}
+
+/// One generated file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocPage {
+    /// Path relative to the output directory
+    pub path: PathBuf,
+    /// File content
+    pub content: String,
+}
+
+/// Render documentation for every directory of `tree`, parents first.
+pub fn render_docs(tree: &Tree, format: DocsFormat) -> Vec<DocPage> {
+    let title = tree.root().name.clone();
+    let mut pages = Vec::new();
+    let mut toc = String::new();
+    render_directory(tree, tree.root_id, &title, 0, &mut pages, &mut toc);
+
+    if format == DocsFormat::MdBook {
+        for page in &mut pages {
+            page.path = Path::new("src").join(&page.path);
+        }
+        pages.push(DocPage {
+            path: PathBuf::from("src/SUMMARY.md"),
+            content: format!("# Summary\n\n[{}]({})\n\n{}", title, PAGE_NAME, toc),
+        });
+        pages.push(DocPage {
+            path: PathBuf::from("book.toml"),
+            content: format!("[book]\ntitle = {:?}\nsrc = \"src\"\n", title),
+        });
+    }
+    pages
+}
+
+/// Render the page of a directory and its subdirectories, adding their
+/// entries to the mdBook table of contents.
+fn render_directory(
+    tree: &Tree,
+    id: NodeId,
+    title: &str,
+    depth: usize,
+    pages: &mut Vec<DocPage>,
+    toc: &mut String,
+) {
+    let Some(dir) = tree.get(id) else {
+        return;
+    };
+    let (mut dirs, mut files): (Vec<&Node>, Vec<&Node>) = tree
+        .children(id)
+        .into_iter()
+        .filter(|child| !child.is_symbol())
+        .partition(|child| child.is_directory());
+    dirs.sort_by(|a, b| a.name.cmp(&b.name));
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut out = format!("# {}\n\n", title);
+    if let Some(parent) = dir.parent.and_then(|parent| tree.get(parent)) {
+        let name = if parent.id == tree.root_id {
+            &tree.root().name
+        } else {
+            &parent.name
+        };
+        let _ = writeln!(out, "Up: [{}](../{})\n", name, PAGE_NAME);
+    }
+
+    if !dirs.is_empty() {
+        out.push_str("## Directories\n\n");
+        for sub in &dirs {
+            let count = file_count(tree, sub.id);
+            let _ = writeln!(
+                out,
+                "- [{}/]({}/{}) — {} file{}",
+                sub.name,
+                sub.name,
+                PAGE_NAME,
+                count,
+                if count == 1 { "" } else { "s" }
+            );
+        }
+        out.push('\n');
+    }
+
+    if !files.is_empty() {
+        out.push_str("## Files\n");
+        for file in &files {
+            render_file(tree, file, &mut out);
+        }
+    }
+
+    pages.push(DocPage {
+        path: dir.path.join(PAGE_NAME),
+        content: out,
+    });
+
+    for sub in dirs {
+        let _ = writeln!(
+            toc,
+            "{}- [{}]({})",
+            "  ".repeat(depth),
+            sub.name,
+            sub.path.join(PAGE_NAME).display()
+        );
+        let title = sub.path.display().to_string();
+        render_directory(tree, sub.id, &title, depth + 1, pages, toc);
+    }
+}
+
+/// Render a file's section: its summary and symbols in source order.
+fn render_file(tree: &Tree, file: &Node, out: &mut String) {
+    let _ = writeln!(out, "\n### `{}`\n", file.name);
+    if let NodeKind::File {
+        language,
+        line_count,
+        ..
+    } = &file.kind
+    {
+        let language = language.map_or("Other", |l| l.name());
+        let _ = writeln!(out, "_{} · {} lines_\n", language, line_count);
+    }
+
+    let summary = tree
+        .content(file.id)
+        .and_then(|content| content.summary.clone());
+    if let Some(summary) = summary {
+        let _ = writeln!(out, "{}\n", summary.trim());
+    }
+
+    let mut symbols: Vec<(&Node, usize, usize)> = tree
+        .children(file.id)
+        .into_iter()
+        .filter_map(|node| match node.kind {
+            NodeKind::Symbol {
+                start_line,
+                end_line,
+                ..
+            } => Some((node, start_line, end_line)),
+            _ => None,
+        })
+        .collect();
+    symbols.sort_by_key(|(node, start, _)| (*start, node.name.clone()));
+    for (node, start, end) in symbols {
+        let NodeKind::Symbol { symbol_kind, .. } = node.kind else {
+            continue;
+        };
+        let _ = write!(
+            out,
+            "- `{}` ({}, lines {}-{})",
+            node.name,
+            format!("{:?}", symbol_kind).to_lowercase(),
+            start,
+            end
+        );
+        let doc = tree
+            .content(node.id)
+            .and_then(|content| content.summary.clone());
+        match doc.as_deref().and_then(|doc| doc.lines().next()) {
+            Some(line) => {
+                let _ = writeln!(out, " — {}", line.trim());
+            }
+            None => out.push('\n'),
+        }
+    }
+}
+
+/// Files below a directory, at any depth.
+fn file_count(tree: &Tree, id: NodeId) -> usize {
+    tree.children(id)
+        .into_iter()
+        .map(|child| match child.kind {
+            NodeKind::Directory => file_count(tree, child.id),
+            NodeKind::File { .. } => 1,
+            NodeKind::Symbol { .. } => 0,
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::{Language, ScanResult, ScannedFile, Symbol, SymbolKind};
+    use crate::tree::TreeBuilder;
+
+    fn file(path: &str, symbols: Vec<Symbol>) -> ScannedFile {
+        ScannedFile {
+            symbols,
+            ..ScannedFile::for_test(path)
+        }
+    }
+
+    fn symbol(name: &str, start_line: usize, doc: Option<&str>) -> Symbol {
+        Symbol {
+            name: name.to_string(),
+            kind: SymbolKind::Function,
+            start_line,
+            end_line: start_line + 1,
+            parent: None,
+            doc: doc.map(str::to_string),
+        }
+    }
+
+    fn tree() -> Tree {
+        let scan = ScanResult {
+            root: PathBuf::from("/project"),
+            files: vec![
+                file(
+                    "src/lib.rs",
+                    vec![
+                        symbol("save", 8, None),
+                        symbol("load", 2, Some("Read the config")),
+                    ],
+                ),
+                file("src/cli/main.rs", vec![]),
+                file("build.rs", vec![]),
+            ],
+            languages: vec![Language::Rust],
+            frameworks: vec![],
+            duration_ms: 0,
+            skipped_count: 0,
+            excluded_dirs: vec![],
+        };
+        let mut tree = TreeBuilder::new().build(&scan);
+        let lib = tree
+            .find_node_by_path(&PathBuf::from("src/lib.rs"))
+            .unwrap();
+        tree.content_mut(lib).unwrap().summary = Some("Config loading.".to_string());
+        tree
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let pages = render_docs(&tree(), DocsFormat::Markdown);
+        let paths: Vec<_> = pages.iter().map(|p| p.path.clone()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("README.md"),
+                PathBuf::from("src/README.md"),
+                PathBuf::from("src/cli/README.md"),
+            ]
+        );
+
+        let root = &pages[0].content;
+        assert!(root.starts_with("# project\n"));
+        assert!(root.contains("- [src/](src/README.md) — 2 files"));
+        assert!(root.contains("### `build.rs`"));
+        assert!(!root.contains("Up:"));
+
+        let src = &pages[1].content;
+        assert!(src.starts_with("# src\n"));
+        assert!(src.contains("Up: [project](../README.md)"));
+        assert!(src.contains("- [cli/](cli/README.md) — 1 file\n"));
+        assert!(src.contains("Config loading."));
+        let load = src
+            .find("`load` (function, lines 2-3) — Read the config")
+            .unwrap();
+        let save = src.find("`save` (function, lines 8-9)\n").unwrap();
+        assert!(load < save);
+
+        assert!(pages[2].content.starts_with("# src/cli\n"));
+        assert!(pages[2].content.contains("Up: [src](../README.md)"));
+    }
+
+    #[test]
+    fn test_render_mdbook() {
+        let pages = render_docs(&tree(), DocsFormat::MdBook);
+        assert!(pages
+            .iter()
+            .any(|page| page.path == Path::new("src/src/cli/README.md")));
+
+        let summary = pages
+            .iter()
+            .find(|page| page.path == Path::new("src/SUMMARY.md"))
+            .unwrap();
+        assert_eq!(
+            summary.content,
+            "# Summary\n\n[project](README.md)\n\n\
+             - [src](src/README.md)\n  - [cli](src/cli/README.md)\n"
+        );
+        let book = pages
+            .iter()
+            .find(|page| page.path == Path::new("book.toml"))
+            .unwrap();
+        assert!(book.content.contains("title = \"project\""));
+    }
+}
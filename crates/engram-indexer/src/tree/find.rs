@@ -0,0 +1,278 @@
+//! Fuzzy matching of file paths.
+//!
+//! Scores a path against a pattern the way fzf does: each whitespace
+//! separated term must appear in the path as a subsequence, and matches
+//! score higher when they start a path segment or word, follow one another
+//! and leave few gaps. Terms containing an uppercase letter match case
+//! sensitively. Used to resolve vague references ("the auth middleware")
+//! into concrete files.
+
+use super::{NodeId, Tree};
+use std::cmp::Reverse;
+use std::path::Path;
+
+const SCORE_MATCH: i64 = 16;
+const SCORE_GAP_START: i64 = -3;
+const SCORE_GAP_EXTENSION: i64 = -1;
+
+/// Bonus for a match starting a path segment
+const BONUS_SEGMENT: i64 = 9;
+/// Bonus for a match starting a word after `_`, `-`, `.` or a space
+const BONUS_BOUNDARY: i64 = 8;
+/// Bonus for a match starting a camelCase hump or a number
+const BONUS_CAMEL: i64 = 7;
+/// Bonus for a match following another one
+const BONUS_CONSECUTIVE: i64 = -(SCORE_GAP_START + SCORE_GAP_EXTENSION);
+/// The bonus of a term's first character counts this many times
+const FIRST_CHAR_MULTIPLIER: i64 = 2;
+
+/// A file matching a pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileMatch {
+    /// File node
+    pub file: NodeId,
+    /// Match score, higher is better
+    pub score: i64,
+}
+
+/// Find the files of `tree` whose paths match `pattern`, best first.
+///
+/// Ties go to the shorter path, then by path. An empty pattern matches
+/// every file with a score of 0.
+pub fn find_files(tree: &Tree, pattern: &str, limit: usize) -> Vec<FileMatch> {
+    let terms: Vec<&str> = pattern.split_whitespace().collect();
+    let mut matches: Vec<(FileMatch, &Path)> = tree
+        .files()
+        .filter_map(|node| {
+            let path = node.path.to_string_lossy().replace('\\', "/");
+            let text: Vec<char> = path.chars().collect();
+            let score = terms
+                .iter()
+                .map(|term| fuzzy_score(term, &text))
+                .sum::<Option<i64>>()?;
+            Some((
+                FileMatch {
+                    file: node.id,
+                    score,
+                },
+                node.path.as_path(),
+            ))
+        })
+        .collect();
+
+    matches.sort_by_key(|(m, path)| (Reverse(m.score), path.as_os_str().len(), *path));
+    matches.truncate(limit);
+    matches.into_iter().map(|(m, _)| m).collect()
+}
+
+/// Score `text` against one term, or `None` if the term is not a
+/// subsequence of it.
+fn fuzzy_score(term: &str, text: &[char]) -> Option<i64> {
+    let case_sensitive = term.chars().any(char::is_uppercase);
+    let fold = |c: char| {
+        if case_sensitive {
+            c
+        } else {
+            c.to_ascii_lowercase()
+        }
+    };
+    let pattern: Vec<char> = term.chars().map(fold).collect();
+    if pattern.is_empty() {
+        return Some(0);
+    }
+
+    // Earliest end of a match, then the latest start leading to it, so the
+    // scored window is as short as a greedy scan allows
+    let mut pidx = 0;
+    let mut end = None;
+    for (idx, &c) in text.iter().enumerate() {
+        if fold(c) == pattern[pidx] {
+            pidx += 1;
+            if pidx == pattern.len() {
+                end = Some(idx + 1);
+                break;
+            }
+        }
+    }
+    let end = end?;
+    let mut pidx = pattern.len();
+    let mut start = end;
+    while pidx > 0 {
+        start -= 1;
+        if fold(text[start]) == pattern[pidx - 1] {
+            pidx -= 1;
+        }
+    }
+
+    let mut score = 0;
+    let mut consecutive = 0;
+    let mut first_bonus = 0;
+    let mut in_gap = false;
+    let mut prev = start
+        .checked_sub(1)
+        .map_or(CharClass::Delimiter, |idx| CharClass::of(text[idx]));
+    for &c in &text[start..end] {
+        let class = CharClass::of(c);
+        if pidx < pattern.len() && fold(c) == pattern[pidx] {
+            score += SCORE_MATCH;
+            let mut bonus = bonus(prev, class);
+            if consecutive == 0 {
+                first_bonus = bonus;
+            } else {
+                // A run keeps the bonus of the boundary it started at
+                if bonus >= BONUS_BOUNDARY && bonus > first_bonus {
+                    first_bonus = bonus;
+                }
+                bonus = bonus.max(first_bonus).max(BONUS_CONSECUTIVE);
+            }
+            score += if pidx == 0 {
+                bonus * FIRST_CHAR_MULTIPLIER
+            } else {
+                bonus
+            };
+            in_gap = false;
+            consecutive += 1;
+            pidx += 1;
+        } else {
+            score += if in_gap {
+                SCORE_GAP_EXTENSION
+            } else {
+                SCORE_GAP_START
+            };
+            in_gap = true;
+            consecutive = 0;
+            first_bonus = 0;
+        }
+        prev = class;
+    }
+    Some(score)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CharClass {
+    Delimiter,
+    NonWord,
+    Lower,
+    Upper,
+    Number,
+}
+
+impl CharClass {
+    fn of(c: char) -> Self {
+        if c == '/' {
+            CharClass::Delimiter
+        } else if c.is_lowercase() {
+            CharClass::Lower
+        } else if c.is_uppercase() {
+            CharClass::Upper
+        } else if c.is_numeric() {
+            CharClass::Number
+        } else if c.is_alphabetic() {
+            CharClass::Lower
+        } else {
+            CharClass::NonWord
+        }
+    }
+
+    fn is_word(self) -> bool {
+        matches!(
+            self,
+            CharClass::Lower | CharClass::Upper | CharClass::Number
+        )
+    }
+}
+
+/// Bonus for matching a character of class `class` following one of class
+/// `prev`.
+fn bonus(prev: CharClass, class: CharClass) -> i64 {
+    match (prev, class) {
+        (CharClass::Delimiter, class) if class.is_word() => BONUS_SEGMENT,
+        (CharClass::NonWord, class) if class.is_word() => BONUS_BOUNDARY,
+        (CharClass::Lower, CharClass::Upper) => BONUS_CAMEL,
+        (prev, CharClass::Number) if prev != CharClass::Number => BONUS_CAMEL,
+        (_, CharClass::Delimiter | CharClass::NonWord) => BONUS_BOUNDARY,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::{Language, ScanResult, ScannedFile};
+    use crate::tree::TreeBuilder;
+    use std::path::PathBuf;
+
+    fn chars(text: &str) -> Vec<char> {
+        text.chars().collect()
+    }
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert_eq!(fuzzy_score("xyz", &chars("src/auth.rs")), None);
+        assert_eq!(fuzzy_score("", &chars("src/auth.rs")), Some(0));
+
+        // Segment starts beat matches inside a word
+        let start = fuzzy_score("auth", &chars("src/auth.rs")).unwrap();
+        let inside = fuzzy_score("auth", &chars("src/oauthlib.rs")).unwrap();
+        assert!(start > inside);
+
+        // Consecutive characters beat scattered ones
+        let run = fuzzy_score("mid", &chars("src/middleware.rs")).unwrap();
+        let scattered = fuzzy_score("mid", &chars("src/main_index_data.rs")).unwrap();
+        assert!(run > scattered);
+
+        // camelCase humps count as word starts
+        let camel = fuzzy_score("am", &chars("src/authMiddleware.ts")).unwrap();
+        let flat = fuzzy_score("am", &chars("src/authmiddleware.ts")).unwrap();
+        assert!(camel > flat);
+
+        // Smart case
+        assert!(fuzzy_score("readme", &chars("README.md")).is_some());
+        assert_eq!(fuzzy_score("Readme", &chars("README.md")), None);
+    }
+
+    #[test]
+    fn test_find_files() {
+        let file = |path: &str| ScannedFile {
+            language: Some(Language::TypeScript),
+            ..ScannedFile::for_test(path)
+        };
+        let scan = ScanResult {
+            root: PathBuf::from("/project"),
+            files: vec![
+                file("src/middleware/auth.ts"),
+                file("src/middleware/cors.ts"),
+                file("src/auth/login.ts"),
+                file("src/mail/attachments.ts"),
+                file("README.md"),
+            ],
+            languages: vec![Language::TypeScript],
+            frameworks: vec![],
+            duration_ms: 0,
+            skipped_count: 0,
+            excluded_dirs: vec![],
+        };
+        let tree = TreeBuilder::new().build(&scan);
+        let paths = |pattern: &str, limit| -> Vec<PathBuf> {
+            find_files(&tree, pattern, limit)
+                .into_iter()
+                .map(|m| tree.get(m.file).unwrap().path.clone())
+                .collect()
+        };
+
+        // Every term must match
+        assert_eq!(
+            paths("auth middleware", 10),
+            vec![PathBuf::from("src/middleware/auth.ts")]
+        );
+        assert_eq!(
+            paths("auth", 2),
+            vec![
+                PathBuf::from("src/auth/login.ts"),
+                PathBuf::from("src/middleware/auth.ts"),
+            ]
+        );
+        assert!(paths("nothing", 10).is_empty());
+        assert_eq!(paths("", 10).len(), 5);
+    }
+}
@@ -0,0 +1,245 @@
+//! Impact of changing a symbol.
+//!
+//! Combines where a symbol is declared, which files import those
+//! declarations, and which files mention the symbol by name, to estimate
+//! what a rename or signature change would touch. Text matches are
+//! heuristic: a same-named local in an unrelated file is reported too.
+
+use super::{NodeId, NodeKind, Tree};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// Files and symbols likely affected by changing a symbol.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImpactReport {
+    /// Symbol nodes declaring the symbol
+    pub definitions: Vec<NodeId>,
+    /// Affected files: declaring files first, then by import distance
+    pub files: Vec<ImpactedFile>,
+}
+
+/// A file likely affected by a change.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImpactedFile {
+    /// File node
+    pub file: NodeId,
+    /// Import distance from a declaring file (0 for the declaring file);
+    /// `None` if the file only mentions the symbol
+    pub depth: Option<usize>,
+    /// Lines (1-based) mentioning the symbol
+    pub lines: Vec<usize>,
+    /// Symbols in this file enclosing those lines
+    pub symbols: Vec<NodeId>,
+}
+
+/// Estimate what changing `symbol` affects.
+///
+/// `symbol` may be qualified (`Session::validate`); declarations and text
+/// references are matched on its last segment. A file is reported if it
+/// declares the symbol, imports a declaring file directly, or mentions
+/// the symbol as a whole word. `source` returns a file's text by its path
+/// relative to the project root; files it cannot read are only reported
+/// through declarations and imports.
+pub fn analyze_impact(
+    tree: &Tree,
+    symbol: &str,
+    mut source: impl FnMut(&Path) -> Option<String>,
+) -> ImpactReport {
+    let name = symbol.rsplit("::").next().unwrap_or(symbol);
+    if name.is_empty() {
+        return ImpactReport::default();
+    }
+
+    let mut definitions: Vec<NodeId> = tree
+        .symbols()
+        .filter(|node| node.name == name)
+        .map(|node| node.id)
+        .collect();
+    definitions.sort_unstable();
+
+    // Import distance of every file depending on a declaring file
+    let mut depths: HashMap<NodeId, usize> = HashMap::new();
+    for file in definitions
+        .iter()
+        .filter_map(|id| tree.get(*id)?.parent)
+        .collect::<Vec<_>>()
+    {
+        depths.insert(file, 0);
+        for (dependent, depth) in tree.dependencies.transitive_dependents(file, usize::MAX) {
+            let entry = depths.entry(dependent).or_insert(depth);
+            *entry = (*entry).min(depth);
+        }
+    }
+
+    let mut files = Vec::new();
+    for file in tree.files() {
+        let depth = depths.get(&file.id).copied();
+        let lines = source(&file.path)
+            .map(|text| reference_lines(&text, name))
+            .unwrap_or_default();
+        if lines.is_empty() && !matches!(depth, Some(0 | 1)) {
+            continue;
+        }
+        files.push(ImpactedFile {
+            file: file.id,
+            depth,
+            symbols: enclosing_symbols(tree, file.id, &lines),
+            lines,
+        });
+    }
+    files.sort_by_key(|file| {
+        (
+            file.depth.unwrap_or(usize::MAX),
+            tree.get(file.file).map(|node| node.path.clone()),
+        )
+    });
+
+    ImpactReport { definitions, files }
+}
+
+/// Lines (1-based) containing `name` as a whole identifier.
+pub(super) fn reference_lines(text: &str, name: &str) -> Vec<usize> {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            line.match_indices(name).any(|(start, _)| {
+                let before = line[..start].chars().next_back();
+                let after = line[start + name.len()..].chars().next();
+                !before.is_some_and(is_ident) && !after.is_some_and(is_ident)
+            })
+        })
+        .map(|(index, _)| index + 1)
+        .collect()
+}
+
+/// The innermost symbol of `file` enclosing each line, de-duplicated.
+pub(super) fn enclosing_symbols(tree: &Tree, file: NodeId, lines: &[usize]) -> Vec<NodeId> {
+    let spans: Vec<(NodeId, usize, usize)> = tree
+        .children(file)
+        .into_iter()
+        .filter_map(|node| match node.kind {
+            NodeKind::Symbol {
+                start_line,
+                end_line,
+                ..
+            } => Some((node.id, start_line, end_line)),
+            _ => None,
+        })
+        .collect();
+
+    // Keyed by start line to list symbols in file order
+    let mut enclosing = BTreeMap::new();
+    for &line in lines {
+        if let Some(&(id, start, _)) = spans
+            .iter()
+            .filter(|(_, start, end)| (*start..=*end).contains(&line))
+            .min_by_key(|(_, start, end)| end - start)
+        {
+            enclosing.insert((start, id), id);
+        }
+    }
+    enclosing.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::{Language, ScanResult, ScannedFile};
+    use crate::tree::TreeBuilder;
+    use std::path::PathBuf;
+
+    fn file(path: &str, symbols: &[(&str, usize, usize)]) -> ScannedFile {
+        ScannedFile::for_test(path).with_functions(symbols)
+    }
+
+    fn test_tree() -> Tree {
+        let scan = ScanResult {
+            root: PathBuf::from("/project"),
+            files: vec![
+                file("src/config.rs", &[("parse_config", 1, 5)]),
+                file("src/main.rs", &[("main", 1, 3), ("run", 5, 9)]),
+                file("src/app.rs", &[]),
+                file("src/cli.rs", &[("usage", 1, 4)]),
+                file("src/other.rs", &[]),
+            ],
+            languages: vec![Language::Rust],
+            frameworks: vec![],
+            duration_ms: 0,
+            skipped_count: 0,
+            excluded_dirs: vec![],
+        };
+        let mut tree = TreeBuilder::new().build(&scan);
+        let id = |path: &str| tree.find_node_by_path(&PathBuf::from(path)).unwrap();
+        let (config, main, app) = (id("src/config.rs"), id("src/main.rs"), id("src/app.rs"));
+        tree.dependencies.add_edge(main, config);
+        tree.dependencies.add_edge(app, main);
+        tree
+    }
+
+    fn source(path: &Path) -> Option<String> {
+        let text = match path.to_str()? {
+            "src/config.rs" => "pub fn parse_config() {}\n",
+            "src/main.rs" => "fn main() {\n    run();\n}\n\nfn run() {\n    parse_config();\n}\n",
+            "src/app.rs" => "use crate::main;\n",
+            "src/cli.rs" => "// see config::parse_config\n",
+            "src/other.rs" => "fn parse_config_v2() {}\n",
+            _ => return None,
+        };
+        Some(text.to_string())
+    }
+
+    #[test]
+    fn test_analyze_impact() {
+        let tree = test_tree();
+        let report = analyze_impact(&tree, "config::parse_config", source);
+        let path = |id: NodeId| tree.get(id).unwrap().path.to_str().unwrap().to_string();
+        let name = |id: NodeId| tree.get(id).unwrap().name.clone();
+
+        assert_eq!(report.definitions.len(), 1);
+        assert_eq!(path(report.definitions[0]), "src/config.rs/parse_config");
+
+        let files: Vec<_> = report
+            .files
+            .iter()
+            .map(|file| (path(file.file), file.depth, file.lines.clone()))
+            .collect();
+        assert_eq!(
+            files,
+            vec![
+                ("src/config.rs".to_string(), Some(0), vec![1]),
+                ("src/main.rs".to_string(), Some(1), vec![6]),
+                ("src/cli.rs".to_string(), None, vec![1]),
+            ]
+        );
+        let main = &report.files[1];
+        assert_eq!(
+            main.symbols.iter().map(|id| name(*id)).collect::<Vec<_>>(),
+            vec!["run"]
+        );
+        assert_eq!(
+            report.files[2]
+                .symbols
+                .iter()
+                .map(|id| name(*id))
+                .collect::<Vec<_>>(),
+            vec!["usage"]
+        );
+    }
+
+    #[test]
+    fn test_analyze_impact_unknown_symbol() {
+        let tree = test_tree();
+        let report = analyze_impact(&tree, "missing", source);
+        assert_eq!(report, ImpactReport::default());
+        assert_eq!(analyze_impact(&tree, "", source), ImpactReport::default());
+    }
+
+    #[test]
+    fn test_reference_lines() {
+        assert_eq!(
+            reference_lines("a foo\nfoobar\n_foo\nfoo()", "foo"),
+            vec![1, 4]
+        );
+    }
+}
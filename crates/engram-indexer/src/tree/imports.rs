@@ -0,0 +1,658 @@
+//! Resolution of parsed imports to file nodes.
+//!
+//! File nodes keep their import specifiers unresolved, so the dependency
+//! graph can be rebuilt from them whenever files come and go. Resolution is
+//! path based and best effort: specifiers naming code outside the project
+//! (crates, npm packages, the Python and Go standard libraries) match no file
+//! and are skipped.
+//!
+//! C/C++ headers are also linked to their implementation: a header depends on
+//! the source file of the same name, so following a file's includes reaches
+//! the code behind the declarations it uses.
+
+use super::{DependencyGraph, NodeId, NodeKind, Tree};
+use crate::scanner::Language;
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::path::{Component, Path, PathBuf};
+
+/// Extensions tried for extensionless TypeScript/JavaScript imports
+const SCRIPT_EXTENSIONS: &[&str] = &["ts", "tsx", "d.ts", "js", "jsx", "mjs", "cjs"];
+
+/// C/C++ header extensions
+const HEADER_EXTENSIONS: &[&str] = &["h", "hh", "hpp", "hxx", "h++"];
+
+/// C/C++ implementation extensions
+const SOURCE_EXTENSIONS: &[&str] = &["c", "cc", "cpp", "cxx", "c++"];
+
+/// Rebuild the dependency graph from the imports of every file.
+pub fn resolve_imports(tree: &mut Tree) {
+    let index = FileIndex::new(tree);
+    let mut graph = DependencyGraph::new();
+
+    for node in tree.files() {
+        let (
+            NodeKind::File {
+                language: Some(language),
+                ..
+            },
+            Some(content),
+        ) = (&node.kind, &node.content)
+        else {
+            continue;
+        };
+        for spec in &content.imports {
+            for target in index.resolve(&node.path, *language, spec) {
+                if target != node.id {
+                    graph.add_edge(node.id, target);
+                }
+            }
+        }
+    }
+
+    for (header, source) in index.header_sources() {
+        graph.add_edge(header, source);
+    }
+
+    tree.dependencies = graph;
+}
+
+/// File lookups by relative path.
+struct FileIndex<'a> {
+    files: HashMap<&'a Path, NodeId>,
+    /// Non-test Go files by directory (a Go import names a package)
+    go_packages: HashMap<&'a Path, Vec<NodeId>>,
+    /// C/C++ files by file name, for include paths relative to an unknown
+    /// include directory
+    c_files: HashMap<&'a OsStr, Vec<(&'a Path, NodeId)>>,
+}
+
+impl<'a> FileIndex<'a> {
+    fn new(tree: &'a Tree) -> Self {
+        let mut files = HashMap::new();
+        let mut go_packages: HashMap<&Path, Vec<NodeId>> = HashMap::new();
+        let mut c_files: HashMap<&OsStr, Vec<(&Path, NodeId)>> = HashMap::new();
+
+        for node in tree.files() {
+            files.insert(node.path.as_path(), node.id);
+
+            let is_c = matches!(
+                node.kind,
+                NodeKind::File {
+                    language: Some(Language::C | Language::Cpp),
+                    ..
+                }
+            );
+            if let (true, Some(name)) = (is_c, node.path.file_name()) {
+                c_files
+                    .entry(name)
+                    .or_default()
+                    .push((node.path.as_path(), node.id));
+            }
+
+            let is_go = matches!(
+                node.kind,
+                NodeKind::File {
+                    language: Some(Language::Go),
+                    ..
+                }
+            );
+            if is_go && !node.name.ends_with("_test.go") {
+                let dir = node.path.parent().unwrap_or(Path::new(""));
+                go_packages.entry(dir).or_default().push(node.id);
+            }
+        }
+
+        Self {
+            files,
+            go_packages,
+            c_files,
+        }
+    }
+
+    fn file(&self, path: &Path) -> Option<NodeId> {
+        self.files.get(path).copied()
+    }
+
+    fn first_file(&self, candidates: impl IntoIterator<Item = PathBuf>) -> Option<NodeId> {
+        candidates.into_iter().find_map(|path| self.file(&path))
+    }
+
+    fn resolve(&self, importer: &Path, language: Language, spec: &str) -> Vec<NodeId> {
+        match language {
+            Language::Rust => expand_use_tree(spec)
+                .iter()
+                .filter_map(|path| self.resolve_rust(importer, path))
+                .collect(),
+            Language::TypeScript | Language::JavaScript => {
+                self.resolve_script(importer, spec).into_iter().collect()
+            }
+            Language::Python => self.resolve_python(importer, spec).into_iter().collect(),
+            Language::Go => self.resolve_go(spec),
+            Language::C | Language::Cpp => {
+                self.resolve_include(importer, spec).into_iter().collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Resolve a Rust path to the file of the deepest module it names.
+    ///
+    /// `crate::`, `self::` and `super::` paths are resolved from the crate
+    /// root or the importer's module; other paths are tried as child modules
+    /// of the importer's module (and otherwise assumed to be external).
+    fn resolve_rust(&self, importer: &Path, path: &str) -> Option<NodeId> {
+        let mut segments = path.split("::").filter(|s| !s.is_empty()).peekable();
+        let anchored = matches!(segments.peek(), Some(&("crate" | "self" | "super")));
+
+        let mut dir = match segments.peek() {
+            Some(&"crate") => {
+                segments.next();
+                self.rust_crate_root(importer)?
+            }
+            _ => rust_module_dir(importer),
+        };
+        if segments.peek() == Some(&"self") {
+            segments.next();
+        }
+        while segments.peek() == Some(&"super") {
+            segments.next();
+            dir = dir.parent()?.to_path_buf();
+        }
+
+        let segments: Vec<&str> = segments.collect();
+        for len in (1..=segments.len()).rev() {
+            let base = segments[..len].iter().fold(dir.clone(), |p, s| p.join(s));
+            let found = self.first_file([with_added_extension(&base, "rs"), base.join("mod.rs")]);
+            if found.is_some() {
+                return found;
+            }
+        }
+
+        // Items of the anchor module itself, e.g. `use super::Config`
+        if anchored {
+            return self.first_file([
+                dir.join("mod.rs"),
+                dir.join("lib.rs"),
+                dir.join("main.rs"),
+                with_added_extension(&dir, "rs"),
+            ]);
+        }
+        None
+    }
+
+    /// The nearest directory above `importer` holding `lib.rs` or `main.rs`.
+    fn rust_crate_root(&self, importer: &Path) -> Option<PathBuf> {
+        importer
+            .ancestors()
+            .skip(1)
+            .find(|dir| {
+                self.file(&dir.join("lib.rs")).is_some()
+                    || self.file(&dir.join("main.rs")).is_some()
+            })
+            .map(Path::to_path_buf)
+    }
+
+    /// Resolve a relative TypeScript/JavaScript import; packages are skipped.
+    fn resolve_script(&self, importer: &Path, spec: &str) -> Option<NodeId> {
+        if !(spec.starts_with("./") || spec.starts_with("../") || spec == "." || spec == "..") {
+            return None;
+        }
+        let base = normalize(&importer.parent()?.join(spec))?;
+
+        // ES module imports of compiled TypeScript name the `.js` output
+        let stem = match base.extension().and_then(|e| e.to_str()) {
+            Some("js" | "jsx" | "mjs" | "cjs") => base.with_extension(""),
+            _ => base.clone(),
+        };
+
+        let candidates = std::iter::once(base.clone())
+            .chain(
+                SCRIPT_EXTENSIONS
+                    .iter()
+                    .map(|ext| with_added_extension(&stem, ext)),
+            )
+            .chain(
+                SCRIPT_EXTENSIONS
+                    .iter()
+                    .map(|ext| base.join(format!("index.{}", ext))),
+            );
+        self.first_file(candidates)
+    }
+
+    /// Resolve a dotted Python module path.
+    ///
+    /// `from m import n` is recorded as `m.n`, where `n` may be a name rather
+    /// than a module, so shorter prefixes are tried too. Absolute imports are
+    /// tried from the project root and every directory above the importer,
+    /// which covers `src/` layouts.
+    fn resolve_python(&self, importer: &Path, spec: &str) -> Option<NodeId> {
+        let module = spec.trim_start_matches('.');
+        let dots = spec.len() - module.len();
+        let segments: Vec<&str> = module.split('.').filter(|s| !s.is_empty()).collect();
+
+        let bases: Vec<PathBuf> = if dots > 0 {
+            let mut dir = importer.parent()?;
+            for _ in 1..dots {
+                dir = dir.parent()?;
+            }
+            vec![dir.to_path_buf()]
+        } else {
+            let mut ancestors: Vec<PathBuf> = importer
+                .ancestors()
+                .skip(1)
+                .map(Path::to_path_buf)
+                .collect();
+            ancestors.reverse();
+            ancestors
+        };
+
+        // A package itself (`from . import x` falling back to `__init__.py`)
+        // only counts for relative imports
+        let min_len = usize::from(dots == 0);
+        for len in (min_len..=segments.len()).rev() {
+            for base in &bases {
+                let path = segments[..len].iter().fold(base.clone(), |p, s| p.join(s));
+                let found =
+                    self.first_file([with_added_extension(&path, "py"), path.join("__init__.py")]);
+                if found.is_some() {
+                    return found;
+                }
+            }
+        }
+        None
+    }
+
+    /// Resolve a Go import path to the files of the package it names.
+    ///
+    /// The project's module path is not known here, so the longest suffix of
+    /// the import path that is a directory in the tree wins. Paths whose
+    /// first element has no dot (the standard library) are skipped.
+    fn resolve_go(&self, spec: &str) -> Vec<NodeId> {
+        let segments: Vec<&str> = spec.split('/').collect();
+        if !segments[0].contains('.') {
+            return Vec::new();
+        }
+
+        (0..segments.len())
+            .find_map(|start| {
+                let dir: PathBuf = segments[start..].iter().collect();
+                self.go_packages.get(dir.as_path())
+            })
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Resolve a C/C++ `#include` path.
+    ///
+    /// Quoted includes are tried next to the importer first. Include
+    /// directories are not known here, so otherwise the file whose path ends
+    /// with the include path is taken, provided there is only one.
+    /// `<system>` includes only match that way, which skips the standard
+    /// library unless the project has a header of the same path.
+    fn resolve_include(&self, importer: &Path, spec: &str) -> Option<NodeId> {
+        let (path, system) = match spec.strip_prefix('<').and_then(|s| s.strip_suffix('>')) {
+            Some(path) => (Path::new(path), true),
+            None => (Path::new(spec), false),
+        };
+
+        if !system {
+            let local = importer
+                .parent()
+                .and_then(|dir| normalize(&dir.join(path)))
+                .and_then(|path| self.file(&path));
+            if local.is_some() {
+                return local;
+            }
+        }
+
+        let suffix = normalize(path)?;
+        let candidates = self.c_files.get(suffix.file_name()?)?;
+        match candidates
+            .iter()
+            .filter(|(candidate, _)| candidate.ends_with(&suffix))
+            .collect::<Vec<_>>()[..]
+        {
+            [(_, id)] => Some(*id),
+            _ => None,
+        }
+    }
+
+    /// Pairs of C/C++ headers and the source file implementing them.
+    ///
+    /// A source with the header's stem in the same directory wins (`foo.h`
+    /// and `foo.c`); otherwise the only such source anywhere in the project
+    /// (`include/foo.h` and `src/foo.c`).
+    fn header_sources(&self) -> Vec<(NodeId, NodeId)> {
+        let mut pairs = Vec::new();
+        for (name, headers) in &self.c_files {
+            let name = Path::new(name);
+            if !has_extension(name, HEADER_EXTENSIONS) {
+                continue;
+            }
+            let Some(stem) = name.file_stem() else {
+                continue;
+            };
+
+            let sources: Vec<(&Path, NodeId)> = SOURCE_EXTENSIONS
+                .iter()
+                .filter_map(|ext| {
+                    let mut name = stem.to_os_string();
+                    name.push(".");
+                    name.push(ext);
+                    self.c_files.get(name.as_os_str())
+                })
+                .flatten()
+                .copied()
+                .collect();
+
+            for (header, header_id) in headers {
+                let same_dir = sources
+                    .iter()
+                    .find(|(source, _)| source.parent() == header.parent());
+                let source = match (same_dir, &sources[..]) {
+                    (Some(source), _) | (None, [source]) => source,
+                    _ => continue,
+                };
+                pairs.push((*header_id, source.1));
+            }
+        }
+        pairs
+    }
+}
+
+/// Whether `path` has one of `extensions`.
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| extensions.contains(&e))
+}
+
+/// Directory holding the child modules of a Rust file's module.
+fn rust_module_dir(file: &Path) -> PathBuf {
+    let parent = file.parent().unwrap_or(Path::new(""));
+    match file.file_name().and_then(|n| n.to_str()) {
+        Some("mod.rs" | "lib.rs" | "main.rs") => parent.to_path_buf(),
+        _ => parent.join(file.file_stem().unwrap_or_default()),
+    }
+}
+
+/// Expand a `use` tree into the paths it imports.
+///
+/// `a::{b as c, d::{self, e}}` expands to `a::b`, `a::d` and `a::d::e`.
+fn expand_use_tree(spec: &str) -> Vec<String> {
+    let mut paths = Vec::new();
+    expand_use_tree_into("", spec, &mut paths);
+    paths
+}
+
+fn expand_use_tree_into(prefix: &str, tree: &str, paths: &mut Vec<String>) {
+    let join = |prefix: &str, item: &str| match (prefix.is_empty(), item.is_empty()) {
+        (true, _) => item.to_string(),
+        (_, true) => prefix.to_string(),
+        _ => format!("{}::{}", prefix, item),
+    };
+
+    let tree = tree.trim();
+    if let (Some(open), true) = (tree.find('{'), tree.ends_with('}')) {
+        let prefix = join(prefix, tree[..open].trim().trim_end_matches("::"));
+        for item in split_top_level(&tree[open + 1..tree.len() - 1]) {
+            if !item.trim().is_empty() {
+                expand_use_tree_into(&prefix, item, paths);
+            }
+        }
+        return;
+    }
+
+    let item = tree.split(" as ").next().unwrap_or("").trim();
+    match item {
+        "self" | "*" => paths.push(prefix.to_string()),
+        item => paths.push(join(prefix, item.trim_end_matches("::*"))),
+    }
+}
+
+/// Split on commas outside of braces.
+fn split_top_level(list: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in list.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                items.push(&list[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(&list[start..]);
+    items
+}
+
+/// Lexically resolve `.` and `..`; `None` if the path leaves the root.
+fn normalize(path: &Path) -> Option<PathBuf> {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    return None;
+                }
+            }
+            Component::Normal(part) => normalized.push(part),
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(normalized)
+}
+
+/// `path` with `.ext` appended (unlike `with_extension`, which replaces).
+fn with_added_extension(path: &Path, ext: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(".");
+    name.push(ext);
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::{ScanResult, ScannedFile};
+    use crate::tree::TreeBuilder;
+
+    fn file(path: &str, language: Language, imports: &[&str]) -> ScannedFile {
+        ScannedFile {
+            language: Some(language),
+            ..ScannedFile::for_test(path).with_imports(imports)
+        }
+    }
+
+    fn build(files: Vec<ScannedFile>) -> Tree {
+        TreeBuilder::new().build(&ScanResult {
+            root: PathBuf::from("/project"),
+            files,
+            languages: vec![],
+            frameworks: vec![],
+            duration_ms: 0,
+            skipped_count: 0,
+            excluded_dirs: vec![],
+        })
+    }
+
+    fn imports_of(tree: &Tree, path: &str) -> Vec<String> {
+        let id = tree.find_node_by_path(&PathBuf::from(path)).unwrap();
+        let mut paths: Vec<String> = tree
+            .dependencies
+            .imports(id)
+            .map(|id| tree.get(id).unwrap().path.display().to_string())
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn test_expand_use_tree() {
+        assert_eq!(
+            expand_use_tree("crate::tree::{Node, builder::{self, TreeBuilder as B}, prune::*}"),
+            vec![
+                "crate::tree::Node",
+                "crate::tree::builder",
+                "crate::tree::builder::TreeBuilder",
+                "crate::tree::prune",
+            ]
+        );
+        assert_eq!(expand_use_tree("super::*"), vec!["super"]);
+    }
+
+    #[test]
+    fn test_resolve_rust() {
+        let tree = build(vec![
+            file("src/lib.rs", Language::Rust, &["self::tree", "std::io"]),
+            file(
+                "src/tree/mod.rs",
+                Language::Rust,
+                &["self::builder", "crate::scanner::{Parser, parser::Symbol}"],
+            ),
+            file("src/tree/builder.rs", Language::Rust, &["super::Node"]),
+            file("src/scanner.rs", Language::Rust, &["self::parser"]),
+            file("src/scanner/parser.rs", Language::Rust, &["crate::Error"]),
+        ]);
+
+        assert_eq!(imports_of(&tree, "src/lib.rs"), vec!["src/tree/mod.rs"]);
+        assert_eq!(
+            imports_of(&tree, "src/tree/mod.rs"),
+            vec![
+                "src/scanner.rs",
+                "src/scanner/parser.rs",
+                "src/tree/builder.rs"
+            ]
+        );
+        assert_eq!(
+            imports_of(&tree, "src/tree/builder.rs"),
+            vec!["src/tree/mod.rs"]
+        );
+        assert_eq!(
+            imports_of(&tree, "src/scanner.rs"),
+            vec!["src/scanner/parser.rs"]
+        );
+        assert_eq!(
+            imports_of(&tree, "src/scanner/parser.rs"),
+            vec!["src/lib.rs"]
+        );
+    }
+
+    #[test]
+    fn test_resolve_script() {
+        let tree = build(vec![
+            file(
+                "web/app.ts",
+                Language::TypeScript,
+                &["./util", "./components", "../shared/types.js", "react"],
+            ),
+            file("web/util.ts", Language::TypeScript, &[]),
+            file("web/components/index.tsx", Language::TypeScript, &[]),
+            file("shared/types.ts", Language::TypeScript, &["../../outside"]),
+        ]);
+
+        assert_eq!(
+            imports_of(&tree, "web/app.ts"),
+            vec!["shared/types.ts", "web/components/index.tsx", "web/util.ts"]
+        );
+        assert!(imports_of(&tree, "shared/types.ts").is_empty());
+    }
+
+    #[test]
+    fn test_resolve_python() {
+        let tree = build(vec![
+            file(
+                "src/app/main.py",
+                Language::Python,
+                &["app.db.connect", ".views", "os.path"],
+            ),
+            file("src/app/__init__.py", Language::Python, &[]),
+            file("src/app/db.py", Language::Python, &["..app"]),
+            file("src/app/views/__init__.py", Language::Python, &["..db"]),
+        ]);
+
+        assert_eq!(
+            imports_of(&tree, "src/app/main.py"),
+            vec!["src/app/db.py", "src/app/views/__init__.py"]
+        );
+        assert_eq!(
+            imports_of(&tree, "src/app/db.py"),
+            vec!["src/app/__init__.py"]
+        );
+        assert_eq!(
+            imports_of(&tree, "src/app/views/__init__.py"),
+            vec!["src/app/db.py"]
+        );
+    }
+
+    #[test]
+    fn test_resolve_go() {
+        let tree = build(vec![
+            file(
+                "cmd/server/main.go",
+                Language::Go,
+                &["fmt", "example.com/app/internal/store"],
+            ),
+            file("internal/store/store.go", Language::Go, &[]),
+            file("internal/store/cache.go", Language::Go, &[]),
+            file("internal/store/store_test.go", Language::Go, &[]),
+        ]);
+
+        assert_eq!(
+            imports_of(&tree, "cmd/server/main.go"),
+            vec!["internal/store/cache.go", "internal/store/store.go"]
+        );
+    }
+
+    #[test]
+    fn test_resolve_include() {
+        let tree = build(vec![
+            file(
+                "src/main.c",
+                Language::C,
+                &["<stdio.h>", "util.h", "net/socket.h", "<config.h>"],
+            ),
+            file("src/util.h", Language::C, &[]),
+            file("include/net/socket.h", Language::C, &["../config.h"]),
+            file("include/config.h", Language::C, &[]),
+            file("vendor/a/common.h", Language::C, &[]),
+            file("vendor/b/common.h", Language::C, &[]),
+            file("src/ambiguous.c", Language::C, &["common.h"]),
+        ]);
+
+        assert_eq!(
+            imports_of(&tree, "src/main.c"),
+            vec!["include/config.h", "include/net/socket.h", "src/util.h"]
+        );
+        assert_eq!(
+            imports_of(&tree, "include/net/socket.h"),
+            vec!["include/config.h"]
+        );
+        assert!(imports_of(&tree, "src/ambiguous.c").is_empty());
+    }
+
+    #[test]
+    fn test_header_source_linking() {
+        let tree = build(vec![
+            file("src/widget.hpp", Language::Cpp, &[]),
+            file("src/widget.cpp", Language::Cpp, &["widget.hpp"]),
+            file("include/list.h", Language::C, &[]),
+            file("src/list.c", Language::C, &["list.h"]),
+            file("include/log.h", Language::C, &[]),
+            file("server/log.c", Language::C, &[]),
+            file("client/log.c", Language::C, &[]),
+        ]);
+
+        assert_eq!(imports_of(&tree, "src/widget.hpp"), vec!["src/widget.cpp"]);
+        assert_eq!(imports_of(&tree, "src/widget.cpp"), vec!["src/widget.hpp"]);
+        assert_eq!(imports_of(&tree, "include/list.h"), vec!["src/list.c"]);
+        assert_eq!(imports_of(&tree, "src/list.c"), vec!["include/list.h"]);
+        assert!(imports_of(&tree, "include/log.h").is_empty());
+    }
+}
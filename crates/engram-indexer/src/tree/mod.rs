@@ -3,17 +3,43 @@
 //! Provides a hierarchical representation of files, directories,
 //! and code symbols with dependency tracking.
 
+mod anonymize;
 mod builder;
+mod calls;
+mod content;
 mod dependency;
-
+mod diff;
+mod docs;
+mod find;
+mod impact;
+mod imports;
+mod prune;
+mod stats;
+mod test_links;
+mod update;
+
+pub use anonymize::{anonymize_tree, AnonymizedNode};
 pub use builder::TreeBuilder;
+pub use calls::{symbol_calls, SymbolCalls};
+pub use content::{ContentHandle, ContentRef, ContentSource, DEFAULT_CONTENT_CACHE};
 pub use dependency::DependencyGraph;
-
+pub use diff::{diff_trees, SymbolChange, TreeDiff};
+pub use docs::{render_docs, DocPage, DocsFormat};
+pub use find::{find_files, FileMatch};
+pub use impact::{analyze_impact, ImpactReport, ImpactedFile};
+pub use imports::resolve_imports;
+pub use prune::{compile_globs, prune_tree, PruneReport};
+pub use stats::{tree_stats, TreeStats};
+pub use test_links::{is_test_path, link_tests, TestLinks};
+pub use update::{apply_updates, FileUpdate, UpdateReport};
+
+use crate::git::GitState;
 use crate::scanner::{Framework, Language, Symbol};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 /// Unique identifier for a tree node.
 pub type NodeId = u64;
@@ -36,6 +62,10 @@ pub struct Tree {
     /// Dependency graph
     pub dependencies: DependencyGraph,
 
+    /// Links between source files and their tests
+    #[serde(default)]
+    pub tests: TestLinks,
+
     /// Detected languages
     pub languages: Vec<Language>,
 
@@ -53,6 +83,19 @@ pub struct Tree {
 
     /// Total symbol count
     pub symbol_count: usize,
+
+    /// Branch and commit the tree was indexed at, if under git
+    #[serde(default)]
+    pub git: Option<GitState>,
+
+    /// When the walk that last visited every file started; files not
+    /// modified since are trusted by [`Scanner::rescan`](crate::Scanner::rescan)
+    #[serde(default)]
+    pub scanned_at: Option<DateTime<Utc>>,
+
+    /// Loads the content of nodes that hold none in memory
+    #[serde(skip)]
+    pub(crate) content_handle: Option<Arc<ContentHandle>>,
 }
 
 impl Tree {
@@ -88,13 +131,68 @@ impl Tree {
             nodes,
             root_id,
             dependencies: DependencyGraph::new(),
+            tests: TestLinks::new(),
             languages: Vec::new(),
             frameworks: Vec::new(),
             created_at: now,
             updated_at: now,
             file_count: 0,
             symbol_count: 0,
+            git: None,
+            scanned_at: None,
+            content_handle: None,
+        }
+    }
+
+    /// Load node content lazily through `handle` when a node holds none.
+    pub fn with_content_handle(mut self, handle: Arc<ContentHandle>) -> Self {
+        self.content_handle = Some(handle);
+        self
+    }
+
+    /// Whether node content is loaded lazily.
+    pub fn is_lazy(&self) -> bool {
+        self.content_handle.is_some()
+    }
+
+    /// Content of a node: held in memory, or loaded through the content
+    /// handle on first access.
+    pub fn content(&self, id: NodeId) -> Option<ContentRef<'_>> {
+        let node = self.nodes.get(&id)?;
+        if let Some(content) = &node.content {
+            return Some(ContentRef::Borrowed(content));
+        }
+        self.content_handle
+            .as_ref()?
+            .get(id)
+            .map(ContentRef::Loaded)
+    }
+
+    /// Mutable content of a node, loading it into the node first if the
+    /// tree is lazy.
+    pub fn content_mut(&mut self, id: NodeId) -> Option<&mut NodeContent> {
+        let loaded = match &self.content_handle {
+            Some(handle) if self.nodes.get(&id)?.content.is_none() => handle.get(id),
+            _ => None,
+        };
+        let node = self.nodes.get_mut(&id)?;
+        if let Some(content) = loaded {
+            node.content = Some((*content).clone());
+        }
+        node.content.as_mut()
+    }
+
+    /// A copy of the tree with every node's content in memory.
+    pub fn materialized(&self) -> Tree {
+        let mut tree = self.clone();
+        if let Some(handle) = tree.content_handle.take() {
+            for node in tree.nodes.values_mut() {
+                if node.content.is_none() {
+                    node.content = handle.get(node.id).map(|content| (*content).clone());
+                }
+            }
         }
+        tree
     }
 
     /// Get a node by ID.
@@ -127,6 +225,27 @@ impl Tree {
         self.nodes.values().find(|n| &n.path == path).map(|n| n.id)
     }
 
+    /// Find a symbol declared in a file.
+    ///
+    /// `name` may be qualified (`Session::validate`); symbols are stored
+    /// by their own name, so the last segment is tried if there is no
+    /// exact match.
+    pub fn find_symbol(&self, file: &PathBuf, name: &str) -> Option<NodeId> {
+        let file_id = self.find_node_by_path(file)?;
+        let symbols: Vec<&Node> = self
+            .children(file_id)
+            .into_iter()
+            .filter(|n| n.is_symbol())
+            .collect();
+        let short = name.rsplit("::").next().unwrap_or(name);
+
+        symbols
+            .iter()
+            .find(|n| n.name == name)
+            .or_else(|| symbols.iter().find(|n| n.name == short))
+            .map(|n| n.id)
+    }
+
     /// Find a node ID by name (searches all nodes).
     pub fn find_node_by_name(&self, name: &str) -> Option<NodeId> {
         self.nodes.values().find(|n| n.name == name).map(|n| n.id)
@@ -163,11 +282,25 @@ impl Tree {
         self.updated_at = Utc::now();
     }
 
+    /// Generation of this tree, changed whenever it is rebuilt or touched.
+    pub fn generation(&self) -> u64 {
+        self.updated_at.timestamp_nanos_opt().unwrap_or_default() as u64
+    }
+
     /// Generate a skeleton string representation of the tree.
     /// Excludes nodes in the focus set (they are shown separately).
     pub fn to_skeleton_string(&self, focus_nodes: &[NodeId]) -> String {
+        self.to_skeleton_string_with(focus_nodes, &SkeletonOptions::default())
+    }
+
+    /// Generate a skeleton string with explicit rendering options.
+    pub fn to_skeleton_string_with(
+        &self,
+        focus_nodes: &[NodeId],
+        options: &SkeletonOptions,
+    ) -> String {
         let mut output = String::new();
-        self.render_node_skeleton(&mut output, self.root_id, "", true, focus_nodes);
+        self.render_node_skeleton(&mut output, self.root_id, "", true, focus_nodes, options);
         output
     }
 
@@ -179,22 +312,51 @@ impl Tree {
         prefix: &str,
         is_last: bool,
         focus_nodes: &[NodeId],
+        options: &SkeletonOptions,
     ) {
-        let Some(node) = self.get(node_id) else {
+        let Some(mut node) = self.get(node_id) else {
             return;
         };
 
         // Skip root's indentation
         if node.parent.is_some() {
+            let mut name = node.name.clone();
+            if options.compress_dirs {
+                // Collapse chains of single-child directories into "a/b/c"
+                while node.is_directory() && !focus_nodes.contains(&node.id) {
+                    let [only_child] = node.children[..] else {
+                        break;
+                    };
+                    match self.get(only_child) {
+                        Some(child) if child.is_directory() => {
+                            name = format!("{}/{}", name, child.name);
+                            node = child;
+                        }
+                        _ => break,
+                    }
+                }
+            }
+
             let connector = if is_last { "└── " } else { "├── " };
-            let focus_marker = if focus_nodes.contains(&node_id) {
+            let focus_marker = if focus_nodes.contains(&node.id) {
                 " ← (focus)"
             } else {
                 ""
             };
+            let annotation = match &node.kind {
+                NodeKind::File {
+                    asset: Some(asset),
+                    size,
+                    ..
+                } => format!(" [{}, {}]", asset.name(), format_size(*size)),
+                NodeKind::File {
+                    truncated: true, ..
+                } => " (truncated)".to_string(),
+                _ => String::new(),
+            };
             output.push_str(&format!(
-                "{}{}{}{}\n",
-                prefix, connector, node.name, focus_marker
+                "{}{}{}{}{}\n",
+                prefix, connector, name, annotation, focus_marker
             ));
         } else {
             output.push_str(&format!("{}/\n", node.name));
@@ -211,11 +373,54 @@ impl Tree {
             } else {
                 String::new()
             };
-            self.render_node_skeleton(output, *child_id, &new_prefix, is_last_child, focus_nodes);
+            self.render_node_skeleton(
+                output,
+                *child_id,
+                &new_prefix,
+                is_last_child,
+                focus_nodes,
+                options,
+            );
         }
     }
 }
 
+/// Human-readable file size, e.g. `40.0 MB`.
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// Options for skeleton rendering.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkeletonOptions {
+    /// Collapse chains of single-child directories (e.g. generated Java
+    /// packages) into one `a/b/c` line
+    #[serde(default = "default_compress_dirs")]
+    pub compress_dirs: bool,
+}
+
+impl Default for SkeletonOptions {
+    fn default() -> Self {
+        Self {
+            compress_dirs: default_compress_dirs(),
+        }
+    }
+}
+
+fn default_compress_dirs() -> bool {
+    true
+}
+
 /// A node in the project tree.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Node {
@@ -247,6 +452,25 @@ impl Node {
         matches!(self.kind, NodeKind::Directory)
     }
 
+    /// Check if this is a file indexed only in part.
+    pub fn is_truncated(&self) -> bool {
+        matches!(
+            self.kind,
+            NodeKind::File {
+                truncated: true,
+                ..
+            }
+        )
+    }
+
+    /// Type of the file if it is a binary or asset file.
+    pub fn asset(&self) -> Option<crate::scanner::AssetKind> {
+        match self.kind {
+            NodeKind::File { asset, .. } => asset,
+            _ => None,
+        }
+    }
+
     /// Check if this is a file node.
     pub fn is_file(&self) -> bool {
         matches!(self.kind, NodeKind::File { .. })
@@ -283,6 +507,13 @@ pub enum NodeKind {
         hash: String,
         /// Line count
         line_count: usize,
+        /// Only the start of the file was indexed, as it is over the size
+        /// limit
+        #[serde(default)]
+        truncated: bool,
+        /// Type of a binary or asset file, which has no content or symbols
+        #[serde(default)]
+        asset: Option<crate::scanner::AssetKind>,
     },
 
     /// Code symbol (function, class, etc.)
@@ -297,7 +528,7 @@ pub enum NodeKind {
 }
 
 /// Additional content for a node.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct NodeContent {
     /// AI-generated summary
     pub summary: Option<String>,
@@ -313,18 +544,51 @@ pub struct NodeContent {
 
     /// Content hash (for change detection)
     pub hash: String,
+
+    /// Import specifiers, unresolved (for file nodes)
+    #[serde(default)]
+    pub imports: Vec<String>,
 }
 
-impl Default for NodeContent {
-    fn default() -> Self {
+/// Files for building trees in tests
+#[cfg(test)]
+impl crate::scanner::ScannedFile {
+    /// A small Rust file at `path`, hashed by its path.
+    pub(crate) fn for_test(path: &str) -> Self {
         Self {
-            summary: None,
-            tags: Vec::new(),
-            symbols: Vec::new(),
-            line_count: 0,
-            hash: String::new(),
+            path: PathBuf::from(path),
+            language: Some(Language::Rust),
+            size: 10,
+            hash: path.to_string(),
+            line_count: 20,
+            symbols: vec![],
+            imports: vec![],
+            truncated: false,
+            asset: None,
         }
     }
+
+    /// Give the file functions, as `(name, start_line, end_line)`.
+    pub(crate) fn with_functions(mut self, functions: &[(&str, usize, usize)]) -> Self {
+        self.symbols = functions
+            .iter()
+            .map(|&(name, start_line, end_line)| Symbol {
+                name: name.to_string(),
+                kind: crate::scanner::SymbolKind::Function,
+                start_line,
+                end_line,
+                parent: None,
+                doc: None,
+            })
+            .collect();
+        self
+    }
+
+    /// Give the file unresolved import specifiers.
+    pub(crate) fn with_imports(mut self, imports: &[&str]) -> Self {
+        self.imports = imports.iter().map(|s| s.to_string()).collect();
+        self
+    }
 }
 
 #[cfg(test)]
@@ -372,6 +636,8 @@ mod tests {
                 size: 100,
                 hash: "abc".to_string(),
                 line_count: 10,
+                truncated: false,
+                asset: None,
             },
             parent: Some(1),
             children: vec![],
@@ -397,6 +663,86 @@ mod tests {
         assert_eq!(tree.root_path, deserialized.root_path);
     }
 
+    #[test]
+    fn test_skeleton_compresses_dir_chains() {
+        use crate::scanner::{ScanResult, ScannedFile};
+
+        let file = |path: &str| ScannedFile {
+            language: None,
+            ..ScannedFile::for_test(path)
+        };
+        let scan = ScanResult {
+            root: PathBuf::from("/project"),
+            files: vec![file("src/com/example/app/Main.java"), file("README.md")],
+            languages: vec![],
+            frameworks: vec![],
+            duration_ms: 0,
+            skipped_count: 0,
+            excluded_dirs: vec![],
+        };
+        let tree = TreeBuilder::new().build(&scan);
+
+        let compressed = tree.to_skeleton_string(&[]);
+        assert!(compressed.contains("src/com/example/app\n"));
+        assert!(compressed.contains("Main.java"));
+
+        let expanded = tree.to_skeleton_string_with(
+            &[],
+            &SkeletonOptions {
+                compress_dirs: false,
+            },
+        );
+        assert!(!expanded.contains("src/com"));
+        assert!(expanded.contains("example"));
+    }
+
+    #[test]
+    fn test_find_symbol() {
+        use crate::scanner::{ScanResult, ScannedFile, Symbol, SymbolKind};
+
+        let symbol = |name: &str, start_line: usize| Symbol {
+            name: name.to_string(),
+            kind: SymbolKind::Function,
+            start_line,
+            end_line: start_line + 2,
+            parent: None,
+            doc: None,
+        };
+        let scan = ScanResult {
+            root: PathBuf::from("/project"),
+            files: vec![ScannedFile {
+                path: PathBuf::from("src/auth.rs"),
+                language: Some(Language::Rust),
+                size: 0,
+                hash: String::new(),
+                line_count: 10,
+                symbols: vec![symbol("authenticate", 1), symbol("validate", 5)],
+                imports: vec![],
+                truncated: false,
+                asset: None,
+            }],
+            languages: vec![],
+            frameworks: vec![],
+            duration_ms: 0,
+            skipped_count: 0,
+            excluded_dirs: vec![],
+        };
+        let tree = TreeBuilder::new().build(&scan);
+        let file = PathBuf::from("src/auth.rs");
+
+        let id = tree.find_symbol(&file, "validate").unwrap();
+        assert!(matches!(
+            tree.get(id).unwrap().kind,
+            NodeKind::Symbol { start_line: 5, .. }
+        ));
+        assert_eq!(tree.find_symbol(&file, "Session::validate"), Some(id));
+        assert_eq!(tree.find_symbol(&file, "missing"), None);
+        assert_eq!(
+            tree.find_symbol(&PathBuf::from("src/other.rs"), "validate"),
+            None
+        );
+    }
+
     #[test]
     fn test_tree_touch() {
         let mut tree = Tree::new(PathBuf::from("/test"));
@@ -0,0 +1,192 @@
+//! Glob-based removal of nodes from a built tree.
+//!
+//! Lets users drop paths (e.g. a freshly vendored SDK) from the persisted
+//! tree without a full re-index.
+
+use super::{NodeId, Tree};
+use crate::IndexerError;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+
+/// What a prune removed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PruneReport {
+    pub removed_dirs: usize,
+    pub removed_files: usize,
+    pub removed_symbols: usize,
+    pub removed_edges: usize,
+}
+
+impl PruneReport {
+    /// Total number of removed nodes.
+    pub fn removed_nodes(&self) -> usize {
+        self.removed_dirs + self.removed_files + self.removed_symbols
+    }
+}
+
+/// Compile globs matched against paths relative to the project root.
+///
+/// `*` does not cross directory separators; use `**` for that.
+pub fn compile_globs(globs: &[String]) -> Result<GlobSet, IndexerError> {
+    let mut builder = GlobSetBuilder::new();
+    for glob in globs {
+        let glob = GlobBuilder::new(glob.trim_end_matches('/'))
+            .literal_separator(true)
+            .build()
+            .map_err(|e| IndexerError::InvalidGlob(e.to_string()))?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map_err(|e| IndexerError::InvalidGlob(e.to_string()))
+}
+
+/// Remove directories and files matching `globs`, with everything below
+/// them and their dependency edges.
+///
+/// Directories left empty by the prune are removed as well. The root is
+/// never removed.
+pub fn prune_tree(tree: &mut Tree, globs: &GlobSet) -> PruneReport {
+    let mut matched: Vec<NodeId> = tree
+        .nodes
+        .values()
+        .filter(|node| node.id != tree.root_id && !node.is_symbol())
+        .filter(|node| globs.is_match(&node.path))
+        .map(|node| node.id)
+        .collect();
+    matched.sort_unstable();
+
+    let mut report = PruneReport::default();
+    for id in matched {
+        // Already removed together with a matched ancestor
+        if !tree.nodes.contains_key(&id) {
+            continue;
+        }
+        let parent = tree.get(id).and_then(|node| node.parent);
+        remove_subtree(tree, id, &mut report);
+        if let Some(parent) = parent {
+            remove_empty_dirs(tree, parent, &mut report);
+        }
+    }
+
+    if report.removed_nodes() > 0 {
+        tree.file_count -= report.removed_files;
+        tree.symbol_count -= report.removed_symbols;
+        tree.touch();
+    }
+    report
+}
+
+pub(super) fn remove_subtree(tree: &mut Tree, id: NodeId, report: &mut PruneReport) {
+    let Some(node) = tree.nodes.remove(&id) else {
+        return;
+    };
+    if let Some(parent) = node.parent.and_then(|p| tree.nodes.get_mut(&p)) {
+        parent.children.retain(|child| *child != id);
+    }
+
+    let edges = tree.dependencies.import_count(id) + tree.dependencies.imported_by_count(id);
+    report.removed_edges += edges;
+    tree.dependencies.remove_node(id);
+
+    if node.is_directory() {
+        report.removed_dirs += 1;
+    } else if node.is_file() {
+        report.removed_files += 1;
+    } else {
+        report.removed_symbols += 1;
+    }
+
+    for child in node.children {
+        remove_subtree(tree, child, report);
+    }
+}
+
+pub(super) fn remove_empty_dirs(tree: &mut Tree, mut id: NodeId, report: &mut PruneReport) {
+    while id != tree.root_id {
+        let Some(node) = tree.get(id) else {
+            return;
+        };
+        if !node.is_directory() || !node.children.is_empty() {
+            return;
+        }
+        let parent = node.parent;
+        remove_subtree(tree, id, report);
+        match parent {
+            Some(parent) => id = parent,
+            None => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::{Language, ScanResult, ScannedFile};
+    use crate::tree::TreeBuilder;
+    use std::path::PathBuf;
+
+    fn test_tree() -> Tree {
+        let scan = ScanResult {
+            root: PathBuf::from("/project"),
+            files: vec![
+                ScannedFile::for_test("src/main.rs"),
+                ScannedFile::for_test("vendor/sdk/a.rs"),
+                ScannedFile::for_test("vendor/sdk/b.rs"),
+                ScannedFile::for_test("vendor/keep.rs"),
+            ],
+            languages: vec![Language::Rust],
+            frameworks: vec![],
+            duration_ms: 0,
+            skipped_count: 0,
+            excluded_dirs: vec![],
+        };
+        let mut tree = TreeBuilder::new().build(&scan);
+        let main = tree
+            .find_node_by_path(&PathBuf::from("src/main.rs"))
+            .unwrap();
+        let sdk = tree
+            .find_node_by_path(&PathBuf::from("vendor/sdk/a.rs"))
+            .unwrap();
+        tree.dependencies.add_edge(main, sdk);
+        tree
+    }
+
+    #[test]
+    fn test_prune_directory() {
+        let mut tree = test_tree();
+        let report = prune_tree(
+            &mut tree,
+            &compile_globs(&["vendor/sdk".to_string()]).unwrap(),
+        );
+
+        assert_eq!(report.removed_dirs, 1);
+        assert_eq!(report.removed_files, 2);
+        assert_eq!(report.removed_edges, 1);
+        assert_eq!(tree.file_count, 2);
+        assert_eq!(tree.dependencies.edge_count(), 0);
+        assert!(tree
+            .find_node_by_path(&PathBuf::from("vendor/keep.rs"))
+            .is_some());
+        assert!(tree
+            .find_node_by_path(&PathBuf::from("vendor/sdk"))
+            .is_none());
+    }
+
+    #[test]
+    fn test_prune_removes_emptied_dirs() {
+        let mut tree = test_tree();
+        let report = prune_tree(
+            &mut tree,
+            &compile_globs(&["vendor/**/*.rs".to_string()]).unwrap(),
+        );
+
+        assert_eq!(report.removed_files, 3);
+        assert!(tree.find_node_by_path(&PathBuf::from("vendor")).is_none());
+        assert_eq!(tree.root().children.len(), 1);
+    }
+
+    #[test]
+    fn test_invalid_glob() {
+        assert!(compile_globs(&["src/[".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,213 @@
+//! Summary statistics over a built tree.
+//!
+//! Counts files by language, assets by type and symbols by kind, and ranks
+//! files by size and by dependency fan-in and fan-out.
+
+use super::{NodeKind, Tree};
+use crate::scanner::{AssetKind, SymbolKind};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Statistics of one tree. Ranked lists are sorted largest first, ties by
+/// path.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TreeStats {
+    pub files: usize,
+    pub symbols: usize,
+    pub lines: usize,
+    pub bytes: u64,
+    pub dependency_edges: usize,
+    /// Files per language name; files without a language count as `Other`,
+    /// assets are not counted
+    pub files_by_language: Vec<(String, usize)>,
+    /// Binary and asset files per type, with their total size, largest first
+    pub assets_by_kind: Vec<(AssetKind, usize, u64)>,
+    pub symbols_by_kind: Vec<(SymbolKind, usize)>,
+    /// Largest files: path, size in bytes and line count
+    pub largest_files: Vec<(PathBuf, u64, usize)>,
+    /// Files imported by the most other files
+    pub most_imported: Vec<(PathBuf, usize)>,
+    /// Files importing the most other files
+    pub most_importing: Vec<(PathBuf, usize)>,
+}
+
+/// Compute statistics of `tree`, keeping the `top` entries of each ranking.
+pub fn tree_stats(tree: &Tree, top: usize) -> TreeStats {
+    let mut stats = TreeStats {
+        dependency_edges: tree.dependencies.edge_count(),
+        ..Default::default()
+    };
+    let mut languages: HashMap<String, usize> = HashMap::new();
+    let mut kinds: HashMap<SymbolKind, usize> = HashMap::new();
+    let mut assets: HashMap<AssetKind, (usize, u64)> = HashMap::new();
+    let mut largest = Vec::new();
+    let mut imported = Vec::new();
+    let mut importing = Vec::new();
+
+    for node in tree.nodes.values() {
+        match &node.kind {
+            NodeKind::Directory => {}
+            NodeKind::File {
+                language,
+                size,
+                line_count,
+                asset,
+                ..
+            } => {
+                stats.files += 1;
+                stats.lines += line_count;
+                stats.bytes += size;
+                if let Some(asset) = asset {
+                    let entry = assets.entry(*asset).or_default();
+                    entry.0 += 1;
+                    entry.1 += size;
+                } else {
+                    let language = language.map_or("Other", |l| l.name());
+                    *languages.entry(language.to_string()).or_default() += 1;
+                }
+                largest.push((node.path.clone(), *size, *line_count));
+
+                let fan_in = tree.dependencies.imported_by_count(node.id);
+                if fan_in > 0 {
+                    imported.push((node.path.clone(), fan_in));
+                }
+                let fan_out = tree.dependencies.import_count(node.id);
+                if fan_out > 0 {
+                    importing.push((node.path.clone(), fan_out));
+                }
+            }
+            NodeKind::Symbol { symbol_kind, .. } => {
+                stats.symbols += 1;
+                *kinds.entry(*symbol_kind).or_default() += 1;
+            }
+        }
+    }
+
+    stats.files_by_language = ranked(languages.into_iter().collect(), usize::MAX);
+    stats.symbols_by_kind = ranked(kinds.into_iter().collect(), usize::MAX);
+    let mut assets: Vec<_> = assets
+        .into_iter()
+        .map(|(kind, (count, bytes))| (kind, count, bytes))
+        .collect();
+    assets.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.0.cmp(&b.0)));
+    stats.assets_by_kind = assets;
+
+    largest.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    largest.truncate(top);
+    stats.largest_files = largest;
+    stats.most_imported = ranked(imported, top);
+    stats.most_importing = ranked(importing, top);
+    stats
+}
+
+/// Sort by count, largest first, then by key, keeping `top` entries.
+fn ranked<K: Ord>(mut entries: Vec<(K, usize)>, top: usize) -> Vec<(K, usize)> {
+    entries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    entries.truncate(top);
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::{Language, ScanResult, ScannedFile, Symbol};
+    use crate::tree::TreeBuilder;
+
+    fn file(
+        path: &str,
+        language: Option<Language>,
+        size: u64,
+        symbols: Vec<Symbol>,
+    ) -> ScannedFile {
+        ScannedFile {
+            language,
+            size,
+            line_count: size as usize / 10,
+            symbols,
+            ..ScannedFile::for_test(path)
+        }
+    }
+
+    fn asset(path: &str, kind: AssetKind, size: u64) -> ScannedFile {
+        ScannedFile {
+            asset: Some(kind),
+            line_count: 0,
+            ..file(path, None, size, vec![])
+        }
+    }
+
+    fn symbol(name: &str, kind: SymbolKind) -> Symbol {
+        Symbol {
+            name: name.to_string(),
+            kind,
+            start_line: 1,
+            end_line: 2,
+            parent: None,
+            doc: None,
+        }
+    }
+
+    #[test]
+    fn test_tree_stats() {
+        let scan = ScanResult {
+            root: PathBuf::from("/project"),
+            files: vec![
+                file(
+                    "src/main.rs",
+                    Some(Language::Rust),
+                    100,
+                    vec![symbol("main", SymbolKind::Function)],
+                ),
+                file(
+                    "src/lib.rs",
+                    Some(Language::Rust),
+                    300,
+                    vec![
+                        symbol("Config", SymbolKind::Struct),
+                        symbol("load", SymbolKind::Function),
+                        symbol("save", SymbolKind::Function),
+                    ],
+                ),
+                file("README", None, 50, vec![]),
+                asset("assets/logo.png", AssetKind::Image, 20),
+                asset("assets/icon.png", AssetKind::Image, 30),
+                asset("models/model.onnx", AssetKind::Model, 250),
+            ],
+            languages: vec![Language::Rust],
+            frameworks: vec![],
+            duration_ms: 0,
+            skipped_count: 0,
+            excluded_dirs: vec![],
+        };
+        let mut tree = TreeBuilder::new().build(&scan);
+        let main = tree
+            .find_node_by_path(&PathBuf::from("src/main.rs"))
+            .unwrap();
+        let lib = tree
+            .find_node_by_path(&PathBuf::from("src/lib.rs"))
+            .unwrap();
+        tree.dependencies.add_edge(main, lib);
+
+        let stats = tree_stats(&tree, 2);
+        assert_eq!(stats.files, 6);
+        assert_eq!(stats.symbols, 4);
+        assert_eq!(stats.bytes, 750);
+        assert_eq!(stats.dependency_edges, 1);
+        assert_eq!(
+            stats.files_by_language,
+            vec![("Rust".to_string(), 2), ("Other".to_string(), 1)]
+        );
+        assert_eq!(
+            stats.assets_by_kind,
+            vec![(AssetKind::Model, 1, 250), (AssetKind::Image, 2, 50)]
+        );
+        assert_eq!(stats.symbols_by_kind[0], (SymbolKind::Function, 3));
+        assert_eq!(stats.largest_files.len(), 2);
+        assert_eq!(stats.largest_files[0].0, PathBuf::from("src/lib.rs"));
+        assert_eq!(stats.most_imported, vec![(PathBuf::from("src/lib.rs"), 1)]);
+        assert_eq!(
+            stats.most_importing,
+            vec![(PathBuf::from("src/main.rs"), 1)]
+        );
+    }
+}
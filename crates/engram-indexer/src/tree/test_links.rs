@@ -0,0 +1,327 @@
+//! Association of test files with the code they test.
+//!
+//! A file is a test when its name follows a test naming convention
+//! (`test_auth.py`, `auth_test.go`, `auth.spec.ts`, `AuthTest.cpp`) or it
+//! sits in a test directory (`tests/`, `__tests__/`). A test is linked to
+//! the source files sharing its stem, preferring those whose directories
+//! mirror its own; a test matching no source by name is linked to the files
+//! it imports instead. Rust files holding a `#[cfg(test)]` module are linked
+//! to themselves.
+
+use super::{NodeId, NodeKind, Tree};
+use crate::scanner::{Language, SymbolKind};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Component, Path};
+
+/// Directories holding tests
+const TEST_DIRS: &[&str] = &["test", "tests", "__tests__", "spec", "specs"];
+
+/// Stems too common to name the file a test covers
+const GENERIC_STEMS: &[&str] = &[
+    "__init__", "conftest", "common", "helpers", "index", "lib", "main", "mod", "setup", "utils",
+];
+
+/// Links between source files and their tests, in both directions.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TestLinks {
+    /// Source file -> test files
+    tests: HashMap<NodeId, BTreeSet<NodeId>>,
+
+    /// Test file -> source files
+    sources: HashMap<NodeId, BTreeSet<NodeId>>,
+}
+
+impl TestLinks {
+    /// Create an empty set of links.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that `test` tests `source`.
+    pub fn add_link(&mut self, source: NodeId, test: NodeId) {
+        self.tests.entry(source).or_default().insert(test);
+        self.sources.entry(test).or_default().insert(source);
+    }
+
+    /// Test files of a source file, including the file itself when it
+    /// holds inline tests.
+    pub fn tests_of(&self, source: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        self.tests
+            .get(&source)
+            .into_iter()
+            .flat_map(|set| set.iter().copied())
+    }
+
+    /// Source files tested by a test file.
+    pub fn sources_of(&self, test: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        self.sources
+            .get(&test)
+            .into_iter()
+            .flat_map(|set| set.iter().copied())
+    }
+
+    /// Number of source-test links.
+    pub fn link_count(&self) -> usize {
+        self.tests.values().map(|set| set.len()).sum()
+    }
+}
+
+/// Check whether a path names a test file by convention.
+pub fn is_test_path(path: &Path) -> bool {
+    let in_test_dir = path.parent().is_some_and(|dir| {
+        dir.components()
+            .any(|c| matches!(c, Component::Normal(name) if is_test_dir(name.to_str())))
+    });
+    in_test_dir || test_stem(path).is_some()
+}
+
+/// Rebuild the test links of every file.
+///
+/// Runs after [`resolve_imports`](super::resolve_imports), since tests
+/// matching no source by name fall back to their imports.
+pub fn link_tests(tree: &mut Tree) {
+    let mut links = TestLinks::new();
+    let mut tests = Vec::new();
+    // Non-test code files by stem
+    let mut sources: HashMap<&str, Vec<(NodeId, &Path, Language)>> = HashMap::new();
+
+    for node in tree.files() {
+        let Some(language) = node.language().filter(Language::has_parser) else {
+            continue;
+        };
+        if is_test_path(&node.path) {
+            tests.push((node.id, node.path.as_path(), language));
+        } else if let Some(stem) = node.path.file_stem().and_then(|s| s.to_str()) {
+            sources
+                .entry(stem)
+                .or_default()
+                .push((node.id, node.path.as_path(), language));
+        }
+
+        let inline = language == Language::Rust
+            && node.children.iter().any(|id| {
+                tree.get(*id).is_some_and(|child| {
+                    matches!(
+                        child.kind,
+                        NodeKind::Symbol {
+                            symbol_kind: SymbolKind::Test,
+                            ..
+                        }
+                    )
+                })
+            });
+        if inline {
+            links.add_link(node.id, node.id);
+        }
+    }
+
+    for (test, path, language) in tests {
+        let stem = test_stem(path)
+            .or_else(|| path.file_stem().and_then(|s| s.to_str()))
+            .filter(|stem| !GENERIC_STEMS.contains(stem));
+        let candidates: Vec<_> = stem
+            .and_then(|stem| sources.get(stem))
+            .into_iter()
+            .flatten()
+            .filter(|(_, _, other)| same_family(language, *other))
+            .map(|(id, source, _)| (*id, affinity(path, source)))
+            .collect();
+
+        match candidates.iter().map(|(_, score)| *score).max() {
+            Some(best) => {
+                for (source, score) in candidates {
+                    if score == best {
+                        links.add_link(source, test);
+                    }
+                }
+            }
+            None => {
+                let imports: Vec<NodeId> = tree.dependencies.imports(test).collect();
+                for source in imports {
+                    let is_source = tree
+                        .get(source)
+                        .is_some_and(|node| !is_test_path(&node.path));
+                    if is_source {
+                        links.add_link(source, test);
+                    }
+                }
+            }
+        }
+    }
+
+    tree.tests = links;
+}
+
+fn is_test_dir(name: Option<&str>) -> bool {
+    name.is_some_and(|name| TEST_DIRS.contains(&name))
+}
+
+/// Stem of the file a test covers, if the file name follows a test naming
+/// convention.
+fn test_stem(path: &Path) -> Option<&str> {
+    let stem = path.file_stem()?.to_str()?;
+    let stripped = stem
+        .strip_prefix("test_")
+        .or_else(|| stem.strip_suffix("_test"))
+        .or_else(|| stem.strip_suffix("_spec"))
+        .or_else(|| stem.strip_suffix(".test"))
+        .or_else(|| stem.strip_suffix(".spec"))
+        .or_else(|| stem.strip_suffix("Tests"))
+        .or_else(|| stem.strip_suffix("Test"))?;
+    (!stripped.is_empty()).then_some(stripped)
+}
+
+/// Languages whose files test each other.
+fn same_family(a: Language, b: Language) -> bool {
+    let family = |language| match language {
+        Language::JavaScript => Language::TypeScript,
+        Language::C => Language::Cpp,
+        other => other,
+    };
+    family(a) == family(b)
+}
+
+/// How closely a source's directory mirrors a test's: the same directory
+/// first, then the number of trailing directory names they share once test
+/// directories are dropped (`tests/api/` mirrors `src/api/`).
+fn affinity(test: &Path, source: &Path) -> (bool, usize) {
+    let dirs = |path: &Path| -> Vec<String> {
+        path.parent()
+            .into_iter()
+            .flat_map(Path::components)
+            .filter_map(|c| match c {
+                Component::Normal(name) => name.to_str(),
+                _ => None,
+            })
+            .filter(|name| !TEST_DIRS.contains(name))
+            .map(str::to_string)
+            .collect()
+    };
+    let same_dir = test.parent() == source.parent();
+    let shared = dirs(test)
+        .iter()
+        .rev()
+        .zip(dirs(source).iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    (same_dir, shared)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::{ScanResult, ScannedFile, Symbol};
+    use crate::tree::TreeBuilder;
+    use std::path::PathBuf;
+
+    fn file(path: &str, language: Language, imports: &[&str], symbols: Vec<Symbol>) -> ScannedFile {
+        ScannedFile {
+            language: Some(language),
+            symbols,
+            ..ScannedFile::for_test(path).with_imports(imports)
+        }
+    }
+
+    fn tree(files: Vec<ScannedFile>) -> Tree {
+        let scan = ScanResult {
+            root: PathBuf::from("/project"),
+            files,
+            languages: vec![],
+            frameworks: vec![],
+            duration_ms: 0,
+            skipped_count: 0,
+            excluded_dirs: vec![],
+        };
+        TreeBuilder::new().build(&scan)
+    }
+
+    fn paths(tree: &Tree, ids: impl Iterator<Item = NodeId>) -> Vec<PathBuf> {
+        let mut paths: Vec<_> = ids.map(|id| tree.get(id).unwrap().path.clone()).collect();
+        paths.sort();
+        paths
+    }
+
+    fn id(tree: &Tree, path: &str) -> NodeId {
+        tree.find_node_by_path(&PathBuf::from(path)).unwrap()
+    }
+
+    #[test]
+    fn test_is_test_path() {
+        for path in [
+            "tests/api.rs",
+            "pkg/auth/auth_test.go",
+            "test_auth.py",
+            "src/auth.spec.ts",
+            "src/__tests__/auth.js",
+            "src/WidgetTest.cpp",
+        ] {
+            assert!(is_test_path(Path::new(path)), "{}", path);
+        }
+        for path in ["src/auth.rs", "src/latest.py", "src/testing.go", "test.py"] {
+            assert!(!is_test_path(Path::new(path)), "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_link_by_name() {
+        let tree = tree(vec![
+            file("src/api/auth.ts", Language::TypeScript, &[], vec![]),
+            file("src/auth.ts", Language::TypeScript, &[], vec![]),
+            file("tests/api/auth.test.ts", Language::TypeScript, &[], vec![]),
+            file("pkg/db/db.go", Language::Go, &[], vec![]),
+            file("pkg/db/db_test.go", Language::Go, &[], vec![]),
+            file("auth.py", Language::Python, &[], vec![]),
+        ]);
+
+        let test = id(&tree, "tests/api/auth.test.ts");
+        assert_eq!(
+            paths(&tree, tree.tests.sources_of(test)),
+            vec![PathBuf::from("src/api/auth.ts")]
+        );
+        assert_eq!(
+            paths(&tree, tree.tests.tests_of(id(&tree, "src/api/auth.ts"))),
+            vec![PathBuf::from("tests/api/auth.test.ts")]
+        );
+        assert_eq!(tree.tests.tests_of(id(&tree, "src/auth.ts")).count(), 0);
+        assert_eq!(
+            paths(&tree, tree.tests.tests_of(id(&tree, "pkg/db/db.go"))),
+            vec![PathBuf::from("pkg/db/db_test.go")]
+        );
+        assert_eq!(tree.tests.link_count(), 2);
+    }
+
+    #[test]
+    fn test_link_by_imports_and_inline() {
+        let tests_module = Symbol {
+            name: "tests".to_string(),
+            kind: SymbolKind::Test,
+            start_line: 5,
+            end_line: 9,
+            parent: None,
+            doc: None,
+        };
+        let tree = tree(vec![
+            file("app/auth.py", Language::Python, &[], vec![]),
+            file("app/session.py", Language::Python, &[], vec![]),
+            file(
+                "tests/test_login.py",
+                Language::Python,
+                &["app.auth", "app.session"],
+                vec![],
+            ),
+            file("src/lib.rs", Language::Rust, &[], vec![tests_module]),
+        ]);
+
+        let test = id(&tree, "tests/test_login.py");
+        assert_eq!(
+            paths(&tree, tree.tests.sources_of(test)),
+            vec![
+                PathBuf::from("app/auth.py"),
+                PathBuf::from("app/session.py")
+            ]
+        );
+        let lib = id(&tree, "src/lib.rs");
+        assert_eq!(tree.tests.tests_of(lib).collect::<Vec<_>>(), vec![lib]);
+    }
+}
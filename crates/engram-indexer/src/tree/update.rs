@@ -0,0 +1,411 @@
+//! Incremental file updates to a built tree.
+//!
+//! Applies watcher changes to the persisted tree one file at a time instead
+//! of re-scanning the project. Node ids of untouched files are stable; a
+//! changed file is replaced with fresh nodes, dropping any enrichment it had.
+//! A file moved without edits keeps its nodes, enrichment and dependency
+//! edges under the new path.
+
+use super::prune::{remove_empty_dirs, remove_subtree};
+use super::{link_tests, resolve_imports, Node, NodeId, NodeKind, PruneReport, Tree, TreeBuilder};
+use crate::git;
+use crate::scanner::ScannedFile;
+use std::path::PathBuf;
+
+/// A change to apply to a single file.
+#[derive(Debug, Clone)]
+pub enum FileUpdate {
+    /// File was created or modified
+    Upsert(ScannedFile),
+    /// File was moved from `from` (relative to the root) to the scanned path
+    Rename { from: PathBuf, file: ScannedFile },
+    /// File was deleted or is no longer indexed (path relative to the root)
+    Remove(PathBuf),
+}
+
+/// What an update changed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UpdateReport {
+    pub added_files: usize,
+    pub updated_files: usize,
+    pub removed_files: usize,
+    pub renamed_files: usize,
+}
+
+impl UpdateReport {
+    /// Whether anything in the tree changed.
+    pub fn is_empty(&self) -> bool {
+        self.added_files + self.updated_files + self.removed_files + self.renamed_files == 0
+    }
+}
+
+/// Apply file updates to a tree.
+///
+/// Upserts of a file whose content hash is unchanged are no-ops, so
+/// touch-only events do not invalidate anything. Renames of a file whose
+/// content and language are unchanged move its nodes; otherwise the old
+/// path is removed and the new one indexed. The dependency graph is
+/// re-resolved whenever anything changed.
+pub fn apply_updates(tree: &mut Tree, updates: &[FileUpdate]) -> UpdateReport {
+    let mut report = UpdateReport::default();
+    let mut removed = PruneReport::default();
+    // Never hand out ids of removed nodes: scopes and indexes may still
+    // refer to them
+    let first_free = tree.nodes.keys().max().map_or(1, |id| id + 1);
+    let mut additions = Vec::new();
+    let mut moves = Vec::new();
+
+    // Remove first: removals can drop empty directories, which must happen
+    // before the builder snapshots the directory map.
+    for update in updates {
+        let (path, replacement) = match update {
+            FileUpdate::Upsert(file) => (&file.path, Some(file)),
+            FileUpdate::Rename { from, file } => {
+                let moved = file_at(tree, from)
+                    .filter(|node| from != &file.path && is_unchanged(node, file))
+                    .map(|node| node.id);
+                if let Some(id) = moved {
+                    // The move replaces whatever was at the new path
+                    if let Some(target) = file_at(tree, &file.path).map(|node| node.id) {
+                        remove_subtree(tree, target, &mut removed);
+                        report.removed_files += 1;
+                    }
+                    report.renamed_files += 1;
+                    moves.push((id, file));
+                    continue;
+                }
+                if let Some(old) = file_at(tree, from).filter(|_| from != &file.path) {
+                    let (id, parent) = (old.id, old.parent);
+                    remove_subtree(tree, id, &mut removed);
+                    report.removed_files += 1;
+                    if let Some(parent) = parent {
+                        remove_empty_dirs(tree, parent, &mut removed);
+                    }
+                }
+                (&file.path, Some(file))
+            }
+            FileUpdate::Remove(path) => (path, None),
+        };
+        let existing =
+            file_at(tree, path).map(|node| (node.id, node.parent, file_hash(&node.kind)));
+
+        match (existing, replacement) {
+            (Some((_, _, hash)), Some(file)) if hash == Some(file.hash.as_str()) => {}
+            (Some((id, parent, _)), replacement) => {
+                remove_subtree(tree, id, &mut removed);
+                if let Some(file) = replacement {
+                    report.updated_files += 1;
+                    additions.push(file);
+                } else {
+                    report.removed_files += 1;
+                    if let Some(parent) = parent {
+                        remove_empty_dirs(tree, parent, &mut removed);
+                    }
+                }
+            }
+            (None, Some(file)) => {
+                report.added_files += 1;
+                additions.push(file);
+            }
+            (None, None) => {}
+        }
+    }
+    tree.file_count -= removed.removed_files;
+    tree.symbol_count -= removed.removed_symbols;
+
+    let mut builder = TreeBuilder::extending(tree).with_first_id(first_free);
+    let mut vacated = Vec::new();
+    for (id, file) in moves {
+        let parent = builder.ensure_directories(&file.path, tree);
+        vacated.extend(move_file(tree, id, parent, file));
+    }
+    for file in additions {
+        builder.add_file(tree, file);
+    }
+    // Only once everything is placed, so a directory that is moved out of
+    // and into again keeps its id
+    for dir in vacated {
+        remove_empty_dirs(tree, dir, &mut removed);
+    }
+
+    if !report.is_empty() {
+        // New files may resolve imports that dangled before, and replaced
+        // files lost their edges with their old ids
+        resolve_imports(tree);
+        link_tests(tree);
+        // Changes often come from switching branches or committing
+        tree.git = git::state(&tree.root_path);
+        tree.touch();
+    }
+    report
+}
+
+fn file_at<'a>(tree: &'a Tree, path: &PathBuf) -> Option<&'a Node> {
+    tree.nodes
+        .values()
+        .find(|node| node.is_file() && &node.path == path)
+}
+
+/// Whether `node` can be moved to `file` as is.
+fn is_unchanged(node: &Node, file: &ScannedFile) -> bool {
+    matches!(&node.kind, NodeKind::File { language, hash, .. }
+        if *language == file.language && *hash == file.hash)
+}
+
+/// Move a file node and its symbols under `parent` at the scanned path.
+///
+/// Returns the directory it was moved out of, which may now be empty.
+fn move_file(tree: &mut Tree, id: NodeId, parent: NodeId, file: &ScannedFile) -> Option<NodeId> {
+    let node = tree.nodes.get_mut(&id)?;
+    let old_parent = node.parent.replace(parent);
+    node.name = file
+        .path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("unknown")
+        .to_string();
+    node.path = file.path.clone();
+    let symbols = node.children.clone();
+    for symbol in symbols {
+        if let Some(symbol) = tree.nodes.get_mut(&symbol) {
+            symbol.path = file.path.join(&symbol.name);
+        }
+    }
+
+    if old_parent == Some(parent) {
+        return None;
+    }
+    if let Some(old) = old_parent.and_then(|p| tree.nodes.get_mut(&p)) {
+        old.children.retain(|child| *child != id);
+    }
+    if let Some(new) = tree.nodes.get_mut(&parent) {
+        new.children.push(id);
+    }
+    old_parent
+}
+
+fn file_hash(kind: &NodeKind) -> Option<&str> {
+    match kind {
+        NodeKind::File { hash, .. } => Some(hash),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::{Language, ScanResult};
+
+    fn file(path: &str, hash: &str, symbols: &[&str]) -> ScannedFile {
+        let functions: Vec<_> = symbols.iter().map(|&name| (name, 1, 1)).collect();
+        ScannedFile {
+            hash: hash.to_string(),
+            ..ScannedFile::for_test(path).with_functions(&functions)
+        }
+    }
+
+    fn test_tree() -> Tree {
+        TreeBuilder::new().build(&ScanResult {
+            root: PathBuf::from("/project"),
+            files: vec![
+                file("src/lib.rs", "a", &["run"]),
+                file("src/util/fmt.rs", "b", &["pad"]),
+            ],
+            languages: vec![Language::Rust],
+            frameworks: vec![],
+            duration_ms: 0,
+            skipped_count: 0,
+            excluded_dirs: vec![],
+        })
+    }
+
+    #[test]
+    fn test_unchanged_hash_is_noop() {
+        let mut tree = test_tree();
+        let generation = tree.generation();
+        let report = apply_updates(
+            &mut tree,
+            &[FileUpdate::Upsert(file("src/lib.rs", "a", &[]))],
+        );
+        assert!(report.is_empty());
+        assert_eq!(tree.generation(), generation);
+    }
+
+    #[test]
+    fn test_modify_add_and_remove() {
+        let mut tree = test_tree();
+        let removed_symbol = tree.find_node_by_name("pad").unwrap();
+
+        let report = apply_updates(
+            &mut tree,
+            &[
+                FileUpdate::Upsert(file("src/lib.rs", "a2", &["run", "stop"])),
+                FileUpdate::Upsert(file("src/net/http.rs", "c", &["get"])),
+                FileUpdate::Remove(PathBuf::from("src/util/fmt.rs")),
+                FileUpdate::Remove(PathBuf::from("src/missing.rs")),
+            ],
+        );
+        assert_eq!(
+            report,
+            UpdateReport {
+                added_files: 1,
+                updated_files: 1,
+                removed_files: 1,
+                renamed_files: 0,
+            }
+        );
+
+        assert!(tree.find_node_by_name("stop").is_some());
+        assert!(tree.find_node_by_name("get").is_some());
+        assert!(tree.get(removed_symbol).is_none());
+        assert!(tree.find_by_path(&PathBuf::from("src/util")).is_none());
+        assert!(tree.find_by_path(&PathBuf::from("src/net")).is_some());
+        assert_eq!(tree.file_count, 2);
+        assert_eq!(tree.symbol_count, 3);
+
+        let ids: std::collections::HashSet<_> = tree.nodes.keys().collect();
+        assert_eq!(ids.len(), tree.nodes.len());
+    }
+
+    #[test]
+    fn test_rename_keeps_nodes_and_edges() {
+        let mut tree = test_tree();
+        let mut main = file("src/main.rs", "m", &[]);
+        main.imports = vec!["crate::util::fmt".to_string()];
+        apply_updates(&mut tree, &[FileUpdate::Upsert(main)]);
+        let main = tree
+            .find_node_by_path(&PathBuf::from("src/main.rs"))
+            .unwrap();
+        let fmt = tree
+            .find_node_by_path(&PathBuf::from("src/util/fmt.rs"))
+            .unwrap();
+        let pad = tree.find_node_by_name("pad").unwrap();
+        tree.nodes
+            .get_mut(&fmt)
+            .unwrap()
+            .content
+            .as_mut()
+            .unwrap()
+            .summary = Some("Formatting helpers".to_string());
+
+        // The moved importer keeps its edges
+        let mut moved_main = file("src/cli.rs", "m", &[]);
+        moved_main.imports = vec!["crate::util::fmt".to_string()];
+        let report = apply_updates(
+            &mut tree,
+            &[FileUpdate::Rename {
+                from: PathBuf::from("src/main.rs"),
+                file: moved_main,
+            }],
+        );
+        assert_eq!(report.renamed_files, 1);
+        assert_eq!(
+            tree.find_node_by_path(&PathBuf::from("src/cli.rs")),
+            Some(main)
+        );
+        assert!(tree.find_by_path(&PathBuf::from("src/main.rs")).is_none());
+        assert_eq!(
+            tree.dependencies.imports(main).collect::<Vec<_>>(),
+            vec![fmt]
+        );
+
+        // Symbols and enrichment move with the file; the old directory goes
+        apply_updates(
+            &mut tree,
+            &[FileUpdate::Rename {
+                from: PathBuf::from("src/util/fmt.rs"),
+                file: file("src/text/fmt.rs", "b", &["pad"]),
+            }],
+        );
+        let node = tree.get(fmt).unwrap();
+        assert_eq!(node.path, PathBuf::from("src/text/fmt.rs"));
+        assert_eq!(node.name, "fmt.rs");
+        assert_eq!(
+            node.content.as_ref().unwrap().summary.as_deref(),
+            Some("Formatting helpers")
+        );
+        assert_eq!(
+            tree.get(pad).unwrap().path,
+            PathBuf::from("src/text/fmt.rs/pad")
+        );
+        assert_eq!(tree.get(pad).unwrap().parent, Some(fmt));
+        assert!(tree.find_by_path(&PathBuf::from("src/util")).is_none());
+        let text = tree.find_node_by_path(&PathBuf::from("src/text")).unwrap();
+        assert_eq!(tree.get(text).unwrap().children, vec![fmt]);
+        assert_eq!(node.parent, Some(text));
+        assert_eq!(tree.file_count, 3);
+        assert_eq!(tree.symbol_count, 2);
+    }
+
+    #[test]
+    fn test_rename_with_edits_reindexes() {
+        let mut tree = test_tree();
+        let old = tree
+            .find_node_by_path(&PathBuf::from("src/util/fmt.rs"))
+            .unwrap();
+        let report = apply_updates(
+            &mut tree,
+            &[FileUpdate::Rename {
+                from: PathBuf::from("src/util/fmt.rs"),
+                file: file("src/fmt.rs", "b2", &["pad", "trim"]),
+            }],
+        );
+        assert_eq!(
+            report,
+            UpdateReport {
+                added_files: 1,
+                updated_files: 0,
+                removed_files: 1,
+                renamed_files: 0,
+            }
+        );
+        assert!(tree.get(old).is_none());
+        assert!(tree.find_node_by_name("trim").is_some());
+        assert_eq!(tree.file_count, 2);
+    }
+
+    #[test]
+    fn test_updates_reresolve_imports() {
+        let mut tree = test_tree();
+        let mut main = file("src/main.rs", "m", &[]);
+        main.imports = vec!["crate::net".to_string()];
+        apply_updates(&mut tree, &[FileUpdate::Upsert(main)]);
+        let main = tree
+            .find_node_by_path(&PathBuf::from("src/main.rs"))
+            .unwrap();
+        let lib = tree
+            .find_node_by_path(&PathBuf::from("src/lib.rs"))
+            .unwrap();
+        // Could be an item of the crate root
+        assert_eq!(
+            tree.dependencies.imports(main).collect::<Vec<_>>(),
+            vec![lib]
+        );
+
+        // The module resolves once it exists
+        apply_updates(
+            &mut tree,
+            &[FileUpdate::Upsert(file("src/net.rs", "n", &[]))],
+        );
+        let net = tree
+            .find_node_by_path(&PathBuf::from("src/net.rs"))
+            .unwrap();
+        assert_eq!(
+            tree.dependencies.imports(main).collect::<Vec<_>>(),
+            vec![net]
+        );
+
+        // and follows the target when it is replaced
+        apply_updates(
+            &mut tree,
+            &[FileUpdate::Upsert(file("src/net.rs", "n2", &[]))],
+        );
+        let net = tree
+            .find_node_by_path(&PathBuf::from("src/net.rs"))
+            .unwrap();
+        assert_eq!(
+            tree.dependencies.imports(main).collect::<Vec<_>>(),
+            vec![net]
+        );
+    }
+}
@@ -4,8 +4,9 @@
 //! file system event monitoring with debouncing.
 
 use crate::IndexerError;
-use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
-use notify_debouncer_full::{new_debouncer, DebouncedEvent, Debouncer, FileIdMap};
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode};
+use notify_debouncer_full::{new_debouncer, DebouncedEvent, Debouncer, RecommendedCache};
 use std::path::{Path, PathBuf};
 use std::time::Duration;
 use tokio::sync::mpsc;
@@ -20,8 +21,8 @@ pub enum ChangeKind {
     Modified,
     /// File was deleted
     Deleted,
-    /// File was renamed (old path, new path available in event)
-    Renamed,
+    /// File or directory was moved here from `from`
+    Renamed { from: PathBuf },
 }
 
 /// A file system change event.
@@ -56,7 +57,7 @@ pub struct FileWatcher {
     options: WatcherOptions,
     tx: mpsc::Sender<FileChange>,
     rx: mpsc::Receiver<FileChange>,
-    _debouncer: Option<Debouncer<RecommendedWatcher, FileIdMap>>,
+    _debouncer: Option<Debouncer<RecommendedWatcher, RecommendedCache>>,
 }
 
 impl FileWatcher {
@@ -138,6 +139,19 @@ impl FileWatcher {
 
 /// Convert a notify Event to our FileChange.
 fn convert_event(event: &Event) -> Option<FileChange> {
+    // The debouncer pairs the two halves of a rename into one event with
+    // the old and new path. Directories are kept so everything below them
+    // can be moved.
+    if let EventKind::Modify(ModifyKind::Name(RenameMode::Both)) = event.kind {
+        if let [from, to, ..] = event.paths.as_slice() {
+            debug!(from = ?from, to = ?to, "File rename detected");
+            return Some(FileChange {
+                path: to.clone(),
+                kind: ChangeKind::Renamed { from: from.clone() },
+            });
+        }
+    }
+
     let path = event.paths.first()?.clone();
 
     // Only care about files, not directories
@@ -146,6 +160,9 @@ fn convert_event(event: &Event) -> Option<FileChange> {
     }
 
     let kind = match &event.kind {
+        // Unpaired halves: moved out of or into the watched tree
+        EventKind::Modify(ModifyKind::Name(RenameMode::From)) => ChangeKind::Deleted,
+        EventKind::Modify(ModifyKind::Name(RenameMode::To)) => ChangeKind::Created,
         EventKind::Create(_) => ChangeKind::Created,
         EventKind::Modify(_) => ChangeKind::Modified,
         EventKind::Remove(_) => ChangeKind::Deleted,
@@ -178,19 +195,66 @@ impl ChangeBatcher {
 
     /// Add a change to the batch.
     pub fn add(&mut self, change: FileChange) {
+        if let ChangeKind::Renamed { from } = change.kind {
+            self.add_rename(from, change.path);
+            return;
+        }
+
         // Deduplicate: if we already have a change for this path, update it
         if let Some(existing) = self.changes.iter_mut().find(|c| c.path == change.path) {
-            // Delete always wins over modify/create
-            if change.kind == ChangeKind::Deleted {
-                existing.kind = ChangeKind::Deleted;
-            } else if existing.kind != ChangeKind::Deleted {
-                existing.kind = change.kind;
+            match (&existing.kind, change.kind) {
+                // The moved file is gone again: drop it under its old path
+                (ChangeKind::Renamed { from }, ChangeKind::Deleted) => {
+                    let from = from.clone();
+                    existing.kind = ChangeKind::Deleted;
+                    self.add(FileChange {
+                        path: from,
+                        kind: ChangeKind::Deleted,
+                    });
+                }
+                // Edits after a move are picked up when the new path is scanned
+                (ChangeKind::Renamed { .. }, _) => {}
+                // Delete always wins over modify/create
+                (_, ChangeKind::Deleted) => existing.kind = ChangeKind::Deleted,
+                (ChangeKind::Deleted, _) => {}
+                (_, kind) => existing.kind = kind,
             }
         } else {
             self.changes.push(change);
         }
     }
 
+    /// Fold a move from `from` to `to` into the pending changes.
+    ///
+    /// Chained moves collapse into one from the original path, and a file
+    /// created within the batch stays a creation at its final path.
+    fn add_rename(&mut self, from: PathBuf, to: PathBuf) {
+        let pending = self
+            .changes
+            .iter()
+            .position(|c| c.path == from)
+            .map(|i| self.changes.remove(i).kind);
+        let kind = match pending {
+            Some(ChangeKind::Created) => ChangeKind::Created,
+            Some(ChangeKind::Renamed { from: original }) if original == to => ChangeKind::Modified,
+            Some(ChangeKind::Renamed { from: original }) => ChangeKind::Renamed { from: original },
+            _ => ChangeKind::Renamed { from },
+        };
+
+        // The move replaces whatever was at the new path
+        if let Some(existing) = self.changes.iter_mut().find(|c| c.path == to) {
+            let replaced = std::mem::replace(&mut existing.kind, kind);
+            if let ChangeKind::Renamed { from } = replaced {
+                self.add(FileChange {
+                    path: from,
+                    kind: ChangeKind::Deleted,
+                });
+            }
+        } else {
+            self.changes.push(FileChange { path: to, kind });
+        }
+    }
+
     /// Check if the batch is ready to process.
     pub fn is_ready(&self) -> bool {
         !self.changes.is_empty() && self.last_batch.elapsed() >= self.batch_timeout
@@ -216,7 +280,6 @@ impl ChangeBatcher {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::fs;
     use tempfile::tempdir;
 
     #[test]
@@ -281,6 +344,74 @@ mod tests {
         assert_eq!(batch[0].kind, ChangeKind::Deleted);
     }
 
+    #[test]
+    fn test_change_batcher_renames() {
+        let mut batcher = ChangeBatcher::new(Duration::from_millis(100));
+        let rename = |from: &str, to: &str| FileChange {
+            path: PathBuf::from(to),
+            kind: ChangeKind::Renamed {
+                from: PathBuf::from(from),
+            },
+        };
+
+        // Chained moves collapse, edits after a move keep the rename
+        batcher.add(rename("a.rs", "b.rs"));
+        batcher.add(rename("b.rs", "c.rs"));
+        batcher.add(FileChange {
+            path: PathBuf::from("c.rs"),
+            kind: ChangeKind::Modified,
+        });
+        // A file created within the batch is just created elsewhere
+        batcher.add(FileChange {
+            path: PathBuf::from("new.rs"),
+            kind: ChangeKind::Created,
+        });
+        batcher.add(rename("new.rs", "moved.rs"));
+        // Moving back and forth is a plain modification
+        batcher.add(rename("x.rs", "y.rs"));
+        batcher.add(rename("y.rs", "x.rs"));
+
+        let batch = batcher.take();
+        let kinds: Vec<_> = batch
+            .iter()
+            .map(|c| (c.path.to_str().unwrap(), c.kind.clone()))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (
+                    "c.rs",
+                    ChangeKind::Renamed {
+                        from: PathBuf::from("a.rs")
+                    }
+                ),
+                ("moved.rs", ChangeKind::Created),
+                ("x.rs", ChangeKind::Modified),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_change_batcher_delete_after_rename() {
+        let mut batcher = ChangeBatcher::new(Duration::from_millis(100));
+        batcher.add(FileChange {
+            path: PathBuf::from("b.rs"),
+            kind: ChangeKind::Renamed {
+                from: PathBuf::from("a.rs"),
+            },
+        });
+        batcher.add(FileChange {
+            path: PathBuf::from("b.rs"),
+            kind: ChangeKind::Deleted,
+        });
+
+        let mut batch = batcher.take();
+        batch.sort_by(|a, b| a.path.cmp(&b.path));
+        assert_eq!(batch.len(), 2);
+        assert!(batch.iter().all(|c| c.kind == ChangeKind::Deleted));
+        assert_eq!(batch[0].path, PathBuf::from("a.rs"));
+    }
+
     #[test]
     fn test_change_batcher_take() {
         let mut batcher = ChangeBatcher::new(Duration::from_millis(100));
@@ -340,6 +471,30 @@ mod tests {
         assert_eq!(change.unwrap().kind, ChangeKind::Deleted);
     }
 
+    #[test]
+    fn test_convert_event_rename() {
+        let event = Event {
+            kind: EventKind::Modify(ModifyKind::Name(RenameMode::Both)),
+            paths: vec![PathBuf::from("old.rs"), PathBuf::from("new.rs")],
+            attrs: Default::default(),
+        };
+        let change = convert_event(&event).unwrap();
+        assert_eq!(change.path, PathBuf::from("new.rs"));
+        assert_eq!(
+            change.kind,
+            ChangeKind::Renamed {
+                from: PathBuf::from("old.rs")
+            }
+        );
+
+        let moved_out = Event {
+            kind: EventKind::Modify(ModifyKind::Name(RenameMode::From)),
+            paths: vec![PathBuf::from("old.rs")],
+            attrs: Default::default(),
+        };
+        assert_eq!(convert_event(&moved_out).unwrap().kind, ChangeKind::Deleted);
+    }
+
     #[test]
     fn test_convert_event_access_ignored() {
         let event = Event {
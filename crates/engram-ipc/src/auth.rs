@@ -0,0 +1,131 @@
+//! Access control for IPC connections.
+//!
+//! On Unix every connection is checked against the peer's credentials
+//! (`SO_PEERCRED` on Linux, `getpeereid` on the BSDs and macOS): only the
+//! user running the daemon and any explicitly allowed UIDs may talk to it.
+//! Optionally a shared token is required as well; clients send it in an
+//! [`Request::Authenticate`](crate::Request::Authenticate) frame ahead of
+//! their request.
+
+use crate::transport::ServerStream;
+
+/// Environment variable the client reads its token from
+pub const TOKEN_ENV: &str = "ENGRAM_TOKEN";
+
+/// Who may issue requests to the daemon
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccessPolicy {
+    /// UIDs allowed besides the daemon's own user
+    pub allowed_uids: Vec<u32>,
+
+    /// Shared token every connection must present
+    pub token: Option<String>,
+}
+
+impl AccessPolicy {
+    /// Policy admitting only the daemon's own user
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also admit these UIDs
+    pub fn with_allowed_uids(mut self, uids: Vec<u32>) -> Self {
+        self.allowed_uids = uids;
+        self
+    }
+
+    /// Require a shared token
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into()).filter(|token| !token.is_empty());
+        self
+    }
+
+    /// Whether a peer with this UID may connect
+    pub fn allows_uid(&self, uid: u32) -> bool {
+        uid == current_uid() || self.allowed_uids.contains(&uid)
+    }
+
+    /// Whether `token` matches the configured token (always true without one)
+    pub fn accepts_token(&self, token: &str) -> bool {
+        match &self.token {
+            Some(expected) => constant_time_eq(expected.as_bytes(), token.as_bytes()),
+            None => true,
+        }
+    }
+
+    /// Check the credentials of a connected peer.
+    pub(crate) fn check_peer(&self, stream: &ServerStream) -> Result<(), String> {
+        match peer_uid(stream) {
+            Ok(Some(uid)) if self.allows_uid(uid) => Ok(()),
+            Ok(Some(uid)) => Err(format!("UID {} is not allowed to use this daemon", uid)),
+            // No peer credentials on this platform
+            Ok(None) => Ok(()),
+            Err(e) => Err(format!("Failed to read peer credentials: {}", e)),
+        }
+    }
+}
+
+/// Whether a connected peer runs as the daemon's own user, as it must to
+/// have the daemon open files at paths it names.
+pub(crate) fn peer_is_owner(stream: &ServerStream) -> bool {
+    match peer_uid(stream) {
+        Ok(Some(uid)) => uid == current_uid(),
+        // No peer credentials on this platform
+        Ok(None) => true,
+        Err(_) => false,
+    }
+}
+
+/// Compare without short-circuiting on the first differing byte.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(unix)]
+fn current_uid() -> u32 {
+    // SAFETY: geteuid has no preconditions and cannot fail
+    unsafe { libc::geteuid() }
+}
+
+#[cfg(not(unix))]
+fn current_uid() -> u32 {
+    0
+}
+
+#[cfg(unix)]
+fn peer_uid(stream: &ServerStream) -> std::io::Result<Option<u32>> {
+    stream.peer_cred().map(|cred| Some(cred.uid()))
+}
+
+#[cfg(not(unix))]
+fn peer_uid(_stream: &ServerStream) -> std::io::Result<Option<u32>> {
+    // Named pipes already reject remote clients
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allows_own_and_listed_uids() {
+        let policy = AccessPolicy::new();
+        assert!(policy.allows_uid(current_uid()));
+        assert!(!policy.allows_uid(current_uid().wrapping_add(1)));
+
+        let policy = policy.with_allowed_uids(vec![current_uid().wrapping_add(1)]);
+        assert!(policy.allows_uid(current_uid().wrapping_add(1)));
+    }
+
+    #[test]
+    fn test_accepts_token() {
+        assert!(AccessPolicy::new().accepts_token("anything"));
+        assert!(AccessPolicy::new().with_token("").accepts_token(""));
+
+        let policy = AccessPolicy::new().with_token("secret");
+        assert!(policy.accepts_token("secret"));
+        assert!(!policy.accepts_token("secreT"));
+        assert!(!policy.accepts_token("secret2"));
+        assert!(!policy.accepts_token(""));
+    }
+}
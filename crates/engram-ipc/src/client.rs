@@ -1,13 +1,32 @@
 //! IPC client for communicating with the Engram daemon.
+//!
+//! Clients can start the daemon on first use: with auto-start enabled, a
+//! request that finds no daemon runs the start command, waits for the socket
+//! with backoff, and retries. The command is `engram-daemon` by default, or
+//! anything that brings the socket up, such as a launchd or systemd unit.
+//!
+//! One-off requests reuse idle connections from a small pool and retry
+//! transient failures with jittered exponential backoff (see
+//! [`RetryPolicy`]), so a daemon restart between hook invocations goes
+//! unnoticed. A request that may already have reached the daemon is only
+//! retried if it is idempotent, and only idempotent requests go out on
+//! pooled connections: one the daemon closed while idle fails only once the
+//! request is written, when it cannot tell whether the daemon read it.
+//!
+//! [`ConnectedClient::hello`] negotiates a protocol version and reports
+//! the daemon's features, so clients can degrade gracefully against older
+//! daemons.
 
-use crate::{IpcError, Request, Response};
+use crate::frame;
+use crate::paths;
+use crate::transport::{self, ClientStream};
+use crate::{IpcError, Request, Response, ResponseData, FEATURES, PROTOCOL_VERSION, TOKEN_ENV};
+use std::hash::BuildHasher;
 use std::path::{Path, PathBuf};
-use std::time::Duration;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::UnixStream;
-
-/// Default socket path
-const DEFAULT_SOCKET_PATH: &str = "/tmp/engram.sock";
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
 
 /// Connection timeout
 const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
@@ -15,26 +34,192 @@ const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
 /// Request/response timeout
 const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
 
+/// Environment variable enabling auto-start: `1` runs `engram-daemon`, any
+/// other value is the start command, e.g. `systemctl --user start engram`
+pub const AUTO_START_ENV: &str = "ENGRAM_AUTO_START";
+
+/// Daemon binary run by the default auto-start command
+const DAEMON_PROGRAM: &str = "engram-daemon";
+
+/// How long an auto-started daemon gets to bring its socket up
+const AUTO_START_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// First wait between socket checks; doubles up to `MAX_BACKOFF`
+const INITIAL_BACKOFF: Duration = Duration::from_millis(10);
+
+/// Longest wait between socket checks
+const MAX_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Idle connections kept for reuse by default
+const POOL_SIZE: usize = 4;
+
+/// How long a pooled connection may idle; shorter than the daemon's own
+/// idle timeout, so it is dropped before the daemon closes it
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How a client retries requests that hit a transient failure
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts in total, including the first
+    pub attempts: u32,
+    /// Wait before the first retry; doubles with each further one
+    pub initial_backoff: Duration,
+    /// Longest wait between attempts
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Send each request once
+    pub fn none() -> Self {
+        Self {
+            attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Make this many attempts in total
+    pub fn with_attempts(mut self, attempts: u32) -> Self {
+        self.attempts = attempts.max(1);
+        self
+    }
+
+    /// Wait before retry number `retry` (from 1): the exponential backoff,
+    /// of which the upper half is random so clients retrying together
+    /// spread out
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let exponent = retry.saturating_sub(1).min(16);
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(1 << exponent)
+            .min(self.max_backoff);
+        let half = backoff / 2;
+        let random = std::collections::hash_map::RandomState::new().hash_one(retry);
+        half + half.mul_f64((random % 1024) as f64 / 1024.0)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 3,
+            initial_backoff: Duration::from_millis(50),
+            max_backoff: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Command a client runs to start a daemon that is not running
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AutoStart {
+    /// Program to run
+    pub program: String,
+    /// Its arguments
+    pub args: Vec<String>,
+    /// How long to wait for the socket after starting it
+    pub timeout: Duration,
+}
+
+impl AutoStart {
+    /// Run `engram-daemon` from `PATH`
+    pub fn daemon() -> Self {
+        Self {
+            program: DAEMON_PROGRAM.to_string(),
+            args: vec![],
+            timeout: AUTO_START_TIMEOUT,
+        }
+    }
+
+    /// Parse an `ENGRAM_AUTO_START` value; `None` if it disables auto-start
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "" | "0" | "false" | "off" => None,
+            "1" | "true" | "on" => Some(Self::daemon()),
+            command => {
+                let mut words = command.split_whitespace().map(str::to_string);
+                Some(Self {
+                    program: words.next()?,
+                    args: words.collect(),
+                    timeout: AUTO_START_TIMEOUT,
+                })
+            }
+        }
+    }
+
+    /// Wait this long for the socket
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
 /// IPC client for communicating with the daemon
 pub struct IpcClient {
     socket_path: PathBuf,
+    token: Option<String>,
+    auto_start: Option<AutoStart>,
+    retry: RetryPolicy,
+    pool_size: usize,
+    /// Idle connections, most recently used last
+    pool: Mutex<Vec<PooledConnection>>,
+}
+
+/// A connection waiting in the pool
+struct PooledConnection {
+    client: ConnectedClient,
+    idle_since: Instant,
 }
 
 impl IpcClient {
-    /// Create a client with default socket path
+    /// Create a client for the socket from `ENGRAM_SOCKET`, the daemon
+    /// config, or the default per-user path
     pub fn new() -> Self {
-        Self {
-            socket_path: PathBuf::from(DEFAULT_SOCKET_PATH),
-        }
+        Self::with_socket_path(paths::discover_socket_path())
     }
 
     /// Create a client with custom socket path
+    ///
+    /// The token for daemons that require one is taken from `ENGRAM_TOKEN`,
+    /// and auto-start from `ENGRAM_AUTO_START`.
     pub fn with_socket_path<P: AsRef<Path>>(socket_path: P) -> Self {
         Self {
             socket_path: socket_path.as_ref().to_path_buf(),
+            token: std::env::var(TOKEN_ENV)
+                .ok()
+                .filter(|token| !token.is_empty()),
+            auto_start: std::env::var(AUTO_START_ENV)
+                .ok()
+                .and_then(|value| AutoStart::parse(&value)),
+            retry: RetryPolicy::default(),
+            pool_size: POOL_SIZE,
+            pool: Mutex::new(Vec::new()),
         }
     }
 
+    /// Authenticate with this token
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Start the daemon with this command when it is not running
+    pub fn with_auto_start(mut self, auto_start: AutoStart) -> Self {
+        self.auto_start = Some(auto_start);
+        self
+    }
+
+    /// Retry transient failures with this policy
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Keep up to this many idle connections for reuse; 0 opens a new
+    /// connection for every request
+    pub fn with_pool_size(mut self, pool_size: usize) -> Self {
+        self.pool_size = pool_size;
+        self
+    }
+
     /// Connect to the daemon and return a connected client
     pub async fn connect() -> Result<ConnectedClient, IpcError> {
         Self::new().do_connect().await
@@ -46,30 +231,89 @@ impl IpcClient {
     }
 
     async fn do_connect(&self) -> Result<ConnectedClient, IpcError> {
-        if !self.socket_path.exists() {
+        Ok(ConnectedClient {
+            stream: self.open().await?,
+            token: self.token.clone(),
+            protocol: 0,
+        })
+    }
+
+    /// Open a stream, first starting the daemon if it is down and
+    /// auto-start is enabled.
+    async fn open(&self) -> Result<ClientStream, IpcError> {
+        match (self.try_open().await, &self.auto_start) {
+            (Err(e), Some(auto_start)) if is_down(&e) => {
+                self.start_daemon(auto_start).await?;
+                self.try_open().await
+            }
+            (result, _) => result,
+        }
+    }
+
+    async fn try_open(&self) -> Result<ClientStream, IpcError> {
+        if !self.is_daemon_running() {
             return Err(IpcError::DaemonNotRunning);
         }
 
-        let stream = tokio::time::timeout(CONNECT_TIMEOUT, UnixStream::connect(&self.socket_path))
+        let stream = tokio::time::timeout(CONNECT_TIMEOUT, transport::connect(&self.socket_path))
             .await
             .map_err(|_| IpcError::ConnectionFailed("Connection timed out".to_string()))??;
+        Ok(stream)
+    }
+
+    /// Run the start command and wait, with backoff, until the daemon
+    /// accepts connections.
+    async fn start_daemon(&self, auto_start: &AutoStart) -> Result<(), IpcError> {
+        tracing::info!(program = %auto_start.program, "Starting daemon");
+        let mut command = std::process::Command::new(&auto_start.program);
+        command
+            .args(&auto_start.args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        // Keep the daemon out of the caller's process group, so it outlives
+        // a hook interrupted with Ctrl+C
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut command, 0);
+        let mut child = command.spawn().map_err(|e| {
+            IpcError::ConnectionFailed(format!("Cannot start {}: {}", auto_start.program, e))
+        })?;
 
-        Ok(ConnectedClient { stream })
+        let deadline = Instant::now() + auto_start.timeout;
+        let mut backoff = INITIAL_BACKOFF;
+        loop {
+            if self.try_open().await.is_ok() {
+                return Ok(());
+            }
+            // Activation commands exit once the unit is started; the daemon
+            // itself keeps running
+            if let Ok(Some(status)) = child.try_wait() {
+                if !status.success() {
+                    return Err(IpcError::ConnectionFailed(format!(
+                        "{} exited with {}",
+                        auto_start.program, status
+                    )));
+                }
+            }
+            if Instant::now() >= deadline {
+                return Err(IpcError::ConnectionFailed(format!(
+                    "Daemon did not start within {:?}",
+                    auto_start.timeout
+                )));
+            }
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
     }
 
     /// Send a fire-and-forget request (don't wait for response)
     pub async fn send_async(&self, request: &Request) -> Result<(), IpcError> {
-        if !self.socket_path.exists() {
-            return Err(IpcError::DaemonNotRunning);
-        }
-
-        let mut stream = UnixStream::connect(&self.socket_path).await?;
-
-        let request_bytes = rmp_serde::to_vec(request)?;
-        let len_bytes = (request_bytes.len() as u32).to_le_bytes();
+        let mut stream = self.open().await?;
 
-        stream.write_all(&len_bytes).await?;
-        stream.write_all(&request_bytes).await?;
+        if let Some(token) = &self.token {
+            write_request(&mut stream, 0, &authenticate(token)).await?;
+        }
+        write_request(&mut stream, 0, request).await?;
 
         // Don't wait for response
         Ok(())
@@ -77,7 +321,7 @@ impl IpcClient {
 
     /// Check if daemon is running
     pub fn is_daemon_running(&self) -> bool {
-        self.socket_path.exists()
+        transport::endpoint_exists(&self.socket_path)
     }
 }
 
@@ -87,12 +331,83 @@ impl Default for IpcClient {
     }
 }
 
+/// What a daemon reported about itself in `Hello`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DaemonInfo {
+    /// Daemon version, or `None` for a daemon predating `Hello`
+    pub version: Option<String>,
+    /// Highest protocol version the daemon speaks
+    pub protocol_version: u8,
+    /// Features the daemon supports
+    pub features: Vec<String>,
+}
+
+impl DaemonInfo {
+    /// A daemon predating `Hello`, assumed to support no optional features
+    pub fn legacy() -> Self {
+        Self {
+            version: None,
+            protocol_version: 0,
+            features: Vec::new(),
+        }
+    }
+
+    /// Whether the daemon advertised `feature`
+    pub fn supports(&self, feature: &str) -> bool {
+        self.features.iter().any(|f| f == feature)
+    }
+}
+
 /// A connected IPC client that can send requests and receive responses
 pub struct ConnectedClient {
-    stream: UnixStream,
+    stream: ClientStream,
+    token: Option<String>,
+    /// Negotiated protocol version, 0 until a `Hello` succeeds
+    protocol: u8,
 }
 
 impl ConnectedClient {
+    /// Exchange versions and features with the daemon.
+    ///
+    /// Later requests on this connection use the negotiated protocol. A
+    /// daemon predating `Hello` rejects it and closes the connection, so
+    /// callers getting [`DaemonInfo::legacy`] must reconnect.
+    pub async fn hello(&mut self) -> Result<DaemonInfo, IpcError> {
+        let response = self
+            .send(Request::Hello {
+                client_version: env!("CARGO_PKG_VERSION").to_string(),
+                protocol_version: PROTOCOL_VERSION,
+                supported_features: FEATURES.iter().map(|f| f.to_string()).collect(),
+            })
+            .await?;
+
+        match response {
+            Response::Ok {
+                data:
+                    Some(ResponseData::Hello {
+                        daemon_version,
+                        protocol_version,
+                        features,
+                    }),
+            } => {
+                self.protocol = protocol_version.min(PROTOCOL_VERSION);
+                Ok(DaemonInfo {
+                    version: Some(daemon_version),
+                    protocol_version,
+                    features,
+                })
+            }
+            Response::Error {
+                code: crate::ErrorCode::InvalidRequest,
+                ..
+            } => Ok(DaemonInfo::legacy()),
+            Response::Error { message, .. } => Err(IpcError::ConnectionFailed(message)),
+            _ => Err(IpcError::ConnectionFailed(
+                "Unexpected response".to_string(),
+            )),
+        }
+    }
+
     /// Send a request and wait for response
     pub async fn send(&mut self, request: Request) -> Result<Response, IpcError> {
         tokio::time::timeout(REQUEST_TIMEOUT, self.do_send(request))
@@ -101,37 +416,200 @@ impl ConnectedClient {
     }
 
     async fn do_send(&mut self, request: Request) -> Result<Response, IpcError> {
-        // Serialize request
-        let request_bytes = rmp_serde::to_vec(&request)?;
-        let len_bytes = (request_bytes.len() as u32).to_le_bytes();
-
-        // Send request
-        self.stream.write_all(&len_bytes).await?;
-        self.stream.write_all(&request_bytes).await?;
-        self.stream.flush().await?;
+        self.exchange(&request)
+            .await
+            .map_err(|failure| failure.error)
+    }
 
-        // Read response length
-        let mut len_buf = [0u8; 4];
-        self.stream.read_exact(&mut len_buf).await?;
-        let len = u32::from_le_bytes(len_buf) as usize;
+    /// Send a request and read its response, noting whether a failure left
+    /// the request undelivered
+    async fn exchange(&mut self, request: &Request) -> Result<Response, Failure> {
+        // Send request, preceded by the token if we have one
+        let written = async {
+            if let Some(token) = &self.token {
+                write_request(&mut self.stream, self.protocol, &authenticate(token)).await?;
+            }
+            write_request(&mut self.stream, self.protocol, request).await?;
+            self.stream.flush().await?;
+            Ok(())
+        };
+        written.await.map_err(Failure::undelivered)?;
 
-        // Read response body
-        let mut response_buf = vec![0u8; len];
-        self.stream.read_exact(&mut response_buf).await?;
+        // Read response
+        let (version, response_buf) =
+            frame::read_frame(&mut self.stream, self.protocol != 0, usize::MAX)
+                .await
+                .map_err(Failure::delivered)?;
 
         // Deserialize response
-        let response: Response = rmp_serde::from_slice(&response_buf)?;
+        rmp_serde::from_slice(&response_buf)
+            .map_err(|e| Failure::delivered(frame::decode_error(version, e)))
+    }
+}
 
-        Ok(response)
+/// A failed request, and whether the daemon may have received it
+struct Failure {
+    error: IpcError,
+    delivered: bool,
+}
+
+impl Failure {
+    fn undelivered(error: IpcError) -> Self {
+        Self {
+            error,
+            delivered: false,
+        }
+    }
+
+    fn delivered(error: IpcError) -> Self {
+        Self {
+            error,
+            delivered: true,
+        }
+    }
+
+    /// Whether sending the request again is safe and may succeed
+    fn is_retryable(&self, idempotent: bool) -> bool {
+        let transient = matches!(self.error, IpcError::DaemonNotRunning | IpcError::Io(_));
+        transient && (idempotent || !self.delivered)
+    }
+}
+
+/// Whether a connection error means no daemon is listening, including a
+/// socket left behind by one that died
+fn is_down(error: &IpcError) -> bool {
+    match error {
+        IpcError::DaemonNotRunning => true,
+        IpcError::Io(e) => matches!(
+            e.kind(),
+            std::io::ErrorKind::ConnectionRefused | std::io::ErrorKind::NotFound
+        ),
+        _ => false,
     }
 }
 
+/// Token frame sent ahead of a request
+fn authenticate(token: &str) -> Request {
+    Request::Authenticate {
+        token: token.to_string(),
+    }
+}
+
+/// Write one length-prefixed MessagePack request in a frame of `protocol`
+async fn write_request(
+    stream: &mut ClientStream,
+    protocol: u8,
+    request: &Request,
+) -> Result<(), IpcError> {
+    // Named fields, since optional fields are skipped when unset
+    let request_bytes = rmp_serde::to_vec_named(request)?;
+    frame::write_frame(stream, protocol, &request_bytes).await
+}
+
 /// Convenience functions for one-off requests
 impl IpcClient {
-    /// Send a request and wait for response (opens new connection)
+    /// Send a request and wait for response, on a pooled connection or a
+    /// new one, retrying transient failures
     pub async fn request(&self, request: Request) -> Result<Response, IpcError> {
+        let idempotent = request.is_idempotent();
+        let mut attempt = 1;
+        loop {
+            match self.request_once(&request).await {
+                Ok(response) => return Ok(response),
+                Err(failure)
+                    if attempt < self.retry.attempts && failure.is_retryable(idempotent) =>
+                {
+                    let backoff = self.retry.backoff(attempt);
+                    tracing::debug!(
+                        action = request.action_name(),
+                        attempt,
+                        error = %failure.error,
+                        "Retrying request in {:?}",
+                        backoff
+                    );
+                    tokio::time::sleep(backoff).await;
+                    attempt += 1;
+                }
+                Err(failure) => return Err(failure.error),
+            }
+        }
+    }
+
+    async fn request_once(&self, request: &Request) -> Result<Response, Failure> {
+        let pooled = if request.is_idempotent() {
+            self.checkout()
+        } else {
+            None
+        };
+        let mut client = match pooled {
+            Some(client) => client,
+            None => self.do_connect().await.map_err(Failure::undelivered)?,
+        };
+        let response = tokio::time::timeout(REQUEST_TIMEOUT, client.exchange(request))
+            .await
+            .map_err(|_| {
+                Failure::delivered(IpcError::ConnectionFailed("Request timed out".to_string()))
+            })??;
+
+        // The daemon closes the connection after rejecting a request
+        if !matches!(response, Response::Error { .. }) {
+            self.checkin(client);
+        }
+        Ok(response)
+    }
+
+    /// Take the most recently used pooled connection that is still fresh
+    fn checkout(&self) -> Option<ConnectedClient> {
+        let mut pool = self.pool.lock().unwrap_or_else(|e| e.into_inner());
+        pool.retain(|pooled| pooled.idle_since.elapsed() < POOL_IDLE_TIMEOUT);
+        pool.pop().map(|pooled| pooled.client)
+    }
+
+    /// Return a connection to the pool, if there is room
+    fn checkin(&self, client: ConnectedClient) {
+        let mut pool = self.pool.lock().unwrap_or_else(|e| e.into_inner());
+        if pool.len() < self.pool_size {
+            pool.push(PooledConnection {
+                client,
+                idle_since: Instant::now(),
+            });
+        }
+    }
+
+    /// Ask the daemon for its version and features (opens new connection)
+    pub async fn hello(&self) -> Result<DaemonInfo, IpcError> {
         let mut client = self.do_connect().await?;
-        client.send(request).await
+        client.hello().await
+    }
+
+    /// Send several requests in one round-trip, returning their responses
+    /// in order
+    pub async fn batch(&self, requests: Vec<Request>) -> Result<Vec<Response>, IpcError> {
+        let response = self.request(Request::Batch { requests }).await?;
+
+        match response {
+            Response::Ok {
+                data: Some(crate::ResponseData::Batch { responses }),
+            } => Ok(responses),
+            Response::Error { message, .. } => Err(IpcError::ConnectionFailed(message)),
+            _ => Err(IpcError::ConnectionFailed(
+                "Unexpected response".to_string(),
+            )),
+        }
+    }
+
+    /// Cancel a tracked request in flight on another connection
+    pub async fn cancel(&self, request_id: &str) -> Result<(), IpcError> {
+        let response = self
+            .request(Request::Cancel {
+                request_id: request_id.to_string(),
+            })
+            .await?;
+
+        match response {
+            Response::Error { message, .. } => Err(IpcError::ConnectionFailed(message)),
+            _ => Ok(()),
+        }
     }
 
     /// Check if a project is initialized
@@ -180,6 +658,11 @@ mod tests {
         async fn handle(&self, request: Request) -> Response {
             match request {
                 Request::Ping => Response::ok_with(ResponseData::Pong { timestamp: 0 }),
+                Request::Hello { .. } => Response::ok_with(ResponseData::Hello {
+                    daemon_version: "test".to_string(),
+                    protocol_version: PROTOCOL_VERSION,
+                    features: vec!["batch".to_string()],
+                }),
                 Request::Status => Response::ok_with(ResponseData::Status {
                     version: "test".to_string(),
                     uptime_secs: 0,
@@ -188,7 +671,19 @@ mod tests {
                     requests_total: 0,
                     cache_hit_rate: 0.0,
                     avg_latency_ms: 0,
+                    request_usage: vec![],
+                    render_cache: vec![],
+                    experiments: vec![],
+                    slos: vec![],
+                    enrichment: vec![],
+                    summarizer: None,
+                    latency: vec![],
+                    scopes_active: 0,
+                    prefetches: 0,
+                    prefetch_hits: 0,
+                    recovery: None,
                 }),
+                Request::MemoryPatch { patch, .. } if patch.content.is_some() => Response::ok(),
                 _ => Response::ack(),
             }
         }
@@ -210,7 +705,7 @@ mod tests {
     #[tokio::test]
     async fn test_client_default() {
         let client = IpcClient::default();
-        assert_eq!(client.socket_path, PathBuf::from(DEFAULT_SOCKET_PATH));
+        assert_eq!(client.socket_path, paths::discover_socket_path());
     }
 
     #[tokio::test]
@@ -241,6 +736,93 @@ mod tests {
         ));
     }
 
+    #[tokio::test]
+    async fn test_client_reuses_connection() {
+        let temp_dir = tempdir().unwrap();
+        let socket_path = temp_dir.path().join("test.sock");
+
+        let server = IpcServer::new(&socket_path, Arc::new(TestHandler))
+            .await
+            .unwrap();
+        tokio::spawn(async move {
+            let _ = server.run().await;
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let mut client = IpcClient::connect_to(&socket_path).await.unwrap();
+        for _ in 0..3 {
+            let response = client.send(Request::Ping).await.unwrap();
+            assert!(matches!(
+                response,
+                Response::Ok {
+                    data: Some(ResponseData::Pong { .. })
+                }
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_client_hello_negotiates_protocol() {
+        let temp_dir = tempdir().unwrap();
+        let socket_path = temp_dir.path().join("test.sock");
+
+        let server = IpcServer::new(&socket_path, Arc::new(TestHandler))
+            .await
+            .unwrap();
+        tokio::spawn(async move {
+            let _ = server.run().await;
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let mut client = IpcClient::connect_to(&socket_path).await.unwrap();
+        let info = client.hello().await.unwrap();
+        assert_eq!(info.version.as_deref(), Some("test"));
+        assert_eq!(info.protocol_version, PROTOCOL_VERSION);
+        assert!(info.supports("batch"));
+        assert!(!info.supports("streaming"));
+        assert_eq!(client.protocol, PROTOCOL_VERSION);
+
+        // Versioned frames from here on
+        let response = client.send(Request::Ping).await.unwrap();
+        assert!(matches!(
+            response,
+            Response::Ok {
+                data: Some(ResponseData::Pong { .. })
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_client_skipped_fields_roundtrip() {
+        let temp_dir = tempdir().unwrap();
+        let socket_path = temp_dir.path().join("test.sock");
+
+        let server = IpcServer::new(&socket_path, Arc::new(TestHandler))
+            .await
+            .unwrap();
+        tokio::spawn(async move {
+            let _ = server.run().await;
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // Only `content` is set; the other patch fields and the response
+        // data are left out of the encoding
+        let client = IpcClient::with_socket_path(&socket_path);
+        let response = client
+            .request(Request::MemoryPatch {
+                cwd: PathBuf::from("/tmp"),
+                id: "m1".to_string(),
+                patch: crate::MemoryPatch {
+                    content: Some("updated".to_string()),
+                    ..Default::default()
+                },
+            })
+            .await
+            .unwrap();
+
+        assert!(matches!(response, Response::Ok { data: None }));
+    }
+
     #[tokio::test]
     async fn test_client_get_status() {
         let temp_dir = tempdir().unwrap();
@@ -284,6 +866,191 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_client_token() {
+        let temp_dir = tempdir().unwrap();
+        let socket_path = temp_dir.path().join("test.sock");
+
+        let handler = Arc::new(TestHandler);
+        let server = IpcServer::new(&socket_path, handler)
+            .await
+            .unwrap()
+            .with_access(crate::AccessPolicy::new().with_token("secret"));
+
+        tokio::spawn(async move {
+            let _ = server.run().await;
+        });
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let client = IpcClient::with_socket_path(&socket_path).with_token("secret");
+        let response = client.request(Request::Ping).await.unwrap();
+        assert!(matches!(
+            response,
+            Response::Ok {
+                data: Some(ResponseData::Pong { .. })
+            }
+        ));
+
+        for client in [
+            IpcClient::with_socket_path(&socket_path).with_token("wrong"),
+            IpcClient {
+                socket_path: socket_path.clone(),
+                token: None,
+                auto_start: None,
+                retry: RetryPolicy::default(),
+                pool_size: POOL_SIZE,
+                pool: Mutex::new(Vec::new()),
+            },
+        ] {
+            let response = client.request(Request::Shutdown).await.unwrap();
+            assert!(matches!(
+                response,
+                Response::Error {
+                    code: crate::ErrorCode::Unauthorized,
+                    ..
+                }
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_client_pools_connections() {
+        let temp_dir = tempdir().unwrap();
+        let socket_path = temp_dir.path().join("test.sock");
+
+        let server = IpcServer::new(&socket_path, Arc::new(TestHandler))
+            .await
+            .unwrap();
+        tokio::spawn(async move {
+            let _ = server.run().await;
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let client = IpcClient::with_socket_path(&socket_path);
+        for _ in 0..3 {
+            client.request(Request::Ping).await.unwrap();
+            assert_eq!(client.pool.lock().unwrap().len(), 1);
+        }
+        // Two requests in flight at once need a connection each
+        let (a, b) = tokio::join!(client.request(Request::Ping), client.request(Request::Ping));
+        assert!(a.is_ok() && b.is_ok());
+        assert_eq!(client.pool.lock().unwrap().len(), 2);
+
+        // A request that is not idempotent gets a new connection
+        client.request(Request::Shutdown).await.unwrap();
+        assert_eq!(client.pool.lock().unwrap().len(), 3);
+
+        let client = IpcClient::with_socket_path(&socket_path).with_pool_size(0);
+        client.request(Request::Ping).await.unwrap();
+        assert!(client.pool.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_client_retries_until_daemon_is_back() {
+        let temp_dir = tempdir().unwrap();
+        let socket_path = temp_dir.path().join("test.sock");
+
+        // The daemon is restarting: its socket comes back a little later
+        let server_path = socket_path.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            let server = IpcServer::new(&server_path, Arc::new(TestHandler))
+                .await
+                .unwrap();
+            let _ = server.run().await;
+        });
+
+        let retry = RetryPolicy {
+            attempts: 10,
+            initial_backoff: Duration::from_millis(20),
+            max_backoff: Duration::from_millis(100),
+        };
+        let client = IpcClient::with_socket_path(&socket_path).with_retry(retry);
+        // Not idempotent, but never delivered while the daemon was down
+        let response = client.request(Request::Shutdown).await.unwrap();
+        assert!(matches!(response, Response::Ack));
+
+        let client = IpcClient::with_socket_path(temp_dir.path().join("none.sock"))
+            .with_retry(RetryPolicy::none());
+        assert!(matches!(
+            client.request(Request::Ping).await,
+            Err(IpcError::DaemonNotRunning)
+        ));
+    }
+
+    #[test]
+    fn test_retry_backoff() {
+        let retry = RetryPolicy::default();
+        for attempt in 1..=10 {
+            let expected = (retry.initial_backoff * 2u32.pow(attempt - 1)).min(retry.max_backoff);
+            let backoff = retry.backoff(attempt);
+            assert!(
+                backoff >= expected / 2 && backoff <= expected,
+                "{:?}",
+                backoff
+            );
+        }
+        assert_eq!(RetryPolicy::none().attempts, 1);
+        assert_eq!(RetryPolicy::default().with_attempts(0).attempts, 1);
+
+        let failure = Failure::delivered(IpcError::Io(std::io::ErrorKind::BrokenPipe.into()));
+        assert!(failure.is_retryable(true));
+        assert!(!failure.is_retryable(false));
+        assert!(Failure::undelivered(IpcError::DaemonNotRunning).is_retryable(false));
+        let timeout = Failure::delivered(IpcError::ConnectionFailed("timed out".to_string()));
+        assert!(!timeout.is_retryable(true));
+    }
+
+    #[test]
+    fn test_auto_start_parse() {
+        assert_eq!(AutoStart::parse(""), None);
+        assert_eq!(AutoStart::parse("0"), None);
+        assert_eq!(AutoStart::parse("1"), Some(AutoStart::daemon()));
+        let command = AutoStart::parse("systemctl --user start engram.service").unwrap();
+        assert_eq!(command.program, "systemctl");
+        assert_eq!(command.args, vec!["--user", "start", "engram.service"]);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_client_auto_start() {
+        let temp_dir = tempdir().unwrap();
+        let socket_path = temp_dir.path().join("test.sock");
+
+        // Stands in for socket activation: the command exits at once and the
+        // daemon comes up a little later
+        let server_path = socket_path.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            let server = IpcServer::new(&server_path, Arc::new(TestHandler))
+                .await
+                .unwrap();
+            let _ = server.run().await;
+        });
+
+        let command = |program: &str| AutoStart {
+            program: program.to_string(),
+            ..AutoStart::daemon()
+        };
+        let activate = command("true");
+        let client = IpcClient::with_socket_path(&socket_path).with_auto_start(activate);
+        let response = client.request(Request::Ping).await.unwrap();
+        assert!(matches!(
+            response,
+            Response::Ok {
+                data: Some(ResponseData::Pong { .. })
+            }
+        ));
+
+        let client = IpcClient::with_socket_path(temp_dir.path().join("none.sock"))
+            .with_auto_start(command("false"));
+        assert!(matches!(
+            client.request(Request::Ping).await,
+            Err(IpcError::ConnectionFailed(_))
+        ));
+    }
+
     #[tokio::test]
     async fn test_client_send_async_no_daemon() {
         let client = IpcClient::with_socket_path("/tmp/nonexistent_socket_12345.sock");
@@ -32,6 +32,10 @@ pub enum IpcError {
     /// Daemon not running
     #[error("Daemon not running (socket not found)")]
     DaemonNotRunning,
+
+    /// Peer speaks a protocol version this build cannot decode
+    #[error("Incompatible protocol: {0}")]
+    Incompatible(String),
 }
 
 #[cfg(test)]
@@ -0,0 +1,95 @@
+//! Length-prefixed message framing.
+//!
+//! Unversioned frames are a 4-byte little-endian body length followed by
+//! the body, as spoken by peers predating versioning. Once a `Hello` has
+//! negotiated a protocol version on a connection, both sides switch to
+//! versioned frames, which prefix the length with the sender's protocol
+//! version byte.
+
+use crate::IpcError;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Read one frame, returning the sender's protocol version (0 for an
+/// unversioned frame) and the body.
+///
+/// Bodies longer than `max` bytes are rejected with
+/// [`IpcError::RequestTooLarge`].
+pub(crate) async fn read_frame<S: AsyncRead + Unpin>(
+    stream: &mut S,
+    versioned: bool,
+    max: usize,
+) -> Result<(u8, Vec<u8>), IpcError> {
+    let version = if versioned {
+        stream.read_u8().await?
+    } else {
+        0
+    };
+
+    let mut len_buf = [0u8; 4];
+    stream.read_exact(&mut len_buf).await?;
+    let len = u32::from_le_bytes(len_buf) as usize;
+    if len > max {
+        return Err(IpcError::RequestTooLarge);
+    }
+
+    let mut body = vec![0u8; len];
+    stream.read_exact(&mut body).await?;
+    Ok((version, body))
+}
+
+/// Write one frame; version 0 writes an unversioned frame.
+pub(crate) async fn write_frame<S: AsyncWrite + Unpin>(
+    stream: &mut S,
+    version: u8,
+    body: &[u8],
+) -> Result<(), IpcError> {
+    if version != 0 {
+        stream.write_u8(version).await?;
+    }
+    stream.write_all(&(body.len() as u32).to_le_bytes()).await?;
+    stream.write_all(body).await?;
+    Ok(())
+}
+
+/// Error for a body the peer sent that could not be decoded, blaming the
+/// protocol when the peer speaks a newer one than this build.
+pub(crate) fn decode_error(peer_version: u8, error: rmp_serde::decode::Error) -> IpcError {
+    if peer_version > crate::PROTOCOL_VERSION {
+        IpcError::Incompatible(format!(
+            "peer speaks protocol {}, this build speaks {}: {}",
+            peer_version,
+            crate::PROTOCOL_VERSION,
+            error
+        ))
+    } else {
+        IpcError::Deserialize(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_frame_roundtrip() {
+        let mut buf = Vec::new();
+        write_frame(&mut buf, 1, b"hello").await.unwrap();
+        assert_eq!(buf.len(), 1 + 4 + 5);
+        let (version, body) = read_frame(&mut buf.as_slice(), true, 1024).await.unwrap();
+        assert_eq!(version, 1);
+        assert_eq!(body, b"hello");
+
+        let result = read_frame(&mut buf.as_slice(), true, 4).await;
+        assert!(matches!(result, Err(IpcError::RequestTooLarge)));
+    }
+
+    #[tokio::test]
+    async fn test_unversioned_frame() {
+        let mut buf = Vec::new();
+        write_frame(&mut buf, 0, b"hello").await.unwrap();
+        assert_eq!(&buf[..4], &5u32.to_le_bytes());
+        let (version, body) = read_frame(&mut buf.as_slice(), false, 1024).await.unwrap();
+        assert_eq!(version, 0);
+        assert_eq!(body, b"hello");
+    }
+}
@@ -0,0 +1,477 @@
+//! HTTP/JSON bridge for the Engram daemon.
+//!
+//! Exposes the same `Request`/`Response` protocol as the Unix socket server
+//! over localhost HTTP, for editor extensions and tooling that would rather
+//! not implement MessagePack framing:
+//!
+//! ```text
+//! POST /request
+//! Content-Type: application/json
+//!
+//! {"action": "status"}
+//! ```
+//!
+//! The response body is the JSON-encoded `Response`. Protocol errors are
+//! reported in the body with HTTP 200; transport errors use HTTP status codes.
+//!
+//! Only loopback addresses can be bound. Requests must carry a localhost
+//! `Host` header and a JSON content type, so web pages cannot reach the
+//! daemon via DNS rebinding or simple cross-origin form posts. Peer
+//! credentials cannot be checked over TCP, so every request must carry the
+//! daemon's token as `Authorization: Bearer <token>`; without a configured
+//! token the bridge refuses to run.
+
+use crate::server::{RequestHandler, MAX_REQUEST_SIZE};
+use crate::{AccessPolicy, ErrorCode, IpcError, Request, Response};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Path that accepts protocol requests
+pub const REQUEST_PATH: &str = "/request";
+
+/// Maximum size of the request line and headers
+const MAX_HEADER_SIZE: usize = 16 * 1024;
+
+/// Timeout for reading a full HTTP request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// HTTP/JSON server on a loopback address
+pub struct HttpServer {
+    listener: TcpListener,
+    handler: Arc<dyn RequestHandler>,
+    access: Arc<AccessPolicy>,
+}
+
+impl HttpServer {
+    /// Bind the HTTP bridge. Non-loopback addresses are rejected.
+    pub async fn new(addr: SocketAddr, handler: Arc<dyn RequestHandler>) -> Result<Self, IpcError> {
+        if !addr.ip().is_loopback() {
+            return Err(IpcError::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("HTTP bridge must bind to a loopback address, got {}", addr),
+            )));
+        }
+
+        let listener = TcpListener::bind(addr).await?;
+        tracing::info!("HTTP bridge listening on {}", listener.local_addr()?);
+
+        Ok(Self {
+            listener,
+            handler,
+            access: Arc::new(AccessPolicy::default()),
+        })
+    }
+
+    /// Require the policy's token; peer UIDs cannot be checked over TCP.
+    pub fn with_access(mut self, access: AccessPolicy) -> Self {
+        self.access = Arc::new(access);
+        self
+    }
+
+    /// Address the server is bound to
+    pub fn local_addr(&self) -> Result<SocketAddr, IpcError> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Run the server, accepting connections until shutdown. Fails at once
+    /// if the access policy has no token.
+    pub async fn run(&self) -> Result<(), IpcError> {
+        if self.access.token.is_none() {
+            return Err(IpcError::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "HTTP bridge requires a token",
+            )));
+        }
+        loop {
+            match self.listener.accept().await {
+                Ok((stream, _addr)) => {
+                    let handler = self.handler.clone();
+                    let access = self.access.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = Self::handle_connection(stream, handler, &access).await {
+                            tracing::debug!("HTTP connection error: {}", e);
+                        }
+                    });
+                }
+                Err(e) => {
+                    tracing::error!("HTTP accept error: {}", e);
+                }
+            }
+        }
+    }
+
+    /// Handle a single request; connections are not kept alive
+    async fn handle_connection(
+        mut stream: TcpStream,
+        handler: Arc<dyn RequestHandler>,
+        access: &AccessPolicy,
+    ) -> Result<(), IpcError> {
+        let http_request = tokio::time::timeout(REQUEST_TIMEOUT, read_http_request(&mut stream))
+            .await
+            .map_err(IpcError::Timeout)?;
+
+        let http_request = match http_request {
+            Ok(req) => req,
+            Err(e) => {
+                write_http_response(&mut stream, 400, "Bad Request", &e.to_string()).await?;
+                return Ok(());
+            }
+        };
+
+        if let Err((code, reason)) = http_request
+            .validate()
+            .and_then(|()| http_request.authorize(access))
+        {
+            write_http_response(&mut stream, code, reason, reason).await?;
+            return Ok(());
+        }
+
+        let response = match serde_json::from_slice::<Request>(&http_request.body) {
+            // Paths would name files on the daemon's host, not the client's
+            Ok(request) if request.opens_client_paths() => Response::error(
+                ErrorCode::Unauthorized,
+                "Requests naming files by path are not served over HTTP",
+            ),
+            Ok(request) => {
+                tracing::debug!("Received HTTP request: {:?}", request);
+                handler.handle(request).await
+            }
+            Err(e) => Response::error(
+                ErrorCode::InvalidRequest,
+                format!("Failed to parse request: {}", e),
+            ),
+        };
+
+        let body = serde_json::to_string(&response).map_err(std::io::Error::other)?;
+        write_http_response(&mut stream, 200, "OK", &body).await
+    }
+}
+
+/// A parsed HTTP request
+struct HttpRequest {
+    method: String,
+    path: String,
+    /// Header names lowercased
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl HttpRequest {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Check route, method and the browser-safety headers.
+    fn validate(&self) -> Result<(), (u16, &'static str)> {
+        if self.path != REQUEST_PATH {
+            return Err((404, "Not Found"));
+        }
+        if self.method != "POST" {
+            return Err((405, "Method Not Allowed"));
+        }
+
+        let host = self.header("host").map(host_name).unwrap_or_default();
+        if !matches!(host, "localhost" | "127.0.0.1" | "::1") {
+            return Err((403, "Forbidden"));
+        }
+
+        let is_json = self
+            .header("content-type")
+            .and_then(|ct| ct.split(';').next())
+            .is_some_and(|ct| ct.trim().eq_ignore_ascii_case("application/json"));
+        if !is_json {
+            return Err((415, "Unsupported Media Type"));
+        }
+
+        Ok(())
+    }
+
+    /// Check the bearer token; without a configured token nothing is
+    /// authorized.
+    fn authorize(&self, access: &AccessPolicy) -> Result<(), (u16, &'static str)> {
+        if access.token.is_none() {
+            return Err((401, "Unauthorized"));
+        }
+        let token = self
+            .header("authorization")
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .unwrap_or_default();
+        if access.accepts_token(token.trim()) {
+            Ok(())
+        } else {
+            Err((401, "Unauthorized"))
+        }
+    }
+}
+
+/// Strip the port (and IPv6 brackets) from a Host header value.
+fn host_name(host: &str) -> &str {
+    match host.strip_prefix('[') {
+        Some(rest) => rest.split(']').next().unwrap_or_default(),
+        None => host.split(':').next().unwrap_or_default(),
+    }
+}
+
+/// Read the request line, headers and body.
+async fn read_http_request(stream: &mut TcpStream) -> Result<HttpRequest, IpcError> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let header_end = loop {
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos;
+        }
+        if buf.len() > MAX_HEADER_SIZE {
+            return Err(IpcError::RequestTooLarge);
+        }
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Err(invalid("connection closed before headers"));
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    };
+
+    let mut body = buf.split_off(header_end + 4);
+    let head = std::str::from_utf8(&buf[..header_end]).map_err(|_| invalid("non-UTF-8 header"))?;
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let (Some(method), Some(path)) = (request_line.next(), request_line.next()) else {
+        return Err(invalid("malformed request line"));
+    };
+
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim().to_ascii_lowercase(), value.trim().to_string()))
+        .collect();
+
+    let content_length = match headers.iter().find(|(key, _)| key == "content-length") {
+        Some((_, value)) => value
+            .parse::<usize>()
+            .map_err(|_| invalid("invalid Content-Length"))?,
+        None => 0,
+    };
+    if content_length > MAX_REQUEST_SIZE {
+        return Err(IpcError::RequestTooLarge);
+    }
+
+    if body.len() < content_length {
+        let already = body.len();
+        body.resize(content_length, 0);
+        stream.read_exact(&mut body[already..]).await?;
+    }
+    body.truncate(content_length);
+
+    Ok(HttpRequest {
+        method: method.to_string(),
+        path: path.to_string(),
+        headers,
+        body,
+    })
+}
+
+/// Write a complete response and close the connection.
+async fn write_http_response(
+    stream: &mut TcpStream,
+    code: u16,
+    reason: &str,
+    body: &str,
+) -> Result<(), IpcError> {
+    let content_type = if code == 200 {
+        "application/json"
+    } else {
+        "text/plain; charset=utf-8"
+    };
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        code,
+        reason,
+        content_type,
+        body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(body.as_bytes()).await?;
+    stream.flush().await?;
+    Ok(())
+}
+
+fn invalid(message: &str) -> IpcError {
+    IpcError::Io(std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        message.to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ResponseData;
+    use async_trait::async_trait;
+
+    struct TestHandler;
+
+    #[async_trait]
+    impl RequestHandler for TestHandler {
+        async fn handle(&self, request: Request) -> Response {
+            match request {
+                Request::Ping => Response::ok_with(ResponseData::Pong { timestamp: 42 }),
+                _ => Response::ack(),
+            }
+        }
+    }
+
+    async fn start_server() -> SocketAddr {
+        let server = HttpServer::new("127.0.0.1:0".parse().unwrap(), Arc::new(TestHandler))
+            .await
+            .unwrap()
+            .with_access(AccessPolicy::new().with_token(TOKEN));
+        let addr = server.local_addr().unwrap();
+        tokio::spawn(async move {
+            let _ = server.run().await;
+        });
+        addr
+    }
+
+    const TOKEN: &str = "secret";
+
+    async fn send(addr: SocketAddr, raw: String) -> String {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(raw.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    fn post(host: &str, content_type: &str, body: &str) -> String {
+        format!(
+            "POST /request HTTP/1.1\r\nHost: {}\r\nAuthorization: Bearer {}\r\n\
+             Content-Type: {}\r\nContent-Length: {}\r\n\r\n{}",
+            host,
+            TOKEN,
+            content_type,
+            body.len(),
+            body
+        )
+    }
+
+    #[tokio::test]
+    async fn test_http_ping() {
+        let addr = start_server().await;
+        let response = send(
+            addr,
+            post(
+                &addr.to_string(),
+                "application/json",
+                r#"{"action":"ping"}"#,
+            ),
+        )
+        .await;
+
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        let body = response.split("\r\n\r\n").nth(1).unwrap();
+        let parsed: Response = serde_json::from_str(body).unwrap();
+        assert!(matches!(
+            parsed,
+            Response::Ok {
+                data: Some(ResponseData::Pong { timestamp: 42 })
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_http_invalid_request_is_protocol_error() {
+        let addr = start_server().await;
+        let response = send(
+            addr,
+            post("localhost", "application/json", r#"{"action":"nope"}"#),
+        )
+        .await;
+
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("invalid_request"));
+    }
+
+    #[tokio::test]
+    async fn test_http_rejects_client_paths() {
+        let addr = start_server().await;
+        let body = r#"{"action":"export_project","cwd":"/p","output":"/home/me/.bashrc"}"#;
+        let response = send(addr, post("localhost", "application/json", body)).await;
+
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("unauthorized"));
+    }
+
+    #[tokio::test]
+    async fn test_http_rejects_unsafe_requests() {
+        let addr = start_server().await;
+        let body = r#"{"action":"ping"}"#;
+
+        let rebinding = send(addr, post("evil.example:80", "application/json", body)).await;
+        assert!(rebinding.starts_with("HTTP/1.1 403"));
+
+        let form = send(addr, post("localhost", "text/plain", body)).await;
+        assert!(form.starts_with("HTTP/1.1 415"));
+
+        let get = send(
+            addr,
+            "GET /request HTTP/1.1\r\nHost: localhost\r\n\r\n".to_string(),
+        )
+        .await;
+        assert!(get.starts_with("HTTP/1.1 405"));
+
+        let missing = send(
+            addr,
+            "GET / HTTP/1.1\r\nHost: localhost\r\n\r\n".to_string(),
+        )
+        .await;
+        assert!(missing.starts_with("HTTP/1.1 404"));
+    }
+
+    #[tokio::test]
+    async fn test_http_requires_token() {
+        let addr = start_server().await;
+        let body = r#"{"action":"ping"}"#;
+        let authorized = post("localhost", "application/json", body);
+
+        let anonymous = authorized.replace(&format!("Authorization: Bearer {}\r\n", TOKEN), "");
+        let anonymous = send(addr, anonymous).await;
+        assert!(anonymous.starts_with("HTTP/1.1 401"));
+
+        let wrong = send(addr, authorized.replace(TOKEN, "guess")).await;
+        assert!(wrong.starts_with("HTTP/1.1 401"));
+
+        let authorized = send(addr, authorized).await;
+        assert!(authorized.starts_with("HTTP/1.1 200 OK"));
+
+        // Peers cannot be identified over TCP, so no token means no bridge
+        let server = HttpServer::new("127.0.0.1:0".parse().unwrap(), Arc::new(TestHandler))
+            .await
+            .unwrap();
+        assert!(server.run().await.is_err());
+        let open = HttpRequest {
+            method: "POST".to_string(),
+            path: REQUEST_PATH.to_string(),
+            headers: Vec::new(),
+            body: Vec::new(),
+        };
+        assert!(open.authorize(&AccessPolicy::new()).is_err());
+    }
+
+    #[test]
+    fn test_host_name() {
+        assert_eq!(host_name("localhost:7878"), "localhost");
+        assert_eq!(host_name("127.0.0.1"), "127.0.0.1");
+        assert_eq!(host_name("[::1]:7878"), "::1");
+        assert_eq!(host_name("[::1]"), "::1");
+    }
+
+    #[tokio::test]
+    async fn test_http_rejects_non_loopback_bind() {
+        let result = HttpServer::new("0.0.0.0:0".parse().unwrap(), Arc::new(TestHandler)).await;
+        assert!(result.is_err());
+    }
+}
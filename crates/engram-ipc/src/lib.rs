@@ -1,14 +1,25 @@
 //! Engram IPC Protocol and Client/Server
 //!
-//! This crate provides the IPC protocol definitions and Unix socket
-//! client/server implementations for communication with the Engram daemon.
+//! This crate provides the IPC protocol definitions and the client/server
+//! implementations for communication with the Engram daemon, over a Unix
+//! socket or, on Windows, a named pipe.
 
+mod auth;
 mod client;
 mod error;
+mod frame;
+#[cfg(feature = "http")]
+mod http;
+pub mod paths;
 mod protocol;
 mod server;
+mod transport;
 
-pub use client::IpcClient;
+pub use auth::{AccessPolicy, TOKEN_ENV};
+pub use client::{AutoStart, DaemonInfo, IpcClient, RetryPolicy, AUTO_START_ENV};
 pub use error::IpcError;
+#[cfg(feature = "http")]
+pub use http::{HttpServer, REQUEST_PATH};
 pub use protocol::*;
-pub use server::{IpcServer, RequestHandler};
+pub use server::{DrainReport, IpcServer, RequestHandler};
+pub use transport::{endpoint_display, endpoint_exists, pipe_name, remove_endpoint};
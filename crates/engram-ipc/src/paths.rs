@@ -0,0 +1,197 @@
+//! Per-user locations of the daemon's socket, PID file and data.
+//!
+//! The socket and PID file live in the user's runtime directory:
+//! `$XDG_RUNTIME_DIR/engram`, which is private and cleared on logout, or
+//! `/tmp/engram-<uid>` where that is unset (e.g. macOS). Each user thus
+//! talks to their own daemon, and a stale socket from before a reboot is
+//! not picked up. Data lives in the XDG data directory, unless an existing
+//! `~/.engram` is still in use.
+
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// Environment variable overriding the socket path
+pub const SOCKET_ENV: &str = "ENGRAM_SOCKET";
+
+/// Socket file name in the runtime directory
+const SOCKET_FILE: &str = "engram.sock";
+
+/// PID file name in the runtime directory
+const PID_FILE: &str = "engram.pid";
+
+/// Config file name in the data directory
+const CONFIG_FILE: &str = "config.yaml";
+
+/// The part of the daemon config clients read
+#[derive(Deserialize)]
+struct ClientConfig {
+    socket_path: Option<PathBuf>,
+}
+
+/// The current user's data directory for Engram.
+///
+/// `~/.engram` if it exists, from before data moved to
+/// `$XDG_DATA_HOME/engram` (`~/.local/share/engram`).
+pub fn data_dir() -> PathBuf {
+    let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+    let legacy = home.join(".engram");
+    if legacy.is_dir() {
+        return legacy;
+    }
+    dirs::data_dir()
+        .unwrap_or_else(|| home.join(".local/share"))
+        .join("engram")
+}
+
+/// The daemon config file.
+pub fn config_file() -> PathBuf {
+    data_dir().join(CONFIG_FILE)
+}
+
+/// The current user's runtime directory for Engram.
+pub fn runtime_dir() -> PathBuf {
+    match std::env::var_os("XDG_RUNTIME_DIR").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir).join("engram"),
+        None => fallback_runtime_dir(),
+    }
+}
+
+#[cfg(unix)]
+fn fallback_runtime_dir() -> PathBuf {
+    // SAFETY: getuid cannot fail
+    let uid = unsafe { libc::getuid() };
+    PathBuf::from(format!("/tmp/engram-{}", uid))
+}
+
+#[cfg(not(unix))]
+fn fallback_runtime_dir() -> PathBuf {
+    // The per-user temp dir; mapped to a named pipe name on Windows
+    std::env::temp_dir().join("engram")
+}
+
+/// Default socket path (mapped to a named pipe on Windows).
+pub fn default_socket_path() -> PathBuf {
+    runtime_dir().join(SOCKET_FILE)
+}
+
+/// Default PID file path.
+pub fn default_pid_file() -> PathBuf {
+    runtime_dir().join(PID_FILE)
+}
+
+/// The socket to use: `ENGRAM_SOCKET` if set, else the configured path,
+/// else the default.
+pub fn socket_path(configured: Option<&Path>) -> PathBuf {
+    match std::env::var_os(SOCKET_ENV).filter(|path| !path.is_empty()) {
+        Some(path) => PathBuf::from(path),
+        None => configured
+            .map(Path::to_path_buf)
+            .unwrap_or_else(default_socket_path),
+    }
+}
+
+/// The socket clients connect to: `ENGRAM_SOCKET` if set, else the
+/// daemon config's `socket_path`, else the default.
+pub fn discover_socket_path() -> PathBuf {
+    let configured = std::fs::read_to_string(config_file())
+        .ok()
+        .and_then(|content| serde_yaml::from_str::<ClientConfig>(&content).ok())
+        .and_then(|config| config.socket_path);
+    socket_path(configured.as_deref())
+}
+
+/// Create a directory only its owner can access, or check that an existing
+/// one is such a directory.
+///
+/// Any user can create `/tmp/engram-<uid>` first and put their own socket
+/// in it, so an existing directory is only accepted if it is not a symlink,
+/// belongs to the current user and is closed to everyone else.
+pub fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    if !dir.exists() {
+        let mut builder = std::fs::DirBuilder::new();
+        builder.recursive(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::DirBuilderExt;
+            builder.mode(0o700);
+        }
+        builder.create(dir)?;
+    }
+    check_private_dir(dir)
+}
+
+#[cfg(unix)]
+fn check_private_dir(dir: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::MetadataExt;
+
+    let metadata = std::fs::symlink_metadata(dir)?;
+    // SAFETY: getuid cannot fail
+    let uid = unsafe { libc::getuid() };
+    let problem = if metadata.file_type().is_symlink() {
+        "is a symlink"
+    } else if !metadata.is_dir() {
+        "is not a directory"
+    } else if metadata.uid() != uid {
+        "belongs to another user"
+    } else if metadata.mode() & 0o777 != 0o700 {
+        "is accessible to other users (expected mode 700)"
+    } else {
+        return Ok(());
+    };
+    Err(std::io::Error::new(
+        std::io::ErrorKind::PermissionDenied,
+        format!("{} {}", dir.display(), problem),
+    ))
+}
+
+#[cfg(not(unix))]
+fn check_private_dir(_dir: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_paths_share_runtime_dir() {
+        assert_eq!(
+            default_socket_path().parent(),
+            Some(runtime_dir().as_path())
+        );
+        assert_eq!(default_pid_file().parent(), Some(runtime_dir().as_path()));
+    }
+
+    #[test]
+    fn test_client_config_ignores_other_keys() {
+        let config: ClientConfig =
+            serde_yaml::from_str("max_projects: 5\nsocket_path: /run/engram.sock\n").unwrap();
+        assert_eq!(config.socket_path, Some(PathBuf::from("/run/engram.sock")));
+        let config: ClientConfig = serde_yaml::from_str("max_projects: 5\n").unwrap();
+        assert!(config.socket_path.is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_create_private_dir() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path().join("run/engram");
+        create_private_dir(&dir).unwrap();
+        let mode = std::fs::metadata(&dir).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+        create_private_dir(&dir).unwrap();
+
+        // A directory others can enter, or a symlink, is not trusted
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o755)).unwrap();
+        assert!(create_private_dir(&dir).is_err());
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700)).unwrap();
+        let link = temp_dir.path().join("link");
+        std::os::unix::fs::symlink(&dir, &link).unwrap();
+        assert!(create_private_dir(&link).is_err());
+        let file = temp_dir.path().join("file");
+        std::fs::write(&file, "").unwrap();
+        assert!(create_private_dir(&file).is_err());
+    }
+}
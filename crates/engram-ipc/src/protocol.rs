@@ -1,9 +1,31 @@
 //! IPC Protocol definitions for Engram daemon communication.
 //!
 //! Uses MessagePack for efficient serialization over Unix sockets.
+//! Clients can open with [`Request::Hello`] to learn the daemon's protocol
+//! version and features before relying on newer requests.
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
+use std::time::Duration;
+
+/// Version of the protocol spoken by this build, carried in message frames
+/// once a `Hello` has negotiated it (0 is the unversioned protocol)
+pub const PROTOCOL_VERSION: u8 = 1;
+
+/// Features this build's daemon advertises in its `Hello` response
+pub const FEATURES: &[&str] = &[
+    "batch",
+    "tracked",
+    "search",
+    "memory",
+    "structured_context",
+    "dependencies",
+    "snapshots",
+    "events",
+    "jobs",
+    "doctor",
+];
 
 /// Request from client (hooks/CLI) to daemon
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,17 +40,72 @@ pub enum Request {
         /// Non-blocking AI enrichment mode
         #[serde(default)]
         async_mode: bool,
+        /// Directories to keep even if they look like build artifacts
+        #[serde(default)]
+        artifact_allowlist: Vec<PathBuf>,
     },
 
     /// Get context for a prompt (pre-computed cache)
     GetContext {
         cwd: PathBuf,
         prompt: Option<String>,
+        /// Bypass the render cache (for debugging stale context)
+        #[serde(default)]
+        no_cache: bool,
+        /// For uninitialized projects, return a shallow skeleton and
+        /// initialize in the background instead of failing
+        #[serde(default)]
+        cold_start: bool,
+        /// Sub-agent receiving the context with read-only memory; its
+        /// `MemoryPut`s are staged until approved via `ApprovePending`
+        #[serde(default)]
+        read_only_subagent: Option<String>,
+        /// Files or symbols to focus on, e.g. `src/auth.rs` or
+        /// `src/auth.rs::authenticate`
+        #[serde(default)]
+        focus: Vec<String>,
+        /// Also focus on files changed since the merge-base with this
+        /// branch, for reviews
+        #[serde(default)]
+        changed_since: Option<String>,
+        /// Return the context as sections with provenance
+        /// (`StructuredContext`) instead of one string
+        #[serde(default)]
+        structured: bool,
+        /// Named context profile from the project's config, e.g. `review`
+        #[serde(default)]
+        profile: Option<String>,
+        /// Agent session the context is for; its scope is recorded in the
+        /// session, which starts if unknown
+        #[serde(default)]
+        session_id: Option<String>,
     },
 
     /// Prepare context for next prompt (async, fire-and-forget)
     PrepareContext { cwd: PathBuf, prompt: String },
 
+    /// Release a context scope once the agent is done with it
+    ReleaseScope { cwd: PathBuf, scope_id: String },
+
+    /// Fork a child scope for a parallel sub-agent: it shares the parent's
+    /// anchor and focuses on `focus`, which must not overlap its siblings.
+    /// Releasing the child grafts its experience back into the parent.
+    ForkScope {
+        cwd: PathBuf,
+        scope_id: String,
+        focus: Vec<PathBuf>,
+    },
+
+    /// Pull the direct importers and imports of a node into a scope's
+    /// focus and render them
+    ExpandContext {
+        cwd: PathBuf,
+        scope_id: String,
+        /// Node id from a `Context` response, a file path, or a symbol
+        /// like `src/auth.rs::authenticate`
+        node: String,
+    },
+
     /// Notify file change (async, fire-and-forget)
     NotifyFileChange {
         cwd: PathBuf,
@@ -58,24 +135,458 @@ pub enum Request {
     /// Get a single memory entry by id
     MemoryGet { cwd: PathBuf, id: String },
 
-    /// List recent memory entries
+    /// List recent memory entries, optionally filtered
     MemoryList {
         cwd: PathBuf,
         #[serde(default = "default_memory_list_limit")]
         limit: usize,
+        #[serde(default)]
+        query: MemoryQuery,
+    },
+
+    /// Find the memory entries most similar in meaning to a query
+    MemorySearch {
+        cwd: PathBuf,
+        query: String,
+        #[serde(default = "default_search_limit")]
+        limit: usize,
+        /// Only entries visible to this reader (default: all entries)
+        #[serde(default)]
+        viewer: Option<MemoryViewer>,
     },
 
     /// Reconcile durable memory state into in-memory state
     MemorySync { cwd: PathBuf },
 
+    /// Rewrite the memory log with only the latest version of each entry
+    MemoryCompact { cwd: PathBuf },
+
+    /// Merge near-duplicate experiences and summarize old ones
+    ConsolidateExperiences { cwd: PathBuf },
+
+    /// Dump a project's memory entries and experiences into a bundle
+    ExportMemory { cwd: PathBuf },
+
+    /// Ingest a bundle from `ExportMemory`, keeping ids and timestamps.
+    ///
+    /// An entry replaces the local version with the same id only if it is
+    /// newer; experiences already in the log are skipped.
+    ImportMemory { cwd: PathBuf, bundle: MemoryBundle },
+
+    /// List memory entries staged by read-only sub-agents
+    ListPending { cwd: PathBuf },
+
+    /// Approve (or reject) staged memory entries; empty `ids` means all
+    ApprovePending {
+        cwd: PathBuf,
+        ids: Vec<String>,
+        #[serde(default)]
+        reject: bool,
+    },
+
+    /// Start an agent session (assigning an id when missing), or reopen
+    /// a known one.
+    ///
+    /// Scopes created for the session and the experiences and memory
+    /// entries carrying its id are grouped under it.
+    SessionStart {
+        cwd: PathBuf,
+        #[serde(default)]
+        session_id: Option<String>,
+        /// Agent running the session, e.g. `claude`
+        #[serde(default)]
+        agent: Option<String>,
+        /// Model the agent runs on
+        #[serde(default)]
+        model: Option<String>,
+        /// Other agent metadata
+        #[serde(default)]
+        metadata: BTreeMap<String, String>,
+    },
+
+    /// End an agent session and release its scopes.
+    ///
+    /// Without a `summary`, one is generated from the session's experiences
+    /// and memory entries. The summary heads the next session's anchor.
+    SessionEnd {
+        cwd: PathBuf,
+        session_id: String,
+        #[serde(default)]
+        summary: Option<String>,
+    },
+
+    /// List a project's sessions, most recently started first
+    ListSessions {
+        cwd: PathBuf,
+        #[serde(default = "default_session_list_limit")]
+        limit: usize,
+    },
+
+    /// Get a session with the experiences and memory entries recorded in it
+    GetSession { cwd: PathBuf, session_id: String },
+
+    /// Execute several requests in order, returning one response each.
+    ///
+    /// A `GraftExperience` and `MemoryPut`s for the same project in one
+    /// batch are linked to each other.
+    Batch { requests: Vec<Request> },
+
+    /// Run a request under an id so it can be cancelled, optionally with a
+    /// deadline after which the daemon gives up on it.
+    ///
+    /// Work stops cooperatively, at the next file or context layer.
+    Tracked {
+        /// Client-chosen id, unique among the client's in-flight requests
+        request_id: String,
+        /// Milliseconds the client will wait for the response
+        #[serde(default)]
+        deadline_ms: Option<u64>,
+        request: Box<Request>,
+    },
+
+    /// Cancel a tracked request; sent on a separate connection
+    Cancel { request_id: String },
+
+    /// Per-line hints for editor gutter annotations
+    LineHints {
+        cwd: PathBuf,
+        /// File path (absolute or relative to `cwd`)
+        path: PathBuf,
+    },
+
+    /// Anonymized project snapshot for debug bundles
+    DebugSnapshot { cwd: PathBuf },
+
+    /// Export a project's scoring calibration (as JSON)
+    CalibrationExport { cwd: PathBuf },
+
+    /// Replace a project's scoring calibration with an exported one
+    CalibrationImport { cwd: PathBuf, calibration: String },
+
+    /// Reset a project's scoring calibration to defaults
+    CalibrationReset { cwd: PathBuf },
+
+    /// Remove paths matching globs from the stored tree without re-indexing
+    PruneTree { cwd: PathBuf, globs: Vec<String> },
+
+    /// Release a project's in-memory state; it reloads on next use
+    UnloadProject { cwd: PathBuf },
+
+    /// Relink a project to its current root after the checkout moved,
+    /// adopting storage kept under the path it was initialized at
+    RelinkProject {
+        cwd: PathBuf,
+        /// Root the project was initialized at; `cwd` if not given
+        #[serde(default)]
+        from: Option<PathBuf>,
+    },
+
+    /// Write the project's manifest and stored data to a compressed archive
+    /// at `output`, a path on the daemon's machine
+    ExportProject { cwd: PathBuf, output: PathBuf },
+
+    /// Replace the project's stored data with an archive written by
+    /// `ExportProject`, pointing it at `cwd`, which need not be initialized
+    ImportProject { cwd: PathBuf, archive: PathBuf },
+
+    /// Search the project index with the hybrid router
+    Search {
+        cwd: PathBuf,
+        query: String,
+        #[serde(default = "default_search_limit")]
+        limit: usize,
+        /// Report why each hit matched
+        #[serde(default)]
+        explain: bool,
+    },
+
+    /// Find files whose paths fuzzily match a pattern, fzf style: every
+    /// whitespace separated term must appear in the path in order
+    FindFiles {
+        cwd: PathBuf,
+        pattern: String,
+        #[serde(default = "default_search_limit")]
+        limit: usize,
+    },
+
+    /// Analyze the import graph: cycles, build order, and the files
+    /// affected by changing one
+    AnalyzeDependencies {
+        cwd: PathBuf,
+        /// Report groups of files importing each other
+        #[serde(default)]
+        cycles: bool,
+        /// Report files ordered with dependencies before their importers
+        #[serde(default)]
+        order: bool,
+        /// File (absolute or relative to `cwd`) whose transitive
+        /// dependents are listed
+        #[serde(default)]
+        dependents_of: Option<PathBuf>,
+        /// Import hops to follow from `dependents_of` (default: unlimited)
+        #[serde(default)]
+        depth: Option<usize>,
+    },
+
+    /// Estimate the files and symbols affected by renaming or changing a
+    /// symbol, from its declarations, their importers and text references
+    ImpactAnalysis {
+        cwd: PathBuf,
+        /// Symbol name, optionally qualified (`Session::validate`)
+        symbol: String,
+    },
+
+    /// List the tests of a file, or the files a test file covers
+    ListTests {
+        cwd: PathBuf,
+        /// File, absolute or relative to the project
+        path: PathBuf,
+    },
+
+    /// Describe one symbol: its signature, documentation, file summary,
+    /// direct callers and callees, and recent related experiences
+    GetSymbol {
+        cwd: PathBuf,
+        /// Declaring file, absolute or relative to the project
+        path: PathBuf,
+        /// Symbol name, optionally qualified (`Session::validate`)
+        symbol: String,
+    },
+
+    /// Report how far a background `InitProject` has got
+    GetInitProgress { cwd: PathBuf },
+
+    /// Index statistics: counts by language and symbol kind, the largest
+    /// and most connected files, and the index's size on disk
+    ProjectStats {
+        cwd: PathBuf,
+        /// Entries per ranked list (default: 10)
+        #[serde(default)]
+        top: Option<usize>,
+    },
+
+    /// Markdown documentation of the project's directories, file summaries
+    /// and symbols, generated from the enriched tree
+    ExportDocs {
+        cwd: PathBuf,
+        /// Lay the pages out as an mdBook source tree
+        #[serde(default)]
+        mdbook: bool,
+    },
+
+    /// Compare the trees kept in two snapshots
+    DiffSnapshots {
+        cwd: PathBuf,
+        /// Snapshot compared against
+        from: String,
+        /// Snapshot compared; the current tree when `None`
+        #[serde(default)]
+        to: Option<String>,
+    },
+
+    /// Recent daemon events, oldest first
+    GetEvents {
+        /// Only events with a higher sequence number
+        #[serde(default)]
+        since: u64,
+        /// Most events returned; the latest are kept
+        #[serde(default = "default_event_limit")]
+        limit: usize,
+    },
+
+    /// List running indexing and enrichment jobs
+    ListJobs,
+
+    /// Cooperatively cancel a running job
+    CancelJob { id: u64 },
+
     /// Get daemon status
     Status,
 
+    /// List loaded projects with their index, scope, memory and cache state
+    ListProjects,
+
+    /// Diagnose the installation: socket, PID file, storage and grammars
+    Doctor,
+
+    /// Find stored data of deleted, moved or abandoned projects, and delete
+    /// it unless `dry_run`
+    CollectGarbage {
+        /// Days without writes after which a project is garbage (default:
+        /// the daemon's `gc.max_idle_days`; 0 keeps every project whose root
+        /// exists)
+        #[serde(default)]
+        max_idle_days: Option<u64>,
+        #[serde(default)]
+        dry_run: bool,
+        /// Only projects with one of these directories, relative to the
+        /// data dir (default: all)
+        #[serde(default)]
+        only: Vec<PathBuf>,
+    },
+
+    /// Encrypt the experience log lines of every stored project written
+    /// before a key was configured
+    EncryptLogs,
+
+    /// Re-read the daemon config file and apply the settings that can
+    /// change without a restart
+    ReloadConfig,
+
     /// Graceful shutdown
     Shutdown,
 
     /// Ping for health check
     Ping,
+
+    /// Probe the daemon's components: storage, file watching, enrichment
+    /// backend and memory pressure
+    HealthCheck,
+
+    /// Exchange versions and features; daemons predating it reject it as
+    /// an invalid request
+    Hello {
+        client_version: String,
+        /// Highest protocol version the client speaks
+        #[serde(default)]
+        protocol_version: u8,
+        /// Features the client can use
+        #[serde(default)]
+        supported_features: Vec<String>,
+    },
+
+    /// Present the shared token; sent ahead of the actual request
+    Authenticate { token: String },
+}
+
+impl Request {
+    /// Wire action name of this request (matches the serde tag).
+    pub fn action_name(&self) -> &'static str {
+        match self {
+            Request::CheckInit { .. } => "check_init",
+            Request::InitProject { .. } => "init_project",
+            Request::GetContext { .. } => "get_context",
+            Request::PrepareContext { .. } => "prepare_context",
+            Request::ReleaseScope { .. } => "release_scope",
+            Request::ForkScope { .. } => "fork_scope",
+            Request::ExpandContext { .. } => "expand_context",
+            Request::NotifyFileChange { .. } => "notify_file_change",
+            Request::GraftExperience { .. } => "graft_experience",
+            Request::MemoryPut { .. } => "memory_put",
+            Request::MemoryPatch { .. } => "memory_patch",
+            Request::MemoryDelete { .. } => "memory_delete",
+            Request::MemoryGet { .. } => "memory_get",
+            Request::MemoryList { .. } => "memory_list",
+            Request::MemorySearch { .. } => "memory_search",
+            Request::MemorySync { .. } => "memory_sync",
+            Request::MemoryCompact { .. } => "memory_compact",
+            Request::ConsolidateExperiences { .. } => "consolidate_experiences",
+            Request::ExportMemory { .. } => "export_memory",
+            Request::ImportMemory { .. } => "import_memory",
+            Request::ListPending { .. } => "list_pending",
+            Request::ApprovePending { .. } => "approve_pending",
+            Request::SessionStart { .. } => "session_start",
+            Request::SessionEnd { .. } => "session_end",
+            Request::ListSessions { .. } => "list_sessions",
+            Request::GetSession { .. } => "get_session",
+            Request::Batch { .. } => "batch",
+            Request::Tracked { .. } => "tracked",
+            Request::Cancel { .. } => "cancel",
+            Request::LineHints { .. } => "line_hints",
+            Request::DebugSnapshot { .. } => "debug_snapshot",
+            Request::CalibrationExport { .. } => "calibration_export",
+            Request::CalibrationImport { .. } => "calibration_import",
+            Request::CalibrationReset { .. } => "calibration_reset",
+            Request::PruneTree { .. } => "prune_tree",
+            Request::UnloadProject { .. } => "unload_project",
+            Request::RelinkProject { .. } => "relink_project",
+            Request::ExportProject { .. } => "export_project",
+            Request::ImportProject { .. } => "import_project",
+            Request::Search { .. } => "search",
+            Request::FindFiles { .. } => "find_files",
+            Request::AnalyzeDependencies { .. } => "analyze_dependencies",
+            Request::ImpactAnalysis { .. } => "impact_analysis",
+            Request::ListTests { .. } => "list_tests",
+            Request::GetSymbol { .. } => "get_symbol",
+            Request::GetInitProgress { .. } => "get_init_progress",
+            Request::ProjectStats { .. } => "project_stats",
+            Request::ExportDocs { .. } => "export_docs",
+            Request::DiffSnapshots { .. } => "diff_snapshots",
+            Request::GetEvents { .. } => "get_events",
+            Request::ListJobs => "list_jobs",
+            Request::CancelJob { .. } => "cancel_job",
+            Request::Status => "status",
+            Request::ListProjects => "list_projects",
+            Request::Doctor => "doctor",
+            Request::CollectGarbage { .. } => "collect_garbage",
+            Request::EncryptLogs => "encrypt_logs",
+            Request::ReloadConfig => "reload_config",
+            Request::Shutdown => "shutdown",
+            Request::Ping => "ping",
+            Request::HealthCheck => "health_check",
+            Request::Hello { .. } => "hello",
+            Request::Authenticate { .. } => "authenticate",
+        }
+    }
+
+    /// Whether sending this request twice leaves the daemon as sending it
+    /// once would, so a client may retry it after losing the response.
+    pub fn is_idempotent(&self) -> bool {
+        match self {
+            Request::Batch { requests } => requests.iter().all(Request::is_idempotent),
+            Request::Tracked { request, .. } => request.is_idempotent(),
+            Request::CheckInit { .. }
+            | Request::MemoryGet { .. }
+            | Request::MemoryList { .. }
+            | Request::MemorySearch { .. }
+            | Request::ExportMemory { .. }
+            | Request::ListPending { .. }
+            | Request::ListSessions { .. }
+            | Request::GetSession { .. }
+            | Request::LineHints { .. }
+            | Request::DebugSnapshot { .. }
+            | Request::CalibrationExport { .. }
+            | Request::Search { .. }
+            | Request::FindFiles { .. }
+            | Request::AnalyzeDependencies { .. }
+            | Request::ImpactAnalysis { .. }
+            | Request::ListTests { .. }
+            | Request::GetSymbol { .. }
+            | Request::GetInitProgress { .. }
+            | Request::ProjectStats { .. }
+            | Request::DiffSnapshots { .. }
+            | Request::GetEvents { .. }
+            | Request::ListJobs
+            | Request::Status
+            | Request::ListProjects
+            | Request::Doctor
+            | Request::ReloadConfig
+            | Request::Ping
+            | Request::HealthCheck
+            | Request::Hello { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Whether the daemon opens files at paths named in the request, which
+    /// only its own user may ask for.
+    pub fn opens_client_paths(&self) -> bool {
+        match self {
+            Request::Batch { requests } => requests.iter().any(Request::opens_client_paths),
+            Request::Tracked { request, .. } => request.opens_client_paths(),
+            Request::ExportProject { .. } | Request::ImportProject { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Wrap in [`Request::Tracked`] so it can be cancelled as `request_id`.
+    pub fn tracked(self, request_id: impl Into<String>, deadline: Option<Duration>) -> Self {
+        Request::Tracked {
+            request_id: request_id.into(),
+            deadline_ms: deadline.map(|deadline| deadline.as_millis() as u64),
+            request: Box::new(self),
+        }
+    }
 }
 
 /// Type of file change event
@@ -97,6 +608,55 @@ pub struct Experience {
     #[serde(default)]
     pub files_touched: Vec<PathBuf>,
     pub timestamp: i64,
+    /// Stable id (assigned by the daemon when missing)
+    #[serde(default)]
+    pub id: Option<String>,
+    /// Memory entries recorded about the same work
+    #[serde(default)]
+    pub memory_ids: Vec<String>,
+    /// Number of experiences this entry summarizes (0 for a single decision)
+    #[serde(default)]
+    pub summarizes: usize,
+    /// Session the decision was made in (assigned by the daemon when missing)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    /// How the decision turned out; outcomes teach the daemon which files
+    /// are worth loading
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outcome: Option<ExperienceOutcome>,
+}
+
+/// How an agent decision turned out
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ExperienceOutcome {
+    /// The decision worked
+    Success,
+    /// The decision failed with an error
+    Failure {
+        #[serde(default)]
+        error: String,
+    },
+    /// The decision was undone
+    Reverted,
+}
+
+/// Version of the memory bundle format written by `ExportMemory`
+pub const MEMORY_BUNDLE_FORMAT: u32 = 1;
+
+/// Portable dump of a project's memory, for moving it to another machine
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryBundle {
+    /// Bundle format version
+    pub format: u32,
+    /// When the bundle was exported (unix seconds)
+    pub exported_at: i64,
+    /// Latest version of every memory entry, tombstones included
+    #[serde(default)]
+    pub entries: Vec<MemoryEntry>,
+    /// Every experience in the log, oldest first
+    #[serde(default)]
+    pub experiences: Vec<Experience>,
 }
 
 /// Memory entry payload (JSON/MessagePack safe)
@@ -113,6 +673,47 @@ pub struct MemoryEntry {
     pub subagent_id: Option<String>,
     #[serde(default)]
     pub deleted: bool,
+    /// Experience this entry summarizes
+    #[serde(default)]
+    pub experience_id: Option<String>,
+    /// Who can read the entry
+    #[serde(default)]
+    pub visibility: MemoryVisibility,
+}
+
+/// Who can read a memory entry.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryVisibility {
+    /// Only the writer: the same sub-agent in the same session, or the
+    /// parent session itself
+    Private,
+    /// The writer's session and all of its sub-agents
+    Session,
+    /// Every reader of the project
+    #[default]
+    Project,
+}
+
+/// Identity of an agent reading memory.
+///
+/// A sub-agent sees its own entries, entries shared with its session
+/// (including the parent's), and project entries.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct MemoryViewer {
+    #[serde(default)]
+    pub session_id: Option<String>,
+    /// Sub-agent reading; `None` for the parent session
+    #[serde(default)]
+    pub subagent_id: Option<String>,
+}
+
+/// A memory entry matching a search
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MemoryMatch {
+    pub entry: MemoryEntry,
+    /// Cosine similarity to the query (0.0 - 1.0)
+    pub score: f32,
 }
 
 /// Partial update payload for memory patch operations.
@@ -134,6 +735,509 @@ pub struct MemoryPatch {
     pub deleted: Option<bool>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub updated_at: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub visibility: Option<MemoryVisibility>,
+}
+
+/// Filter for memory listings.
+///
+/// Every set field must match; the default query matches all entries.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct MemoryQuery {
+    /// Exact entry kind
+    #[serde(default)]
+    pub kind: Option<String>,
+    /// Tags the entry must all carry
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Session that recorded the entry
+    #[serde(default)]
+    pub session_id: Option<String>,
+    /// Only entries created at or after this Unix timestamp
+    #[serde(default)]
+    pub created_after: Option<i64>,
+    /// Only entries created before this Unix timestamp
+    #[serde(default)]
+    pub created_before: Option<i64>,
+    /// Case-insensitive substring of the content
+    #[serde(default)]
+    pub text: Option<String>,
+    /// Only entries visible to this reader (default: all entries)
+    #[serde(default)]
+    pub viewer: Option<MemoryViewer>,
+}
+
+/// Kind of an editor line hint
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LineHintKind {
+    /// Symbol boundary (function, struct, class, ...)
+    Symbol,
+    /// Memory entry referencing these lines
+    Memory,
+}
+
+/// Compact hint anchored to a line range, for gutter annotations
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LineHint {
+    /// First line (1-indexed)
+    pub line: usize,
+    /// Last line (1-indexed, inclusive)
+    pub end_line: usize,
+    pub kind: LineHintKind,
+    /// Short single-line label
+    pub label: String,
+    /// Referenced entity id (memory id for memory hints)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ref_id: Option<String>,
+}
+
+/// Directory skipped by the scanner because it looks like build output
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ExcludedDirInfo {
+    /// Path relative to the project root
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+/// Resource usage attributed to one request action
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RequestUsage {
+    pub action: String,
+    pub count: u64,
+    /// Bytes read from disk
+    pub bytes_read: u64,
+    /// Tree nodes (or symbols) visited
+    pub nodes_touched: u64,
+    /// Approximate bytes allocated for materialized payloads
+    pub alloc_bytes: u64,
+}
+
+/// Latency percentiles for one request action
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EndpointLatency {
+    pub action: String,
+    /// Requests served since startup
+    pub count: u64,
+    /// Median latency over recent requests
+    pub p50_ms: f64,
+    /// 95th percentile latency over recent requests
+    #[serde(default)]
+    pub p95_ms: f64,
+    /// 99th percentile latency over recent requests
+    pub p99_ms: f64,
+}
+
+/// API usage of the enrichment summarizer since the daemon started
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SummarizerInfo {
+    /// `openai` or `anthropic`
+    pub backend: String,
+    pub model: String,
+    pub requests: u64,
+    /// Requests that got no summary back
+    pub failed: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// Estimated from the configured token prices
+    pub cost: f64,
+}
+
+/// Render cache counters for one project
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RenderCacheInfo {
+    pub project: PathBuf,
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    /// Entries currently cached
+    pub entries: usize,
+}
+
+/// Compliance of one latency SLO over its rolling window
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SloInfo {
+    /// Objective name, e.g. `get_context p99 < 150ms`
+    pub name: String,
+    pub window_secs: u64,
+    /// Requests in the window
+    pub total: u64,
+    /// Requests over the latency threshold
+    pub slow: u64,
+    /// Fraction of requests within the threshold
+    pub compliance: f64,
+    /// Error budget burn rate (1.0 = spending exactly the budget)
+    pub burn_rate: f64,
+    pub at_risk: bool,
+}
+
+/// Daemon-wide state of one experiment flag
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ExperimentInfo {
+    pub name: String,
+    pub enabled: bool,
+}
+
+/// A project loaded in the daemon
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LoadedProjectInfo {
+    pub path: PathBuf,
+    pub name: String,
+    pub file_count: usize,
+    /// Last index time (Unix timestamp)
+    pub last_index: Option<i64>,
+    /// Context scopes that have not expired or been released
+    pub scopes_active: usize,
+    /// Live memory entries
+    pub memory_entries: usize,
+    /// Render cache hit rate (0.0-1.0)
+    pub cache_hit_rate: f64,
+    pub pinned: bool,
+    pub read_only: bool,
+}
+
+/// An agent session on a project
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SessionInfo {
+    pub id: String,
+    #[serde(default)]
+    pub agent: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub metadata: BTreeMap<String, String>,
+    /// When the session started (Unix timestamp)
+    pub started_at: i64,
+    /// When the session ended; `None` while it is active
+    #[serde(default)]
+    pub ended_at: Option<i64>,
+    /// Scopes created in the session, oldest first
+    #[serde(default)]
+    pub scope_ids: Vec<String>,
+    /// What the session did, set when it ends
+    #[serde(default)]
+    pub summary: Option<String>,
+    /// Experiences recorded in the session
+    #[serde(default)]
+    pub experiences: usize,
+    /// Live memory entries recorded in the session
+    #[serde(default)]
+    pub memory_entries: usize,
+}
+
+/// Project statistics without identifying paths
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ProjectSummary {
+    pub file_count: usize,
+    pub languages: Vec<String>,
+    pub frameworks: Vec<String>,
+    /// Last scan time (Unix timestamp)
+    pub last_scan: Option<i64>,
+    pub enriched: bool,
+    /// Number of auto-excluded artifact directories
+    pub auto_excluded_count: usize,
+}
+
+/// Directory or file in an anonymized structure export
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct StructureNode {
+    pub id: u64,
+    pub parent: Option<u64>,
+    /// `dir` or `file`
+    pub kind: String,
+    /// Salted hash of the name (file extensions are kept)
+    pub name: String,
+    pub language: Option<String>,
+    pub size: u64,
+    pub line_count: usize,
+    pub symbol_count: usize,
+}
+
+/// A running background job
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct JobInfo {
+    pub id: u64,
+    /// `indexing` or `enrichment`
+    pub kind: String,
+    pub project: PathBuf,
+    pub processed: usize,
+    /// Items to process (0 while still discovering)
+    pub total: usize,
+    pub elapsed_secs: u64,
+    /// `scanning`, `parsing`, `building` or `enriching`
+    #[serde(default)]
+    pub phase: String,
+    /// Estimated seconds left in the current phase
+    #[serde(default)]
+    pub eta_secs: Option<u64>,
+    /// Cancellation requested, job not yet stopped
+    pub cancelling: bool,
+    /// Waiting for another job writing the same project to finish
+    #[serde(default)]
+    pub queued: bool,
+}
+
+/// A recorded daemon event
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventInfo {
+    /// Sequence number, for polling with `since`
+    pub seq: u64,
+    /// Unix timestamp in seconds
+    pub time: i64,
+    /// `project_loaded`, `index_run`, `error`, `memory_compacted`,
+    /// `experiences_consolidated`, `memory_imported`, `project_relinked`,
+    /// `project_imported`, `recovered`, `garbage_collected` or
+    /// `config_reloaded`
+    pub kind: String,
+    pub project: Option<PathBuf>,
+    pub message: String,
+}
+
+/// A file depending on the analyzed one
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DependentFile {
+    /// Path relative to the project root
+    pub path: PathBuf,
+    /// Import hops from the analyzed file (1 = imports it directly)
+    pub depth: usize,
+}
+
+/// A symbol in an impact report
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImpactSymbol {
+    /// Path of the declaring file, relative to the project root
+    pub path: PathBuf,
+    pub name: String,
+    /// Symbol kind, e.g. `function`
+    pub kind: String,
+    /// Start line
+    pub line: usize,
+}
+
+/// A symbol that differs between two trees
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffSymbol {
+    /// Path of the declaring file, relative to the project root
+    pub path: PathBuf,
+    pub name: String,
+    /// Symbol kind, e.g. `function`
+    pub kind: String,
+    /// Start line, in the newer tree unless the symbol was removed
+    pub line: usize,
+}
+
+/// A name with how often it occurs, e.g. a language and its file count
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NamedCount {
+    pub name: String,
+    pub count: usize,
+}
+
+/// Binary or asset files of one type, e.g. `image`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AssetCount {
+    pub kind: String,
+    pub count: usize,
+    /// Total size of the files
+    pub bytes: u64,
+}
+
+/// A file ranked by a count, e.g. how many files import it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FileCount {
+    /// Path relative to the project root
+    pub path: PathBuf,
+    pub count: usize,
+}
+
+/// A file ranked by size
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FileSize {
+    /// Path relative to the project root
+    pub path: PathBuf,
+    pub size: u64,
+    pub line_count: usize,
+}
+
+/// A file in the project's storage directory
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct IndexFile {
+    /// Path relative to the storage directory
+    pub name: String,
+    pub size: u64,
+    /// Last write (Unix timestamp)
+    pub modified: Option<i64>,
+}
+
+/// A generated documentation file
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DocFile {
+    /// Path relative to the output directory
+    pub path: PathBuf,
+    pub content: String,
+}
+
+/// A file in an impact report
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImpactFile {
+    /// Path relative to the project root
+    pub path: PathBuf,
+    /// Import hops from a declaring file (0 = declares the symbol);
+    /// `None` if the file only mentions it
+    pub depth: Option<usize>,
+    /// Lines mentioning the symbol
+    pub lines: Vec<usize>,
+    /// Symbols in this file enclosing those lines
+    pub symbols: Vec<ImpactSymbol>,
+}
+
+/// A ranked search result
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SearchHit {
+    /// Path relative to the project root
+    pub path: PathBuf,
+    /// File or symbol name
+    pub name: String,
+    /// `file` or the symbol kind (e.g. `function`)
+    pub kind: String,
+    /// Start line for symbols
+    #[serde(default)]
+    pub line: Option<usize>,
+    /// Relevance score (0.0 - 1.0)
+    pub score: f32,
+    /// `tree`, `vector` or `merged`
+    pub source: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
+    /// Why the hit matched, if requested with `explain`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<Vec<MatchReason>>,
+}
+
+/// A file matching a `FindFiles` pattern
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FileMatch {
+    /// Path relative to the project root
+    pub path: PathBuf,
+    /// Match score, higher is better
+    pub score: i64,
+}
+
+/// A signal that made a search hit match
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum MatchReason {
+    /// The hit's name matched a name in the query
+    NameMatch { name: String },
+    /// The hit imports the file named in the query
+    DependencyEdge { imports: PathBuf },
+    /// Cosine similarity (-1 to 1) of the hit's text to the query
+    VectorSimilarity { similarity: f32 },
+    /// Calibration weight applied to hits from the hit's source
+    Calibration { weight: f32 },
+    /// Weight learned from past outcomes of work on the hit's file
+    Relevance { weight: f32 },
+    /// Boost for a hit in one of the files most worked on lately
+    HotNode { boost: f32 },
+}
+
+/// One titled part of a structured context
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContextSection {
+    /// Section title, e.g. `Focus Area`
+    pub title: String,
+    /// Ids of the tree nodes rendered into the section
+    pub source_nodes: Vec<String>,
+    /// Estimated token count of `text`
+    pub tokens: usize,
+    /// Rendered markdown
+    pub text: String,
+}
+
+/// Outcome of a diagnostic check, ordered from best to worst
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Ok,
+    /// Works, but needs attention
+    Warning,
+    /// Likely keeps the daemon from starting or serving requests
+    Failed,
+}
+
+/// Stored data of a project that can be deleted
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GarbageProject {
+    /// Project root recorded in its stored data, if readable
+    pub root: Option<PathBuf>,
+    /// Its directories, relative to the data dir
+    pub dirs: Vec<PathBuf>,
+    /// `source_missing`, `idle` or `unreadable`
+    pub reason: String,
+    pub bytes: u64,
+    /// Whole days since any of its files was written
+    pub idle_days: u64,
+}
+
+/// One diagnostic check run by `engram doctor`, or a component probed by
+/// `HealthCheck`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DoctorCheck {
+    /// What was checked, e.g. `socket` or `storage`
+    pub name: String,
+    pub status: CheckStatus,
+    pub message: String,
+    /// Suggested fix for warnings and failures
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fix: Option<String>,
+}
+
+impl DoctorCheck {
+    /// Create a check result without a fix
+    pub fn new(name: impl Into<String>, status: CheckStatus, message: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status,
+            message: message.into(),
+            fix: None,
+        }
+    }
+
+    /// Attach a suggested fix
+    pub fn with_fix(mut self, fix: impl Into<String>) -> Self {
+        self.fix = Some(fix.into());
+        self
+    }
+}
+
+/// Latencies measured by `engram bench` on a synthetic repository
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BenchReport {
+    /// Files in the synthetic repository
+    pub files: usize,
+    /// Timed runs per measurement
+    pub runs: usize,
+    /// Initializing and fully indexing the repository
+    pub full_index_ms: f64,
+    /// Applying a one-file change to the stored tree (median)
+    pub incremental_update_ms: f64,
+    /// Rendering context for a prompt, uncached (median)
+    pub context_render_ms: f64,
+    /// Peak resident memory of the benchmark process
+    pub peak_memory_bytes: u64,
+}
+
+/// What the daemon found and repaired after an unclean shutdown
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RecoveryInfo {
+    /// PID of the daemon that stopped without shutting down
+    pub previous_pid: u32,
+    /// Last heartbeat of that daemon (Unix timestamp)
+    pub last_heartbeat: i64,
+    /// Repairs made, e.g. torn experience log lines set aside
+    pub actions: Vec<String>,
+    /// Damage found but left alone
+    pub problems: Vec<String>,
 }
 
 /// Response from daemon to client
@@ -150,7 +1254,14 @@ pub enum Response {
     Ack,
 
     /// Error response
-    Error { code: ErrorCode, message: String },
+    Error {
+        code: ErrorCode,
+        message: String,
+        /// Structured description for retry logic and user-facing messages;
+        /// absent from daemons predating it
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        details: Option<ErrorDetails>,
+    },
 }
 
 impl Response {
@@ -169,11 +1280,30 @@ impl Response {
         Response::Ack
     }
 
-    /// Create an error response
+    /// Create an error response, with the details implied by `code`
     pub fn error(code: ErrorCode, message: impl Into<String>) -> Self {
         Response::Error {
             code,
             message: message.into(),
+            details: Some(ErrorDetails::from(code)),
+        }
+    }
+
+    /// Create an error response from a typed error
+    pub fn failure<E: DescribeError + ?Sized>(error: &E) -> Self {
+        let details = error.details();
+        Response::Error {
+            code: details.kind.code(),
+            message: error.to_string(),
+            details: Some(details),
+        }
+    }
+
+    /// Structured details of an error response, if any
+    pub fn error_details(&self) -> Option<&ErrorDetails> {
+        match self {
+            Response::Error { details, .. } => details.as_ref(),
+            _ => None,
         }
     }
 }
@@ -186,7 +1316,22 @@ pub enum ResponseData {
     InitStatus { initialized: bool },
 
     /// Context retrieval result
-    Context { context: String, nodes: Vec<String> },
+    Context {
+        context: String,
+        nodes: Vec<String>,
+        /// Scope the context was rendered from, for `ReleaseScope`
+        #[serde(default)]
+        scope_id: String,
+    },
+
+    /// Context retrieval result split into sections, for clients that show
+    /// provenance or fit sections to their own budgets
+    StructuredContext {
+        /// Sections in display order
+        sections: Vec<ContextSection>,
+        nodes: Vec<String>,
+        scope_id: String,
+    },
 
     /// Daemon status
     Status {
@@ -203,19 +1348,491 @@ pub enum ResponseData {
         /// Average request latency in milliseconds
         #[serde(default)]
         avg_latency_ms: u64,
+        /// Per-action resource usage (shown in verbose status)
+        #[serde(default)]
+        request_usage: Vec<RequestUsage>,
+        /// Per-project render cache counters
+        #[serde(default)]
+        render_cache: Vec<RenderCacheInfo>,
+        /// Experiment flags (before per-project overrides)
+        #[serde(default)]
+        experiments: Vec<ExperimentInfo>,
+        /// Latency SLO compliance
+        #[serde(default)]
+        slos: Vec<SloInfo>,
+        /// Running enrichment jobs with their progress
+        #[serde(default)]
+        enrichment: Vec<JobInfo>,
+        /// API usage of the enrichment summarizer, if it calls one
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        summarizer: Option<Box<SummarizerInfo>>,
+        /// Per-action latency percentiles
+        #[serde(default)]
+        latency: Vec<EndpointLatency>,
+        /// Context scopes that have not expired or been released
+        #[serde(default)]
+        scopes_active: usize,
+        /// Contexts cached ahead of a request after a file change
+        #[serde(default)]
+        prefetches: u64,
+        /// Prefetched contexts that served a request
+        #[serde(default)]
+        prefetch_hits: u64,
+        /// Recovery after an unclean shutdown, reported by the first
+        /// `Status` after the restart
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        recovery: Option<Box<RecoveryInfo>>,
     },
 
     /// Pong response
     Pong { timestamp: i64 },
 
+    /// Status of each daemon component
+    Health {
+        /// Worst status of the components
+        status: CheckStatus,
+        timestamp: i64,
+        uptime_secs: u64,
+        /// `data_dir`, `watcher`, `enrichment` and `memory`
+        components: Vec<DoctorCheck>,
+    },
+
+    /// The daemon's side of a `Hello`
+    Hello {
+        daemon_version: String,
+        /// Highest protocol version the daemon speaks
+        protocol_version: u8,
+        /// Features the daemon supports, see [`FEATURES`]
+        features: Vec<String>,
+    },
+
+    /// Result of a synchronous project scan
+    ScanReport {
+        file_count: usize,
+        /// Artifact directories that were automatically excluded
+        #[serde(default)]
+        auto_excluded: Vec<ExcludedDirInfo>,
+    },
+
     /// Single memory entry
     MemoryEntry { entry: MemoryEntry },
 
     /// Multiple memory entries
     MemoryEntries { entries: Vec<MemoryEntry> },
 
+    /// Memory search results, best first
+    MemoryMatches { matches: Vec<MemoryMatch> },
+
     /// Memory write/update acknowledgment
     MemoryAck { id: String },
+
+    /// Memory write staged for parent-agent approval
+    MemoryStaged { id: String },
+
+    /// Staged entries that were approved or rejected
+    PendingResolved { ids: Vec<String> },
+
+    /// A session that was started or ended
+    Session { session: SessionInfo },
+
+    /// Sessions of a project, most recently started first
+    Sessions { sessions: Vec<SessionInfo> },
+
+    /// A session with what was recorded in it, oldest first
+    SessionDetail {
+        session: SessionInfo,
+        experiences: Vec<Experience>,
+        memory: Vec<MemoryEntry>,
+    },
+
+    /// Responses to a batch, in request order
+    Batch { responses: Vec<Response> },
+
+    /// Scoring calibration as JSON (the export/import format)
+    Calibration { calibration: String },
+
+    /// What a tree prune removed
+    PruneReport {
+        removed_dirs: usize,
+        removed_files: usize,
+        removed_symbols: usize,
+        removed_edges: usize,
+    },
+
+    /// Running background jobs
+    Jobs { jobs: Vec<JobInfo> },
+
+    /// Recent daemon events
+    Events { events: Vec<EventInfo> },
+
+    /// Progress of indexing a project
+    InitProgress {
+        /// `scanning`, `parsing`, `building` or `enriching`; `None` when
+        /// the project is not being indexed
+        phase: Option<String>,
+        /// Items processed in the current phase
+        processed: usize,
+        /// Items in the current phase (0 while still discovering)
+        total: usize,
+        elapsed_secs: u64,
+        /// Estimated seconds left in the current phase
+        eta_secs: Option<u64>,
+    },
+
+    /// What a memory log compaction removed
+    MemoryCompacted {
+        lines_before: usize,
+        lines_after: usize,
+        dropped_versions: usize,
+        dropped_tombstones: usize,
+    },
+
+    /// What an experience consolidation merged and summarized
+    ExperiencesConsolidated {
+        experiences_before: usize,
+        experiences_after: usize,
+        /// Duplicates merged into a later experience
+        merged: usize,
+        /// Experiences folded into summaries
+        summarized: usize,
+        /// Summary entries created
+        summaries: usize,
+    },
+
+    /// Memory of a project, from `ExportMemory`
+    MemoryBundle { bundle: MemoryBundle },
+
+    /// What a memory import added and skipped
+    MemoryImported {
+        /// Entries written, new or newer than the local version
+        entries_imported: usize,
+        /// Entries whose local version is as new or newer
+        entries_skipped: usize,
+        experiences_imported: usize,
+        /// Experiences already in the log
+        experiences_skipped: usize,
+    },
+
+    /// Search results, best first
+    SearchResults { hits: Vec<SearchHit> },
+
+    /// Files matching a pattern, best first
+    FileMatches { matches: Vec<FileMatch> },
+
+    /// Import graph analysis; unrequested parts are left empty
+    Dependencies {
+        /// Groups of files importing each other, each sorted by path
+        cycles: Vec<Vec<PathBuf>>,
+        /// Files with dependencies before their importers; `None` if not
+        /// requested or the imports have cycles
+        order: Option<Vec<PathBuf>>,
+        /// Files affected by changing the analyzed file, nearest first
+        dependents: Vec<DependentFile>,
+    },
+
+    /// Files and symbols likely affected by changing a symbol
+    Impact {
+        /// Declarations of the symbol
+        definitions: Vec<ImpactSymbol>,
+        /// Affected files, declaring files first, then by import distance
+        files: Vec<ImpactFile>,
+    },
+
+    /// Test links of a file, sorted by path
+    Tests {
+        /// Whether the file is itself a test
+        is_test: bool,
+        /// Whether the file holds inline tests
+        inline: bool,
+        /// Its tests, or for a test file the files it covers
+        files: Vec<PathBuf>,
+    },
+
+    /// Documentation of a symbol
+    Symbol {
+        symbol: ImpactSymbol,
+        /// Declaration lines, up to the start of the body
+        signature: String,
+        /// Doc comment, if any
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        doc: Option<String>,
+        /// Summary of the declaring file, if any
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        file_summary: Option<String>,
+        /// Symbols mentioning it, by path and line
+        callers: Vec<ImpactSymbol>,
+        /// Symbols it calls, in order of first call
+        callees: Vec<ImpactSymbol>,
+        /// Experiences touching its file or naming it, most recent first
+        experiences: Vec<Experience>,
+    },
+
+    /// Index statistics; ranked lists are sorted largest first
+    ProjectStats {
+        files: usize,
+        symbols: usize,
+        lines: usize,
+        /// Total size of indexed files
+        bytes: u64,
+        dependency_edges: usize,
+        languages: Vec<NamedCount>,
+        /// Binary and asset files by type, largest total first
+        #[serde(default)]
+        assets: Vec<AssetCount>,
+        symbol_kinds: Vec<NamedCount>,
+        largest_files: Vec<FileSize>,
+        /// Files imported by the most other files
+        most_imported: Vec<FileCount>,
+        /// Files importing the most other files
+        most_importing: Vec<FileCount>,
+        /// Storage files, largest first
+        index_files: Vec<IndexFile>,
+        /// Total size of the storage directory
+        index_bytes: u64,
+        /// When the project was initialized (Unix timestamp)
+        created_at: i64,
+        /// Last scan time (Unix timestamp)
+        last_scan: Option<i64>,
+        enriched: bool,
+    },
+
+    /// Generated documentation, parent directories first
+    Docs {
+        files: Vec<DocFile>,
+        /// Whether file summaries came from an enriched tree
+        enriched: bool,
+    },
+
+    /// Files and symbols that differ between two trees, sorted by path
+    TreeDiff {
+        added_files: Vec<PathBuf>,
+        removed_files: Vec<PathBuf>,
+        /// Files in both trees whose content changed
+        modified_files: Vec<PathBuf>,
+        added_symbols: Vec<DiffSymbol>,
+        removed_symbols: Vec<DiffSymbol>,
+        /// Symbols of modified files whose span changed
+        modified_symbols: Vec<DiffSymbol>,
+    },
+
+    /// Anonymized project snapshot (no names, contents or summaries)
+    DebugSnapshot {
+        project: Option<ProjectSummary>,
+        structure: Vec<StructureNode>,
+    },
+
+    /// Per-line hints for a single file, sorted by line
+    LineHints {
+        path: PathBuf,
+        hints: Vec<LineHint>,
+        /// Most recent experience that touched the file
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        last_experience: Option<Experience>,
+    },
+
+    /// Result of unloading a project
+    Unloaded {
+        /// Whether the project was loaded before
+        was_loaded: bool,
+    },
+
+    /// Result of relinking a project
+    ProjectRelinked {
+        /// Stable project id
+        id: String,
+        /// Root the project was stored under before
+        previous_path: PathBuf,
+        /// Whether storage kept under the previous path was moved
+        adopted: bool,
+    },
+
+    /// Result of exporting or importing a project archive
+    ProjectArchive {
+        /// Archive written or read
+        path: PathBuf,
+        /// Files in the archive
+        files: usize,
+        /// Uncompressed size of the files
+        bytes: u64,
+        /// Root the archive was exported from
+        source_path: PathBuf,
+    },
+
+    /// Diagnostic checks, in the order they ran
+    Doctor { checks: Vec<DoctorCheck> },
+
+    /// Loaded projects, by path
+    Projects { projects: Vec<LoadedProjectInfo> },
+
+    /// Garbage found, and deleted unless the request was a dry run
+    Garbage {
+        projects: Vec<GarbageProject>,
+        /// Bytes the projects take up
+        reclaimable_bytes: u64,
+        /// Projects deleted
+        deleted: usize,
+    },
+
+    /// Experience logs encrypted
+    LogsEncrypted {
+        /// Stored projects whose logs had plaintext lines
+        projects: usize,
+        /// Lines encrypted
+        lines: usize,
+    },
+
+    /// Result of reloading the daemon config
+    ConfigReloaded {
+        /// Config file that was read
+        path: PathBuf,
+        /// Changed settings now in effect, as dotted names (e.g.
+        /// `enrichment.workers`)
+        applied: Vec<String>,
+        /// Changed settings that take effect after a restart
+        restart_required: Vec<String>,
+    },
+}
+
+/// What went wrong, finer-grained than [`ErrorCode`]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// Project has not been initialized
+    NotInitialized,
+    /// A project, scope, node or file does not exist
+    NotFound,
+    /// A request field has an invalid value
+    InvalidInput,
+    /// The request conflicts with existing state
+    Conflict,
+    /// The project is read-only
+    ReadOnly,
+    /// Filesystem or socket failure
+    Io,
+    /// Stored data could not be read or written
+    Storage,
+    /// Source could not be parsed
+    Parse,
+    /// The client cancelled the request
+    Cancelled,
+    /// The request ran past its deadline
+    Timeout,
+    /// The daemon cannot serve requests right now
+    Unavailable,
+    /// Peer is not allowed to use the daemon
+    Unauthorized,
+    /// The daemon does not support the request
+    Unsupported,
+    /// Anything else
+    Internal,
+}
+
+impl ErrorKind {
+    /// Error code reported alongside this kind
+    pub fn code(self) -> ErrorCode {
+        match self {
+            ErrorKind::NotInitialized => ErrorCode::NotInitialized,
+            ErrorKind::NotFound | ErrorKind::InvalidInput | ErrorKind::Conflict => {
+                ErrorCode::InvalidRequest
+            }
+            ErrorKind::ReadOnly => ErrorCode::ReadOnly,
+            ErrorKind::Cancelled => ErrorCode::Cancelled,
+            ErrorKind::Timeout => ErrorCode::Timeout,
+            ErrorKind::Unavailable => ErrorCode::ShuttingDown,
+            ErrorKind::Unauthorized => ErrorCode::Unauthorized,
+            ErrorKind::Unsupported => ErrorCode::Unsupported,
+            ErrorKind::Io | ErrorKind::Storage | ErrorKind::Parse | ErrorKind::Internal => {
+                ErrorCode::InternalError
+            }
+        }
+    }
+}
+
+/// Structured description of an error response
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ErrorDetails {
+    pub kind: ErrorKind,
+    /// Whether sending the same request again may succeed
+    pub retryable: bool,
+    /// How long to wait before retrying, in milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after_ms: Option<u64>,
+    /// Request field holding the offending value
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+}
+
+impl ErrorDetails {
+    /// Details of a non-retryable error of `kind`
+    pub fn new(kind: ErrorKind) -> Self {
+        Self {
+            kind,
+            retryable: false,
+            retry_after_ms: None,
+            field: None,
+        }
+    }
+
+    /// Mark the error retryable
+    pub fn retryable(mut self) -> Self {
+        self.retryable = true;
+        self
+    }
+
+    /// Mark the error retryable after waiting `delay`
+    pub fn with_retry_after(mut self, delay: Duration) -> Self {
+        self.retryable = true;
+        self.retry_after_ms = Some(delay.as_millis() as u64);
+        self
+    }
+
+    /// Name the request field holding the offending value
+    pub fn with_field(mut self, field: impl Into<String>) -> Self {
+        self.field = Some(field.into());
+        self
+    }
+
+    /// How long to wait before retrying, if the error is retryable
+    pub fn retry_after(&self) -> Option<Duration> {
+        self.retryable
+            .then(|| Duration::from_millis(self.retry_after_ms.unwrap_or(0)))
+    }
+}
+
+impl From<ErrorCode> for ErrorDetails {
+    fn from(code: ErrorCode) -> Self {
+        match code {
+            ErrorCode::NotInitialized => Self::new(ErrorKind::NotInitialized).with_field("cwd"),
+            ErrorCode::InvalidRequest => Self::new(ErrorKind::InvalidInput),
+            ErrorCode::InternalError => Self::new(ErrorKind::Internal),
+            ErrorCode::Timeout => Self::new(ErrorKind::Timeout).retryable(),
+            ErrorCode::ShuttingDown => {
+                Self::new(ErrorKind::Unavailable).with_retry_after(Duration::from_secs(1))
+            }
+            ErrorCode::Unauthorized => Self::new(ErrorKind::Unauthorized),
+            ErrorCode::Cancelled => Self::new(ErrorKind::Cancelled),
+            ErrorCode::ReadOnly => Self::new(ErrorKind::ReadOnly),
+            ErrorCode::Unsupported => Self::new(ErrorKind::Unsupported),
+        }
+    }
+}
+
+/// An error that can describe itself on the wire
+pub trait DescribeError: std::fmt::Display {
+    /// Structured details of this error
+    fn details(&self) -> ErrorDetails;
+}
+
+/// Details for an I/O error, retryable when the failure is transient
+pub fn io_error_details(error: &std::io::Error) -> ErrorDetails {
+    use std::io::ErrorKind as Io;
+    match error.kind() {
+        Io::NotFound => ErrorDetails::new(ErrorKind::NotFound),
+        Io::Interrupted | Io::WouldBlock | Io::TimedOut => {
+            ErrorDetails::new(ErrorKind::Io).retryable()
+        }
+        _ => ErrorDetails::new(ErrorKind::Io),
+    }
 }
 
 /// Error codes for error responses
@@ -232,12 +1849,32 @@ pub enum ErrorCode {
     Timeout,
     /// Daemon is shutting down
     ShuttingDown,
+    /// Peer is not allowed to use the daemon
+    Unauthorized,
+    /// Request was cancelled by the client
+    Cancelled,
+    /// Request would write to a read-only project
+    ReadOnly,
+    /// Client speaks a newer protocol than the daemon understands
+    Unsupported,
 }
 
 fn default_memory_list_limit() -> usize {
     50
 }
 
+fn default_search_limit() -> usize {
+    10
+}
+
+fn default_session_list_limit() -> usize {
+    20
+}
+
+fn default_event_limit() -> usize {
+    100
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -264,6 +1901,21 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_error_details_roundtrip() {
+        let resp = Response::error(ErrorCode::ShuttingDown, "Daemon is shutting down");
+        let bytes = rmp_serde::to_vec_named(&resp).unwrap();
+        let decoded: Response = rmp_serde::from_slice(&bytes).unwrap();
+        let details = decoded.error_details().unwrap();
+        assert_eq!(details.kind, ErrorKind::Unavailable);
+        assert_eq!(details.retry_after(), Some(Duration::from_secs(1)));
+
+        // Errors from daemons predating details still decode
+        let legacy = r#"{"status":"error","code":"internal_error","message":"boom"}"#;
+        let decoded: Response = serde_json::from_str(legacy).unwrap();
+        assert!(decoded.error_details().is_none());
+    }
+
     #[test]
     fn test_response_serialization() {
         let resp = Response::ok_with(ResponseData::Status {
@@ -274,11 +1926,232 @@ mod tests {
             requests_total: 100,
             cache_hit_rate: 0.95,
             avg_latency_ms: 5,
+            request_usage: vec![RequestUsage {
+                action: "get_context".to_string(),
+                count: 3,
+                bytes_read: 1024,
+                nodes_touched: 12,
+                alloc_bytes: 2048,
+            }],
+            render_cache: vec![RenderCacheInfo {
+                project: PathBuf::from("/test/path"),
+                hits: 4,
+                misses: 1,
+                evictions: 0,
+                entries: 1,
+            }],
+            experiments: vec![ExperimentInfo {
+                name: "incremental_indexing".to_string(),
+                enabled: true,
+            }],
+            slos: vec![SloInfo {
+                name: "get_context p99 < 150ms".to_string(),
+                window_secs: 3600,
+                total: 200,
+                slow: 6,
+                compliance: 0.97,
+                burn_rate: 3.0,
+                at_risk: true,
+            }],
+            enrichment: vec![],
+            summarizer: None,
+            latency: vec![EndpointLatency {
+                action: "get_context".to_string(),
+                count: 200,
+                p50_ms: 4.2,
+                p95_ms: 48.0,
+                p99_ms: 120.0,
+            }],
+            scopes_active: 2,
+            prefetches: 0,
+            prefetch_hits: 0,
+            recovery: None,
         });
 
         let json = serde_json::to_string(&resp).unwrap();
         assert!(json.contains("ok"));
+        assert!(json.contains("burn_rate"));
         assert!(json.contains("0.1.0"));
+        assert!(json.contains("nodes_touched"));
+        assert!(json.contains("evictions"));
+        assert!(json.contains("p95_ms"));
+        assert!(json.contains("p99_ms"));
+    }
+
+    #[test]
+    fn test_action_name_matches_serde_tag() {
+        let requests = vec![
+            Request::Ping,
+            Request::HealthCheck,
+            Request::Hello {
+                client_version: "0.1.0".to_string(),
+                protocol_version: PROTOCOL_VERSION,
+                supported_features: vec!["batch".to_string()],
+            },
+            Request::Status,
+            Request::ListProjects,
+            Request::Doctor,
+            Request::CollectGarbage {
+                max_idle_days: Some(30),
+                dry_run: true,
+                only: vec![PathBuf::from("projects/0123abcd")],
+            },
+            Request::EncryptLogs,
+            Request::ReloadConfig,
+            Request::MemoryList {
+                cwd: PathBuf::from("/test/path"),
+                limit: 5,
+                query: MemoryQuery::default(),
+            },
+            Request::MemorySearch {
+                cwd: PathBuf::from("/test/path"),
+                query: "token rotation".to_string(),
+                limit: 5,
+                viewer: None,
+            },
+            Request::LineHints {
+                cwd: PathBuf::from("/test/path"),
+                path: PathBuf::from("src/lib.rs"),
+            },
+            Request::MemoryCompact {
+                cwd: PathBuf::from("/test/path"),
+            },
+            Request::ConsolidateExperiences {
+                cwd: PathBuf::from("/test/path"),
+            },
+            Request::ExportMemory {
+                cwd: PathBuf::from("/test/path"),
+            },
+            Request::ImportMemory {
+                cwd: PathBuf::from("/test/path"),
+                bundle: MemoryBundle {
+                    format: MEMORY_BUNDLE_FORMAT,
+                    exported_at: 1_700_000_000,
+                    entries: vec![],
+                    experiences: vec![],
+                },
+            },
+            Request::Search {
+                cwd: PathBuf::from("/test/path"),
+                query: "auth".to_string(),
+                limit: 10,
+                explain: false,
+            },
+            Request::FindFiles {
+                cwd: PathBuf::from("/test/path"),
+                pattern: "auth middleware".to_string(),
+                limit: 10,
+            },
+            Request::AnalyzeDependencies {
+                cwd: PathBuf::from("/test/path"),
+                cycles: true,
+                order: false,
+                dependents_of: None,
+                depth: None,
+            },
+            Request::ImpactAnalysis {
+                cwd: PathBuf::from("/test/path"),
+                symbol: "parse_config".to_string(),
+            },
+            Request::ListTests {
+                cwd: PathBuf::from("/test/path"),
+                path: PathBuf::from("src/auth.rs"),
+            },
+            Request::GetSymbol {
+                cwd: PathBuf::from("/test/path"),
+                path: PathBuf::from("src/auth.rs"),
+                symbol: "validate".to_string(),
+            },
+            Request::GetInitProgress {
+                cwd: PathBuf::from("/test/path"),
+            },
+            Request::ProjectStats {
+                cwd: PathBuf::from("/test/path"),
+                top: Some(5),
+            },
+            Request::ExportDocs {
+                cwd: PathBuf::from("/test/path"),
+                mdbook: true,
+            },
+            Request::DiffSnapshots {
+                cwd: PathBuf::from("/test/path"),
+                from: "20240101_000000".to_string(),
+                to: None,
+            },
+            Request::GetEvents {
+                since: 0,
+                limit: 100,
+            },
+            Request::Authenticate {
+                token: "secret".to_string(),
+            },
+            Request::UnloadProject {
+                cwd: PathBuf::from("/test/path"),
+            },
+            Request::RelinkProject {
+                cwd: PathBuf::from("/test/path"),
+                from: Some(PathBuf::from("/old/path")),
+            },
+            Request::ExportProject {
+                cwd: PathBuf::from("/test/path"),
+                output: PathBuf::from("/tmp/project.tar.gz"),
+            },
+            Request::ImportProject {
+                cwd: PathBuf::from("/test/path"),
+                archive: PathBuf::from("/tmp/project.tar.gz"),
+            },
+            Request::ReleaseScope {
+                cwd: PathBuf::from("/test/path"),
+                scope_id: "scope-1".to_string(),
+            },
+            Request::ForkScope {
+                cwd: PathBuf::from("/test/path"),
+                scope_id: "scope-1".to_string(),
+                focus: vec![PathBuf::from("src/lib.rs")],
+            },
+            Request::ExpandContext {
+                cwd: PathBuf::from("/test/path"),
+                scope_id: "scope-1".to_string(),
+                node: "src/auth.rs".to_string(),
+            },
+            Request::Ping.tracked("req-1", None),
+            Request::Cancel {
+                request_id: "req-1".to_string(),
+            },
+        ];
+
+        for request in requests {
+            let value = serde_json::to_value(&request).unwrap();
+            assert_eq!(value["action"], request.action_name());
+        }
+    }
+
+    #[test]
+    fn test_tracked_roundtrip() {
+        let request = Request::Status.tracked("req-7", Some(Duration::from_secs(5)));
+        let bytes = rmp_serde::to_vec(&request).unwrap();
+        let Request::Tracked {
+            request_id,
+            deadline_ms,
+            request,
+        } = rmp_serde::from_slice(&bytes).unwrap()
+        else {
+            panic!("Decoded wrong request variant");
+        };
+        assert_eq!(request_id, "req-7");
+        assert_eq!(deadline_ms, Some(5000));
+        assert!(matches!(*request, Request::Status));
+
+        // The deadline is optional on the wire
+        let json = r#"{"action":"tracked","request_id":"r","request":{"action":"ping"}}"#;
+        let request: Request = serde_json::from_str(json).unwrap();
+        assert!(matches!(
+            request,
+            Request::Tracked {
+                deadline_ms: None,
+                ..
+            }
+        ));
     }
 
     #[test]
@@ -295,6 +2168,8 @@ mod tests {
                 session_id: Some("session-1".to_string()),
                 subagent_id: None,
                 deleted: false,
+                experience_id: None,
+                visibility: MemoryVisibility::Project,
             },
         };
 
@@ -327,12 +2202,14 @@ mod tests {
                 subagent_id: Some("subagent-2".to_string()),
                 deleted: Some(false),
                 updated_at: Some(1_700_000_200),
+                visibility: Some(MemoryVisibility::Private),
             },
         };
 
         let json = serde_json::to_string(&req).unwrap();
         assert!(json.contains("memory_patch"));
         assert!(json.contains("\"id\":\"mem-1\""));
+        assert!(json.contains("\"visibility\":\"private\""));
         assert!(json.contains("\"session_id\":\"session-2\""));
 
         let msgpack = rmp_serde::to_vec(&req).unwrap();
@@ -402,6 +2279,8 @@ mod tests {
             session_id: None,
             subagent_id: Some("subagent-1".to_string()),
             deleted: false,
+            experience_id: None,
+            visibility: MemoryVisibility::Project,
         };
 
         let response = Response::ok_with(ResponseData::MemoryEntries {
@@ -426,4 +2305,240 @@ mod tests {
         assert!(json.contains("memory_ack"));
         assert!(json.contains("mem-2"));
     }
+
+    #[test]
+    fn test_line_hints_roundtrip() {
+        let req = Request::LineHints {
+            cwd: PathBuf::from("/test/path"),
+            path: PathBuf::from("src/lib.rs"),
+        };
+        let json = serde_json::to_string(&req).unwrap();
+        assert!(json.contains("line_hints"));
+
+        let response = Response::ok_with(ResponseData::LineHints {
+            path: PathBuf::from("src/lib.rs"),
+            hints: vec![LineHint {
+                line: 3,
+                end_line: 10,
+                kind: LineHintKind::Symbol,
+                label: "fn main".to_string(),
+                ref_id: None,
+            }],
+            last_experience: None,
+        });
+        let json = serde_json::to_string(&response).unwrap();
+        assert!(!json.contains("ref_id"));
+        assert!(!json.contains("last_experience"));
+
+        let msgpack = rmp_serde::to_vec(&response).unwrap();
+        let decoded: Response = rmp_serde::from_slice(&msgpack).unwrap();
+        if let Response::Ok {
+            data: Some(ResponseData::LineHints { hints, .. }),
+        } = decoded
+        {
+            assert_eq!(hints.len(), 1);
+            assert_eq!(hints[0].kind, LineHintKind::Symbol);
+            assert_eq!(hints[0].end_line, 10);
+        } else {
+            panic!("Decoded wrong response variant");
+        }
+    }
+
+    #[test]
+    fn test_memory_bundle_roundtrip() {
+        // Experiences written before session ids were exported
+        let bundle: MemoryBundle = serde_json::from_str(
+            r#"{"format":1,"exported_at":1700000000,"experiences":[
+                {"agent_id":"agent-1","decision":"Use JWT","timestamp":1690000000,"id":"exp-1"}
+            ]}"#,
+        )
+        .unwrap();
+        assert!(bundle.entries.is_empty());
+        assert_eq!(bundle.experiences[0].id.as_deref(), Some("exp-1"));
+        assert_eq!(bundle.experiences[0].session_id, None);
+
+        let req = Request::ImportMemory {
+            cwd: PathBuf::from("/test/path"),
+            bundle,
+        };
+        let msgpack = rmp_serde::to_vec_named(&req).unwrap();
+        let decoded: Request = rmp_serde::from_slice(&msgpack).unwrap();
+        if let Request::ImportMemory { bundle, .. } = decoded {
+            assert_eq!(bundle.format, MEMORY_BUNDLE_FORMAT);
+            assert_eq!(bundle.experiences[0].timestamp, 1_690_000_000);
+        } else {
+            panic!("Decoded wrong request variant");
+        }
+    }
+
+    #[test]
+    fn test_session_requests() {
+        let req: Request = serde_json::from_str(
+            r#"{"action":"session_start","cwd":"/test/path","session_id":"s1","agent":"claude","metadata":{"source":"startup"}}"#,
+        )
+        .unwrap();
+        assert_eq!(req.action_name(), "session_start");
+        let msgpack = rmp_serde::to_vec(&req).unwrap();
+        let decoded: Request = rmp_serde::from_slice(&msgpack).unwrap();
+        if let Request::SessionStart {
+            session_id,
+            agent,
+            model,
+            metadata,
+            ..
+        } = decoded
+        {
+            assert_eq!(session_id.as_deref(), Some("s1"));
+            assert_eq!(agent.as_deref(), Some("claude"));
+            assert!(model.is_none());
+            assert_eq!(metadata["source"], "startup");
+        } else {
+            panic!("Decoded wrong request variant");
+        }
+
+        let req: Request =
+            serde_json::from_str(r#"{"action":"list_sessions","cwd":"/test/path"}"#).unwrap();
+        assert!(matches!(req, Request::ListSessions { limit: 20, .. }));
+        let req: Request = serde_json::from_str(
+            r#"{"action":"session_end","cwd":"/test/path","session_id":"s1"}"#,
+        )
+        .unwrap();
+        assert!(matches!(req, Request::SessionEnd { summary: None, .. }));
+        assert_eq!(
+            Request::GetSession {
+                cwd: PathBuf::from("/test/path"),
+                session_id: "s1".to_string(),
+            }
+            .action_name(),
+            "get_session"
+        );
+    }
+
+    #[test]
+    fn test_request_idempotence() {
+        assert!(Request::Ping.is_idempotent());
+        assert!(!Request::Shutdown.is_idempotent());
+
+        let graft: Request = serde_json::from_str(
+            r#"{"action":"graft_experience","cwd":"/p","experience":{"agent_id":"a","decision":"d","timestamp":0}}"#,
+        )
+        .unwrap();
+        assert!(!graft.is_idempotent());
+        assert!(Request::Batch {
+            requests: vec![Request::Ping, Request::Status],
+        }
+        .is_idempotent());
+        assert!(!Request::Batch {
+            requests: vec![Request::Ping, graft.clone()],
+        }
+        .is_idempotent());
+        assert!(Request::Status.tracked("r1", None).is_idempotent());
+        assert!(!graft.tracked("r2", None).is_idempotent());
+
+        // Exports returning data may be resent; those writing a file may not
+        let cwd = PathBuf::from("/p");
+        assert!(Request::ExportMemory { cwd: cwd.clone() }.is_idempotent());
+        assert!(!Request::ExportProject {
+            cwd,
+            output: PathBuf::from("/p.tar.gz"),
+        }
+        .is_idempotent());
+    }
+
+    #[test]
+    fn test_request_opens_client_paths() {
+        let export = Request::ExportProject {
+            cwd: PathBuf::from("/p"),
+            output: PathBuf::from("/p.tar.gz"),
+        };
+        assert!(export.opens_client_paths());
+        assert!(Request::ImportProject {
+            cwd: PathBuf::from("/p"),
+            archive: PathBuf::from("/p.tar.gz"),
+        }
+        .opens_client_paths());
+        assert!(!Request::CheckInit {
+            cwd: PathBuf::from("/p"),
+        }
+        .opens_client_paths());
+        assert!(Request::Batch {
+            requests: vec![Request::Ping, export.clone()],
+        }
+        .opens_client_paths());
+        assert!(export.tracked("r1", None).opens_client_paths());
+    }
+
+    #[test]
+    fn test_memory_list_query() {
+        let req: Request = serde_json::from_str(
+            r#"{"action":"memory_list","cwd":"/test/path","query":{"tags":["auth"],"text":"jwt"}}"#,
+        )
+        .unwrap();
+        if let Request::MemoryList { limit, query, .. } = &req {
+            assert_eq!(*limit, 50);
+            assert_eq!(query.tags, ["auth"]);
+            assert_eq!(query.text.as_deref(), Some("jwt"));
+            assert!(query.kind.is_none());
+        } else {
+            panic!("Decoded wrong request variant");
+        }
+
+        let msgpack = rmp_serde::to_vec(&req).unwrap();
+        let decoded: Request = rmp_serde::from_slice(&msgpack).unwrap();
+        assert!(matches!(decoded, Request::MemoryList { query, .. } if query.tags == ["auth"]));
+    }
+
+    #[test]
+    fn test_search_roundtrip() {
+        let req: Request =
+            serde_json::from_str(r#"{"action":"search","cwd":"/test/path","query":"auth"}"#)
+                .unwrap();
+        assert!(matches!(req, Request::Search { limit: 10, .. }));
+
+        let response = Response::ok_with(ResponseData::SearchResults {
+            hits: vec![SearchHit {
+                path: PathBuf::from("src/auth.rs"),
+                name: "validate_token".to_string(),
+                kind: "function".to_string(),
+                line: Some(12),
+                score: 0.8,
+                source: "vector".to_string(),
+                snippet: None,
+                explanation: None,
+            }],
+        });
+        let json = serde_json::to_string(&response).unwrap();
+        assert!(!json.contains("snippet"));
+        assert!(!json.contains("explanation"));
+
+        let explained = Response::ok_with(ResponseData::SearchResults {
+            hits: vec![SearchHit {
+                path: PathBuf::from("src/api.rs"),
+                name: "api.rs".to_string(),
+                kind: "file".to_string(),
+                line: None,
+                score: 1.0,
+                source: "tree".to_string(),
+                snippet: None,
+                explanation: Some(vec![MatchReason::DependencyEdge {
+                    imports: PathBuf::from("src/auth.rs"),
+                }]),
+            }],
+        });
+        let json = serde_json::to_string(&explained).unwrap();
+        assert!(json
+            .contains(r#""explanation":[{"reason":"dependency_edge","imports":"src/auth.rs"}]"#));
+
+        let msgpack = rmp_serde::to_vec(&response).unwrap();
+        let decoded: Response = rmp_serde::from_slice(&msgpack).unwrap();
+        if let Response::Ok {
+            data: Some(ResponseData::SearchResults { hits }),
+        } = decoded
+        {
+            assert_eq!(hits[0].line, Some(12));
+            assert_eq!(hits[0].name, "validate_token");
+        } else {
+            panic!("Decoded wrong response variant");
+        }
+    }
 }
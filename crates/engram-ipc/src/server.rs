@@ -1,25 +1,78 @@
-//! Unix socket IPC server for the Engram daemon.
+//! IPC server for the Engram daemon.
 //!
 //! Handles incoming connections and dispatches requests to handlers.
 
-use crate::{IpcError, Request, Response};
+use crate::auth;
+use crate::frame;
+use crate::transport::{self, Listener, ServerStream};
+use crate::{AccessPolicy, ErrorCode, IpcError, Request, Response, ResponseData, PROTOCOL_VERSION};
 use async_trait::async_trait;
-use std::path::Path;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::{UnixListener, UnixStream};
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use tokio::sync::{watch, Notify};
 
 /// Maximum request size (1MB)
-const MAX_REQUEST_SIZE: usize = 1024 * 1024;
+pub(crate) const MAX_REQUEST_SIZE: usize = 1024 * 1024;
 
 /// Request timeout for reading from socket
 const REQUEST_TIMEOUT: Duration = Duration::from_millis(100);
 
-/// Unix socket IPC server
+/// How long a connection may idle between pipelined requests
+const IDLE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// IPC server on a Unix socket (named pipe on Windows)
 pub struct IpcServer {
-    listener: UnixListener,
+    listener: Listener,
+    socket_path: PathBuf,
     handler: Arc<dyn RequestHandler>,
+    access: Arc<AccessPolicy>,
+    in_flight: Arc<InFlight>,
+}
+
+/// Outcome of draining in-flight requests on shutdown
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DrainReport {
+    /// Requests that finished before the deadline
+    pub drained: usize,
+    /// Requests still running at the deadline
+    pub aborted: usize,
+}
+
+/// Requests being handled, and whether the server is closing
+struct InFlight {
+    requests: AtomicUsize,
+    idle: Notify,
+    closing: watch::Sender<bool>,
+}
+
+impl InFlight {
+    fn new() -> Self {
+        Self {
+            requests: AtomicUsize::new(0),
+            idle: Notify::new(),
+            closing: watch::Sender::new(false),
+        }
+    }
+
+    fn start(self: &Arc<Self>) -> RequestGuard {
+        self.requests.fetch_add(1, Ordering::SeqCst);
+        RequestGuard(self.clone())
+    }
+}
+
+/// Counts a request as in flight until dropped
+struct RequestGuard(Arc<InFlight>);
+
+impl Drop for RequestGuard {
+    fn drop(&mut self) {
+        if self.0.requests.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.idle.notify_waiters();
+        }
+    }
 }
 
 impl IpcServer {
@@ -29,39 +82,61 @@ impl IpcServer {
         handler: Arc<dyn RequestHandler>,
     ) -> Result<Self, IpcError> {
         let socket_path = socket_path.as_ref();
+        let listener = Listener::bind(socket_path)?;
+
+        tracing::info!(
+            "IPC server listening on {}",
+            transport::endpoint_display(socket_path)
+        );
+
+        Ok(Self {
+            listener,
+            socket_path: socket_path.to_path_buf(),
+            handler,
+            access: Arc::new(AccessPolicy::default()),
+            in_flight: Arc::new(InFlight::new()),
+        })
+    }
 
-        // Remove stale socket file if it exists
-        if socket_path.exists() {
-            let _ = std::fs::remove_file(socket_path);
-        }
-
-        // Ensure parent directory exists
-        if let Some(parent) = socket_path.parent() {
-            std::fs::create_dir_all(parent)?;
-        }
-
-        let listener = UnixListener::bind(socket_path)?;
-
-        // Set socket permissions (user only - 0600)
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            std::fs::set_permissions(socket_path, std::fs::Permissions::from_mode(0o600))?;
+    /// Restrict who may issue requests.
+    ///
+    /// When other UIDs are allowed the socket is opened up to all users and
+    /// access is decided by peer credentials alone.
+    pub fn with_access(mut self, access: AccessPolicy) -> Self {
+        if !access.allowed_uids.is_empty() {
+            if let Err(e) = transport::share_endpoint(&self.socket_path) {
+                tracing::warn!("Failed to share IPC socket with allowed users: {}", e);
+            }
         }
-
-        tracing::info!("IPC server listening on {}", socket_path.display());
-
-        Ok(Self { listener, handler })
+        self.access = Arc::new(access);
+        self
     }
 
     /// Run the server, accepting connections until shutdown
     pub async fn run(&self) -> Result<(), IpcError> {
+        self.run_until(std::future::pending()).await
+    }
+
+    /// Run the server, accepting connections until `shutdown` completes.
+    ///
+    /// Requests being handled when it does are left running; see
+    /// [`IpcServer::drain`].
+    pub async fn run_until(&self, shutdown: impl Future<Output = ()>) -> Result<(), IpcError> {
+        tokio::pin!(shutdown);
         loop {
-            match self.listener.accept().await {
-                Ok((stream, _addr)) => {
+            let accepted = tokio::select! {
+                accepted = self.listener.accept() => accepted,
+                _ = &mut shutdown => return Ok(()),
+            };
+            match accepted {
+                Ok(stream) => {
                     let handler = self.handler.clone();
+                    let access = self.access.clone();
+                    let in_flight = self.in_flight.clone();
                     tokio::spawn(async move {
-                        if let Err(e) = Self::handle_connection(stream, handler).await {
+                        if let Err(e) =
+                            Self::handle_connection(stream, handler, &access, &in_flight).await
+                        {
                             tracing::debug!("Connection error: {}", e);
                         }
                     });
@@ -73,54 +148,187 @@ impl IpcServer {
         }
     }
 
-    /// Handle a single connection
-    async fn handle_connection(
-        mut stream: UnixStream,
-        handler: Arc<dyn RequestHandler>,
-    ) -> Result<(), IpcError> {
-        // Read request with timeout to avoid blocking
-        let request = tokio::time::timeout(REQUEST_TIMEOUT, Self::read_request(&mut stream))
-            .await
-            .map_err(IpcError::Timeout)?;
-
-        let request = match request {
-            Ok(req) => req,
-            Err(e) => {
-                // Send error response
-                let response = Response::error(
-                    crate::ErrorCode::InvalidRequest,
-                    format!("Failed to parse request: {}", e),
-                );
-                Self::write_response(&mut stream, &response).await?;
-                return Err(e);
+    /// Close idle connections and wait up to `deadline` for the requests
+    /// being handled to be answered.
+    ///
+    /// Connections read no further requests once their current one is
+    /// answered, and new connections are turned away as shutting down.
+    /// Requests still running at the deadline are counted as aborted; they
+    /// are dropped when the process exits.
+    pub async fn drain(&self, deadline: Duration) -> DrainReport {
+        self.in_flight.closing.send_replace(true);
+        let pending = self.in_flight.requests.load(Ordering::SeqCst);
+
+        let idle = tokio::time::timeout(deadline, async {
+            loop {
+                let idle = self.in_flight.idle.notified();
+                if self.in_flight.requests.load(Ordering::SeqCst) == 0 {
+                    return;
+                }
+                idle.await;
             }
-        };
-
-        tracing::debug!("Received request: {:?}", request);
+        });
+        tokio::pin!(idle);
+        loop {
+            let accepted = tokio::select! {
+                _ = &mut idle => break,
+                accepted = self.listener.accept() => accepted,
+            };
+            if let Ok(mut stream) = accepted {
+                tokio::spawn(async move {
+                    let response =
+                        Response::error(ErrorCode::ShuttingDown, "Daemon is shutting down");
+                    let _ = Self::write_response(&mut stream, 0, &response).await;
+                });
+            }
+        }
 
-        // Handle request
-        let response = handler.handle(request).await;
+        let aborted = self.in_flight.requests.load(Ordering::SeqCst).min(pending);
+        DrainReport {
+            drained: pending - aborted,
+            aborted,
+        }
+    }
 
-        // Send response
-        Self::write_response(&mut stream, &response).await?;
+    /// Handle a single connection.
+    ///
+    /// Clients may pipeline further requests on the same connection; they
+    /// are answered in order until the client closes it or goes idle. After
+    /// a `Hello` both sides switch to frames of the negotiated version.
+    async fn handle_connection(
+        mut stream: ServerStream,
+        handler: Arc<dyn RequestHandler>,
+        access: &AccessPolicy,
+        in_flight: &Arc<InFlight>,
+    ) -> Result<(), IpcError> {
+        if let Err(message) = access.check_peer(&stream) {
+            tracing::warn!("Rejected connection: {}", message);
+            let response = Response::error(ErrorCode::Unauthorized, message);
+            return Self::write_response(&mut stream, 0, &response).await;
+        }
+        let owner = auth::peer_is_owner(&stream);
 
-        Ok(())
+        let mut closing = in_flight.closing.subscribe();
+        let mut authenticated = false;
+        let mut protocol = 0;
+        loop {
+            // The first request must arrive promptly; later ones may idle
+            let timeout = if authenticated {
+                IDLE_TIMEOUT
+            } else {
+                REQUEST_TIMEOUT
+            };
+            let request = tokio::select! {
+                request = tokio::time::timeout(
+                    timeout,
+                    Self::read_authenticated(&mut stream, access, authenticated, protocol),
+                ) => request,
+                // The server is shutting down
+                _ = closing.wait_for(|closing| *closing) => return Ok(()),
+            };
+            let _guard = in_flight.start();
+
+            let request = match request {
+                Ok(Ok(Ok(req))) => req,
+                Ok(Ok(Err(response))) => {
+                    tracing::warn!("Rejected request without a valid token");
+                    return Self::write_response(&mut stream, protocol, &response).await;
+                }
+                // Client finished pipelining
+                Ok(Err(IpcError::Io(e)))
+                    if authenticated && e.kind() == std::io::ErrorKind::UnexpectedEof =>
+                {
+                    return Ok(());
+                }
+                Err(_) if authenticated => return Ok(()),
+                Err(elapsed) => return Err(IpcError::Timeout(elapsed)),
+                Ok(Err(e)) => {
+                    // Send error response
+                    let code = match e {
+                        IpcError::Incompatible(_) => ErrorCode::Unsupported,
+                        _ => ErrorCode::InvalidRequest,
+                    };
+                    let response = Response::error(code, format!("Failed to parse request: {}", e));
+                    Self::write_response(&mut stream, protocol, &response).await?;
+                    return Err(e);
+                }
+            };
+            authenticated = true;
+
+            tracing::debug!("Received request: {:?}", request);
+
+            // Handle request
+            let client_protocol = match &request {
+                Request::Hello {
+                    protocol_version, ..
+                } => Some(*protocol_version),
+                _ => None,
+            };
+            let response = if request.opens_client_paths() && !owner {
+                Response::error(
+                    ErrorCode::Unauthorized,
+                    "Only the daemon's own user may have it open files by path",
+                )
+            } else {
+                handler.handle(request).await
+            };
+
+            // Send response
+            Self::write_response(&mut stream, protocol, &response).await?;
+
+            // Later frames use the version both sides speak
+            if let (
+                Some(client_protocol),
+                Response::Ok {
+                    data:
+                        Some(ResponseData::Hello {
+                            protocol_version, ..
+                        }),
+                },
+            ) = (client_protocol, &response)
+            {
+                protocol = client_protocol.min(*protocol_version).min(PROTOCOL_VERSION);
+            }
+        }
     }
 
-    /// Read a request from the stream
-    async fn read_request(stream: &mut UnixStream) -> Result<Request, IpcError> {
-        // Read length prefix (4 bytes, little-endian)
-        let mut len_buf = [0u8; 4];
-        stream.read_exact(&mut len_buf).await?;
-        let len = u32::from_le_bytes(len_buf) as usize;
+    /// Read a request, first consuming the token frame if one is required.
+    ///
+    /// Once a connection has `authenticated`, later requests need no token.
+    /// The inner error is the response to send when authentication fails.
+    async fn read_authenticated<S: AsyncRead + Unpin>(
+        stream: &mut S,
+        access: &AccessPolicy,
+        authenticated: bool,
+        protocol: u8,
+    ) -> Result<Result<Request, Response>, IpcError> {
+        let request = Self::read_request(stream, protocol).await?;
+        let Request::Authenticate { token } = request else {
+            return Ok(match access.token {
+                Some(_) if !authenticated => Err(Response::error(
+                    ErrorCode::Unauthorized,
+                    "This daemon requires a token",
+                )),
+                _ => Ok(request),
+            });
+        };
 
-        if len > MAX_REQUEST_SIZE {
-            return Err(IpcError::RequestTooLarge);
+        if !access.accepts_token(&token) {
+            return Ok(Err(Response::error(
+                ErrorCode::Unauthorized,
+                "Invalid token",
+            )));
         }
+        Self::read_request(stream, protocol).await.map(Ok)
+    }
 
-        // Read request body
-        let mut buf = vec![0u8; len];
-        stream.read_exact(&mut buf).await?;
+    /// Read a request from the stream, in versioned frames once `protocol`
+    /// has been negotiated
+    async fn read_request<S: AsyncRead + Unpin>(
+        stream: &mut S,
+        protocol: u8,
+    ) -> Result<Request, IpcError> {
+        let (version, buf) = frame::read_frame(stream, protocol != 0, MAX_REQUEST_SIZE).await?;
 
         // Try MessagePack first, fall back to JSON for easier debugging
         if let Ok(request) = rmp_serde::from_slice(&buf) {
@@ -132,18 +340,22 @@ impl IpcServer {
             return Ok(request);
         }
 
-        Err(IpcError::Deserialize(
+        Err(frame::decode_error(
+            version,
             rmp_serde::from_slice::<Request>(&buf).unwrap_err(),
         ))
     }
 
-    /// Write a response to the stream
-    async fn write_response(stream: &mut UnixStream, response: &Response) -> Result<(), IpcError> {
-        let response_bytes = rmp_serde::to_vec(response)?;
-        let len_bytes = (response_bytes.len() as u32).to_le_bytes();
-
-        stream.write_all(&len_bytes).await?;
-        stream.write_all(&response_bytes).await?;
+    /// Write a response to the stream, in a versioned frame once `protocol`
+    /// has been negotiated
+    async fn write_response<S: AsyncWrite + Unpin>(
+        stream: &mut S,
+        protocol: u8,
+        response: &Response,
+    ) -> Result<(), IpcError> {
+        // Named fields, since optional fields are skipped when unset
+        let response_bytes = rmp_serde::to_vec_named(response)?;
+        frame::write_frame(stream, protocol, &response_bytes).await?;
         stream.flush().await?;
 
         Ok(())
@@ -160,9 +372,7 @@ pub trait RequestHandler: Send + Sync {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::ResponseData;
-    use tokio::io::{AsyncReadExt, AsyncWriteExt};
-    use tokio::net::UnixStream;
+    use tokio::io::AsyncReadExt;
 
     struct TestHandler;
 
@@ -173,6 +383,11 @@ mod tests {
                 Request::Ping => Response::ok_with(ResponseData::Pong {
                     timestamp: chrono::Utc::now().timestamp(),
                 }),
+                Request::Hello { .. } => Response::ok_with(ResponseData::Hello {
+                    daemon_version: "0.1.0".to_string(),
+                    protocol_version: PROTOCOL_VERSION,
+                    features: vec![],
+                }),
                 Request::Status => Response::ok_with(ResponseData::Status {
                     version: "0.1.0".to_string(),
                     uptime_secs: 0,
@@ -181,6 +396,17 @@ mod tests {
                     requests_total: 0,
                     cache_hit_rate: 0.0,
                     avg_latency_ms: 0,
+                    request_usage: vec![],
+                    render_cache: vec![],
+                    experiments: vec![],
+                    slos: vec![],
+                    enrichment: vec![],
+                    summarizer: None,
+                    latency: vec![],
+                    scopes_active: 0,
+                    prefetches: 0,
+                    prefetch_hits: 0,
+                    recovery: None,
                 }),
                 _ => Response::ack(),
             }
@@ -189,11 +415,11 @@ mod tests {
 
     #[tokio::test]
     async fn test_server_ping() {
-        let socket_path = "/tmp/engram_test.sock";
-        let _ = std::fs::remove_file(socket_path);
+        let temp_dir = tempfile::tempdir().unwrap();
+        let socket_path = temp_dir.path().join("server.sock");
 
         let handler = Arc::new(TestHandler);
-        let server = IpcServer::new(socket_path, handler).await.unwrap();
+        let server = IpcServer::new(&socket_path, handler).await.unwrap();
 
         // Spawn server
         tokio::spawn(async move {
@@ -204,7 +430,7 @@ mod tests {
         tokio::time::sleep(Duration::from_millis(50)).await;
 
         // Connect and send request
-        let mut stream = UnixStream::connect(socket_path).await.unwrap();
+        let mut stream = transport::connect(&socket_path).await.unwrap();
 
         let request = Request::Ping;
         let request_bytes = rmp_serde::to_vec(&request).unwrap();
@@ -231,8 +457,110 @@ mod tests {
         } else {
             panic!("Expected Pong response, got {:?}", response);
         }
+    }
+
+    #[tokio::test]
+    async fn test_server_pipelined_requests() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let socket_path = temp_dir.path().join("server.sock");
+
+        let server = IpcServer::new(&socket_path, Arc::new(TestHandler))
+            .await
+            .unwrap();
+        tokio::spawn(async move {
+            let _ = server.run().await;
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
 
-        // Cleanup
-        let _ = std::fs::remove_file(socket_path);
+        // Write both requests before reading either response
+        let mut stream = transport::connect(&socket_path).await.unwrap();
+        for request in [Request::Ping, Request::Status] {
+            let request_bytes = rmp_serde::to_vec(&request).unwrap();
+            stream
+                .write_all(&(request_bytes.len() as u32).to_le_bytes())
+                .await
+                .unwrap();
+            stream.write_all(&request_bytes).await.unwrap();
+        }
+
+        let mut responses = Vec::new();
+        for _ in 0..2 {
+            let mut len_buf = [0u8; 4];
+            stream.read_exact(&mut len_buf).await.unwrap();
+            let mut response_buf = vec![0u8; u32::from_le_bytes(len_buf) as usize];
+            stream.read_exact(&mut response_buf).await.unwrap();
+            responses.push(rmp_serde::from_slice::<Response>(&response_buf).unwrap());
+        }
+
+        assert!(matches!(
+            responses[0],
+            Response::Ok {
+                data: Some(ResponseData::Pong { .. })
+            }
+        ));
+        assert!(matches!(
+            responses[1],
+            Response::Ok {
+                data: Some(ResponseData::Status { .. })
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_server_switches_framing_after_hello() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let socket_path = temp_dir.path().join("server.sock");
+
+        let server = IpcServer::new(&socket_path, Arc::new(TestHandler))
+            .await
+            .unwrap();
+        tokio::spawn(async move {
+            let _ = server.run().await;
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let mut stream = transport::connect(&socket_path).await.unwrap();
+        let hello = Request::Hello {
+            client_version: "0.1.0".to_string(),
+            protocol_version: PROTOCOL_VERSION,
+            supported_features: vec![],
+        };
+        let body = rmp_serde::to_vec_named(&hello).unwrap();
+        frame::write_frame(&mut stream, 0, &body).await.unwrap();
+        let (version, _) = frame::read_frame(&mut stream, false, usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(version, 0);
+
+        // Negotiated: both directions carry the version byte
+        let body = rmp_serde::to_vec_named(&Request::Ping).unwrap();
+        frame::write_frame(&mut stream, PROTOCOL_VERSION, &body)
+            .await
+            .unwrap();
+        let (version, body) = frame::read_frame(&mut stream, true, usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(version, PROTOCOL_VERSION);
+        assert!(matches!(
+            rmp_serde::from_slice::<Response>(&body).unwrap(),
+            Response::Ok {
+                data: Some(ResponseData::Pong { .. })
+            }
+        ));
+
+        // A newer client's request this build cannot decode
+        frame::write_frame(&mut stream, PROTOCOL_VERSION + 1, &[0xc1])
+            .await
+            .unwrap();
+        let (_, body) = frame::read_frame(&mut stream, true, usize::MAX)
+            .await
+            .unwrap();
+        assert!(matches!(
+            rmp_serde::from_slice::<Response>(&body).unwrap(),
+            Response::Error {
+                code: ErrorCode::Unsupported,
+                ..
+            }
+        ));
     }
 }
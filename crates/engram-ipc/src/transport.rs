@@ -0,0 +1,197 @@
+//! Platform transports for the IPC protocol.
+//!
+//! On Unix the daemon listens on a domain socket at the configured path. On
+//! Windows the same path is mapped to a named pipe (see [`pipe_name`]), so
+//! configs and `--socket` style options stay portable. The length-prefixed
+//! MessagePack framing on top is identical on both.
+
+use std::io;
+use std::path::Path;
+
+/// Prefix of the Windows named pipe namespace.
+const PIPE_PREFIX: &str = r"\\.\pipe\";
+
+/// Named pipe name for a socket path.
+///
+/// Paths already in the pipe namespace are used as-is; anything else is
+/// flattened into a single pipe name, e.g. `/tmp/engram.sock` becomes
+/// `\\.\pipe\tmp-engram.sock`.
+pub fn pipe_name(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    if path.starts_with(PIPE_PREFIX) {
+        return path.into_owned();
+    }
+
+    let flattened: String = path
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' => '-',
+            c => c,
+        })
+        .collect();
+    format!("{}{}", PIPE_PREFIX, flattened.trim_matches('-'))
+}
+
+#[cfg(unix)]
+mod imp {
+    use super::*;
+    use tokio::net::{UnixListener, UnixStream};
+
+    pub type ServerStream = UnixStream;
+    pub type ClientStream = UnixStream;
+
+    /// Unix domain socket listener
+    pub struct Listener {
+        inner: UnixListener,
+    }
+
+    impl Listener {
+        pub fn bind(path: &Path) -> io::Result<Self> {
+            // Remove stale socket file if it exists
+            if path.exists() {
+                let _ = std::fs::remove_file(path);
+            }
+
+            // Ensure parent directory exists
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            let inner = UnixListener::bind(path)?;
+
+            // Set socket permissions (user only - 0600)
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+
+            Ok(Self { inner })
+        }
+
+        pub async fn accept(&self) -> io::Result<ServerStream> {
+            self.inner.accept().await.map(|(stream, _addr)| stream)
+        }
+    }
+
+    pub async fn connect(path: &Path) -> io::Result<ClientStream> {
+        UnixStream::connect(path).await
+    }
+
+    pub fn endpoint_exists(path: &Path) -> bool {
+        path.exists()
+    }
+
+    /// Let other users connect; peer credentials then decide who is served.
+    pub fn share_endpoint(path: &Path) -> io::Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o666))
+    }
+
+    pub fn remove_endpoint(path: &Path) {
+        if path.exists() {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    pub fn display(path: &Path) -> String {
+        path.display().to_string()
+    }
+}
+
+#[cfg(windows)]
+mod imp {
+    use super::*;
+    use std::time::Duration;
+    use tokio::net::windows::named_pipe::{
+        ClientOptions, NamedPipeClient, NamedPipeServer, ServerOptions,
+    };
+
+    /// `ERROR_PIPE_BUSY`: every server instance is currently connected.
+    const ERROR_PIPE_BUSY: i32 = 231;
+
+    /// Delay between attempts while the pipe is busy
+    const BUSY_RETRY_DELAY: Duration = Duration::from_millis(10);
+
+    pub type ServerStream = NamedPipeServer;
+    pub type ClientStream = NamedPipeClient;
+
+    /// Named pipe listener.
+    ///
+    /// A pipe instance serves exactly one client, so a fresh instance is
+    /// created as soon as the pending one is connected.
+    pub struct Listener {
+        name: String,
+        next: tokio::sync::Mutex<NamedPipeServer>,
+    }
+
+    impl Listener {
+        pub fn bind(path: &Path) -> io::Result<Self> {
+            let name = pipe_name(path);
+            // Fails if another daemon already owns the pipe
+            let first = ServerOptions::new()
+                .first_pipe_instance(true)
+                .reject_remote_clients(true)
+                .create(&name)?;
+
+            Ok(Self {
+                name,
+                next: tokio::sync::Mutex::new(first),
+            })
+        }
+
+        pub async fn accept(&self) -> io::Result<ServerStream> {
+            let mut next = self.next.lock().await;
+            next.connect().await?;
+            let fresh = ServerOptions::new()
+                .reject_remote_clients(true)
+                .create(&self.name)?;
+            Ok(std::mem::replace(&mut *next, fresh))
+        }
+    }
+
+    pub async fn connect(path: &Path) -> io::Result<ClientStream> {
+        let name = pipe_name(path);
+        loop {
+            match ClientOptions::new().open(&name) {
+                Err(e) if e.raw_os_error() == Some(ERROR_PIPE_BUSY) => {}
+                result => return result,
+            }
+            tokio::time::sleep(BUSY_RETRY_DELAY).await;
+        }
+    }
+
+    pub fn endpoint_exists(path: &Path) -> bool {
+        Path::new(&pipe_name(path)).exists()
+    }
+
+    /// Pipes use the default security descriptor; nothing to widen.
+    pub fn share_endpoint(_path: &Path) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Pipes disappear with their last handle; nothing to clean up.
+    pub fn remove_endpoint(_path: &Path) {}
+
+    pub fn display(path: &Path) -> String {
+        pipe_name(path)
+    }
+}
+
+pub(crate) use imp::{connect, share_endpoint, ClientStream, Listener, ServerStream};
+pub use imp::{display as endpoint_display, endpoint_exists, remove_endpoint};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pipe_name() {
+        assert_eq!(
+            pipe_name(Path::new("/tmp/engram.sock")),
+            r"\\.\pipe\tmp-engram.sock"
+        );
+        assert_eq!(
+            pipe_name(Path::new(r"C:\Users\dev\engram.sock")),
+            r"\\.\pipe\C--Users-dev-engram.sock"
+        );
+        assert_eq!(pipe_name(Path::new(r"\\.\pipe\engram")), r"\\.\pipe\engram");
+    }
+}
@@ -4,7 +4,10 @@ use std::collections::HashSet;
 use std::fs;
 use std::path::{Path, PathBuf};
 
-use engram_ipc::{ChangeType, Experience, MemoryEntry, MemoryPatch, Request};
+use engram_ipc::{
+    ChangeType, Experience, MemoryBundle, MemoryEntry, MemoryPatch, MemoryVisibility, Request,
+    MEMORY_BUNDLE_FORMAT,
+};
 
 fn repo_root() -> PathBuf {
     Path::new(env!("CARGO_MANIFEST_DIR"))
@@ -30,15 +33,38 @@ fn supported_actions() -> HashSet<String> {
         Request::InitProject {
             cwd: cwd.clone(),
             async_mode: false,
+            artifact_allowlist: vec![],
         },
         Request::GetContext {
             cwd: cwd.clone(),
             prompt: None,
+            no_cache: false,
+            cold_start: false,
+            read_only_subagent: None,
+            focus: vec![],
+            changed_since: None,
+            structured: false,
+            profile: None,
+            session_id: None,
         },
         Request::PrepareContext {
             cwd: cwd.clone(),
             prompt: String::new(),
         },
+        Request::ReleaseScope {
+            cwd: cwd.clone(),
+            scope_id: String::new(),
+        },
+        Request::ForkScope {
+            cwd: cwd.clone(),
+            scope_id: String::new(),
+            focus: vec![],
+        },
+        Request::ExpandContext {
+            cwd: cwd.clone(),
+            scope_id: String::new(),
+            node: String::new(),
+        },
         Request::NotifyFileChange {
             cwd: cwd.clone(),
             path: PathBuf::from("src/lib.rs"),
@@ -52,6 +78,11 @@ fn supported_actions() -> HashSet<String> {
                 rationale: None,
                 files_touched: vec![],
                 timestamp: 0,
+                id: None,
+                memory_ids: vec![],
+                summarizes: 0,
+                session_id: None,
+                outcome: None,
             },
         },
         Request::MemoryPut {
@@ -66,6 +97,8 @@ fn supported_actions() -> HashSet<String> {
                 session_id: Some("session-1".to_string()),
                 subagent_id: None,
                 deleted: false,
+                experience_id: None,
+                visibility: MemoryVisibility::Project,
             },
         },
         Request::MemoryGet {
@@ -83,6 +116,7 @@ fn supported_actions() -> HashSet<String> {
                 subagent_id: Some("subagent-1".to_string()),
                 deleted: Some(false),
                 updated_at: Some(1),
+                visibility: Some(MemoryVisibility::Session),
             },
         },
         Request::MemoryDelete {
@@ -92,13 +126,164 @@ fn supported_actions() -> HashSet<String> {
         Request::MemoryList {
             cwd: PathBuf::from("/tmp/project"),
             limit: 10,
+            query: Default::default(),
+        },
+        Request::MemorySearch {
+            cwd: PathBuf::from("/tmp/project"),
+            query: "auth".to_string(),
+            limit: 10,
+            viewer: None,
         },
         Request::MemorySync {
             cwd: PathBuf::from("/tmp/project"),
         },
+        Request::MemoryCompact {
+            cwd: PathBuf::from("/tmp/project"),
+        },
+        Request::ConsolidateExperiences {
+            cwd: PathBuf::from("/tmp/project"),
+        },
+        Request::ExportMemory {
+            cwd: PathBuf::from("/tmp/project"),
+        },
+        Request::ImportMemory {
+            cwd: PathBuf::from("/tmp/project"),
+            bundle: MemoryBundle {
+                format: MEMORY_BUNDLE_FORMAT,
+                exported_at: 0,
+                entries: vec![],
+                experiences: vec![],
+            },
+        },
+        Request::ListPending {
+            cwd: PathBuf::from("/tmp/project"),
+        },
+        Request::ApprovePending {
+            cwd: PathBuf::from("/tmp/project"),
+            ids: vec![],
+            reject: false,
+        },
+        Request::SessionStart {
+            cwd: PathBuf::from("/tmp/project"),
+            session_id: Some("session-1".to_string()),
+            agent: Some("claude".to_string()),
+            model: None,
+            metadata: Default::default(),
+        },
+        Request::SessionEnd {
+            cwd: PathBuf::from("/tmp/project"),
+            session_id: "session-1".to_string(),
+            summary: None,
+        },
+        Request::ListSessions {
+            cwd: PathBuf::from("/tmp/project"),
+            limit: 20,
+        },
+        Request::GetSession {
+            cwd: PathBuf::from("/tmp/project"),
+            session_id: "session-1".to_string(),
+        },
+        Request::Batch { requests: vec![] },
+        Request::LineHints {
+            cwd: PathBuf::from("/tmp/project"),
+            path: PathBuf::from("src/lib.rs"),
+        },
+        Request::CalibrationExport {
+            cwd: PathBuf::from("/tmp/project"),
+        },
+        Request::CalibrationImport {
+            cwd: PathBuf::from("/tmp/project"),
+            calibration: "{}".to_string(),
+        },
+        Request::CalibrationReset {
+            cwd: PathBuf::from("/tmp/project"),
+        },
+        Request::UnloadProject {
+            cwd: PathBuf::from("/tmp/project"),
+        },
+        Request::RelinkProject {
+            cwd: PathBuf::from("/tmp/project"),
+            from: None,
+        },
+        Request::PruneTree {
+            cwd: PathBuf::from("/tmp/project"),
+            globs: vec!["vendor/**".to_string()],
+        },
+        Request::Search {
+            cwd: PathBuf::from("/tmp/project"),
+            query: "auth".to_string(),
+            limit: 10,
+            explain: false,
+        },
+        Request::AnalyzeDependencies {
+            cwd: PathBuf::from("/tmp/project"),
+            cycles: true,
+            order: false,
+            dependents_of: None,
+            depth: None,
+        },
+        Request::ImpactAnalysis {
+            cwd: PathBuf::from("/tmp/project"),
+            symbol: "parse_config".to_string(),
+        },
+        Request::ListTests {
+            cwd: PathBuf::from("/tmp/project"),
+            path: PathBuf::from("src/auth.rs"),
+        },
+        Request::GetSymbol {
+            cwd: PathBuf::from("/tmp/project"),
+            path: PathBuf::from("src/auth.rs"),
+            symbol: "validate".to_string(),
+        },
+        Request::GetInitProgress {
+            cwd: PathBuf::from("/tmp/project"),
+        },
+        Request::DiffSnapshots {
+            cwd: PathBuf::from("/tmp/project"),
+            from: "20240101_000000".to_string(),
+            to: None,
+        },
+        Request::GetEvents {
+            since: 0,
+            limit: 100,
+        },
+        Request::ListJobs,
+        Request::ProjectStats {
+            cwd: PathBuf::from("/tmp/project"),
+            top: None,
+        },
+        Request::ExportDocs {
+            cwd: PathBuf::from("/tmp/project"),
+            mdbook: false,
+        },
+        Request::Hello {
+            client_version: "0.1.0".to_string(),
+            protocol_version: 1,
+            supported_features: vec![],
+        },
+        Request::CancelJob { id: 1 },
+        Request::Ping.tracked("req-1", None),
+        Request::Cancel {
+            request_id: "req-1".to_string(),
+        },
+        Request::DebugSnapshot {
+            cwd: PathBuf::from("/tmp/project"),
+        },
         Request::Status,
+        Request::ListProjects,
+        Request::Doctor,
+        Request::CollectGarbage {
+            max_idle_days: None,
+            dry_run: true,
+            only: vec![],
+        },
+        Request::EncryptLogs,
+        Request::ReloadConfig,
         Request::Shutdown,
         Request::Ping,
+        Request::Authenticate {
+            token: String::new(),
+        },
     ]
     .into_iter()
     .map(request_action)
@@ -160,11 +345,22 @@ fn session_end_hook_uses_supported_memory_write_action() {
 
     assert_eq!(
         actions,
-        vec!["memory_put".to_string()],
-        "session_end hook must emit exactly one supported memory write action",
+        vec!["session_end".to_string(), "memory_put".to_string()],
+        "session_end hook must end the session and emit exactly one memory write action",
     );
     assert!(
         supported.contains("memory_put"),
         "protocol must support memory_put",
     );
 }
+
+#[test]
+fn session_hooks_track_sessions() {
+    let hook = repo_root().join("claude-integration/hooks/session_start.sh");
+    let actions = read_hook_actions(&hook);
+
+    assert!(
+        actions.contains(&"session_start".to_string()),
+        "session_start hook must start a session",
+    );
+}
@@ -8,8 +8,8 @@ use tokio::sync::Barrier;
 
 use async_trait::async_trait;
 use engram_ipc::{
-    ErrorCode, IpcClient, IpcServer, MemoryEntry, MemoryPatch, Request, RequestHandler, Response,
-    ResponseData,
+    ErrorCode, IpcClient, IpcServer, MemoryEntry, MemoryPatch, MemoryVisibility, Request,
+    RequestHandler, Response, ResponseData,
 };
 use tempfile::tempdir;
 
@@ -31,6 +31,17 @@ impl RequestHandler for IntegrationHandler {
                 requests_total: 0,
                 cache_hit_rate: 0.0,
                 avg_latency_ms: 0,
+                request_usage: vec![],
+                render_cache: vec![],
+                experiments: vec![],
+                slos: vec![],
+                enrichment: vec![],
+                summarizer: None,
+                latency: vec![],
+                scopes_active: 0,
+                prefetches: 0,
+                prefetch_hits: 0,
+                recovery: None,
             }),
             Request::CheckInit { cwd: _ } => {
                 Response::ok_with(ResponseData::InitStatus { initialized: false })
@@ -187,6 +198,84 @@ async fn test_client_reconnect() {
     ));
 }
 
+/// Test handler that takes a while to answer a ping
+struct SlowHandler(Duration);
+
+#[async_trait]
+impl RequestHandler for SlowHandler {
+    async fn handle(&self, request: Request) -> Response {
+        if let Request::Ping = request {
+            tokio::time::sleep(self.0).await;
+        }
+        IntegrationHandler.handle(request).await
+    }
+}
+
+#[tokio::test]
+async fn test_shutdown_drains_in_flight_requests() {
+    let temp_dir = tempdir().unwrap();
+    let socket_path = temp_dir.path().join("drain.sock");
+
+    for (deadline, drained, aborted) in [
+        (Duration::from_secs(5), 1, 0),
+        (Duration::from_millis(10), 0, 1),
+    ] {
+        let handler = Arc::new(SlowHandler(Duration::from_millis(300)));
+        let server = Arc::new(IpcServer::new(&socket_path, handler).await.unwrap());
+        let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+        let serving = tokio::spawn({
+            let server = server.clone();
+            async move {
+                server
+                    .run_until(async {
+                        let _ = shutdown_rx.await;
+                    })
+                    .await
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let client = IpcClient::with_socket_path(&socket_path);
+        let ping = tokio::spawn(async move { client.request(Request::Ping).await });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        shutdown_tx.send(()).unwrap();
+        serving.await.unwrap().unwrap();
+        let draining = tokio::spawn({
+            let server = server.clone();
+            async move { server.drain(deadline).await }
+        });
+
+        if drained == 1 {
+            // New connections are turned away while draining
+            let late = IpcClient::with_socket_path(&socket_path)
+                .request(Request::Status)
+                .await
+                .unwrap();
+            assert!(matches!(
+                late,
+                Response::Error {
+                    code: ErrorCode::ShuttingDown,
+                    ..
+                }
+            ));
+        }
+
+        let report = draining.await.unwrap();
+        assert_eq!((report.drained, report.aborted), (drained, aborted));
+        if drained == 1 {
+            assert!(matches!(
+                ping.await.unwrap().unwrap(),
+                Response::Ok {
+                    data: Some(ResponseData::Pong { .. })
+                }
+            ));
+        }
+        drop(server);
+        engram_ipc::remove_endpoint(&socket_path);
+    }
+}
+
 /// Test handler that stores memory entries in-process for roundtrip validation.
 struct MemoryIntegrationHandler {
     memories: tokio::sync::RwLock<Vec<MemoryEntry>>,
@@ -239,7 +328,7 @@ impl RequestHandler for MemoryIntegrationHandler {
                     ),
                 }
             }
-            Request::MemoryList { cwd: _, limit } => {
+            Request::MemoryList { limit, .. } => {
                 let memories = self.memories.read().await;
                 let entries = if memories.len() > limit {
                     memories[memories.len() - limit..].to_vec()
@@ -303,6 +392,8 @@ async fn test_memory_write_then_read_over_ipc() {
         session_id: None,
         subagent_id: None,
         deleted: false,
+        experience_id: None,
+        visibility: MemoryVisibility::Project,
     };
 
     let put_response = client
@@ -339,6 +430,7 @@ async fn test_memory_write_then_read_over_ipc() {
         .request(Request::MemoryList {
             cwd: temp_dir.path().to_path_buf(),
             limit: 10,
+            query: Default::default(),
         })
         .await
         .unwrap();
@@ -378,6 +470,8 @@ async fn test_memory_patch_delete_sync_over_ipc() {
         session_id: Some("session-1".to_string()),
         subagent_id: None,
         deleted: false,
+        experience_id: None,
+        visibility: MemoryVisibility::Project,
     };
 
     let put_response = client
@@ -406,6 +500,7 @@ async fn test_memory_patch_delete_sync_over_ipc() {
                 subagent_id: Some("subagent-2".to_string()),
                 deleted: Some(false),
                 updated_at: Some(1_700_000_050),
+                visibility: None,
             },
         })
         .await